@@ -0,0 +1,176 @@
+//! Counts over many grants, e.g. a bulk file's: grants by kind code, by CPC
+//! section and by assignee country, how many claims grants have, and their
+//! citations. Add each grant as it's parsed, so nothing but the counts is
+//! kept; aggregates of parts of a file (e.g. parsed in parallel) merge.
+
+use std::collections::BTreeMap;
+
+use crate::data::{Citation, PatentGrant};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Aggregate {
+    pub grants: usize,
+    /// by publication kind code, e.g. "B2"; "" for grants without one
+    pub by_kind: BTreeMap<String, usize>,
+    /// by the main CPC classification's section, e.g. "A"; grants without a
+    /// main CPC (e.g. plant patents, or before CPC) aren't counted
+    pub by_cpc_section: BTreeMap<String, usize>,
+    /// by assignee country, each grant once for each country its assignees
+    /// are in; grants without assignees (or their country) aren't counted
+    pub by_assignee_country: BTreeMap<String, usize>,
+    /// number of grants with each number of claims
+    pub claims_per_grant: BTreeMap<usize, usize>,
+    pub claims: usize,
+    pub patent_citations: usize,
+    pub non_patent_citations: usize,
+    /// by citation category, e.g. "cited by examiner"; "" for citations
+    /// without one
+    pub citations_by_category: BTreeMap<String, usize>,
+}
+
+impl Aggregate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, grant: &PatentGrant) {
+        let biblio = &grant.us_bibliographic_data_grant;
+        self.grants += 1;
+
+        let kind = biblio.publication_reference.kind.as_ref().map(|kind| kind.as_str()).unwrap_or_default();
+        increment(&mut self.by_kind, kind);
+
+        let section = biblio.classifications_cpc.main_cpc.section.trim();
+        if !section.is_empty() {
+            increment(&mut self.by_cpc_section, section);
+        }
+
+        let mut countries: Vec<&str> = biblio.assignees.iter()
+            .filter_map(|assignee| assignee.addressbook.address.country.as_ref())
+            .map(|country| country.as_str())
+            .filter(|country| !country.is_empty())
+            .collect();
+        countries.sort_unstable();
+        countries.dedup();
+        for country in countries {
+            increment(&mut self.by_assignee_country, country);
+        }
+
+        *self.claims_per_grant.entry(grant.claims.len()).or_insert(0) += 1;
+        self.claims += grant.claims.len();
+
+        for cited in &biblio.us_references_cited {
+            match cited.citation {
+                Citation::Patent(_) => self.patent_citations += 1,
+                Citation::NonPatent(_) => self.non_patent_citations += 1,
+            }
+            increment(&mut self.citations_by_category, cited.category.as_deref().unwrap_or_default());
+        }
+    }
+
+    /// Adds other's counts, as if its grants had been added to this
+    pub fn merge(&mut self, other: &Aggregate) {
+        self.grants += other.grants;
+        merge_counts(&mut self.by_kind, &other.by_kind);
+        merge_counts(&mut self.by_cpc_section, &other.by_cpc_section);
+        merge_counts(&mut self.by_assignee_country, &other.by_assignee_country);
+        merge_counts(&mut self.claims_per_grant, &other.claims_per_grant);
+        self.claims += other.claims;
+        self.patent_citations += other.patent_citations;
+        self.non_patent_citations += other.non_patent_citations;
+        merge_counts(&mut self.citations_by_category, &other.citations_by_category);
+    }
+
+    pub fn citations(&self) -> usize {
+        self.patent_citations + self.non_patent_citations
+    }
+
+    /// None if no grants have been added
+    pub fn mean_claims(&self) -> Option<f64> {
+        if self.grants == 0 {
+            return None;
+        }
+
+        Some(self.claims as f64 / self.grants as f64)
+    }
+
+    /// The number of claims that fraction q (0 to 1) of grants have at most,
+    /// e.g. 0.5 for the median; None if no grants have been added
+    pub fn claims_quantile(&self, q: f64) -> Option<usize> {
+        if self.grants == 0 {
+            return None;
+        }
+
+        // the rank of the grant, in order of claims, that's the quantile
+        let rank = ((q.clamp(0.0, 1.0) * self.grants as f64).ceil() as usize).max(1);
+        let mut seen = 0;
+        for (&claims, &count) in &self.claims_per_grant {
+            seen += count;
+            if seen >= rank {
+                return Some(claims);
+            }
+        }
+
+        self.claims_per_grant.keys().next_back().copied()
+    }
+}
+
+impl<'a> Extend<&'a PatentGrant> for Aggregate {
+    fn extend<I: IntoIterator<Item = &'a PatentGrant>>(&mut self, grants: I) {
+        for grant in grants {
+            self.add(grant);
+        }
+    }
+}
+
+fn increment(counts: &mut BTreeMap<String, usize>, key: &str) {
+    match counts.get_mut(key) {
+        Some(count) => *count += 1,
+        None => {
+            counts.insert(key.to_string(), 1);
+        },
+    }
+}
+
+fn merge_counts<K: Ord + Clone>(counts: &mut BTreeMap<K, usize>, other: &BTreeMap<K, usize>) {
+    for (key, count) in other {
+        *counts.entry(key.clone()).or_insert(0) += count;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deserialize::PatentGrants;
+
+    #[test]
+    fn test_aggregate() {
+        let grants = PatentGrants::from_reader(include_str!("../tests/fixtures/grants.xml").as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let mut aggregate = Aggregate::new();
+        aggregate.extend(&grants);
+        assert_eq!(aggregate.grants, 3);
+        assert_eq!(aggregate.by_kind.values().copied().collect::<Vec<_>>(), vec![1, 1, 1]);
+        assert_eq!(aggregate.by_kind.get("S1"), Some(&1));
+        assert_eq!(aggregate.by_cpc_section.get("A"), Some(&2));
+        assert_eq!(aggregate.by_assignee_country.get("JP"), Some(&2));
+        assert_eq!(aggregate.claims_per_grant.get(&1), Some(&2));
+        assert_eq!(aggregate.claims, 5);
+        assert_eq!(aggregate.claims_quantile(0.5), Some(1));
+        assert_eq!(aggregate.claims_quantile(1.0), Some(3));
+        assert_eq!(aggregate.citations(), 4);
+        assert_eq!(aggregate.citations_by_category.get("cited by examiner"), Some(&2));
+
+        let mut merged = Aggregate::new();
+        merged.add(&grants[0]);
+        let mut rest = Aggregate::new();
+        rest.extend(&grants[1..]);
+        merged.merge(&rest);
+        assert_eq!(merged, aggregate);
+
+        assert_eq!(Aggregate::new().mean_claims(), None);
+        assert_eq!(Aggregate::new().claims_quantile(0.5), None);
+    }
+}
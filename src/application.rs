@@ -0,0 +1,259 @@
+//! Pre-grant publications (`us-patent-application`, in ipa*.xml bulk files).
+//!
+//! The format is close to the grants', so this reuses the grant parser's
+//! helpers for everything but the top level and the biblio.
+
+use quick_xml::{self, Reader};
+use quick_xml::events::Event;
+use std::io::BufRead;
+
+use crate::data::*;
+use crate::deserialize::*;
+use crate::error::{Error, WithinExt};
+use crate::options::{ParserOptions, Projection};
+use crate::util::{skip_element, unknown_element, Scratch};
+use crate::schema::{self, ElementNames};
+
+/// Like PatentGrants, over a bulk file of applications
+pub struct PatentApplications<B: BufRead> {
+    rdr: quick_xml::Reader<B>,
+    buf: Vec<u8>,
+    scratch: Scratch,
+    options: ParserOptions,
+}
+
+impl<B: BufRead> PatentApplications<B> {
+    pub fn from_reader(b: B) -> Self {
+        Self::with_options(b, ParserOptions::default())
+    }
+
+    /// See `PatentGrants::with_options`; consistency checks are only for
+    /// grants, so `check_consistency` is ignored.
+    pub fn with_options(b: B, options: ParserOptions) -> Self {
+        let mut rdr = Reader::from_reader(b);
+        rdr.trim_text(options.trim_text);
+
+        PatentApplications {
+            rdr,
+            buf: Vec::new(),
+            scratch: Scratch::default(),
+            options,
+        }
+    }
+
+    /// returns None if no more data, see PatentGrants
+    fn deser_patent_application(&mut self) -> Option<Result<PatentApplication, Error>> {
+        match deser_header(&mut self.rdr, &mut self.buf) {
+            Some(Err(err)) => return Some(Err(err)),
+            Some(Ok(())) => (),
+            None => return None,
+        }
+        self.buf.clear();
+
+        let mut application = PatentApplication::default();
+        if let Err(err) = self.deser_application_into(&mut application) {
+            let doc_number = &application.us_bibliographic_data_application.publication_reference.doc_number;
+            return Some(Err(err.at_position(self.rdr.buffer_position(), doc_number)));
+        }
+
+        Some(Ok(application))
+    }
+
+    /// the document after its headers. On error, application keeps what was
+    /// parsed so far, to locate the error with
+    fn deser_application_into(&mut self, application: &mut PatentApplication) -> Result<(), Error> {
+        let mut names = schema::element_names(None);
+        let projection = self.options.projection;
+
+        loop {
+            match self.rdr.read_event(&mut self.buf) {
+                Ok(Event::PI(pi_bytes)) => {
+                    deser_top_pi(pi_bytes, &mut self.rdr, &mut self.scratch, &self.options, &mut application.descriptions, &mut application.descriptions_raw)?;
+                },
+                Ok(Event::Start(ref e)) => {
+                    match e.name() {
+                        b"us-patent-application" => {
+                            deser_grant_meta(e, &self.rdr, &self.options, &mut application.meta).within(b"us-patent-application")?;
+                            names = schema::element_names(application.meta.dtd_version.as_deref());
+                        },
+                        // sections not in the projection, see PatentGrants
+                        b"claims" if !projection.contains(Projection::CLAIMS) => {
+                            skip_element(b"claims", &mut self.rdr, &mut self.scratch)?;
+                        },
+                        b"description" if !projection.contains(Projection::DESCRIPTION) => {
+                            skip_element(b"description", &mut self.rdr, &mut self.scratch)?;
+                        },
+                        b"abstract" if !projection.contains(Projection::ABSTRACT) => {
+                            skip_element(b"abstract", &mut self.rdr, &mut self.scratch)?;
+                        },
+                        b"claims" => {
+                            deser_claims(&mut self.rdr, &mut self.buf, &mut self.scratch, &self.options, &mut application.claims).within(b"claims")?;
+                        },
+                        b"us-bibliographic-data-application" => {
+                            deser_application_biblio(&mut self.rdr, &mut self.buf, &mut self.scratch, &self.options, names, &mut application.us_bibliographic_data_application).within(b"us-bibliographic-data-application")?;
+                        },
+                        b"abstract" => {
+                            deser_abstract(&mut self.rdr, &mut self.buf, &mut self.scratch, &self.options, &mut application.r#abstract).within(b"abstract")?;
+                        },
+                        _ => continue,
+                    }
+                },
+                Ok(Event::Eof) => break,
+                Ok(Event::End(e)) => {
+                    if e.name() == b"us-patent-application" {
+                        break;
+                    } else {
+                        continue;
+                    }
+                },
+                Ok(_) => continue,
+                Err(err) => return Err(Error::deser(err.to_string())),
+            };
+        }
+
+        self.buf.clear();
+
+        application.government_interest = deser_government_interest(&application.descriptions_raw, &self.options)?;
+        application.chemistry = deser_description_chemistry(&application.descriptions_raw, &self.options)?;
+        application.sequence_listing = deser_sequence_listing(&mut application.descriptions, &mut application.descriptions_raw, &self.options)?;
+
+        Ok(())
+    }
+}
+
+impl<B: BufRead> Iterator for PatentApplications<B> {
+    type Item = Result<PatentApplication, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let res = self.deser_patent_application();
+        self.buf.clear();
+
+        res
+    }
+}
+
+/// Mirrors deser_biblio, for the elements an application has.
+///
+/// called after tag us-bibliographic-data-application is already hit
+fn deser_application_biblio<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    names: &ElementNames,
+    biblio: &mut ApplicationBiblio,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    name if !opts.projection.contains(biblio_section(name, names)) => skip_element(name, rdr, scratch)?,
+                    b"publication-reference" => {
+                        deser_doc_id(rdr, buf, scratch, opts, &mut biblio.publication_reference).within(b"publication-reference")?;
+                    },
+                    b"application-reference" => {
+                        deser_doc_id(rdr, buf, scratch, opts, &mut biblio.application_reference).within(b"application-reference")?;
+                    },
+                    b"us-application-series-code" => {
+                        biblio.us_application_series_code = deser_text_from(e.name(), rdr, scratch, opts).within(b"us-application-series-code")?;
+                    },
+                    b"priority-claims" => {
+                        deser_priority_claims(rdr, buf, scratch, opts, &mut biblio.priority_claims).within(b"priority-claims")?;
+                    },
+                    b"classifications-ipcr" => {
+                        deser_classifications_ipcr(rdr, buf, scratch, opts, &mut biblio.classifications_ipcr).within(b"classifications-ipcr")?;
+                    },
+                    b"classifications-cpc" => {
+                        deser_classifications_cpc(rdr, buf, scratch, opts, &mut biblio.classifications_cpc).within(b"classifications-cpc")?;
+                    },
+                    b"classification-national" => {
+                        deser_class_national(rdr, buf, scratch, opts, &mut biblio.classification_national).within(b"classification-national")?;
+                    },
+                    b"invention-title" => {
+                        deser_invention_title(e, rdr, scratch, opts, &mut biblio.invention_title).within(b"invention-title")?;
+                    },
+                    b"us-related-documents" => {
+                        deser_us_related_documents(rdr, buf, scratch, opts, &mut biblio.us_related_documents).within(b"us-related-documents")?;
+                    },
+                    name if name == names.applicants => {
+                        deser_us_applicants(rdr, buf, scratch, opts, names, &mut biblio.us_applicants).within(names.applicants)?;
+                    },
+                    b"inventors" => {
+                        deser_inventors(rdr, buf, scratch, opts, &mut biblio.inventors).within(b"inventors")?;
+                    },
+                    b"agents" => {
+                        deser_agents(rdr, buf, scratch, opts, &mut biblio.agents).within(b"agents")?;
+                    },
+                    b"assignees" => {
+                        deser_assignees(rdr, buf, scratch, opts, &mut biblio.assignees).within(b"assignees")?;
+                    },
+                    b"pct-or-regional-filing-data" => {
+                        let mut filing_data = PctOrRegionalFilingData::default();
+                        deser_pct_filing_data(rdr, buf, scratch, opts, &mut filing_data).within(b"pct-or-regional-filing-data")?;
+                        biblio.pct_or_regional_filing_data = Some(filing_data);
+                    },
+                    b"pct-or-regional-publishing-data" => {
+                        let mut publishing_data = PctOrRegionalPublishingData::default();
+                        deser_pct_publishing_data(rdr, buf, scratch, opts, &mut publishing_data).within(b"pct-or-regional-publishing-data")?;
+                        biblio.pct_or_regional_publishing_data = Some(publishing_data);
+                    },
+                    // same as the grant biblio
+                    b"us-parties" | b"parties" => continue,
+                    _ => unknown_element(e.name(), "us-bibliographic-data-application", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"us-bibliographic-data-application" {
+                    break;
+                }
+            },
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        };
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/applications.xml");
+
+    #[test]
+    fn test_applications() {
+        let applications = PatentApplications::from_reader(FIXTURE.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(applications.len(), 2);
+
+        let app = &applications[0];
+        assert_eq!(app.meta.dtd_version.as_deref(), Some("v4.4 2014-04-03"));
+
+        let biblio = &app.us_bibliographic_data_application;
+        assert_eq!(biblio.publication_reference.doc_number, "20190000001");
+        assert_eq!(biblio.publication_reference.kind, Some(KindCode::A1));
+        assert_eq!(biblio.application_reference.doc_number, "16012345");
+        assert_eq!(biblio.us_application_series_code, "16");
+        assert_eq!(biblio.priority_claims.len(), 1);
+        assert_eq!(biblio.classifications_ipcr.len(), 1);
+        assert_eq!(biblio.invention_title.title, "Hand tool with ergonomic handle");
+        assert_eq!(biblio.us_related_documents.len(), 1);
+        assert_eq!(biblio.us_applicants.len(), 1);
+        assert_eq!(biblio.inventors.len(), 1);
+        assert_eq!(biblio.assignees.len(), 1);
+
+        assert_eq!(app.r#abstract.len(), 1);
+        assert_eq!(app.claims.len(), 2);
+        assert!(app.descriptions.contains_key("summary-of-invention"));
+        assert!(app.descriptions_raw["detailed-description"].contains("rubber"));
+
+        let biblio = &applications[1].us_bibliographic_data_application;
+        assert_eq!(biblio.publication_reference.doc_number, "20190000002");
+        assert_eq!(biblio.invention_title.title, "Garden rake");
+        assert!(biblio.assignees.is_empty());
+        assert_eq!(applications[1].claims.len(), 1);
+    }
+}
@@ -0,0 +1,572 @@
+//! APS "Green Book", the key-coded text format of grants from 1976 to 2001
+//! (pftaps*.txt).
+//!
+//! Each line is a field: a key of up to four characters, then its value from
+//! the sixth column, continued on following lines indented by five spaces.
+//! A grant starts with a PATN line, and lines with only a key start a section
+//! (e.g. INVT for an inventor, CLAS for classifications). Fields are mapped
+//! onto the same PatentGrant as the xml formats.
+
+use snafu::ResultExt;
+use std::io::BufRead;
+
+use crate::data::*;
+use crate::error::{Error, Read};
+use crate::util::FromText;
+
+/// Iterator over the grants in an APS text file
+pub struct ApsGrants<B: BufRead> {
+    rdr: B,
+    // the PATN line starting the next grant, once it's been read
+    next_patn: bool,
+    eof: bool,
+}
+
+impl<B: BufRead> ApsGrants<B> {
+    pub fn from_reader(rdr: B) -> Self {
+        ApsGrants {
+            rdr,
+            next_patn: false,
+            eof: false,
+        }
+    }
+
+    /// lines as utf8; older files aren't always valid
+    fn read_line(&mut self) -> Result<Option<String>, Error> {
+        let mut line = Vec::new();
+        let read = self.rdr.read_until(b'\n', &mut line)
+            .context(Read)?;
+
+        if read == 0 {
+            return Ok(None);
+        }
+
+        let line = String::from_utf8_lossy(&line);
+        Ok(Some(line.trim_end_matches(['\r', '\n']).to_string()))
+    }
+
+    /// The fields (key, value) of the next grant, not including its PATN line
+    fn next_record(&mut self) -> Result<Option<Vec<(String, String)>>, Error> {
+        // skip the file header, anything before the first grant
+        while !self.next_patn {
+            match self.read_line()? {
+                Some(line) => self.next_patn = line.trim_end() == "PATN",
+                None => return Ok(None),
+            }
+        }
+
+        let mut fields: Vec<(String, String)> = Vec::new();
+        loop {
+            let line = match self.read_line()? {
+                Some(line) => line,
+                None => {
+                    self.next_patn = false;
+                    self.eof = true;
+                    break;
+                },
+            };
+
+            if line.trim_end() == "PATN" {
+                break;
+            }
+
+            if line.starts_with(' ') {
+                // continuation of the last field
+                if let Some((_, value)) = fields.last_mut() {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                }
+                continue;
+            }
+
+            let (key, value) = match line.char_indices().nth(5) {
+                Some((i, _)) => (line[..i].trim(), line[i..].trim()),
+                None => (line.trim(), ""),
+            };
+            if !key.is_empty() {
+                fields.push((key.to_string(), value.to_string()));
+            }
+        }
+
+        Ok(Some(fields))
+    }
+}
+
+impl<B: BufRead> Iterator for ApsGrants<B> {
+    type Item = Result<PatentGrant, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.eof {
+            return None;
+        }
+
+        match self.next_record() {
+            Ok(Some(fields)) => Some(deser_aps_grant(&fields)),
+            Ok(None) => {
+                self.eof = true;
+                None
+            },
+            Err(err) => {
+                // stop after a read error
+                self.eof = true;
+                Some(Err(err))
+            },
+        }
+    }
+}
+
+/// the sections fields can be in; a section starts with a line with only its
+/// key, and fields before any (other than PATN's) are in PATN
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Section {
+    Patn,
+    Invt,
+    Assg,
+    Prir,
+    Clas,
+    Uref,
+    Fref,
+    Oref,
+    Lrep,
+    Abst,
+    Govt,
+    Bsum,
+    Drwd,
+    Detd,
+    Clms,
+    // e.g. RLAP, REIS, PCTA; not mapped yet
+    Other,
+}
+
+impl Section {
+    fn from_key(key: &str) -> Option<Self> {
+        let section = match key {
+            "INVT" => Section::Invt,
+            "ASSG" => Section::Assg,
+            "PRIR" => Section::Prir,
+            "CLAS" => Section::Clas,
+            "UREF" => Section::Uref,
+            "FREF" => Section::Fref,
+            "OREF" => Section::Oref,
+            "LREP" => Section::Lrep,
+            "ABST" => Section::Abst,
+            "GOVT" => Section::Govt,
+            "BSUM" => Section::Bsum,
+            "DRWD" => Section::Drwd,
+            "DETD" => Section::Detd,
+            "CLMS" => Section::Clms,
+            "RLAP" | "REIS" | "PCTA" | "PARN" | "DCLM" => Section::Other,
+            _ => return None,
+        };
+
+        Some(section)
+    }
+
+    /// description sections, keyed as in the xml formats
+    fn description_key(self) -> Option<&'static str> {
+        match self {
+            Section::Govt => Some("GOVINT"),
+            Section::Bsum => Some("BRFSUM"),
+            Section::Drwd => Some("brief-description-of-drawings"),
+            Section::Detd => Some("DETDESC"),
+            _ => None,
+        }
+    }
+}
+
+/// Maps one grant's fields onto a PatentGrant.
+fn deser_aps_grant(fields: &[(String, String)]) -> Result<PatentGrant, Error> {
+    let mut patent_grant = PatentGrant::default();
+    patent_grant.meta.lang = "EN".to_string();
+    patent_grant.meta.country = "US".to_string();
+
+    let biblio = &mut patent_grant.us_bibliographic_data_grant;
+    biblio.publication_reference.country = CountryCode::US;
+    biblio.application_reference.country = CountryCode::US;
+
+    let mut section = Section::Patn;
+    let mut application_number = None;
+    let mut field_of_search_class = String::new();
+    let mut ipc_edition = String::new();
+    let mut descriptions: Vec<(&'static str, Vec<&str>)> = Vec::new();
+
+    for (key, value) in fields {
+        let value = value.as_str();
+
+        if value.is_empty() {
+            if let Some(new_section) = Section::from_key(key) {
+                section = new_section;
+                match section {
+                    Section::Invt => biblio.inventors.push(Inventor {
+                        sequence: format!("{:03}", biblio.inventors.len() + 1),
+                        ..Inventor::default()
+                    }),
+                    Section::Assg => biblio.assignees.push(Assignee::default()),
+                    Section::Prir => biblio.priority_claims.push(PriorityClaim {
+                        sequence: format!("{:02}", biblio.priority_claims.len() + 1),
+                        kind: "national".to_string(),
+                        ..PriorityClaim::default()
+                    }),
+                    Section::Uref | Section::Fref => biblio.us_references_cited.push(UsCitation {
+                        citation: Citation::Patent(PatCit {
+                            num: format!("{:05}", biblio.us_references_cited.len() + 1),
+                            ..PatCit::default()
+                        }),
+                        category: None,
+                        classification_cpc_text: None,
+                        classification_national: None,
+                    }),
+                    _ => (),
+                }
+                if let Some(key) = section.description_key() {
+                    descriptions.push((key, Vec::new()));
+                }
+                continue;
+            }
+        }
+
+        match (section, key.as_str()) {
+            (Section::Patn, "WKU") => {
+                // the last character is a check digit
+                let wku = value.get(..value.len().saturating_sub(1)).unwrap_or(value);
+                biblio.publication_reference.doc_number = wku.to_string();
+            },
+            (Section::Patn, "SRC") => biblio.us_application_series_code = format!("{:0>2}", value),
+            (Section::Patn, "APN") => application_number = Some(value),
+            (Section::Patn, "APD") => biblio.application_reference.date = parse_date(key, value)?,
+            (Section::Patn, "TTL") => biblio.invention_title.title = value.to_string(),
+            (Section::Patn, "ISD") => biblio.publication_reference.date = parse_date(key, value)?,
+            (Section::Patn, "NCL") => biblio.number_of_claims = Some(parse_u32(key, value)?),
+            (Section::Patn, "ECL") => {
+                for claim in value.split(',') {
+                    biblio.us_exemplary_claim.push(parse_u32(key, claim.trim())?);
+                }
+            },
+            (Section::Patn, "EXP") => biblio.examiners.primary_examiner = examiner(value),
+            (Section::Patn, "EXA") => biblio.examiners.assistant_examiner = Some(examiner(value)),
+            (Section::Patn, "NDR") => biblio.figures.number_of_drawing_sheets = Some(parse_u32(key, value)?),
+            (Section::Patn, "NFG") => biblio.figures.number_of_figures = Some(parse_u32(key, value)?),
+
+            (Section::Invt, _) => {
+                if let Some(inventor) = biblio.inventors.last_mut() {
+                    party_field(key, value, &mut inventor.addressbook);
+                }
+            },
+            (Section::Assg, "COD") => {
+                if let Some(assignee) = biblio.assignees.last_mut() {
+                    assignee.role = Some(value.to_string());
+                    assignee.addressbook.role = Some(value.to_string());
+                }
+            },
+            (Section::Assg, _) => {
+                if let Some(assignee) = biblio.assignees.last_mut() {
+                    if key == "NAM" {
+                        assignee.orgname = Some(value.to_string());
+                        assignee.addressbook.orgname = Some(value.to_string());
+                    } else {
+                        party_field(key, value, &mut assignee.addressbook);
+                    }
+                }
+            },
+            (Section::Prir, _) => {
+                if let Some(priority_claim) = biblio.priority_claims.last_mut() {
+                    match key.as_str() {
+                        "CNT" => priority_claim.country = country(value),
+                        "APD" => priority_claim.date = parse_date(key, value)?,
+                        "APN" => priority_claim.doc_number = Some(value.to_string()),
+                        _ => (),
+                    }
+                }
+            },
+
+            (Section::Clas, "OCL") => {
+                biblio.classification_national.country = CountryCode::US;
+                biblio.classification_national.main_classification = Uspc::from(value);
+            },
+            // only one further classification is kept
+            (Section::Clas, "XCL") if biblio.classification_national.further_classification.is_none() => {
+                biblio.classification_national.further_classification = Some(Uspc::from(value));
+            },
+            (Section::Clas, "EDF") => ipc_edition = value.to_string(),
+            (Section::Clas, "ICL") => biblio.classifications_ipcr.push(ipc_from_text(value)),
+            (Section::Clas, "FSC") => field_of_search_class = value.to_string(),
+            (Section::Clas, "FSS") => {
+                for subclass in value.split(';') {
+                    biblio.us_field_of_classification_search.classification_nationals.push(ClassificationNational {
+                        country: CountryCode::US,
+                        main_classification: Uspc::new(&field_of_search_class, subclass.trim()),
+                        ..ClassificationNational::default()
+                    });
+                }
+            },
+
+            (Section::Uref, _) | (Section::Fref, _) => {
+                if let Some(UsCitation { citation: Citation::Patent(patcit), classification_national, .. }) = biblio.us_references_cited.last_mut() {
+                    let doc_id = &mut patcit.document_id;
+                    match key.as_str() {
+                        "PNO" => doc_id.doc_number = value.to_string(),
+                        "ISD" => doc_id.date = parse_date(key, value)?,
+                        "NAM" => doc_id.name = Some(value.to_string()),
+                        "CNT" => doc_id.country = country(value),
+                        "OCL" => *classification_national = Some(ClassificationNational {
+                            country: CountryCode::US,
+                            main_classification: Uspc::from(value),
+                            ..ClassificationNational::default()
+                        }),
+                        _ => (),
+                    }
+                    if section == Section::Uref {
+                        doc_id.country = CountryCode::US;
+                    }
+                }
+            },
+            // one citation per paragraph
+            (Section::Oref, "PAL") | (Section::Oref, "PAR") => {
+                biblio.us_references_cited.push(UsCitation {
+                    citation: Citation::NonPatent(NplCit {
+                        num: format!("{:05}", biblio.us_references_cited.len() + 1),
+                        othercit: value.to_string(),
+                    }),
+                    category: None,
+                    classification_cpc_text: None,
+                    classification_national: None,
+                });
+            },
+            (Section::Lrep, "FRM") | (Section::Lrep, "FR2") | (Section::Lrep, "AGT") | (Section::Lrep, "ATT") => {
+                let mut agent = Agent {
+                    sequence: format!("{:02}", biblio.agents.len() + 1),
+                    rep_type: if key == "AGT" { "agent" } else { "attorney" }.to_string(),
+                    ..Agent::default()
+                };
+                if key == "FRM" {
+                    agent.addressbook.orgname = Some(value.to_string());
+                } else {
+                    let (last_name, first_name) = split_name(value);
+                    agent.addressbook.last_name = Some(last_name);
+                    agent.addressbook.first_name = first_name;
+                }
+                biblio.agents.push(agent);
+            },
+
+            (Section::Abst, "PAL") | (Section::Abst, "PAR") | (Section::Abst, "PA1") => {
+                patent_grant.r#abstract.push(Paragraph {
+                    id: format!("p-{:04}", patent_grant.r#abstract.len() + 1),
+                    num: format!("{:04}", patent_grant.r#abstract.len()),
+                    text: value.to_string(),
+                    ..Paragraph::default()
+                });
+            },
+            (Section::Govt, _) | (Section::Bsum, _) | (Section::Drwd, _) | (Section::Detd, _) => {
+                if let Some((_, paragraphs)) = descriptions.last_mut() {
+                    paragraphs.push(value);
+                }
+            },
+            (Section::Clms, "STM") => patent_grant.us_claim_statement = value.to_string(),
+            (Section::Clms, "NUM") => {
+                let num = format!("{:05}", patent_grant.claims.len() + 1);
+                patent_grant.claims.push(Claim {
+                    id: format!("CLM-{}", num),
+                    num,
+                    ..Claim::default()
+                });
+            },
+            (Section::Clms, _) => {
+                // claim text (PAR, PA1, ...) before any NUM isn't a claim
+                if let Some(claim) = patent_grant.claims.last_mut() {
+                    claim.claim_texts.push(ClaimText {
+                        text: value.to_string(),
+                        ..ClaimText::default()
+                    });
+                }
+            },
+            _ => (),
+        }
+    }
+
+    let biblio = &mut patent_grant.us_bibliographic_data_grant;
+
+    if biblio.publication_reference.doc_number.is_empty() {
+        return Err(Error::ApsDeser { src: "no WKU in PATN".to_string() });
+    }
+
+    // the application number is the series code and the serial number,
+    // whose last character is a check digit
+    if let Some(apn) = application_number {
+        let serial = apn.get(..apn.len().saturating_sub(1)).unwrap_or(apn);
+        biblio.application_reference.doc_number = format!("{}{}", biblio.us_application_series_code, serial);
+    }
+
+    for ipcr in &mut biblio.classifications_ipcr {
+        ipcr.ipc_version_indicator = ipc_edition.clone();
+    }
+
+    for (key, paragraphs) in descriptions {
+        let text = paragraphs.join(" ");
+        let description = patent_grant.descriptions.entry(key.to_string()).or_default();
+        if !description.is_empty() {
+            description.push(' ');
+        }
+        description.push_str(&text);
+    }
+
+    patent_grant.government_interest = patent_grant.descriptions.get("GOVINT").cloned();
+    patent_grant.meta.date_publ = Some(date_text(&patent_grant.us_bibliographic_data_grant.publication_reference.date));
+
+    Ok(patent_grant)
+}
+
+fn parse_date(key: &str, value: &str) -> Result<Date, Error> {
+    Date::from_text(value.to_string(), key.as_bytes())
+}
+
+fn parse_u32(key: &str, value: &str) -> Result<u32, Error> {
+    value.parse()
+        .map_err(|err| Error::ApsDeser { src: format!("err: {} parsing {:?} in {}", err, value, key) })
+}
+
+/// names are written `Last; First Middle`
+fn split_name(value: &str) -> (String, Option<String>) {
+    match value.find(';') {
+        Some(i) => (value[..i].trim().to_string(), Some(value[i + 1..].trim().to_string())),
+        None => (value.trim().to_string(), None),
+    }
+}
+
+fn examiner(value: &str) -> Examiner {
+    let (last_name, first_name) = split_name(value);
+
+    Examiner {
+        last_name,
+        first_name: first_name.unwrap_or_default(),
+        ..Examiner::default()
+    }
+}
+
+/// NAM, CTY, STA and CNT of an inventor or assignee
+fn party_field(key: &str, value: &str, addressbook: &mut AddressBook) {
+    match key {
+        "NAM" => {
+            let (last_name, first_name) = split_name(value);
+            addressbook.last_name = Some(last_name);
+            addressbook.first_name = first_name;
+        },
+        "CTY" => addressbook.address.city = Some(value.to_string()),
+        "STA" => {
+            addressbook.address.state = Some(value.to_string());
+            // us addresses have a state instead of a country
+            if addressbook.address.country.is_none() {
+                addressbook.address.country = Some(CountryCode::US);
+            }
+        },
+        "CNT" => addressbook.address.country = Some(country(value)),
+        _ => (),
+    }
+}
+
+/// APS countries are padded to three characters with an X, e.g. `JPX`
+fn country(value: &str) -> CountryCode {
+    match value.strip_suffix('X') {
+        Some(country) if country.len() == 2 => CountryCode::from(country),
+        _ => CountryCode::from(value),
+    }
+}
+
+/// e.g. `A01B  100`: subclass, then the main group, with the last two
+/// characters the subgroup
+fn ipc_from_text(ipc: &str) -> ClassificationIpcr {
+    let subclass = ipc.get(..4).unwrap_or(ipc);
+    let group = ipc.get(4..).unwrap_or("").trim();
+    let (main_group, subgroup) = match group.len() {
+        0 => (None, None),
+        1 | 2 => (Some(group.to_string()), None),
+        n => (Some(group[..n - 2].trim().to_string()), Some(group[n - 2..].to_string())),
+    };
+
+    ClassificationIpcr {
+        section: subclass.get(..1).unwrap_or_default().to_string(),
+        class: subclass.get(1..3).unwrap_or_default().to_string(),
+        subclass: subclass.get(3..).unwrap_or_default().to_string(),
+        main_group,
+        subgroup,
+        ..ClassificationIpcr::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/aps.txt");
+
+    #[test]
+    fn test_aps_grants() {
+        let grants = ApsGrants::from_reader(FIXTURE.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(grants.len(), 2);
+
+        let pg = &grants[0];
+        let biblio = &pg.us_bibliographic_data_grant;
+        assert_eq!(biblio.publication_reference.doc_number, "03930584");
+        // no kind code, so from the doc-number
+        assert_eq!(pg.doc_type(), Some(DocType::Utility));
+        assert_eq!(date_text(&biblio.publication_reference.date), "19760106");
+        assert_eq!(biblio.us_application_series_code, "05");
+        assert_eq!(biblio.application_reference.doc_number, "05484939");
+        assert_eq!(date_text(&biblio.application_reference.date), "19740803");
+        // continued over two lines
+        assert_eq!(biblio.invention_title.title, "Rotary grain cutter with adjustable blades and cutting guide");
+        assert_eq!(biblio.number_of_claims, Some(2));
+        assert_eq!(biblio.us_exemplary_claim, vec![1]);
+        assert_eq!(biblio.examiners.primary_examiner.last_name, "Novosad");
+        assert_eq!(biblio.examiners.primary_examiner.first_name, "Christopher J.");
+
+        assert_eq!(biblio.inventors.len(), 2);
+        assert_eq!(biblio.inventors[0].addressbook.last_name.as_deref(), Some("Tanaka"));
+        assert_eq!(biblio.inventors[0].addressbook.address.country, Some(CountryCode::from("JP")));
+        assert_eq!(biblio.inventors[1].addressbook.address.country, Some(CountryCode::US));
+        assert_eq!(biblio.assignees[0].orgname.as_deref(), Some("ACME Tool Co."));
+        assert_eq!(biblio.assignees[0].role.as_deref(), Some("03"));
+        assert_eq!(biblio.priority_claims[0].country, CountryCode::from("JP"));
+
+        assert_eq!(biblio.classification_national.main_classification, Uspc::new("172", "13"));
+        assert_eq!(biblio.classification_national.further_classification, Some(Uspc::new("294", "49")));
+        let ipc = &biblio.classifications_ipcr[0];
+        assert_eq!((&ipc.section[..], &ipc.class[..], &ipc.subclass[..]), ("A", "01", "B"));
+        assert_eq!((ipc.main_group.as_deref(), ipc.subgroup.as_deref()), (Some("1"), Some("00")));
+        assert_eq!(ipc.ipc_version_indicator, "2");
+        assert_eq!(biblio.us_field_of_classification_search.classification_nationals.len(), 2);
+        assert_eq!(biblio.us_field_of_classification_search.classification_nationals[1].main_classification, Uspc::new("172", "14"));
+
+        let citations = &biblio.us_references_cited;
+        assert_eq!(citations.len(), 3);
+        match &citations[1].citation {
+            Citation::Patent(patcit) => assert_eq!(patcit.document_id.country, CountryCode::from("DE")),
+            other => panic!("expected patcit, got {:?}", other),
+        }
+        match &citations[2].citation {
+            Citation::NonPatent(nplcit) => assert_eq!(nplcit.othercit, "Garden Tools Catalog, 1972."),
+            other => panic!("expected nplcit, got {:?}", other),
+        }
+        assert_eq!(biblio.agents[0].addressbook.orgname.as_deref(), Some("Example & Partners"));
+
+        assert_eq!(pg.r#abstract.len(), 1);
+        assert!(pg.descriptions["BRFSUM"].starts_with("BACKGROUND Hand tools are known."));
+        assert_eq!(pg.government_interest.as_deref(), Some("This invention was made with government support."));
+        assert_eq!(pg.us_claim_statement, "I claim:");
+        assert_eq!(pg.claims.len(), 2);
+        assert_eq!(pg.claims[1].claim_texts[0].text, "2. The cutter of claim 1, wherein the blades are steel.");
+
+        let biblio = &grants[1].us_bibliographic_data_grant;
+        assert_eq!(biblio.publication_reference.doc_number, "D0245000");
+        assert_eq!(grants[1].doc_type(), Some(DocType::Design));
+        assert!(biblio.inventors.is_empty());
+    }
+
+    #[test]
+    fn test_aps_missing_wku() {
+        let results: Vec<_> = ApsGrants::from_reader(&b"PATN\nTTL  Untitled\n"[..]).collect();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_err());
+    }
+}
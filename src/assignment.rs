@@ -0,0 +1,372 @@
+//! Patent assignments (`us-patent-assignments`, the daily ad*.xml files):
+//! transfers of rights recorded with the USPTO, a document family of their
+//! own beside grants and applications.
+//!
+//! A file is one document, with a `patent-assignment` for each recorded
+//! assignment, which is what's iterated over. Its header (the action key
+//! code and transaction date) is skipped.
+
+use quick_xml::{self, Reader};
+use quick_xml::events::Event;
+use std::io::BufRead;
+
+use crate::data::*;
+use crate::deserialize::{deser_date, deser_doc_id_from, deser_text_from};
+use crate::error::{Error, WithinExt};
+use crate::options::ParserOptions;
+use crate::util::{unknown_element, Scratch};
+
+/// Like PatentGrants, over a file of assignments
+pub struct Assignments<B: BufRead> {
+    rdr: quick_xml::Reader<B>,
+    buf: Vec<u8>,
+    scratch: Scratch,
+    options: ParserOptions,
+}
+
+impl<B: BufRead> Assignments<B> {
+    pub fn from_reader(b: B) -> Self {
+        Self::with_options(b, ParserOptions::default())
+    }
+
+    /// Of the options (see `PatentGrants::with_options`), `trim_text`,
+    /// `entities`, `decoding`, `unknown_elements` and `skipped` apply;
+    /// `missing_fields` only to the document-ids of the properties.
+    pub fn with_options(b: B, options: ParserOptions) -> Self {
+        let mut rdr = Reader::from_reader(b);
+        rdr.trim_text(options.trim_text);
+
+        Assignments {
+            rdr,
+            buf: Vec::new(),
+            scratch: Scratch::default(),
+            options,
+        }
+    }
+
+    /// returns None if no more assignments
+    fn deser_patent_assignment(&mut self) -> Option<Result<PatentAssignment, Error>> {
+        loop {
+            match self.rdr.read_event(&mut self.buf) {
+                Ok(Event::Start(ref e)) if e.name() == b"patent-assignment" => break,
+                Ok(Event::Eof) => return None,
+                Ok(_) => continue,
+                Err(err) => return Some(Err(Error::deser(err.to_string()))),
+            }
+        }
+        self.buf.clear();
+
+        let mut assignment = PatentAssignment::default();
+        if let Err(err) = deser_assignment(&mut self.rdr, &mut self.buf, &mut self.scratch, &self.options, &mut assignment).within(b"patent-assignment") {
+            return Some(Err(err.at_position(self.rdr.buffer_position(), &assignment.reel_frame())));
+        }
+
+        Some(Ok(assignment))
+    }
+}
+
+impl<B: BufRead> Iterator for Assignments<B> {
+    type Item = Result<PatentAssignment, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let res = self.deser_patent_assignment();
+        self.buf.clear();
+
+        res
+    }
+}
+
+/// called after tag patent-assignment is already hit
+fn deser_assignment<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    assignment: &mut PatentAssignment,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"assignment-record" => {
+                        deser_assignment_record(rdr, buf, scratch, opts, &mut assignment.assignment_record).within(b"assignment-record")?;
+                    },
+                    // the lists' items are read as they come
+                    b"patent-assignors" | b"patent-assignees" | b"patent-properties" => continue,
+                    b"patent-assignor" => {
+                        let mut assignor = Assignor::default();
+                        deser_assignor(rdr, buf, scratch, opts, &mut assignor).within(b"patent-assignor")?;
+                        assignment.assignors.push(assignor);
+                    },
+                    b"patent-assignee" => {
+                        let mut assignee = AssignmentAssignee::default();
+                        deser_assignee(rdr, buf, scratch, opts, &mut assignee).within(b"patent-assignee")?;
+                        assignment.assignees.push(assignee);
+                    },
+                    b"patent-property" => {
+                        let mut property = PatentProperty::default();
+                        deser_property(rdr, buf, scratch, opts, &mut property).within(b"patent-property")?;
+                        assignment.properties.push(property);
+                    },
+                    _ => unknown_element(e.name(), "patent-assignment", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"patent-assignment" {
+                    break;
+                }
+            },
+            Ok(Event::Eof) => return Err(Error::deser("unexpected end of file in patent-assignment".to_string())),
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        };
+    }
+
+    Ok(())
+}
+
+/// called after tag assignment-record is already hit
+fn deser_assignment_record<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    record: &mut AssignmentRecord,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"reel-no" => record.reel_no = deser_text_from(e.name(), rdr, scratch, opts)?,
+                    b"frame-no" => record.frame_no = deser_text_from(e.name(), rdr, scratch, opts)?,
+                    b"last-update-date" => record.last_update_date = deser_date(b"date", rdr, scratch, opts).within(b"last-update-date")?,
+                    b"purge-indicator" => record.purge_indicator = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"recorded-date" => record.recorded_date = deser_date(b"date", rdr, scratch, opts).within(b"recorded-date")?,
+                    b"page-count" => {
+                        let txt = deser_text_from(e.name(), rdr, scratch, opts)?;
+                        let page_count = txt.trim().parse()
+                            .map_err(|err| Error::deser(format!("err: {} parsing {:?} in page-count", err, txt)))?;
+                        record.page_count = Some(page_count);
+                    },
+                    b"correspondent" => {
+                        deser_correspondent(rdr, buf, scratch, opts, &mut record.correspondent).within(b"correspondent")?;
+                    },
+                    b"conveyance-text" => record.conveyance_text = deser_text_from(e.name(), rdr, scratch, opts)?,
+                    _ => unknown_element(e.name(), "assignment-record", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"assignment-record" {
+                    break;
+                }
+            },
+            Ok(Event::Eof) => return Err(Error::deser("unexpected end of file in assignment-record".to_string())),
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        };
+    }
+
+    Ok(())
+}
+
+/// called after tag correspondent is already hit. Also read for trademark
+/// assignments, where the name is person-or-organization-name
+pub(crate) fn deser_correspondent<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    correspondent: &mut Correspondent,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"name" | b"person-or-organization-name" => correspondent.name = deser_text_from(e.name(), rdr, scratch, opts)?,
+                    b"address-1" | b"address-2" | b"address-3" | b"address-4" => {
+                        correspondent.address.push(deser_text_from(e.name(), rdr, scratch, opts)?);
+                    },
+                    _ => unknown_element(e.name(), "correspondent", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"correspondent" {
+                    break;
+                }
+            },
+            Ok(Event::Eof) => return Err(Error::deser("unexpected end of file in correspondent".to_string())),
+            // empty elements (e.g. `<address-3/>`) are left out
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        };
+    }
+
+    Ok(())
+}
+
+/// called after tag patent-assignor is already hit
+fn deser_assignor<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    assignor: &mut Assignor,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"name" => assignor.name = deser_text_from(e.name(), rdr, scratch, opts)?,
+                    b"execution-date" => assignor.execution_date = deser_date(b"date", rdr, scratch, opts).within(b"execution-date")?,
+                    b"date-acknowledged" => assignor.date_acknowledged = Some(deser_date(b"date", rdr, scratch, opts).within(b"date-acknowledged")?),
+                    _ => unknown_element(e.name(), "patent-assignor", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"patent-assignor" {
+                    break;
+                }
+            },
+            Ok(Event::Eof) => return Err(Error::deser("unexpected end of file in patent-assignor".to_string())),
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        };
+    }
+
+    Ok(())
+}
+
+/// called after tag patent-assignee is already hit
+fn deser_assignee<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    assignee: &mut AssignmentAssignee,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"name" => assignee.name = deser_text_from(e.name(), rdr, scratch, opts)?,
+                    b"address-1" | b"address-2" => assignee.address.push(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"city" => assignee.city = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"state" => assignee.state = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"country-name" => assignee.country_name = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"postcode" => assignee.postcode = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    _ => unknown_element(e.name(), "patent-assignee", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"patent-assignee" {
+                    break;
+                }
+            },
+            Ok(Event::Eof) => return Err(Error::deser("unexpected end of file in patent-assignee".to_string())),
+            // empty elements (e.g. `<state/>`) are left at None
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        };
+    }
+
+    Ok(())
+}
+
+/// called after tag patent-property is already hit
+fn deser_property<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    property: &mut PatentProperty,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"document-id" => {
+                        let mut doc_id = DocumentId::default();
+                        deser_doc_id_from(rdr, buf, scratch, opts, &mut doc_id).within(b"document-id")?;
+                        property.document_ids.push(doc_id);
+                    },
+                    b"invention-title" => property.invention_title = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    _ => unknown_element(e.name(), "patent-property", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"patent-property" {
+                    break;
+                }
+            },
+            Ok(Event::Eof) => return Err(Error::deser("unexpected end of file in patent-property".to_string())),
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        };
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/assignments.xml");
+
+    #[test]
+    fn test_assignments() {
+        let assignments = Assignments::from_reader(FIXTURE.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(assignments.len(), 2);
+
+        let assignment = &assignments[0];
+        assert_eq!(assignment.reel_frame(), "047866/0001");
+        let record = &assignment.assignment_record;
+        assert_eq!(date_text(&record.recorded_date), "20181231");
+        assert_eq!(record.page_count, Some(3));
+        assert_eq!(record.correspondent.name, "IP GROUP LLP");
+        assert_eq!(record.correspondent.address, vec!["100 MAIN STREET", "ALEXANDRIA, VA 22314"]);
+        assert!(record.conveyance_text.starts_with("ASSIGNMENT OF ASSIGNORS INTEREST"));
+
+        assert_eq!(assignment.assignors.len(), 2);
+        assert_eq!(assignment.assignors[0].name, "TANAKA, TARO");
+        assert_eq!(date_text(&assignment.assignors[0].execution_date), "20181201");
+        assert!(assignment.assignors[0].date_acknowledged.is_none());
+
+        let assignee = &assignment.assignees[0];
+        assert_eq!(assignee.name, "ACME TOOL CO., LTD.");
+        assert_eq!(assignee.city.as_deref(), Some("OSAKA"));
+        assert_eq!(assignee.country_name.as_deref(), Some("JAPAN"));
+        assert!(assignee.state.is_none());
+
+        let property = &assignment.properties[0];
+        assert_eq!(property.document_ids.len(), 2);
+        assert_eq!(property.document_ids[0].kind, Some(KindCode::Other("X0".to_string())));
+        assert_eq!(property.grant().map(|doc_id| doc_id.doc_number.as_str()), Some("10165721"));
+        assert_eq!(property.invention_title.as_deref(), Some("Hand tool with ergonomic grip"));
+
+        // a security interest in two applications, neither granted yet
+        let assignment = &assignments[1];
+        assert_eq!(assignment.reel_frame(), "047866/0105");
+        assert_eq!(assignment.properties.len(), 2);
+        assert!(assignment.properties.iter().all(|property| property.grant().is_none()));
+    }
+
+    #[test]
+    fn test_assignments_unknown_element() {
+        let xml = FIXTURE.replace("<page-count>3</page-count>", "<page-count>3</page-count><remark>x</remark>");
+
+        assert_eq!(Assignments::from_reader(xml.as_bytes()).filter(Result::is_ok).count(), 2);
+
+        let options = ParserOptions::strict();
+        let err = Assignments::with_options(xml.as_bytes(), options).next().unwrap().unwrap_err();
+        assert!(err.to_string().contains("remark"));
+    }
+}
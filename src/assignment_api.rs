@@ -0,0 +1,354 @@
+//! The USPTO's patent assignment search api (`assignment-api.uspto.gov`):
+//! the assignments recorded against a patent or application, looked up by
+//! its number, as the same `PatentAssignment`s the bulk files are parsed
+//! into (see `Assignments`).
+//!
+//! The api answers in a search engine's xml, a `doc` of named fields (a
+//! string, date or number, or an `arr` of them) per assignment. A field of
+//! the assignors', assignees' or properties' is an arr with an item for
+//! each, in the same order as the others. Properties' kind codes aren't
+//! given, so are made from the kind of document: X0 for the application, A1
+//! for the publication, and for the grant its type's (B2 for a utility
+//! patent with a publication, B1 without one, and so on).
+
+use quick_xml::{self, Reader};
+use quick_xml::events::{BytesStart, Event};
+use snafu::ResultExt;
+use std::collections::HashMap;
+use std::io::BufRead;
+
+use crate::data::*;
+use crate::docnum::{grant_number, split_grant_number};
+use crate::error::{AssignmentApi, Error};
+use crate::util::FromText;
+
+const ASSIGNMENT_API_URL: &str = "https://assignment-api.uspto.gov/patent/lookup";
+
+/// the most assignments returned for a number
+const ROWS: usize = 1000;
+
+/// A doc's fields, each the text of its items in order (one, unless an arr)
+type Doc = HashMap<String, Vec<String>>;
+
+fn name_attr<B: BufRead>(start: &BytesStart, rdr: &quick_xml::Reader<B>) -> Result<Option<String>, Error> {
+    for attr_res in start.attributes() {
+        let attr = attr_res
+            .map_err(|err| Error::deser(err.to_string()))?;
+        if attr.key == b"name" {
+            let name = attr.unescape_and_decode_value(rdr)
+                .map_err(|err| Error::deser(err.to_string()))?;
+            return Ok(Some(name));
+        }
+    }
+
+    Ok(None)
+}
+
+/// The response's docs
+fn read_docs<B: BufRead>(b: B) -> Result<Vec<Doc>, Error> {
+    let mut rdr = Reader::from_reader(b);
+    rdr.trim_text(true);
+    let mut buf = Vec::new();
+
+    let mut docs = Vec::new();
+    let mut doc: Option<Doc> = None;
+    // the arr open, if any, and the field whose text is being read
+    let mut arr: Option<String> = None;
+    let mut field: Option<String> = None;
+
+    loop {
+        match rdr.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"doc" => doc = Some(Doc::new()),
+                    b"arr" => arr = name_attr(e, &rdr)?,
+                    _ => {
+                        // an item with no text is still an item, to keep
+                        // the arrs lined up
+                        field = name_attr(e, &rdr)?.or_else(|| arr.clone());
+                        if let (Some(doc), Some(field)) = (doc.as_mut(), &field) {
+                            doc.entry(field.clone()).or_default().push(String::new());
+                        }
+                    },
+                }
+            },
+            Ok(Event::Empty(ref e)) => {
+                if let (Some(doc), Some(field)) = (doc.as_mut(), name_attr(e, &rdr)?.or_else(|| arr.clone())) {
+                    doc.entry(field).or_default().push(String::new());
+                }
+            },
+            Ok(Event::Text(ref e)) => {
+                if let (Some(doc), Some(field)) = (doc.as_mut(), &field) {
+                    let text = e.unescape_and_decode(&rdr)
+                        .map_err(|err| Error::deser(err.to_string()))?;
+                    if let Some(item) = doc.get_mut(field).and_then(|items| items.last_mut()) {
+                        item.push_str(&text);
+                    }
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                match e.name() {
+                    b"doc" => docs.extend(doc.take()),
+                    b"arr" => arr = None,
+                    _ => field = None,
+                }
+            },
+            Ok(Event::Eof) => break,
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+        buf.clear();
+    }
+
+    Ok(docs)
+}
+
+/// The api's dates (e.g. "2018-12-12T00:00:00Z") as a Date
+fn api_date(text: &str, field: &str) -> Result<Date, Error> {
+    let date = text.trim().get(..10).unwrap_or(text).replace('-', "");
+
+    Date::from_text(date, field.as_bytes())
+}
+
+/// The kind of a grant by its number, with or without a publication before
+fn grant_kind(patent_number: &str, published: bool) -> KindCode {
+    match (split_grant_number(patent_number).map(|(prefix, _)| prefix), published) {
+        (Some("D"), _) => KindCode::S1,
+        (Some("RE"), _) => KindCode::E1,
+        (Some("H"), _) => KindCode::H1,
+        (Some("PP"), true) => KindCode::P3,
+        (Some("PP"), false) => KindCode::P2,
+        (_, true) => KindCode::B2,
+        (_, false) => KindCode::B1,
+    }
+}
+
+/// A doc as an assignment
+fn to_assignment(doc: &Doc) -> Result<PatentAssignment, Error> {
+    let items = |field: &str| doc.get(field).map(Vec::as_slice).unwrap_or_default();
+    let item = |field: &str, i: usize| items(field).get(i)
+        .map(|text| text.trim())
+        .filter(|text| !text.is_empty())
+        .map(str::to_string);
+    let first = |field: &str| item(field, 0);
+    let date = |field: &str, i: usize| item(field, i).map(|text| api_date(&text, field)).transpose();
+
+    let mut assignment = PatentAssignment::default();
+
+    let record = &mut assignment.assignment_record;
+    record.reel_no = first("reelNo").unwrap_or_default();
+    record.frame_no = first("frameNo").unwrap_or_default();
+    if let Some(last_update_date) = date("lastUpdateDate", 0)? {
+        record.last_update_date = last_update_date;
+    }
+    record.purge_indicator = first("purgeIndicator");
+    if let Some(recorded_date) = date("recordedDate", 0)? {
+        record.recorded_date = recorded_date;
+    }
+    record.page_count = first("pageCount").and_then(|page_count| page_count.parse().ok());
+    record.correspondent = Correspondent {
+        name: first("corrName").unwrap_or_default(),
+        address: ["corrAddress1", "corrAddress2", "corrAddress3", "corrAddress4"].iter()
+            .filter_map(|field| first(field))
+            .collect(),
+    };
+    record.conveyance_text = first("conveyanceText").unwrap_or_default();
+
+    for i in 0..items("patAssignorName").len() {
+        assignment.assignors.push(Assignor {
+            name: item("patAssignorName", i).unwrap_or_default(),
+            execution_date: date("patAssignorExDate", i)?.unwrap_or_default(),
+            date_acknowledged: date("patAssignorDateAck", i)?,
+        });
+    }
+
+    for i in 0..items("patAssigneeName").len() {
+        assignment.assignees.push(AssignmentAssignee {
+            name: item("patAssigneeName", i).unwrap_or_default(),
+            address: ["patAssigneeAddress1", "patAssigneeAddress2"].iter()
+                .filter_map(|field| item(field, i))
+                .collect(),
+            city: item("patAssigneeCity", i),
+            state: item("patAssigneeState", i),
+            country_name: item("patAssigneeCountryName", i),
+            postcode: item("patAssigneePostcode", i),
+        });
+    }
+
+    let properties = ["applNum", "publNum", "patNum"].iter()
+        .map(|field| items(field).len())
+        .max()
+        .unwrap_or_default();
+    for i in 0..properties {
+        let mut property = PatentProperty {
+            invention_title: item("inventionTitle", i),
+            ..PatentProperty::default()
+        };
+
+        if let Some(doc_number) = item("applNum", i) {
+            property.document_ids.push(DocumentId {
+                country: CountryCode::US,
+                doc_number,
+                kind: Some(KindCode::Other("X0".to_string())),
+                name: None,
+                date: date("filingDate", i)?.unwrap_or_default(),
+            });
+        }
+        let published = item("publNum", i).is_some();
+        if let Some(doc_number) = item("publNum", i) {
+            property.document_ids.push(DocumentId {
+                country: CountryCode::US,
+                doc_number,
+                kind: Some(KindCode::A1),
+                name: None,
+                date: date("publDate", i)?.unwrap_or_default(),
+            });
+        }
+        if let Some(doc_number) = item("patNum", i) {
+            property.document_ids.push(DocumentId {
+                country: CountryCode::US,
+                kind: Some(grant_kind(&doc_number, published)),
+                doc_number,
+                name: None,
+                date: date("issueDate", i)?.unwrap_or_default(),
+            });
+        }
+
+        assignment.properties.push(property);
+    }
+
+    Ok(assignment)
+}
+
+/// Reads a response of the api, as assignments
+pub fn read_response<B: BufRead>(b: B) -> Result<Vec<PatentAssignment>, Error> {
+    read_docs(b)?
+        .iter()
+        .map(to_assignment)
+        .collect()
+}
+
+/// Queries the assignment search api, over a reqwest (async) client
+#[derive(Debug, Clone)]
+pub struct AssignmentClient {
+    client: reqwest_async::Client,
+    url: String,
+}
+
+impl Default for AssignmentClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AssignmentClient {
+    pub fn new() -> Self {
+        Self::with_client(reqwest_async::Client::new(), ASSIGNMENT_API_URL)
+    }
+
+    /// Looks each number up with a GET of url (an assignment api lookup
+    /// endpoint like `https://assignment-api.uspto.gov/patent/lookup`),
+    /// filtered by patent or application number, with client
+    pub fn with_client(client: reqwest_async::Client, url: &str) -> Self {
+        AssignmentClient {
+            client,
+            url: url.to_string(),
+        }
+    }
+
+    async fn lookup(&self, filter: &str, number: &str) -> Result<Vec<PatentAssignment>, Error> {
+        let url = format!("{}?filter={}&query={}&rows={}", self.url, filter, number, ROWS);
+        let body = self.client.get(&url)
+            .header(reqwest_async::header::ACCEPT, "application/xml")
+            .send()
+            .await
+            .and_then(|resp| resp.error_for_status())
+            .context(AssignmentApi)?
+            .bytes()
+            .await
+            .context(AssignmentApi)?;
+
+        read_response(body.as_ref())
+    }
+
+    /// The assignments recorded against a grant, by (padded or unpadded)
+    /// number
+    pub async fn by_patent(&self, patent_number: &str) -> Result<Vec<PatentAssignment>, Error> {
+        self.lookup("PatentNumber", &grant_number(patent_number)).await
+    }
+
+    /// The assignments recorded against an application, by its (eight-digit)
+    /// number
+    pub async fn by_application(&self, application_number: &str) -> Result<Vec<PatentAssignment>, Error> {
+        let application_number = application_number.chars()
+            .filter(char::is_ascii_digit)
+            .collect::<String>();
+
+        self.lookup("ApplicationNumber", &application_number).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESPONSE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<response>
+<lst name="responseHeader"><int name="status">0</int></lst>
+<result name="response" numFound="1" start="0">
+<doc>
+<str name="id">47866-1</str>
+<str name="reelNo">47866</str>
+<str name="frameNo">1</str>
+<date name="lastUpdateDate">2019-01-10T00:00:00Z</date>
+<date name="recordedDate">2019-01-08T00:00:00Z</date>
+<int name="pageCount">3</int>
+<str name="conveyanceText">ASSIGNMENT OF ASSIGNORS INTEREST (SEE DOCUMENT FOR DETAILS).</str>
+<str name="corrName">SMITH &amp; JONES LLP</str>
+<str name="corrAddress1">100 MAIN STREET</str>
+<str name="corrAddress2">AUSTIN, TX 78701</str>
+<arr name="patAssignorName"><str>TANAKA, HIRO</str><str>SMITH, ANN</str></arr>
+<arr name="patAssignorExDate"><date>2018-12-01T00:00:00Z</date><date>2018-12-03T00:00:00Z</date></arr>
+<arr name="patAssigneeName"><str>ACME TOOL CO., LTD.</str></arr>
+<arr name="patAssigneeAddress1"><str>1-2-3 KITA</str></arr>
+<arr name="patAssigneeCity"><str>OSAKA</str></arr>
+<arr name="patAssigneeCountryName"><str>JAPAN</str></arr>
+<arr name="applNum"><str>15123456</str><str>15999999</str></arr>
+<arr name="filingDate"><date>2017-03-01T00:00:00Z</date><date>2018-06-01T00:00:00Z</date></arr>
+<arr name="publNum"><str/><str>20190123456</str></arr>
+<arr name="patNum"><str>10165721</str><str/></arr>
+<arr name="issueDate"><date>2019-01-01T00:00:00Z</date><date/></arr>
+<arr name="inventionTitle"><str>HAND TOOL WITH ERGONOMIC GRIP</str><str>GRIP FOR A HAND TOOL</str></arr>
+</doc>
+</result>
+</response>
+"#;
+
+    #[test]
+    fn test_read_response() {
+        let assignments = read_response(RESPONSE.as_bytes()).unwrap();
+        assert_eq!(assignments.len(), 1);
+
+        let assignment = &assignments[0];
+        assert_eq!(assignment.reel_frame(), "047866/0001");
+        let record = &assignment.assignment_record;
+        assert_eq!(date_text(&record.recorded_date), "20190108");
+        assert_eq!(record.page_count, Some(3));
+        assert_eq!(record.correspondent.name, "SMITH & JONES LLP");
+        assert_eq!(record.correspondent.address.len(), 2);
+
+        assert_eq!(assignment.assignors.len(), 2);
+        assert_eq!(date_text(&assignment.assignors[1].execution_date), "20181203");
+        assert_eq!(assignment.assignees[0].country_name.as_deref(), Some("JAPAN"));
+
+        assert_eq!(assignment.properties.len(), 2);
+        let grant = assignment.properties[0].grant().unwrap();
+        assert_eq!(grant.doc_number, "10165721");
+        assert_eq!(grant.kind, Some(KindCode::B1));
+        assert_eq!(date_text(&grant.date), "20190101");
+        // the second is only published
+        assert!(assignment.properties[1].grant().is_none());
+        assert_eq!(assignment.properties[1].document_ids.len(), 2);
+        assert_eq!(assignment.properties[1].invention_title.as_deref(), Some("GRIP FOR A HAND TOOL"));
+    }
+}
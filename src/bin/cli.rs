@@ -2,140 +2,1283 @@
 // there's probably no other way to do it, since they insert a xml and doctype
 // between each patent grant
 
-#![feature(custom_attribute)]
-
-use csv;
+use chrono::{NaiveDate, Utc};
+use clap::{Parser, Subcommand, ValueEnum};
 use snafu::{Snafu, ResultExt};
+use std::collections::HashMap;
 use std::fs;
-use std::io::BufReader;
+use std::io::{self, BufWriter, Write};
 use std::path::{Path, PathBuf};
-use structopt::StructOpt;
-use uspto::{PatentGrants, PatentOutput};
-use uspto::fetch;
+use std::process;
+use std::sync::Arc;
+#[cfg(feature = "progress")]
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use uspto::aggregate::Aggregate;
+use uspto::census::TagCensus;
+use uspto::data::{date_text, KindCode, PatentGrant};
+use uspto::diff::Snapshot;
+#[cfg(feature = "avro")]
+use uspto::export::avro::AvroExporter;
+#[cfg(feature = "clickhouse")]
+use uspto::export::clickhouse::ClickHouseExporter;
+use uspto::export::csv::CsvExporter;
+#[cfg(feature = "parquet")]
+use uspto::export::parquet::ParquetExporter;
+use uspto::export::patentsview::PatentsViewExporter;
+#[cfg(feature = "postgres")]
+use uspto::export::postgres::PostgresLoader;
+#[cfg(feature = "sqlite")]
+use uspto::export::sqlite::SqliteExporter;
+use uspto::export::star::StarExporter;
+#[cfg(feature = "progress")]
+use uspto::progress::{Progress, ProgressObserver, ProgressReader};
+use uspto::docnum::grant_number;
+use uspto::metrics::Metrics;
+#[cfg(feature = "search")]
+use uspto::search::SearchIndex;
+use uspto::split::{self, Documents};
+use uspto::sync::SyncState;
+use uspto::{GrantIndex, InputReader, LenientPatentGrants, ParserOptions, PatentGrants, PatentOutput, Projection, SkippedElements};
+use uspto::bulkdata::{BulkData, BulkFile, Product};
 
 fn main() {
-    match run() {
-        Ok(_) => (),
-        Err(err) => eprintln!("{}", err),
+    if let Err(err) = run() {
+        eprintln!("{}", err);
+        process::exit(1);
     }
 }
 
+/// Whether open_grants shows a progress bar, see `CliOpt::progress`
+#[cfg(feature = "progress")]
+static PROGRESS: AtomicBool = AtomicBool::new(false);
+
 fn run() -> Result<(), Error> {
-    let opts = CliOpt::from_args();
+    let opts = CliOpt::parse();
+    #[cfg(feature = "progress")]
+    PROGRESS.store(opts.progress, Ordering::Relaxed);
 
     match opts.command {
-        Command::Fetch { year, target_dir }=> {
-            // for now, just one year
-            let mut fetcher = fetch::FetchGrants::new(year, year, target_dir);
+        Command::Fetch { from, to, target_dir, product, parse } => {
+            fetch(from, to, &target_dir, product, parse)
+        },
+        Command::Sync { state_file, target_dir, from, product, exporter, out_dir, url, interval_hours, once, metrics_addr } => {
+            let target = match (exporter, out_dir, url) {
+                (SyncExporter::Csv, Some(out_dir), _) => SyncTarget::Csv(out_dir),
+                #[cfg(feature = "parquet")]
+                (SyncExporter::Parquet, Some(out_dir), _) => SyncTarget::Parquet(out_dir),
+                #[cfg(feature = "postgres")]
+                (SyncExporter::Postgres, _, Some(url)) => SyncTarget::Postgres(url),
+                #[cfg(feature = "clickhouse")]
+                (SyncExporter::Clickhouse, _, Some(url)) => SyncTarget::ClickHouse(url),
+                (exporter, _, _) => return MissingOption { option: exporter.option(), exporter }.fail(),
+            };
+            let metrics = Arc::new(Metrics::new());
+            if let Some(addr) = metrics_addr {
+                metrics.serve(addr.as_str())
+                    .context(UsPto)?;
+            }
+            let daemon = SyncDaemon {
+                state_path: state_file,
+                target_dir,
+                from,
+                product,
+                target,
+                interval: Duration::from_secs(interval_hours * 60 * 60),
+                metrics,
+            };
+
+            daemon.run(once)
+        },
+        Command::Parse { data_filepath, target_filepath, format, limit, strict } => {
+            let patents = open_grants(&data_filepath, ParserOptions::new().check_consistency(strict))?
+                .take(limit.unwrap_or(usize::MAX));
+            let mut output = Output::new(format, target_filepath.as_deref())?;
+
+            for patent_res in patents {
+                match patent_res {
+                    Ok(patent) => output.write(&patent)?,
+                    Err(err) => eprintln!("{}", err),
+                }
+            }
+
+            output.finish()
+        },
+        Command::Stats { data_filepath } => {
+            stats(&data_filepath)
+        },
+        Command::Validate { data_filepath, quarantine_dir } => {
+            validate(&data_filepath, quarantine_dir.as_deref())
+        },
+        Command::Extract { data_filepath, doc_numbers, format, raw } => {
+            extract(&data_filepath, &doc_numbers, format, raw)
+        },
+        Command::Export { data_filepath, format, out_dir } => {
+            export(&data_filepath, format, &out_dir)
+        },
+        #[cfg(feature = "search")]
+        Command::Index { data_filepath, full_text: Some(index_dir), .. } => {
+            full_text_index(&data_filepath, &index_dir)
+        },
+        Command::Index { data_filepath, index_filepath, .. } => {
+            index(&data_filepath, index_filepath.as_deref())
+        },
+        Command::Filter { data_filepath, target_filepath, from, to, cpc, assignee, kinds } => {
+            let filter = Filter {
+                from: from.map(|date| date.format("%Y%m%d").to_string()),
+                to: to.map(|date| date.format("%Y%m%d").to_string()),
+                cpc: cpc.iter().map(|prefix| cpc_key(prefix)).collect(),
+                assignee: assignee.map(|assignee| assignee.to_lowercase()),
+                kinds: kinds.iter().map(|kind| KindCode::from(kind.as_str())).collect(),
+            };
+
+            filter_documents(&data_filepath, target_filepath.as_deref(), &filter)
+        },
+        Command::Diff { old_filepath, new_filepath } => {
+            diff(&old_filepath, &new_filepath)
+        },
+        Command::Inspect { data_filepath, names } => {
+            inspect(&data_filepath, names)
+        },
+        Command::Split { data_filepath, out_dir, shard_width } => {
+            split_documents(&data_filepath, &out_dir, shard_width)
+        },
+        #[cfg(feature = "search")]
+        Command::Search { index_dir, query, limit } => {
+            search(&index_dir, &query, limit)
+        },
+    }
+}
+
+/// xml, gzipped xml, or weekly zip archives. Parsed leniently, so a bad grant
+/// is reported without stopping the rest of the file.
+/// data_path can also be an http(s) url, streamed as it's parsed, or an object
+/// storage url (e.g. `s3://bucket/ipg190101.zip`) with the `object-store`
+/// feature
+fn open_grants(data_path: &Path, options: ParserOptions) -> Result<LenientPatentGrants<InputReader>, Error> {
+    let patents = match data_path.to_str().filter(|path| path.contains("://")) {
+        Some(url) if url.starts_with("http://") || url.starts_with("https://") => PatentGrants::from_http_url(url),
+        #[cfg(feature = "object-store")]
+        Some(url) => PatentGrants::from_url(url),
+        #[cfg(feature = "progress")]
+        None if PROGRESS.load(Ordering::Relaxed) => return open_grants_with_progress(data_path, options),
+        _ => PatentGrants::from_path(data_path),
+    };
+    let patents = patents
+        .context(UsPto)?;
+
+    Ok(patents.options(options).lenient())
+}
+
+/// As open_grants, for a file on disk, with a progress bar on stderr of how
+/// far through the file it is
+#[cfg(feature = "progress")]
+fn open_grants_with_progress(data_path: &Path, options: ParserOptions) -> Result<LenientPatentGrants<InputReader>, Error> {
+    let file = fs::File::open(data_path)
+        .context(ReadInput)?;
+    let file_len = file.metadata()
+        .context(ReadInput)?
+        .len();
+
+    let rdr = ProgressReader::new(file);
+    let bytes_read = rdr.bytes_read();
+    let patents = PatentGrants::from_seekable(rdr)
+        .context(UsPto)?;
+
+    let bar = indicatif::ProgressBar::new(file_len);
+    if let Ok(style) = indicatif::ProgressStyle::with_template("{bar:40} {bytes}/{total_bytes} {msg}") {
+        bar.set_style(style);
+    }
+
+    Ok(patents.options(options)
+        .lenient()
+        .observe(ProgressBarObserver(bar))
+        .input_progress(bytes_read, Some(file_len)))
+}
+
+#[cfg(feature = "progress")]
+struct ProgressBarObserver(indicatif::ProgressBar);
+
+#[cfg(feature = "progress")]
+impl ProgressObserver for ProgressBarObserver {
+    fn progress(&mut self, progress: &Progress) {
+        let eta = progress.eta()
+            .map(|eta| format!(", {} left", indicatif::HumanDuration(eta)))
+            .unwrap_or_default();
+
+        self.0.set_position(progress.bytes_read);
+        self.0.set_message(format!("{} documents, {} errors{}", progress.documents, progress.errors, eta));
+    }
 
-            fetcher.fetch_listings()
+    fn finish(&mut self, progress: &Progress) {
+        self.progress(progress);
+        self.0.finish();
+    }
+}
+
+/// Finds the documents by their publication-reference only, so the rest of
+/// the file isn't parsed, and writes each one found as it was (raw) or
+/// parsed in format
+fn extract(data_path: &Path, doc_numbers: &[String], format: Format, raw: bool) -> Result<(), Error> {
+    // normalized, to what was asked for
+    let mut wanted: HashMap<String, &str> = doc_numbers.iter()
+        .map(|doc_number| (grant_number(doc_number), doc_number.as_str()))
+        .collect();
+
+    let mut output = if raw { None } else { Some(Output::new(format, None)?) };
+    let mut out = BufWriter::new(io::stdout());
+
+    for document in Documents::from_path(data_path).context(UsPto)? {
+        let document = document.context(UsPto)?;
+        let doc_number = match split::doc_number(&document) {
+            Some(doc_number) => doc_number,
+            None => continue,
+        };
+        if wanted.remove(&grant_number(&doc_number)).is_none() {
+            continue;
+        }
+
+        match output {
+            Some(ref mut output) => match PatentGrants::from_reader(&document[..]).next() {
+                Some(Ok(patent)) => output.write(&patent)?,
+                Some(Err(err)) => eprintln!("{}", err),
+                None => {},
+            },
+            None => out.write_all(&document).context(WriteOutput)?,
+        }
+
+        if wanted.is_empty() {
+            break;
+        }
+    }
+
+    for doc_number in wanted.values() {
+        eprintln!("not found: {}", doc_number);
+    }
+
+    match output {
+        Some(output) => output.finish(),
+        None => out.flush().context(WriteOutput),
+    }
+}
+
+/// Downloads each file in the date range, and if given a format, parses it
+/// to stdout as soon as it's downloaded
+fn fetch(from: NaiveDate, to: NaiveDate, target_dir: &Path, product: FetchProduct, parse: Option<Format>) -> Result<(), Error> {
+    let product = match product {
+        FetchProduct::Fulltext => Product::GrantFullText,
+        FetchProduct::Bibliographic => Product::GrantBibliographic,
+    };
+
+    fs::create_dir_all(target_dir)
+        .context(WriteOutput)?;
+
+    let bulkdata = BulkData::new();
+    let files = bulkdata.resolve(product, from, to)
+        .context(UsPto)?;
+    eprintln!("{} files from {} to {}", files.len(), from, to);
+
+    let mut output = match parse {
+        Some(format) => Some(Output::new(format, None)?),
+        None => None,
+    };
+
+    for file in &files {
+        eprintln!("Fetching file {}", file.name);
+        let path = bulkdata.download(file, target_dir)
+            .context(UsPto)?;
+
+        if let Some(output) = output.as_mut() {
+            for patent_res in open_grants(&path, ParserOptions::new())? {
+                match patent_res {
+                    Ok(patent) => output.write(&patent)?,
+                    Err(err) => eprintln!("{}: {}", file.name, err),
+                }
+            }
+        }
+    }
+
+    match output {
+        Some(output) => output.finish(),
+        None => Ok(()),
+    }
+}
+
+/// Counts of the grants in a file, see `uspto::aggregate`
+fn stats(data_path: &Path) -> Result<(), Error> {
+    let mut errors = 0;
+    let mut aggregate = Aggregate::new();
+    let skipped = SkippedElements::new();
+
+    for patent_res in open_grants(data_path, ParserOptions::new().collect_skipped(&skipped))? {
+        match patent_res {
+            Ok(patent) => aggregate.add(&patent),
+            Err(err) => {
+                eprintln!("{}", err);
+                errors += 1;
+            },
+        }
+    }
+
+    println!("grants: {}", aggregate.grants);
+    println!("errors: {}", errors);
+    println!("claims: {}", aggregate.claims);
+    if let (Some(mean), Some(median), Some(max)) = (aggregate.mean_claims(), aggregate.claims_quantile(0.5), aggregate.claims_quantile(1.0)) {
+        println!("claims per grant: mean {:.1}, median {}, max {}", mean, median, max);
+    }
+    for (claims, count) in &aggregate.claims_per_grant {
+        println!("grants with {} claims: {}", claims, count);
+    }
+    for (kind, count) in &aggregate.by_kind {
+        println!("kind {}: {}", kind, count);
+    }
+    for (section, count) in &aggregate.by_cpc_section {
+        println!("cpc section {}: {}", section, count);
+    }
+    for (country, count) in &aggregate.by_assignee_country {
+        println!("assignee country {}: {}", country, count);
+    }
+    println!("citations: {} ({} patent, {} non-patent)", aggregate.citations(), aggregate.patent_citations, aggregate.non_patent_citations);
+    for (category, count) in &aggregate.citations_by_category {
+        println!("citations {}: {}", category, count);
+    }
+    // the parts of the schema not parsed yet
+    for (path, element) in &skipped.elements() {
+        let positions: Vec<_> = element.positions.iter().map(|pos| pos.to_string()).collect();
+        println!("skipped {}: {} (e.g. at bytes {} in their documents)", path, element.count, positions.join(", "));
+    }
+
+    Ok(())
+}
+
+/// Parses every grant in strict mode, reporting each failure. Each document
+/// is parsed on its own, so one that fails (the error's position is within
+/// it) can be copied as it was into quarantine_dir, as `<doc-number>.xml`,
+/// or `document-<n>.xml` if it hasn't got one
+fn validate(data_path: &Path, quarantine_dir: Option<&Path>) -> Result<(), Error> {
+    if let Some(dir) = quarantine_dir {
+        fs::create_dir_all(dir)
+            .context(WriteOutput)?;
+    }
+    let options = ParserOptions::new().check_consistency(true);
+
+    let mut grants = 0;
+    let mut errors = 0;
+    let mut offset = 0;
+    for (i, document_res) in Documents::from_path(data_path).context(UsPto)?.enumerate() {
+        let document = document_res.context(UsPto)?;
+        let start = offset;
+        offset += document.len();
+
+        let err = match PatentGrants::with_options(&document[..], options.clone()).next() {
+            Some(Ok(_)) => {
+                grants += 1;
+                continue;
+            },
+            Some(Err(err)) => err,
+            // e.g. only a declaration
+            None => continue,
+        };
+        grants += 1;
+        errors += 1;
+        eprintln!("document {} (at byte {}): {}", i + 1, start, err);
+
+        if let Some(dir) = quarantine_dir {
+            let name = match split::doc_number(&document) {
+                Some(doc_number) if doc_number.bytes().all(|b| b.is_ascii_alphanumeric()) => doc_number,
+                _ => format!("document-{}", i + 1),
+            };
+            fs::write(dir.join(format!("{}.xml", name)), &document)
+                .context(WriteOutput)?;
+        }
+    }
+
+    println!("{} grants ok, {} failed", grants - errors, errors);
+    if errors > 0 {
+        return Err(Error::Invalid { errors, grants });
+    }
+
+    Ok(())
+}
+
+/// Where `sync` exports each file's grants to
+enum SyncTarget {
+    Csv(PathBuf),
+    #[cfg(feature = "parquet")]
+    Parquet(PathBuf),
+    #[cfg(feature = "postgres")]
+    Postgres(String),
+    #[cfg(feature = "clickhouse")]
+    ClickHouse(String),
+}
+
+/// Exports the weekly files as they're issued: a file is recorded in the
+/// state file once all the grants parsed are exported, and all the files
+/// before it are too (they're synced in issue date order, and a pass stops
+/// at the first that fails). A file with documents that failed to parse is
+/// recorded with their number, and exported again at each pass until they
+/// all parse. A file that was being exported when the sync stopped is
+/// exported again from the start. Exporting a file again replaces what's
+/// there of it: its csv or parquet directory is rewritten, and postgres and
+/// ClickHouse replace the grants' rows by doc_number.
+struct SyncDaemon {
+    state_path: PathBuf,
+    target_dir: PathBuf,
+    from: NaiveDate,
+    product: FetchProduct,
+    target: SyncTarget,
+    interval: Duration,
+    metrics: Arc<Metrics>,
+}
+
+impl SyncDaemon {
+    /// Polls until stopped, unless once. A pass that fails (e.g. the portal
+    /// can't be reached) is reported and tried again at the next poll.
+    fn run(&self, once: bool) -> Result<(), Error> {
+        fs::create_dir_all(&self.target_dir)
+            .context(WriteOutput)?;
+
+        loop {
+            match self.pass() {
+                Ok(()) => {},
+                Err(err) if once => return Err(err),
+                Err(err) => {
+                    if let Error::UsPto { source } = &err {
+                        self.metrics.error(source);
+                    }
+                    eprintln!("{}", err);
+                },
+            }
+
+            if once {
+                return Ok(());
+            }
+            thread::sleep(self.interval);
+        }
+    }
+
+    /// Syncs the files not in the state file yet
+    fn pass(&self) -> Result<(), Error> {
+        let product = match self.product {
+            FetchProduct::Fulltext => Product::GrantFullText,
+            FetchProduct::Bibliographic => Product::GrantBibliographic,
+        };
+
+        let mut state = SyncState::open(&self.state_path)
+            .context(UsPto)?;
+        // the files before the last synced are synced too, so the listing
+        // can start there
+        let from = state.resume_date().map_or(self.from, |last| last.max(self.from));
+        let to = Utc::now().date_naive();
+
+        let bulkdata = BulkData::new();
+        let mut files = state.pending(bulkdata.resolve(product, from, to).context(UsPto)?);
+        files.sort_by_key(|file| file.date);
+        eprintln!("{} new files from {} to {}", files.len(), from, to);
+        self.metrics.pending(files.first().map(|file| file.date));
+
+        for (i, file) in files.iter().enumerate() {
+            eprintln!("Syncing file {}", file.name);
+            let path = bulkdata.download(file, &self.target_dir)
+                .context(UsPto)?;
+            let (grants, errors) = self.export(file, &path)?;
+
+            state.record(file, grants, errors)
                 .context(UsPto)?;
+            self.metrics.file_exported();
+            self.metrics.pending(files.get(i + 1).map(|file| file.date));
+            if errors > 0 {
+                eprintln!("Synced {} grants from {}, {} failed to parse (exported again next time)", grants, file.name, errors);
+            } else {
+                eprintln!("Synced {} grants from {}", grants, file.name);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The number of grants exported, and of documents that failed to parse
+    fn export(&self, file: &BulkFile, data_path: &Path) -> Result<(usize, usize), Error> {
+        let stem = Path::new(&file.name).file_stem()
+            .unwrap_or_default();
+
+        match &self.target {
+            SyncTarget::Csv(out_dir) => {
+                let mut exporter = CsvExporter::create(&out_dir.join(stem))
+                    .context(UsPto)?;
+                let exported = self.write_grants(data_path, |patent| exporter.write(patent).context(UsPto))?;
+                exporter.finish()
+                    .context(UsPto)?;
+
+                Ok(exported)
+            },
+            #[cfg(feature = "parquet")]
+            SyncTarget::Parquet(out_dir) => {
+                let mut exporter = ParquetExporter::create(&out_dir.join(stem))
+                    .context(UsPto)?;
+                let exported = self.write_grants(data_path, |patent| exporter.write(patent).context(UsPto))?;
+                exporter.finish()
+                    .context(UsPto)?;
+
+                Ok(exported)
+            },
+            #[cfg(feature = "postgres")]
+            SyncTarget::Postgres(url) => {
+                self.load_postgres(data_path, url)
+            },
+            #[cfg(feature = "clickhouse")]
+            SyncTarget::ClickHouse(url) => {
+                let mut exporter = ClickHouseExporter::new(url);
+                exporter.create_tables()
+                    .context(UsPto)?;
+                let exported = self.write_grants(data_path, |patent| exporter.write(patent).context(UsPto))?;
+                exporter.finish()
+                    .context(UsPto)?;
+
+                Ok(exported)
+            },
+        }
+    }
+
+    /// The file's grants, counted in the metrics
+    fn grants(&self, data_path: &Path) -> Result<LenientPatentGrants<InputReader>, Error> {
+        Ok(open_grants(data_path, ParserOptions::new())?
+            .observe(self.metrics.observer()))
+    }
+
+    fn parse_error(&self, err: uspto::Error) {
+        self.metrics.error(&err);
+        eprintln!("{}", err);
+    }
 
-            fetcher.fetch_all()
+    /// Writes each grant parsed; the number written, and that failed to
+    /// parse
+    fn write_grants<F>(&self, data_path: &Path, mut write: F) -> Result<(usize, usize), Error>
+        where F: FnMut(&PatentGrant) -> Result<(), Error>
+    {
+        let (mut grants, mut errors) = (0, 0);
+        for patent_res in self.grants(data_path)? {
+            match patent_res {
+                Ok(patent) => {
+                    write(&patent)?;
+                    grants += 1;
+                },
+                Err(err) => {
+                    self.parse_error(err);
+                    errors += 1;
+                },
+            }
+        }
+
+        Ok((grants, errors))
+    }
+
+    /// Over a connection of its own, on a runtime of its own
+    #[cfg(feature = "postgres")]
+    fn load_postgres(&self, data_path: &Path, url: &str) -> Result<(usize, usize), Error> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .context(WriteOutput)?;
+
+        runtime.block_on(async {
+            let (mut client, connection) = tokio_postgres::connect(url, tokio_postgres::NoTls)
+                .await
+                .context(ConnectPostgres)?;
+            tokio::spawn(async move {
+                if let Err(err) = connection.await {
+                    eprintln!("{}", err);
+                }
+            });
+
+            let mut loader = PostgresLoader::new(&mut client)
+                .await
                 .context(UsPto)?;
+            let (mut grants, mut errors) = (0, 0);
+            for patent_res in self.grants(data_path)? {
+                match patent_res {
+                    Ok(patent) => {
+                        loader.write(&patent).await.context(UsPto)?;
+                        grants += 1;
+                    },
+                    Err(err) => {
+                        self.parse_error(err);
+                        errors += 1;
+                    },
+                }
+            }
+            loader.finish()
+                .await
+                .context(UsPto)?;
+
+            Ok((grants, errors))
+        })
+    }
+}
+
+/// Writes the flattened tables (see `uspto::export`) in out_dir
+fn export(data_path: &Path, format: ExportFormat, out_dir: &Path) -> Result<(), Error> {
+    match format {
+        ExportFormat::Csv => run_export(data_path, CsvExporter::create(out_dir).context(UsPto)?, CsvExporter::write, CsvExporter::finish),
+        ExportFormat::Patentsview => {
+            let filename = data_path.file_stem()
+                .map(|stem| format!("{}.xml", stem.to_string_lossy()))
+                .unwrap_or_default();
+            let exporter = PatentsViewExporter::create(out_dir)
+                .context(UsPto)?
+                .filename(&filename);
 
-            Ok(())
+            run_export(data_path, exporter, PatentsViewExporter::write, PatentsViewExporter::finish)
         },
-        Command::Process { data_filepath, target_filepath } => {
-            process(&data_filepath, &target_filepath)
+        ExportFormat::Star => run_export(data_path, StarExporter::create(out_dir).context(UsPto)?, StarExporter::write, StarExporter::finish),
+        #[cfg(feature = "parquet")]
+        ExportFormat::Parquet => run_export(data_path, ParquetExporter::create(out_dir).context(UsPto)?, ParquetExporter::write, ParquetExporter::<fs::File>::finish),
+        #[cfg(feature = "avro")]
+        ExportFormat::Avro => run_export(data_path, AvroExporter::create(out_dir).context(UsPto)?, AvroExporter::write, AvroExporter::finish),
+        #[cfg(feature = "sqlite")]
+        ExportFormat::Sqlite => {
+            fs::create_dir_all(out_dir)
+                .context(WriteOutput)?;
+
+            run_export(data_path, SqliteExporter::create(&out_dir.join("uspto.sqlite")).context(UsPto)?, SqliteExporter::write, SqliteExporter::finish)
         },
     }
 }
 
-fn process(data_path: &Path, target_path: &Path) -> Result<(), Error> {
-    let f = fs::File::open(data_path)
-        .context(OpenDataFile)?;
-    let f = BufReader::new(f);
+/// Writes each grant parsed to the exporter, then finishes it. Grants that
+/// fail to parse are reported and skipped.
+fn run_export<E, W, F>(data_path: &Path, mut exporter: E, write: W, finish: F) -> Result<(), Error>
+    where W: Fn(&mut E, &PatentGrant) -> Result<(), uspto::Error>,
+          F: FnOnce(E) -> Result<(), uspto::Error>,
+{
+    for patent_res in open_grants(data_path, ParserOptions::new())? {
+        match patent_res {
+            Ok(patent) => write(&mut exporter, &patent).context(UsPto)?,
+            Err(err) => eprintln!("{}", err),
+        }
+    }
+
+    finish(exporter)
+        .context(UsPto)
+}
+
+/// Writes the sidecar index for random access by doc-number, see
+/// `IndexedGrants::open`
+fn index(data_path: &Path, index_path: Option<&Path>) -> Result<(), Error> {
+    let index = GrantIndex::build(data_path)
+        .context(UsPto)?;
+
+    let index_path = index_path.map(Path::to_path_buf).unwrap_or_else(|| GrantIndex::sidecar_path(data_path));
+    let file = fs::File::create(&index_path)
+        .context(WriteOutput)?;
+    index.write(file)
+        .context(UsPto)?;
 
-    let mut wtr = csv::Writer::from_path(target_path)
-        .context(WriteCsv)?;
+    eprintln!("{} grants indexed in {}", index.len(), index_path.display());
 
-    // deserialize returns an iter of PatentGrant
-    let patents = PatentGrants::from_reader(f);
-    for patent_res in patents {
+    Ok(())
+}
+
+/// Adds the grants to the full-text index in index_dir
+#[cfg(feature = "search")]
+fn full_text_index(data_path: &Path, index_dir: &Path) -> Result<(), Error> {
+    let index = SearchIndex::open_or_create(index_dir)
+        .context(UsPto)?;
+    let mut writer = index.writer()
+        .context(UsPto)?;
+
+    let mut grants = 0;
+    for patent_res in open_grants(data_path, ParserOptions::new())? {
         match patent_res {
             Ok(patent) => {
-                let output: PatentOutput = (&patent).into();
-                wtr.serialize(output)
-                    .context(WriteCsv)?;
-
-                // Some examples for inspecting data. You may be able to also use grep
-                //if patent.us_bibliographic_data_grant.publication_reference.doc_number == "RE047539" {
-                //    println!("{:#?}", patent);
-                //}
-                //println!("{:#?}", patent.us_bibliographic_data_grant.examiners);
-                //println!("{:#?}", patent.us_bibliographic_data_grant.assignees);
-                //println!("{:#?}", patent.us_bibliographic_data_grant.agents);
-                //println!("{:#?}", patent.us_bibliographic_data_grant.inventors);
-                //println!("{:#?}", patent.us_bibliographic_data_grant.us_applicants);
-                //println!("{:#?}", patent.us_bibliographic_data_grant.us_field_of_classification_search);
-                //println!("{:#?}", patent.us_bibliographic_data_grant.number_of_claims);
-                //println!("{:#?}", patent.us_bibliographic_data_grant.us_exemplary_claim);
-                //println!("{:#?}", patent.us_bibliographic_data_grant.invention_title);
-                //println!("{:#?}", patent.us_bibliographic_data_grant.us_term_of_grant);
-                //println!("{:#?}", patent.us_bibliographic_data_grant.us_application_series_code);
-                //println!("{:#?}", patent.us_bibliographic_data_grant.classification_national);
-                //println!("{:#?}", patent.us_bibliographic_data_grant.classification_locarno);
-                //println!("{:#?}", patent.us_bibliographic_data_grant.application_reference);
-                //println!("{:#?}", patent.us_bibliographic_data_grant.publication_reference);
-                //println!("{:#?}", patent.descriptions);
-                //println!("{:#?}", patent.us_claim_statement);
-                //println!("{:#?}", patent.claims);
-                //println!("{:#?}", patent);
+                writer.write(&patent).context(UsPto)?;
+                grants += 1;
             },
-            Err(err) => {
-                eprintln!("{}", err);
-                break;
+            Err(err) => eprintln!("{}", err),
+        }
+    }
+
+    writer.finish()
+        .context(UsPto)?;
+
+    eprintln!("{} grants indexed in {}", grants, index_dir.display());
+
+    Ok(())
+}
+
+#[cfg(feature = "search")]
+fn search(index_dir: &Path, query: &str, limit: usize) -> Result<(), Error> {
+    let hits = SearchIndex::open(index_dir)
+        .and_then(|index| index.search(query, limit))
+        .context(UsPto)?;
+
+    for hit in hits {
+        let date = hit.date.map(|date| date.to_string()).unwrap_or_default();
+        println!("{}\t{}\t{:.3}\t{}", hit.doc_number, date, hit.score, hit.title);
+    }
+
+    Ok(())
+}
+
+/// Which grants `filter` keeps: those matching every filter given
+struct Filter {
+    // publication dates, as YYYYMMDD
+    from: Option<String>,
+    to: Option<String>,
+    // see cpc_key; any of them
+    cpc: Vec<String>,
+    // lowercase
+    assignee: Option<String>,
+    // any of them
+    kinds: Vec<KindCode>,
+}
+
+impl Filter {
+    fn matches(&self, patent: &PatentGrant) -> bool {
+        let biblio = &patent.us_bibliographic_data_grant;
+        let publication = &biblio.publication_reference;
+
+        let date = date_text(&publication.date);
+        if self.from.as_ref().is_some_and(|from| date < *from) || self.to.as_ref().is_some_and(|to| date > *to) {
+            return false;
+        }
+
+        if !self.kinds.is_empty() && !publication.kind.as_ref().is_some_and(|kind| self.kinds.contains(kind)) {
+            return false;
+        }
+
+        if !self.cpc.is_empty() {
+            let cpcs = &biblio.classifications_cpc;
+            let matches = std::iter::once(&cpcs.main_cpc)
+                .chain(&cpcs.further_cpc)
+                .filter(|cpc| !cpc.section.is_empty())
+                .map(|cpc| cpc_key(&cpc.symbol()))
+                .any(|symbol| self.cpc.iter().any(|prefix| symbol.starts_with(prefix.as_str())));
+            if !matches {
+                return false;
+            }
+        }
+
+        if let Some(ref assignee) = self.assignee {
+            let matches = biblio.assignees.iter().any(|a| {
+                let addressbook = &a.addressbook;
+                [&a.orgname, &addressbook.orgname, &addressbook.last_name, &addressbook.first_name].iter()
+                    .filter_map(|name| name.as_deref())
+                    .any(|name| name.to_lowercase().contains(assignee.as_str()))
+            });
+            if !matches {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// A CPC symbol (or prefix of one) without its spaces, e.g. "A01B1/00", so
+/// "A01B 1/" and "A01B1/" are the same prefix
+fn cpc_key(symbol: &str) -> String {
+    symbol.split_whitespace().collect::<String>().to_uppercase()
+}
+
+/// Writes the documents of a grants file that filter keeps to target_path
+/// (stdout if not given), with their original bytes; the output is a grants
+/// file itself. Only the biblio and parties of each are parsed.
+fn filter_documents(data_path: &Path, target_path: Option<&Path>, filter: &Filter) -> Result<(), Error> {
+    let mut out: Box<dyn Write> = match target_path {
+        Some(path) => Box::new(BufWriter::new(fs::File::create(path).context(WriteOutput)?)),
+        None => Box::new(BufWriter::new(io::stdout())),
+    };
+    let options = ParserOptions::new().projection(Projection::BIBLIO | Projection::PARTIES);
+
+    let mut grants = 0;
+    let mut matched = 0;
+    for document_res in Documents::from_path(data_path).context(UsPto)? {
+        let document = document_res.context(UsPto)?;
+
+        match PatentGrants::with_options(&document[..], options.clone()).next() {
+            Some(Ok(patent)) => {
+                grants += 1;
+                if filter.matches(&patent) {
+                    out.write_all(&document)
+                        .context(WriteOutput)?;
+                    matched += 1;
+                }
             },
+            Some(Err(err)) => eprintln!("{}", err),
+            // e.g. only a declaration
+            None => continue,
         }
     }
 
-    wtr.flush()
+    out.flush()
         .context(WriteOutput)?;
+    eprintln!("{} of {} grants matched", matched, grants);
 
+    Ok(())
+}
+
+/// Only each grant's field hashes are kept, see `uspto::diff`
+fn diff(old_path: &Path, new_path: &Path) -> Result<(), Error> {
+    let snapshot = |path: &Path| -> Result<Snapshot, Error> {
+        let mut snapshot = Snapshot::new();
+        for patent_res in open_grants(path, ParserOptions::new())? {
+            match patent_res {
+                Ok(patent) => snapshot.add(&patent),
+                Err(err) => eprintln!("{}", err),
+            }
+        }
+        Ok(snapshot)
+    };
+
+    let diff = snapshot(old_path)?.diff(&snapshot(new_path)?);
+    for doc_number in &diff.added {
+        println!("added {}", doc_number);
+    }
+    for doc_number in &diff.removed {
+        println!("removed {}", doc_number);
+    }
+    for (doc_number, fields) in &diff.changed {
+        println!("changed {}: {}", doc_number, fields.join(", "));
+    }
+    eprintln!("{} added, {} removed, {} changed, {} unchanged", diff.added.len(), diff.removed.len(), diff.changed.len(), diff.unchanged);
 
     Ok(())
 }
 
-#[derive(Debug, StructOpt)]
-#[structopt(name="uspto")]
+/// A census of the raw markup, see `uspto::census`; a document with bad xml
+/// is reported, with what was read of it counted
+fn inspect(data_path: &Path, names: bool) -> Result<(), Error> {
+    let mut census = TagCensus::new();
+    for (i, document_res) in Documents::from_path(data_path).context(UsPto)?.enumerate() {
+        let document = document_res.context(UsPto)?;
+        if let Err(err) = census.add_document(&document) {
+            eprintln!("document {}: {}", i + 1, err);
+        }
+    }
+
+    println!("documents: {}", census.documents);
+    if names {
+        for (name, count) in &census.element_names() {
+            println!("element {}: {}", name, count);
+        }
+        for (name, count) in &census.attribute_names() {
+            println!("attribute {}: {}", name, count);
+        }
+    } else {
+        for (path, count) in &census.paths {
+            println!("element {}: {}", path, count);
+        }
+        for (attribute, count) in &census.attributes {
+            println!("attribute {}: {}", attribute, count);
+        }
+    }
+
+    Ok(())
+}
+
+/// Documents are found by the bulk file's declarations, and named by their
+/// publication-reference only, so none is parsed further. One without a
+/// doc-number it can be named by is reported and left out.
+fn split_documents(data_path: &Path, out_dir: &Path, shard_width: Option<usize>) -> Result<(), Error> {
+    fs::create_dir_all(out_dir)
+        .context(WriteOutput)?;
+
+    let mut written = 0;
+    for (i, document_res) in Documents::from_path(data_path).context(UsPto)?.enumerate() {
+        let document = document_res.context(UsPto)?;
+
+        // the doc-number is the path, so nothing that could leave out_dir
+        let doc_number = match split::doc_number(&document) {
+            Some(doc_number) if doc_number.bytes().all(|b| b.is_ascii_alphanumeric()) => doc_number,
+            _ => {
+                eprintln!("document {}: no doc-number to name it by", i + 1);
+                continue;
+            },
+        };
+
+        let mut dir = out_dir.to_path_buf();
+        if let Some(width) = shard_width {
+            dir.push(&doc_number[doc_number.len().saturating_sub(width)..]);
+            fs::create_dir_all(&dir)
+                .context(WriteOutput)?;
+        }
+
+        fs::write(dir.join(format!("{}.xml", doc_number)), &document)
+            .context(WriteOutput)?;
+        written += 1;
+    }
+    eprintln!("{} documents written to {}", written, out_dir.display());
+
+    Ok(())
+}
+
+/// Where parsed grants are written to, in the chosen format
+enum Output {
+    Csv(Box<csv::Writer<Box<dyn Write>>>),
+    #[cfg(feature = "serde")]
+    Jsonl(Box<dyn Write>),
+    Debug(Box<dyn Write>),
+    /// whether a document has been written yet, to rule off the next one
+    Markdown(Box<dyn Write>, bool),
+}
+
+impl Output {
+    /// no target path means stdout, so output can be piped (e.g. into jq)
+    fn new(format: Format, target_path: Option<&Path>) -> Result<Self, Error> {
+        let out: Box<dyn Write> = match target_path {
+            Some(path) => Box::new(BufWriter::new(fs::File::create(path).context(WriteOutput)?)),
+            None => Box::new(BufWriter::new(io::stdout())),
+        };
+
+        match format {
+            Format::Csv => Ok(Output::Csv(Box::new(csv::Writer::from_writer(out)))),
+            #[cfg(feature = "serde")]
+            Format::Jsonl => Ok(Output::Jsonl(out)),
+            #[cfg(not(feature = "serde"))]
+            Format::Jsonl => Err(Error::JsonlUnsupported),
+            Format::Debug => Ok(Output::Debug(out)),
+            Format::Markdown => Ok(Output::Markdown(out, false)),
+        }
+    }
+
+    fn write(&mut self, patent: &PatentGrant) -> Result<(), Error> {
+        match self {
+            Output::Csv(wtr) => {
+                let output: PatentOutput = patent.into();
+                wtr.serialize(output)
+                    .context(WriteCsv)?;
+            },
+            #[cfg(feature = "serde")]
+            Output::Jsonl(out) => {
+                // one json object per line
+                serde_json::to_writer(&mut *out, patent)
+                    .context(WriteJson)?;
+                writeln!(out)
+                    .context(WriteOutput)?;
+            },
+            Output::Debug(out) => {
+                writeln!(out, "{:#?}", patent)
+                    .context(WriteOutput)?;
+            },
+            Output::Markdown(out, written) => {
+                if std::mem::replace(written, true) {
+                    writeln!(out, "\n---\n")
+                        .context(WriteOutput)?;
+                }
+                write!(out, "{}", patent.to_markdown())
+                    .context(WriteOutput)?;
+            },
+        }
+
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), Error> {
+        match self {
+            Output::Csv(mut wtr) => wtr.flush(),
+            #[cfg(feature = "serde")]
+            Output::Jsonl(mut out) => out.flush(),
+            Output::Debug(mut out) => out.flush(),
+            Output::Markdown(mut out, _) => out.flush(),
+        }.context(WriteOutput)
+    }
+}
+
+#[derive(Debug, Parser)]
+#[command(name="uspto", about="Fetch and parse USPTO patent grant bulk data")]
 struct CliOpt {
-    #[structopt(subcommand)]
+    #[command(subcommand)]
     command: Command,
+    /// Show a progress bar while parsing grants files from disk. Requires
+    /// building with the `progress` feature
+    #[cfg(feature = "progress")]
+    #[arg(long="progress", global=true)]
+    progress: bool,
 }
 
-#[derive(Debug, StructOpt)]
+#[derive(Debug, Subcommand)]
 enum Command {
-    #[structopt(name="fetch")]
+    /// Download the weekly grant files issued in a date range
     Fetch {
-        // TODO this currently only allows one year.
-        #[structopt(long="year")]
-        year: i32,
-        #[structopt(long="target-dir", parse(from_os_str))]
+        /// First issue date, e.g. 2023-01-01
+        #[arg(long="from")]
+        from: NaiveDate,
+        /// Last issue date (inclusive)
+        #[arg(long="to")]
+        to: NaiveDate,
+        #[arg(long="target-dir")]
+        target_dir: PathBuf,
+        #[arg(long="product", value_enum, default_value_t=FetchProduct::Fulltext)]
+        product: FetchProduct,
+        /// Also parse each file as it's downloaded, writing to stdout in this format
+        #[arg(long="parse", value_enum)]
+        parse: Option<Format>,
+    },
+    /// Keep an export up to date with the weekly grant files: poll the bulk
+    /// data portal, and download, parse and export each file issued since
+    /// `--from` that isn't in the state file yet, recording it there once
+    /// it's exported (and exporting it again while any of its documents fail
+    /// to parse)
+    Sync {
+        /// A record of the files synced; created if it isn't there
+        #[arg(long="state-file")]
+        state_file: PathBuf,
+        /// Where the files are downloaded to
+        #[arg(long="target-dir")]
         target_dir: PathBuf,
+        /// First issue date, e.g. 2023-01-01
+        #[arg(long="from")]
+        from: NaiveDate,
+        #[arg(long="product", value_enum, default_value_t=FetchProduct::Fulltext)]
+        product: FetchProduct,
+        #[arg(long="exporter", value_enum, default_value_t=SyncExporter::Csv)]
+        exporter: SyncExporter,
+        /// For csv and parquet: each file's tables are written to a
+        /// subdirectory of this named by the file, e.g. `ipg190101/`
+        #[arg(long="out-dir")]
+        out_dir: Option<PathBuf>,
+        /// For postgres, its connection string (e.g. `host=localhost
+        /// user=uspto`); for clickhouse, the url of its HTTP interface
+        #[arg(long="url")]
+        url: Option<String>,
+        /// Hours between polls
+        #[arg(long="interval-hours", default_value_t=24)]
+        interval_hours: u64,
+        /// Sync the files issued so far and exit, instead of polling
+        #[arg(long="once")]
+        once: bool,
+        /// Serve Prometheus metrics of the sync at `/metrics` on this
+        /// address, e.g. 0.0.0.0:9898
+        #[arg(long="metrics-addr")]
+        metrics_addr: Option<String>,
+    },
+    /// Parse a grants file and write each grant out
+    #[command(alias="process")]
+    Parse {
+        data_filepath: PathBuf,
+        /// Output file; writes to stdout if not given
+        #[arg(long="target-path")]
+        target_filepath: Option<PathBuf>,
+        /// jsonl requires building with the `serde` feature
+        #[arg(long="format", value_enum, default_value_t=Format::Csv)]
+        format: Format,
+        /// Stop after this many grants
+        #[arg(long="limit")]
+        limit: Option<usize>,
+        /// Check each grant for consistency, see `PatentGrants::strict`
+        #[arg(long="strict")]
+        strict: bool,
+    },
+    /// Print counts of the grants in a grants file: by kind code, CPC section
+    /// and assignee country, of their claims and citations, and of the
+    /// elements skipped as unknown
+    Stats {
+        data_filepath: PathBuf,
+    },
+    /// Parse a grants file in strict mode, reporting every grant that fails
+    /// and the counts of those that passed and failed
+    Validate {
+        data_filepath: PathBuf,
+        /// Copy the xml of each grant that fails into this directory
+        #[arg(long="quarantine-dir")]
+        quarantine_dir: Option<PathBuf>,
+    },
+    /// Print the grants with the given publication doc numbers, parsing
+    /// only their publication-reference until they're found
+    Extract {
+        data_filepath: PathBuf,
+        /// Publication doc number, e.g. 10165721 or D0838001; may be repeated
+        #[arg(long="doc-number", required=true)]
+        doc_numbers: Vec<String>,
+        #[arg(long="format", value_enum, default_value_t=Format::Debug)]
+        format: Format,
+        /// Write each grant's xml as it is in the grants file, instead of
+        /// parsing it
+        #[arg(long="raw", conflicts_with="format")]
+        raw: bool,
+    },
+    /// Flatten a grants file into tables of grants, claims, citations,
+    /// classifications, and parties, keyed on publication doc number
+    Export {
+        data_filepath: PathBuf,
+        #[arg(long="format", value_enum, default_value_t=ExportFormat::Csv)]
+        format: ExportFormat,
+        /// Directory to write one file per table (or the database) into
+        #[arg(long="out-dir")]
+        out_dir: PathBuf,
+    },
+    /// Index where each grant is in a grants file (plain xml), for reading
+    /// grants by doc number without parsing the whole file
+    Index {
+        data_filepath: PathBuf,
+        /// Index file; defaults to the grants file's path with `.idx` added,
+        /// where `IndexedGrants::open` looks for it
+        #[arg(long="output")]
+        index_filepath: Option<PathBuf>,
+        /// Add the grants to a full-text index in this directory instead
+        /// (created if needed), for `search`. Requires building with the
+        /// `search` feature
+        #[cfg(feature = "search")]
+        #[arg(long="full-text", conflicts_with="index_filepath")]
+        full_text: Option<PathBuf>,
+    },
+    /// Write the grants of a grants file that match every filter given to a
+    /// new grants file, each exactly as it was in the original
+    Filter {
+        data_filepath: PathBuf,
+        /// Output file; writes to stdout if not given
+        #[arg(long="target-path")]
+        target_filepath: Option<PathBuf>,
+        /// Earliest publication date, e.g. 2019-01-01
+        #[arg(long="from")]
+        from: Option<NaiveDate>,
+        /// Latest publication date (inclusive)
+        #[arg(long="to")]
+        to: Option<NaiveDate>,
+        /// Prefix of any of the grant's CPC symbols, e.g. A01B or "A01B 1/";
+        /// may be repeated
+        #[arg(long="cpc")]
+        cpc: Vec<String>,
+        /// Part of any assignee's name, ignoring case
+        #[arg(long="assignee")]
+        assignee: Option<String>,
+        /// Kind code, e.g. B2 or S1; may be repeated
+        #[arg(long="kind")]
+        kinds: Vec<String>,
     },
-    #[structopt(name="process")]
-    Process {
-        #[structopt(parse(from_os_str))]
+    /// Compare two grants files by doc number, printing the grants added to,
+    /// removed from, and changed in the new one, with the fields that changed
+    Diff {
+        old_filepath: PathBuf,
+        new_filepath: PathBuf,
+    },
+    /// Print every element path and attribute in a grants file, with how
+    /// often each occurs, whether the parser reads it or not
+    Inspect {
+        data_filepath: PathBuf,
+        /// Count elements and attributes by name alone, instead of by path
+        #[arg(long="names")]
+        names: bool,
+    },
+    /// Write each document of a grants file to its own file,
+    /// `<out-dir>/<doc-number>.xml`, exactly as it was
+    Split {
         data_filepath: PathBuf,
-        #[structopt(long="target-path", parse(from_os_str))]
-        target_filepath: PathBuf,
+        #[arg(long="out-dir")]
+        out_dir: PathBuf,
+        /// Put each file in a subdirectory named by the last this many
+        /// characters of its doc-number (e.g. 3, for `721/10165721.xml`), so
+        /// no directory holds more than a fraction of the files
+        #[arg(long="shard-width")]
+        shard_width: Option<usize>,
+    },
+    /// Search a full-text index built with `index --full-text`, printing the
+    /// doc number, date, score and title of each grant found, best first.
+    /// Requires building with the `search` feature
+    #[cfg(feature = "search")]
+    Search {
+        index_dir: PathBuf,
+        /// In tantivy's query syntax; searches the title, abstract, claims
+        /// and description, unless a field (doc_number, kind, date, cpc,
+        /// cpc_subclass) is given, e.g. `cpc_subclass:A01B AND shovel`
+        query: String,
+        #[arg(long="limit", default_value_t=10)]
+        limit: usize,
     },
 }
 
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum FetchProduct {
+    Fulltext,
+    Bibliographic,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    Csv,
+    /// PatentsView's bulk download layout: patent.tsv, inventor.tsv,
+    /// assignee.tsv, cpc_current.tsv and uspatentcitation.tsv
+    Patentsview,
+    /// a star schema for an OLAP cube: fact_grant, dimension tables of
+    /// dates, assignees, inventors and cpc codes, and bridges between them
+    Star,
+    /// requires building with the `parquet` feature
+    #[cfg(feature = "parquet")]
+    Parquet,
+    /// one database, `uspto.sqlite`, with a table for each; grants already
+    /// in it are replaced. Requires building with the `sqlite` feature
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+    /// a `<table>.avro` container file for each, with its schema. Requires
+    /// building with the `avro` feature
+    #[cfg(feature = "avro")]
+    Avro,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum SyncExporter {
+    /// the `export` csv tables
+    Csv,
+    /// requires building with the `parquet` feature
+    #[cfg(feature = "parquet")]
+    Parquet,
+    /// requires building with the `postgres` feature
+    #[cfg(feature = "postgres")]
+    Postgres,
+    /// requires building with the `clickhouse` feature
+    #[cfg(feature = "clickhouse")]
+    Clickhouse,
+}
+
+impl SyncExporter {
+    /// the option it exports to
+    fn option(self) -> &'static str {
+        match self {
+            SyncExporter::Csv => "--out-dir",
+            #[cfg(feature = "parquet")]
+            SyncExporter::Parquet => "--out-dir",
+            #[cfg(feature = "postgres")]
+            SyncExporter::Postgres => "--url",
+            #[cfg(feature = "clickhouse")]
+            SyncExporter::Clickhouse => "--url",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Csv,
+    Jsonl,
+    Debug,
+    /// a Markdown document for each grant
+    #[value(name = "md")]
+    Markdown,
+}
+
 #[derive(Debug, Snafu)]
 enum Error {
-    #[snafu(display("Cli Error: missing filepath"))]
-    CliNoPath,
-    #[snafu(display("Open Datafile Error: {}", source))]
-    OpenDataFile { source: std::io::Error },
-    #[snafu(display("Read Datafile Error: {}", source))]
-    ReadDataFile { source: std::io::Error },
     #[snafu(display("USPTO lib Error: {}", source))]
     UsPto { source: uspto::Error },
     #[snafu(display("Write Csv Error: {}", source))]
     WriteCsv { source: csv::Error },
+    #[cfg(feature = "serde")]
+    #[snafu(display("Write Json Error: {}", source))]
+    WriteJson { source: serde_json::Error },
     #[snafu(display("Serialize Output Error: {}", source))]
     WriteOutput { source: std::io::Error },
+    #[cfg(feature = "progress")]
+    #[snafu(display("Read Input Error: {}", source))]
+    ReadInput { source: std::io::Error },
+    #[cfg(not(feature = "serde"))]
+    #[snafu(display("jsonl output requires building with the `serde` feature"))]
+    JsonlUnsupported,
+    #[snafu(display("Validation Error: {} of {} grants failed", errors, grants))]
+    Invalid { errors: usize, grants: usize },
+    #[snafu(display("{} is required for the {:?} exporter", option, exporter))]
+    MissingOption { option: &'static str, exporter: SyncExporter },
+    #[cfg(feature = "postgres")]
+    #[snafu(display("Connect Postgres Error: {}", source))]
+    ConnectPostgres { source: tokio_postgres::Error },
 }
-
@@ -0,0 +1,46 @@
+// uspto-serve: the Parser service of proto/uspto.proto over gRPC, so
+// clients in any language can have bulk files parsed without parsing them
+// themselves. See `uspto::grpc`.
+
+use clap::Parser;
+use std::net::SocketAddr;
+use std::process;
+use tonic::transport::Server;
+use uspto::grpc::ParseService;
+use uspto::proto::parser_server::ParserServer;
+use uspto::ParserOptions;
+
+#[derive(Debug, Parser)]
+#[command(name="uspto-serve", about="Parse USPTO patent grant bulk files for gRPC clients")]
+struct ServeOpt {
+    /// Address to listen on
+    #[arg(long="addr", default_value="127.0.0.1:50051")]
+    addr: SocketAddr,
+    /// Check each grant for consistency, see `PatentGrants::strict`
+    #[arg(long="strict")]
+    strict: bool,
+    /// Parse urls clients send that start with this prefix (e.g.
+    /// https://bulkdata.uspto.gov/), as well as streamed bytes; can be given
+    /// more than once. Without it, no url is fetched.
+    #[arg(long="allow-url", value_name="PREFIX")]
+    allow_urls: Vec<String>,
+}
+
+#[tokio::main]
+async fn main() {
+    let opts = ServeOpt::parse();
+    let service = ParseService::new()
+        .options(ParserOptions::new().check_consistency(opts.strict))
+        .allow_urls(opts.allow_urls);
+
+    eprintln!("Listening on {}", opts.addr);
+    let served = Server::builder()
+        .add_service(ParserServer::new(service))
+        .serve(opts.addr)
+        .await;
+
+    if let Err(err) = served {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
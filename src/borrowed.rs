@@ -0,0 +1,470 @@
+//! Grants whose fields borrow from the document's bytes, for pipelines that
+//! only look at a few fields of each and would otherwise pay for allocating
+//! all of them.
+//!
+//! The biblio's ids, title, classifications, citations and parties are read,
+//! and the document isn't read past the biblio. A field is only allocated if
+//! its text has entities (or tags) to resolve, or isn't UTF-8. Grants from
+//! 2005 on only (not PATDOC or APS).
+
+use quick_xml::{self, Reader};
+use quick_xml::events::{BytesText, Event};
+use std::borrow::Cow;
+use std::io::BufRead;
+
+use crate::error::Error;
+use crate::options::ParserOptions;
+use crate::split::Documents;
+use crate::util::{decode_bytes, decode_text};
+
+/// An iterator whose items borrow from it, so each is only valid until the
+/// next is read (and they can't be collected)
+pub trait LendingIterator {
+    type Item<'a> where Self: 'a;
+
+    fn next(&mut self) -> Option<Self::Item<'_>>;
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DocumentIdRef<'a> {
+    pub country: Cow<'a, str>,
+    pub doc_number: Cow<'a, str>,
+    pub kind: Option<Cow<'a, str>>,
+    /// of a cited document, the patentee
+    pub name: Option<Cow<'a, str>>,
+    /// as in the xml, YYYYMMDD
+    pub date: Cow<'a, str>,
+}
+
+/// An IPC or CPC symbol, in its parts: e.g. `B`, `25`, `G`, `1` and `102`
+/// for B25G 1/102
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ClassificationRef<'a> {
+    pub section: Cow<'a, str>,
+    pub class: Cow<'a, str>,
+    pub subclass: Cow<'a, str>,
+    pub main_group: Cow<'a, str>,
+    pub subgroup: Cow<'a, str>,
+}
+
+/// A cited patent (document_id) or other literature (othercit)
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CitationRef<'a> {
+    pub document_id: Option<DocumentIdRef<'a>>,
+    pub othercit: Option<Cow<'a, str>>,
+    /// e.g. "cited by examiner"
+    pub category: Option<Cow<'a, str>>,
+}
+
+/// An applicant, inventor, agent or assignee: their addressbook
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PartyRef<'a> {
+    pub orgname: Option<Cow<'a, str>>,
+    pub first_name: Option<Cow<'a, str>>,
+    pub last_name: Option<Cow<'a, str>>,
+    pub city: Option<Cow<'a, str>>,
+    pub state: Option<Cow<'a, str>>,
+    pub country: Option<Cow<'a, str>>,
+}
+
+/// The biblio fields of a PatentGrant most often filtered or joined on, as
+/// text
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PatentGrantRef<'a> {
+    pub publication_reference: DocumentIdRef<'a>,
+    pub application_reference: DocumentIdRef<'a>,
+    pub us_application_series_code: Cow<'a, str>,
+    pub classifications_ipcr: Vec<ClassificationRef<'a>>,
+    /// the main classification first, then the further ones
+    pub classifications_cpc: Vec<ClassificationRef<'a>>,
+    pub invention_title: Cow<'a, str>,
+    pub us_references_cited: Vec<CitationRef<'a>>,
+    pub us_applicants: Vec<PartyRef<'a>>,
+    pub inventors: Vec<PartyRef<'a>>,
+    pub agents: Vec<PartyRef<'a>>,
+    pub assignees: Vec<PartyRef<'a>>,
+}
+
+// names of the elements read; any other is kept on the stack as empty
+const NAMES: &[&[u8]] = &[
+    b"us-bibliographic-data-grant",
+    b"publication-reference",
+    b"application-reference",
+    b"document-id",
+    b"country",
+    b"doc-number",
+    b"kind",
+    b"name",
+    b"date",
+    b"us-application-series-code",
+    b"classifications-ipcr",
+    b"classification-ipcr",
+    b"classifications-cpc",
+    b"main-cpc",
+    b"further-cpc",
+    b"classification-cpc",
+    b"section",
+    b"class",
+    b"subclass",
+    b"main-group",
+    b"subgroup",
+    b"invention-title",
+    b"us-references-cited",
+    b"us-citation",
+    b"patcit",
+    b"nplcit",
+    b"othercit",
+    b"category",
+    b"us-parties",
+    b"us-applicants",
+    b"us-applicant",
+    b"inventors",
+    b"inventor",
+    b"agents",
+    b"agent",
+    b"assignees",
+    b"assignee",
+    b"addressbook",
+    b"orgname",
+    b"first-name",
+    b"last-name",
+    b"address",
+    b"city",
+    b"state",
+];
+
+// names of the elements renamed in v4.3 (see `schema`), as the later ones
+const RENAMED: &[(&[u8], &[u8])] = &[
+    (b"references-cited", b"us-references-cited"),
+    (b"citation", b"us-citation"),
+    (b"parties", b"us-parties"),
+    (b"applicants", b"us-applicants"),
+    (b"applicant", b"us-applicant"),
+];
+
+impl<'a> PatentGrantRef<'a> {
+    /// From one document (as split by `split::Documents`). None if it has no
+    /// elements, e.g. only a declaration.
+    pub fn parse(document: &'a [u8], opts: &ParserOptions) -> Result<Option<Self>, Error> {
+        let mut rdr = Reader::from_reader(document);
+        let mut buf = Vec::new();
+        let mut stack: Vec<&'static [u8]> = Vec::new();
+        // where the content of each element on the stack starts
+        let mut starts: Vec<usize> = Vec::new();
+        let mut grant = PatentGrantRef::default();
+        let mut found_biblio = false;
+        let mut found_element = false;
+
+        loop {
+            let before = rdr.buffer_position();
+            match rdr.read_event(&mut buf) {
+                Ok(Event::Start(ref e)) => {
+                    found_element = true;
+                    stack.push(element_name(e.name()));
+                    starts.push(rdr.buffer_position());
+                    grant.start(&stack);
+                },
+                Ok(Event::Empty(_)) => found_element = true,
+                Ok(Event::End(_)) => {
+                    let content = starts.pop().map(|start| &document[start..before]);
+                    if let [.., b"us-bibliographic-data-grant"] = &stack[..] {
+                        found_biblio = true;
+                        break;
+                    }
+                    if let (Some(field), Some(content)) = (grant.field(&stack), content) {
+                        *field = content_text(content, &rdr, opts)?;
+                    }
+                    stack.pop();
+                },
+                Ok(Event::Eof) => break,
+                Ok(_) => (),
+                Err(err) => return Err(Error::deser(err.to_string())),
+            }
+            buf.clear();
+        }
+
+        match (found_biblio, found_element) {
+            (true, _) => Ok(Some(grant)),
+            (false, true) => Err(Error::deser("no us-bibliographic-data-grant in document".to_string())),
+            (false, false) => Ok(None),
+        }
+    }
+
+    /// adds the item an element starts, if it's one of a list
+    fn start(&mut self, stack: &[&[u8]]) {
+        match stack {
+            [.., b"us-bibliographic-data-grant", b"classifications-ipcr", b"classification-ipcr"] => {
+                self.classifications_ipcr.push(ClassificationRef::default());
+            },
+            [.., b"us-bibliographic-data-grant", b"classifications-cpc", b"main-cpc" | b"further-cpc", b"classification-cpc"] => {
+                self.classifications_cpc.push(ClassificationRef::default());
+            },
+            [.., b"us-bibliographic-data-grant", b"us-references-cited", b"us-citation"] => {
+                self.us_references_cited.push(CitationRef::default());
+            },
+            [.., b"us-bibliographic-data-grant", b"us-references-cited", b"us-citation", b"patcit", b"document-id"] => {
+                if let Some(citation) = self.us_references_cited.last_mut() {
+                    citation.document_id = Some(DocumentIdRef::default());
+                }
+            },
+            [.., b"us-bibliographic-data-grant", b"us-parties", list, _] |
+            [.., b"us-bibliographic-data-grant", list @ b"assignees", b"assignee"] => {
+                if let Some(parties) = self.parties(list) {
+                    parties.push(PartyRef::default());
+                }
+            },
+            _ => (),
+        }
+    }
+
+    /// the field for the text of the element ending
+    fn field(&mut self, stack: &[&[u8]]) -> Option<&mut Cow<'a, str>> {
+        match stack {
+            [.., b"us-bibliographic-data-grant", b"publication-reference", b"document-id", name] => {
+                document_id_field(&mut self.publication_reference, name)
+            },
+            [.., b"us-bibliographic-data-grant", b"application-reference", b"document-id", name] => {
+                document_id_field(&mut self.application_reference, name)
+            },
+            [.., b"us-bibliographic-data-grant", b"us-application-series-code"] => Some(&mut self.us_application_series_code),
+            [.., b"us-bibliographic-data-grant", b"invention-title"] => Some(&mut self.invention_title),
+            [.., b"us-bibliographic-data-grant", b"classifications-ipcr", b"classification-ipcr", name] => {
+                classification_field(self.classifications_ipcr.last_mut()?, name)
+            },
+            [.., b"us-bibliographic-data-grant", b"classifications-cpc", _, b"classification-cpc", name] => {
+                classification_field(self.classifications_cpc.last_mut()?, name)
+            },
+            [.., b"us-bibliographic-data-grant", b"us-references-cited", b"us-citation", b"patcit", b"document-id", name] => {
+                document_id_field(self.us_references_cited.last_mut()?.document_id.as_mut()?, name)
+            },
+            [.., b"us-bibliographic-data-grant", b"us-references-cited", b"us-citation", b"nplcit", b"othercit"] => {
+                Some(self.us_references_cited.last_mut()?.othercit.get_or_insert(Cow::Borrowed("")))
+            },
+            [.., b"us-bibliographic-data-grant", b"us-references-cited", b"us-citation", b"category"] => {
+                Some(self.us_references_cited.last_mut()?.category.get_or_insert(Cow::Borrowed("")))
+            },
+            [.., b"us-bibliographic-data-grant", b"us-parties", list, _, b"addressbook", name] |
+            [.., b"us-bibliographic-data-grant", b"us-parties", list, _, b"addressbook", b"address", name] |
+            [.., b"us-bibliographic-data-grant", list @ b"assignees", b"assignee", b"addressbook", name] |
+            [.., b"us-bibliographic-data-grant", list @ b"assignees", b"assignee", b"addressbook", b"address", name] |
+            [.., b"us-bibliographic-data-grant", list @ b"assignees", b"assignee", name @ b"orgname"] => {
+                party_field(self.parties(list)?.last_mut()?, name)
+            },
+            _ => None,
+        }
+    }
+
+    fn parties(&mut self, list: &[u8]) -> Option<&mut Vec<PartyRef<'a>>> {
+        match list {
+            b"us-applicants" => Some(&mut self.us_applicants),
+            b"inventors" => Some(&mut self.inventors),
+            b"agents" => Some(&mut self.agents),
+            b"assignees" => Some(&mut self.assignees),
+            _ => None,
+        }
+    }
+}
+
+/// as in NAMES, or renamed; empty if it isn't read
+fn element_name(name: &[u8]) -> &'static [u8] {
+    NAMES.iter()
+        .find(|known| **known == name)
+        .copied()
+        .or_else(|| RENAMED.iter().find(|(old, _)| *old == name).map(|(_, new)| *new))
+        .unwrap_or(b"")
+}
+
+/// the field for an element of a document-id; kind and name are set to Some
+fn document_id_field<'a, 'b>(doc_id: &'b mut DocumentIdRef<'a>, name: &[u8]) -> Option<&'b mut Cow<'a, str>> {
+    match name {
+        b"country" => Some(&mut doc_id.country),
+        b"doc-number" => Some(&mut doc_id.doc_number),
+        b"kind" => Some(doc_id.kind.get_or_insert(Cow::Borrowed(""))),
+        b"name" => Some(doc_id.name.get_or_insert(Cow::Borrowed(""))),
+        b"date" => Some(&mut doc_id.date),
+        _ => None,
+    }
+}
+
+fn classification_field<'a, 'b>(classification: &'b mut ClassificationRef<'a>, name: &[u8]) -> Option<&'b mut Cow<'a, str>> {
+    match name {
+        b"section" => Some(&mut classification.section),
+        b"class" => Some(&mut classification.class),
+        b"subclass" => Some(&mut classification.subclass),
+        b"main-group" => Some(&mut classification.main_group),
+        b"subgroup" => Some(&mut classification.subgroup),
+        _ => None,
+    }
+}
+
+/// the field for an element of an addressbook (or its address); set to Some
+fn party_field<'a, 'b>(party: &'b mut PartyRef<'a>, name: &[u8]) -> Option<&'b mut Cow<'a, str>> {
+    let field = match name {
+        b"orgname" => &mut party.orgname,
+        b"first-name" => &mut party.first_name,
+        b"last-name" => &mut party.last_name,
+        b"city" => &mut party.city,
+        b"state" => &mut party.state,
+        b"country" => &mut party.country,
+        _ => return None,
+    };
+
+    Some(field.get_or_insert(Cow::Borrowed("")))
+}
+
+/// an element's content as text, trimmed; borrowed unless it has entities or
+/// tags, or isn't UTF-8
+fn content_text<'a>(content: &'a [u8], rdr: &Reader<&'a [u8]>, opts: &ParserOptions) -> Result<Cow<'a, str>, Error> {
+    let content = trim(content);
+
+    if content.contains(&b'<') {
+        let text = strip_tags(content);
+        return decode_text(&BytesText::from_escaped(trim(&text)), rdr, opts).map(Cow::Owned);
+    }
+    if content.contains(&b'&') {
+        return decode_text(&BytesText::from_escaped(content), rdr, opts).map(Cow::Owned);
+    }
+
+    decode_bytes(content, rdr, opts)
+}
+
+fn trim(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(bytes.len());
+    let end = bytes.iter().rposition(|b| !b.is_ascii_whitespace()).map_or(start, |i| i + 1);
+
+    &bytes[start..end]
+}
+
+/// the text between tags, e.g. of a title with `<i>` in it
+fn strip_tags(content: &[u8]) -> Vec<u8> {
+    let mut text = Vec::with_capacity(content.len());
+    let mut in_tag = false;
+
+    for &b in content {
+        match b {
+            b'<' => in_tag = true,
+            b'>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(b),
+            _ => (),
+        }
+    }
+
+    text
+}
+
+/// Lending iterator over the PatentGrantRef of each grant in a bulk file;
+/// each borrows the document read into the iterator.
+pub struct PatentGrantRefs<B: BufRead> {
+    documents: Documents<B>,
+    document: Vec<u8>,
+    options: ParserOptions,
+}
+
+impl<B: BufRead> PatentGrantRefs<B> {
+    pub fn from_reader(b: B) -> Self {
+        Self::with_options(b, ParserOptions::default())
+    }
+
+    /// Of the options, only the entities and decoding apply
+    pub fn with_options(b: B, options: ParserOptions) -> Self {
+        PatentGrantRefs {
+            documents: Documents::from_reader(b),
+            document: Vec::new(),
+            options,
+        }
+    }
+}
+
+impl<B: BufRead> LendingIterator for PatentGrantRefs<B> {
+    type Item<'a> = Result<PatentGrantRef<'a>, Error> where Self: 'a;
+
+    fn next(&mut self) -> Option<Self::Item<'_>> {
+        // skips documents without elements (e.g. only a declaration), so the
+        // parse below always returns one
+        loop {
+            self.document = match self.documents.next()? {
+                Ok(document) => document,
+                Err(err) => return Some(Err(err)),
+            };
+            if self.document.windows(2).any(|w| w[0] == b'<' && w[1].is_ascii_alphabetic()) {
+                break;
+            }
+        }
+
+        PatentGrantRef::parse(&self.document, &self.options).transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PatentGrants;
+    use crate::data::{date_text, KindCode};
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/grants.xml");
+
+    #[test]
+    fn test_patent_grant_refs() {
+        let grants = PatentGrants::from_reader(FIXTURE.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let mut refs = PatentGrantRefs::from_reader(FIXTURE.as_bytes());
+        let mut count = 0;
+        while let Some(grant_ref) = refs.next() {
+            let grant_ref = grant_ref.unwrap();
+            let biblio = &grants[count].us_bibliographic_data_grant;
+
+            let publication_reference = &grant_ref.publication_reference;
+            assert_eq!(publication_reference.doc_number, biblio.publication_reference.doc_number);
+            assert_eq!(publication_reference.kind.as_deref().map(KindCode::from), biblio.publication_reference.kind);
+            assert_eq!(publication_reference.date, date_text(&biblio.publication_reference.date));
+            assert_eq!(grant_ref.application_reference.doc_number, biblio.application_reference.doc_number);
+            assert_eq!(grant_ref.invention_title, biblio.invention_title.title);
+            assert!(matches!(publication_reference.doc_number, Cow::Borrowed(_)));
+
+            let cpc = &biblio.classifications_cpc;
+            let symbols: Vec<String> = grant_ref.classifications_cpc.iter()
+                .map(|c| format!("{}{}{}{}{}", c.section, c.class, c.subclass, c.main_group, c.subgroup))
+                .collect();
+            let expected: Vec<String> = std::iter::once(&cpc.main_cpc).chain(&cpc.further_cpc)
+                .filter(|c| !c.section.is_empty())
+                .map(|c| format!("{}{}{}{}{}", c.section, c.class, c.subclass, c.main_group, c.subgroup))
+                .collect();
+            assert_eq!(symbols, expected);
+            assert_eq!(grant_ref.classifications_ipcr.len(), biblio.classifications_ipcr.len());
+            assert_eq!(grant_ref.us_references_cited.len(), biblio.us_references_cited.len());
+            assert_eq!(grant_ref.us_applicants.len(), biblio.us_applicants.len());
+            assert_eq!(grant_ref.agents.len(), biblio.agents.len());
+            assert_eq!(grant_ref.assignees.len(), biblio.assignees.len());
+            let inventors: Vec<_> = grant_ref.inventors.iter().map(|inventor| inventor.last_name.as_deref()).collect();
+            let expected: Vec<_> = biblio.inventors.iter().map(|inventor| inventor.addressbook.last_name.as_deref()).collect();
+            assert_eq!(inventors, expected);
+
+            count += 1;
+        }
+        assert_eq!(count, grants.len());
+
+        let document = Documents::from_reader(FIXTURE.as_bytes()).next().unwrap().unwrap();
+        let grant_ref = PatentGrantRef::parse(&document, &ParserOptions::default()).unwrap().unwrap();
+        let citations = &grant_ref.us_references_cited;
+        assert_eq!(citations[0].document_id.as_ref().unwrap().name.as_deref(), Some("Muckle"));
+        assert_eq!(citations[0].category.as_deref(), Some("cited by examiner"));
+        assert!(citations[2].document_id.is_none());
+        assert!(citations[2].othercit.as_deref().unwrap().starts_with("Office Action issued"));
+        assert_eq!(grant_ref.inventors[1].state.as_deref(), Some("OR"));
+        assert_eq!(grant_ref.us_applicants[0].orgname.as_deref(), Some("ACME TOOL CO., LTD."));
+        assert_eq!(grant_ref.agents[0].orgname, Some(Cow::Owned("Example & Partners LLP".to_string())));
+        assert_eq!(grant_ref.assignees[0].city.as_deref(), Some("Osaka"));
+    }
+
+    #[test]
+    fn test_content_text() {
+        let document = b"<a> Hand &amp; <i>foot</i> tool </a>";
+        let rdr = Reader::from_reader(&document[..]);
+        let opts = ParserOptions::default();
+
+        assert_eq!(content_text(&document[3..34], &rdr, &opts).unwrap(), "Hand & foot tool");
+        assert!(matches!(content_text(b" plain ", &rdr, &opts).unwrap(), Cow::Borrowed("plain")));
+    }
+}
@@ -0,0 +1,567 @@
+//! Downloads from the USPTO Bulk Data Storage System (bulkdata.uspto.gov).
+//!
+//! Each product is published as one zip per weekly issue, listed in a
+//! directory per year. Files are resolved by issue date range, and downloads
+//! resume from a partial file left by an earlier attempt.
+//!
+//! A file can also be parsed straight from its url, without saving it, with
+//! an HttpReader: it's decompressed as it streams in, and a connection
+//! dropped mid-file is resumed with a range request from where it stopped.
+
+use chrono::{Datelike, NaiveDate};
+use lazy_static::lazy_static;
+use regex::Regex;
+use reqwest::header::{LOCATION, RANGE};
+use reqwest::StatusCode;
+use snafu::ResultExt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use crate::deserialize::PatentGrants;
+use crate::error::{Error, Fetch, FetchRedirect, CreateFile, DownloadSize};
+use crate::input::InputReader;
+
+const BULKDATA_URL: &str = "https://bulkdata.uspto.gov/data/patent/grant/redbook";
+
+/// times an HttpReader retries a dropped connection before failing, if no
+/// bytes came in between
+pub const DEFAULT_RETRIES: usize = 3;
+
+/// wait before a retry, times the retries already made
+const RETRY_WAIT: Duration = Duration::from_secs(1);
+
+lazy_static!{
+    // e.g. <a href="ipg230103.zip">ipg230103.zip</a></td><td align="right">123456789</td>
+    // the size column isn't always there
+    static ref FULLTEXT_RE: Regex = Regex::new(r#"href="(ipg(\d{6})\.zip)"[^>]*>[^<]*</a>(?:\s*</td>\s*<td[^>]*>\s*(\d+)\s*<)?"#).unwrap();
+    static ref BIBLIOGRAPHIC_RE: Regex = Regex::new(r#"href="(ipgb(\d{8})_wk\d{2}\.zip)"[^>]*>[^<]*</a>(?:\s*</td>\s*<td[^>]*>\s*(\d+)\s*<)?"#).unwrap();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Product {
+    /// full text grants, `ipgYYMMDD.zip`
+    GrantFullText,
+    /// bibliographic (front page) grants, `ipgbYYYYMMDD_wkNN.zip`
+    GrantBibliographic,
+}
+
+impl Product {
+    fn dir_url(self, base_url: &str, year: i32) -> String {
+        match self {
+            Product::GrantFullText => format!("{}/fulltext/{}", base_url, year),
+            Product::GrantBibliographic => format!("{}/bibliographic/{}", base_url, year),
+        }
+    }
+}
+
+/// One weekly file of a product
+#[derive(Debug, Clone, PartialEq)]
+pub struct BulkFile {
+    pub product: Product,
+    pub name: String,
+    /// issue date
+    pub date: NaiveDate,
+    /// bytes, if the listing gives it
+    pub size: Option<u64>,
+}
+
+impl BulkFile {
+    /// On bulkdata.uspto.gov
+    pub fn url(&self) -> String {
+        self.url_at(BULKDATA_URL)
+    }
+
+    fn url_at(&self, base_url: &str) -> String {
+        format!("{}/{}", self.product.dir_url(base_url, self.date.year()), self.name)
+    }
+}
+
+/// Blocking client for listing and downloading bulk files
+pub struct BulkData {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl BulkData {
+    pub fn new() -> Self {
+        BulkData {
+            client: reqwest::Client::new(),
+            base_url: BULKDATA_URL.to_string(),
+        }
+    }
+
+    /// Lists and downloads from a mirror of the products' directories,
+    /// instead of bulkdata.uspto.gov
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// All the files of a product listed for a year
+    pub fn list_year(&self, product: Product, year: i32) -> Result<Vec<BulkFile>, Error> {
+        let listing = self.client.get(&product.dir_url(&self.base_url, year))
+            .send()
+            .and_then(|resp| resp.error_for_status())
+            .and_then(|mut resp| resp.text())
+            .context(Fetch)?;
+
+        Ok(parse_listing(product, &listing))
+    }
+
+    /// Files of a product issued from `from` to `to` (inclusive), by date
+    pub fn resolve(&self, product: Product, from: NaiveDate, to: NaiveDate) -> Result<Vec<BulkFile>, Error> {
+        let mut files = Vec::new();
+
+        for year in from.year()..=to.year() {
+            files.extend(
+                self.list_year(product, year)?
+                    .into_iter()
+                    .filter(|file| file.date >= from && file.date <= to)
+            );
+        }
+
+        files.sort_by_key(|file| file.date);
+
+        Ok(files)
+    }
+
+    /// Downloads into target_dir, returning the file's path.
+    ///
+    /// A partial file already in target_dir is resumed with a range request
+    /// (or restarted, if the server doesn't support it), and a complete one is
+    /// left as is. The final size is checked against the listing; a file
+    /// longer than listed is downloaded again.
+    pub fn download(&self, file: &BulkFile, target_dir: &Path) -> Result<PathBuf, Error> {
+        let path = target_dir.join(&file.name);
+        let existing = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+
+        if file.size.is_some_and(|size| existing == size) {
+            return Ok(path);
+        }
+
+        let url = file.url_at(&self.base_url);
+        let mut req = self.client.get(&url);
+        if existing > 0 {
+            req = req.header(RANGE, format!("bytes={}-", existing));
+        }
+
+        let mut resp = req.send()
+            .context(Fetch)?;
+
+        if existing > 0 && resp.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+            // file was already complete, but its size wasn't listed
+            if file.size.is_none() {
+                return Ok(path);
+            }
+
+            // longer than listed, so not the file listed
+            resp = self.client.get(&url)
+                .send()
+                .context(Fetch)?;
+        }
+
+        let mut resp = resp.error_for_status()
+            .context(Fetch)?;
+
+        // a server ignoring the range sends the whole file
+        let resumed = resp.status() == StatusCode::PARTIAL_CONTENT;
+        let mut target = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resumed)
+            .truncate(!resumed)
+            .open(&path)
+            .context(CreateFile)?;
+
+        std::io::copy(&mut resp, &mut target)
+            .context(CreateFile)?;
+
+        let actual = fs::metadata(&path).context(CreateFile)?.len();
+        if let Some(expected) = file.size {
+            if actual != expected {
+                return DownloadSize { name: file.name.clone(), expected, actual }.fail();
+            }
+        }
+
+        Ok(path)
+    }
+
+    /// Streams the grants in a file, without saving it; see HttpReader
+    pub fn open(&self, file: &BulkFile) -> Result<PatentGrants<InputReader>, Error> {
+        PatentGrants::from_seekable(HttpReader::with_client(self.client.clone(), &file.url_at(&self.base_url))?)
+    }
+}
+
+impl Default for BulkData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A file over http(s), read as it streams in.
+///
+/// When the connection drops (or stalls past the client's timeout) before
+/// the end, reading resumes from the same position with a range request.
+/// Seeking, e.g. to a zip archive's central directory, is a range request
+/// too, sent on the next read.
+pub struct HttpReader {
+    client: reqwest::Client,
+    url: String,
+    resp: Option<reqwest::Response>,
+    pos: u64,
+    len: Option<u64>,
+    retries: usize,
+}
+
+impl HttpReader {
+    pub fn open(url: &str) -> Result<Self, Error> {
+        Self::with_client(reqwest::Client::new(), url)
+    }
+
+    /// With a client configured otherwise (e.g. a proxy, or a timeout)
+    pub fn with_client(client: reqwest::Client, url: &str) -> Result<Self, Error> {
+        let resp = client.get(url)
+            .send()
+            .and_then(|resp| resp.error_for_status())
+            .context(Fetch)?;
+        // one the client's redirect policy didn't follow
+        if resp.status().is_redirection() {
+            let location = resp.headers().get(LOCATION)
+                .map(|location| String::from_utf8_lossy(location.as_bytes()).into_owned())
+                .unwrap_or_default();
+            return FetchRedirect { url, location }.fail();
+        }
+
+        Ok(HttpReader {
+            len: resp.content_length(),
+            resp: Some(resp),
+            client,
+            url: url.to_string(),
+            pos: 0,
+            retries: DEFAULT_RETRIES,
+        })
+    }
+
+    /// Times a dropped connection is retried before the read fails, if no
+    /// bytes come in between
+    pub fn retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Bytes in the file, if the server said
+    pub fn content_length(&self) -> Option<u64> {
+        self.len
+    }
+
+    /// The rest of the file, from pos
+    fn request(&self) -> io::Result<reqwest::Response> {
+        let resp = self.client.get(&self.url)
+            .header(RANGE, format!("bytes={}-", self.pos))
+            .send()
+            .map_err(|err| io::Error::new(io::ErrorKind::ConnectionAborted, err))?;
+
+        match resp.status() {
+            StatusCode::PARTIAL_CONTENT => Ok(resp),
+            // a server ignoring the range sends the whole file
+            StatusCode::OK if self.pos == 0 => Ok(resp),
+            StatusCode::OK => Err(io::Error::new(io::ErrorKind::Unsupported, format!("{} can't be resumed, the server doesn't take range requests", self.url))),
+            status => Err(io::Error::other(format!("{} at byte {}: {}", self.url, self.pos, status))),
+        }
+    }
+}
+
+impl Read for HttpReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.len.is_some_and(|len| self.pos >= len) {
+            return Ok(0);
+        }
+
+        let mut failures = 0;
+        loop {
+            let result = match self.resp {
+                Some(ref mut resp) => resp.read(buf),
+                None => match self.request() {
+                    Ok(resp) => {
+                        self.resp = Some(resp);
+                        continue;
+                    },
+                    Err(err) if err.kind() == io::ErrorKind::ConnectionAborted => Err(err),
+                    Err(err) => return Err(err),
+                },
+            };
+
+            let err = match result {
+                Ok(0) if self.len.is_some_and(|len| self.pos < len) => {
+                    io::Error::new(io::ErrorKind::UnexpectedEof, format!("{} ended at byte {}", self.url, self.pos))
+                },
+                Ok(n) => {
+                    self.pos += n as u64;
+                    return Ok(n);
+                },
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => err,
+            };
+
+            // dropped, resumed by the next request
+            self.resp = None;
+            if failures >= self.retries {
+                return Err(err);
+            }
+            thread::sleep(RETRY_WAIT * failures as u32);
+            failures += 1;
+        }
+    }
+}
+
+impl Seek for HttpReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+            SeekFrom::End(offset) => {
+                let len = self.len
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, format!("{} has no content-length to seek from", self.url)))?;
+                len.checked_add_signed(offset)
+            },
+        };
+        let target = target
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "seek before the start"))?;
+
+        if target != self.pos {
+            self.resp = None;
+            self.pos = target;
+        }
+
+        Ok(target)
+    }
+}
+
+impl PatentGrants<InputReader> {
+    /// Streams plain xml, gzipped xml, or a zip archive from an http(s) url,
+    /// as `from_path` opens a file; see HttpReader
+    pub fn from_http_url(url: &str) -> Result<Self, Error> {
+        Self::from_seekable(HttpReader::open(url)?)
+    }
+}
+
+/// Finds the product's files in a year's directory listing (html)
+fn parse_listing(product: Product, listing: &str) -> Vec<BulkFile> {
+    let (re, date_fmt) = match product {
+        Product::GrantFullText => (&*FULLTEXT_RE, "%y%m%d"),
+        Product::GrantBibliographic => (&*BIBLIOGRAPHIC_RE, "%Y%m%d"),
+    };
+
+    let mut files: Vec<BulkFile> = re.captures_iter(listing)
+        .filter_map(|caps| {
+            let date = NaiveDate::parse_from_str(&caps[2], date_fmt).ok()?;
+
+            Some(BulkFile {
+                product,
+                name: caps[1].to_owned(),
+                date,
+                size: caps.get(3).and_then(|size| size.as_str().parse().ok()),
+            })
+        })
+        .collect();
+
+    // listings may link a file more than once
+    files.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.name.cmp(&b.name)));
+    files.dedup_by(|a, b| a.name == b.name);
+
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/grants.xml");
+
+    const LISTING: &str = r#"<html><body><table>
+<tr><td><a href="ipg230103.zip">ipg230103.zip</a></td><td align="right">123456789</td><td>2023-01-03 00:12</td></tr>
+<tr><td><a href="ipg230110.zip">ipg230110.zip</a></td><td align="right">98765</td><td>2023-01-10 00:12</td></tr>
+<tr><td><a href="ipg230117.zip">ipg230117.zip</a></td></tr>
+<tr><td><a href="ipgb20230103_wk01.zip">ipgb20230103_wk01.zip</a></td><td align="right">5555</td></tr>
+<tr><td><a href="README.txt">README.txt</a></td></tr>
+</table></body></html>"#;
+
+    #[test]
+    fn test_parse_listing() {
+        let files = parse_listing(Product::GrantFullText, LISTING);
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[0].name, "ipg230103.zip");
+        assert_eq!(files[0].date, NaiveDate::from_ymd_opt(2023, 1, 3).unwrap());
+        assert_eq!(files[0].size, Some(123456789));
+        assert_eq!(files[2].size, None);
+        assert_eq!(files[0].url(), "https://bulkdata.uspto.gov/data/patent/grant/redbook/fulltext/2023/ipg230103.zip");
+
+        let files = parse_listing(Product::GrantBibliographic, LISTING);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].name, "ipgb20230103_wk01.zip");
+        assert_eq!(files[0].size, Some(5555));
+    }
+
+    /// Serves body at the returned url, taking range requests. The first
+    /// response is cut off part way, as a dropped connection.
+    fn serve(body: Vec<u8>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/ipg190101", listener.local_addr().unwrap());
+
+        thread::spawn(move || {
+            for (i, stream) in listener.incoming().enumerate() {
+                let mut rdr = BufReader::new(stream.unwrap());
+                let mut start = 0;
+                loop {
+                    let mut line = String::new();
+                    if rdr.read_line(&mut line).unwrap_or(0) == 0 || line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(range) = line.to_lowercase().strip_prefix("range: bytes=") {
+                        start = range.trim().trim_end_matches('-').parse().unwrap();
+                    }
+                }
+
+                let mut header = if start == 0 {
+                    "HTTP/1.1 200 OK\r\n".to_string()
+                } else {
+                    format!("HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {}-{}/{}\r\n", start, body.len() - 1, body.len())
+                };
+                header += &format!("Content-Length: {}\r\nConnection: close\r\n\r\n", body.len() - start);
+                let end = if i == 0 { body.len() / 2 } else { body.len() };
+
+                // the reader hangs up on a seek
+                let stream = rdr.get_mut();
+                let _ = stream.write_all(header.as_bytes())
+                    .and_then(|_| stream.write_all(&body[start..end]));
+            }
+        });
+
+        url
+    }
+
+    #[test]
+    fn test_http_reader_resume() {
+        let url = serve(FIXTURE.as_bytes().to_vec());
+
+        let mut reader = HttpReader::open(&url).unwrap();
+        assert_eq!(reader.content_length(), Some(FIXTURE.len() as u64));
+        let mut xml = String::new();
+        reader.read_to_string(&mut xml).unwrap();
+        assert_eq!(xml, FIXTURE);
+
+        reader.seek(SeekFrom::End(-10)).unwrap();
+        let mut tail = String::new();
+        reader.read_to_string(&mut tail).unwrap();
+        assert_eq!(tail, &FIXTURE[FIXTURE.len() - 10..]);
+
+        let grants = PatentGrants::from_http_url(&serve(FIXTURE.as_bytes().to_vec())).unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(grants.len(), 3);
+    }
+
+    /// Serves body at every path of the returned base url, taking range
+    /// requests unless told not to; a range past the end is a 416
+    fn serve_file(body: Vec<u8>, ranges: bool) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut rdr = BufReader::new(stream.unwrap());
+                let mut start = 0;
+                loop {
+                    let mut line = String::new();
+                    if rdr.read_line(&mut line).unwrap_or(0) == 0 || line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(range) = line.to_lowercase().strip_prefix("range: bytes=") {
+                        start = if ranges { range.trim().trim_end_matches('-').parse().unwrap() } else { 0 };
+                    }
+                }
+
+                let response = if start >= body.len() && start > 0 {
+                    format!("HTTP/1.1 416 Range Not Satisfiable\r\nContent-Range: bytes */{}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", body.len()).into_bytes()
+                } else {
+                    let status = if start == 0 { "200 OK" } else { "206 Partial Content" };
+                    let mut response = format!("HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", status, body.len() - start).into_bytes();
+                    response.extend_from_slice(&body[start..]);
+                    response
+                };
+                let _ = rdr.get_mut().write_all(&response);
+            }
+        });
+
+        base_url
+    }
+
+    #[test]
+    fn test_download() {
+        let body = FIXTURE.as_bytes().to_vec();
+        let target_dir = std::env::temp_dir().join(format!("uspto-download-{}", std::process::id()));
+        fs::create_dir_all(&target_dir).unwrap();
+        let path = target_dir.join("ipg190101.zip");
+
+        let listed = BulkFile {
+            product: Product::GrantFullText,
+            name: "ipg190101.zip".to_string(),
+            date: NaiveDate::from_ymd_opt(2019, 1, 1).unwrap(),
+            size: Some(body.len() as u64),
+        };
+        let unlisted = BulkFile { size: None, ..listed.clone() };
+        let bulkdata = BulkData::new().base_url(&serve_file(body.clone(), true));
+
+        // from nothing, then resumed from half
+        assert_eq!(bulkdata.download(&listed, &target_dir).unwrap(), path);
+        assert_eq!(fs::read(&path).unwrap(), body);
+        fs::write(&path, &body[..body.len() / 2]).unwrap();
+        bulkdata.download(&unlisted, &target_dir).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), body);
+
+        // complete, by the 416, when the size isn't listed
+        bulkdata.download(&unlisted, &target_dir).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), body);
+
+        // longer than listed: downloaded again, not taken as complete
+        fs::write(&path, [&body[..], b"trailing"].concat()).unwrap();
+        bulkdata.download(&listed, &target_dir).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), body);
+
+        // then not the size listed, either way
+        let wrong_size = BulkFile { size: Some(body.len() as u64 + 1), ..listed.clone() };
+        fs::write(&path, [&body[..], b"trailing"].concat()).unwrap();
+        match bulkdata.download(&wrong_size, &target_dir) {
+            Err(Error::DownloadSize { expected, actual, .. }) => assert_eq!((expected, actual), (body.len() as u64 + 1, body.len() as u64)),
+            other => panic!("expected a size error, got {:?}", other),
+        }
+
+        // restarted, by a server ignoring the range
+        let bulkdata = BulkData::new().base_url(&serve_file(body.clone(), false));
+        fs::write(&path, b"not the start").unwrap();
+        bulkdata.download(&listed, &target_dir).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), body);
+
+        fs::remove_dir_all(&target_dir).unwrap();
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn test_from_http_url_zip() {
+        let mut buf = io::Cursor::new(Vec::new());
+        let mut zip = zip::ZipWriter::new(&mut buf);
+        zip.start_file("ipg190101.xml", zip::write::SimpleFileOptions::default()).unwrap();
+        zip.write_all(FIXTURE.as_bytes()).unwrap();
+        zip.finish().unwrap();
+
+        let grants = PatentGrants::from_http_url(&serve(buf.into_inner())).unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(grants.len(), 3);
+    }
+}
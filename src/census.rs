@@ -0,0 +1,142 @@
+//! A census of the markup in documents, read as raw xml events with no
+//! schema: how often each element path (e.g.
+//! `us-patent-grant/us-bibliographic-data-grant/invention-title`) and each
+//! attribute on it occur. For seeing what's in bulk files, all of it,
+//! including what the typed parser doesn't read (see also
+//! `ParserOptions::collect_skipped`).
+
+use quick_xml::events::{BytesStart, Event};
+use std::collections::BTreeMap;
+
+use crate::error::Error;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagCensus {
+    pub documents: usize,
+    /// by element path, the element names from the root down, `/`-separated
+    pub paths: BTreeMap<String, usize>,
+    /// by element path and attribute name, e.g.
+    /// `us-patent-grant/us-bibliographic-data-grant/invention-title@id`
+    pub attributes: BTreeMap<String, usize>,
+}
+
+impl TagCensus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts a document's elements and attributes, e.g. one of
+    /// `split::Documents`. On an xml error, what was read before it is
+    /// counted.
+    pub fn add_document(&mut self, document: &[u8]) -> Result<(), Error> {
+        let mut rdr = quick_xml::Reader::from_reader(document);
+        rdr.trim_text(true);
+        let mut buf = Vec::new();
+        // the path of the element being read, and where its parent's ends
+        let mut path = String::new();
+        let mut parent_ends: Vec<usize> = Vec::new();
+
+        self.documents += 1;
+        loop {
+            match rdr.read_event(&mut buf) {
+                Ok(Event::Start(ref e)) => {
+                    parent_ends.push(path.len());
+                    self.element(&mut path, e)?;
+                },
+                Ok(Event::Empty(ref e)) => {
+                    let parent_end = path.len();
+                    self.element(&mut path, e)?;
+                    path.truncate(parent_end);
+                },
+                Ok(Event::End(_)) => {
+                    path.truncate(parent_ends.pop().unwrap_or(0));
+                },
+                Ok(Event::Eof) => break,
+                Ok(_) => {},
+                Err(err) => return Err(Error::deser(format!("{}; at {}", err, rdr.buffer_position()))),
+            }
+            buf.clear();
+        }
+
+        Ok(())
+    }
+
+    /// Adds other's counts, as if its documents had been added to this
+    pub fn merge(&mut self, other: &TagCensus) {
+        self.documents += other.documents;
+        for (path, count) in &other.paths {
+            *self.paths.entry(path.clone()).or_insert(0) += count;
+        }
+        for (attribute, count) in &other.attributes {
+            *self.attributes.entry(attribute.clone()).or_insert(0) += count;
+        }
+    }
+
+    /// Counts by element name alone, wherever the element is
+    pub fn element_names(&self) -> BTreeMap<&str, usize> {
+        let mut names = BTreeMap::new();
+        for (path, count) in &self.paths {
+            let name = path.rsplit('/').next().unwrap_or(path);
+            *names.entry(name).or_insert(0) += count;
+        }
+
+        names
+    }
+
+    /// Counts by attribute name alone, on whatever element
+    pub fn attribute_names(&self) -> BTreeMap<&str, usize> {
+        let mut names = BTreeMap::new();
+        for (attribute, count) in &self.attributes {
+            let name = attribute.rsplit('@').next().unwrap_or(attribute);
+            *names.entry(name).or_insert(0) += count;
+        }
+
+        names
+    }
+
+    /// pushes the element onto path, counting it and its attributes
+    fn element(&mut self, path: &mut String, e: &BytesStart) -> Result<(), Error> {
+        if !path.is_empty() {
+            path.push('/');
+        }
+        path.push_str(&String::from_utf8_lossy(e.name()));
+        *self.paths.entry(path.clone()).or_insert(0) += 1;
+
+        for attr in e.attributes() {
+            let attr = attr.map_err(|err| Error::deser(err.to_string()))?;
+            let attribute = format!("{}@{}", path, String::from_utf8_lossy(attr.key));
+            *self.attributes.entry(attribute).or_insert(0) += 1;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::split::Documents;
+
+    #[test]
+    fn test_census() {
+        let mut census = TagCensus::new();
+        for document in Documents::from_reader(include_str!("../tests/fixtures/grants.xml").as_bytes()) {
+            census.add_document(&document.unwrap()).unwrap();
+        }
+
+        assert_eq!(census.documents, 3);
+        assert_eq!(census.paths.get("us-patent-grant"), Some(&3));
+        assert_eq!(census.paths.get("us-patent-grant/us-bibliographic-data-grant/invention-title"), Some(&3));
+        assert_eq!(census.attributes.get("us-patent-grant@dtd-version"), Some(&3));
+        assert!(census.element_names()["doc-number"] > 3);
+        assert_eq!(census.attribute_names()["dtd-version"], 3);
+
+        // paths of empty elements are counted too, and don't nest
+        let mut census = TagCensus::new();
+        census.add_document(b"<a x=\"1\"><b/><c><b/></c></a>").unwrap();
+        let paths: Vec<_> = census.paths.iter().map(|(path, count)| (path.as_str(), *count)).collect();
+        assert_eq!(paths, vec![("a", 1), ("a/b", 1), ("a/c", 1), ("a/c/b", 1)]);
+        assert_eq!(census.attributes.get("a@x"), Some(&1));
+        assert_eq!(census.element_names()["b"], 2);
+    }
+}
@@ -0,0 +1,293 @@
+//! Checkpoints of a long parse, so a job that stops partway through a bulk
+//! file can resume where it was instead of from the start.
+//!
+//! A checkpoint is the number of bytes of input before the next document,
+//! and the number of documents read before it. It's kept in a sidecar
+//! file, text: a header line, then the offset and count tab-separated.
+//! `CheckpointedGrants` writes one every so many documents, each time the
+//! next grant is asked for (so the grants before it are done with); a job
+//! that stops resumes at the last one, reading again the grants yielded
+//! since. Resuming skips the bytes before the offset without parsing them;
+//! they're still read (not seeked over), so the offset is of the
+//! decompressed input, the same for gzipped and zipped files as plain.
+
+use snafu::{IntoError, OptionExt, ResultExt};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read as IoRead, Write};
+use std::path::{Path, PathBuf};
+
+use crate::data::PatentGrant;
+use crate::deserialize::PatentGrants;
+use crate::error::{CheckpointFormat, CheckpointWrite, Document, Error, Read};
+use crate::input::InputReader;
+use crate::options::ParserOptions;
+use crate::split::Documents;
+
+const HEADER: &str = "uspto-checkpoint";
+
+/// documents read between checkpoints
+pub const DEFAULT_EVERY: u64 = 1_000;
+
+/// Where a parse is in its input
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// bytes before the next document
+    pub offset: u64,
+    /// documents before the next, including any that failed to parse
+    pub documents: u64,
+}
+
+impl Checkpoint {
+    /// Reads a checkpoint written by `write`
+    pub fn read<R: BufRead>(rdr: R) -> Result<Self, Error> {
+        let mut lines = rdr.lines();
+
+        let header = lines.next()
+            .unwrap_or_else(|| Ok(String::new()))
+            .context(Read)?;
+        if header != HEADER {
+            return CheckpointFormat { line: 1usize, src: format!("expected {:?} header", HEADER) }.fail();
+        }
+
+        let line = lines.next()
+            .unwrap_or_else(|| Ok(String::new()))
+            .context(Read)?;
+        match line.split('\t').collect::<Vec<_>>()[..] {
+            [offset, documents] => Ok(Checkpoint {
+                offset: parse_number(offset)?,
+                documents: parse_number(documents)?,
+            }),
+            _ => CheckpointFormat { line: 2usize, src: "expected offset and documents".to_string() }.fail(),
+        }
+    }
+
+    /// Writes the checkpoint, see the module docs for its format
+    pub fn write<W: Write>(&self, wtr: W) -> Result<(), Error> {
+        let mut wtr = BufWriter::new(wtr);
+
+        writeln!(wtr, "{}", HEADER)
+            .context(CheckpointWrite)?;
+        writeln!(wtr, "{}\t{}", self.offset, self.documents)
+            .context(CheckpointWrite)?;
+
+        wtr.flush()
+            .context(CheckpointWrite)
+    }
+
+    /// The checkpoint in the file at path, or None if there isn't one
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Option<Self>, Error> {
+        match File::open(path) {
+            Ok(file) => Self::read(BufReader::new(file)).map(Some),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err).context(Read),
+        }
+    }
+
+    /// Writes the checkpoint to a temporary file, then renames it over path,
+    /// so a job stopped while saving leaves the checkpoint before
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+
+        let file = File::create(&tmp_path)
+            .context(CheckpointWrite)?;
+        self.write(&file)?;
+        file.sync_all()
+            .context(CheckpointWrite)?;
+
+        fs::rename(&tmp_path, path)
+            .context(CheckpointWrite)
+    }
+
+    /// The sidecar checkpoint's path for a bulk file, e.g.
+    /// `ipg230103.zip.checkpoint`
+    pub fn sidecar_path<P: AsRef<Path>>(path: P) -> PathBuf {
+        let mut sidecar = path.as_ref().as_os_str().to_owned();
+        sidecar.push(".checkpoint");
+        sidecar.into()
+    }
+}
+
+fn parse_number(text: &str) -> Result<u64, Error> {
+    text.parse()
+        .ok()
+        .context(CheckpointFormat { line: 2usize, src: format!("invalid number {:?}", text) })
+}
+
+/// Like LenientPatentGrants, writing checkpoints as it goes. See
+/// `PatentGrants::resume_from_checkpoint`.
+pub struct CheckpointedGrants<B: BufRead> {
+    documents: Documents<B>,
+    options: ParserOptions,
+    checkpoint_path: PathBuf,
+    // where the next document is
+    checkpoint: Checkpoint,
+    every: u64,
+    // documents read since the last checkpoint written
+    unsaved: u64,
+}
+
+impl<B: BufRead> CheckpointedGrants<B> {
+    /// Resumes at the checkpoint in the file at checkpoint_path, skipping
+    /// rdr past the checkpoint's offset; starts at the beginning if there's
+    /// no checkpoint yet. rdr is the input from its start.
+    pub fn from_reader<P: AsRef<Path>>(mut rdr: B, checkpoint_path: P) -> Result<Self, Error> {
+        let checkpoint_path = checkpoint_path.as_ref().to_path_buf();
+        let checkpoint = Checkpoint::load(&checkpoint_path)?
+            .unwrap_or_default();
+
+        let skipped = io::copy(&mut rdr.by_ref().take(checkpoint.offset), &mut io::sink())
+            .context(Read)?;
+        if skipped < checkpoint.offset {
+            return CheckpointFormat { line: 2usize, src: format!("offset {} is past the end of the input", checkpoint.offset) }.fail();
+        }
+
+        Ok(CheckpointedGrants {
+            documents: Documents::from_reader(rdr),
+            options: ParserOptions::default(),
+            checkpoint_path,
+            checkpoint,
+            every: DEFAULT_EVERY,
+            unsaved: 0,
+        })
+    }
+
+    /// See `PatentGrants::with_options`
+    pub fn options(mut self, options: ParserOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Number of documents read between checkpoints
+    pub fn every(mut self, every: u64) -> Self {
+        self.every = every.max(1);
+        self
+    }
+
+    /// Where the next document is
+    pub fn checkpoint(&self) -> Checkpoint {
+        self.checkpoint
+    }
+
+    /// Writes the checkpoint now, e.g. before stopping deliberately
+    pub fn save_checkpoint(&mut self) -> Result<(), Error> {
+        self.checkpoint.save(&self.checkpoint_path)?;
+        self.unsaved = 0;
+
+        Ok(())
+    }
+}
+
+impl<B: BufRead> Iterator for CheckpointedGrants<B> {
+    type Item = Result<PatentGrant, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.unsaved >= self.every {
+            if let Err(err) = self.save_checkpoint() {
+                return Some(Err(err));
+            }
+        }
+
+        loop {
+            // a read error isn't in any document, and ends the input
+            let document = match self.documents.next() {
+                Some(Ok(document)) => document,
+                Some(Err(err)) => return Some(Err(err)),
+                None => {
+                    // so resuming a finished parse reads nothing
+                    if self.unsaved > 0 {
+                        if let Err(err) = self.save_checkpoint() {
+                            return Some(Err(err));
+                        }
+                    }
+                    return None;
+                },
+            };
+
+            let start = self.checkpoint.offset;
+            self.checkpoint.offset += document.len() as u64;
+
+            let res = match PatentGrants::with_options(&document[..], self.options.clone()).next() {
+                Some(Ok(patent_grant)) => Ok(patent_grant),
+                Some(Err(err)) => Err(Document { start, end: self.checkpoint.offset }.into_error(Box::new(err))),
+                // e.g. only a declaration
+                None => continue,
+            };
+            self.checkpoint.documents += 1;
+            self.unsaved += 1;
+
+            return Some(res);
+        }
+    }
+}
+
+impl PatentGrants<InputReader> {
+    /// Parses a bulk file (as `from_path`, leniently), checkpointing to its
+    /// sidecar (see `Checkpoint::sidecar_path`) every `DEFAULT_EVERY`
+    /// documents, and resuming at the checkpoint there if there is one.
+    pub fn resume_from_checkpoint<P: AsRef<Path>>(path: P) -> Result<CheckpointedGrants<InputReader>, Error> {
+        let path = path.as_ref();
+        let grants = PatentGrants::from_path(path)?;
+
+        CheckpointedGrants::from_reader(grants.into_reader(), Checkpoint::sidecar_path(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE_PATH: &str = "tests/fixtures/grants.xml";
+
+    fn doc_number(patent_grant: Result<PatentGrant, Error>) -> String {
+        patent_grant.unwrap().us_bibliographic_data_grant.publication_reference.doc_number
+    }
+
+    #[test]
+    fn test_resume_from_checkpoint() {
+        let path = std::env::temp_dir().join(format!("uspto-checkpoint-{}.xml", std::process::id()));
+        fs::copy(FIXTURE_PATH, &path).unwrap();
+        let sidecar = Checkpoint::sidecar_path(&path);
+
+        let expected: Vec<_> = PatentGrants::from_path(FIXTURE_PATH).unwrap()
+            .map(doc_number)
+            .collect();
+        assert!(expected.len() > 2);
+
+        // stops after two grants; only the first is checkpointed, as the
+        // second might not be done with
+        let mut grants = PatentGrants::resume_from_checkpoint(&path).unwrap().every(1);
+        let mut doc_numbers: Vec<_> = grants.by_ref().take(2).map(doc_number).collect();
+        drop(grants);
+        assert_eq!(Checkpoint::load(&sidecar).unwrap().unwrap().documents, 1);
+
+        let grants = PatentGrants::resume_from_checkpoint(&path).unwrap();
+        assert_eq!(grants.checkpoint().documents, 1);
+        doc_numbers.truncate(1);
+        doc_numbers.extend(grants.map(doc_number));
+        assert_eq!(doc_numbers, expected);
+
+        // finished, so there's nothing left to resume
+        let checkpoint = Checkpoint::load(&sidecar).unwrap().unwrap();
+        assert_eq!(checkpoint.documents, expected.len() as u64);
+        assert_eq!(checkpoint.offset, fs::metadata(&path).unwrap().len());
+        assert_eq!(PatentGrants::resume_from_checkpoint(&path).unwrap().count(), 0);
+
+        fs::remove_file(&sidecar).unwrap();
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_checkpoint_format() {
+        let checkpoint = Checkpoint { offset: 123456, documents: 12 };
+        let mut written = Vec::new();
+        checkpoint.write(&mut written).unwrap();
+        assert_eq!(Checkpoint::read(&written[..]).unwrap(), checkpoint);
+
+        match Checkpoint::read(&b"uspto-checkpoint\n123456\n"[..]) {
+            Err(Error::CheckpointFormat { line, .. }) => assert_eq!(line, 2),
+            other => panic!("expected a format error, got {:?}", other),
+        }
+    }
+}
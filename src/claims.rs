@@ -0,0 +1,178 @@
+//! How a grant's claims depend on each other, from their claim-refs. A
+//! claim that refers to no other claim is independent; the rest depend on
+//! the claims they refer to.
+
+use std::collections::HashMap;
+
+use crate::data::{Claim, ClaimRef, ClaimText};
+
+impl Claim {
+    /// The claim-refs in all of the claim's claim-text, in order
+    pub fn claim_refs(&self) -> Vec<&ClaimRef> {
+        fn collect<'a>(claim_texts: &'a [ClaimText], refs: &mut Vec<&'a ClaimRef>) {
+            for claim_text in claim_texts {
+                refs.extend(&claim_text.claim_refs);
+                collect(&claim_text.claim_texts, refs);
+            }
+        }
+
+        let mut refs = Vec::new();
+        collect(&self.claim_texts, &mut refs);
+        refs
+    }
+
+    /// Ids of the claims this one refers to, in order, without repeats
+    pub fn depends_on(&self) -> Vec<&str> {
+        let mut ids: Vec<&str> = Vec::new();
+        for claim_ref in self.claim_refs() {
+            if !ids.contains(&claim_ref.idref.as_str()) {
+                ids.push(&claim_ref.idref);
+            }
+        }
+
+        ids
+    }
+
+    pub fn is_independent(&self) -> bool {
+        self.claim_refs().is_empty()
+    }
+}
+
+/// Dependencies of a list of claims, e.g. `grant.claims.dependency_graph()`
+pub trait Claims {
+    fn dependency_graph(&self) -> DependencyGraph;
+
+    /// The claims that refer to no other claim, in order
+    fn independent_claims(&self) -> Vec<&Claim>;
+
+    /// The claims that refer to another claim, in order
+    fn dependent_claims(&self) -> Vec<&Claim>;
+}
+
+impl Claims for [Claim] {
+    fn dependency_graph(&self) -> DependencyGraph {
+        DependencyGraph::new(self)
+    }
+
+    fn independent_claims(&self) -> Vec<&Claim> {
+        self.iter().filter(|claim| claim.is_independent()).collect()
+    }
+
+    fn dependent_claims(&self) -> Vec<&Claim> {
+        self.iter().filter(|claim| !claim.is_independent()).collect()
+    }
+}
+
+/// The claims as a directed graph, each claim a node (by its position in the
+/// claims) with an edge to each claim it depends on. A claim-ref to a claim
+/// that isn't in the claims (e.g. a typo in the idref) is left out.
+///
+/// For petgraph, `DiGraphMap::from_edges(graph.edges())` has the same nodes
+/// (but for claims with no dependencies and no dependents) and edges.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DependencyGraph {
+    /// claim ids, a node's at its index
+    pub ids: Vec<String>,
+    /// adjacency list: for each node, the nodes it depends on, in the order
+    /// it refers to them
+    pub depends_on: Vec<Vec<usize>>,
+}
+
+impl DependencyGraph {
+    pub fn new(claims: &[Claim]) -> Self {
+        let nodes: HashMap<&str, usize> = claims.iter()
+            .enumerate()
+            .map(|(node, claim)| (claim.id.as_str(), node))
+            .collect();
+
+        DependencyGraph {
+            ids: claims.iter().map(|claim| claim.id.clone()).collect(),
+            depends_on: claims.iter()
+                .map(|claim| claim.depends_on().into_iter().filter_map(|id| nodes.get(id).copied()).collect())
+                .collect(),
+        }
+    }
+
+    /// Number of nodes, one for each claim
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// The node of the claim with this id
+    pub fn node(&self, id: &str) -> Option<usize> {
+        self.ids.iter().position(|claim_id| claim_id == id)
+    }
+
+    pub fn is_independent(&self, node: usize) -> bool {
+        self.depends_on[node].is_empty()
+    }
+
+    /// The independent claims' nodes, in order
+    pub fn independent(&self) -> Vec<usize> {
+        (0..self.len()).filter(|&node| self.is_independent(node)).collect()
+    }
+
+    /// The nodes that depend on node directly, in order
+    pub fn dependents(&self, node: usize) -> Vec<usize> {
+        (0..self.len()).filter(|&dependent| self.depends_on[dependent].contains(&node)).collect()
+    }
+
+    /// The independent claims that node depends on, through any number of
+    /// claims, in order; just node if it's independent. Ignores cycles,
+    /// which only a malformed grant would have.
+    pub fn roots(&self, node: usize) -> Vec<usize> {
+        let mut seen = vec![false; self.len()];
+        let mut stack = vec![node];
+        let mut roots = Vec::new();
+
+        while let Some(node) = stack.pop() {
+            if std::mem::replace(&mut seen[node], true) {
+                continue;
+            }
+
+            if self.is_independent(node) {
+                roots.push(node);
+            }
+            stack.extend(self.depends_on[node].iter().rev());
+        }
+        roots.sort_unstable();
+
+        roots
+    }
+
+    /// Each dependency as (dependent, depended on)
+    pub fn edges(&self) -> Vec<(usize, usize)> {
+        self.depends_on.iter()
+            .enumerate()
+            .flat_map(|(node, depends_on)| depends_on.iter().map(move |&other| (node, other)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deserialize::PatentGrants;
+
+    #[test]
+    fn test_dependency_graph() {
+        let grant = PatentGrants::from_reader(include_str!("../tests/fixtures/grants.xml").as_bytes()).next().unwrap().unwrap();
+        let claims = &grant.claims;
+
+        assert_eq!(claims[2].depends_on(), vec!["CLM-00001", "CLM-00002"]);
+        assert_eq!(claims.independent_claims().len(), 1);
+        assert_eq!(claims.dependent_claims().len(), 2);
+
+        let graph = claims.dependency_graph();
+        assert_eq!(graph.depends_on, vec![vec![], vec![0], vec![0, 1]]);
+        assert_eq!(graph.independent(), vec![0]);
+        assert_eq!(graph.dependents(0), vec![1, 2]);
+        assert_eq!(graph.roots(2), vec![0]);
+        assert_eq!(graph.edges(), vec![(1, 0), (2, 0), (2, 1)]);
+        assert_eq!(graph.node("CLM-00003"), Some(2));
+    }
+}
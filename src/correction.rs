@@ -0,0 +1,416 @@
+//! Certificates of correction (`us-certificates-of-correction`): errors in
+//! issued grants, e.g. a misprinted claim or a misspelled assignee, and the
+//! text they're corrected to.
+//!
+//! A file is one document, with a `us-certificate-of-correction` for each
+//! certificate issued, which is what's iterated over. Each names its grant
+//! by the grant's publication-reference, and lists its corrections, each at
+//! a location: a claim by number, a field of the front page (e.g. the
+//! invention-title, or an assignee), or a column and line of the
+//! description as printed.
+//!
+//! Certificates can be overlaid onto grants already parsed (see
+//! `CertificateOfCorrection::apply` and `overlay_corrections`). A correction
+//! is applied by replacing its erroneous text with the corrected text where
+//! it's found; the printed columns and lines aren't kept in the grant, so a
+//! description's correction is applied to the first section with its
+//! erroneous text. A claim or title corrected without erroneous text is
+//! replaced whole. Corrections of other fields, or whose erroneous text
+//! isn't found, are returned as not applied.
+
+use quick_xml::{self, Reader};
+use quick_xml::events::Event;
+use std::collections::HashMap;
+use std::io::BufRead;
+
+use crate::data::{ClaimText, Date, DocumentId, PatentGrant};
+use crate::deserialize::{deser_date, deser_doc_id_from, deser_text_from};
+use crate::docnum::padded_grant_number;
+use crate::error::{Error, WithinExt};
+use crate::options::ParserOptions;
+use crate::util::{unknown_element, Scratch};
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CertificateOfCorrection {
+    /// the corrected grant
+    pub publication_reference: DocumentId,
+    pub date_issued: Date,
+    pub corrections: Vec<Correction>,
+}
+
+impl CertificateOfCorrection {
+    /// The corrected grant's number, padded as grants' doc-numbers are
+    pub fn patent_number(&self) -> String {
+        padded_grant_number(&self.publication_reference.doc_number)
+    }
+
+    /// Overlays the corrections onto the grant, returning those that
+    /// couldn't be applied. Only the grant's text is corrected: its
+    /// descriptions_raw are left as printed.
+    pub fn apply(&self, patent_grant: &mut PatentGrant) -> Vec<&Correction> {
+        self.corrections.iter()
+            .filter(|correction| !correction.apply(patent_grant))
+            .collect()
+    }
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Correction {
+    pub location: CorrectionLocation,
+    /// as printed; not always given, e.g. for text left out
+    pub erroneous_text: Option<String>,
+    pub corrected_text: String,
+}
+
+/// Where in the grant a correction is
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CorrectionLocation {
+    /// by claim number, e.g. "2"
+    Claim(String),
+    InventionTitle,
+    Abstract,
+    /// as printed, by column and line
+    Description { column: Option<String>, line: Option<String> },
+    /// another field of the front page, by its element name (e.g.
+    /// "assignee", "inventor")
+    Field(String),
+}
+
+impl Default for CorrectionLocation {
+    fn default() -> Self {
+        CorrectionLocation::Description { column: None, line: None }
+    }
+}
+
+impl Correction {
+    /// Whether it could be applied to the grant
+    fn apply(&self, patent_grant: &mut PatentGrant) -> bool {
+        let erroneous_text = self.erroneous_text.as_deref().filter(|text| !text.is_empty());
+        let corrected_text = &self.corrected_text;
+
+        match &self.location {
+            CorrectionLocation::Claim(num) => {
+                // claim numbers are zero-padded in the grants' xml
+                let claim = patent_grant.claims.iter_mut()
+                    .find(|claim| claim.num.trim_start_matches('0') == num.trim().trim_start_matches('0'));
+                match (claim, erroneous_text) {
+                    (Some(claim), Some(erroneous_text)) => replace_in_claim_texts(&mut claim.claim_texts, erroneous_text, corrected_text),
+                    (Some(claim), None) => {
+                        claim.claim_texts = vec![ClaimText {
+                            text: corrected_text.clone(),
+                            ..ClaimText::default()
+                        }];
+                        true
+                    },
+                    (None, _) => false,
+                }
+            },
+            CorrectionLocation::InventionTitle => {
+                let title = &mut patent_grant.us_bibliographic_data_grant.invention_title.title;
+                match erroneous_text {
+                    Some(erroneous_text) => replace_in(title, erroneous_text, corrected_text),
+                    None => {
+                        *title = corrected_text.clone();
+                        true
+                    },
+                }
+            },
+            CorrectionLocation::Abstract => {
+                erroneous_text.is_some_and(|erroneous_text| {
+                    patent_grant.r#abstract.iter_mut()
+                        .any(|paragraph| replace_in(&mut paragraph.text, erroneous_text, corrected_text))
+                })
+            },
+            CorrectionLocation::Description { .. } => {
+                erroneous_text.is_some_and(|erroneous_text| {
+                    // in a fixed order, the descriptions being a map
+                    let mut sections = patent_grant.descriptions.iter_mut().collect::<Vec<_>>();
+                    sections.sort_by_key(|(key, _)| *key);
+                    sections.into_iter()
+                        .any(|(_, description)| replace_in(description, erroneous_text, corrected_text))
+                })
+            },
+            CorrectionLocation::Field(_) => false,
+        }
+    }
+}
+
+/// Replaces the first occurrence, if any
+fn replace_in(text: &mut String, from: &str, to: &str) -> bool {
+    match text.find(from) {
+        Some(start) => {
+            text.replace_range(start..start + from.len(), to);
+            true
+        },
+        None => false,
+    }
+}
+
+fn replace_in_claim_texts(claim_texts: &mut [ClaimText], from: &str, to: &str) -> bool {
+    claim_texts.iter_mut()
+        .any(|claim_text| replace_in(&mut claim_text.text, from, to) || replace_in_claim_texts(&mut claim_text.claim_texts, from, to))
+}
+
+/// Certificates by the corrected grant's padded number; a grant can have
+/// several, in the order given
+pub fn by_patent<I>(certificates: I) -> HashMap<String, Vec<CertificateOfCorrection>>
+where
+    I: IntoIterator<Item = CertificateOfCorrection>,
+{
+    let mut by_patent: HashMap<String, Vec<CertificateOfCorrection>> = HashMap::new();
+    for certificate in certificates {
+        by_patent.entry(certificate.patent_number())
+            .or_default()
+            .push(certificate);
+    }
+
+    by_patent
+}
+
+/// Applies the grant's certificates (from `by_patent`), if any, returning
+/// the corrections that couldn't be applied
+pub fn overlay_corrections<'a>(patent_grant: &mut PatentGrant, by_patent: &'a HashMap<String, Vec<CertificateOfCorrection>>) -> Vec<&'a Correction> {
+    let patent_number = padded_grant_number(&patent_grant.us_bibliographic_data_grant.publication_reference.doc_number);
+
+    match by_patent.get(&patent_number) {
+        Some(certificates) => certificates.iter()
+            .flat_map(|certificate| certificate.apply(patent_grant))
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Like PatentGrants, over a file of certificates of correction
+pub struct Corrections<B: BufRead> {
+    rdr: quick_xml::Reader<B>,
+    buf: Vec<u8>,
+    scratch: Scratch,
+    options: ParserOptions,
+}
+
+impl<B: BufRead> Corrections<B> {
+    pub fn from_reader(b: B) -> Self {
+        Self::with_options(b, ParserOptions::default())
+    }
+
+    /// Of the options (see `PatentGrants::with_options`), `trim_text`,
+    /// `entities`, `decoding`, `unknown_elements` and `skipped` apply;
+    /// `missing_fields` only to the publication-reference's document-id.
+    pub fn with_options(b: B, options: ParserOptions) -> Self {
+        let mut rdr = Reader::from_reader(b);
+        rdr.trim_text(options.trim_text);
+
+        Corrections {
+            rdr,
+            buf: Vec::new(),
+            scratch: Scratch::default(),
+            options,
+        }
+    }
+
+    /// returns None if no more certificates
+    fn deser_certificate_of_correction(&mut self) -> Option<Result<CertificateOfCorrection, Error>> {
+        loop {
+            match self.rdr.read_event(&mut self.buf) {
+                Ok(Event::Start(ref e)) if e.name() == b"us-certificate-of-correction" => break,
+                Ok(Event::Eof) => return None,
+                Ok(_) => continue,
+                Err(err) => return Some(Err(Error::deser(err.to_string()))),
+            }
+        }
+        self.buf.clear();
+
+        let mut certificate = CertificateOfCorrection::default();
+        if let Err(err) = deser_certificate(&mut self.rdr, &mut self.buf, &mut self.scratch, &self.options, &mut certificate).within(b"us-certificate-of-correction") {
+            return Some(Err(err.at_position(self.rdr.buffer_position(), &certificate.publication_reference.doc_number)));
+        }
+
+        Some(Ok(certificate))
+    }
+}
+
+impl<B: BufRead> Iterator for Corrections<B> {
+    type Item = Result<CertificateOfCorrection, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let res = self.deser_certificate_of_correction();
+        self.buf.clear();
+
+        res
+    }
+}
+
+/// called after tag us-certificate-of-correction is already hit
+fn deser_certificate<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    certificate: &mut CertificateOfCorrection,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    // the document-id is read as it comes
+                    b"publication-reference" | b"corrections" => continue,
+                    b"document-id" => {
+                        deser_doc_id_from(rdr, buf, scratch, opts, &mut certificate.publication_reference).within(b"document-id")?;
+                    },
+                    b"date-issued" => certificate.date_issued = deser_date(b"date", rdr, scratch, opts).within(b"date-issued")?,
+                    b"correction" => {
+                        let mut correction = Correction::default();
+                        deser_correction(rdr, buf, scratch, opts, &mut correction).within(b"correction")?;
+                        certificate.corrections.push(correction);
+                    },
+                    _ => unknown_element(e.name(), "us-certificate-of-correction", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"us-certificate-of-correction" {
+                    break;
+                }
+            },
+            Ok(Event::Eof) => return Err(Error::deser("unexpected end of file in us-certificate-of-correction".to_string())),
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        };
+    }
+
+    Ok(())
+}
+
+/// called after tag correction is already hit
+fn deser_correction<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    correction: &mut Correction,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"location" => correction.location = deser_location(rdr, buf, scratch, opts).within(b"location")?,
+                    b"erroneous-text" => correction.erroneous_text = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"corrected-text" => correction.corrected_text = deser_text_from(e.name(), rdr, scratch, opts)?,
+                    _ => unknown_element(e.name(), "correction", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"correction" {
+                    break;
+                }
+            },
+            Ok(Event::Eof) => return Err(Error::deser("unexpected end of file in correction".to_string())),
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        };
+    }
+
+    Ok(())
+}
+
+/// called after tag location is already hit
+fn deser_location<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    ) -> Result<CorrectionLocation, Error>
+{
+    let mut claim_num = None;
+    let mut field = None;
+    let mut column = None;
+    let mut line = None;
+
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"claim-num" => claim_num = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"field" => field = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"column" => column = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"line" => line = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    _ => unknown_element(e.name(), "location", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"location" {
+                    break;
+                }
+            },
+            Ok(Event::Eof) => return Err(Error::deser("unexpected end of file in location".to_string())),
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        };
+    }
+
+    // a claim's column and line are only where it was printed
+    Ok(match (claim_num, field) {
+        (Some(num), _) => CorrectionLocation::Claim(num),
+        (None, Some(field)) => match field.trim() {
+            "invention-title" => CorrectionLocation::InventionTitle,
+            "abstract" => CorrectionLocation::Abstract,
+            "description" => CorrectionLocation::Description { column, line },
+            other => CorrectionLocation::Field(other.to_string()),
+        },
+        (None, None) => CorrectionLocation::Description { column, line },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::date_text;
+    use crate::PatentGrants;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/corrections.xml");
+    const GRANTS_FIXTURE: &str = include_str!("../tests/fixtures/grants.xml");
+
+    #[test]
+    fn test_corrections() {
+        let certificates = Corrections::from_reader(FIXTURE.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(certificates.len(), 2);
+
+        let certificate = &certificates[0];
+        assert_eq!(certificate.patent_number(), "10165721");
+        assert_eq!(date_text(&certificate.date_issued), "20190604");
+        assert_eq!(certificate.corrections.len(), 5);
+        assert_eq!(certificate.corrections[0].location, CorrectionLocation::Description {
+            column: Some("4".to_string()),
+            line: Some("12".to_string()),
+        });
+        assert_eq!(certificate.corrections[1].location, CorrectionLocation::Claim("3".to_string()));
+        assert_eq!(certificate.corrections[3].location, CorrectionLocation::InventionTitle);
+        assert_eq!(certificate.corrections[4].location, CorrectionLocation::Field("assignee".to_string()));
+        assert!(certificate.corrections[2].erroneous_text.is_none());
+
+        let by_patent = by_patent(certificates);
+        assert_eq!(by_patent["D0838001"].len(), 1);
+    }
+
+    #[test]
+    fn test_overlay_corrections() {
+        let by_patent = by_patent(Corrections::from_reader(FIXTURE.as_bytes()).map(Result::unwrap));
+        let mut grant = PatentGrants::from_reader(GRANTS_FIXTURE.as_bytes()).next().unwrap().unwrap();
+
+        let not_applied = overlay_corrections(&mut grant, &by_patent);
+        assert_eq!(not_applied.len(), 1);
+        assert_eq!(not_applied[0].location, CorrectionLocation::Field("assignee".to_string()));
+
+        assert!(grant.descriptions.values().any(|description| description.contains("made of stainless steel")));
+        assert!(grant.claims[2].claim_texts[0].text.contains("the body is stainless steel"));
+        assert_eq!(grant.claims[1].claim_texts.len(), 1);
+        assert_eq!(grant.claims[1].claim_texts[0].text, "2. The hand tool of claim 1, wherein the grip is rubber.");
+        assert_eq!(grant.us_bibliographic_data_grant.invention_title.title, "Hand tool with an ergonomic grip");
+    }
+}
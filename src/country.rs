@@ -0,0 +1,137 @@
+//! WIPO ST.3 two-letter codes, for the countries (or offices, e.g. EP and WO)
+//! of documents, classifications and addresses.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+/// A country code. Text that isn't an ST.3 code (e.g. lower case, or a
+/// typo in an older grant) is kept as is in `Unknown`, so nothing is lost.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CountryCode {
+    St3(&'static str),
+    Unknown(String),
+}
+
+/// The default is an empty (so unknown) code, for a missing country
+impl Default for CountryCode {
+    fn default() -> Self {
+        CountryCode::Unknown(String::new())
+    }
+}
+
+impl CountryCode {
+    pub const US: CountryCode = CountryCode::St3("US");
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            CountryCode::St3(code) => code,
+            CountryCode::Unknown(code) => code,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.as_str().is_empty()
+    }
+
+    pub fn is_us(&self) -> bool {
+        *self == CountryCode::US
+    }
+
+    pub fn is_st3(&self) -> bool {
+        matches!(self, CountryCode::St3(_))
+    }
+}
+
+impl From<&str> for CountryCode {
+    fn from(code: &str) -> Self {
+        match ST3_CODES.binary_search(&code) {
+            Ok(i) => CountryCode::St3(ST3_CODES[i]),
+            Err(_) => CountryCode::Unknown(code.to_string()),
+        }
+    }
+}
+
+impl From<String> for CountryCode {
+    fn from(code: String) -> Self {
+        match ST3_CODES.binary_search(&code.as_str()) {
+            Ok(i) => CountryCode::St3(ST3_CODES[i]),
+            Err(_) => CountryCode::Unknown(code),
+        }
+    }
+}
+
+impl From<CountryCode> for String {
+    fn from(code: CountryCode) -> Self {
+        match code {
+            CountryCode::St3(code) => code.to_string(),
+            CountryCode::Unknown(code) => code,
+        }
+    }
+}
+
+impl fmt::Display for CountryCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// as the code's text, like the String it replaced
+#[cfg(feature = "serde")]
+impl Serialize for CountryCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for CountryCode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(CountryCode::from)
+    }
+}
+
+/// Sorted, for binary search. The ISO 3166 countries, the offices and
+/// organizations (e.g. EP, WO, EM), and former codes still found in older
+/// citations (e.g. SU, DD, YU).
+const ST3_CODES: &[&str] = &[
+    "AD", "AE", "AF", "AG", "AI", "AL", "AM", "AN", "AO", "AP", "AQ", "AR", "AS", "AT", "AU", "AW",
+    "AX", "AZ", "BA", "BB", "BD", "BE", "BF", "BG", "BH", "BI", "BJ", "BL", "BM", "BN", "BO", "BQ",
+    "BR", "BS", "BT", "BU", "BV", "BW", "BX", "BY", "BZ", "CA", "CC", "CD", "CF", "CG", "CH", "CI",
+    "CK", "CL", "CM", "CN", "CO", "CR", "CS", "CU", "CV", "CW", "CX", "CY", "CZ", "DD", "DE", "DJ",
+    "DK", "DM", "DO", "DY", "DZ", "EA", "EC", "EE", "EG", "EH", "EM", "EP", "ER", "ES", "ET", "FI",
+    "FJ", "FK", "FM", "FO", "FR", "GA", "GB", "GC", "GD", "GE", "GF", "GG", "GH", "GI", "GL", "GM",
+    "GN", "GP", "GQ", "GR", "GS", "GT", "GU", "GW", "GY", "HK", "HM", "HN", "HR", "HT", "HU", "IB",
+    "ID", "IE", "IL", "IM", "IN", "IO", "IQ", "IR", "IS", "IT", "JE", "JM", "JO", "JP", "KE", "KG",
+    "KH", "KI", "KM", "KN", "KP", "KR", "KW", "KY", "KZ", "LA", "LB", "LC", "LI", "LK", "LR", "LS",
+    "LT", "LU", "LV", "LY", "MA", "MC", "MD", "ME", "MF", "MG", "MH", "MK", "ML", "MM", "MN", "MO",
+    "MP", "MQ", "MR", "MS", "MT", "MU", "MV", "MW", "MX", "MY", "MZ", "NA", "NC", "NE", "NF", "NG",
+    "NI", "NL", "NO", "NP", "NR", "NU", "NZ", "OA", "OM", "PA", "PE", "PF", "PG", "PH", "PK", "PL",
+    "PM", "PN", "PR", "PS", "PT", "PW", "PY", "QA", "QZ", "RE", "RH", "RO", "RS", "RU", "RW", "SA",
+    "SB", "SC", "SD", "SE", "SG", "SH", "SI", "SJ", "SK", "SL", "SM", "SN", "SO", "SR", "SS", "ST",
+    "SU", "SV", "SX", "SY", "SZ", "TC", "TD", "TF", "TG", "TH", "TJ", "TK", "TL", "TM", "TN", "TO",
+    "TP", "TR", "TT", "TV", "TW", "TZ", "UA", "UG", "UM", "US", "UY", "UZ", "VA", "VC", "VE", "VG",
+    "VI", "VN", "VU", "WF", "WO", "WS", "XN", "XU", "XV", "XX", "YD", "YE", "YT", "YU", "ZA", "ZM",
+    "ZR", "ZW",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_country_code() {
+        assert!(ST3_CODES.windows(2).all(|pair| pair[0] < pair[1]));
+
+        assert_eq!(CountryCode::from("US"), CountryCode::US);
+        assert!(CountryCode::from("US").is_us());
+        assert!(CountryCode::from("EP").is_st3());
+        assert!(CountryCode::from("SU").is_st3());
+
+        let unknown = CountryCode::from("us");
+        assert_eq!(unknown, CountryCode::Unknown("us".to_string()));
+        assert!(!unknown.is_us());
+        assert_eq!(String::from(unknown), "us");
+        assert!(!CountryCode::default().is_st3());
+    }
+}
@@ -0,0 +1,248 @@
+//! The CPC scheme's titles, from its title list bulk files (one per
+//! section, e.g. `cpc-section-G_20240101.txt`), so the CPC symbols parsed
+//! from grants can be looked up by title and ancestors.
+//!
+//! A line of a title list is a symbol, tab, and its title; a group's line
+//! also has its dot level (0 for a main group, 1 for its subgroups, 2 for
+//! theirs, and so on) between them. With the tabs as spaces:
+//!
+//! ```text
+//! G        PHYSICS
+//! G06N     COMPUTING ARRANGEMENTS BASED ON SPECIFIC COMPUTATIONAL MODELS
+//! G06N3/00 0 Computing arrangements based on biological models
+//! G06N3/02 1 Neural networks
+//! G06N3/08 2 Learning methods
+//! ```
+//!
+//! The lines are in the scheme's order, so an entry's parent is the last
+//! one before it a level up: a subgroup's is the group a dot less, not
+//! necessarily the one it shares the most of its symbol with. Symbols are
+//! looked up with their spaces removed, so `ClassificationCpc::symbol`'s
+//! "G06N 3/08" is found as "G06N3/08".
+
+use snafu::ResultExt;
+use std::collections::HashMap;
+use std::io::BufRead;
+
+use crate::error::{Error, Read};
+
+/// An entry's level in the scheme
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpcLevel {
+    /// e.g. G
+    Section,
+    /// e.g. G06
+    Class,
+    /// e.g. G06N
+    Subclass,
+    /// e.g. G06N3/00
+    MainGroup,
+    /// e.g. G06N3/08, by its dot level (1 or more)
+    Subgroup(u8),
+}
+
+impl CpcLevel {
+    /// how far below the section it is
+    fn depth(self) -> usize {
+        match self {
+            CpcLevel::Section => 0,
+            CpcLevel::Class => 1,
+            CpcLevel::Subclass => 2,
+            CpcLevel::MainGroup => 3,
+            CpcLevel::Subgroup(dots) => 3 + dots as usize,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CpcEntry {
+    /// without spaces, e.g. "G06N3/08"
+    pub symbol: String,
+    pub title: String,
+    pub level: CpcLevel,
+    parent: Option<usize>,
+}
+
+/// The scheme's entries, as a tree from the sections down
+#[derive(Debug, Default)]
+pub struct CpcScheme {
+    entries: Vec<CpcEntry>,
+    by_symbol: HashMap<String, usize>,
+}
+
+impl CpcScheme {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads a title list
+    pub fn from_reader<B: BufRead>(rdr: B) -> Result<Self, Error> {
+        let mut scheme = Self::new();
+        scheme.add_title_list(rdr)?;
+
+        Ok(scheme)
+    }
+
+    /// Adds a title list (e.g. another section's) to the scheme. A symbol
+    /// already in the scheme has its title replaced.
+    pub fn add_title_list<B: BufRead>(&mut self, mut rdr: B) -> Result<(), Error> {
+        // the last entry at each depth above the one being added
+        let mut open: Vec<usize> = Vec::new();
+        let mut line_number = 0;
+
+        loop {
+            let mut line = Vec::new();
+            let read = rdr.read_until(b'\n', &mut line)
+                .context(Read)?;
+            if read == 0 {
+                break;
+            }
+            line_number += 1;
+
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\r', '\n']);
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let (symbol, level, title) = parse_line(line)
+                .map_err(|src| Error::CpcSchemeFormat { line: line_number, src })?;
+
+            while open.last().is_some_and(|&i| self.entries[i].level.depth() >= level.depth()) {
+                open.pop();
+            }
+
+            let i = match self.by_symbol.get(&symbol) {
+                Some(&i) => {
+                    self.entries[i].title = title;
+                    i
+                },
+                None => {
+                    self.entries.push(CpcEntry {
+                        symbol: symbol.clone(),
+                        title,
+                        level,
+                        parent: open.last().copied(),
+                    });
+                    self.by_symbol.insert(symbol, self.entries.len() - 1);
+                    self.entries.len() - 1
+                },
+            };
+            open.push(i);
+        }
+
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn entry(&self, symbol: &str) -> Option<&CpcEntry> {
+        let symbol = normalize(symbol);
+        self.by_symbol.get(&symbol).map(|&i| &self.entries[i])
+    }
+
+    pub fn title(&self, symbol: &str) -> Option<&str> {
+        self.entry(symbol).map(|entry| entry.title.as_str())
+    }
+
+    pub fn parent(&self, symbol: &str) -> Option<&CpcEntry> {
+        self.entry(symbol)
+            .and_then(|entry| entry.parent)
+            .map(|i| &self.entries[i])
+    }
+
+    /// The entries above the symbol's, from its section down to its parent.
+    /// Empty if the symbol isn't in the scheme.
+    pub fn ancestors(&self, symbol: &str) -> Vec<&CpcEntry> {
+        let mut ancestors = Vec::new();
+        let mut parent = self.entry(symbol).and_then(|entry| entry.parent);
+        while let Some(i) = parent {
+            ancestors.push(&self.entries[i]);
+            parent = self.entries[i].parent;
+        }
+        ancestors.reverse();
+
+        ancestors
+    }
+}
+
+fn normalize(symbol: &str) -> String {
+    symbol.chars()
+        .filter(|c| !c.is_whitespace())
+        .collect()
+}
+
+/// symbol, level and title; a missing or empty dot level is the symbol's
+fn parse_line(line: &str) -> Result<(String, CpcLevel, String), String> {
+    let fields = line.split('\t').collect::<Vec<_>>();
+    let (symbol, dots, title) = match fields.as_slice() {
+        [symbol, title] => (*symbol, "", *title),
+        [symbol, dots, title, ..] => (*symbol, *dots, *title),
+        _ => return Err("expected a symbol and title".to_string()),
+    };
+
+    let symbol = normalize(symbol);
+    let level = match (symbol.contains('/'), symbol.len()) {
+        (true, _) => match dots.trim() {
+            "" | "0" => CpcLevel::MainGroup,
+            dots => match dots.parse::<u8>() {
+                Ok(dots) => CpcLevel::Subgroup(dots),
+                Err(_) => return Err(format!("invalid dot level {:?}", dots)),
+            },
+        },
+        (false, 1) => CpcLevel::Section,
+        (false, 3) => CpcLevel::Class,
+        (false, 4) => CpcLevel::Subclass,
+        (false, _) => return Err(format!("invalid symbol {:?}", symbol)),
+    };
+
+    Ok((symbol, level, title.trim().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TITLES: &str = "\
+G\tPHYSICS
+G06\tCOMPUTING; CALCULATING OR COUNTING
+G06N\tCOMPUTING ARRANGEMENTS BASED ON SPECIFIC COMPUTATIONAL MODELS
+G06N3/00\t0\tComputing arrangements based on biological models
+G06N3/02\t1\tNeural networks
+G06N3/04\t2\tArchitecture, e.g. interconnection topology
+G06N3/08\t2\tLearning methods
+G06N3/12\t1\tusing genetic models
+G06N5/00\t0\tComputing arrangements using knowledge-based models
+";
+
+    #[test]
+    fn test_cpc_scheme() {
+        let scheme = CpcScheme::from_reader(TITLES.as_bytes()).unwrap();
+        assert_eq!(scheme.len(), 9);
+        assert_eq!(scheme.title("G06N3/08"), Some("Learning methods"));
+        assert_eq!(scheme.title("G06N 3/08"), Some("Learning methods"));
+        assert_eq!(scheme.entry("G06N3/08").unwrap().level, CpcLevel::Subgroup(2));
+        assert!(scheme.title("G06N3/10").is_none());
+
+        let ancestors = scheme.ancestors("G06N3/08").into_iter()
+            .map(|entry| entry.symbol.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ancestors, vec!["G", "G06", "G06N", "G06N3/00", "G06N3/02"]);
+        // a dot less, not the symbol before it
+        assert_eq!(scheme.parent("G06N3/12").unwrap().symbol, "G06N3/00");
+        assert_eq!(scheme.parent("G06N5/00").unwrap().symbol, "G06N");
+        assert!(scheme.ancestors("G").is_empty());
+
+        let invalid = TITLES.replace("G06N3/12\t1", "G06N3/12\tone");
+        match CpcScheme::from_reader(invalid.as_bytes()) {
+            Err(Error::CpcSchemeFormat { line, .. }) => assert_eq!(line, 8),
+            other => panic!("expected a format error, got {:?}", other),
+        }
+    }
+}
@@ -1,11 +1,42 @@
 //! data struct definitions for xml data
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
+
+pub use crate::country::CountryCode;
+pub use crate::uspc::Uspc;
+
+/// A YYYYMMDD date: the text as is, or with the `typed-dates` feature, parsed
+/// into a NaiveDate (and the document is an error if it's not a valid date).
+/// A 00 month or day, as in citations dated only to the month, is parsed as
+/// the first.
+/// A missing date is left at the default, which for a NaiveDate is 1970-01-01.
+#[cfg(not(feature = "typed-dates"))]
+pub type Date = String;
+#[cfg(feature = "typed-dates")]
+pub type Date = chrono::NaiveDate;
+
+/// A date as YYYYMMDD, as in the xml, whether or not dates are typed
+#[cfg(not(feature = "typed-dates"))]
+pub fn date_text(date: &Date) -> String {
+    date.clone()
+}
+#[cfg(feature = "typed-dates")]
+pub fn date_text(date: &Date) -> String {
+    date.format("%Y%m%d").to_string()
+}
 
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PatentGrant {
+    pub meta: GrantMeta,
+
     pub us_bibliographic_data_grant: BibliographicDataGrant,
 
+    pub r#abstract: Vec<Paragraph>,
+
     //pub drawings: Drawings,
 
     // encompasses all possible descriptions in grant:
@@ -15,26 +46,269 @@ pub struct PatentGrant {
     // - DETDESC (detailed description)
     // - in-line-formulae
     pub descriptions: HashMap<String, String>,
+    // same keys as descriptions, but holding the raw inner xml of each section
+    // (paragraphs, headings, figrefs, tables, etc.)
+    pub descriptions_raw: HashMap<String, String>,
+
+    // federally sponsored research statement, if any
+    pub government_interest: Option<String>,
+
+    // chemical structures in the description sections (those in claims are
+    // in their claim-text), and the sequence listing section's files
+    pub chemistry: Vec<Chemistry>,
+    pub sequence_listing: Option<SequenceListing>,
 
     pub us_claim_statement: String,
-    pub claims: Vec<String>,
+    pub claims: Vec<Claim>,
+}
+
+/// The type of a grant, see `PatentGrant::doc_type`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DocType {
+    Utility,
+    Design,
+    Plant,
+    Reissue,
+    /// statutory invention registration
+    Sir,
+}
+
+impl PatentGrant {
+    /// From the publication's kind code, or (for APS grants, which have
+    /// none) its doc-number's prefix. None for a kind code that isn't a
+    /// grant's.
+    pub fn doc_type(&self) -> Option<DocType> {
+        let publication_reference = &self.us_bibliographic_data_grant.publication_reference;
+
+        match publication_reference.kind {
+            Some(ref kind) => kind.doc_type(),
+            None => {
+                let doc_number = &publication_reference.doc_number;
+                if doc_number.starts_with("RE") {
+                    Some(DocType::Reissue)
+                } else if doc_number.starts_with("PP") {
+                    Some(DocType::Plant)
+                } else if doc_number.starts_with('D') {
+                    Some(DocType::Design)
+                } else if doc_number.starts_with('H') {
+                    Some(DocType::Sir)
+                } else if doc_number.starts_with(|c: char| c.is_ascii_digit()) {
+                    Some(DocType::Utility)
+                } else {
+                    None
+                }
+            },
+        }
+    }
+
+    /// Resets to the default, but keeps the allocations of the collections
+    /// (claims, descriptions, citations, etc.) for the next grant to be read
+    /// into, see `PatentGrants::next_into`
+    pub fn clear(&mut self) {
+        let biblio = &mut self.us_bibliographic_data_grant;
+        let mut us_bibliographic_data_grant = BibliographicDataGrant {
+            priority_claims: std::mem::take(&mut biblio.priority_claims),
+            classifications_ipcr: std::mem::take(&mut biblio.classifications_ipcr),
+            us_references_cited: std::mem::take(&mut biblio.us_references_cited),
+            us_exemplary_claim: std::mem::take(&mut biblio.us_exemplary_claim),
+            us_related_documents: std::mem::take(&mut biblio.us_related_documents),
+            us_applicants: std::mem::take(&mut biblio.us_applicants),
+            inventors: std::mem::take(&mut biblio.inventors),
+            agents: std::mem::take(&mut biblio.agents),
+            assignees: std::mem::take(&mut biblio.assignees),
+            ..BibliographicDataGrant::default()
+        };
+        us_bibliographic_data_grant.priority_claims.clear();
+        us_bibliographic_data_grant.classifications_ipcr.clear();
+        us_bibliographic_data_grant.us_references_cited.clear();
+        us_bibliographic_data_grant.us_exemplary_claim.clear();
+        us_bibliographic_data_grant.us_related_documents.clear();
+        us_bibliographic_data_grant.us_applicants.clear();
+        us_bibliographic_data_grant.inventors.clear();
+        us_bibliographic_data_grant.agents.clear();
+        us_bibliographic_data_grant.assignees.clear();
+
+        let mut us_claim_statement = std::mem::take(&mut self.us_claim_statement);
+        us_claim_statement.clear();
+        self.r#abstract.clear();
+        self.descriptions.clear();
+        self.descriptions_raw.clear();
+        self.claims.clear();
+
+        *self = PatentGrant {
+            us_bibliographic_data_grant,
+            r#abstract: std::mem::take(&mut self.r#abstract),
+            descriptions: std::mem::take(&mut self.descriptions),
+            descriptions_raw: std::mem::take(&mut self.descriptions_raw),
+            us_claim_statement,
+            claims: std::mem::take(&mut self.claims),
+            ..PatentGrant::default()
+        };
+    }
+}
+
+/// A PatentGrant without its claims and descriptions, see
+/// `PatentGrants::biblio_only`
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PatentGrantBiblio {
+    pub meta: GrantMeta,
+    pub us_bibliographic_data_grant: BibliographicDataGrant,
+    pub r#abstract: Vec<Paragraph>,
+}
+
+/// A pre-grant publication (`us-patent-application`, in ipa*.xml bulk files).
+/// Same layout as PatentGrant, minus what only exists once granted.
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PatentApplication {
+    pub meta: GrantMeta,
+
+    pub us_bibliographic_data_application: ApplicationBiblio,
+
+    pub r#abstract: Vec<Paragraph>,
+
+    // same sections as in PatentGrant
+    pub descriptions: HashMap<String, String>,
+    pub descriptions_raw: HashMap<String, String>,
+
+    pub government_interest: Option<String>,
+
+    pub chemistry: Vec<Chemistry>,
+    pub sequence_listing: Option<SequenceListing>,
+
+    pub claims: Vec<Claim>,
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Paragraph {
+    pub id: String,
+    pub num: String,
+    pub text: String,
+    /// only with `ParserOptions::formulae`
+    pub formulae: Vec<Formula>,
+    pub figrefs: Vec<FigRef>,
+}
+
+/// Reference to a figure, e.g. "FIG. 1" in a paragraph
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FigRef {
+    /// id of the referenced figure, e.g. "DRAWINGS" (most grants refer to
+    /// the drawings as a whole) or "F00001"
+    pub idref: String,
+    pub text: String,
+}
+
+/// A chemical structure (`chemistry`, a CWU). Its drawing and structure
+/// files (e.g. a TIF, and CDX and MOL files) are in the grant's zip, not its
+/// xml.
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Chemistry {
+    /// e.g. "CHEM-US-00001"
+    pub id: String,
+    pub num: String,
+    pub files: Vec<String>,
+}
+
+/// The sequence listing (SEQLST) section of the description. Its text is
+/// in descriptions unless `ParserOptions::sequence_listing_text` is off.
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SequenceListing {
+    /// ids of the elements in the section that have one
+    pub ids: Vec<String>,
+    /// files the section references, e.g. the listing itself or page images
+    pub files: Vec<String>,
+}
+
+/// A formula (`maths`) in a paragraph or claim, see `ParserOptions::formulae`
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Formula {
+    /// e.g. "MATH-US-00001"
+    pub id: String,
+    pub num: String,
+    /// the `math` element, as xml
+    pub mathml: String,
+    /// only with `ParserOptions::latex`, and if it could be converted
+    pub latex: Option<String>,
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Claim {
+    pub id: String,
+    pub num: String,
+    pub claim_type: Option<String>,
+    pub claim_texts: Vec<ClaimText>,
+}
+
+/// A claim-text segment. Claim text nests (e.g. the elements of a claim
+/// are claim-text within the claim's lead claim-text), so nested segments
+/// are kept in order under their parent rather than flattened.
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ClaimText {
+    /// text of this segment, not including nested claim-text
+    pub text: String,
+    pub claim_refs: Vec<ClaimRef>,
+    /// only with `ParserOptions::formulae`
+    pub formulae: Vec<Formula>,
+    pub chemistry: Vec<Chemistry>,
+    pub figrefs: Vec<FigRef>,
+    pub claim_texts: Vec<ClaimText>,
+}
+
+/// Reference to another claim, e.g. "claim 1" in a dependent claim.
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ClaimRef {
+    /// id of the referenced claim, e.g. "CLM-00001"
+    pub idref: String,
+    pub text: String,
 }
 
+/// attributes of the us-patent-grant root element
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GrantMeta {
+    pub lang: String,
+    // e.g. "v4.5 2014-04-03"; which schema variant produced the record
+    pub dtd_version: Option<String>,
+    pub file: Option<String>,
+    pub status: Option<String>,
+    pub id: Option<String>,
+    pub country: String,
+    pub file_reference_id: Option<String>,
+    pub date_produced: Option<String>,
+    pub date_publ: Option<String>,
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BibliographicDataGrant {
     pub publication_reference: DocumentId,
     pub application_reference: DocumentId,
     pub us_application_series_code: String,
-    // TODO: handle disclaimer
-    pub us_term_of_grant: String,
+    pub priority_claims: Vec<PriorityClaim>,
+    pub us_term_of_grant: UsTermOfGrant,
+    pub classifications_ipcr: Vec<ClassificationIpcr>,
+    pub classifications_cpc: ClassificationsCpc,
     pub classification_locarno: ClassificationLocarno,
     pub classification_national: ClassificationNational,
-    // TODO: handle ID
-    pub invention_title: String,
-//    pub us_references_cited: Vec<UsCitation>,
-    pub number_of_claims: String,
-    pub us_exemplary_claim: String,
+    pub invention_title: InventionTitle,
+    // plant patents only
+    pub botanic: Option<UsBotanic>,
+    pub us_references_cited: Vec<UsCitation>,
+    pub number_of_claims: Option<u32>,
+    pub us_exemplary_claim: Vec<u32>,
     pub us_field_of_classification_search: UsFieldOfClassificationSearch,
+    pub figures: Figures,
+    pub us_related_documents: Vec<RelatedDocument>,
 
     // ==================
     // Us Parties
@@ -45,44 +319,405 @@ pub struct BibliographicDataGrant {
 
     pub assignees: Vec<Assignee>,
     pub examiners: Examiners,
+
+    pub pct_or_regional_filing_data: Option<PctOrRegionalFilingData>,
+    pub pct_or_regional_publishing_data: Option<PctOrRegionalPublishingData>,
+    // design patents filed through the Hague system only
+    pub hague_agreement_data: Option<HagueAgreementData>,
+}
+
+/// us-bibliographic-data-application; the grant biblio's fields that exist
+/// before grant (no term of grant, citations, examiners, claim counts, etc.)
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ApplicationBiblio {
+    pub publication_reference: DocumentId,
+    pub application_reference: DocumentId,
+    pub us_application_series_code: String,
+    pub priority_claims: Vec<PriorityClaim>,
+    pub classifications_ipcr: Vec<ClassificationIpcr>,
+    pub classifications_cpc: ClassificationsCpc,
+    pub classification_national: ClassificationNational,
+    pub invention_title: InventionTitle,
+    pub us_related_documents: Vec<RelatedDocument>,
+
+    // ==================
+    // Us Parties
+    pub us_applicants: Vec<UsApplicant>,
+    pub inventors: Vec<Inventor>,
+    pub agents: Vec<Agent>,
+    // ==================
+
+    pub assignees: Vec<Assignee>,
+
+    pub pct_or_regional_filing_data: Option<PctOrRegionalFilingData>,
+    pub pct_or_regional_publishing_data: Option<PctOrRegionalPublishingData>,
+}
+
+/// A WIPO ST.16 kind code, as used by the USPTO. The one-letter codes are
+/// those of documents published before 2001. Any other code (e.g. a foreign
+/// cited document's) is kept as is in `Other`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(from = "String", into = "String"))]
+pub enum KindCode {
+    /// utility patent, before 2001
+    A,
+    /// application publication
+    A1,
+    /// application republication
+    A2,
+    /// corrected application publication
+    A9,
+    /// utility patent, with no previous application publication
+    B1,
+    /// utility patent, with a previous application publication
+    B2,
+    /// reexamination certificates
+    C1,
+    C2,
+    C3,
+    /// reissue patent, before 2001
+    E,
+    /// reissue patent
+    E1,
+    /// statutory invention registration, before 2001
+    H,
+    /// statutory invention registration
+    H1,
+    /// plant patent, before 2001
+    P,
+    /// plant application publication
+    P1,
+    /// plant patent, with no previous application publication
+    P2,
+    /// plant patent, with a previous application publication
+    P3,
+    /// plant application republication
+    P4,
+    /// corrected plant application publication
+    P9,
+    /// design patent, before 2001
+    S,
+    /// design patent
+    S1,
+    Other(String),
+}
+
+impl KindCode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            KindCode::A => "A",
+            KindCode::A1 => "A1",
+            KindCode::A2 => "A2",
+            KindCode::A9 => "A9",
+            KindCode::B1 => "B1",
+            KindCode::B2 => "B2",
+            KindCode::C1 => "C1",
+            KindCode::C2 => "C2",
+            KindCode::C3 => "C3",
+            KindCode::E => "E",
+            KindCode::E1 => "E1",
+            KindCode::H => "H",
+            KindCode::H1 => "H1",
+            KindCode::P => "P",
+            KindCode::P1 => "P1",
+            KindCode::P2 => "P2",
+            KindCode::P3 => "P3",
+            KindCode::P4 => "P4",
+            KindCode::P9 => "P9",
+            KindCode::S => "S",
+            KindCode::S1 => "S1",
+            KindCode::Other(kind) => kind,
+        }
+    }
+
+    /// The type of grant with this kind code, if it's a grant's
+    pub fn doc_type(&self) -> Option<DocType> {
+        match self {
+            KindCode::A | KindCode::B1 | KindCode::B2 => Some(DocType::Utility),
+            KindCode::S | KindCode::S1 => Some(DocType::Design),
+            KindCode::P | KindCode::P2 | KindCode::P3 => Some(DocType::Plant),
+            KindCode::E | KindCode::E1 => Some(DocType::Reissue),
+            KindCode::H | KindCode::H1 => Some(DocType::Sir),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for KindCode {
+    fn from(kind: &str) -> Self {
+        match kind.trim() {
+            "A" => KindCode::A,
+            "A1" => KindCode::A1,
+            "A2" => KindCode::A2,
+            "A9" => KindCode::A9,
+            "B1" => KindCode::B1,
+            "B2" => KindCode::B2,
+            "C1" => KindCode::C1,
+            "C2" => KindCode::C2,
+            "C3" => KindCode::C3,
+            "E" => KindCode::E,
+            "E1" => KindCode::E1,
+            "H" => KindCode::H,
+            "H1" => KindCode::H1,
+            "P" => KindCode::P,
+            "P1" => KindCode::P1,
+            "P2" => KindCode::P2,
+            "P3" => KindCode::P3,
+            "P4" => KindCode::P4,
+            "P9" => KindCode::P9,
+            "S" => KindCode::S,
+            "S1" => KindCode::S1,
+            other => KindCode::Other(other.to_string()),
+        }
+    }
+}
+
+impl From<String> for KindCode {
+    fn from(kind: String) -> Self {
+        KindCode::from(kind.as_str())
+    }
+}
+
+impl From<KindCode> for String {
+    fn from(kind: KindCode) -> Self {
+        match kind {
+            KindCode::Other(kind) => kind,
+            kind => kind.as_str().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for KindCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DocumentId {
-    pub country: String,
+    pub country: CountryCode,
     pub doc_number: String,
-    pub kind: Option<String>,
-    pub date: String,
+    pub kind: Option<KindCode>,
+    // patentee or applicant; only given for cited documents
+    pub name: Option<String>,
+    pub date: Date,
+}
+
+/// One entry of us-references-cited
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UsCitation {
+    pub citation: Citation,
+    // e.g. "cited by examiner", "cited by applicant"
+    pub category: Option<String>,
+    pub classification_cpc_text: Option<String>,
+    pub classification_national: Option<ClassificationNational>,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Citation {
+    Patent(PatCit),
+    NonPatent(NplCit),
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PatCit {
+    pub num: String,
+    pub document_id: DocumentId,
+}
+
+/// Non-patent literature, e.g. an article or office action
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NplCit {
+    pub num: String,
+    pub othercit: String,
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UsTermOfGrant {
+    pub length_of_grant: Option<String>, // years
+    pub us_term_extension: Option<String>, // days
+    pub disclaimer: Option<Disclaimer>,
+    pub prior_disclosure_affidavit_filed: Option<String>,
+    pub text: Option<String>,
+}
+
+/// Terminal disclaimer
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Disclaimer {
+    pub date: Option<String>,
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PriorityClaim {
+    pub sequence: String,
+    pub kind: String, // national, regional, or international
+    pub country: CountryCode,
+    pub doc_number: Option<String>,
+    pub date: Date,
+    pub office_of_filing: Option<String>, // country
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ClassificationIpcr {
+    pub ipc_version_indicator: String, // date, the ipc edition
+    pub classification_level: Option<String>,
+    pub section: String,
+    pub class: String,
+    pub subclass: String,
+    pub main_group: Option<String>,
+    pub subgroup: Option<String>,
+    pub symbol_position: Option<String>,
+    pub classification_value: Option<String>,
+    pub action_date: Option<Date>,
+    pub generating_office: Option<String>, // country
+    pub classification_status: Option<String>,
+    pub classification_data_source: Option<String>,
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ClassificationsCpc {
+    pub main_cpc: ClassificationCpc,
+    pub further_cpc: Vec<ClassificationCpc>,
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ClassificationCpc {
+    pub cpc_version_indicator: String, // date
+    pub section: String,
+    pub class: String,
+    pub subclass: String,
+    pub main_group: String,
+    pub subgroup: String,
+    pub symbol_position: String,
+    pub classification_value: String,
+    pub action_date: Date,
+    pub generating_office: String, // country
+    pub classification_status: Option<String>,
+    pub classification_data_source: Option<String>,
+    pub scheme_origination_code: Option<String>,
+}
+
+impl ClassificationCpc {
+    /// e.g. "A01B 1/00"
+    pub fn symbol(&self) -> String {
+        format!("{}{}{} {}/{}", self.section, self.class, self.subclass, self.main_group, self.subgroup)
+    }
+}
+
+/// One entry of us-related-documents. Most kinds of relation hold a parent
+/// document and the child (this patent's application).
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RelatedDocument {
+    Addition(Relation),
+    Division(Relation),
+    Continuation(Relation),
+    ContinuationInPart(Relation),
+    ContinuingReissue(Relation),
+    Reissue(Relation),
+    DivisionalReissue(Relation),
+    Reexamination(Relation),
+    ReexaminationReissueMerger(Relation),
+    Substitution(Relation),
+    UtilityModelBasis(Relation),
+    ProvisionalApplication(ProvisionalApplication),
+    RelatedPublication(DocumentId),
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Relation {
+    pub parent_doc: ParentDoc,
+    // one, except for us-divisional-reissue which can have several
+    pub child_docs: Vec<ChildDoc>,
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ParentDoc {
+    pub document_id: DocumentId,
+    pub parent_status: Option<String>,
+    pub parent_grant_document: Option<DocumentId>,
+    pub parent_pct_document: Option<DocumentId>,
+    pub international_filing_date: Option<String>,
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChildDoc {
+    pub document_id: DocumentId,
+    pub international_filing_date: Option<String>,
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ProvisionalApplication {
+    pub document_id: DocumentId,
+    pub status: Option<String>,
 }
 
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ClassificationLocarno {
     pub edition: String,
     pub main_classification: String,
 }
 
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ClassificationNational {
-    pub country: String,
-    pub additional_info: String,
-    pub main_classification: String,
-    pub further_classification: Option<String>,
+    pub country: CountryCode,
+    pub additional_info: Option<String>,
+    pub main_classification: Uspc,
+    pub further_classification: Option<Uspc>,
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct InventionTitle {
+    pub id: Option<String>,
+    pub lang: Option<String>,
+    pub title: String,
 }
 
-//#[derive(Debug, Default)]
-//pub struct InventionTitle {
-//    pub id: String,
-//    pub title: String,
-//}
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UsBotanic {
+    pub latin_name: String,
+    pub variety: String,
+}
 
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UsFieldOfClassificationSearch {
+    pub us_classifications_ipcr: Vec<String>,
     pub classification_nationals: Vec<ClassificationNational>,
     pub classification_cpc_texts: Vec<String>,
     pub classification_cpc_combination_texts: Vec<String>,
 }
 
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Figures {
+    pub number_of_drawing_sheets: Option<u32>,
+    pub number_of_figures: Option<u32>,
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UsApplicant {
     pub sequence: String,
     pub app_type: String,
@@ -93,6 +728,7 @@ pub struct UsApplicant {
 }
 
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct AddressBook {
     pub orgname: Option<String>,
     pub first_name: Option<String>,
@@ -102,13 +738,15 @@ pub struct AddressBook {
 }
 
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Address {
     pub city: Option<String>,
     pub state: Option<String>,
-    pub country: Option<String>,
+    pub country: Option<CountryCode>,
 }
 
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Inventor {
     pub sequence: String,
     pub designation: String,
@@ -116,6 +754,7 @@ pub struct Inventor {
 }
 
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Agent {
     pub sequence: String,
     pub rep_type: String,
@@ -123,6 +762,7 @@ pub struct Agent {
 }
 
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Assignee {
     pub orgname: Option<String>,
     pub role: Option<String>,
@@ -130,15 +770,136 @@ pub struct Assignee {
 }
 
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Examiners {
     pub primary_examiner: Examiner,
-    pub assistant_examiner: Examiner,
+    pub assistant_examiner: Option<Examiner>,
+}
+
+impl Examiners {
+    /// The art unit the patent was examined in. USPTO publishes it as the
+    /// primary examiner's department.
+    pub fn art_unit(&self) -> Option<&str> {
+        self.primary_examiner.department.as_deref()
+    }
 }
 
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Examiner {
     pub first_name: String,
     pub last_name: String,
+    pub middle_name: Option<String>,
+    pub suffix: Option<String>,
+    // the art unit, e.g. "3671"
     pub department: Option<String>,
 }
 
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PctOrRegionalFilingData {
+    pub document_id: DocumentId,
+    // 371(c)(1), (2), (4) date
+    pub us_371c124_date: Option<String>,
+    // 371(c)(1), (2) date
+    pub us_371c12_date: Option<String>,
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PctOrRegionalPublishingData {
+    pub document_id: DocumentId,
+    pub gazette_num: Option<String>,
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HagueAgreementData {
+    pub international_filing_date: String,
+    pub international_registration_publication_date: String,
+    pub international_registration_number: String,
+    pub international_registration_date: String,
+}
+
+/// A recorded assignment (`patent-assignment`, in ad*.xml assignment files):
+/// a transfer of, or security interest in, rights to patents and
+/// applications, recorded at a reel and frame.
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PatentAssignment {
+    pub assignment_record: AssignmentRecord,
+    pub assignors: Vec<Assignor>,
+    pub assignees: Vec<AssignmentAssignee>,
+    pub properties: Vec<PatentProperty>,
+}
+
+impl PatentAssignment {
+    /// e.g. "047866/0001", as the USPTO cites a recorded assignment
+    pub fn reel_frame(&self) -> String {
+        let record = &self.assignment_record;
+        format!("{:0>6}/{:0>4}", record.reel_no, record.frame_no)
+    }
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AssignmentRecord {
+    pub reel_no: String,
+    pub frame_no: String,
+    pub last_update_date: Date,
+    // "Y" if the assignment was removed from the record
+    pub purge_indicator: Option<String>,
+    pub recorded_date: Date,
+    pub page_count: Option<u32>,
+    pub correspondent: Correspondent,
+    // e.g. "ASSIGNMENT OF ASSIGNORS INTEREST (SEE DOCUMENT FOR DETAILS)."
+    pub conveyance_text: String,
+}
+
+/// Who the recorded assignment was sent back to
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Correspondent {
+    pub name: String,
+    // address-1 to address-4, those given
+    pub address: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Assignor {
+    pub name: String,
+    pub execution_date: Date,
+    pub date_acknowledged: Option<Date>,
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AssignmentAssignee {
+    pub name: String,
+    // address-1 and address-2, those given
+    pub address: Vec<String>,
+    pub city: Option<String>,
+    pub state: Option<String>,
+    // a name, e.g. "JAPAN", not a country code
+    pub country_name: Option<String>,
+    pub postcode: Option<String>,
+}
+
+/// A patent or application the assignment is recorded against
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PatentProperty {
+    // usually the application (kind X0), and the publication and grant
+    // once there are any
+    pub document_ids: Vec<DocumentId>,
+    pub invention_title: Option<String>,
+}
+
+impl PatentProperty {
+    /// The granted patent's id, if the property has been granted
+    pub fn grant(&self) -> Option<&DocumentId> {
+        self.document_ids.iter()
+            .find(|doc_id| doc_id.kind.as_ref().is_some_and(|kind| kind.doc_type().is_some()))
+    }
+}
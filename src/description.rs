@@ -0,0 +1,90 @@
+//! The description split into titled sections (each heading, and the
+//! paragraphs under it), from its raw xml, e.g. for NLP pipelines that want
+//! the background apart from the detailed description.
+
+use std::collections::HashMap;
+
+use crate::data::{PatentApplication, PatentGrant, Paragraph};
+use crate::deserialize::deser_raw_sections;
+use crate::error::Error;
+use crate::options::ParserOptions;
+
+/// PI names of the brief summary, in grants and in applications
+const SUMMARY: &[&str] = &["BRFSUM", "summary-of-invention"];
+/// PI names of the detailed description, in grants and in applications
+const DETAILED: &[&str] = &["DETDESC", "detailed-description"];
+
+/// A heading (None for the paragraphs before the first heading), and the
+/// paragraphs under it, with their ids
+pub type Section = (Option<String>, Vec<Paragraph>);
+
+/// A grant's (or application's) description sections, see
+/// `PatentGrant::description`
+#[derive(Debug, Clone, Copy)]
+pub struct Description<'a> {
+    descriptions_raw: &'a HashMap<String, String>,
+}
+
+impl<'a> Description<'a> {
+    /// Over raw description sections, as in `PatentGrant::descriptions_raw`
+    pub fn new(descriptions_raw: &'a HashMap<String, String>) -> Self {
+        Description { descriptions_raw }
+    }
+
+    /// The brief summary's sections, then the detailed description's
+    pub fn sections(&self) -> Result<Vec<Section>, Error> {
+        let mut sections = Vec::new();
+        for names in &[SUMMARY, DETAILED] {
+            for name in names.iter() {
+                sections.extend(self.sections_of(name)?);
+            }
+        }
+
+        Ok(sections)
+    }
+
+    /// As sections, for one description section by its PI name (e.g.
+    /// "RELAPP"); empty if the description hasn't got it
+    pub fn sections_of(&self, name: &str) -> Result<Vec<Section>, Error> {
+        match self.descriptions_raw.get(name) {
+            Some(raw) => deser_raw_sections(raw, &ParserOptions::default()),
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+impl PatentGrant {
+    pub fn description(&self) -> Description<'_> {
+        Description::new(&self.descriptions_raw)
+    }
+}
+
+impl PatentApplication {
+    pub fn description(&self) -> Description<'_> {
+        Description::new(&self.descriptions_raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::application::PatentApplications;
+    use crate::deserialize::PatentGrants;
+
+    #[test]
+    fn test_sections() {
+        let grant = PatentGrants::from_reader(include_str!("../tests/fixtures/grants.xml").as_bytes()).next().unwrap().unwrap();
+        let sections = grant.description().sections().unwrap();
+
+        let headings: Vec<_> = sections.iter().map(|(heading, _)| heading.as_deref()).collect();
+        assert_eq!(headings, vec![Some("BACKGROUND"), Some("SUMMARY"), Some("DETAILED DESCRIPTION"), Some("Test Results")]);
+        let ids: Vec<_> = sections[2].1.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["p-0009", "p-0010"]);
+        assert_eq!(sections[0].1[0].text, "Hand tools are known. See Muckle , U.S. Pat. No. 4,418,955.");
+
+        assert_eq!(grant.description().sections_of("RELAPP").unwrap()[0].0.as_deref(), Some("CROSS-REFERENCE TO RELATED APPLICATIONS"));
+        assert!(grant.description().sections_of("SEQLST").unwrap().is_empty());
+
+        let application = PatentApplications::from_reader(include_str!("../tests/fixtures/applications.xml").as_bytes()).next().unwrap().unwrap();
+        assert!(!application.description().sections().unwrap().is_empty());
+    }
+}
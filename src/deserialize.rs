@@ -1,39 +1,116 @@
 use quick_xml::{self, Reader};
-use quick_xml::events::{Event, BytesText};
-use snafu::OptionExt;
+use quick_xml::events::{Event, BytesStart, BytesText};
+use std::collections::HashMap;
 use std::io::BufRead;
 
 use crate::data::*;
-use crate::error::Error;
-use crate::error::Deser;
+use crate::error::{Error, WithinExt};
+use crate::patdoc::{deser_patdoc, deser_patdoc_meta};
+use crate::schema::{self, ElementNames};
 // helper macros
-use crate::{try_some, parse_struct_update, parse_struct_update_from};
-use crate::util::{consume_start, skip_to_tag_within};
+use crate::{parse_struct_update, parse_struct_update_from};
+use crate::options::{ParserOptions, Projection};
+use crate::util::{check_required, consume_start, decode_attribute, decode_owned, decode_text, read_text, skip_element, skip_to_tag_within, unknown_element, FromText, Scratch};
+
+/// With the `tracing` feature, a span for the section (element) being
+/// parsed, entered until the end of the block
+macro_rules! section_span {
+    ($($field:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _section = tracing::trace_span!("section", $($field)*).entered();
+    };
+}
 
 pub struct PatentGrants<B: BufRead> {
     rdr: quick_xml::Reader<B>,
     buf: Vec<u8>,
+    scratch: Scratch,
+    options: ParserOptions,
 }
 
 impl<B: BufRead> PatentGrants<B> {
     pub fn from_reader(b: B) -> Self {
-        let mut rdr = Reader::from_reader(b);
+        Self::with_options(b, ParserOptions::default())
+    }
 
-        // TODO check other options
-        rdr.trim_text(true);
+    /// See ParserOptions for what can be set, e.g.
+    /// `PatentGrants::with_options(rdr, ParserOptions::strict())`
+    pub fn with_options(b: B, options: ParserOptions) -> Self {
+        let mut rdr = Reader::from_reader(b);
+        rdr.trim_text(options.trim_text);
 
         PatentGrants {
             rdr,
             buf: Vec::new(),
+            scratch: Scratch::default(),
+            options,
+        }
+    }
+
+    /// In strict mode, each grant is also checked for consistency after it's parsed
+    /// (e.g. number-of-claims matches the claims found), and is an error if not.
+    /// See `ParserOptions::check_consistency`.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.options.check_consistency = strict;
+        self
+    }
+
+    /// Replaces the options given at construction; call before reading any
+    /// grants.
+    pub fn options(mut self, options: ParserOptions) -> Self {
+        self.rdr.trim_text(options.trim_text);
+        self.options = options;
+        self
+    }
+
+    /// Yields only each grant's meta, biblio and abstract, skipping over
+    /// the claims and description without parsing them. Much faster, and
+    /// lighter, when only the metadata is needed. (2002 to 2004 grants are
+    /// still parsed whole.)
+    pub fn biblio_only(self) -> PatentGrantBiblios<B> {
+        PatentGrantBiblios {
+            grants: self,
         }
     }
 
+    /// Yields only the grants whose publication-reference (doc-number, kind
+    /// and date) keep returns true for. The publication-reference is the
+    /// first element of the biblio, so it's peeked at before anything else is
+    /// parsed, and a grant that isn't kept is skipped to its end tag without
+    /// decoding any of it. (2002 to 2004 grants are parsed whole, then kept
+    /// or not.)
+    pub fn filter_raw<F: FnMut(&DocumentId) -> bool>(self, keep: F) -> FilteredPatentGrants<B, F> {
+        FilteredPatentGrants {
+            grants: self,
+            keep,
+        }
+    }
+
+    pub(crate) fn parser_options(&self) -> &ParserOptions {
+        &self.options
+    }
+
+    pub(crate) fn into_reader(self) -> B {
+        self.rdr.into_underlying_reader()
+    }
+
+    /// Reads the next grant into patent_grant, reusing its allocations (see
+    /// `PatentGrant::clear`) instead of returning a new one; for reading many
+    /// grants one after another with less allocation. None if there are no
+    /// more, when patent_grant is left as is.
+    pub fn next_into(&mut self, patent_grant: &mut PatentGrant) -> Option<Result<(), Error>> {
+        let res = self.deser_patent_grant(patent_grant, self.options.projection, None);
+        self.buf.clear();
+
+        res.map(|res| res.map(|_| ()))
+    }
+
     /// main entry point for deserialization
     ///
     /// returns None if no more data
     /// else if there's an error in deser (e.g. partial data)
-    /// return Some(Result<_>)
-    fn deser_patent_grant(&mut self) -> Option<Result<PatentGrant, Error>> {
+    /// return Some(Result<_>), with whether keep (if given) kept the grant
+    fn deser_patent_grant(&mut self, patent_grant: &mut PatentGrant, projection: Projection, keep: Option<&mut dyn FnMut(&DocumentId) -> bool>) -> Option<Result<bool, Error>> {
         // first skip through headers
         let hdr = deser_header(&mut self.rdr, &mut self.buf);
         match hdr {
@@ -47,24 +124,91 @@ impl<B: BufRead> PatentGrants<B> {
         self.buf.clear();
 
         // if headers are in the right place, we can continue
-        let mut patent_grant = PatentGrant::default();
+        patent_grant.clear();
+        #[cfg(feature = "tracing")]
+        let document = tracing::debug_span!("document", doc_number = tracing::field::Empty, position = self.rdr.buffer_position()).entered();
+
+        let res = self.deser_patent_grant_into(patent_grant, projection, keep);
+        let doc_number = &patent_grant.us_bibliographic_data_grant.publication_reference.doc_number;
+        #[cfg(feature = "tracing")]
+        document.record("doc_number", doc_number.as_str());
+
+        match res {
+            Ok(kept) => Some(Ok(kept)),
+            Err(err) => {
+                let err = err.at_position(self.rdr.buffer_position(), doc_number);
+                #[cfg(feature = "tracing")]
+                tracing::warn!(error = %err, "failed to parse document");
+                Some(Err(err))
+            },
+        }
+    }
+
+    /// the document after its headers, only the sections in projection. On
+    /// error, patent_grant keeps what was parsed so far, to locate the error
+    /// with. False if keep didn't keep it, when the rest of the document is
+    /// skipped.
+    fn deser_patent_grant_into(&mut self, patent_grant: &mut PatentGrant, projection: Projection, mut keep: Option<&mut dyn FnMut(&DocumentId) -> bool>) -> Result<bool, Error> {
+        // until the root element gives the dtd-version
+        let mut names = schema::element_names(None);
+        // whether keep has been asked yet
+        let mut peeked = false;
 
         // deser for each element, update default patent grant
         loop {
             match self.rdr.read_event(&mut self.buf) {
                 Ok(Event::PI(pi_bytes)) => {
-                    try_some!(deser_top_pi(pi_bytes, &mut self.rdr, &mut patent_grant));
+                    deser_top_pi(pi_bytes, &mut self.rdr, &mut self.scratch, &self.options, &mut patent_grant.descriptions, &mut patent_grant.descriptions_raw)?;
                 },
                 Ok(Event::Start(ref e)) => {
                     match e.name() {
+                        b"us-patent-grant" => {
+                            deser_grant_meta(e, &self.rdr, &self.options, &mut patent_grant.meta).within(b"us-patent-grant")?;
+                            names = schema::element_names(patent_grant.meta.dtd_version.as_deref());
+                        },
+                        // sections not in the projection: straight to the
+                        // end tag, without decoding anything within
+                        name @ (b"claims" | b"us-claim-statement") if !projection.contains(Projection::CLAIMS) => {
+                            skip_element(name, &mut self.rdr, &mut self.scratch)?;
+                        },
+                        b"description" if !projection.contains(Projection::DESCRIPTION) => {
+                            skip_element(b"description", &mut self.rdr, &mut self.scratch)?;
+                        },
+                        b"abstract" if !projection.contains(Projection::ABSTRACT) => {
+                            skip_element(b"abstract", &mut self.rdr, &mut self.scratch)?;
+                        },
                         b"us-claim-statement" => {
-                            patent_grant.us_claim_statement = try_some!(deser_text_from(e.name(), &mut self.rdr));
+                            section_span!(element = "us-claim-statement");
+                            patent_grant.us_claim_statement = deser_text_from(e.name(), &mut self.rdr, &mut self.scratch, &self.options).within(b"us-claim-statement")?;
                         },
                         b"claims" => {
-                            try_some!(deser_claims(&mut self.rdr, &mut self.buf, &mut patent_grant));
+                            section_span!(element = "claims");
+                            deser_claims(&mut self.rdr, &mut self.buf, &mut self.scratch, &self.options, &mut patent_grant.claims).within(b"claims")?;
                         },
                         b"us-bibliographic-data-grant" => {
-                            try_some!(deser_biblio(&mut self.rdr, &mut self.buf, &mut patent_grant.us_bibliographic_data_grant));
+                            section_span!(element = "us-bibliographic-data-grant");
+                            if let Some(keep) = keep.as_mut() {
+                                let publication_reference = &mut patent_grant.us_bibliographic_data_grant.publication_reference;
+                                deser_publication_reference(&mut self.rdr, &mut self.buf, &mut self.scratch, &self.options, publication_reference).within(b"us-bibliographic-data-grant")?;
+                                peeked = true;
+
+                                if !keep(publication_reference) {
+                                    skip_element(b"us-patent-grant", &mut self.rdr, &mut self.scratch)?;
+                                    return Ok(false);
+                                }
+                            }
+                            deser_biblio(&mut self.rdr, &mut self.buf, &mut self.scratch, &self.options, names, &mut patent_grant.us_bibliographic_data_grant).within(b"us-bibliographic-data-grant")?;
+                        },
+                        b"abstract" => {
+                            section_span!(element = "abstract");
+                            deser_abstract(&mut self.rdr, &mut self.buf, &mut self.scratch, &self.options, &mut patent_grant.r#abstract).within(b"abstract")?;
+                        },
+                        // 2002 to 2004 grants
+                        b"PATDOC" => {
+                            section_span!(element = "PATDOC");
+                            deser_patdoc_meta(e, &self.rdr, &self.options, &mut patent_grant.meta).within(b"PATDOC")?;
+                            deser_patdoc(&mut self.rdr, &mut self.buf, &self.options, patent_grant).within(b"PATDOC")?;
+                            break;
                         },
                         _ => continue,
                     }
@@ -78,13 +222,28 @@ impl<B: BufRead> PatentGrants<B> {
                     }
                 },
                 Ok(_) => continue,
-                Err(err) => return Some(Err(Error::Deser { src: err.to_string() })),
+                Err(err) => return Err(Error::deser(err.to_string())),
             };
         }
 
         self.buf.clear();
 
-        Some(Ok(patent_grant))
+        // e.g. PATDOC, which has no us-bibliographic-data-grant to peek in
+        if let Some(keep) = keep {
+            if !peeked && !keep(&patent_grant.us_bibliographic_data_grant.publication_reference) {
+                return Ok(false);
+            }
+        }
+
+        patent_grant.government_interest = deser_government_interest(&patent_grant.descriptions_raw, &self.options)?;
+        patent_grant.chemistry = deser_description_chemistry(&patent_grant.descriptions_raw, &self.options)?;
+        patent_grant.sequence_listing = deser_sequence_listing(&mut patent_grant.descriptions, &mut patent_grant.descriptions_raw, &self.options)?;
+
+        if self.options.check_consistency && projection.contains(Projection::CLAIMS) {
+            check_claims(patent_grant)?;
+        }
+
+        Ok(true)
     }
 }
 
@@ -92,38 +251,155 @@ impl<B: BufRead> Iterator for PatentGrants<B> {
     type Item = Result<PatentGrant, Error>;
 
     // clear buf after each PatentGrant;
-    // in the future, when GAT lands,
-    // the iterator will be able to borrow
-    // the underlying data.
+    // see borrowed::PatentGrantRefs for
+    // an iterator that borrows the
+    // underlying data.
     fn next(&mut self) -> Option<Self::Item> {
-        let res = self.deser_patent_grant();
-        self.buf.clear();
+        let mut patent_grant = PatentGrant::default();
+
+        self.next_into(&mut patent_grant)
+            .map(|res| res.map(|()| patent_grant))
+    }
+}
+
+/// Iterator over the biblio of each grant, see `PatentGrants::biblio_only`
+pub struct PatentGrantBiblios<B: BufRead> {
+    grants: PatentGrants<B>,
+}
 
-        res
+impl<B: BufRead> Iterator for PatentGrantBiblios<B> {
+    type Item = Result<PatentGrantBiblio, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let projection = self.grants.options.projection.without(Projection::CLAIMS | Projection::DESCRIPTION);
+        let mut patent_grant = PatentGrant::default();
+        let res = self.grants.deser_patent_grant(&mut patent_grant, projection, None);
+        self.grants.buf.clear();
+
+        res.map(|patent_grant_res| patent_grant_res.map(|_| PatentGrantBiblio {
+            meta: patent_grant.meta,
+            us_bibliographic_data_grant: patent_grant.us_bibliographic_data_grant,
+            r#abstract: patent_grant.r#abstract,
+        }))
+    }
+}
+
+/// Iterator over the grants a predicate keeps, see `PatentGrants::filter_raw`
+pub struct FilteredPatentGrants<B: BufRead, F> {
+    grants: PatentGrants<B>,
+    keep: F,
+}
+
+impl<B: BufRead, F: FnMut(&DocumentId) -> bool> Iterator for FilteredPatentGrants<B, F> {
+    type Item = Result<PatentGrant, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let projection = self.grants.options.projection;
+        let mut patent_grant = PatentGrant::default();
+
+        loop {
+            let res = self.grants.deser_patent_grant(&mut patent_grant, projection, Some(&mut self.keep));
+            self.grants.buf.clear();
+
+            match res? {
+                Ok(true) => return Some(Ok(patent_grant)),
+                Ok(false) => continue,
+                Err(err) => return Some(Err(err)),
+            }
+        }
     }
 }
 
 // helper fns for deser
 // never clear buffer inside fn!
 
+/// strict mode check of number-of-claims and us-exemplary-claim against
+/// the parsed claims
+fn check_claims(patent_grant: &PatentGrant) -> Result<(), Error> {
+    let biblio = &patent_grant.us_bibliographic_data_grant;
+    let doc_number = &biblio.publication_reference.doc_number;
+    let claims_len = patent_grant.claims.len() as u32;
+
+    if let Some(number_of_claims) = biblio.number_of_claims {
+        if number_of_claims != claims_len {
+            return Err(Error::deser(format!("number-of-claims is {} but found {} claims in {}", number_of_claims, claims_len, doc_number)));
+        }
+    }
+
+    for &exemplary_claim in &biblio.us_exemplary_claim {
+        if exemplary_claim == 0 || exemplary_claim > claims_len {
+            return Err(Error::deser(format!("us-exemplary-claim {} not found in {} claims in {}", exemplary_claim, claims_len, doc_number)));
+        }
+    }
+
+    Ok(())
+}
+
 /// only returns None if there's no input. Otherwise
 /// tries to parse, and will error if necessary.
-fn deser_header<B: BufRead>(rdr: &mut quick_xml::Reader<B>, buf: &mut Vec<u8>) -> Option<Result<(), Error>> {
+pub(crate) fn deser_header<B: BufRead>(rdr: &mut quick_xml::Reader<B>, buf: &mut Vec<u8>) -> Option<Result<(), Error>> {
     // first match xml declaration
     match rdr.read_event(buf) {
         Ok(Event::Decl(_)) => (),
         Ok(Event::Eof) => return None,
-        Ok(_) => return Some(Err(Error::Deser { src: "xml decl not found at head of patent grant xml".to_owned() })),
-        Err(err) => return Some(Err(Error::Deser { src: err.to_string() })),
+        Ok(_) => return Some(Err(Error::deser("xml decl not found at head of patent grant xml".to_owned()))),
+        Err(err) => return Some(Err(Error::deser(err.to_string()))),
     }
 
-    // then match doctype declaration
-    match rdr.read_event(buf) {
-        Ok(Event::DocType(_)) => Some(Ok(())),
-        Ok(Event::Eof) => None,
-        Ok(_) => Some(Err(Error::Deser { src: "doctype decl not found at head of patent grant xml".to_owned() })),
-        Err(err) => Some(Err(Error::Deser { src: err.to_string() })),
+    // then match doctype declaration, past any processing instructions
+    // (e.g. a stylesheet) or comments
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::DocType(_)) => return Some(Ok(())),
+            Ok(Event::PI(_)) | Ok(Event::Comment(_)) => (),
+            Ok(Event::Text(ref e)) if e.iter().all(u8::is_ascii_whitespace) => (),
+            Ok(Event::Eof) => return None,
+            Ok(_) => return Some(Err(Error::deser("doctype decl not found at head of patent grant xml".to_owned()))),
+            Err(err) => return Some(Err(Error::deser(err.to_string()))),
+        }
+    }
+}
+
+/// pub struct GrantMeta {
+///     pub lang: String,
+///     pub dtd_version: Option<String>,
+///     pub file: Option<String>,
+///     pub status: Option<String>,
+///     pub id: Option<String>,
+///     pub country: String,
+///     pub file_reference_id: Option<String>,
+///     pub date_produced: Option<String>,
+///     pub date_publ: Option<String>,
+/// }
+///
+/// call with the us-patent-grant (or us-patent-application) start tag; only
+/// reads attributes
+pub(crate) fn deser_grant_meta<B: BufRead>(
+    start: &BytesStart,
+    rdr: &quick_xml::Reader<B>,
+    opts: &ParserOptions,
+    meta: &mut GrantMeta,
+    ) -> Result<(), Error>
+{
+    for attr_res in start.attributes() {
+        let attr = attr_res
+            .map_err(|err| Error::deser(err.to_string()))?;
+
+        match attr.key {
+            b"lang" => meta.lang = decode_attribute(&attr, rdr, opts)?,
+            b"dtd-version" => meta.dtd_version = Some(decode_attribute(&attr, rdr, opts)?),
+            b"file" => meta.file = Some(decode_attribute(&attr, rdr, opts)?),
+            b"status" => meta.status = Some(decode_attribute(&attr, rdr, opts)?),
+            b"id" => meta.id = Some(decode_attribute(&attr, rdr, opts)?),
+            b"country" => meta.country = decode_attribute(&attr, rdr, opts)?,
+            b"file-reference-id" => meta.file_reference_id = Some(decode_attribute(&attr, rdr, opts)?),
+            b"date-produced" => meta.date_produced = Some(decode_attribute(&attr, rdr, opts)?),
+            b"date-publ" => meta.date_publ = Some(decode_attribute(&attr, rdr, opts)?),
+            _ => return Err(Error::deser(format!("unrecognized attr in {}", String::from_utf8_lossy(start.name())))),
+        }
     }
+
+    Ok(())
 }
 
 /// top level program instruction handling.
@@ -140,77 +416,356 @@ fn deser_header<B: BufRead>(rdr: &mut quick_xml::Reader<B>, buf: &mut Vec<u8>) -
 /// new buffer cvan then be converted directly to a string.
 ///
 /// One downside of this string conversion: tags are lost (i guess quick-xml didn't think it needed
-/// to save them)
-fn deser_top_pi<B: BufRead>(
+/// to save them). So the inner xml of each section is also written back out as-is to
+/// `descriptions_raw`, for anything that needs paragraphs, headings, figrefs, tables, etc.
+pub(crate) fn deser_top_pi<B: BufRead>(
     pi_bytes: BytesText,
     rdr: &mut quick_xml::Reader<B>,
-    patent_grant: &mut PatentGrant
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    descriptions: &mut HashMap<String, String>,
+    descriptions_raw: &mut HashMap<String, String>,
     ) -> Result<(), Error>
 {
-    let pi_name_res = pi_bytes.unescape_and_decode(&rdr);
+    if let Some((name, text, raw)) = deser_description_section(pi_bytes, rdr, scratch, opts)? {
+        descriptions.insert(name.clone(), text);
+        descriptions_raw.insert(name, raw);
+    }
+
+    Ok(())
+}
+
+/// As deser_top_pi: the description section a PI starts, as its name, text
+/// and raw xml. None for any other PI.
+pub(crate) fn deser_description_section<B: BufRead>(
+    pi_bytes: BytesText,
+    rdr: &mut quick_xml::Reader<B>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    ) -> Result<Option<(String, String, String)>, Error>
+{
+    let pi_name_res = pi_bytes.unescape_and_decode(rdr);
     let pi_name = match pi_name_res {
-        Ok(ref s) => s.split_whitespace().nth(0).context(Deser { src: "No name for PI".to_string() })?,
-        Err(_) => return Err(Error::Deser { src: "No name for PI".into() }),
+        Ok(ref s) => s.split_whitespace().next().ok_or_else(|| Error::deser("No name for PI".to_string()))?,
+        Err(_) => return Err(Error::deser("No name for PI".into())),
     };
 
     let end = match pi_name_res {
-        Ok(ref s) => s.split_whitespace().last().context(Deser { src: "No end for PI".to_string() })?,
-        Err(_) => return Err(Error::Deser { src: "No end for PI".into() }),
+        Ok(ref s) => s.split_whitespace().last().ok_or_else(|| Error::deser("No end for PI".to_string()))?,
+        Err(_) => return Err(Error::deser("No end for PI".into())),
     };
 
     if end != "end=\"lead\"" {
         // just skip if not lead; it means it's some other top level PI
-        return Ok(());
+        return Ok(None);
     }
 
+    section_span!(element = "description", section = pi_name);
     // get end byte of PI.
     // find beginning byte of next PI.
     // get string in between
-    let text = deser_pi_text_with_tags_to_tail_from(rdr)?;
-    patent_grant.descriptions.insert(pi_name.to_string(), text);
+    let (text, raw) = deser_pi_text_with_tags_to_tail_from(pi_name, rdr, scratch, opts)?;
+
+    Ok(Some((pi_name.to_string(), text, raw)))
+}
+
+/// Deserializes the abstract's paragraphs, including those within
+/// abst-problem and abst-solution.
+///
+/// called after tag abstract is already hit
+pub(crate) fn deser_abstract<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    paragraphs: &mut Vec<Paragraph>,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"p" => {
+                        let mut paragraph = Paragraph::default();
+                        deser_paragraph_from(e, rdr, scratch, opts, &mut paragraph).within(b"p")?;
+                        paragraphs.push(paragraph);
+                    },
+                    b"abst-problem" | b"abst-solution" => continue,
+                    _ => unknown_element(e.name(), "abstract", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(e)) => {
+                if e.name() == "abstract".as_bytes() {
+                    break;
+                } else {
+                    continue;
+                }
+            },
+            // doc-page (abstract as image only)
+            Ok(Event::Empty(_)) => continue,
+            Ok(_) => return Err(Error::deser("found non-start-element besides abstract".to_string())),
+
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    Ok(())
+}
+
+/// pub struct Paragraph {
+///     pub id: String,
+///     pub num: String,
+///     pub text: String,
+///     pub formulae: Vec<Formula>,
+///     pub figrefs: Vec<FigRef>,
+/// }
+///
+/// call with the p start tag, after it's already hit
+fn deser_paragraph_from<B: BufRead>(
+    start: &BytesStart,
+    rdr: &mut quick_xml::Reader<B>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    paragraph: &mut Paragraph,
+    ) -> Result<(), Error>
+{
+    for attr_res in start.attributes() {
+        let attr = attr_res
+            .map_err(|err| Error::deser(err.to_string()))?;
+
+        match attr.key {
+            b"id" => paragraph.id = decode_attribute(&attr, rdr, opts)?,
+            b"num" => paragraph.num = decode_attribute(&attr, rdr, opts)?,
+            _ => return Err(Error::deser("unrecognized attr in p".to_string())),
+        }
+    }
+
+    let inline = Inline {
+        formulae: &mut paragraph.formulae,
+        figrefs: &mut paragraph.figrefs,
+    };
+    paragraph.text = deser_text_and_inline_from(b"p", rdr, scratch, opts, Some(inline))?;
 
     Ok(())
 }
 
-fn deser_claims<B: BufRead>(
+/// Deserializes a Vec of Claim
+///
+/// called after tag claims is already hit
+pub(crate) fn deser_claims<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    claims: &mut Vec<Claim>,
+    ) -> Result<(), Error>
+{
+    deser_claims_each(rdr, buf, scratch, opts, |claim| claims.push(claim))
+}
+
+/// As deser_claims, passing each claim to on_claim instead of keeping them
+pub(crate) fn deser_claims_each<B: BufRead, F: FnMut(Claim)>(
     rdr: &mut quick_xml::Reader<B>,
     buf: &mut Vec<u8>,
-    patent_grant: &mut PatentGrant
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    mut on_claim: F,
     ) -> Result<(), Error>
 {
     loop {
         match rdr.read_event(buf) {
             Ok(Event::Start(ref e)) => {
-                if e.name() == b"claim" {
-                    loop {
-                        match rdr.read_event(buf) {
-                            Ok(Event::Start(ref e)) => {
-                                if e.name() == b"claim-text" {
-                                    patent_grant.claims.push(deser_text_with_tags_from(e.name(), rdr)?);
-                                } else {
-                                    break;
-                                }
-                            },
-                            Ok(_) => break,
-                            Err(err) => return Err(Error::Deser { src: err.to_string() }),
+                match e.name() {
+                    b"claim" => {
+                        let mut claim = Claim::default();
+
+                        // first update attributes
+                        for attr_res in e.attributes() {
+                            let attr = attr_res
+                                .map_err(|err| Error::deser(err.to_string()))?;
+
+                            match attr.key {
+                                b"id" => claim.id = decode_attribute(&attr, rdr, opts)?,
+                                b"num" => claim.num = decode_attribute(&attr, rdr, opts)?,
+                                b"claim-type" => claim.claim_type = Some(decode_attribute(&attr, rdr, opts)?),
+                                _ => return Err(Error::deser("unrecognized attr in claim".to_string())),
+                            }
                         }
-                    }
+
+                        deser_claim(rdr, buf, scratch, opts, &mut claim).within(b"claim")?;
+
+                        on_claim(claim);
+                    },
+                    _ => unknown_element(e.name(), "claims", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(e)) => {
+                if e.name() == "claims".as_bytes() {
+                    break;
+                } else {
+                    continue;
+                }
+            },
+            Ok(_) => return Err(Error::deser("found non-start-element besides claims".to_string())),
+
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    Ok(())
+}
+
+/// pub struct Claim {
+///     pub id: String,
+///     pub num: String,
+///     pub claim_type: Option<String>,
+///     pub claim_texts: Vec<ClaimText>,
+/// }
+///
+/// called after tag claim is already hit
+fn deser_claim<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    claim: &mut Claim,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"claim-text" => {
+                        let mut claim_text = ClaimText::default();
+                        deser_claim_text(rdr, buf, scratch, opts, &mut claim_text).within(b"claim-text")?;
+                        claim.claim_texts.push(claim_text);
+                    },
+                    _ => unknown_element(e.name(), "claim", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(e)) => {
+                if e.name() == "claim".as_bytes() {
+                    break;
+                } else {
+                    continue;
+                }
+            },
+            Ok(_) => return Err(Error::deser("found non-start-element besides claim".to_string())),
+
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    Ok(())
+}
+
+/// pub struct ClaimText {
+///     pub text: String,
+///     pub claim_refs: Vec<ClaimRef>,
+///     pub formulae: Vec<Formula>,
+///     pub chemistry: Vec<Chemistry>,
+///     pub figrefs: Vec<FigRef>,
+///     pub claim_texts: Vec<ClaimText>,
+/// }
+///
+/// Recurses on nested claim-text. Text inside inline markup (b, i, figref, etc.)
+/// is kept as part of the segment's text, as is the text of a claim-ref.
+///
+/// called after tag claim-text is already hit
+fn deser_claim_text<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    claim_text: &mut ClaimText,
+    ) -> Result<(), Error>
+{
+    // fragments are joined by a space as they're read
+    let mut text = String::new();
+    let mut frags = 0;
+
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"claim-text" => {
+                        let mut nested = ClaimText::default();
+                        deser_claim_text(rdr, buf, scratch, opts, &mut nested).within(b"claim-text")?;
+                        claim_text.claim_texts.push(nested);
+                    },
+                    b"claim-ref" => {
+                        let mut claim_ref = ClaimRef::default();
+
+                        for attr_res in e.attributes() {
+                            let attr = attr_res
+                                .map_err(|err| Error::deser(err.to_string()))?;
+
+                            match attr.key {
+                                b"idref" => claim_ref.idref = decode_attribute(&attr, rdr, opts)?,
+                                _ => return Err(Error::deser("unrecognized attr in claim-ref".to_string())),
+                            }
+                        }
+
+                        claim_ref.text = deser_text_with_tags_from(b"claim-ref", rdr, scratch, opts).within(b"claim-ref")?;
+                        push_frag(&mut text, &mut frags, &claim_ref.text);
+
+                        claim_text.claim_refs.push(claim_ref);
+                    },
+                    b"figref" => {
+                        let figref = deser_figref_from(e, rdr, scratch, opts).within(b"figref")?;
+                        if !figref.text.is_empty() {
+                            push_frag(&mut text, &mut frags, &figref.text);
+                        }
+
+                        claim_text.figrefs.push(figref);
+                    },
+                    b"chemistry" => {
+                        let chemistry = deser_chemistry_from(e, rdr, scratch, opts).within(b"chemistry")?;
+                        claim_text.chemistry.push(chemistry);
+                    },
+                    b"maths" if opts.formulae => {
+                        let (formula, frag) = deser_maths_from(e, rdr, scratch, opts).within(b"maths")?;
+                        if !frag.is_empty() {
+                            push_frag(&mut text, &mut frags, &frag);
+                        }
+
+                        claim_text.formulae.push(formula);
+                    },
+                    // inline markup; its text is picked up below
+                    _ => continue,
+                }
+            },
+            Ok(Event::Text(e)) => {
+                let frag = decode_text(&e, rdr, opts)?;
+
+                push_frag(&mut text, &mut frags, &frag);
+            },
+            Ok(Event::End(e)) => {
+                if e.name() == "claim-text".as_bytes() {
+                    break;
                 } else {
-                    break; // if no claims, exit
+                    continue;
                 }
             },
-            Ok(_) => break, // if there's no more claims, exit
-            Err(err) => return Err(Error::Deser { src: err.to_string() }),
+            Ok(Event::Eof) => return Err(Error::deser("unexpected eof in claim-text".to_string())),
+            Ok(_) => continue,
+
+            Err(err) => return Err(Error::deser(err.to_string())),
         }
     }
 
+    claim_text.text = text;
+
     Ok(())
 }
 
 /// call after you hit biblio tag
-fn deser_biblio<B: BufRead>(
+pub(crate) fn deser_biblio<B: BufRead>(
     rdr: &mut quick_xml::Reader<B>,
     buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    names: &ElementNames,
     biblio: &mut BibliographicDataGrant,
     ) -> Result<(), Error>
 {
@@ -218,66 +773,94 @@ fn deser_biblio<B: BufRead>(
         match rdr.read_event(buf) {
             Ok(Event::Start(ref e)) => {
                 match e.name() {
+                    // not in the projection
+                    name if !opts.projection.contains(biblio_section(name, names)) => skip_element(name, rdr, scratch)?,
                     b"publication-reference" => {
-                        deser_doc_id(rdr, buf, &mut biblio.publication_reference)?;
+                        deser_doc_id(rdr, buf, scratch, opts, &mut biblio.publication_reference).within(b"publication-reference")?;
                     },
                     b"application-reference" => {
-                        deser_doc_id(rdr, buf, &mut biblio.application_reference)?;
+                        deser_doc_id(rdr, buf, scratch, opts, &mut biblio.application_reference).within(b"application-reference")?;
                     },
                     b"us-application-series-code" => {
-                        biblio.us_application_series_code = deser_text_from(e.name(), rdr)?;
+                        biblio.us_application_series_code = deser_text_from(e.name(), rdr, scratch, opts).within(b"us-application-series-code")?;
+                    },
+                    b"priority-claims" => {
+                        deser_priority_claims(rdr, buf, scratch, opts, &mut biblio.priority_claims).within(b"priority-claims")?;
                     },
                     b"us-term-of-grant" => {
-                        let within = skip_to_tag_within(b"length-of-grant", b"us-term-of-grant", rdr, buf)?;
-                        if within {
-                            biblio.us_term_of_grant = deser_text_from(b"length-of-grant", rdr)?;
-                        }
+                        deser_us_term_of_grant(rdr, buf, scratch, opts, &mut biblio.us_term_of_grant).within(b"us-term-of-grant")?;
                     },
                     b"classifications-ipcr" => {
-                        // TODO skip for now
-                        skip_to_tag_within(b"", b"classifications-ipcr", rdr, buf)?;
+                        deser_classifications_ipcr(rdr, buf, scratch, opts, &mut biblio.classifications_ipcr).within(b"classifications-ipcr")?;
                     },
                     b"classifications-cpc" => {
-                        // TODO skip for now
-                        skip_to_tag_within(b"", b"classifications-cpc", rdr, buf)?;
+                        deser_classifications_cpc(rdr, buf, scratch, opts, &mut biblio.classifications_cpc).within(b"classifications-cpc")?;
                     },
                     b"classification-locarno" => {
-                        deser_class_locarno(rdr, buf, &mut biblio.classification_locarno)?;
+                        deser_class_locarno(rdr, buf, scratch, opts, &mut biblio.classification_locarno).within(b"classification-locarno")?;
                     },
                     b"classification-national" => {
-                        deser_class_national(rdr, buf, &mut biblio.classification_national)?;
+                        deser_class_national(rdr, buf, scratch, opts, &mut biblio.classification_national).within(b"classification-national")?;
                     },
                     b"invention-title" => {
-                        biblio.invention_title = deser_text_with_tags_from(e.name(), rdr)?;
+                        deser_invention_title(e, rdr, scratch, opts, &mut biblio.invention_title).within(b"invention-title")?;
+                    },
+                    b"us-botanic" => {
+                        let mut botanic = UsBotanic::default();
+                        deser_us_botanic(rdr, buf, scratch, opts, &mut botanic).within(b"us-botanic")?;
+                        biblio.botanic = Some(botanic);
                     },
                     b"number-of-claims" => {
-                        biblio.number_of_claims = deser_text_from(e.name(), rdr)?;
+                        biblio.number_of_claims = Some(deser_u32_from(e.name(), rdr, scratch, opts)?);
                     },
                     b"us-exemplary-claim" => {
-                        biblio.us_exemplary_claim = deser_text_from(e.name(), rdr)?;
+                        biblio.us_exemplary_claim.push(deser_u32_from(e.name(), rdr, scratch, opts)?);
+                    },
+                    name if name == names.field_of_search => {
+                        deser_field_class_search(rdr, buf, scratch, opts, names, &mut biblio.us_field_of_classification_search).within(names.field_of_search)?;
+                    },
+                    name if name == names.references_cited => {
+                        deser_us_references_cited(rdr, buf, scratch, opts, names, &mut biblio.us_references_cited).within(names.references_cited)?;
                     },
-                    b"us-field-of-classification-search" => {
-                        deser_field_class_search(rdr, buf, &mut biblio.us_field_of_classification_search)?;
+                    b"figures" => {
+                        deser_figures(rdr, buf, scratch, opts, &mut biblio.figures).within(b"figures")?;
                     },
-                    b"us-applicants" => {
-                        deser_us_applicants(rdr, buf, &mut biblio.us_applicants)?;
+                    b"us-related-documents" => {
+                        deser_us_related_documents(rdr, buf, scratch, opts, &mut biblio.us_related_documents).within(b"us-related-documents")?;
+                    },
+                    name if name == names.applicants => {
+                        deser_us_applicants(rdr, buf, scratch, opts, names, &mut biblio.us_applicants).within(names.applicants)?;
                     },
                     b"inventors" => {
-                        deser_inventors(rdr, buf, &mut biblio.inventors)?;
+                        deser_inventors(rdr, buf, scratch, opts, &mut biblio.inventors).within(b"inventors")?;
                     },
                     b"agents" => {
-                        deser_agents(rdr, buf, &mut biblio.agents)?;
+                        deser_agents(rdr, buf, scratch, opts, &mut biblio.agents).within(b"agents")?;
                     },
                     b"assignees" => {
-                        deser_assignees(rdr, buf, &mut biblio.assignees)?;
+                        deser_assignees(rdr, buf, scratch, opts, &mut biblio.assignees).within(b"assignees")?;
                     },
                     b"examiners" => {
-                        deser_examiners(rdr, buf, &mut biblio.examiners)?;
+                        deser_examiners(rdr, buf, scratch, opts, &mut biblio.examiners).within(b"examiners")?;
                     },
-
-                    // TODO when all elements in, use this line instead
-                    //_ => break,
-                    _ => continue,
+                    b"pct-or-regional-filing-data" => {
+                        let mut filing_data = PctOrRegionalFilingData::default();
+                        deser_pct_filing_data(rdr, buf, scratch, opts, &mut filing_data).within(b"pct-or-regional-filing-data")?;
+                        biblio.pct_or_regional_filing_data = Some(filing_data);
+                    },
+                    b"pct-or-regional-publishing-data" => {
+                        let mut publishing_data = PctOrRegionalPublishingData::default();
+                        deser_pct_publishing_data(rdr, buf, scratch, opts, &mut publishing_data).within(b"pct-or-regional-publishing-data")?;
+                        biblio.pct_or_regional_publishing_data = Some(publishing_data);
+                    },
+                    b"hague-agreement-data" => {
+                        let mut hague = HagueAgreementData::default();
+                        deser_hague_agreement_data(rdr, buf, scratch, opts, &mut hague).within(b"hague-agreement-data")?;
+                        biblio.hague_agreement_data = Some(hague);
+                    },
+                    // wrappers; their elements are matched above
+                    b"us-parties" | b"parties" => continue,
+                    _ => unknown_element(e.name(), "us-bibliographic-data-grant", rdr, scratch, opts)?,
                 }
             },
             Ok(Event::End(ref e)) => {
@@ -286,683 +869,2894 @@ fn deser_biblio<B: BufRead>(
                 }
             },
             // TODO when all elements in, use this line instead
-            // Ok(_) => return Err(Error::Deser { src: "found non-start-element not in biblio".to_string() }),
+            // Ok(_) => return Err(Error::deser("found non-start-element not in biblio".to_string())),
             // for now, can just break out of biblio loop
             Ok(_) => continue,
-            Err(err) => return Err(Error::Deser { src: err.to_string() }),
+            Err(err) => return Err(Error::deser(err.to_string())),
         };
     }
 
     Ok(())
 }
 
-/// pub struct DocumentId {
-///     pub country: String,
-///     pub doc_number: String,
-///     pub kind: Option<String>,
-///     pub date: String,
+/// Which Projection section an element in the biblio (of a grant or an
+/// application) belongs to. The publication-reference, and the wrappers of
+/// parties, are in all of them.
+pub(crate) fn biblio_section(name: &[u8], names: &ElementNames) -> Projection {
+    match name {
+        b"publication-reference" | b"us-parties" | b"parties" => Projection::NONE,
+        b"inventors" | b"agents" | b"assignees" | b"examiners" => Projection::PARTIES,
+        name if name == names.applicants => Projection::PARTIES,
+        name if name == names.references_cited => Projection::CITATIONS,
+        _ => Projection::BIBLIO,
+    }
+}
+
+/// pub struct InventionTitle {
+///     pub id: Option<String>,
+///     pub lang: Option<String>,
+///     pub title: String,
 /// }
-fn deser_doc_id<B: BufRead>(rdr: &mut quick_xml::Reader<B>, buf: &mut Vec<u8>, doc_id: &mut DocumentId) -> Result<(), Error> {
+///
+/// call with the invention-title start tag; reads its attributes, then the title
+pub(crate) fn deser_invention_title<B: BufRead>(
+    start: &BytesStart,
+    rdr: &mut quick_xml::Reader<B>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    invention_title: &mut InventionTitle,
+    ) -> Result<(), Error>
+{
+    for attr_res in start.attributes() {
+        let attr = attr_res
+            .map_err(|err| Error::deser(err.to_string()))?;
+
+        match attr.key {
+            b"id" => invention_title.id = Some(decode_attribute(&attr, rdr, opts)?),
+            b"lang" => invention_title.lang = Some(decode_attribute(&attr, rdr, opts)?),
+            _ => return Err(Error::deser("unrecognized attr in invention-title".to_string())),
+        }
+    }
+
+    invention_title.title = deser_text_with_tags_from(start.name(), rdr, scratch, opts)?;
+
+    Ok(())
+}
+
+/// pub struct DocumentId {
+///     pub country: CountryCode,
+///     pub doc_number: String,
+///     pub kind: Option<KindCode>,
+///     pub name: Option<String>,
+///     pub date: Date,
+/// }
+pub(crate) fn deser_doc_id<B: BufRead>(rdr: &mut quick_xml::Reader<B>, buf: &mut Vec<u8>, scratch: &mut Scratch, opts: &ParserOptions, doc_id: &mut DocumentId) -> Result<(), Error> {
     parse_struct_update!(
         rdr,
         buf,
+        scratch,
+        opts,
         "document-id",
         doc_id,
         // Required
         {
             b"country" => country,
             b"doc-number" => doc_number,
-            b"date" => date,
         },
         // Option
         {
             b"kind" => kind,
-        }
-    );
-
-    Ok(())
-}
-
-/// pub struct ClassificationLocarno {
-///     pub edition: String,
-///     pub main_classification: String,
-/// }
-fn deser_class_locarno<B: BufRead>(
-    rdr: &mut quick_xml::Reader<B>,
-    buf: &mut Vec<u8>,
-    class_locarno: &mut ClassificationLocarno,
-    ) -> Result<(), Error>
-{
-    parse_struct_update_from!(
-        rdr,
-        buf,
-        "classification-locarno",
-        class_locarno,
-        // Required
-        {
-            b"edition" => edition,
-            b"main-classification" => main_classification,
+            b"name" => name,
         },
-        // Optional
-        {}
+        // Not required, not an Option
+        {
+            b"date" => date,
+        }
     );
 
     Ok(())
 }
 
-/// pub struct ClassificationNational {
-///     pub country: String,
-///     pub main_classification: String,
-/// }
-fn deser_class_national<B: BufRead>(
-    rdr: &mut quick_xml::Reader<B>,
-    buf: &mut Vec<u8>,
-    class_national: &mut ClassificationNational,
-    ) -> Result<(), Error>
-{
-    parse_struct_update_from!(
-        rdr,
-        buf,
-        "classification-national",
-        class_national,
-        // Required
-        {
-            b"country" => country,
-            b"additional-info" => additional_info,
-            b"main-classification" => main_classification,
-        },
-        // Optional
-        {
-            b"further-classification" => further_classification,
-        }
-    );
+/// The publication-reference, for `filter_raw` to peek at; call after the
+/// us-bibliographic-data-grant start tag, as it's the biblio's first element
+fn deser_publication_reference<B: BufRead>(rdr: &mut quick_xml::Reader<B>, buf: &mut Vec<u8>, scratch: &mut Scratch, opts: &ParserOptions, doc_id: &mut DocumentId) -> Result<(), Error> {
+    if !skip_to_tag_within(b"publication-reference", b"us-bibliographic-data-grant", rdr, buf)? {
+        return Err(Error::deser("no publication-reference in us-bibliographic-data-grant".to_string()));
+    }
 
-    Ok(())
+    deser_doc_id(rdr, buf, scratch, opts, doc_id).within(b"publication-reference")
 }
 
-/// pub struct UsFieldOfClassificationSearch {
-///     pub classification_nationals: Vec<ClassificationNational>,
-///     pub classification_cpc_text: Vec<String>,
-///     pub classification_cpc_combinationtext: Vec<String>,
+/// pub struct UsTermOfGrant {
+///     pub length_of_grant: Option<String>,
+///     pub us_term_extension: Option<String>,
+///     pub disclaimer: Option<Disclaimer>,
+///     pub prior_disclosure_affidavit_filed: Option<String>,
+///     pub text: Option<String>,
 /// }
-fn deser_field_class_search<B: BufRead>(
+///
+/// called after tag us-term-of-grant is already hit
+fn deser_us_term_of_grant<B: BufRead>(
     rdr: &mut quick_xml::Reader<B>,
     buf: &mut Vec<u8>,
-    field_class_search: &mut UsFieldOfClassificationSearch,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    term_of_grant: &mut UsTermOfGrant,
     ) -> Result<(), Error>
 {
     loop {
         match rdr.read_event(buf) {
             Ok(Event::Start(ref e)) => {
                 match e.name() {
-                    b"us-classifications-ipcr" => {
-                        // TODO skip for now
-                        skip_to_tag_within(b"", b"us-classifications-ipcr", rdr, buf)?;
-                    },
-                    b"classification-national" => {
-                        let mut class_national = ClassificationNational::default();
+                    b"length-of-grant" => term_of_grant.length_of_grant = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"us-term-extension" => term_of_grant.us_term_extension = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"prior-disclosure-affidavit-filed" => term_of_grant.prior_disclosure_affidavit_filed = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"text" => term_of_grant.text = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"disclaimer" => {
+                        let mut disclaimer = Disclaimer::default();
 
                         parse_struct_update_from!(
                             rdr,
                             buf,
-                            "classification-national",
-                            class_national,
+                            scratch,
+                            opts,
+                            "disclaimer",
+                            disclaimer,
                             // Required
-                            {
-                                b"country" => country,
-                                b"additional-info" => additional_info,
-                                b"main-classification" => main_classification,
-                            },
+                            {},
                             // Optional
                             {
-                                b"further-classification" => further_classification,
+                                b"date" => date,
+                                b"text" => text,
                             }
                         );
-                        field_class_search.classification_nationals.push(class_national);
-                    },
-                    b"classification-cpc-text" => {
-                        field_class_search.classification_cpc_texts.push(
-                            deser_text_from(e.name(), rdr)?
-                        );
+
+                        term_of_grant.disclaimer = Some(disclaimer);
                     },
-                    b"classification-cpc-combination-text" => {
-                        field_class_search.classification_cpc_combination_texts.push(
-                            deser_text_from(e.name(), rdr)?
-                        );
+                    b"lapse-of-patent" => {
+                        // TODO skip for now
+                        skip_to_tag_within(b"", b"lapse-of-patent", rdr, buf)?;
                     },
-                    _ => return Err(Error::Deser { src: format!("found element {:?}, not in us-field-of-classification-search", std::str::from_utf8(e.name())) }),
+                    _ => unknown_element(e.name(), "us-term-of-grant", rdr, scratch, opts)?,
                 }
             },
             Ok(Event::End(e)) => {
-                if e.name() == "us-field-of-classification-search".as_bytes() {
+                if e.name() == "us-term-of-grant".as_bytes() {
                     break;
                 } else {
                     continue;
                 }
             },
-            Ok(_) => return Err(Error::Deser { src: format!("found non-start-element besides classification-national") }),
+            Ok(_) => return Err(Error::deser("found non-start-element besides us-term-of-grant".to_string())),
 
-            Err(err) => return Err(Error::Deser { src: err.to_string() }),
+            Err(err) => return Err(Error::deser(err.to_string())),
         }
     }
 
     Ok(())
 }
 
-/// pub struct UsApplicant {
-///    pub sequence: String,
-///    pub app_type: String,
-///    pub designation: String,
-///    pub applicant_authority_category: String,
-///    pub addressbook: AddressBook,
-///    pub residence: String, // Country
+/// pub struct PriorityClaim {
+///     pub sequence: String,
+///     pub kind: String,
+///     pub country: CountryCode,
+///     pub doc_number: Option<String>,
+///     pub date: Date,
+///     pub office_of_filing: Option<String>,
 /// }
 ///
-/// Deserializes a Vec of Applicant
+/// Deserializes a Vec of PriorityClaim
 ///
-/// called after tag us-applicants is already hit
-fn deser_us_applicants<B: BufRead>(
+/// called after tag priority-claims is already hit
+pub(crate) fn deser_priority_claims<B: BufRead>(
     rdr: &mut quick_xml::Reader<B>,
     buf: &mut Vec<u8>,
-    applicants: &mut Vec<UsApplicant>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    priority_claims: &mut Vec<PriorityClaim>,
     ) -> Result<(), Error>
 {
     loop {
         match rdr.read_event(buf) {
             Ok(Event::Start(ref e)) => {
                 match e.name() {
-                    b"us-applicant" => {
-                        let mut applicant = UsApplicant::default();
+                    b"priority-claim" => {
+                        let mut priority_claim = PriorityClaim::default();
 
                         // first update attributes
                         for attr_res in e.attributes() {
                             let attr = attr_res
-                                .map_err(|err| Error::Deser { src: err.to_string() })?;
+                                .map_err(|err| Error::deser(err.to_string()))?;
 
                             match attr.key {
-                                b"sequence" => applicant.sequence = attr.unescape_and_decode_value(rdr).expect("never fail utf8?"),
-                                b"app-type" => applicant.app_type = attr.unescape_and_decode_value(rdr).expect("never fail utf8?"),
-                                b"designation" => applicant.designation = attr.unescape_and_decode_value(rdr).expect("never fail utf8?"),
-                                b"applicant-authority-category" => applicant.applicant_authority_category = Some(attr.unescape_and_decode_value(rdr).expect("never fail utf8?")),
-                                _ => return Err(Error::Deser { src: format!("unrecognized attr in us-applicant") }),
+                                b"sequence" => priority_claim.sequence = decode_attribute(&attr, rdr, opts)?,
+                                b"kind" => priority_claim.kind = decode_attribute(&attr, rdr, opts)?,
+                                b"id" => continue,
+                                _ => return Err(Error::deser("unrecognized attr in priority-claim".to_string())),
                             }
                         }
 
-                        // now parse and update the addressbook
-                        deser_addressbook(rdr, buf, &mut applicant.addressbook)?;
-
-                        // TODO this is done in order for now; if need to do out of order w/
-                        // addressbook, create a loop and match
-                        if skip_to_tag_within(b"residence", b"us-applicant", rdr, buf)? {
-                            applicant.residence = Some(deser_text(b"country", rdr)?);
-                        }
+                        deser_priority_claim(rdr, buf, scratch, opts, &mut priority_claim).within(b"priority-claim")?;
 
-                        applicants.push(applicant);
+                        priority_claims.push(priority_claim);
                     },
-                    _ => return Err(Error::Deser { src: format!("found element {:?}, not us-applicant", std::str::from_utf8(e.name())) }),
+                    _ => unknown_element(e.name(), "priority-claims", rdr, scratch, opts)?,
                 }
             },
             Ok(Event::End(e)) => {
-                if e.name() == "us-applicants".as_bytes() {
+                if e.name() == "priority-claims".as_bytes() {
                     break;
                 } else {
                     continue;
                 }
             },
-            Ok(_) => return Err(Error::Deser { src: format!("found non-start-element besides us-applicants") }),
+            Ok(_) => return Err(Error::deser("found non-start-element besides priority-claims".to_string())),
 
-            Err(err) => return Err(Error::Deser { src: err.to_string() }),
+            Err(err) => return Err(Error::deser(err.to_string())),
         }
     }
 
     Ok(())
 }
 
-/// pub struct Inventor . {
-///    pub sequence: String,
-///    pub designation: String,
-///    pub addressbook: AddressBook,
-/// }
-///
-/// Deserializes a Vec of Inventor
-///
-/// called after tag us-applicants is already hit
-fn deser_inventors<B: BufRead>(
+/// called after tag priority-claim is already hit
+fn deser_priority_claim<B: BufRead>(
     rdr: &mut quick_xml::Reader<B>,
     buf: &mut Vec<u8>,
-    inventors: &mut Vec<Inventor>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    priority_claim: &mut PriorityClaim,
     ) -> Result<(), Error>
 {
     loop {
         match rdr.read_event(buf) {
             Ok(Event::Start(ref e)) => {
                 match e.name() {
-                    b"inventor" => {
-                        let mut inventor = Inventor::default();
-
-                        // first update attributes
-                        for attr_res in e.attributes() {
-                            let attr = attr_res
-                                .map_err(|err| Error::Deser { src: err.to_string() })?;
-
-                            match attr.key {
-                                b"sequence" => inventor.sequence = attr.unescape_and_decode_value(rdr).expect("never fail utf8?"),
-                                b"designation" => inventor.designation = attr.unescape_and_decode_value(rdr).expect("never fail utf8?"),
-                                _ => return Err(Error::Deser { src: format!("unrecognized attr in inventor") }),
-                            }
+                    b"country" => priority_claim.country = CountryCode::from(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"doc-number" => priority_claim.doc_number = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"date" => priority_claim.date = deser_date_from(e.name(), rdr, scratch, opts)?,
+                    b"office-of-filing" => {
+                        // office-of-filing is (region, country?) | country; keep the country
+                        if skip_to_tag_within(b"country", b"office-of-filing", rdr, buf)? {
+                            priority_claim.office_of_filing = Some(deser_text_from(b"country", rdr, scratch, opts)?);
                         }
-
-                        // now parse and update the addressbook
-                        deser_addressbook(rdr, buf, &mut inventor.addressbook)?;
-
-                        inventors.push(inventor);
                     },
-                    _ => return Err(Error::Deser { src: format!("found element {:?}, not inventor", std::str::from_utf8(e.name())) }),
+                    _ => unknown_element(e.name(), "priority-claim", rdr, scratch, opts)?,
                 }
             },
             Ok(Event::End(e)) => {
-                if e.name() == "inventors".as_bytes() {
+                if e.name() == "priority-claim".as_bytes() {
                     break;
                 } else {
                     continue;
                 }
             },
-            Ok(_) => return Err(Error::Deser { src: format!("found non-start-element besides inventors") }),
+            // priority-doc-requested, priority-doc-attached flags
+            Ok(Event::Empty(_)) => continue,
+            Ok(e) => return Err(Error::deser(format!("found non-start-element {:?} besides priority-claim", e))),
 
-            Err(err) => return Err(Error::Deser { src: err.to_string() }),
+            Err(err) => return Err(Error::deser(err.to_string())),
         }
     }
 
     Ok(())
 }
 
-// TODO: refactor Agent, Inventor, UsApplicant into one deser method with params?
-/// pub struct Agent {
-///    pub sequence: String,
-///    pub rep_type: String,
-///    pub addressbook: AddressBook,
-/// }
-///
-/// Deserializes a Vec of Agent
+/// Deserializes a Vec of ClassificationIpcr
 ///
-/// called after tag agents is already hit
-fn deser_agents<B: BufRead>(
+/// called after tag classifications-ipcr is already hit
+pub(crate) fn deser_classifications_ipcr<B: BufRead>(
     rdr: &mut quick_xml::Reader<B>,
     buf: &mut Vec<u8>,
-    agents: &mut Vec<Agent>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    classifications_ipcr: &mut Vec<ClassificationIpcr>,
     ) -> Result<(), Error>
 {
     loop {
         match rdr.read_event(buf) {
             Ok(Event::Start(ref e)) => {
                 match e.name() {
-                    b"agent" => {
-                        let mut agent = Agent::default();
-
-                        // first update attributes
-                        for attr_res in e.attributes() {
-                            let attr = attr_res
-                                .map_err(|err| Error::Deser { src: err.to_string() })?;
-
-                            match attr.key {
-                                b"sequence" => agent.sequence = attr.unescape_and_decode_value(rdr).expect("never fail utf8?"),
-                                b"rep-type" => agent.rep_type = attr.unescape_and_decode_value(rdr).expect("never fail utf8?"),
-                                _ => return Err(Error::Deser { src: format!("unrecognized attr in agent") }),
-                            }
-                        }
-
-                        // now parse and update the addressbook
-                        deser_addressbook(rdr, buf, &mut agent.addressbook)?;
-
-                        agents.push(agent);
+                    b"classification-ipcr" => {
+                        let mut class_ipcr = ClassificationIpcr::default();
+                        deser_class_ipcr(rdr, buf, scratch, opts, &mut class_ipcr).within(b"classification-ipcr")?;
+                        classifications_ipcr.push(class_ipcr);
                     },
-                    _ => return Err(Error::Deser { src: format!("found element {:?}, not agent", std::str::from_utf8(e.name())) }),
+                    _ => unknown_element(e.name(), "classifications-ipcr", rdr, scratch, opts)?,
                 }
             },
             Ok(Event::End(e)) => {
-                if e.name() == "agents".as_bytes() {
+                if e.name() == "classifications-ipcr".as_bytes() {
                     break;
                 } else {
                     continue;
                 }
             },
-            Ok(_) => return Err(Error::Deser { src: format!("found non-start-element besides agents") }),
+            Ok(_) => return Err(Error::deser("found non-start-element besides classifications-ipcr".to_string())),
 
-            Err(err) => return Err(Error::Deser { src: err.to_string() }),
+            Err(err) => return Err(Error::deser(err.to_string())),
         }
     }
 
     Ok(())
 }
 
-// TODO: refactor Agent, Inventor, UsApplicant into one deser method with params?
-/// pub struct Assignee {
-///    pub orgname: Option<String>,
-///    pub role: Option<String>,
-///    pub addressbook: AddressBook,
+/// pub struct ClassificationIpcr {
+///     pub ipc_version_indicator: String, // date, the ipc edition
+///     pub classification_level: Option<String>,
+///     pub section: String,
+///     pub class: String,
+///     pub subclass: String,
+///     pub main_group: Option<String>,
+///     pub subgroup: Option<String>,
+///     pub symbol_position: Option<String>,
+///     pub classification_value: Option<String>,
+///     pub action_date: Option<Date>,
+///     pub generating_office: Option<String>, // country
+///     pub classification_status: Option<String>,
+///     pub classification_data_source: Option<String>,
 /// }
 ///
-/// Deserializes a Vec of Assignee
-///
-/// called after tag assignees is already hit
-fn deser_assignees<B: BufRead>(
+/// called after tag classification-ipcr is already hit
+fn deser_class_ipcr<B: BufRead>(
     rdr: &mut quick_xml::Reader<B>,
     buf: &mut Vec<u8>,
-    assignees: &mut Vec<Assignee>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    class_ipcr: &mut ClassificationIpcr,
     ) -> Result<(), Error>
 {
     loop {
         match rdr.read_event(buf) {
             Ok(Event::Start(ref e)) => {
                 match e.name() {
-                    b"assignee" => {
-                        let mut assignee = Assignee::default();
-
-                        deser_assignee(rdr, buf, &mut assignee)?;
-                        assignees.push(assignee);
-                    },
-                    _ => return Err(Error::Deser { src: format!("found element {:?}, not assignee", std::str::from_utf8(e.name())) }),
+                    b"ipc-version-indicator" => class_ipcr.ipc_version_indicator = deser_text(b"date", rdr, scratch, opts)?,
+                    b"classification-level" => class_ipcr.classification_level = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"section" => class_ipcr.section = deser_text_from(e.name(), rdr, scratch, opts)?,
+                    b"class" => class_ipcr.class = deser_text_from(e.name(), rdr, scratch, opts)?,
+                    b"subclass" => class_ipcr.subclass = deser_text_from(e.name(), rdr, scratch, opts)?,
+                    b"main-group" => class_ipcr.main_group = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"subgroup" => class_ipcr.subgroup = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"symbol-position" => class_ipcr.symbol_position = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"classification-value" => class_ipcr.classification_value = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"action-date" => class_ipcr.action_date = Some(deser_date(b"date", rdr, scratch, opts)?),
+                    b"generating-office" => class_ipcr.generating_office = Some(deser_text(b"country", rdr, scratch, opts)?),
+                    b"classification-status" => class_ipcr.classification_status = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"classification-data-source" => class_ipcr.classification_data_source = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    _ => unknown_element(e.name(), "classification-ipcr", rdr, scratch, opts)?,
                 }
             },
             Ok(Event::End(e)) => {
-                if e.name() == "assignees".as_bytes() {
+                if e.name() == "classification-ipcr".as_bytes() {
                     break;
                 } else {
                     continue;
                 }
             },
-            Ok(_) => return Err(Error::Deser { src: format!("found non-start-element besides assignees") }),
+            Ok(e) => return Err(Error::deser(format!("found non-start-element {:?} besides classification-ipcr", e))),
 
-            Err(err) => return Err(Error::Deser { src: err.to_string() }),
+            Err(err) => return Err(Error::deser(err.to_string())),
         }
     }
 
     Ok(())
 }
 
-/// assignee
-fn deser_assignee<B: BufRead>(
+/// pub struct ClassificationsCpc {
+///     pub main_cpc: ClassificationCpc,
+///     pub further_cpc: Vec<ClassificationCpc>,
+/// }
+///
+/// called after tag classifications-cpc is already hit
+pub(crate) fn deser_classifications_cpc<B: BufRead>(
     rdr: &mut quick_xml::Reader<B>,
     buf: &mut Vec<u8>,
-    assignee: &mut Assignee,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    classifications_cpc: &mut ClassificationsCpc,
     ) -> Result<(), Error>
 {
     loop {
         match rdr.read_event(buf) {
             Ok(Event::Start(ref e)) => {
                 match e.name() {
-                    b"orgname" => {
-                        let txt = deser_text_from(b"orgname", rdr)?;
-                        assignee.orgname = Some(txt);
+                    b"main-cpc" => {
+                        consume_start(rdr, buf, b"classification-cpc")?;
+                        deser_class_cpc(rdr, buf, scratch, opts, &mut classifications_cpc.main_cpc).within(b"main-cpc")?;
                     },
-                    b"role" => {
-                        let txt = deser_text_from(b"role", rdr)?;
-                        assignee.role = Some(txt);
+                    b"further-cpc" => {
+                        // continue, classification-cpc will be the next start tag
+                        continue;
                     },
-                    b"addressbook" => {
-                        deser_addressbook_from(rdr, buf, &mut assignee.addressbook)?;
+                    b"classification-cpc" => {
+                        // only reached within further-cpc
+                        let mut class_cpc = ClassificationCpc::default();
+                        deser_class_cpc(rdr, buf, scratch, opts, &mut class_cpc).within(b"classification-cpc")?;
+                        classifications_cpc.further_cpc.push(class_cpc);
+                    },
+                    b"combination-set" => {
+                        // TODO skip for now
+                        skip_to_tag_within(b"", b"combination-set", rdr, buf)?;
                     },
-                    _ => return Err(Error::Deser { src: format!("found element {:?}, not in assignee", std::str::from_utf8(e.name())) }),
+                    _ => unknown_element(e.name(), "classifications-cpc", rdr, scratch, opts)?,
                 }
             },
             Ok(Event::End(e)) => {
-                if e.name() == "assignee".as_bytes() {
+                if e.name() == "classifications-cpc".as_bytes() {
                     break;
                 } else {
                     continue;
                 }
             },
-            Ok(_) => return Err(Error::Deser { src: format!("found non-start-element besides assignee") }),
+            Ok(_) => return Err(Error::deser("found non-start-element besides classifications-cpc".to_string())),
 
-            Err(err) => return Err(Error::Deser { src: err.to_string() }),
+            Err(err) => return Err(Error::deser(err.to_string())),
         }
     }
 
     Ok(())
 }
 
-/// #[derive(Debug)]
-/// pub struct AddressBook {
-///     pub orgname: Option<String>,
-///     pub first_name: Option<String>,
-///     pub last_name: Option<String>,
-///     pub role: Option<String>,
-/// 
-///     // Address
-///     pub city: Option<String>,
-///     pub state: Option<String>,
-///     pub country: Option<String>,
+/// pub struct ClassificationCpc {
+///     pub cpc_version_indicator: String, // date
+///     pub section: String,
+///     pub class: String,
+///     pub subclass: String,
+///     pub main_group: String,
+///     pub subgroup: String,
+///     pub symbol_position: String,
+///     pub classification_value: String,
+///     pub action_date: Date,
+///     pub generating_office: String, // country
+///     pub classification_status: Option<String>,
+///     pub classification_data_source: Option<String>,
+///     pub scheme_origination_code: Option<String>,
 /// }
 ///
-/// called before addressbook tag consumed
-fn deser_addressbook<B: BufRead>(rdr: &mut quick_xml::Reader<B>, buf: &mut Vec<u8>, addressbook: &mut AddressBook) -> Result<(), Error> {
-    consume_start(rdr, buf, b"addressbook")?;
-    deser_addressbook_from(rdr, buf, addressbook)
-}
-
-fn deser_addressbook_from<B: BufRead>(rdr: &mut quick_xml::Reader<B>, buf: &mut Vec<u8>, addressbook: &mut AddressBook) -> Result<(), Error> {
+/// called after tag classification-cpc is already hit
+fn deser_class_cpc<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    class_cpc: &mut ClassificationCpc,
+    ) -> Result<(), Error>
+{
     loop {
         match rdr.read_event(buf) {
             Ok(Event::Start(ref e)) => {
                 match e.name() {
-                    b"orgname" => addressbook.orgname = Some(deser_text_from(e.name(), rdr)?),
-                    b"first-name" => addressbook.first_name = Some(deser_text_from(e.name(), rdr)?),
-                    b"last-name" => addressbook.last_name = Some(deser_text_from(e.name(), rdr)?),
-                    b"role" => addressbook.role = Some(deser_text_from(e.name(), rdr)?),
-                    b"address" => {
-                        let address = &mut addressbook.address;
-
-                        parse_struct_update_from!(
-                            rdr,
-                            buf,
-                            "address",
-                            address,
-                            // Required
-                            {
-                            },
-                            // Optional
-                            {
-                                b"city" => city,
-                                b"state" => state,
-                                b"country" => country,
-                            }
-                        );
-                    }
-                    _ => return Err(Error::Deser { src: format!("unrecognized element {:?} in addressbook", std::str::from_utf8(e.name())) }),
+                    b"cpc-version-indicator" => class_cpc.cpc_version_indicator = deser_text(b"date", rdr, scratch, opts)?,
+                    b"section" => class_cpc.section = deser_text_from(e.name(), rdr, scratch, opts)?,
+                    b"class" => class_cpc.class = deser_text_from(e.name(), rdr, scratch, opts)?,
+                    b"subclass" => class_cpc.subclass = deser_text_from(e.name(), rdr, scratch, opts)?,
+                    b"main-group" => class_cpc.main_group = deser_text_from(e.name(), rdr, scratch, opts)?,
+                    b"subgroup" => class_cpc.subgroup = deser_text_from(e.name(), rdr, scratch, opts)?,
+                    b"symbol-position" => class_cpc.symbol_position = deser_text_from(e.name(), rdr, scratch, opts)?,
+                    b"classification-value" => class_cpc.classification_value = deser_text_from(e.name(), rdr, scratch, opts)?,
+                    b"action-date" => class_cpc.action_date = deser_date(b"date", rdr, scratch, opts)?,
+                    b"generating-office" => class_cpc.generating_office = deser_text(b"country", rdr, scratch, opts)?,
+                    b"classification-status" => class_cpc.classification_status = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"classification-data-source" => class_cpc.classification_data_source = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"scheme-origination-code" => class_cpc.scheme_origination_code = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    _ => unknown_element(e.name(), "classification-cpc", rdr, scratch, opts)?,
                 }
             },
             Ok(Event::End(e)) => {
-                if e.name() == "addressbook".as_bytes() {
+                if e.name() == "classification-cpc".as_bytes() {
                     break;
                 } else {
                     continue;
                 }
             },
-            Ok(e) => return Err(Error::Deser { src: format!("found non-start-element {:?} besides addressbook", e) }),
+            Ok(e) => return Err(Error::deser(format!("found non-start-element {:?} besides classification-cpc", e))),
 
-            Err(err) => return Err(Error::Deser { src: err.to_string() }),
+            Err(err) => return Err(Error::deser(err.to_string())),
         }
     }
 
     Ok(())
 }
 
-/// pub struct Examiners {
-///    pub primary_examiner: Examiner,
+/// same as deser_doc_id, but called after tag document-id is already hit
+pub(crate) fn deser_doc_id_from<B: BufRead>(rdr: &mut quick_xml::Reader<B>, buf: &mut Vec<u8>, scratch: &mut Scratch, opts: &ParserOptions, doc_id: &mut DocumentId) -> Result<(), Error> {
+    parse_struct_update_from!(
+        rdr,
+        buf,
+        scratch,
+        opts,
+        "document-id",
+        doc_id,
+        // Required
+        {
+            b"country" => country,
+            b"doc-number" => doc_number,
+        },
+        // Option
+        {
+            b"kind" => kind,
+            b"name" => name,
+        },
+        // Not required, not an Option
+        {
+            b"date" => date,
+        }
+    );
+
+    Ok(())
+}
+
+/// pub enum RelatedDocument {
+///     Continuation(Relation),
+///     ...
+///     ProvisionalApplication(ProvisionalApplication),
+///     RelatedPublication(DocumentId),
 /// }
 ///
-/// pub struct Examiner {
-///    pub first_name: String,
-///    pub last_name: String,
-///    pub department: String,
-///
-/// }
+/// Deserializes a Vec of RelatedDocument
 ///
-/// called after tag examiners is already hit
-fn deser_examiners<B: BufRead>(
+/// called after tag us-related-documents is already hit
+pub(crate) fn deser_us_related_documents<B: BufRead>(
     rdr: &mut quick_xml::Reader<B>,
     buf: &mut Vec<u8>,
-    examiners: &mut Examiners,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    related_documents: &mut Vec<RelatedDocument>,
     ) -> Result<(), Error>
 {
     loop {
         match rdr.read_event(buf) {
             Ok(Event::Start(ref e)) => {
-                match e.name() {
-                    b"primary-examiner" => {
-                        let primary_examiner = &mut examiners.primary_examiner;
-
-                        parse_struct_update_from!(
-                            rdr,
-                            buf,
-                            "primary-examiner",
-                            primary_examiner,
-                            {
-                                b"first-name" => first_name,
-                                b"last-name" => last_name,
-                            },
-                            {
-                                b"department" => department,
-                            }
-                        );
+                let related_document = match e.name() {
+                    b"addition" => RelatedDocument::Addition(deser_relation(rdr, buf, scratch, opts, "addition").within(b"addition")?),
+                    b"division" => RelatedDocument::Division(deser_relation(rdr, buf, scratch, opts, "division").within(b"division")?),
+                    b"continuation" => RelatedDocument::Continuation(deser_relation(rdr, buf, scratch, opts, "continuation").within(b"continuation")?),
+                    b"continuation-in-part" => RelatedDocument::ContinuationInPart(deser_relation(rdr, buf, scratch, opts, "continuation-in-part").within(b"continuation-in-part")?),
+                    b"continuing-reissue" => RelatedDocument::ContinuingReissue(deser_relation(rdr, buf, scratch, opts, "continuing-reissue").within(b"continuing-reissue")?),
+                    b"reissue" => RelatedDocument::Reissue(deser_relation(rdr, buf, scratch, opts, "reissue").within(b"reissue")?),
+                    b"us-divisional-reissue" => RelatedDocument::DivisionalReissue(deser_relation(rdr, buf, scratch, opts, "us-divisional-reissue").within(b"us-divisional-reissue")?),
+                    b"reexamination" => RelatedDocument::Reexamination(deser_relation(rdr, buf, scratch, opts, "reexamination").within(b"reexamination")?),
+                    b"us-reexamination-reissue-merger" => RelatedDocument::ReexaminationReissueMerger(deser_relation(rdr, buf, scratch, opts, "us-reexamination-reissue-merger").within(b"us-reexamination-reissue-merger")?),
+                    b"substitution" => RelatedDocument::Substitution(deser_relation(rdr, buf, scratch, opts, "substitution").within(b"substitution")?),
+                    b"utility-model-basis" => RelatedDocument::UtilityModelBasis(deser_relation(rdr, buf, scratch, opts, "utility-model-basis").within(b"utility-model-basis")?),
+                    b"us-provisional-application" => {
+                        let mut provisional = ProvisionalApplication::default();
+                        deser_doc_id(rdr, buf, scratch, opts, &mut provisional.document_id).within(b"us-provisional-application")?;
+                        if skip_to_tag_within(b"us-provisional-application-status", b"us-provisional-application", rdr, buf)? {
+                            provisional.status = Some(deser_text_from(b"us-provisional-application-status", rdr, scratch, opts)?);
+                        }
+                        RelatedDocument::ProvisionalApplication(provisional)
                     },
-                    b"assistant-examiner" => {
-                        let assistant_examiner = &mut examiners.assistant_examiner;
-
-                        parse_struct_update_from!(
-                            rdr,
-                            buf,
-                            "assistant-examiner",
-                            assistant_examiner,
-                            {
-                                b"first-name" => first_name,
-                                b"last-name" => last_name,
-                            },
-                            {
-                                b"department" => department,
-                            }
-                        );
+                    b"related-publication" => {
+                        let mut doc_id = DocumentId::default();
+                        deser_doc_id(rdr, buf, scratch, opts, &mut doc_id).within(b"related-publication")?;
+                        RelatedDocument::RelatedPublication(doc_id)
                     },
-                    _ => return Err(Error::Deser { src: format!("found element {:?}, not expected in examiners", std::str::from_utf8(e.name())) }),
-                }
+                    b"correction" => {
+                        // TODO skip for now
+                        skip_to_tag_within(b"", b"correction", rdr, buf)?;
+                        continue;
+                    },
+                    _ => {
+                        unknown_element(e.name(), "us-related-documents", rdr, scratch, opts)?;
+                        continue;
+                    },
+                };
+
+                related_documents.push(related_document);
             },
             Ok(Event::End(e)) => {
-                if e.name() == "examiners".as_bytes() {
+                if e.name() == "us-related-documents".as_bytes() {
                     break;
                 } else {
                     continue;
                 }
             },
-            Ok(_) => return Err(Error::Deser { src: format!("found non-start-element besides examiners") }),
+            Ok(_) => return Err(Error::deser("found non-start-element besides us-related-documents".to_string())),
 
-            Err(err) => return Err(Error::Deser { src: err.to_string() }),
+            Err(err) => return Err(Error::deser(err.to_string())),
         }
     }
 
     Ok(())
 }
 
-/// call when the start tag has already been consumed, now you need the text to the end tag
-fn deser_text_from<B: BufRead, K: AsRef<[u8]>>(end: K, rdr: &mut quick_xml::Reader<B>) -> Result<String, Error> {
-    match rdr.read_text(end, &mut Vec::new()) {
-        Ok(txt) => Ok(txt),
-        Err(err) => Err(Error::Deser { src: format!("err: {}, position: {}", err, rdr.buffer_position()) }),
+/// pub struct Relation {
+///     pub parent_doc: ParentDoc,
+///     pub child_docs: Vec<ChildDoc>,
+/// }
+///
+/// called after the relation kind tag (e.g. continuation) is already hit
+fn deser_relation<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    xml_element: &str,
+    ) -> Result<Relation, Error>
+{
+    let mut relation = Relation::default();
+
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"relation" | b"us-relation" => continue,
+                    b"parent-doc" => deser_parent_doc(rdr, buf, scratch, opts, &mut relation.parent_doc).within(b"parent-doc")?,
+                    b"child-doc" => {
+                        let mut child_doc = ChildDoc::default();
+                        deser_doc_id(rdr, buf, scratch, opts, &mut child_doc.document_id).within(b"child-doc")?;
+                        if skip_to_tag_within(b"international-filing-date", b"child-doc", rdr, buf)? {
+                            child_doc.international_filing_date = Some(deser_text(b"date", rdr, scratch, opts)?);
+                        }
+                        relation.child_docs.push(child_doc);
+                    },
+                    _ => unknown_element(e.name(), xml_element, rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(e)) => {
+                if e.name() == xml_element.as_bytes() {
+                    break;
+                } else {
+                    continue;
+                }
+            },
+            Ok(_) => return Err(Error::deser(format!("found non-start-element besides {}", xml_element))),
+
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
     }
+
+    Ok(relation)
 }
 
-/// call when the start tag has already been consumed, now you need the text to the end tag
-fn deser_text<B: BufRead>(name: &[u8], rdr: &mut quick_xml::Reader<B>) -> Result<String, Error> {
-    let mut buf = Vec::new();
+/// pub struct ParentDoc {
+///     pub document_id: DocumentId,
+///     pub parent_status: Option<String>,
+///     pub parent_grant_document: Option<DocumentId>,
+///     pub parent_pct_document: Option<DocumentId>,
+///     pub international_filing_date: Option<String>,
+/// }
+///
+/// called after tag parent-doc is already hit
+fn deser_parent_doc<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    parent_doc: &mut ParentDoc,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"document-id" => deser_doc_id_from(rdr, buf, scratch, opts, &mut parent_doc.document_id).within(b"document-id")?,
+                    b"parent-status" => parent_doc.parent_status = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"parent-grant-document" => {
+                        let mut doc_id = DocumentId::default();
+                        deser_doc_id(rdr, buf, scratch, opts, &mut doc_id).within(b"parent-grant-document")?;
+                        parent_doc.parent_grant_document = Some(doc_id);
+                    },
+                    b"parent-pct-document" => {
+                        let mut doc_id = DocumentId::default();
+                        deser_doc_id(rdr, buf, scratch, opts, &mut doc_id).within(b"parent-pct-document")?;
+                        parent_doc.parent_pct_document = Some(doc_id);
+                    },
+                    b"international-filing-date" => parent_doc.international_filing_date = Some(deser_text(b"date", rdr, scratch, opts)?),
+                    _ => unknown_element(e.name(), "parent-doc", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(e)) => {
+                if e.name() == "parent-doc".as_bytes() {
+                    break;
+                } else {
+                    continue;
+                }
+            },
+            Ok(_) => return Err(Error::deser("found non-start-element besides parent-doc".to_string())),
 
-    consume_start(rdr, &mut buf, name)?;
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
 
-    buf.clear();
+    Ok(())
+}
 
-    match rdr.read_text(name, &mut buf) {
-        Ok(txt) => Ok(txt),
-        Err(err) => Err(Error::Deser { src: err.to_string() }),
-    }
+/// pub struct ClassificationLocarno {
+///     pub edition: String,
+///     pub main_classification: String,
+/// }
+fn deser_class_locarno<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    class_locarno: &mut ClassificationLocarno,
+    ) -> Result<(), Error>
+{
+    parse_struct_update_from!(
+        rdr,
+        buf,
+        scratch,
+        opts,
+        "classification-locarno",
+        class_locarno,
+        // Required
+        {
+            b"edition" => edition,
+            b"main-classification" => main_classification,
+        },
+        // Optional
+        {}
+    );
+
+    Ok(())
 }
 
-/// special function for dealing with text which has nested tags.
-/// TODO handle nested same tags using depth counter
-fn deser_text_with_tags_from<B: BufRead>(end: &[u8], rdr: &mut quick_xml::Reader<B>) -> Result<String, Error> {
-    let mut frags: Vec<String> = Vec::new();
-    let mut buf = Vec::new();
+/// pub struct ClassificationNational {
+///     pub country: CountryCode,
+///     pub main_classification: Uspc,
+///     pub further_classification: Option<Uspc>,
+/// }
+pub(crate) fn deser_class_national<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    class_national: &mut ClassificationNational,
+    ) -> Result<(), Error>
+{
+    parse_struct_update_from!(
+        rdr,
+        buf,
+        scratch,
+        opts,
+        "classification-national",
+        class_national,
+        // Required
+        {
+            b"country" => country,
+            b"main-classification" => main_classification,
+        },
+        // Optional
+        {
+            b"additional-info" => additional_info,
+            b"further-classification" => further_classification,
+        }
+    );
 
-    // depth starts at one because we already consumed first start tag.
-    let mut depth = 1;
+    Ok(())
+}
 
+/// pub struct UsBotanic {
+///     pub latin_name: String,
+///     pub variety: String,
+/// }
+///
+/// called after tag us-botanic is already hit
+fn deser_us_botanic<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    botanic: &mut UsBotanic,
+    ) -> Result<(), Error>
+{
     loop {
-        match rdr.read_event(&mut buf) {
+        match rdr.read_event(buf) {
             Ok(Event::Start(ref e)) => {
-                if &e.name() == &end {
-                    depth += 1;
+                match e.name() {
+                    // latin name is usually partly italicized
+                    b"latin-name" => botanic.latin_name = deser_text_with_tags_from(e.name(), rdr, scratch, opts)?,
+                    b"variety" => botanic.variety = deser_text_with_tags_from(e.name(), rdr, scratch, opts)?,
+                    _ => unknown_element(e.name(), "us-botanic", rdr, scratch, opts)?,
                 }
             },
-            Ok(Event::End(ref e)) => {
-                if &e.name() == &end {
-                    depth -= 1;
+            Ok(Event::End(e)) => {
+                if e.name() == "us-botanic".as_bytes() {
+                    break;
+                } else {
+                    continue;
                 }
+            },
+            Ok(_) => return Err(Error::deser("found non-start-element besides us-botanic".to_string())),
+
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    Ok(())
+}
 
-                // now final
-                if &e.name() == &end && depth == 0 {
+/// pub struct UsFieldOfClassificationSearch {
+///     pub us_classifications_ipcr: Vec<String>,
+///     pub classification_nationals: Vec<ClassificationNational>,
+///     pub classification_cpc_texts: Vec<String>,
+///     pub classification_cpc_combination_texts: Vec<String>,
+/// }
+///
+/// called after tag us-field-of-classification-search (field-of-search before v4.2) is already hit
+fn deser_field_class_search<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    names: &ElementNames,
+    field_class_search: &mut UsFieldOfClassificationSearch,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"us-classifications-ipcr" => {
+                        field_class_search.us_classifications_ipcr.push(
+                            deser_text_from(e.name(), rdr, scratch, opts)?
+                        );
+                    },
+                    b"classification-national" => {
+                        let mut class_national = ClassificationNational::default();
+                        deser_class_national(rdr, buf, scratch, opts, &mut class_national).within(b"classification-national")?;
+                        field_class_search.classification_nationals.push(class_national);
+                    },
+                    b"classification-cpc-text" => {
+                        field_class_search.classification_cpc_texts.push(
+                            deser_text_from(e.name(), rdr, scratch, opts)?
+                        );
+                    },
+                    b"classification-cpc-combination-text" => {
+                        field_class_search.classification_cpc_combination_texts.push(
+                            deser_text_from(e.name(), rdr, scratch, opts)?
+                        );
+                    },
+                    _ => unknown_element(e.name(), names.field_of_search, rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(e)) => {
+                if e.name() == names.field_of_search {
                     break;
+                } else {
+                    continue;
                 }
             },
-            Ok(Event::Text(e)) => {
-                let frag = e.unescape_and_decode(rdr)
-                    .map_err(|err| Error::Deser { src: err.to_string() })?;
+            Ok(_) => return Err(Error::deser(format!("found non-start-element besides {:?}", std::str::from_utf8(names.field_of_search)))),
 
-                frags.push(frag);
-            },
-            Err(err) => return Err(Error::Deser { src: err.to_string() }),
-            _ => {},
+            Err(err) => return Err(Error::deser(err.to_string())),
         }
     }
 
-    Ok(frags.join(" "))
+    Ok(())
 }
 
-/// special function for dealing with text which has nested tags, and which will read
-/// to the next PI tag that has end=tail
-fn deser_pi_text_with_tags_to_tail_from<B: BufRead>(rdr: &mut quick_xml::Reader<B>) -> Result<String, Error> {
-    let mut frags: Vec<String> = Vec::new();
-    let mut buf = Vec::new();
-
+/// Deserializes a Vec of UsCitation
+///
+/// called after tag us-references-cited (references-cited before v4.3) is already hit
+fn deser_us_references_cited<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    names: &ElementNames,
+    citations: &mut Vec<UsCitation>,
+    ) -> Result<(), Error>
+{
     loop {
-        match rdr.read_event(&mut buf) {
-            Ok(Event::Start(_)) => {
-                continue;
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    name if name == names.citation => citations.push(deser_us_citation(rdr, buf, scratch, opts, names).within(names.citation)?),
+                    _ => unknown_element(e.name(), names.references_cited, rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(e)) => {
+                if e.name() == names.references_cited {
+                    break;
+                } else {
+                    continue;
+                }
             },
-            Ok(Event::PI(ref tag_bytes)) => {
-                // just search for the next tail, don't need to match on name.
-                let pi_tag_res = tag_bytes.unescape_and_decode(&rdr);
+            Ok(_) => return Err(Error::deser(format!("found non-start-element besides {:?}", std::str::from_utf8(names.references_cited)))),
 
-                let end = match pi_tag_res {
-                    Ok(ref s) => s.split_whitespace().last().context(Deser { src: "No end for PI".to_string() })?,
-                    // continue in case of nested PI tag
-                    Err(_) => {
-                        continue;
-                    },
-                };
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    Ok(())
+}
+
+/// pub struct UsCitation {
+///     pub citation: Citation,
+///     pub category: Option<String>,
+///     pub classification_cpc_text: Option<String>,
+///     pub classification_national: Option<ClassificationNational>,
+/// }
+///
+/// called after tag us-citation (citation before v4.3) is already hit
+fn deser_us_citation<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    names: &ElementNames,
+    ) -> Result<UsCitation, Error>
+{
+    let mut citation = None;
+    let mut category = None;
+    let mut classification_cpc_text = None;
+    let mut classification_national = None;
 
-                if end == "end=\"tail\"" {
-                    // in case of nested PI; I don't care about them unless they're
-                    // one of the description ones, so just grab it as part of text
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"patcit" => {
+                        let mut patcit = PatCit {
+                            num: deser_citation_num(e, rdr, opts, "patcit").within(b"patcit")?,
+                            ..PatCit::default()
+                        };
+                        deser_doc_id(rdr, buf, scratch, opts, &mut patcit.document_id).within(b"patcit")?;
+                        citation = Some(Citation::Patent(patcit));
+                    },
+                    b"nplcit" => {
+                        let mut nplcit = NplCit {
+                            num: deser_citation_num(e, rdr, opts, "nplcit").within(b"nplcit")?,
+                            ..NplCit::default()
+                        };
+                        if skip_to_tag_within(b"othercit", b"nplcit", rdr, buf)? {
+                            // titles are often partly italicized
+                            nplcit.othercit = deser_text_with_tags_from(b"othercit", rdr, scratch, opts).within(b"nplcit")?;
+                        }
+                        citation = Some(Citation::NonPatent(nplcit));
+                    },
+                    b"category" => category = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"classification-cpc-text" => classification_cpc_text = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"classification-national" => {
+                        let mut class_national = ClassificationNational::default();
+                        deser_class_national(rdr, buf, scratch, opts, &mut class_national).within(b"classification-national")?;
+                        classification_national = Some(class_national);
+                    },
+                    _ => unknown_element(e.name(), names.citation, rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(e)) => {
+                if e.name() == names.citation {
                     break;
+                } else {
+                    continue;
                 }
             },
-            Ok(Event::Text(e)) => {
-                let frag = e.unescape_and_decode(rdr)
-                    .map_err(|err| Error::Deser { src: err.to_string() })?;
+            Ok(_) => return Err(Error::deser(format!("found non-start-element besides {:?}", std::str::from_utf8(names.citation)))),
 
-                frags.push(frag);
-            },
-            Err(err) => return Err(Error::Deser { src: err.to_string() }),
-            _ => {},
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    let citation = citation
+        .ok_or_else(|| Error::deser("no patcit or nplcit in us-citation".to_string()))?;
+
+    Ok(UsCitation {
+        citation,
+        category,
+        classification_cpc_text,
+        classification_national,
+    })
+}
+
+/// the num attr of a patcit or nplcit
+fn deser_citation_num<B: BufRead>(e: &BytesStart, rdr: &quick_xml::Reader<B>, opts: &ParserOptions, element: &str) -> Result<String, Error> {
+    let mut num = String::new();
+
+    for attr_res in e.attributes() {
+        let attr = attr_res
+            .map_err(|err| Error::deser(err.to_string()))?;
+
+        match attr.key {
+            b"num" => num = decode_attribute(&attr, rdr, opts)?,
+            _ => return Err(Error::deser(format!("unrecognized attr in {}", element))),
         }
     }
 
-    Ok(frags.join(" "))
+    Ok(num)
+}
+
+/// pub struct Figures {
+///     pub number_of_drawing_sheets: Option<u32>,
+///     pub number_of_figures: Option<u32>,
+/// }
+///
+/// called after tag figures is already hit
+fn deser_figures<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    figures: &mut Figures,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"number-of-drawing-sheets" => figures.number_of_drawing_sheets = Some(deser_u32_from(e.name(), rdr, scratch, opts)?),
+                    b"number-of-figures" => figures.number_of_figures = Some(deser_u32_from(e.name(), rdr, scratch, opts)?),
+                    b"figure-to-publish" => {
+                        // TODO skip for now
+                        skip_to_tag_within(b"", b"figure-to-publish", rdr, buf)?;
+                    },
+                    _ => unknown_element(e.name(), "figures", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(e)) => {
+                if e.name() == "figures".as_bytes() {
+                    break;
+                } else {
+                    continue;
+                }
+            },
+            Ok(_) => return Err(Error::deser("found non-start-element besides figures".to_string())),
+
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    Ok(())
+}
+
+/// pub struct UsApplicant {
+///    pub sequence: String,
+///    pub app_type: String,
+///    pub designation: String,
+///    pub applicant_authority_category: String,
+///    pub addressbook: AddressBook,
+///    pub residence: String, // Country
+/// }
+///
+/// Deserializes a Vec of Applicant
+///
+/// called after tag us-applicants (applicants before v4.3) is already hit
+pub(crate) fn deser_us_applicants<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    names: &ElementNames,
+    applicants: &mut Vec<UsApplicant>,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    name if name == names.applicant => {
+                        let mut applicant = UsApplicant::default();
+
+                        // first update attributes
+                        for attr_res in e.attributes() {
+                            let attr = attr_res
+                                .map_err(|err| Error::deser(err.to_string()))?;
+
+                            match attr.key {
+                                b"sequence" => applicant.sequence = decode_attribute(&attr, rdr, opts)?,
+                                b"app-type" => applicant.app_type = decode_attribute(&attr, rdr, opts)?,
+                                b"designation" => applicant.designation = decode_attribute(&attr, rdr, opts)?,
+                                b"applicant-authority-category" => applicant.applicant_authority_category = Some(decode_attribute(&attr, rdr, opts)?),
+                                _ => return Err(Error::deser("unrecognized attr in us-applicant".to_string())),
+                            }
+                        }
+
+                        // now parse and update the addressbook
+                        deser_addressbook(rdr, buf, scratch, opts, &mut applicant.addressbook).within(names.applicant)?;
+
+                        // TODO this is done in order for now; if need to do out of order w/
+                        // addressbook, create a loop and match
+                        if skip_to_tag_within(b"residence", names.applicant, rdr, buf)? {
+                            applicant.residence = Some(deser_text(b"country", rdr, scratch, opts)?);
+                        }
+
+                        applicants.push(applicant);
+                    },
+                    _ => unknown_element(e.name(), names.applicants, rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(e)) => {
+                if e.name() == names.applicants {
+                    break;
+                } else {
+                    continue;
+                }
+            },
+            Ok(_) => return Err(Error::deser(format!("found non-start-element besides {:?}", std::str::from_utf8(names.applicants)))),
+
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    Ok(())
+}
+
+/// pub struct Inventor . {
+///    pub sequence: String,
+///    pub designation: String,
+///    pub addressbook: AddressBook,
+/// }
+///
+/// Deserializes a Vec of Inventor
+///
+/// called after tag us-applicants is already hit
+pub(crate) fn deser_inventors<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    inventors: &mut Vec<Inventor>,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"inventor" => {
+                        let mut inventor = Inventor::default();
+
+                        // first update attributes
+                        for attr_res in e.attributes() {
+                            let attr = attr_res
+                                .map_err(|err| Error::deser(err.to_string()))?;
+
+                            match attr.key {
+                                b"sequence" => inventor.sequence = decode_attribute(&attr, rdr, opts)?,
+                                b"designation" => inventor.designation = decode_attribute(&attr, rdr, opts)?,
+                                _ => return Err(Error::deser("unrecognized attr in inventor".to_string())),
+                            }
+                        }
+
+                        // now parse and update the addressbook
+                        deser_addressbook(rdr, buf, scratch, opts, &mut inventor.addressbook).within(b"inventor")?;
+
+                        inventors.push(inventor);
+                    },
+                    _ => unknown_element(e.name(), "inventors", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(e)) => {
+                if e.name() == "inventors".as_bytes() {
+                    break;
+                } else {
+                    continue;
+                }
+            },
+            Ok(_) => return Err(Error::deser("found non-start-element besides inventors".to_string())),
+
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    Ok(())
+}
+
+// TODO: refactor Agent, Inventor, UsApplicant into one deser method with params?
+/// pub struct Agent {
+///    pub sequence: String,
+///    pub rep_type: String,
+///    pub addressbook: AddressBook,
+/// }
+///
+/// Deserializes a Vec of Agent
+///
+/// called after tag agents is already hit
+pub(crate) fn deser_agents<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    agents: &mut Vec<Agent>,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"agent" => {
+                        let mut agent = Agent::default();
+
+                        // first update attributes
+                        for attr_res in e.attributes() {
+                            let attr = attr_res
+                                .map_err(|err| Error::deser(err.to_string()))?;
+
+                            match attr.key {
+                                b"sequence" => agent.sequence = decode_attribute(&attr, rdr, opts)?,
+                                b"rep-type" => agent.rep_type = decode_attribute(&attr, rdr, opts)?,
+                                _ => return Err(Error::deser("unrecognized attr in agent".to_string())),
+                            }
+                        }
+
+                        // now parse and update the addressbook
+                        deser_addressbook(rdr, buf, scratch, opts, &mut agent.addressbook).within(b"agent")?;
+
+                        agents.push(agent);
+                    },
+                    _ => unknown_element(e.name(), "agents", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(e)) => {
+                if e.name() == "agents".as_bytes() {
+                    break;
+                } else {
+                    continue;
+                }
+            },
+            Ok(_) => return Err(Error::deser("found non-start-element besides agents".to_string())),
+
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    Ok(())
+}
+
+// TODO: refactor Agent, Inventor, UsApplicant into one deser method with params?
+/// pub struct Assignee {
+///    pub orgname: Option<String>,
+///    pub role: Option<String>,
+///    pub addressbook: AddressBook,
+/// }
+///
+/// Deserializes a Vec of Assignee
+///
+/// called after tag assignees is already hit
+pub(crate) fn deser_assignees<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    assignees: &mut Vec<Assignee>,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"assignee" => {
+                        let mut assignee = Assignee::default();
+
+                        deser_assignee(rdr, buf, scratch, opts, &mut assignee).within(b"assignee")?;
+                        assignees.push(assignee);
+                    },
+                    _ => unknown_element(e.name(), "assignees", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(e)) => {
+                if e.name() == "assignees".as_bytes() {
+                    break;
+                } else {
+                    continue;
+                }
+            },
+            Ok(_) => return Err(Error::deser("found non-start-element besides assignees".to_string())),
+
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    Ok(())
+}
+
+/// assignee
+fn deser_assignee<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    assignee: &mut Assignee,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"orgname" => {
+                        let txt = deser_text_from(b"orgname", rdr, scratch, opts).within(b"orgname")?;
+                        assignee.orgname = Some(txt);
+                    },
+                    b"role" => {
+                        let txt = deser_text_from(b"role", rdr, scratch, opts).within(b"role")?;
+                        assignee.role = Some(txt);
+                    },
+                    b"addressbook" => {
+                        deser_addressbook_from(rdr, buf, scratch, opts, &mut assignee.addressbook).within(b"addressbook")?;
+                    },
+                    _ => unknown_element(e.name(), "assignee", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(e)) => {
+                if e.name() == "assignee".as_bytes() {
+                    break;
+                } else {
+                    continue;
+                }
+            },
+            Ok(_) => return Err(Error::deser("found non-start-element besides assignee".to_string())),
+
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    Ok(())
+}
+
+/// #[derive(Debug)]
+/// pub struct AddressBook {
+///     pub orgname: Option<String>,
+///     pub first_name: Option<String>,
+///     pub last_name: Option<String>,
+///     pub role: Option<String>,
+///     // Address
+///     pub city: Option<String>,
+///     pub state: Option<String>,
+///     pub country: Option<CountryCode>,
+/// }
+///
+/// called before addressbook tag consumed
+fn deser_addressbook<B: BufRead>(rdr: &mut quick_xml::Reader<B>, buf: &mut Vec<u8>, scratch: &mut Scratch, opts: &ParserOptions, addressbook: &mut AddressBook) -> Result<(), Error> {
+    consume_start(rdr, buf, b"addressbook")?;
+    deser_addressbook_from(rdr, buf, scratch, opts, addressbook)
+}
+
+fn deser_addressbook_from<B: BufRead>(rdr: &mut quick_xml::Reader<B>, buf: &mut Vec<u8>, scratch: &mut Scratch, opts: &ParserOptions, addressbook: &mut AddressBook) -> Result<(), Error> {
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"orgname" => addressbook.orgname = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"first-name" => addressbook.first_name = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"last-name" => addressbook.last_name = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"role" => addressbook.role = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"address" => {
+                        let address = &mut addressbook.address;
+
+                        parse_struct_update_from!(
+                            rdr,
+                            buf,
+                            scratch,
+                            opts,
+                            "address",
+                            address,
+                            // Required
+                            {
+                            },
+                            // Optional
+                            {
+                                b"city" => city,
+                                b"state" => state,
+                                b"country" => country,
+                            }
+                        );
+                    }
+                    _ => unknown_element(e.name(), "addressbook", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(e)) => {
+                if e.name() == "addressbook".as_bytes() {
+                    break;
+                } else {
+                    continue;
+                }
+            },
+            Ok(e) => return Err(Error::deser(format!("found non-start-element {:?} besides addressbook", e))),
+
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    Ok(())
+}
+
+/// pub struct Examiners {
+///    pub primary_examiner: Examiner,
+///    pub assistant_examiner: Option<Examiner>,
+/// }
+///
+/// called after tag examiners is already hit
+fn deser_examiners<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    examiners: &mut Examiners,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"primary-examiner" => {
+                        deser_examiner(rdr, buf, scratch, opts, "primary-examiner", &mut examiners.primary_examiner).within(b"primary-examiner")?;
+                    },
+                    b"assistant-examiner" => {
+                        let mut assistant_examiner = Examiner::default();
+                        deser_examiner(rdr, buf, scratch, opts, "assistant-examiner", &mut assistant_examiner).within(b"assistant-examiner")?;
+                        examiners.assistant_examiner = Some(assistant_examiner);
+                    },
+                    _ => unknown_element(e.name(), "examiners", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(e)) => {
+                if e.name() == "examiners".as_bytes() {
+                    break;
+                } else {
+                    continue;
+                }
+            },
+            Ok(_) => return Err(Error::deser("found non-start-element besides examiners".to_string())),
+
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    Ok(())
+}
+
+/// pub struct Examiner {
+///    pub first_name: String,
+///    pub last_name: String,
+///    pub middle_name: Option<String>,
+///    pub suffix: Option<String>,
+///    pub department: Option<String>,
+/// }
+///
+/// called after tag primary-examiner or assistant-examiner is already hit
+fn deser_examiner<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    xml_element: &str,
+    examiner: &mut Examiner,
+    ) -> Result<(), Error>
+{
+    parse_struct_update_from!(
+        rdr,
+        buf,
+        scratch,
+        opts,
+        xml_element,
+        examiner,
+        {
+            b"first-name" => first_name,
+            b"last-name" => last_name,
+        },
+        {
+            b"middle-name" => middle_name,
+            b"suffix" => suffix,
+            b"department" => department,
+        }
+    );
+
+    Ok(())
+}
+
+/// pub struct PctOrRegionalFilingData {
+///     pub document_id: DocumentId,
+///     pub us_371c124_date: Option<String>,
+///     pub us_371c12_date: Option<String>,
+/// }
+///
+/// called after tag pct-or-regional-filing-data is already hit
+pub(crate) fn deser_pct_filing_data<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    filing_data: &mut PctOrRegionalFilingData,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"document-id" => deser_doc_id_from(rdr, buf, scratch, opts, &mut filing_data.document_id).within(b"document-id")?,
+                    b"us-371c124-date" => filing_data.us_371c124_date = Some(deser_text(b"date", rdr, scratch, opts)?),
+                    b"us-371c12-date" => filing_data.us_371c12_date = Some(deser_text(b"date", rdr, scratch, opts)?),
+                    _ => unknown_element(e.name(), "pct-or-regional-filing-data", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(e)) => {
+                if e.name() == "pct-or-regional-filing-data".as_bytes() {
+                    break;
+                } else {
+                    continue;
+                }
+            },
+            Ok(_) => return Err(Error::deser("found non-start-element besides pct-or-regional-filing-data".to_string())),
+
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    Ok(())
+}
+
+/// pub struct PctOrRegionalPublishingData {
+///     pub document_id: DocumentId,
+///     pub gazette_num: Option<String>,
+/// }
+///
+/// called after tag pct-or-regional-publishing-data is already hit
+pub(crate) fn deser_pct_publishing_data<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    publishing_data: &mut PctOrRegionalPublishingData,
+    ) -> Result<(), Error>
+{
+    deser_doc_id(rdr, buf, scratch, opts, &mut publishing_data.document_id)?;
+    if skip_to_tag_within(b"gazette-num", b"pct-or-regional-publishing-data", rdr, buf)? {
+        publishing_data.gazette_num = Some(deser_text_from(b"gazette-num", rdr, scratch, opts)?);
+        skip_to_tag_within(b"", b"pct-or-regional-publishing-data", rdr, buf)?;
+    }
+
+    Ok(())
+}
+
+/// pub struct HagueAgreementData {
+///     pub international_filing_date: String,
+///     pub international_registration_publication_date: String,
+///     pub international_registration_number: String,
+///     pub international_registration_date: String,
+/// }
+///
+/// called after tag hague-agreement-data is already hit
+fn deser_hague_agreement_data<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    hague: &mut HagueAgreementData,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"international-filing-date" => hague.international_filing_date = deser_text(b"date", rdr, scratch, opts)?,
+                    b"international-registration-publication-date" => hague.international_registration_publication_date = deser_text(b"date", rdr, scratch, opts)?,
+                    b"international-registration-number" => hague.international_registration_number = deser_text_from(e.name(), rdr, scratch, opts)?,
+                    b"international-registration-date" => hague.international_registration_date = deser_text(b"date", rdr, scratch, opts)?,
+                    _ => unknown_element(e.name(), "hague-agreement-data", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(e)) => {
+                if e.name() == "hague-agreement-data".as_bytes() {
+                    break;
+                } else {
+                    continue;
+                }
+            },
+            Ok(_) => return Err(Error::deser("found non-start-element besides hague-agreement-data".to_string())),
+
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    Ok(())
+}
+
+/// call when the start tag has already been consumed, now you need the text to the end tag
+pub(crate) fn deser_text_from<B: BufRead, K: AsRef<[u8]>>(end: K, rdr: &mut quick_xml::Reader<B>, scratch: &mut Scratch, opts: &ParserOptions) -> Result<String, Error> {
+    scratch.with(|buf, _| read_text(end, rdr, buf, opts))
+}
+
+/// call when the start tag has already been consumed, now you need the text to the end tag
+/// parsed as a number
+fn deser_u32_from<B: BufRead, K: AsRef<[u8]>>(end: K, rdr: &mut quick_xml::Reader<B>, scratch: &mut Scratch, opts: &ParserOptions) -> Result<u32, Error> {
+    let txt = deser_text_from(end.as_ref(), rdr, scratch, opts)?;
+
+    txt.parse()
+        .map_err(|err| Error::deser(format!("err: {} parsing {:?} in {:?}, position: {}", err, txt, std::str::from_utf8(end.as_ref()), rdr.buffer_position())))
+}
+
+/// call when the start tag has already been consumed, now you need the text to the end tag
+/// as a date, see `data::Date`
+fn deser_date_from<B: BufRead, K: AsRef<[u8]>>(end: K, rdr: &mut quick_xml::Reader<B>, scratch: &mut Scratch, opts: &ParserOptions) -> Result<Date, Error> {
+    let txt = deser_text_from(end.as_ref(), rdr, scratch, opts)?;
+
+    Date::from_text(txt, end.as_ref())
+}
+
+/// like deser_text, as a date
+pub(crate) fn deser_date<B: BufRead>(name: &[u8], rdr: &mut quick_xml::Reader<B>, scratch: &mut Scratch, opts: &ParserOptions) -> Result<Date, Error> {
+    let txt = deser_text(name, rdr, scratch, opts)?;
+
+    Date::from_text(txt, name)
+}
+
+/// call when the start tag has already been consumed, now you need the text to the end tag
+fn deser_text<B: BufRead>(name: &[u8], rdr: &mut quick_xml::Reader<B>, scratch: &mut Scratch, opts: &ParserOptions) -> Result<String, Error> {
+    scratch.with(|buf, _| {
+        consume_start(rdr, buf, name)?;
+
+        buf.clear();
+
+        read_text(name, rdr, buf, opts)
+    })
+}
+
+/// special function for dealing with text which has nested tags.
+/// TODO handle nested same tags using depth counter
+pub(crate) fn deser_text_with_tags_from<B: BufRead>(end: &[u8], rdr: &mut quick_xml::Reader<B>, scratch: &mut Scratch, opts: &ParserOptions) -> Result<String, Error> {
+    deser_text_and_inline_from(end, rdr, scratch, opts, None)
+}
+
+/// Where deser_text_and_inline_from keeps the inline elements it reads
+struct Inline<'a> {
+    // only if `opts.formulae`
+    formulae: &'a mut Vec<Formula>,
+    figrefs: &'a mut Vec<FigRef>,
+}
+
+/// As deser_text_with_tags_from, also keeping formulae and figure
+/// references in inline. Their text is part of the text either way.
+fn deser_text_and_inline_from<B: BufRead>(
+    end: &[u8],
+    rdr: &mut quick_xml::Reader<B>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    mut inline: Option<Inline>,
+    ) -> Result<String, Error>
+{
+    scratch.with(|buf, scratch| {
+        // fragments are joined by a space as they're read
+        let mut text = String::new();
+        let mut frags = 0;
+
+        // depth starts at one because we already consumed first start tag.
+        let mut depth = 1;
+
+        loop {
+            match rdr.read_event(buf) {
+                Ok(Event::Start(ref e)) if e.name() == end => {
+                    depth += 1;
+                },
+                Ok(Event::Start(ref e)) if e.name() == b"maths" && opts.formulae && inline.is_some() => {
+                    let (formula, frag) = deser_maths_from(e, rdr, scratch, opts).within(b"maths")?;
+                    if !frag.is_empty() {
+                        push_frag(&mut text, &mut frags, &frag);
+                    }
+
+                    if let Some(ref mut inline) = inline {
+                        inline.formulae.push(formula);
+                    }
+                },
+                Ok(Event::Start(ref e)) if e.name() == b"figref" && inline.is_some() => {
+                    let figref = deser_figref_from(e, rdr, scratch, opts).within(b"figref")?;
+                    if !figref.text.is_empty() {
+                        push_frag(&mut text, &mut frags, &figref.text);
+                    }
+
+                    if let Some(ref mut inline) = inline {
+                        inline.figrefs.push(figref);
+                    }
+                },
+                Ok(Event::End(ref e)) => {
+                    if e.name() == end {
+                        depth -= 1;
+                    }
+
+                    // now final
+                    if e.name() == end && depth == 0 {
+                        break;
+                    }
+                },
+                Ok(Event::Text(e)) => {
+                    let frag = decode_text(&e, rdr, opts)?;
+
+                    push_frag(&mut text, &mut frags, &frag);
+                },
+                Err(err) => return Err(Error::deser(err.to_string())),
+                _ => {},
+            }
+            buf.clear();
+        }
+
+        Ok(text)
+    })
+}
+
+/// call with the figref start tag, after it's already hit
+fn deser_figref_from<B: BufRead>(
+    start: &BytesStart,
+    rdr: &mut quick_xml::Reader<B>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    ) -> Result<FigRef, Error>
+{
+    let mut figref = FigRef::default();
+
+    for attr_res in start.attributes() {
+        let attr = attr_res
+            .map_err(|err| Error::deser(err.to_string()))?;
+
+        match attr.key {
+            b"idref" => figref.idref = decode_attribute(&attr, rdr, opts)?,
+            _ => continue,
+        }
+    }
+
+    figref.text = deser_text_with_tags_from(b"figref", rdr, scratch, opts)?;
+
+    Ok(figref)
+}
+
+/// A formula, and its text (as deser_text_with_tags_from would give it).
+/// The MathML kept is the `math` element; anything else in the maths (e.g.
+/// its img) is dropped.
+///
+/// call with the maths start tag, after it's already hit
+fn deser_maths_from<B: BufRead>(
+    start: &BytesStart,
+    rdr: &mut quick_xml::Reader<B>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    ) -> Result<(Formula, String), Error>
+{
+    let mut formula = Formula::default();
+
+    for attr_res in start.attributes() {
+        let attr = attr_res
+            .map_err(|err| Error::deser(err.to_string()))?;
+
+        match attr.key {
+            b"id" => formula.id = decode_attribute(&attr, rdr, opts)?,
+            b"num" => formula.num = decode_attribute(&attr, rdr, opts)?,
+            _ => continue,
+        }
+    }
+
+    let (text, mathml) = scratch.with(|buf, _| {
+        let mut text = String::new();
+        let mut frags = 0;
+        let mut mathml = quick_xml::Writer::new(Vec::new());
+        // depth within the math element, 0 outside it
+        let mut depth = 0;
+
+        loop {
+            let event = rdr.read_event(buf)
+                .map_err(|err| Error::deser(err.to_string()))?;
+
+            match event {
+                Event::Start(ref e) if depth > 0 || e.local_name() == b"math" => depth += 1,
+                Event::End(ref e) if depth > 0 => {
+                    depth -= 1;
+                    // the math element's end tag is still written below
+                    if depth == 0 {
+                        mathml.write_event(Event::End(e.clone()))
+                            .map_err(|err| Error::deser(err.to_string()))?;
+                    }
+                },
+                Event::End(ref e) if e.name() == b"maths" => break,
+                Event::Text(ref e) => {
+                    let frag = decode_text(e, rdr, opts)?;
+
+                    push_frag(&mut text, &mut frags, &frag);
+                },
+                Event::Eof => return Err(Error::deser("unexpected eof in maths".to_string())),
+                _ => {},
+            }
+
+            if depth > 0 {
+                mathml.write_event(&event)
+                    .map_err(|err| Error::deser(err.to_string()))?;
+            }
+            buf.clear();
+        }
+
+        Ok((text, mathml.into_inner()))
+    })?;
+
+    formula.mathml = decode_owned(mathml, rdr, opts)?;
+    if let Some(ref latex) = opts.latex {
+        formula.latex = latex.0.to_latex(&formula.mathml);
+    }
+
+    Ok((formula, text))
+}
+
+/// appends frag to text, after a space if it isn't the first; as joining
+/// the fragments with spaces, without collecting them first
+fn push_frag(text: &mut String, frags: &mut usize, frag: &str) {
+    if *frags > 0 {
+        text.push(' ');
+    }
+    text.push_str(frag);
+    *frags += 1;
+}
+
+/// like str::trim, without copying
+fn trim_in_place(text: &mut String) {
+    let end = text.trim_end().len();
+    text.truncate(end);
+    let start = text.len() - text.trim_start().len();
+    text.drain(..start);
+}
+
+/// The government interest statement. USPTO marks it as its own GOVINT description
+/// section; older grants instead have it under a heading (e.g. "STATEMENT REGARDING
+/// FEDERALLY SPONSORED RESEARCH") in the related applications or brief summary.
+///
+/// Headings are not included, only the paragraphs.
+pub(crate) fn deser_government_interest(descriptions_raw: &HashMap<String, String>, opts: &ParserOptions) -> Result<Option<String>, Error> {
+    fn join(paragraphs: &[Paragraph]) -> String {
+        paragraphs.iter().map(|p| p.text.as_str()).collect::<Vec<_>>().join(" ")
+    }
+
+    if let Some(raw) = descriptions_raw.get("GOVINT") {
+        let paragraphs: Vec<_> = deser_raw_sections(raw, opts)?
+            .into_iter()
+            .flat_map(|(_, paragraphs)| paragraphs)
+            .collect();
+
+        return Ok(Some(join(&paragraphs)));
+    }
+
+    for section_name in &["RELAPP", "BRFSUM"] {
+        if let Some(raw) = descriptions_raw.get(*section_name) {
+            for (heading, paragraphs) in deser_raw_sections(raw, opts)? {
+                let is_govint = heading
+                    .map(|h| {
+                        let h = h.to_uppercase();
+                        h.contains("GOVERNMENT") || h.contains("FEDERALLY SPONSORED")
+                    })
+                    .unwrap_or(false);
+
+                if is_govint && !paragraphs.is_empty() {
+                    return Ok(Some(join(&paragraphs)));
+                }
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// The chemical structures in the description sections, by num. Most
+/// sections have none, so only those that do are read again.
+pub(crate) fn deser_description_chemistry(descriptions_raw: &HashMap<String, String>, opts: &ParserOptions) -> Result<Vec<Chemistry>, Error> {
+    let mut chemistry = Vec::new();
+    let mut scratch = Scratch::default();
+
+    for raw in descriptions_raw.values().filter(|raw| raw.contains("<chemistry")) {
+        let mut rdr = Reader::from_str(raw);
+        rdr.trim_text(opts.trim_text);
+        let mut buf = Vec::new();
+
+        loop {
+            match rdr.read_event(&mut buf) {
+                Ok(Event::Start(ref e)) if e.name() == b"chemistry" => {
+                    chemistry.push(deser_chemistry_from(e, &mut rdr, &mut scratch, opts).within(b"chemistry")?);
+                },
+                Ok(Event::Empty(ref e)) if e.name() == b"chemistry" => {
+                    chemistry.push(deser_chemistry_attrs(e, &rdr, opts)?);
+                },
+                Ok(Event::Eof) => break,
+                Ok(_) => {},
+                Err(err) => return Err(Error::deser(err.to_string())),
+            }
+            buf.clear();
+        }
+    }
+    chemistry.sort_by(|a, b| a.num.cmp(&b.num));
+
+    Ok(chemistry)
+}
+
+/// call with the chemistry start tag, after it's already hit. The files
+/// are those of its img and chem elements.
+fn deser_chemistry_from<B: BufRead>(
+    start: &BytesStart,
+    rdr: &mut quick_xml::Reader<B>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    ) -> Result<Chemistry, Error>
+{
+    let mut chemistry = deser_chemistry_attrs(start, rdr, opts)?;
+
+    scratch.with(|buf, scratch| {
+        loop {
+            match rdr.read_event(buf) {
+                Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) if e.name() == b"img" || e.name() == b"chem" => {
+                    chemistry.files.extend(deser_file_attr(e, rdr, opts)?);
+                },
+                Ok(Event::End(ref e)) if e.name() == b"chemistry" => break,
+                Ok(Event::Eof) => return Err(Error::deser("unexpected eof in chemistry".to_string())),
+                Ok(Event::Start(ref e)) => unknown_element(e.name(), "chemistry", rdr, scratch, opts)?,
+                Ok(_) => {},
+                Err(err) => return Err(Error::deser(err.to_string())),
+            }
+            buf.clear();
+        }
+
+        Ok(chemistry)
+    })
+}
+
+fn deser_chemistry_attrs<B: BufRead>(start: &BytesStart, rdr: &quick_xml::Reader<B>, opts: &ParserOptions) -> Result<Chemistry, Error> {
+    let mut chemistry = Chemistry::default();
+
+    for attr_res in start.attributes() {
+        let attr = attr_res
+            .map_err(|err| Error::deser(err.to_string()))?;
+
+        match attr.key {
+            b"id" => chemistry.id = decode_attribute(&attr, rdr, opts)?,
+            b"num" => chemistry.num = decode_attribute(&attr, rdr, opts)?,
+            _ => continue,
+        }
+    }
+
+    Ok(chemistry)
+}
+
+/// the element's file attribute, if any
+fn deser_file_attr<B: BufRead>(start: &BytesStart, rdr: &quick_xml::Reader<B>, opts: &ParserOptions) -> Result<Option<String>, Error> {
+    for attr_res in start.attributes() {
+        let attr = attr_res
+            .map_err(|err| Error::deser(err.to_string()))?;
+
+        if attr.key == b"file" {
+            return Ok(Some(decode_attribute(&attr, rdr, opts)?));
+        }
+    }
+
+    Ok(None)
+}
+
+/// The sequence listing section's ids and files, if there's one. Unless
+/// `opts.sequence_listing_text`, the section is then taken out of
+/// descriptions.
+pub(crate) fn deser_sequence_listing(
+    descriptions: &mut HashMap<String, String>,
+    descriptions_raw: &mut HashMap<String, String>,
+    opts: &ParserOptions,
+    ) -> Result<Option<SequenceListing>, Error>
+{
+    let raw = match descriptions_raw.get(SEQUENCE_LISTING) {
+        Some(raw) => raw,
+        None => return Ok(None),
+    };
+
+    let mut sequence_listing = SequenceListing::default();
+    let mut rdr = Reader::from_str(raw);
+    let mut buf = Vec::new();
+
+    loop {
+        match rdr.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
+                for attr_res in e.attributes() {
+                    let attr = attr_res
+                        .map_err(|err| Error::deser(err.to_string()))?;
+
+                    match attr.key {
+                        b"id" => sequence_listing.ids.push(decode_attribute(&attr, &rdr, opts)?),
+                        b"file" => sequence_listing.files.push(decode_attribute(&attr, &rdr, opts)?),
+                        _ => continue,
+                    }
+                }
+            },
+            Ok(Event::Eof) => break,
+            Ok(_) => {},
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+        buf.clear();
+    }
+
+    if !opts.sequence_listing_text {
+        descriptions.remove(SEQUENCE_LISTING);
+        descriptions_raw.remove(SEQUENCE_LISTING);
+    }
+
+    Ok(Some(sequence_listing))
+}
+
+/// the PI name of the sequence listing section
+const SEQUENCE_LISTING: &str = "SEQLST";
+
+/// a heading and the paragraphs under it
+pub(crate) type RawSection = (Option<String>, Vec<Paragraph>);
+
+/// Splits the raw xml of a description section into its headings and
+/// the paragraphs under each heading. Paragraphs before any heading get
+/// a heading of None.
+pub(crate) fn deser_raw_sections(raw: &str, opts: &ParserOptions) -> Result<Vec<RawSection>, Error> {
+    let mut rdr = Reader::from_str(raw);
+    rdr.trim_text(opts.trim_text);
+    let mut buf = Vec::new();
+    let mut scratch = Scratch::default();
+
+    let mut sections: Vec<RawSection> = Vec::new();
+
+    loop {
+        match rdr.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"heading" => {
+                        let heading = deser_text_with_tags_from(b"heading", &mut rdr, &mut scratch, opts).within(b"heading")?;
+                        sections.push((Some(heading), Vec::new()));
+                    },
+                    b"p" => {
+                        let mut paragraph = Paragraph::default();
+                        deser_paragraph_from(e, &mut rdr, &mut scratch, opts, &mut paragraph).within(b"p")?;
+
+                        if sections.is_empty() {
+                            sections.push((None, Vec::new()));
+                        }
+                        sections.last_mut().expect("sections not empty").1.push(paragraph);
+                    },
+                    // wrappers, e.g. description-of-drawings
+                    _ => continue,
+                }
+            },
+            Ok(Event::Eof) => break,
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+        buf.clear();
+    }
+
+    Ok(sections)
+}
+
+/// special function for dealing with text which has nested tags, and which will read
+/// to the PI tag with the same name that has end=tail
+///
+/// returns the text (tags dropped) and the raw inner xml (tags kept).
+fn deser_pi_text_with_tags_to_tail_from<B: BufRead>(pi_name: &str, rdr: &mut quick_xml::Reader<B>, scratch: &mut Scratch, opts: &ParserOptions) -> Result<(String, String), Error> {
+    // don't trim while capturing, otherwise the raw xml loses the whitespace
+    // between text and inline tags (e.g. "shown in <figref>").
+    rdr.trim_text(false);
+    let res = deser_pi_section_to_tail_from(pi_name, rdr, scratch, opts);
+    rdr.trim_text(opts.trim_text);
+
+    res
+}
+
+fn deser_pi_section_to_tail_from<B: BufRead>(pi_name: &str, rdr: &mut quick_xml::Reader<B>, scratch: &mut Scratch, opts: &ParserOptions) -> Result<(String, String), Error> {
+    let mut text = String::new();
+    let mut raw = quick_xml::Writer::new(Vec::new());
+
+    scratch.with(|buf, _| {
+        loop {
+            let event = rdr.read_event(buf)
+                .map_err(|err| Error::deser(err.to_string()))?;
+
+            match event {
+                Event::PI(ref tag_bytes) => {
+                    // search for the tail with the same name; nested PI (e.g. in-line-formulae)
+                    // have their own lead and tail.
+                    let pi_tag_res = tag_bytes.unescape_and_decode(rdr);
+
+                    if let Ok(ref s) = pi_tag_res {
+                        let name = s.split_whitespace().next().ok_or_else(|| Error::deser("No name for PI".to_string()))?;
+                        let end = s.split_whitespace().last().ok_or_else(|| Error::deser("No end for PI".to_string()))?;
+
+                        if name == pi_name && end == "end=\"tail\"" {
+                            break;
+                        }
+                    }
+                },
+                Event::Text(ref e) => {
+                    let frag = decode_text(e, rdr, opts)?;
+
+                    let frag = frag.trim();
+                    if !frag.is_empty() {
+                        if !text.is_empty() {
+                            text.push(' ');
+                        }
+                        text.push_str(frag);
+                    }
+                },
+                Event::Eof => return Err(Error::deser(format!("unexpected eof looking for tail of PI {}", pi_name))),
+                _ => {},
+            }
+
+            raw.write_event(&event)
+                .map_err(|err| Error::deser(err.to_string()))?;
+
+            buf.clear();
+        }
+
+        Ok(())
+    })?;
+
+    let mut raw = decode_owned(raw.into_inner(), rdr, opts)?;
+    trim_in_place(&mut raw);
+
+    Ok((text, raw))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::{Decoding, Entities, SkippedElements};
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/grants.xml");
+
+    fn fixture_grants() -> Vec<PatentGrant> {
+        PatentGrants::from_reader(FIXTURE.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .expect("fixture should parse")
+    }
+
+    #[test]
+    fn test_classifications_cpc() {
+        let grants = fixture_grants();
+        assert_eq!(grants.len(), 3);
+
+        let cpc = &grants[0].us_bibliographic_data_grant.classifications_cpc;
+        assert_eq!(cpc.main_cpc.section, "A");
+        assert_eq!(cpc.main_cpc.class, "01");
+        assert_eq!(cpc.main_cpc.subclass, "B");
+        assert_eq!(cpc.main_cpc.main_group, "1");
+        assert_eq!(cpc.main_cpc.subgroup, "00");
+        assert_eq!(cpc.main_cpc.cpc_version_indicator, "20130101");
+        assert_eq!(date_text(&cpc.main_cpc.action_date), "20190101");
+        assert_eq!(cpc.main_cpc.generating_office, "US");
+        assert_eq!(cpc.main_cpc.scheme_origination_code.as_deref(), Some("C"));
+        assert_eq!(cpc.further_cpc.len(), 2);
+        assert_eq!(cpc.further_cpc[1].section, "Y");
+        assert_eq!(cpc.further_cpc[1].classification_value, "A");
+
+        // design patents have no cpc
+        let cpc = &grants[1].us_bibliographic_data_grant.classifications_cpc;
+        assert_eq!(cpc.main_cpc.section, "");
+        assert!(cpc.further_cpc.is_empty());
+    }
+
+    #[test]
+    fn test_claims() {
+        let grants = fixture_grants();
+
+        let claims = &grants[0].claims;
+        assert_eq!(claims.len(), 3);
+
+        // independent claim, with nested claim-text
+        assert_eq!(claims[0].id, "CLM-00001");
+        assert_eq!(claims[0].num, "00001");
+        assert_eq!(claims[0].claim_texts.len(), 1);
+        let lead = &claims[0].claim_texts[0];
+        assert_eq!(lead.text, "1. A hand tool comprising:");
+        assert_eq!(lead.claim_texts.len(), 2);
+        assert_eq!(lead.claim_texts[0].text, "a body; and");
+        assert!(lead.claim_refs.is_empty());
+
+        // dependent claims
+        let refs = &claims[1].claim_texts[0].claim_refs;
+        assert_eq!(refs.len(), 1);
+        assert_eq!(refs[0].idref, "CLM-00001");
+        assert_eq!(refs[0].text, "claim 1");
+        assert!(claims[1].claim_texts[0].text.contains("FIG. 2A"));
+
+        let refs = &claims[2].claim_texts[0].claim_refs;
+        assert_eq!(refs.iter().map(|r| r.idref.as_str()).collect::<Vec<_>>(), vec!["CLM-00001", "CLM-00002"]);
+    }
+
+    #[test]
+    fn test_descriptions_raw() {
+        let grants = fixture_grants();
+
+        let raw = &grants[0].descriptions_raw["DETDESC"];
+        assert!(raw.starts_with("<heading id=\"h-0005\" level=\"1\">DETAILED DESCRIPTION</heading>"));
+        assert!(raw.contains("As shown in <figref idref=\"DRAWINGS\">FIG. 1</figref>, the body <b>10</b> is made of steel."));
+        // nested in-line-formulae PI doesn't end the section
+        assert!(raw.contains("<?in-line-formulae description=\"In-line Formulae\" end=\"tail\"?>"));
+        assert!(raw.contains("<entry>12.5</entry>"));
+        assert!(!raw.contains("DETDESC"));
+
+        let text = &grants[0].descriptions["DETDESC"];
+        assert!(text.starts_with("DETAILED DESCRIPTION As shown in FIG. 1"));
+        assert!(text.contains("where F is force."));
+
+        assert_eq!(grants[0].descriptions.len(), 5);
+        assert_eq!(grants[0].descriptions_raw.len(), 5);
+    }
+
+    #[test]
+    fn test_abstract() {
+        let grants = fixture_grants();
+
+        let abst = &grants[0].r#abstract;
+        assert_eq!(abst.len(), 2);
+        assert_eq!(abst[0].id, "p-0001");
+        assert_eq!(abst[0].num, "0000");
+        assert_eq!(abst[0].text, "A hand tool includes a body and a handle attached to the body.");
+        assert_eq!(abst[1].text, "The handle has an ergonomic grip.");
+
+        // design patents have no abstract
+        assert!(grants[1].r#abstract.is_empty());
+        assert_eq!(grants[2].r#abstract.len(), 1);
+    }
+
+    #[test]
+    fn test_grant_meta() {
+        let grants = fixture_grants();
+
+        let meta = &grants[0].meta;
+        assert_eq!(meta.lang, "EN");
+        assert_eq!(meta.country, "US");
+        assert_eq!(meta.dtd_version.as_deref(), Some("v4.5 2014-04-03"));
+        assert_eq!(meta.file.as_deref(), Some("US10165721-20190101.XML"));
+        assert_eq!(meta.status.as_deref(), Some("PRODUCTION"));
+        assert_eq!(meta.date_produced.as_deref(), Some("20181218"));
+        assert_eq!(meta.date_publ.as_deref(), Some("20190101"));
+
+        let title = &grants[0].us_bibliographic_data_grant.invention_title;
+        assert_eq!(title.id.as_deref(), Some("d2e61"));
+        assert_eq!(title.title, "Hand tool with ergonomic grip");
+    }
+
+    #[test]
+    fn test_examiners() {
+        let grants = fixture_grants();
+
+        let examiners = &grants[0].us_bibliographic_data_grant.examiners;
+        assert_eq!(examiners.primary_examiner.last_name, "Novosad");
+        assert_eq!(examiners.primary_examiner.first_name, "Christopher J");
+        assert_eq!(examiners.art_unit(), Some("3671"));
+        let assistant = examiners.assistant_examiner.as_ref().expect("assistant examiner");
+        assert_eq!(assistant.last_name, "Lee");
+        assert_eq!(assistant.department, None);
+
+        let examiners = &grants[1].us_bibliographic_data_grant.examiners;
+        assert_eq!(examiners.art_unit(), Some("2913"));
+        assert!(examiners.assistant_examiner.is_none());
+    }
+
+    #[test]
+    fn test_priority_claims() {
+        let grants = fixture_grants();
+
+        let claims = &grants[0].us_bibliographic_data_grant.priority_claims;
+        assert_eq!(claims.len(), 2);
+        assert_eq!(claims[0].sequence, "01");
+        assert_eq!(claims[0].kind, "national");
+        assert_eq!(claims[0].country, CountryCode::from("JP"));
+        assert_eq!(claims[0].doc_number.as_deref(), Some("2015-101492"));
+        assert_eq!(date_text(&claims[1].date), "20150925");
+
+        assert!(grants[1].us_bibliographic_data_grant.priority_claims.is_empty());
+    }
+
+    #[test]
+    fn test_projection() {
+        let options = ParserOptions::new().projection(Projection::CLAIMS);
+        let grants = PatentGrants::with_options(FIXTURE.as_bytes(), options)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let biblio = &grants[0].us_bibliographic_data_grant;
+        assert_eq!(biblio.publication_reference.doc_number, "10165721");
+        assert!(biblio.invention_title.title.is_empty());
+        assert!(biblio.inventors.is_empty());
+        assert!(grants[0].r#abstract.is_empty());
+        assert!(grants[0].descriptions.is_empty());
+        assert_eq!(grants[0].claims.len(), 3);
+
+        let options = ParserOptions::new().projection(Projection::BIBLIO | Projection::PARTIES);
+        let grants = PatentGrants::with_options(FIXTURE.as_bytes(), options)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let biblio = &grants[0].us_bibliographic_data_grant;
+        assert!(!biblio.invention_title.title.is_empty());
+        assert!(!biblio.inventors.is_empty());
+        assert!(biblio.us_references_cited.is_empty());
+        assert!(grants[0].claims.is_empty());
+
+        assert!(Projection::ALL.contains(Projection::CITATIONS | Projection::CLAIMS));
+        assert!(!Projection::ALL.without(Projection::CLAIMS).contains(Projection::CLAIMS));
+    }
+
+    #[test]
+    fn test_biblio_only() {
+        let grants = fixture_grants();
+        let biblios = PatentGrants::from_reader(FIXTURE.as_bytes())
+            .biblio_only()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(biblios.len(), grants.len());
+
+        for (biblio, grant) in biblios.iter().zip(&grants) {
+            let doc_id = &biblio.us_bibliographic_data_grant.publication_reference;
+            assert_eq!(doc_id.doc_number, grant.us_bibliographic_data_grant.publication_reference.doc_number);
+            assert_eq!(biblio.us_bibliographic_data_grant.inventors.len(), grant.us_bibliographic_data_grant.inventors.len());
+            assert_eq!(biblio.r#abstract.len(), grant.r#abstract.len());
+            assert_eq!(biblio.meta.dtd_version, grant.meta.dtd_version);
+        }
+    }
+
+    #[test]
+    fn test_filter_raw() {
+        let grants = fixture_grants();
+
+        let mut peeked = Vec::new();
+        let filtered = PatentGrants::from_reader(FIXTURE.as_bytes())
+            .filter_raw(|doc_id| {
+                peeked.push(doc_id.doc_number.clone());
+                doc_id.kind != Some(KindCode::S1)
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(peeked, vec!["10165721", "D0838001", "PP030001"]);
+
+        // the grant after the skipped one parses whole
+        assert_eq!(filtered.len(), 2);
+        for (grant, expected) in filtered.iter().zip(&[&grants[0], &grants[2]]) {
+            assert_eq!(format!("{:?}", grant.us_bibliographic_data_grant), format!("{:?}", expected.us_bibliographic_data_grant));
+            assert_eq!(format!("{:?}", grant.claims), format!("{:?}", expected.claims));
+            assert_eq!(grant.descriptions, expected.descriptions);
+        }
+
+        let none = PatentGrants::from_reader(FIXTURE.as_bytes()).filter_raw(|_| false).count();
+        assert_eq!(none, 0);
+    }
+
+    #[test]
+    fn test_next_into() {
+        let grants = fixture_grants();
+
+        let mut patent_grants = PatentGrants::from_reader(FIXTURE.as_bytes());
+        let mut patent_grant = PatentGrant::default();
+        let mut count = 0;
+        while let Some(res) = patent_grants.next_into(&mut patent_grant) {
+            res.unwrap();
+            let grant = &grants[count];
+
+            // nothing left over from the grant before
+            assert_eq!(format!("{:?}", patent_grant.us_bibliographic_data_grant), format!("{:?}", grant.us_bibliographic_data_grant));
+            assert_eq!(format!("{:?}", patent_grant.claims), format!("{:?}", grant.claims));
+            assert_eq!(format!("{:?}", patent_grant.r#abstract), format!("{:?}", grant.r#abstract));
+            assert_eq!(patent_grant.descriptions, grant.descriptions);
+            assert_eq!(patent_grant.us_claim_statement, grant.us_claim_statement);
+
+            count += 1;
+        }
+        assert_eq!(count, grants.len());
+    }
+
+    #[test]
+    fn test_doc_type() {
+        let grants = fixture_grants();
+
+        assert_eq!(grants[0].us_bibliographic_data_grant.publication_reference.kind, Some(KindCode::B2));
+        let doc_types: Vec<_> = grants.iter().map(PatentGrant::doc_type).collect();
+        assert_eq!(doc_types, vec![Some(DocType::Utility), Some(DocType::Design), Some(DocType::Plant)]);
+
+        // foreign kind codes are kept as is, trimmed
+        let foreign = KindCode::from("00");
+        assert_eq!(foreign, KindCode::Other("00".to_string()));
+        assert_eq!(foreign.doc_type(), None);
+        assert_eq!(KindCode::from("A "), KindCode::A);
+        assert_eq!(KindCode::E1.to_string(), "E1");
+    }
+
+    #[test]
+    fn test_us_related_documents() {
+        let grants = fixture_grants();
+
+        let related = &grants[0].us_bibliographic_data_grant.us_related_documents;
+        assert_eq!(related.len(), 3);
+
+        match &related[0] {
+            RelatedDocument::ContinuationInPart(relation) => {
+                assert_eq!(relation.parent_doc.document_id.doc_number, "14500123");
+                assert_eq!(relation.parent_doc.parent_status.as_deref(), Some("ABANDONED"));
+                assert_eq!(relation.child_docs.len(), 1);
+                assert_eq!(relation.child_docs[0].document_id.doc_number, "15154638");
+            },
+            other => panic!("expected continuation-in-part, found {:?}", other),
+        }
+        match &related[1] {
+            RelatedDocument::ProvisionalApplication(provisional) => {
+                assert_eq!(provisional.document_id.doc_number, "62161234");
+                assert_eq!(date_text(&provisional.document_id.date), "20150513");
+            },
+            other => panic!("expected provisional application, found {:?}", other),
+        }
+        match &related[2] {
+            RelatedDocument::RelatedPublication(doc_id) => assert_eq!(doc_id.kind, Some(KindCode::A1)),
+            other => panic!("expected related publication, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_pct_data() {
+        let grants = fixture_grants();
+        let biblio = &grants[0].us_bibliographic_data_grant;
+
+        let filing = biblio.pct_or_regional_filing_data.as_ref().expect("pct filing data");
+        assert_eq!(filing.document_id.country, CountryCode::from("WO"));
+        assert_eq!(filing.document_id.doc_number, "PCT/JP2016/064321");
+        assert_eq!(filing.us_371c124_date.as_deref(), Some("20171101"));
+        assert_eq!(filing.us_371c12_date, None);
+
+        let publishing = biblio.pct_or_regional_publishing_data.as_ref().expect("pct publishing data");
+        assert_eq!(publishing.document_id.doc_number, "WO2016/186012");
+        assert_eq!(publishing.document_id.kind, Some(KindCode::A));
+
+        assert!(grants[1].us_bibliographic_data_grant.pct_or_regional_filing_data.is_none());
+    }
+
+    #[test]
+    fn test_us_term_of_grant() {
+        let grants = fixture_grants();
+
+        let term = &grants[0].us_bibliographic_data_grant.us_term_of_grant;
+        assert_eq!(term.length_of_grant, None);
+        assert_eq!(term.us_term_extension.as_deref(), Some("201"));
+        let disclaimer = term.disclaimer.as_ref().expect("terminal disclaimer");
+        assert_eq!(disclaimer.text.as_deref(), Some("This patent is subject to a terminal disclaimer."));
+
+        let term = &grants[1].us_bibliographic_data_grant.us_term_of_grant;
+        assert_eq!(term.length_of_grant.as_deref(), Some("15"));
+        assert!(term.disclaimer.is_none());
+    }
+
+    #[test]
+    fn test_figures() {
+        let grants = fixture_grants();
+
+        let figures = &grants[0].us_bibliographic_data_grant.figures;
+        assert_eq!(figures.number_of_drawing_sheets, Some(3));
+        assert_eq!(figures.number_of_figures, Some(5));
+    }
+
+    #[test]
+    fn test_number_of_claims() {
+        let grants: Vec<_> = PatentGrants::from_reader(FIXTURE.as_bytes())
+            .strict(true)
+            .collect::<Result<_, _>>()
+            .expect("fixture should pass strict checks");
+
+        let biblio = &grants[0].us_bibliographic_data_grant;
+        assert_eq!(biblio.number_of_claims, Some(3));
+        assert_eq!(biblio.us_exemplary_claim, vec![1]);
+
+        let bad = FIXTURE.replacen("<number-of-claims>3</number-of-claims>", "<number-of-claims>4</number-of-claims>", 1);
+        let mut grants = PatentGrants::from_reader(bad.as_bytes()).strict(true);
+        assert!(grants.next().unwrap().is_err());
+
+        // not checked when not strict
+        let mut grants = PatentGrants::from_reader(bad.as_bytes());
+        assert!(grants.next().unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_field_of_classification_search() {
+        let grants = fixture_grants();
+        let biblio = &grants[0].us_bibliographic_data_grant;
+
+        // not overwritten by the classification-national of a citation
+        assert_eq!(biblio.classification_national.main_classification, Uspc::new("172", "13"));
+
+        let search = &biblio.us_field_of_classification_search;
+        assert_eq!(search.classification_nationals.len(), 2);
+        assert_eq!(search.classification_nationals[1].main_classification, Uspc::new("294", "49-59"));
+        assert_eq!(search.classification_cpc_texts, vec!["A01B 1/00", "B25G 1/102"]);
+        assert_eq!(search.classification_cpc_combination_texts, vec!["A01B 1/00 B25G 1/102"]);
+    }
+
+    #[test]
+    fn test_us_botanic() {
+        let grants = fixture_grants();
+
+        let botanic = grants[2].us_bibliographic_data_grant.botanic.as_ref().expect("plant patent botanic");
+        assert_eq!(botanic.latin_name, "Rosa hybrid");
+        assert_eq!(botanic.variety, "RED STAR");
+
+        assert!(grants[0].us_bibliographic_data_grant.botanic.is_none());
+    }
+
+    #[test]
+    fn test_hague_agreement_data() {
+        let grants = fixture_grants();
+
+        let hague = grants[1].us_bibliographic_data_grant.hague_agreement_data.as_ref().expect("hague data");
+        assert_eq!(hague.international_filing_date, "20170615");
+        assert_eq!(hague.international_registration_publication_date, "20171215");
+        assert_eq!(hague.international_registration_number, "DM/098765");
+        assert_eq!(hague.international_registration_date, "20170615");
+
+        assert!(grants[0].us_bibliographic_data_grant.hague_agreement_data.is_none());
+    }
+
+    #[test]
+    fn test_government_interest() {
+        let grants = fixture_grants();
+
+        assert_eq!(
+            grants[0].government_interest.as_deref(),
+            Some("This invention was made with government support under Grant No. 1234567 awarded by the National Science Foundation. The government has certain rights in the invention."),
+        );
+        assert!(grants[1].government_interest.is_none());
+
+        // statement under a heading instead of a GOVINT section
+        let mut descriptions_raw = HashMap::new();
+        descriptions_raw.insert("BRFSUM".to_string(), r#"<heading id="h-0001" level="1">BACKGROUND</heading>
+<p id="p-0001" num="0001">Background.</p>
+<heading id="h-0002" level="1">STATEMENT OF GOVERNMENT INTEREST</heading>
+<p id="p-0002" num="0002">Made with support from DARPA.</p>"#.to_string());
+        assert_eq!(deser_government_interest(&descriptions_raw, &ParserOptions::default()).unwrap().as_deref(), Some("Made with support from DARPA."));
+    }
+
+    #[test]
+    fn test_classifications_ipcr() {
+        let grants = fixture_grants();
+
+        let ipcr = &grants[0].us_bibliographic_data_grant.classifications_ipcr;
+        assert_eq!(ipcr.len(), 2);
+        assert_eq!(ipcr[0].ipc_version_indicator, "20060101");
+        assert_eq!(ipcr[0].classification_level.as_deref(), Some("A"));
+        assert_eq!(ipcr[1].section, "B");
+        assert_eq!(ipcr[1].class, "25");
+        assert_eq!(ipcr[1].subclass, "G");
+        assert_eq!(ipcr[1].main_group.as_deref(), Some("1"));
+        assert_eq!(ipcr[1].subgroup.as_deref(), Some("10"));
+        assert_eq!(ipcr[1].symbol_position.as_deref(), Some("L"));
+        assert_eq!(ipcr[1].action_date.as_ref().map(date_text).as_deref(), Some("20190101"));
+
+        assert!(grants[1].us_bibliographic_data_grant.classifications_ipcr.is_empty());
+    }
+
+    #[test]
+    fn test_us_references_cited() {
+        let grants = fixture_grants();
+        let biblio = &grants[0].us_bibliographic_data_grant;
+
+        let citations = &biblio.us_references_cited;
+        assert_eq!(citations.len(), 3);
+
+        match &citations[0].citation {
+            Citation::Patent(patcit) => {
+                assert_eq!(patcit.num, "00001");
+                assert_eq!(patcit.document_id.doc_number, "4418955");
+                assert_eq!(patcit.document_id.name.as_deref(), Some("Muckle"));
+            },
+            other => panic!("expected patcit, got {:?}", other),
+        }
+        assert_eq!(citations[0].category.as_deref(), Some("cited by examiner"));
+        assert_eq!(citations[0].classification_cpc_text.as_deref(), Some("A01B 1/00"));
+        assert_eq!(citations[0].classification_national.as_ref().unwrap().main_classification, Uspc::new("294", "49"));
+
+        match &citations[2].citation {
+            Citation::NonPatent(nplcit) => assert!(nplcit.othercit.starts_with("Office Action issued")),
+            other => panic!("expected nplcit, got {:?}", other),
+        }
+
+        // citations' classification-national doesn't leak into the grant's
+        assert_eq!(biblio.classification_national.main_classification, Uspc::new("172", "13"));
+    }
+
+    #[test]
+    fn test_legacy_element_names() {
+        // v4.1: references-cited, parties/applicants, field-of-search
+        let grants = PatentGrants::from_reader(&include_bytes!("../tests/fixtures/grant_v41.xml")[..])
+            .strict(true)
+            .collect::<Result<Vec<_>, _>>()
+            .expect("fixture should parse");
+        assert_eq!(grants.len(), 1);
+
+        let biblio = &grants[0].us_bibliographic_data_grant;
+        assert_eq!(biblio.us_references_cited.len(), 2);
+        assert_eq!(biblio.us_references_cited[1].category.as_deref(), Some("cited by other"));
+        assert_eq!(biblio.us_applicants.len(), 1);
+        assert_eq!(biblio.us_applicants[0].app_type, "applicant-inventor");
+        assert_eq!(biblio.us_applicants[0].residence.as_deref(), Some("US"));
+        assert_eq!(biblio.us_field_of_classification_search.classification_nationals.len(), 1);
+    }
+
+    #[test]
+    fn test_parser_options() {
+        let first = |input: &str, options| PatentGrants::with_options(input.as_bytes(), options).next().unwrap();
+
+        assert!(PatentGrants::with_options(FIXTURE.as_bytes(), ParserOptions::strict()).all(|res| res.is_ok()));
+
+        // unknown elements, in the biblio and in an element parsed by the macros
+        let unknown = FIXTURE
+            .replacen("<us-references-cited>", "<new-element><p>text</p></new-element><us-references-cited>", 1)
+            .replacen("<main-classification>", "<new-element>D11</new-element><main-classification>", 1);
+        let grant = first(&unknown, ParserOptions::default()).unwrap();
+        assert_eq!(grant.us_bibliographic_data_grant.us_references_cited.len(), fixture_grants()[0].us_bibliographic_data_grant.us_references_cited.len());
+        let err = first(&unknown, ParserOptions::strict()).unwrap_err();
+        assert!(err.to_string().contains(r#"unrecognized element "new-element" in classification-national"#), "{}", err);
+        let unknown = unknown.replacen("<new-element>D11</new-element>", "", 1);
+        let err = first(&unknown, ParserOptions::strict()).unwrap_err();
+        assert!(err.to_string().contains(r#"unrecognized element "new-element" in us-bibliographic-data-grant"#), "{}", err);
+
+        let missing = FIXTURE.replacen("<doc-number>10165721</doc-number>", "", 1);
+        assert!(first(&missing, ParserOptions::default()).is_ok());
+        let err = first(&missing, ParserOptions::strict()).unwrap_err();
+        assert!(err.to_string().contains(r#"missing element "doc-number" in document-id"#), "{}", err);
+
+        let entities = FIXTURE.replacen("Hand tool with", "Hand &amp; &mgr; tool with", 1);
+        assert!(first(&entities, ParserOptions::default()).is_err());
+        let grant = first(&entities, ParserOptions::lenient()).unwrap();
+        assert_eq!(grant.us_bibliographic_data_grant.invention_title.title, "Hand &amp; &mgr; tool with ergonomic grip");
+        let grant = first(&entities.replacen("&mgr;", "&mgr; &nosuch;", 1), ParserOptions::default().entities(Entities::Dtd)).unwrap();
+        assert_eq!(grant.us_bibliographic_data_grant.invention_title.title, "Hand & \u{3bc} &nosuch; tool with ergonomic grip");
+        let entities = FIXTURE.replacen("Hand tool with", "Hand &amp; tool with", 1);
+        let grant = first(&entities, ParserOptions::lenient()).unwrap();
+        assert_eq!(grant.us_bibliographic_data_grant.invention_title.title, "Hand & tool with ergonomic grip");
+        let grant = first(&entities, ParserOptions::default().entities(Entities::Raw)).unwrap();
+        assert_eq!(grant.us_bibliographic_data_grant.invention_title.title, "Hand &amp; tool with ergonomic grip");
+    }
+
+    #[test]
+    fn test_decoding() {
+        let first = |input: &[u8], options| PatentGrants::with_options(input, options).next().unwrap();
+
+        // a stray Windows-1252 right quote (marked by \u{1} until it's put
+        // in), in the title and in a description section's raw xml
+        let stray: Vec<u8> = FIXTURE
+            .replacen("Hand tool with", "Hand\u{1}s tool with", 1)
+            .replacen("<description id=\"description\">", "<description id=\"description\"><?COMPUTER-PROGRAM-LISTING description=\"Listing\" end=\"lead\"?><p id=\"p-9\" num=\"9\">Hand\u{1}s</p><?COMPUTER-PROGRAM-LISTING description=\"Listing\" end=\"tail\"?>", 1)
+            .bytes()
+            .map(|b| if b == 1 { 0x92 } else { b })
+            .collect();
+
+        let grant = first(&stray, ParserOptions::default()).unwrap();
+        assert_eq!(grant.us_bibliographic_data_grant.invention_title.title, "Hand\u{fffd}s tool with ergonomic grip");
+        assert!(first(&stray, ParserOptions::default().decoding(Decoding::Strict)).is_err());
+        let grant = first(&stray, ParserOptions::default().decoding(Decoding::Fallback(encoding_rs::WINDOWS_1252))).unwrap();
+        assert_eq!(grant.us_bibliographic_data_grant.invention_title.title, "Hand\u{2019}s tool with ergonomic grip");
+        assert!(grant.descriptions_raw["COMPUTER-PROGRAM-LISTING"].contains("Hand\u{2019}s"));
+    }
+
+    #[test]
+    fn test_attribute_decoding() {
+        let first = |input: &[u8], options| PatentGrants::with_options(input, options).next().unwrap();
+
+        // attribute values are decoded as text is, per the options
+        let entities = FIXTURE.replacen(r#"lang="EN""#, r#"lang="E&mgr;N""#, 1);
+        let err = first(entities.as_bytes(), ParserOptions::default()).unwrap_err();
+        assert!(err.to_string().contains("us-patent-grant"), "{}", err);
+        let grant = first(entities.as_bytes(), ParserOptions::lenient()).unwrap();
+        assert_eq!(grant.meta.lang, "E&mgr;N");
+        let grant = first(entities.as_bytes(), ParserOptions::default().entities(Entities::Dtd)).unwrap();
+        assert_eq!(grant.meta.lang, "E\u{3bc}N");
+
+        let stray: Vec<u8> = FIXTURE
+            .replacen(r#"status="PRODUCTION""#, "status=\"PRODUCTION\u{1}\"", 1)
+            .bytes()
+            .map(|b| if b == 1 { 0x92 } else { b })
+            .collect();
+        assert!(first(&stray, ParserOptions::default().decoding(Decoding::Strict)).is_err());
+        let grant = first(&stray, ParserOptions::default().decoding(Decoding::Fallback(encoding_rs::WINDOWS_1252))).unwrap();
+        assert_eq!(grant.meta.status.as_deref(), Some("PRODUCTION\u{2019}"));
+    }
+
+    #[test]
+    fn test_formulae() {
+        use crate::maths::BasicLatex;
+
+        let maths = r#"<maths id="MATH-US-00002" num="00002"><math overflow="scroll"><msup><mi>x</mi><mn>2</mn></msup></math><img id="EMI-M00002" file="US0-M00002.TIF"/></maths>"#;
+        let with_maths = FIXTURE
+            .replacen("The handle has an <i>ergonomic</i> grip.", &format!("The handle follows {} closely.", maths), 1)
+            .replacen("a body; and</claim-text>", &format!("a body of width {}; and</claim-text>", maths), 1);
+        let first = |options| PatentGrants::with_options(with_maths.as_bytes(), options).next().unwrap().unwrap();
+
+        // not kept by default, the text is the same either way
+        let grant = first(ParserOptions::default());
+        assert!(grant.r#abstract[1].formulae.is_empty());
+        assert_eq!(grant.r#abstract[1].text, "The handle follows x 2 closely.");
+
+        let grant = first(ParserOptions::default().formulae(true));
+        let paragraph = &grant.r#abstract[1];
+        assert_eq!(paragraph.text, "The handle follows x 2 closely.");
+        assert_eq!(paragraph.formulae.len(), 1);
+        assert_eq!(paragraph.formulae[0].id, "MATH-US-00002");
+        assert_eq!(paragraph.formulae[0].mathml, r#"<math overflow="scroll"><msup><mi>x</mi><mn>2</mn></msup></math>"#);
+        assert!(paragraph.formulae[0].latex.is_none());
+        let claim_text = &grant.claims[0].claim_texts[0].claim_texts[0];
+        assert_eq!(claim_text.text, "a body of width x 2 ; and");
+        assert_eq!(claim_text.formulae[0].num, "00002");
+
+        let grant = first(ParserOptions::default().latex(BasicLatex));
+        assert_eq!(grant.r#abstract[1].formulae[0].latex.as_deref(), Some("x^{2}"));
+    }
+
+    #[test]
+    fn test_figrefs() {
+        let with_figref = FIXTURE.replacen("a body; and</claim-text>", "a body, as in <figref idref=\"DRAWINGS\">FIG. <b>2</b></figref>; and</claim-text>", 1);
+        let grant = PatentGrants::from_reader(with_figref.as_bytes()).next().unwrap().unwrap();
+
+        let sections = deser_raw_sections(&grant.descriptions_raw["brief-description-of-drawings"], &ParserOptions::default()).unwrap();
+        let paragraph = &sections[0].1[1];
+        assert_eq!(paragraph.text, "FIGS. 2A and 2B are side views of the handle.");
+        assert_eq!((paragraph.figrefs[0].idref.as_str(), paragraph.figrefs[0].text.as_str()), ("DRAWINGS", "FIGS. 2A and 2B"));
+
+        let claim_text = &grant.claims[0].claim_texts[0].claim_texts[0];
+        assert_eq!(claim_text.text, "a body, as in FIG. 2 ; and");
+        assert_eq!(claim_text.figrefs[0].text, "FIG. 2");
+    }
+
+    #[test]
+    fn test_chemistry_and_sequence_listing() {
+        let chemistry = |n: u32| format!(
+            r#"<chemistry id="CHEM-US-{n:05}" num="{n:05}"><img id="EMI-C{n:05}" he="30mm" wi="50mm" file="US11223344-20220111-C{n:05}.TIF" alt="chemistry" img-content="chem" img-format="tif"/><chem type="cdx" file="US11223344-20220111-C{n:05}.CDX"/><chem type="mol" file="US11223344-20220111-C{n:05}.MOL"/></chemistry>"#,
+            n = n,
+        );
+        let sequence_listing = r#"<?SEQLST description="Sequence Listing" end="lead"?><p id="p-9001" num="9001">SEQ ID NO: 1 ... <doc-page id="SEQ-1" file="US11223344-20220111-S00001.TIF"/></p><?SEQLST description="Sequence Listing" end="tail"?>"#;
+        let with_chemistry = FIXTURE
+            .replacen("where F is force.</p>", &format!("where F is force.</p><p id=\"p-0100\" num=\"0100\">{}</p>", chemistry(2)), 1)
+            .replacen("<?DETDESC description=\"Detailed Description\" end=\"tail\"?>", &format!("<?DETDESC description=\"Detailed Description\" end=\"tail\"?>{}", sequence_listing), 1)
+            .replacen("a body; and</claim-text>", &format!("a body of {}; and</claim-text>", chemistry(1)), 1);
+
+        let grant = PatentGrants::from_reader(with_chemistry.as_bytes()).next().unwrap().unwrap();
+        assert_eq!(grant.chemistry.len(), 1);
+        assert_eq!(grant.chemistry[0].id, "CHEM-US-00002");
+        assert_eq!(grant.chemistry[0].files, vec!["US11223344-20220111-C00002.TIF", "US11223344-20220111-C00002.CDX", "US11223344-20220111-C00002.MOL"]);
+        let claim_text = &grant.claims[0].claim_texts[0].claim_texts[0];
+        assert_eq!(claim_text.text, "a body of ; and");
+        assert_eq!(claim_text.chemistry[0].num, "00001");
+
+        let sequence_listing = grant.sequence_listing.unwrap();
+        assert_eq!(sequence_listing.ids, vec!["p-9001", "SEQ-1"]);
+        assert_eq!(sequence_listing.files, vec!["US11223344-20220111-S00001.TIF"]);
+        assert!(grant.descriptions["SEQLST"].contains("SEQ ID NO: 1"));
+
+        let options = ParserOptions::default().sequence_listing_text(false);
+        let grant = PatentGrants::with_options(with_chemistry.as_bytes(), options).next().unwrap().unwrap();
+        assert!(grant.sequence_listing.is_some());
+        assert!(!grant.descriptions.contains_key("SEQLST") && !grant.descriptions_raw.contains_key("SEQLST"));
+
+        assert!(fixture_grants().iter().all(|grant| grant.chemistry.is_empty() && grant.sequence_listing.is_none()));
+    }
+
+    #[test]
+    fn test_collect_skipped() {
+        let unknown = FIXTURE
+            .replace("<us-references-cited>", "<new-element><p>text</p></new-element><us-references-cited>")
+            .replacen("<main-classification>", "<other-element>D11</other-element><main-classification>", 1);
+
+        let skipped = SkippedElements::new();
+        let options = ParserOptions::new().collect_skipped(&skipped);
+        let grants = PatentGrants::with_options(unknown.as_bytes(), options)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(grants.len(), 3);
+
+        let elements = skipped.elements();
+        assert_eq!(elements.keys().collect::<Vec<_>>(), vec!["classification-national/other-element", "us-bibliographic-data-grant/new-element"]);
+        let element = &elements["us-bibliographic-data-grant/new-element"];
+        assert_eq!(element.count, 2);
+        assert_eq!(element.positions.len(), 2);
+        assert_eq!(&unknown[element.positions[0] - "<new-element>".len()..element.positions[0]], "<new-element>");
+        assert_eq!(elements["classification-national/other-element"].count, 1);
+
+        // nothing is recorded when unknown elements are errors
+        let skipped = SkippedElements::new();
+        let options = ParserOptions::strict().collect_skipped(&skipped);
+        assert!(PatentGrants::with_options(unknown.as_bytes(), options).next().unwrap().is_err());
+        assert!(skipped.elements().is_empty());
+    }
+
+    #[test]
+    fn test_error_context() {
+        let bad = FIXTURE.replacen(r#"<patcit num="00001">"#, r#"<patcit num="00001" bad="x">"#, 1);
+        let err = PatentGrants::from_reader(bad.as_bytes())
+            .next()
+            .unwrap()
+            .unwrap_err();
+
+        match err {
+            Error::Deser { ref position, ref path, ref doc_number, .. } => {
+                assert!(position.is_some());
+                assert_eq!(path.join("/"), "us-bibliographic-data-grant/us-references-cited/us-citation/patcit");
+                assert_eq!(doc_number.as_deref(), Some("10165721"));
+            },
+            _ => panic!("expected a Deser error, got {}", err),
+        }
+
+        let msg = err.to_string();
+        assert!(msg.contains("unrecognized attr in patcit; at byte "), "{}", msg);
+        assert!(msg.ends_with("; in us-bibliographic-data-grant/us-references-cited/us-citation/patcit; doc-number 10165721"), "{}", msg);
+    }
+
+    #[cfg(feature = "typed-dates")]
+    #[test]
+    fn test_typed_dates() {
+        let grants = fixture_grants();
+        let biblio = &grants[0].us_bibliographic_data_grant;
+        assert_eq!(biblio.publication_reference.date, chrono::NaiveDate::from_ymd_opt(2019, 1, 1).unwrap());
+        assert_eq!(biblio.priority_claims[1].date, chrono::NaiveDate::from_ymd_opt(2015, 9, 25).unwrap());
+
+        let bad = FIXTURE.replacen("<date>20190101</date>", "<date>20191301</date>", 1);
+        let err = PatentGrants::from_reader(bad.as_bytes())
+            .next()
+            .unwrap()
+            .unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains(r#"invalid date "20191301" in date: no such day"#), "{}", msg);
+        assert!(msg.contains("in us-bibliographic-data-grant/publication-reference"), "{}", msg);
+
+        let bad = FIXTURE.replacen("<date>20190101</date>", "<date>2019-01-01</date>", 1);
+        let err = PatentGrants::from_reader(bad.as_bytes())
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert!(err.to_string().contains("expected YYYYMMDD"), "{}", err);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_tracing_spans() {
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata};
+
+        type SpanFields = (&'static str, Vec<(String, String)>);
+
+        /// each span's name and fields, by id - 1
+        #[derive(Clone, Default)]
+        struct Spans(Arc<Mutex<Vec<SpanFields>>>);
+
+        struct Fields<'a>(&'a mut Vec<(String, String)>);
+
+        impl Visit for Fields<'_> {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                self.0.push((field.name().to_string(), format!("{:?}", value)));
+            }
+
+            fn record_str(&mut self, field: &Field, value: &str) {
+                self.0.push((field.name().to_string(), value.to_string()));
+            }
+        }
+
+        impl tracing::Subscriber for Spans {
+            fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                let mut spans = self.0.lock().unwrap();
+                let mut fields = Vec::new();
+                span.record(&mut Fields(&mut fields));
+                spans.push((span.metadata().name(), fields));
+                Id::from_u64(spans.len() as u64)
+            }
+
+            fn record(&self, span: &Id, values: &Record<'_>) {
+                let mut spans = self.0.lock().unwrap();
+                values.record(&mut Fields(&mut spans[span.into_u64() as usize - 1].1));
+            }
+
+            fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+            fn event(&self, _event: &Event<'_>) {}
+            fn enter(&self, _span: &Id) {}
+            fn exit(&self, _span: &Id) {}
+        }
+
+        let spans = Spans::default();
+        let grants = tracing::subscriber::with_default(spans.clone(), fixture_grants);
+
+        let spans = spans.0.lock().unwrap();
+        let doc_numbers = spans.iter()
+            .filter(|(name, _)| *name == "document")
+            .filter_map(|(_, fields)| fields.iter().find(|(field, _)| field == "doc_number"))
+            .map(|(_, doc_number)| doc_number.as_str())
+            .collect::<Vec<_>>();
+        let expected = grants.iter()
+            .map(|grant| grant.us_bibliographic_data_grant.publication_reference.doc_number.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(doc_numbers, expected);
+
+        let elements = spans.iter()
+            .filter(|(name, _)| *name == "section")
+            .map(|(_, fields)| fields[0].1.as_str())
+            .collect::<Vec<_>>();
+        assert!(elements.contains(&"us-bibliographic-data-grant"));
+        assert!(elements.contains(&"claims"));
+        assert!(elements.contains(&"description"));
+    }
 }
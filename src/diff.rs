@@ -0,0 +1,240 @@
+//! Differences between two sets of grants by doc-number, e.g. two weekly
+//! files, or a file and its corrected re-publication: the grants added,
+//! removed, and changed, with the fields that changed.
+//!
+//! Each side is a `Snapshot`, holding a hash of each field of each grant
+//! rather than the grants, so files of any size can be compared. The grant
+//! meta (file name, date produced, etc.) isn't compared, as it changes with
+//! every re-publication.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Debug, Write};
+use std::hash::Hasher;
+
+use crate::data::PatentGrant;
+use crate::docnum::grant_number;
+
+/// The fields compared, in the order changes are reported
+pub const FIELDS: &[&str] = &[
+    "publication_reference",
+    "application_reference",
+    "us_application_series_code",
+    "priority_claims",
+    "us_term_of_grant",
+    "classifications_ipcr",
+    "classifications_cpc",
+    "classification_locarno",
+    "classification_national",
+    "invention_title",
+    "botanic",
+    "us_references_cited",
+    "number_of_claims",
+    "us_exemplary_claim",
+    "us_field_of_classification_search",
+    "figures",
+    "us_related_documents",
+    "us_applicants",
+    "inventors",
+    "agents",
+    "assignees",
+    "examiners",
+    "pct_or_regional_filing_data",
+    "pct_or_regional_publishing_data",
+    "hague_agreement_data",
+    "abstract",
+    "descriptions",
+    "government_interest",
+    "chemistry",
+    "sequence_listing",
+    "us_claim_statement",
+    "claims",
+];
+
+/// Hashes of each of a grant's FIELDS, in order
+pub fn field_hashes(grant: &PatentGrant) -> Vec<u64> {
+    let biblio = &grant.us_bibliographic_data_grant;
+    // in key order, as a HashMap's isn't stable
+    let descriptions: BTreeMap<_, _> = grant.descriptions.iter().collect();
+
+    let fields: [&dyn Debug; 32] = [
+        &biblio.publication_reference,
+        &biblio.application_reference,
+        &biblio.us_application_series_code,
+        &biblio.priority_claims,
+        &biblio.us_term_of_grant,
+        &biblio.classifications_ipcr,
+        &biblio.classifications_cpc,
+        &biblio.classification_locarno,
+        &biblio.classification_national,
+        &biblio.invention_title,
+        &biblio.botanic,
+        &biblio.us_references_cited,
+        &biblio.number_of_claims,
+        &biblio.us_exemplary_claim,
+        &biblio.us_field_of_classification_search,
+        &biblio.figures,
+        &biblio.us_related_documents,
+        &biblio.us_applicants,
+        &biblio.inventors,
+        &biblio.agents,
+        &biblio.assignees,
+        &biblio.examiners,
+        &biblio.pct_or_regional_filing_data,
+        &biblio.pct_or_regional_publishing_data,
+        &biblio.hague_agreement_data,
+        &grant.r#abstract,
+        &descriptions,
+        &grant.government_interest,
+        &grant.chemistry,
+        &grant.sequence_listing,
+        &grant.us_claim_statement,
+        &grant.claims,
+    ];
+
+    fields.iter().map(|field| debug_hash(*field)).collect()
+}
+
+/// hashes the field's Debug output as it's written, so large fields (e.g.
+/// descriptions) aren't formatted into a String first
+fn debug_hash(field: &dyn Debug) -> u64 {
+    struct HashWriter(DefaultHasher);
+
+    impl Write for HashWriter {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.0.write(s.as_bytes());
+            Ok(())
+        }
+    }
+
+    let mut wtr = HashWriter(DefaultHasher::new());
+    // writing to a hasher can't fail
+    let _ = write!(wtr, "{:?}", field);
+    wtr.0.finish()
+}
+
+/// One side of a diff: each grant's doc-number and field hashes
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    // in the order added
+    doc_numbers: Vec<String>,
+    // by normalized doc-number (see `docnum::grant_number`)
+    grants: HashMap<String, Vec<u64>>,
+}
+
+impl Snapshot {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A grant added again (e.g. a file with a re-publication in it)
+    /// replaces the one added before
+    pub fn add(&mut self, grant: &PatentGrant) {
+        let doc_number = &grant.us_bibliographic_data_grant.publication_reference.doc_number;
+        if self.grants.insert(grant_number(doc_number), field_hashes(grant)).is_none() {
+            self.doc_numbers.push(doc_number.clone());
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.doc_numbers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.doc_numbers.is_empty()
+    }
+
+    /// What changed from this snapshot to new
+    pub fn diff(&self, new: &Snapshot) -> GrantsDiff {
+        let mut diff = GrantsDiff::default();
+
+        for doc_number in &self.doc_numbers {
+            if !new.grants.contains_key(&grant_number(doc_number)) {
+                diff.removed.push(doc_number.clone());
+            }
+        }
+
+        for doc_number in &new.doc_numbers {
+            let key = grant_number(doc_number);
+            let old_hashes = match self.grants.get(&key) {
+                Some(old_hashes) => old_hashes,
+                None => {
+                    diff.added.push(doc_number.clone());
+                    continue;
+                },
+            };
+
+            let fields: Vec<_> = FIELDS.iter()
+                .zip(old_hashes.iter().zip(&new.grants[&key]))
+                .filter(|(_, (old, new))| old != new)
+                .map(|(field, _)| *field)
+                .collect();
+            if fields.is_empty() {
+                diff.unchanged += 1;
+            } else {
+                diff.changed.push((doc_number.clone(), fields));
+            }
+        }
+
+        diff
+    }
+}
+
+/// See `Snapshot::diff`. Doc-numbers are as the grants have them, in the
+/// order of their snapshot (new's, for changed grants)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GrantsDiff {
+    /// in new, not old
+    pub added: Vec<String>,
+    /// in old, not new
+    pub removed: Vec<String>,
+    /// in both, with the FIELDS that differ
+    pub changed: Vec<(String, Vec<&'static str>)>,
+    /// in both, the same
+    pub unchanged: usize,
+}
+
+impl GrantsDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deserialize::PatentGrants;
+
+    #[test]
+    fn test_diff() {
+        let grants = || PatentGrants::from_reader(include_str!("../tests/fixtures/grants.xml").as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(field_hashes(&grants()[0]).len(), FIELDS.len());
+
+        let mut old = Snapshot::new();
+        for grant in &grants()[..2] {
+            old.add(grant);
+        }
+
+        let mut new = Snapshot::new();
+        let mut new_grants = grants();
+        new_grants[0].us_bibliographic_data_grant.invention_title.title.push_str(" (corrected)");
+        new_grants[0].claims.pop();
+        for grant in &new_grants[1..] {
+            new.add(grant);
+        }
+        new.add(&new_grants[0]);
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.added, vec!["PP030001"]);
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.changed, vec![("10165721".to_string(), vec!["invention_title", "claims"])]);
+        assert_eq!(diff.unchanged, 1);
+
+        let diff = new.diff(&old);
+        assert_eq!(diff.removed, vec!["PP030001"]);
+        assert!(old.diff(&old).is_empty());
+    }
+}
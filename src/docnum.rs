@@ -0,0 +1,136 @@
+//! Document numbers in the forms used across USPTO data and elsewhere.
+//!
+//! Grant doc-numbers are zero-padded differently by format (e.g. `03930584`
+//! in APS, `D0838001` in the xml), so to match grants across files or
+//! against other datasets, normalize them first.
+
+use crate::data::KindCode;
+
+/// Prefixes of grant numbers that aren't utility patents: reissue, plant,
+/// design, statutory invention registration, and defensive publication.
+const GRANT_PREFIXES: &[&str] = &["RE", "PP", "D", "H", "T"];
+
+/// Splits a grant number into its prefix (empty for utility patents) and its
+/// number, without leading zeros. None if it isn't a grant number.
+pub fn split_grant_number(doc_number: &str) -> Option<(&'static str, &str)> {
+    let doc_number = doc_number.trim();
+    let prefix = GRANT_PREFIXES
+        .iter()
+        .find(|prefix| doc_number.starts_with(*prefix))
+        .copied()
+        .unwrap_or("");
+    let number = doc_number[prefix.len()..].trim_start_matches('0');
+
+    if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    Some((prefix, number))
+}
+
+/// A grant number with its prefix and no leading zeros, e.g. `D838001` for
+/// `D0838001`, or `3930584` for `03930584`. Anything that isn't a grant
+/// number is returned trimmed, as is.
+pub fn grant_number(doc_number: &str) -> String {
+    match split_grant_number(doc_number) {
+        Some((prefix, number)) => format!("{}{}", prefix, number),
+        None => doc_number.trim().to_string(),
+    }
+}
+
+/// A grant number zero-padded to the xml's eight characters, e.g. `D0838001`
+/// or `RE049000`.
+pub fn padded_grant_number(doc_number: &str) -> String {
+    match split_grant_number(doc_number) {
+        Some((prefix, number)) => format!("{}{:0>width$}", prefix, number, width = 8 - prefix.len()),
+        None => doc_number.trim().to_string(),
+    }
+}
+
+/// An application number as the series code and the six-digit serial
+/// number, e.g. `16012345`. Takes the forms it's written in (`16/012,345`,
+/// `16012345`, or the series code and serial separately as `series_code`).
+/// None if it isn't one.
+pub fn application_number(text: &str, series_code: Option<&str>) -> Option<String> {
+    let digits: String = text.chars().filter(|c| !matches!(c, '/' | ',' | ' ' | '-')).collect();
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    match (digits.len(), series_code) {
+        (8, _) => Some(digits),
+        (len, Some(series_code)) if len <= 6 => {
+            let series_code = series_code.trim();
+            if series_code.is_empty() || series_code.len() > 2 || !series_code.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            Some(format!("{:0>2}{:0>6}", series_code, digits))
+        },
+        _ => None,
+    }
+}
+
+/// Splits an (eight-digit) application number into its series code and serial
+/// number.
+pub fn split_application_number(application_number: &str) -> Option<(&str, &str)> {
+    if application_number.len() != 8 || !application_number.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    Some(application_number.split_at(2))
+}
+
+/// A grant's id in PatentsView (`patent_id`), which is the grant number
+/// without leading zeros, as `grant_number`.
+pub fn patentsview_patent_id(doc_number: &str) -> String {
+    grant_number(doc_number)
+}
+
+/// A document's id in Google Patents, e.g. `US10165721B2`, `USD838001S1`, or
+/// `US20190000001A1` for an application publication: the country, the
+/// number without leading zeros, and the kind code if there is one.
+pub fn google_patents_id(country: &str, doc_number: &str, kind: Option<&KindCode>) -> String {
+    let kind = kind.map(KindCode::as_str).unwrap_or("");
+
+    format!("{}{}{}", country, grant_number(doc_number), kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grant_number() {
+        assert_eq!(grant_number("03930584"), "3930584");
+        assert_eq!(grant_number("10165721"), "10165721");
+        assert_eq!(grant_number("D0838001"), "D838001");
+        assert_eq!(grant_number("PP029000"), "PP29000");
+        assert_eq!(grant_number("RE049000"), "RE49000");
+        assert_eq!(grant_number("H0001234"), "H1234");
+        assert_eq!(grant_number("2015-101492"), "2015-101492");
+        assert_eq!(split_grant_number("D0838001"), Some(("D", "838001")));
+        assert_eq!(split_grant_number("0000"), None);
+
+        assert_eq!(padded_grant_number("3930584"), "03930584");
+        assert_eq!(padded_grant_number("D838001"), "D0838001");
+        assert_eq!(padded_grant_number("RE49000"), "RE049000");
+
+        assert_eq!(patentsview_patent_id("D0838001"), "D838001");
+        assert_eq!(google_patents_id("US", "10165721", Some(&KindCode::B2)), "US10165721B2");
+        assert_eq!(google_patents_id("US", "D0838001", Some(&KindCode::S1)), "USD838001S1");
+        assert_eq!(google_patents_id("US", "20190000001", Some(&KindCode::A1)), "US20190000001A1");
+        assert_eq!(google_patents_id("US", "03930584", None), "US3930584");
+    }
+
+    #[test]
+    fn test_application_number() {
+        assert_eq!(application_number("16/012,345", None).as_deref(), Some("16012345"));
+        assert_eq!(application_number("16012345", None).as_deref(), Some("16012345"));
+        assert_eq!(application_number("12345", Some("5")).as_deref(), Some("05012345"));
+        assert_eq!(application_number("12345", None), None);
+        assert_eq!(application_number("PCT/US2015", None), None);
+
+        assert_eq!(split_application_number("16012345"), Some(("16", "012345")));
+        assert_eq!(split_application_number("1601234"), None);
+    }
+}
@@ -0,0 +1,561 @@
+//! Entities declared in the dtds USPTO files use (the ISO 8879 sets of
+//! characters, e.g. `&angst;`, `&bull;`, `&mgr;`), which quick-xml can't
+//! unescape without the dtd. See `Entities::Dtd`.
+
+use std::borrow::Cow;
+
+/// The entity's text, if it's one of the dtds' entities (or predefined in
+/// xml)
+pub fn lookup(name: &str) -> Option<&'static str> {
+    ENTITIES.binary_search_by(|(entity, _)| entity.as_bytes().cmp(name.as_bytes()))
+        .ok()
+        .map(|i| ENTITIES[i].1)
+}
+
+/// Unescapes escaped text: the entities in `lookup`, and character
+/// references. Any other entity, or an `&` that doesn't start one, is kept as
+/// is. Borrowed if there's nothing to unescape.
+pub fn resolve(escaped: &[u8]) -> Cow<'_, [u8]> {
+    let mut amps = memchr::memchr_iter(b'&', escaped).peekable();
+    if amps.peek().is_none() {
+        return Cow::Borrowed(escaped);
+    }
+
+    let mut text = Vec::with_capacity(escaped.len());
+    let mut last = 0;
+    for amp in amps {
+        // already copied, as part of an unresolved entity
+        if amp < last {
+            continue;
+        }
+        text.extend_from_slice(&escaped[last..amp]);
+        last = amp;
+
+        let end = match memchr::memchr(b';', &escaped[amp..]) {
+            Some(end) => amp + end,
+            None => break,
+        };
+        let name = match std::str::from_utf8(&escaped[amp + 1..end]) {
+            Ok(name) => name,
+            Err(_) => continue,
+        };
+
+        let resolved = match name.strip_prefix('#') {
+            Some(number) => char_reference(number).map(|c| Cow::Owned(c.to_string())),
+            None => lookup(name).map(Cow::Borrowed),
+        };
+        if let Some(resolved) = resolved {
+            text.extend_from_slice(resolved.as_bytes());
+            last = end + 1;
+        }
+    }
+    text.extend_from_slice(&escaped[last..]);
+
+    Cow::Owned(text)
+}
+
+/// e.g. `x3B1` or `945`
+fn char_reference(number: &str) -> Option<char> {
+    let code = match number.strip_prefix('x') {
+        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+        None => number.parse().ok()?,
+    };
+
+    std::char::from_u32(code)
+}
+
+/// By name, sorted, from the ISO 8879 sets the dtds include: isonum, isopub,
+/// isotech, isodia, isolat1, isolat2, isogrk1 and isogrk3
+const ENTITIES: &[(&str, &str)] = &[
+    ("AElig", "\u{c6}"),
+    ("Aacute", "\u{c1}"),
+    ("Abreve", "\u{102}"),
+    ("Acirc", "\u{c2}"),
+    ("Agr", "\u{391}"),
+    ("Agrave", "\u{c0}"),
+    ("Amacr", "\u{100}"),
+    ("Aogon", "\u{104}"),
+    ("Aring", "\u{c5}"),
+    ("Atilde", "\u{c3}"),
+    ("Auml", "\u{c4}"),
+    ("Bgr", "\u{392}"),
+    ("Cacute", "\u{106}"),
+    ("Ccaron", "\u{10c}"),
+    ("Ccedil", "\u{c7}"),
+    ("Dagger", "\u{2021}"),
+    ("Dcaron", "\u{10e}"),
+    ("Delta", "\u{394}"),
+    ("Dgr", "\u{394}"),
+    ("Dot", "\u{a8}"),
+    ("DotDot", "\u{20dc}"),
+    ("Dstrok", "\u{110}"),
+    ("EEgr", "\u{397}"),
+    ("ETH", "\u{d0}"),
+    ("Eacute", "\u{c9}"),
+    ("Ecaron", "\u{11a}"),
+    ("Ecirc", "\u{ca}"),
+    ("Egr", "\u{395}"),
+    ("Egrave", "\u{c8}"),
+    ("Emacr", "\u{112}"),
+    ("Eogon", "\u{118}"),
+    ("Euml", "\u{cb}"),
+    ("Gamma", "\u{393}"),
+    ("Gbreve", "\u{11e}"),
+    ("Ggr", "\u{393}"),
+    ("Iacute", "\u{cd}"),
+    ("Icirc", "\u{ce}"),
+    ("Idot", "\u{130}"),
+    ("Igr", "\u{399}"),
+    ("Igrave", "\u{cc}"),
+    ("Imacr", "\u{12a}"),
+    ("Iuml", "\u{cf}"),
+    ("KHgr", "\u{3a7}"),
+    ("Kgr", "\u{39a}"),
+    ("Lacute", "\u{139}"),
+    ("Lambda", "\u{39b}"),
+    ("Lcaron", "\u{13d}"),
+    ("Lgr", "\u{39b}"),
+    ("Lstrok", "\u{141}"),
+    ("Mgr", "\u{39c}"),
+    ("Nacute", "\u{143}"),
+    ("Ncaron", "\u{147}"),
+    ("Ngr", "\u{39d}"),
+    ("Ntilde", "\u{d1}"),
+    ("OElig", "\u{152}"),
+    ("OHgr", "\u{3a9}"),
+    ("Oacute", "\u{d3}"),
+    ("Ocirc", "\u{d4}"),
+    ("Odblac", "\u{150}"),
+    ("Ogr", "\u{39f}"),
+    ("Ograve", "\u{d2}"),
+    ("Omacr", "\u{14c}"),
+    ("Omega", "\u{3a9}"),
+    ("Oslash", "\u{d8}"),
+    ("Otilde", "\u{d5}"),
+    ("Ouml", "\u{d6}"),
+    ("PHgr", "\u{3a6}"),
+    ("PSgr", "\u{3a8}"),
+    ("Pgr", "\u{3a0}"),
+    ("Phi", "\u{3a6}"),
+    ("Pi", "\u{3a0}"),
+    ("Prime", "\u{2033}"),
+    ("Psi", "\u{3a8}"),
+    ("Racute", "\u{154}"),
+    ("Rcaron", "\u{158}"),
+    ("Rgr", "\u{3a1}"),
+    ("Sacute", "\u{15a}"),
+    ("Scaron", "\u{160}"),
+    ("Scedil", "\u{15e}"),
+    ("Sgr", "\u{3a3}"),
+    ("Sigma", "\u{3a3}"),
+    ("THORN", "\u{de}"),
+    ("THgr", "\u{398}"),
+    ("Tcaron", "\u{164}"),
+    ("Tcedil", "\u{162}"),
+    ("Tgr", "\u{3a4}"),
+    ("Theta", "\u{398}"),
+    ("Uacute", "\u{da}"),
+    ("Ucirc", "\u{db}"),
+    ("Udblac", "\u{170}"),
+    ("Ugr", "\u{3a5}"),
+    ("Ugrave", "\u{d9}"),
+    ("Umacr", "\u{16a}"),
+    ("Upsi", "\u{3d2}"),
+    ("Uring", "\u{16e}"),
+    ("Uuml", "\u{dc}"),
+    ("Verbar", "\u{2016}"),
+    ("Xgr", "\u{39e}"),
+    ("Xi", "\u{39e}"),
+    ("Yacute", "\u{dd}"),
+    ("Yuml", "\u{178}"),
+    ("Zacute", "\u{179}"),
+    ("Zcaron", "\u{17d}"),
+    ("Zdot", "\u{17b}"),
+    ("Zgr", "\u{396}"),
+    ("aacute", "\u{e1}"),
+    ("abreve", "\u{103}"),
+    ("acirc", "\u{e2}"),
+    ("acute", "\u{b4}"),
+    ("aelig", "\u{e6}"),
+    ("agr", "\u{3b1}"),
+    ("agrave", "\u{e0}"),
+    ("aleph", "\u{2135}"),
+    ("alpha", "\u{3b1}"),
+    ("amacr", "\u{101}"),
+    ("amp", "&"),
+    ("and", "\u{2227}"),
+    ("ang", "\u{2220}"),
+    ("ang90", "\u{221f}"),
+    ("angsph", "\u{2222}"),
+    ("angst", "\u{c5}"),
+    ("aogon", "\u{105}"),
+    ("ap", "\u{2248}"),
+    ("apos", "'"),
+    ("aring", "\u{e5}"),
+    ("ast", "*"),
+    ("atilde", "\u{e3}"),
+    ("auml", "\u{e4}"),
+    ("becaus", "\u{2235}"),
+    ("bernou", "\u{212c}"),
+    ("beta", "\u{3b2}"),
+    ("bgr", "\u{3b2}"),
+    ("blank", "\u{2423}"),
+    ("blk12", "\u{2592}"),
+    ("blk14", "\u{2591}"),
+    ("blk34", "\u{2593}"),
+    ("block", "\u{2588}"),
+    ("bottom", "\u{22a5}"),
+    ("breve", "\u{2d8}"),
+    ("brvbar", "\u{a6}"),
+    ("bsol", "\\"),
+    ("bull", "\u{2022}"),
+    ("cacute", "\u{107}"),
+    ("cap", "\u{2229}"),
+    ("caret", "\u{2041}"),
+    ("caron", "\u{2c7}"),
+    ("ccaron", "\u{10d}"),
+    ("ccedil", "\u{e7}"),
+    ("cedil", "\u{b8}"),
+    ("cent", "\u{a2}"),
+    ("check", "\u{2713}"),
+    ("chi", "\u{3c7}"),
+    ("cir", "\u{25cb}"),
+    ("circ", "\u{2c6}"),
+    ("clubs", "\u{2663}"),
+    ("colon", ":"),
+    ("comma", ","),
+    ("commat", "@"),
+    ("compfn", "\u{2218}"),
+    ("cong", "\u{2245}"),
+    ("conint", "\u{222e}"),
+    ("copy", "\u{a9}"),
+    ("copysr", "\u{2117}"),
+    ("cross", "\u{2717}"),
+    ("cup", "\u{222a}"),
+    ("curren", "\u{a4}"),
+    ("dagger", "\u{2020}"),
+    ("darr", "\u{2193}"),
+    ("dash", "\u{2010}"),
+    ("dblac", "\u{2dd}"),
+    ("dcaron", "\u{10f}"),
+    ("deg", "\u{b0}"),
+    ("delta", "\u{3b4}"),
+    ("dgr", "\u{3b4}"),
+    ("diams", "\u{2666}"),
+    ("die", "\u{a8}"),
+    ("divide", "\u{f7}"),
+    ("dlcrop", "\u{230d}"),
+    ("dollar", "$"),
+    ("dot", "\u{2d9}"),
+    ("drcrop", "\u{230c}"),
+    ("dstrok", "\u{111}"),
+    ("dtri", "\u{25bf}"),
+    ("dtrif", "\u{25be}"),
+    ("eacute", "\u{e9}"),
+    ("ecaron", "\u{11b}"),
+    ("ecirc", "\u{ea}"),
+    ("eegr", "\u{3b7}"),
+    ("egr", "\u{3b5}"),
+    ("egrave", "\u{e8}"),
+    ("emacr", "\u{113}"),
+    ("empty", "\u{2205}"),
+    ("emsp", "\u{2003}"),
+    ("emsp13", "\u{2004}"),
+    ("emsp14", "\u{2005}"),
+    ("ensp", "\u{2002}"),
+    ("eogon", "\u{119}"),
+    ("epsi", "\u{3f5}"),
+    ("epsis", "\u{3f5}"),
+    ("epsiv", "\u{3b5}"),
+    ("equals", "="),
+    ("equiv", "\u{2261}"),
+    ("eta", "\u{3b7}"),
+    ("eth", "\u{f0}"),
+    ("euml", "\u{eb}"),
+    ("excl", "!"),
+    ("exist", "\u{2203}"),
+    ("female", "\u{2640}"),
+    ("ffilig", "\u{fb03}"),
+    ("fflig", "\u{fb00}"),
+    ("ffllig", "\u{fb04}"),
+    ("filig", "\u{fb01}"),
+    ("fjlig", "fj"),
+    ("flat", "\u{266d}"),
+    ("fllig", "\u{fb02}"),
+    ("fnof", "\u{192}"),
+    ("forall", "\u{2200}"),
+    ("frac12", "\u{bd}"),
+    ("frac13", "\u{2153}"),
+    ("frac14", "\u{bc}"),
+    ("frac15", "\u{2155}"),
+    ("frac16", "\u{2159}"),
+    ("frac18", "\u{215b}"),
+    ("frac23", "\u{2154}"),
+    ("frac25", "\u{2156}"),
+    ("frac34", "\u{be}"),
+    ("frac35", "\u{2157}"),
+    ("frac38", "\u{215c}"),
+    ("frac45", "\u{2158}"),
+    ("frac56", "\u{215a}"),
+    ("frac58", "\u{215d}"),
+    ("frac78", "\u{215e}"),
+    ("gamma", "\u{3b3}"),
+    ("gammad", "\u{3dd}"),
+    ("gbreve", "\u{11f}"),
+    ("ge", "\u{2265}"),
+    ("ggr", "\u{3b3}"),
+    ("grave", "`"),
+    ("gt", ">"),
+    ("hArr", "\u{21d4}"),
+    ("hairsp", "\u{200a}"),
+    ("half", "\u{bd}"),
+    ("hamilt", "\u{210b}"),
+    ("harr", "\u{2194}"),
+    ("hearts", "\u{2665}"),
+    ("hellip", "\u{2026}"),
+    ("horbar", "\u{2015}"),
+    ("hybull", "\u{2043}"),
+    ("hyphen", "-"),
+    ("iacute", "\u{ed}"),
+    ("icirc", "\u{ee}"),
+    ("iexcl", "\u{a1}"),
+    ("iff", "\u{21d4}"),
+    ("igr", "\u{3b9}"),
+    ("igrave", "\u{ec}"),
+    ("imacr", "\u{12b}"),
+    ("incare", "\u{2105}"),
+    ("infin", "\u{221e}"),
+    ("inodot", "\u{131}"),
+    ("int", "\u{222b}"),
+    ("iota", "\u{3b9}"),
+    ("iquest", "\u{bf}"),
+    ("isin", "\u{2208}"),
+    ("iuml", "\u{ef}"),
+    ("kappa", "\u{3ba}"),
+    ("kappav", "\u{3f0}"),
+    ("kgr", "\u{3ba}"),
+    ("khgr", "\u{3c7}"),
+    ("lArr", "\u{21d0}"),
+    ("lacute", "\u{13a}"),
+    ("lagran", "\u{2112}"),
+    ("lambda", "\u{3bb}"),
+    ("lang", "\u{2329}"),
+    ("laquo", "\u{ab}"),
+    ("larr", "\u{2190}"),
+    ("lcaron", "\u{13e}"),
+    ("lcub", "{"),
+    ("ldquo", "\u{201c}"),
+    ("ldquor", "\u{201e}"),
+    ("le", "\u{2264}"),
+    ("lgr", "\u{3bb}"),
+    ("lhblk", "\u{2584}"),
+    ("lowast", "\u{2217}"),
+    ("lowbar", "_"),
+    ("loz", "\u{25ca}"),
+    ("lozf", "\u{29eb}"),
+    ("lpar", "("),
+    ("lsqb", "["),
+    ("lsquo", "\u{2018}"),
+    ("lsquor", "\u{201a}"),
+    ("lstrok", "\u{142}"),
+    ("lt", "<"),
+    ("ltri", "\u{25c3}"),
+    ("ltrif", "\u{25c2}"),
+    ("macr", "\u{af}"),
+    ("male", "\u{2642}"),
+    ("malt", "\u{2720}"),
+    ("marker", "\u{25ae}"),
+    ("mdash", "\u{2014}"),
+    ("mgr", "\u{3bc}"),
+    ("micro", "\u{b5}"),
+    ("middot", "\u{b7}"),
+    ("minus", "\u{2212}"),
+    ("mldr", "\u{2026}"),
+    ("mnplus", "\u{2213}"),
+    ("mu", "\u{3bc}"),
+    ("nabla", "\u{2207}"),
+    ("nacute", "\u{144}"),
+    ("natur", "\u{266e}"),
+    ("nbsp", "\u{a0}"),
+    ("ncaron", "\u{148}"),
+    ("ndash", "\u{2013}"),
+    ("ne", "\u{2260}"),
+    ("ngr", "\u{3bd}"),
+    ("ni", "\u{220b}"),
+    ("nldr", "\u{2025}"),
+    ("not", "\u{ac}"),
+    ("notin", "\u{2209}"),
+    ("ntilde", "\u{f1}"),
+    ("nu", "\u{3bd}"),
+    ("num", "#"),
+    ("numsp", "\u{2007}"),
+    ("oacute", "\u{f3}"),
+    ("ocirc", "\u{f4}"),
+    ("odblac", "\u{151}"),
+    ("oelig", "\u{153}"),
+    ("ogon", "\u{2db}"),
+    ("ogr", "\u{3bf}"),
+    ("ograve", "\u{f2}"),
+    ("ohgr", "\u{3c9}"),
+    ("ohm", "\u{2126}"),
+    ("omacr", "\u{14d}"),
+    ("omega", "\u{3c9}"),
+    ("or", "\u{2228}"),
+    ("order", "\u{2134}"),
+    ("ordf", "\u{aa}"),
+    ("ordm", "\u{ba}"),
+    ("oslash", "\u{f8}"),
+    ("otilde", "\u{f5}"),
+    ("ouml", "\u{f6}"),
+    ("par", "\u{2225}"),
+    ("para", "\u{b6}"),
+    ("part", "\u{2202}"),
+    ("percnt", "%"),
+    ("period", "."),
+    ("permil", "\u{2030}"),
+    ("perp", "\u{22a5}"),
+    ("pgr", "\u{3c0}"),
+    ("phgr", "\u{3c6}"),
+    ("phi", "\u{3c6}"),
+    ("phis", "\u{3d5}"),
+    ("phiv", "\u{3d5}"),
+    ("phmmat", "\u{2133}"),
+    ("phone", "\u{260e}"),
+    ("pi", "\u{3c0}"),
+    ("piv", "\u{3d6}"),
+    ("plus", "+"),
+    ("plusmn", "\u{b1}"),
+    ("pound", "\u{a3}"),
+    ("prime", "\u{2032}"),
+    ("prod", "\u{220f}"),
+    ("prop", "\u{221d}"),
+    ("psgr", "\u{3c8}"),
+    ("psi", "\u{3c8}"),
+    ("puncsp", "\u{2008}"),
+    ("quest", "?"),
+    ("quot", "\""),
+    ("rArr", "\u{21d2}"),
+    ("racute", "\u{155}"),
+    ("radic", "\u{221a}"),
+    ("rang", "\u{232a}"),
+    ("raquo", "\u{bb}"),
+    ("rarr", "\u{2192}"),
+    ("rcaron", "\u{159}"),
+    ("rcub", "}"),
+    ("rdquo", "\u{201d}"),
+    ("rdquor", "\u{201d}"),
+    ("rect", "\u{25ad}"),
+    ("reg", "\u{ae}"),
+    ("rgr", "\u{3c1}"),
+    ("rho", "\u{3c1}"),
+    ("rhov", "\u{3f1}"),
+    ("ring", "\u{2da}"),
+    ("rpar", ")"),
+    ("rsqb", "]"),
+    ("rsquo", "\u{2019}"),
+    ("rsquor", "\u{2019}"),
+    ("rtri", "\u{25b9}"),
+    ("rtrif", "\u{25b8}"),
+    ("rx", "\u{211e}"),
+    ("sacute", "\u{15b}"),
+    ("scaron", "\u{161}"),
+    ("scedil", "\u{15f}"),
+    ("sect", "\u{a7}"),
+    ("semi", ";"),
+    ("sext", "\u{2736}"),
+    ("sfgr", "\u{3c2}"),
+    ("sgr", "\u{3c3}"),
+    ("sharp", "\u{266f}"),
+    ("shy", "\u{ad}"),
+    ("sigma", "\u{3c3}"),
+    ("sigmav", "\u{3c2}"),
+    ("sim", "\u{223c}"),
+    ("sime", "\u{2243}"),
+    ("sol", "/"),
+    ("spades", "\u{2660}"),
+    ("squ", "\u{25a1}"),
+    ("square", "\u{25a1}"),
+    ("squf", "\u{25aa}"),
+    ("star", "\u{2606}"),
+    ("starf", "\u{2605}"),
+    ("sub", "\u{2282}"),
+    ("sube", "\u{2286}"),
+    ("sum", "\u{2211}"),
+    ("sung", "\u{266a}"),
+    ("sup", "\u{2283}"),
+    ("sup1", "\u{b9}"),
+    ("sup2", "\u{b2}"),
+    ("sup3", "\u{b3}"),
+    ("supe", "\u{2287}"),
+    ("szlig", "\u{df}"),
+    ("target", "\u{2316}"),
+    ("tau", "\u{3c4}"),
+    ("tcaron", "\u{165}"),
+    ("tcedil", "\u{163}"),
+    ("tdot", "\u{20db}"),
+    ("telrec", "\u{2315}"),
+    ("tgr", "\u{3c4}"),
+    ("there4", "\u{2234}"),
+    ("theta", "\u{3b8}"),
+    ("thetas", "\u{3b8}"),
+    ("thetav", "\u{3d1}"),
+    ("thgr", "\u{3b8}"),
+    ("thinsp", "\u{2009}"),
+    ("thorn", "\u{fe}"),
+    ("tilde", "\u{2dc}"),
+    ("times", "\u{d7}"),
+    ("tprime", "\u{2034}"),
+    ("trade", "\u{2122}"),
+    ("uacute", "\u{fa}"),
+    ("uarr", "\u{2191}"),
+    ("ucirc", "\u{fb}"),
+    ("udblac", "\u{171}"),
+    ("ugr", "\u{3c5}"),
+    ("ugrave", "\u{f9}"),
+    ("uhblk", "\u{2580}"),
+    ("ulcrop", "\u{230f}"),
+    ("umacr", "\u{16b}"),
+    ("uml", "\u{a8}"),
+    ("upsi", "\u{3c5}"),
+    ("urcrop", "\u{230e}"),
+    ("uring", "\u{16f}"),
+    ("utri", "\u{25b5}"),
+    ("utrif", "\u{25b4}"),
+    ("uuml", "\u{fc}"),
+    ("vellip", "\u{22ee}"),
+    ("verbar", "|"),
+    ("wedgeq", "\u{2259}"),
+    ("xgr", "\u{3be}"),
+    ("xi", "\u{3be}"),
+    ("yacute", "\u{fd}"),
+    ("yen", "\u{a5}"),
+    ("yuml", "\u{ff}"),
+    ("zacute", "\u{17a}"),
+    ("zcaron", "\u{17e}"),
+    ("zdot", "\u{17c}"),
+    ("zeta", "\u{3b6}"),
+    ("zgr", "\u{3b6}"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entities_sorted() {
+        assert!(ENTITIES.windows(2).all(|w| w[0].0.as_bytes() < w[1].0.as_bytes()));
+    }
+
+    #[test]
+    fn test_resolve() {
+        assert_eq!(lookup("mgr"), Some("\u{3bc}"));
+        assert_eq!(lookup("angst"), Some("\u{c5}"));
+        assert_eq!(lookup("nosuch"), None);
+
+        let resolve = |text: &str| String::from_utf8(resolve(text.as_bytes()).into_owned()).unwrap();
+        assert_eq!(resolve("5 &mgr;m &plusmn; 1&deg; &amp; &#x3B1;&#946;"), "5 \u{3bc}m \u{b1} 1\u{b0} & \u{3b1}\u{3b2}");
+        // kept as is
+        assert_eq!(resolve("&nosuch; & AT&T &amp"), "&nosuch; & AT&T &amp");
+        assert!(matches!(super::resolve(b"plain"), Cow::Borrowed(_)));
+    }
+}
@@ -2,6 +2,7 @@
 // thoughts about pub visibility for errors.
 
 use snafu::Snafu;
+use std::fmt::Write;
 
 #[derive(Debug, Snafu)]
 #[snafu(visibility="pub(crate)")]
@@ -16,11 +17,282 @@ pub enum Error {
     //  = note: the method `as_error_source` exists but the following trait bounds were not satisfied:
     //          `&quick_xml::errors::Error : snafu::AsErrorSource`
     //          `quick_xml::errors::Error : snafu::AsErrorSource`
-    #[snafu(display("Xml Deserialization Error: {}", src))]
-    Deser{ src: String },
+    #[snafu(display("Xml Deserialization Error: {}{}", src, deser_context(*position, path, doc_number.as_deref())))]
+    Deser{
+        src: String,
+        // where the reader was when the error was hit, in bytes
+        position: Option<usize>,
+        // elements being parsed, outermost first
+        path: Vec<String>,
+        // of the grant being parsed, if already read
+        doc_number: Option<String>,
+    },
+    #[snafu(display("Error in document at bytes {}..{}: {}", start, end, source))]
+    Document{ start: u64, end: u64, source: Box<Error> },
+    #[snafu(display("APS Deserialization Error: {}", src))]
+    ApsDeser{ src: String },
+    #[cfg(feature = "bulkdata")]
     #[snafu(display("Fetch Error: {}", source))]
     Fetch{ source: reqwest::Error },
+    #[cfg(feature = "bulkdata")]
+    #[snafu(display("Fetch Error: {} redirects to {:?}, which wasn't followed", url, location))]
+    FetchRedirect{ url: String, location: String },
     #[snafu(display("Fetch Create File Error: {}", source))]
     CreateFile{ source: std::io::Error },
+    #[snafu(display("Fetch Error: {} is {} bytes, expected {}", name, actual, expected))]
+    DownloadSize{ name: String, expected: u64, actual: u64 },
+    #[snafu(display("Read Error: {}", source))]
+    Read{ source: std::io::Error },
+    #[cfg(feature = "zip")]
+    #[snafu(display("Zip Error: {}", source))]
+    Zip{ source: zip::result::ZipError },
+    #[cfg(feature = "zip")]
+    #[snafu(display("Zip Error: no ipg*.xml grants file in archive"))]
+    ZipNoXml,
+    #[cfg(feature = "zip")]
+    #[snafu(display("Zip Error: unsupported compression method {}", method))]
+    ZipCompression{ method: String },
+    #[cfg(not(feature = "zip"))]
+    #[snafu(display("Zip Error: zip archives require the `zip` feature"))]
+    ZipDisabled,
+    #[snafu(display("Index Write Error: {}", source))]
+    IndexWrite{ source: std::io::Error },
+    #[snafu(display("Index Error: line {}: {}", line, src))]
+    IndexFormat{ line: usize, src: String },
+    #[snafu(display("Checkpoint Write Error: {}", source))]
+    CheckpointWrite{ source: std::io::Error },
+    #[snafu(display("Checkpoint Error: line {}: {}", line, src))]
+    CheckpointFormat{ line: usize, src: String },
+    #[snafu(display("Metrics Server Error: {}", source))]
+    MetricsServer{ source: std::io::Error },
+    #[snafu(display("Sync State Write Error: {}", source))]
+    SyncStateWrite{ source: std::io::Error },
+    #[snafu(display("Sync State Error: line {}: {}", line, src))]
+    SyncStateFormat{ line: usize, src: String },
+    #[snafu(display("Maintenance Fee Events Error: line {}: {}", line, src))]
+    MaintFeeFormat{ line: usize, src: String },
+    #[snafu(display("CPC Scheme Error: line {}: {}", line, src))]
+    CpcSchemeFormat{ line: usize, src: String },
+    #[snafu(display("USPC Concordance Error: line {}: {}", line, src))]
+    ConcordanceFormat{ line: usize, src: String },
+    #[snafu(display("Xml Write Error: {}", source))]
+    WriteXml{ source: std::io::Error },
+    #[snafu(display("Export Io Error: {}", source))]
+    ExportIo{ source: std::io::Error },
+    #[cfg(feature = "csv")]
+    #[snafu(display("Export Write Csv Error: {}", source))]
+    WriteCsv{ source: csv::Error },
+    #[cfg(feature = "csv")]
+    #[snafu(display("Read Csv Error: {}", source))]
+    ReadCsv{ source: csv::Error },
+    #[cfg(feature = "parquet")]
+    #[snafu(display("Export Arrow Error: {}", source))]
+    Arrow{ source: arrow_schema::ArrowError },
+    #[cfg(feature = "parquet")]
+    #[snafu(display("Export Write Parquet Error: {}", source))]
+    WriteParquet{ source: parquet::errors::ParquetError },
+    #[cfg(feature = "sqlite")]
+    #[snafu(display("Export Write Sqlite Error: {}", source))]
+    WriteSqlite{ source: rusqlite::Error },
+    #[cfg(feature = "postgres")]
+    #[snafu(display("Export Write Postgres Error: {}", source))]
+    WritePostgres{ source: tokio_postgres::Error },
+    #[cfg(feature = "clickhouse")]
+    #[snafu(display("Export Write ClickHouse Error: {}", source))]
+    WriteClickHouse{ source: reqwest::Error },
+    #[cfg(feature = "clickhouse")]
+    #[snafu(display("Export ClickHouse Error: status {}: {}", status, body))]
+    ClickHouseResponse{ status: u16, body: String },
+    #[cfg(feature = "avro")]
+    #[snafu(display("Export Write Avro Error: {}", source))]
+    WriteAvro{ source: apache_avro::Error },
+    #[cfg(feature = "object-store")]
+    #[snafu(display("Object Store Error: {}", source))]
+    ObjectStore{ source: object_store::Error },
+    #[cfg(feature = "object-store")]
+    #[snafu(display("Object Store Error: url {}: {}", url, source))]
+    ObjectStoreUrl{ url: String, source: url::ParseError },
+    #[cfg(feature = "object-store")]
+    #[snafu(display("Object Store Error: starting runtime: {}", source))]
+    ObjectStoreRuntime{ source: std::io::Error },
+    #[cfg(feature = "kafka")]
+    #[snafu(display("Export Kafka Error: {}", source))]
+    WriteKafka{ source: kafka::Error },
+    #[cfg(feature = "kafka")]
+    #[snafu(display("Export Kafka Error: no topic {}", topic))]
+    KafkaTopic{ topic: String },
+    #[cfg(feature = "kafka")]
+    #[snafu(display("Export Json Error: {}", source))]
+    EncodeJson{ source: serde_json::Error },
+    #[cfg(feature = "search")]
+    #[snafu(display("Search Index Error: {}", source))]
+    Search{ source: tantivy::TantivyError },
+    #[cfg(feature = "search")]
+    #[snafu(display("Search Query Error: {}", source))]
+    SearchQuery{ source: tantivy::query::QueryParserError },
+    #[cfg(feature = "sled")]
+    #[snafu(display("Grant Store Error: {}", source))]
+    KvStore{ source: sled::Error },
+    #[cfg(feature = "sled")]
+    #[snafu(display("Grant Store Encoding Error: {}", source))]
+    KvEncode{ source: bincode::Error },
+    #[cfg(feature = "ptab")]
+    #[snafu(display("PTAB Json Error: {}", source))]
+    PtabJson{ source: serde_json::Error },
+    #[cfg(feature = "peds")]
+    #[snafu(display("PEDS Error: {}", source))]
+    Peds{ source: reqwest_async::Error },
+    #[cfg(feature = "patentsview")]
+    #[snafu(display("PatentsView Error: {}", source))]
+    PatentsView{ source: reqwest_async::Error },
+    #[cfg(feature = "assignment-api")]
+    #[snafu(display("Assignment Api Error: {}", source))]
+    AssignmentApi{ source: reqwest_async::Error },
 }
 
+
+impl Error {
+    /// A deserialization error with no context yet; see `within` and
+    /// `at_position`, which add it as the error is returned up through the
+    /// parser.
+    pub(crate) fn deser(src: String) -> Self {
+        Error::Deser {
+            src,
+            position: None,
+            path: Vec::new(),
+            doc_number: None,
+        }
+    }
+
+    /// The variant's name, e.g. "Deser", for counting errors by type (see
+    /// `metrics::Metrics::error`); a document's error is that of the error
+    /// within it.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::Document { source, .. } => source.kind(),
+            Error::Deser { .. } => "Deser",
+            Error::ApsDeser { .. } => "ApsDeser",
+            #[cfg(feature = "bulkdata")]
+            Error::Fetch { .. } => "Fetch",
+            #[cfg(feature = "bulkdata")]
+            Error::FetchRedirect { .. } => "FetchRedirect",
+            Error::CreateFile { .. } => "CreateFile",
+            Error::DownloadSize { .. } => "DownloadSize",
+            Error::Read { .. } => "Read",
+            #[cfg(feature = "zip")]
+            Error::Zip { .. } => "Zip",
+            #[cfg(feature = "zip")]
+            Error::ZipNoXml => "ZipNoXml",
+            #[cfg(feature = "zip")]
+            Error::ZipCompression { .. } => "ZipCompression",
+            #[cfg(not(feature = "zip"))]
+            Error::ZipDisabled => "ZipDisabled",
+            Error::IndexWrite { .. } => "IndexWrite",
+            Error::IndexFormat { .. } => "IndexFormat",
+            Error::CheckpointWrite { .. } => "CheckpointWrite",
+            Error::CheckpointFormat { .. } => "CheckpointFormat",
+            Error::MetricsServer { .. } => "MetricsServer",
+            Error::SyncStateWrite { .. } => "SyncStateWrite",
+            Error::SyncStateFormat { .. } => "SyncStateFormat",
+            Error::MaintFeeFormat { .. } => "MaintFeeFormat",
+            Error::CpcSchemeFormat { .. } => "CpcSchemeFormat",
+            Error::ConcordanceFormat { .. } => "ConcordanceFormat",
+            Error::WriteXml { .. } => "WriteXml",
+            Error::ExportIo { .. } => "ExportIo",
+            #[cfg(feature = "csv")]
+            Error::WriteCsv { .. } => "WriteCsv",
+            #[cfg(feature = "csv")]
+            Error::ReadCsv { .. } => "ReadCsv",
+            #[cfg(feature = "parquet")]
+            Error::Arrow { .. } => "Arrow",
+            #[cfg(feature = "parquet")]
+            Error::WriteParquet { .. } => "WriteParquet",
+            #[cfg(feature = "sqlite")]
+            Error::WriteSqlite { .. } => "WriteSqlite",
+            #[cfg(feature = "postgres")]
+            Error::WritePostgres { .. } => "WritePostgres",
+            #[cfg(feature = "clickhouse")]
+            Error::WriteClickHouse { .. } => "WriteClickHouse",
+            #[cfg(feature = "clickhouse")]
+            Error::ClickHouseResponse { .. } => "ClickHouseResponse",
+            #[cfg(feature = "avro")]
+            Error::WriteAvro { .. } => "WriteAvro",
+            #[cfg(feature = "object-store")]
+            Error::ObjectStore { .. } => "ObjectStore",
+            #[cfg(feature = "object-store")]
+            Error::ObjectStoreUrl { .. } => "ObjectStoreUrl",
+            #[cfg(feature = "object-store")]
+            Error::ObjectStoreRuntime { .. } => "ObjectStoreRuntime",
+            #[cfg(feature = "kafka")]
+            Error::WriteKafka { .. } => "WriteKafka",
+            #[cfg(feature = "kafka")]
+            Error::KafkaTopic { .. } => "KafkaTopic",
+            #[cfg(feature = "kafka")]
+            Error::EncodeJson { .. } => "EncodeJson",
+            #[cfg(feature = "search")]
+            Error::Search { .. } => "Search",
+            #[cfg(feature = "search")]
+            Error::SearchQuery { .. } => "SearchQuery",
+            #[cfg(feature = "sled")]
+            Error::KvStore { .. } => "KvStore",
+            #[cfg(feature = "sled")]
+            Error::KvEncode { .. } => "KvEncode",
+            #[cfg(feature = "ptab")]
+            Error::PtabJson { .. } => "PtabJson",
+            #[cfg(feature = "peds")]
+            Error::Peds { .. } => "Peds",
+            #[cfg(feature = "patentsview")]
+            Error::PatentsView { .. } => "PatentsView",
+            #[cfg(feature = "assignment-api")]
+            Error::AssignmentApi { .. } => "AssignmentApi",
+        }
+    }
+
+    /// Adds an element the error was hit within; called from the innermost
+    /// element outwards.
+    pub(crate) fn within(mut self, element: &[u8]) -> Self {
+        if let Error::Deser { ref mut path, .. } = self {
+            path.insert(0, String::from_utf8_lossy(element).into_owned());
+        }
+        self
+    }
+
+    /// Adds the reader's position and the grant's doc-number, if not already
+    /// there.
+    pub(crate) fn at_position(mut self, reader_position: usize, grant_doc_number: &str) -> Self {
+        if let Error::Deser { ref mut position, ref mut doc_number, .. } = self {
+            position.get_or_insert(reader_position);
+            if doc_number.is_none() && !grant_doc_number.is_empty() {
+                *doc_number = Some(grant_doc_number.to_string());
+            }
+        }
+        self
+    }
+}
+
+/// Adds `within` to results of the deser helpers
+pub(crate) trait WithinExt {
+    fn within(self, element: &[u8]) -> Self;
+}
+
+impl<T> WithinExt for Result<T, Error> {
+    fn within(self, element: &[u8]) -> Self {
+        self.map_err(|err| err.within(element))
+    }
+}
+
+fn deser_context(position: Option<usize>, path: &[String], doc_number: Option<&str>) -> String {
+    let mut context = String::new();
+
+    if let Some(position) = position {
+        let _ = write!(context, "; at byte {}", position);
+    }
+    if !path.is_empty() {
+        let _ = write!(context, "; in {}", path.join("/"));
+    }
+    if let Some(doc_number) = doc_number {
+        let _ = write!(context, "; doc-number {}", doc_number);
+    }
+
+    context
+}
@@ -0,0 +1,324 @@
+//! Writes the export tables as avro object container files, one
+//! `<table>.avro` per table with its schema embedded in the header.
+//!
+//! Schemas are generated from each Row's columns: a record named after the
+//! table, in the `uspto` namespace. Every column but doc_number is a union
+//! with null, defaulting to null, so a column added to a table later can be
+//! read from files written before it (and a removed one ignored), as avro
+//! schema resolution allows.
+//!
+//! A grant can also be encoded on its own, e.g. as a message, in avro's
+//! single object encoding: a `uspto.grant` record of its `grants` row and
+//! arrays of its rows in the other tables (see `grant_schema`).
+
+use apache_avro::{Codec, DeflateSettings, GenericSingleObjectWriter, Schema, Writer};
+use lazy_static::lazy_static;
+use serde::Serialize;
+use snafu::ResultExt;
+use std::fs::{self, File};
+use std::path::Path;
+
+use crate::data::PatentGrant;
+use crate::error::{Error, ExportIo, WriteAvro};
+use super::*;
+
+lazy_static! {
+    static ref GRANTS_SCHEMA: Schema = GrantRow::parse_schema();
+    static ref CLAIMS_SCHEMA: Schema = ClaimRow::parse_schema();
+    static ref CITATIONS_SCHEMA: Schema = CitationRow::parse_schema();
+    static ref CLASSIFICATIONS_SCHEMA: Schema = ClassificationRow::parse_schema();
+    static ref PARTIES_SCHEMA: Schema = PartyRow::parse_schema();
+    static ref GRANT_SCHEMA: Schema = Schema::parse_str(&grant_schema_json())
+        .expect("generated avro schema");
+}
+
+/// Avro types of a Row's columns.
+pub trait AvroRow: Row {
+    /// columns that aren't strings
+    const TYPED_COLUMNS: &'static [(&'static str, &'static str)] = &[];
+
+    /// The schema, parsed
+    fn schema() -> &'static Schema;
+
+    /// The schema as json, as embedded in the files
+    fn schema_json() -> String {
+        let fields: Vec<String> = Self::COLUMNS.iter()
+            .map(|column| {
+                let avro_type = Self::TYPED_COLUMNS.iter()
+                    .find(|(name, _)| name == column)
+                    .map(|(_, avro_type)| *avro_type)
+                    .unwrap_or("string");
+
+                if *column == "doc_number" {
+                    format!(r#"{{"name": "{}", "type": "{}"}}"#, column, avro_type)
+                } else {
+                    format!(r#"{{"name": "{}", "type": ["null", "{}"], "default": null}}"#, column, avro_type)
+                }
+            })
+            .collect();
+
+        format!(
+            r#"{{"type": "record", "name": "{}", "namespace": "uspto", "fields": [{}]}}"#,
+            Self::TABLE,
+            fields.join(", "),
+        )
+    }
+
+    /// generated from COLUMNS, so it can't fail to parse
+    fn parse_schema() -> Schema {
+        Schema::parse_str(&Self::schema_json())
+            .expect("generated avro schema")
+    }
+}
+
+impl AvroRow for GrantRow {
+    const TYPED_COLUMNS: &'static [(&'static str, &'static str)] = &[
+        ("number_of_claims", "long"),
+        ("number_of_figures", "long"),
+        ("number_of_drawing_sheets", "long"),
+    ];
+
+    fn schema() -> &'static Schema {
+        &GRANTS_SCHEMA
+    }
+}
+
+impl AvroRow for ClaimRow {
+    fn schema() -> &'static Schema {
+        &CLAIMS_SCHEMA
+    }
+}
+
+impl AvroRow for CitationRow {
+    fn schema() -> &'static Schema {
+        &CITATIONS_SCHEMA
+    }
+}
+
+impl AvroRow for ClassificationRow {
+    const TYPED_COLUMNS: &'static [(&'static str, &'static str)] = &[
+        ("main", "boolean"),
+    ];
+
+    fn schema() -> &'static Schema {
+        &CLASSIFICATIONS_SCHEMA
+    }
+}
+
+impl AvroRow for PartyRow {
+    fn schema() -> &'static Schema {
+        &PARTIES_SCHEMA
+    }
+}
+
+fn grant_schema_json() -> String {
+    format!(
+        r#"{{"type": "record", "name": "grant", "namespace": "uspto", "fields": [{{"name": "{}", "type": {}}}, {}]}}"#,
+        GrantRow::TABLE,
+        GrantRow::schema_json(),
+        [
+            (ClaimRow::TABLE, ClaimRow::schema_json()),
+            (CitationRow::TABLE, CitationRow::schema_json()),
+            (ClassificationRow::TABLE, ClassificationRow::schema_json()),
+            (PartyRow::TABLE, PartyRow::schema_json()),
+        ].iter()
+            .map(|(table, schema)| format!(r#"{{"name": "{}", "type": {{"type": "array", "items": {}}}}}"#, table, schema))
+            .collect::<Vec<_>>()
+            .join(", "),
+    )
+}
+
+/// The schema of a grant on its own, with all its rows, see
+/// `single_object`
+pub fn grant_schema() -> &'static Schema {
+    &GRANT_SCHEMA
+}
+
+/// The rows of a grant, as in `grant_schema`
+#[derive(Serialize)]
+struct GrantRecord<'a> {
+    grants: &'a GrantRow,
+    claims: &'a [ClaimRow],
+    citations: &'a [CitationRow],
+    classifications: &'a [ClassificationRow],
+    parties: &'a [PartyRow],
+}
+
+/// A grant in avro's single object encoding: a header with the fingerprint
+/// of `grant_schema`, then the record
+pub fn single_object(patent_grant: &PatentGrant) -> Result<Vec<u8>, Error> {
+    let rows = Rows::from(patent_grant);
+    let record = GrantRecord {
+        grants: &rows.grant,
+        claims: &rows.claims,
+        citations: &rows.citations,
+        classifications: &rows.classifications,
+        parties: &rows.parties,
+    };
+
+    let value = apache_avro::to_value(record)
+        .and_then(|value| value.resolve(grant_schema()))
+        .context(WriteAvro)?;
+
+    let mut writer = GenericSingleObjectWriter::new_with_capacity(grant_schema(), 1024)
+        .context(WriteAvro)?;
+    let mut bytes = Vec::new();
+    writer.write_value(value, &mut bytes)
+        .context(WriteAvro)?;
+
+    Ok(bytes)
+}
+
+pub struct AvroExporter {
+    grants: TableWriter,
+    claims: TableWriter,
+    citations: TableWriter,
+    classifications: TableWriter,
+    parties: TableWriter,
+}
+
+impl AvroExporter {
+    /// Creates out_dir if needed, and a `<table>.avro` in it for each table;
+    /// an existing file is truncated. Blocks are deflate compressed.
+    pub fn create(out_dir: &Path) -> Result<Self, Error> {
+        fs::create_dir_all(out_dir)
+            .context(ExportIo)?;
+
+        Ok(AvroExporter {
+            grants: TableWriter::create::<GrantRow>(out_dir)?,
+            claims: TableWriter::create::<ClaimRow>(out_dir)?,
+            citations: TableWriter::create::<CitationRow>(out_dir)?,
+            classifications: TableWriter::create::<ClassificationRow>(out_dir)?,
+            parties: TableWriter::create::<PartyRow>(out_dir)?,
+        })
+    }
+
+    pub fn write(&mut self, patent_grant: &PatentGrant) -> Result<(), Error> {
+        let rows = Rows::from(patent_grant);
+
+        self.grants.append(&[rows.grant])?;
+        self.claims.append(&rows.claims)?;
+        self.citations.append(&rows.citations)?;
+        self.classifications.append(&rows.classifications)?;
+        self.parties.append(&rows.parties)?;
+
+        Ok(())
+    }
+
+    /// Writes out the remaining blocks
+    pub fn finish(self) -> Result<(), Error> {
+        self.grants.finish()?;
+        self.claims.finish()?;
+        self.citations.finish()?;
+        self.classifications.finish()?;
+        self.parties.finish()?;
+
+        Ok(())
+    }
+}
+
+struct TableWriter {
+    writer: Writer<'static, File>,
+}
+
+impl TableWriter {
+    fn create<R: AvroRow>(out_dir: &Path) -> Result<Self, Error> {
+        let path = out_dir.join(format!("{}.avro", R::TABLE));
+        let file = File::create(path)
+            .context(ExportIo)?;
+
+        let writer = Writer::with_codec(R::schema(), file, Codec::Deflate(DeflateSettings::default()))
+            .context(WriteAvro)?;
+
+        Ok(TableWriter {
+            writer,
+        })
+    }
+
+    fn append<R: AvroRow>(&mut self, rows: &[R]) -> Result<(), Error> {
+        for row in rows {
+            self.writer.append_ser(row)
+                .context(WriteAvro)?;
+        }
+
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<(), Error> {
+        // also writes the header, for a table with no rows
+        self.writer.flush()
+            .context(WriteAvro)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PatentGrants;
+    use apache_avro::types::Value;
+    use apache_avro::{GenericSingleObjectReader, Reader};
+
+    const FIXTURE: &str = include_str!("../../tests/fixtures/grants.xml");
+
+    #[test]
+    fn test_schema() {
+        let Schema::Record(record) = GrantRow::schema() else { panic!("not a record") };
+        assert_eq!(record.name.fullname(None), "uspto.grants");
+        let columns: Vec<_> = record.fields.iter().map(|field| field.name.as_str()).collect();
+        assert_eq!(columns, GrantRow::COLUMNS);
+
+        assert_eq!(record.fields[0].schema, Schema::String);
+        assert!(matches!(record.fields[8].schema, Schema::Union(_)));
+        assert!(record.fields[8].default.as_ref().is_some_and(|default| default.is_null()));
+
+        for schema in &[ClaimRow::schema(), CitationRow::schema(), ClassificationRow::schema(), PartyRow::schema()] {
+            assert!(matches!(schema, Schema::Record(_)));
+        }
+    }
+
+    #[test]
+    fn test_single_object() {
+        let patent_grant = PatentGrants::from_reader(FIXTURE.as_bytes()).next().unwrap().unwrap();
+        let bytes = single_object(&patent_grant).unwrap();
+        assert_eq!(&bytes[..2], &[0xc3, 0x01]);
+
+        let reader = GenericSingleObjectReader::builder().schema(grant_schema().clone()).build().unwrap();
+        let value = reader.read_value(&mut &bytes[..]).unwrap();
+        let Value::Record(fields) = value else { panic!("not a record") };
+        let names: Vec<_> = fields.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["grants", "claims", "citations", "classifications", "parties"]);
+        let Value::Array(claims) = &fields[1].1 else { panic!("not an array") };
+        assert_eq!(claims.len(), 3);
+    }
+
+    #[test]
+    fn test_avro_export() {
+        let out_dir = std::env::temp_dir().join(format!("uspto-avro-export-{}", std::process::id()));
+
+        let mut exporter = AvroExporter::create(&out_dir).unwrap();
+        for patent_grant in PatentGrants::from_reader(FIXTURE.as_bytes()) {
+            exporter.write(&patent_grant.unwrap()).unwrap();
+        }
+        exporter.finish().unwrap();
+
+        let read = |table: &str| -> (Schema, Vec<Value>) {
+            let reader = Reader::new(File::open(out_dir.join(format!("{}.avro", table))).unwrap()).unwrap();
+            let schema = reader.writer_schema().clone();
+            (schema, reader.collect::<Result<_, _>>().unwrap())
+        };
+
+        let (schema, grants) = read("grants");
+        assert_eq!(&schema, GrantRow::schema());
+        assert_eq!(grants.len(), 3);
+        let Value::Record(fields) = &grants[0] else { panic!("not a record") };
+        assert_eq!(fields[0], ("doc_number".to_string(), Value::String("10165721".to_string())));
+        assert_eq!(fields[8], ("number_of_claims".to_string(), Value::Union(1, Box::new(Value::Long(3)))));
+
+        assert_eq!(read("claims").1.len(), 5);
+        assert_eq!(read("citations").1.len(), 4);
+
+        fs::remove_dir_all(&out_dir).unwrap();
+    }
+}
@@ -0,0 +1,529 @@
+//! Loads the export tables into ClickHouse over its HTTP interface, as
+//! RowBinary.
+//!
+//! `schema` is the tables' DDL, generated from the rows: `grants` is a
+//! ReplacingMergeTree on doc_number, so a grant loaded again (e.g. a
+//! re-publication, or a file exported again) replaces the one before once
+//! parts merge; the other tables are MergeTrees ordered by doc_number, and
+//! their rows of a grant are deleted (a lightweight DELETE, ClickHouse 23.3
+//! or later) before it's loaded again. Grants are buffered and inserted a
+//! batch at a time, one INSERT per table, retried with backoff when the
+//! server can't be reached or answers with a 5xx.
+//!
+//! A retried INSERT may have been written before its response was lost, so
+//! each is sent with an `insert_deduplication_token` of its exporter, table
+//! and rows, and the tables keep the tokens of their last
+//! `DEDUPLICATION_WINDOW` inserts: the same batch inserted again by the same
+//! exporter is dropped.
+
+use snafu::ResultExt;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::data::PatentGrant;
+use crate::error::{ClickHouseResponse, Error, WriteClickHouse};
+use crate::fingerprint::Fnv;
+use super::*;
+
+/// grants buffered per insert
+pub const DEFAULT_BATCH_SIZE: usize = 10_000;
+/// attempts at each insert, after the first
+pub const DEFAULT_RETRIES: u32 = 3;
+/// inserts per table whose deduplication tokens are kept
+pub const DEDUPLICATION_WINDOW: u32 = 1000;
+
+/// A column value in ClickHouse's RowBinary format
+pub trait RowBinary {
+    /// the column type, e.g. `Nullable(String)`
+    fn clickhouse_type(&self) -> &'static str;
+
+    fn encode(&self, buf: &mut Vec<u8>);
+}
+
+impl RowBinary for String {
+    fn clickhouse_type(&self) -> &'static str {
+        "String"
+    }
+
+    // LEB128 length, then the bytes
+    fn encode(&self, buf: &mut Vec<u8>) {
+        let mut len = self.len();
+        loop {
+            let byte = (len & 0x7f) as u8;
+            len >>= 7;
+            if len == 0 {
+                buf.push(byte);
+                break;
+            }
+            buf.push(byte | 0x80);
+        }
+        buf.extend_from_slice(self.as_bytes());
+    }
+}
+
+impl RowBinary for bool {
+    fn clickhouse_type(&self) -> &'static str {
+        "Bool"
+    }
+
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.push(*self as u8);
+    }
+}
+
+impl RowBinary for Option<String> {
+    fn clickhouse_type(&self) -> &'static str {
+        "Nullable(String)"
+    }
+
+    fn encode(&self, buf: &mut Vec<u8>) {
+        match self {
+            Some(value) => {
+                buf.push(0);
+                value.encode(buf);
+            },
+            None => buf.push(1),
+        }
+    }
+}
+
+impl RowBinary for Option<u32> {
+    fn clickhouse_type(&self) -> &'static str {
+        "Nullable(UInt32)"
+    }
+
+    fn encode(&self, buf: &mut Vec<u8>) {
+        match self {
+            Some(value) => {
+                buf.push(0);
+                buf.extend_from_slice(&value.to_le_bytes());
+            },
+            None => buf.push(1),
+        }
+    }
+}
+
+/// ClickHouse values of a Row's columns, and its table.
+pub trait ClickHouseRow: Row + Default {
+    /// in COLUMNS order
+    fn values(&self) -> Vec<&dyn RowBinary>;
+
+    fn encode(&self, buf: &mut Vec<u8>) {
+        for value in self.values() {
+            value.encode(buf);
+        }
+    }
+
+    /// column types are those of the values
+    fn create_table() -> String {
+        let row = Self::default();
+        let columns: Vec<String> = Self::COLUMNS.iter()
+            .zip(row.values())
+            .map(|(column, value)| format!("{} {}", column, value.clickhouse_type()))
+            .collect();
+
+        let engine = if Self::TABLE == GrantRow::TABLE { "ReplacingMergeTree" } else { "MergeTree" };
+        format!("CREATE TABLE IF NOT EXISTS {} ({}) ENGINE = {} ORDER BY doc_number SETTINGS non_replicated_deduplication_window = {}", Self::TABLE, columns.join(", "), engine, DEDUPLICATION_WINDOW)
+    }
+}
+
+impl ClickHouseRow for GrantRow {
+    fn values(&self) -> Vec<&dyn RowBinary> {
+        vec![
+            &self.doc_number,
+            &self.kind,
+            &self.country,
+            &self.date,
+            &self.application_doc_number,
+            &self.application_date,
+            &self.us_application_series_code,
+            &self.invention_title,
+            &self.number_of_claims,
+            &self.number_of_figures,
+            &self.number_of_drawing_sheets,
+            &self.length_of_grant,
+            &self.us_term_extension,
+            &self.art_unit,
+        ]
+    }
+}
+
+impl ClickHouseRow for ClaimRow {
+    fn values(&self) -> Vec<&dyn RowBinary> {
+        vec![
+            &self.doc_number,
+            &self.num,
+            &self.id,
+            &self.claim_type,
+            &self.claim_refs,
+            &self.text,
+        ]
+    }
+}
+
+impl ClickHouseRow for CitationRow {
+    fn values(&self) -> Vec<&dyn RowBinary> {
+        vec![
+            &self.doc_number,
+            &self.num,
+            &self.citation_type,
+            &self.category,
+            &self.cited_country,
+            &self.cited_doc_number,
+            &self.cited_kind,
+            &self.cited_name,
+            &self.cited_date,
+            &self.othercit,
+            &self.classification_cpc_text,
+            &self.classification_national,
+        ]
+    }
+}
+
+impl ClickHouseRow for ClassificationRow {
+    fn values(&self) -> Vec<&dyn RowBinary> {
+        vec![
+            &self.doc_number,
+            &self.scheme,
+            &self.main,
+            &self.symbol,
+            &self.version,
+        ]
+    }
+}
+
+impl ClickHouseRow for PartyRow {
+    fn values(&self) -> Vec<&dyn RowBinary> {
+        vec![
+            &self.doc_number,
+            &self.role,
+            &self.sequence,
+            &self.orgname,
+            &self.first_name,
+            &self.last_name,
+            &self.city,
+            &self.state,
+            &self.country,
+        ]
+    }
+}
+
+/// The tables' DDL, one statement each (the HTTP interface takes one at a
+/// time)
+pub fn schema() -> Vec<String> {
+    vec![
+        GrantRow::create_table(),
+        ClaimRow::create_table(),
+        CitationRow::create_table(),
+        ClassificationRow::create_table(),
+        PartyRow::create_table(),
+    ]
+}
+
+/// A table's rows waiting to be inserted, as RowBinary
+struct TableBuffer<R> {
+    buf: Vec<u8>,
+    _row: std::marker::PhantomData<R>,
+}
+
+impl<R: ClickHouseRow> TableBuffer<R> {
+    fn new() -> Self {
+        TableBuffer {
+            buf: Vec::new(),
+            _row: std::marker::PhantomData,
+        }
+    }
+
+    fn push(&mut self, rows: &[R]) {
+        for row in rows {
+            row.encode(&mut self.buf);
+        }
+    }
+
+    fn insert(&self) -> String {
+        format!("INSERT INTO {} ({}) FORMAT RowBinary", R::TABLE, R::COLUMNS.join(", "))
+    }
+}
+
+/// The same for the same rows of the same table from the same exporter,
+/// however often they're sent
+fn deduplication_token(exporter: &str, query: &str, body: &[u8]) -> String {
+    let mut hash = Fnv::new();
+    hash.write(query.as_bytes());
+    hash.write(body);
+
+    format!("{}-{:016x}-{}", exporter, hash.0, body.len())
+}
+
+/// Deletes the rows of the grants from a table other than `grants`
+fn delete_grants(table: &str, doc_numbers: &[String]) -> String {
+    let quoted: Vec<String> = doc_numbers.iter()
+        .map(|doc_number| format!("'{}'", doc_number.replace('\\', "\\\\").replace('\'', "\\'")))
+        .collect();
+
+    format!("DELETE FROM {} WHERE doc_number IN ({})", table, quoted.join(", "))
+}
+
+pub struct ClickHouseExporter {
+    client: reqwest::Client,
+    url: String,
+    batch_size: usize,
+    retries: u32,
+    // tells its inserts from another exporter's, see deduplication_token
+    id: String,
+    // doc_numbers of the grants buffered
+    pending: Vec<String>,
+    grants: TableBuffer<GrantRow>,
+    claims: TableBuffer<ClaimRow>,
+    citations: TableBuffer<CitationRow>,
+    classifications: TableBuffer<ClassificationRow>,
+    parties: TableBuffer<PartyRow>,
+}
+
+impl ClickHouseExporter {
+    /// url of the HTTP interface, e.g. `http://localhost:8123/?database=uspto`
+    /// (with `user` and `password` too, if needed)
+    pub fn new(url: &str) -> Self {
+        let started = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+
+        ClickHouseExporter {
+            client: reqwest::Client::new(),
+            url: url.to_string(),
+            batch_size: DEFAULT_BATCH_SIZE,
+            retries: DEFAULT_RETRIES,
+            id: format!("{:x}{:08x}", started.as_nanos(), std::process::id()),
+            pending: Vec::new(),
+            grants: TableBuffer::new(),
+            claims: TableBuffer::new(),
+            citations: TableBuffer::new(),
+            classifications: TableBuffer::new(),
+            parties: TableBuffer::new(),
+        }
+    }
+
+    /// Number of grants buffered before they're inserted
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Number of times a failed request is retried, waiting twice as long
+    /// each time, from a second
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Creates the tables if they aren't there
+    pub fn create_tables(&self) -> Result<(), Error> {
+        for statement in schema() {
+            self.post(&statement, None, Vec::new())?;
+        }
+
+        Ok(())
+    }
+
+    pub fn write(&mut self, patent_grant: &PatentGrant) -> Result<(), Error> {
+        let rows = Rows::from(patent_grant);
+
+        self.pending.push(rows.grant.doc_number.clone());
+        self.grants.push(&[rows.grant]);
+        self.claims.push(&rows.claims);
+        self.citations.push(&rows.citations);
+        self.classifications.push(&rows.classifications);
+        self.parties.push(&rows.parties);
+
+        if self.pending.len() >= self.batch_size {
+            self.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// Inserts the grants still buffered
+    pub fn finish(mut self) -> Result<(), Error> {
+        self.flush()
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+
+        // the rows of the grants loaded before, if any, go first
+        let doc_numbers = std::mem::take(&mut self.pending);
+        for table in [ClaimRow::TABLE, CitationRow::TABLE, ClassificationRow::TABLE, PartyRow::TABLE] {
+            self.post(&delete_grants(table, &doc_numbers), None, Vec::new())?;
+        }
+
+        // grants last, so a grant isn't there before its rows are
+        let inserts = [
+            (self.claims.insert(), std::mem::take(&mut self.claims.buf)),
+            (self.citations.insert(), std::mem::take(&mut self.citations.buf)),
+            (self.classifications.insert(), std::mem::take(&mut self.classifications.buf)),
+            (self.parties.insert(), std::mem::take(&mut self.parties.buf)),
+            (self.grants.insert(), std::mem::take(&mut self.grants.buf)),
+        ];
+
+        for (query, body) in inserts {
+            if !body.is_empty() {
+                let token = deduplication_token(&self.id, &query, &body);
+                self.post(&query, Some(&token), body)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// the query, with body as its data, deduplicated on the token if any
+    fn post(&self, query: &str, dedup_token: Option<&str>, body: Vec<u8>) -> Result<(), Error> {
+        let mut params = vec![("query", query)];
+        if let Some(token) = dedup_token {
+            params.push(("insert_deduplication_token", token));
+        }
+
+        let mut attempt = 0;
+        loop {
+            let res = self.client.post(&self.url)
+                .query(&params)
+                .body(body.clone())
+                .send();
+
+            let retry = match res {
+                Ok(ref resp) => resp.status().is_server_error(),
+                Err(_) => true,
+            };
+            if retry && attempt < self.retries {
+                thread::sleep(Duration::from_secs(1 << attempt));
+                attempt += 1;
+                continue;
+            }
+
+            let mut resp = res.context(WriteClickHouse)?;
+            if !resp.status().is_success() {
+                return ClickHouseResponse {
+                    status: resp.status().as_u16(),
+                    body: resp.text().unwrap_or_default(),
+                }.fail();
+            }
+
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_row_binary() {
+        let mut buf = Vec::new();
+        "ab".to_string().encode(&mut buf);
+        "x".repeat(200).encode(&mut buf);
+        assert_eq!(&buf[..5], &[2, b'a', b'b', 0xc8, 0x01]);
+        assert_eq!(buf.len(), 5 + 200);
+
+        let mut buf = Vec::new();
+        None::<String>.encode(&mut buf);
+        Some(7u32).encode(&mut buf);
+        true.encode(&mut buf);
+        assert_eq!(buf, vec![1, 0, 7, 0, 0, 0, 1]);
+
+        let row = ClassificationRow {
+            doc_number: "1".to_string(),
+            scheme: "cpc".to_string(),
+            main: true,
+            symbol: "A".to_string(),
+            version: None,
+        };
+        let mut buf = Vec::new();
+        row.encode(&mut buf);
+        assert_eq!(buf, b"\x011\x03cpc\x01\x01A\x01".to_vec());
+    }
+
+    #[test]
+    fn test_schema() {
+        let schema = schema();
+        assert_eq!(schema.len(), 5);
+        assert!(schema[0].starts_with("CREATE TABLE IF NOT EXISTS grants (doc_number String, kind Nullable(String), "));
+        assert!(schema[0].contains(", number_of_claims Nullable(UInt32), "));
+        assert!(schema[0].ends_with(") ENGINE = ReplacingMergeTree ORDER BY doc_number SETTINGS non_replicated_deduplication_window = 1000"));
+        assert!(schema[3].contains("(doc_number String, scheme String, main Bool, symbol String, version Nullable(String))"));
+    }
+
+    /// answers each request with the next status, recording the queries
+    fn serve(statuses: Vec<u16>) -> (String, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+
+        let handle = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for status in statuses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut rdr = BufReader::new(stream);
+
+                let mut request_line = String::new();
+                rdr.read_line(&mut request_line).unwrap();
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    rdr.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(len) = line.to_lowercase().strip_prefix("content-length:") {
+                        content_length = len.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0; content_length];
+                rdr.read_exact(&mut body).unwrap();
+                requests.push(request_line);
+
+                write!(rdr.get_mut(), "HTTP/1.1 {} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status).unwrap();
+            }
+            requests
+        });
+
+        (url, handle)
+    }
+
+    #[test]
+    fn test_insert_batches() {
+        // deletes from claims, citations, classifications and parties, then
+        // inserts into them and grants; a 503 retried
+        let (url, handle) = serve(vec![200, 200, 200, 200, 200, 503, 200, 200, 200, 200]);
+
+        let mut exporter = ClickHouseExporter::new(&url).batch_size(10).retries(1);
+        let grants = crate::PatentGrants::from_reader(include_str!("../../tests/fixtures/grants.xml").as_bytes());
+        for patent_grant in grants {
+            exporter.write(&patent_grant.unwrap()).unwrap();
+        }
+        exporter.finish().unwrap();
+
+        let requests = handle.join().unwrap();
+        assert_eq!(requests.len(), 10);
+        assert!(requests[0].contains("DELETE+FROM+claims+WHERE+doc_number+IN+%28%2710165721%27%2C+%27D0838001%27%2C+%27PP030001%27%29"), "{}", requests[0]);
+        assert!(requests[3].contains("DELETE+FROM+parties"));
+        assert!(requests[4].contains("INSERT+INTO+claims"));
+        assert!(requests[5].contains("INSERT+INTO+citations"));
+        assert!(requests[6].contains("INSERT+INTO+citations"));
+        assert!(requests[9].contains("INSERT+INTO+grants"));
+        // the retry is the same insert, so it's deduplicated as one
+        let token = |request: &str| request.split(['&', ' ']).find(|param| param.starts_with("insert_deduplication_token=")).unwrap().to_string();
+        assert_eq!(token(&requests[5]), token(&requests[6]));
+        assert_ne!(token(&requests[4]), token(&requests[5]));
+    }
+
+    #[test]
+    fn test_error_response() {
+        let (url, handle) = serve(vec![400]);
+
+        let exporter = ClickHouseExporter::new(&url).retries(0);
+        let err = exporter.create_tables().unwrap_err();
+        assert!(err.to_string().contains("400"));
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,115 @@
+//! Writes the export tables as one csv file per table into a directory.
+
+use snafu::ResultExt;
+use std::fs::File;
+use std::path::Path;
+
+use crate::data::PatentGrant;
+use crate::error::{Error, WriteCsv};
+use super::tables::{self, write_rows, Delimited, TableDir};
+use super::*;
+
+pub struct CsvExporter {
+    grants: ::csv::Writer<File>,
+    claims: ::csv::Writer<File>,
+    citations: ::csv::Writer<File>,
+    classifications: ::csv::Writer<File>,
+    parties: ::csv::Writer<File>,
+}
+
+impl CsvExporter {
+    /// A `<table>.csv` in out_dir for each table, see `export::tables`
+    pub fn create(out_dir: &Path) -> Result<Self, Error> {
+        let dir = TableDir::create(out_dir, Delimited::Csv)?;
+
+        Ok(CsvExporter {
+            grants: dir.writer::<GrantRow>()?,
+            claims: dir.writer::<ClaimRow>()?,
+            citations: dir.writer::<CitationRow>()?,
+            classifications: dir.writer::<ClassificationRow>()?,
+            parties: dir.writer::<PartyRow>()?,
+        })
+    }
+
+    pub fn write(&mut self, patent_grant: &PatentGrant) -> Result<(), Error> {
+        let rows = Rows::from(patent_grant);
+
+        self.grants.serialize(&rows.grant).context(WriteCsv)?;
+        write_rows(&mut self.claims, &rows.claims)?;
+        write_rows(&mut self.citations, &rows.citations)?;
+        write_rows(&mut self.classifications, &rows.classifications)?;
+        write_rows(&mut self.parties, &rows.parties)?;
+
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<(), Error> {
+        tables::flush(&mut [
+            &mut self.grants,
+            &mut self.claims,
+            &mut self.citations,
+            &mut self.classifications,
+            &mut self.parties,
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::PatentGrants;
+
+    const FIXTURE: &str = include_str!("../../tests/fixtures/grants.xml");
+
+    // COLUMNS has to be kept in sync with the row's fields by hand
+    fn serialized_header<R: Row + Default>() -> Vec<String> {
+        let mut wtr = ::csv::Writer::from_writer(vec![]);
+        wtr.serialize(R::default()).unwrap();
+        let data = String::from_utf8(wtr.into_inner().unwrap()).unwrap();
+
+        data.lines().next().unwrap().split(',').map(|s| s.to_owned()).collect()
+    }
+
+    #[test]
+    fn test_columns_match_rows() {
+        assert_eq!(serialized_header::<GrantRow>(), GrantRow::COLUMNS);
+        assert_eq!(serialized_header::<ClaimRow>(), ClaimRow::COLUMNS);
+        assert_eq!(serialized_header::<CitationRow>(), CitationRow::COLUMNS);
+        assert_eq!(serialized_header::<ClassificationRow>(), ClassificationRow::COLUMNS);
+        assert_eq!(serialized_header::<PartyRow>(), PartyRow::COLUMNS);
+    }
+
+    #[test]
+    fn test_csv_export() {
+        let out_dir = std::env::temp_dir().join(format!("uspto-csv-export-{}", std::process::id()));
+
+        let mut exporter = CsvExporter::create(&out_dir).unwrap();
+        for patent_grant in PatentGrants::from_reader(FIXTURE.as_bytes()) {
+            exporter.write(&patent_grant.unwrap()).unwrap();
+        }
+        exporter.finish().unwrap();
+
+        let grants = fs::read_to_string(out_dir.join("grants.csv")).unwrap();
+        assert_eq!(grants.lines().count(), 4);
+        assert!(grants.lines().nth(1).unwrap().starts_with("10165721,B2,US,20190101,"));
+
+        let claims = fs::read_to_string(out_dir.join("claims.csv")).unwrap();
+        assert_eq!(claims.lines().count(), 6);
+        assert!(claims.contains("10165721,00003,CLM-00003,,CLM-00001;CLM-00002,"));
+
+        let citations = fs::read_to_string(out_dir.join("citations.csv")).unwrap();
+        // typed dates take the 00 day as the first
+        let cited_date = if cfg!(feature = "typed-dates") { "19831201" } else { "19831200" };
+        assert!(citations.contains(&format!("10165721,00001,patent,cited by examiner,US,4418955,A,Muckle,{},,A01B 1/00,294 49", cited_date)));
+
+        let classifications = fs::read_to_string(out_dir.join("classifications.csv")).unwrap();
+        assert!(classifications.contains("10165721,cpc,true,A01B 1/00,20130101"));
+        assert!(classifications.contains("D0838001,locarno,true,0806,"));
+
+        let parties = fs::read_to_string(out_dir.join("parties.csv")).unwrap();
+        assert!(parties.lines().any(|line| line.starts_with("10165721,primary-examiner,")));
+
+        fs::remove_dir_all(&out_dir).unwrap();
+    }
+}
@@ -0,0 +1,274 @@
+//! Publishes grants to a kafka topic, a message for each keyed by its
+//! doc-number, e.g. at the head of a streaming pipeline.
+//!
+//! Messages are sent in batches. Sending is synchronous, so writing a grant
+//! that fills a batch blocks until the brokers have acknowledged it (as
+//! configured on the Producer): the parser goes no faster than the topic
+//! takes grants. Grants a broker refused are reported, with the error, as
+//! DeliveryFailures rather than failing the write; an error reaching the
+//! brokers at all is returned.
+//!
+//! Messages are partitioned as kafka's java producer does (murmur2 of the
+//! key), so a grant goes to the same partition whichever produced it.
+
+use kafka::error::KafkaCode;
+use kafka::producer::{ProduceConfirm, Producer, Record, RequiredAcks};
+use snafu::{OptionExt, ResultExt};
+use std::time::Duration;
+
+use crate::data::PatentGrant;
+use crate::error::{EncodeJson, Error, KafkaTopic, WriteKafka};
+
+/// grants sent per batch
+pub const DEFAULT_BATCH_SIZE: usize = 100;
+
+/// How a grant's message is encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Payload {
+    /// the grant as json, as in the cli's jsonl output
+    Json,
+    /// the grant's rows in avro single object encoding, see
+    /// `export::avro::single_object`
+    #[cfg(feature = "avro")]
+    Avro,
+}
+
+impl Payload {
+    pub fn encode(self, patent_grant: &PatentGrant) -> Result<Vec<u8>, Error> {
+        match self {
+            Payload::Json => serde_json::to_vec(patent_grant)
+                .context(EncodeJson),
+            #[cfg(feature = "avro")]
+            Payload::Avro => super::avro::single_object(patent_grant),
+        }
+    }
+}
+
+/// A grant the brokers didn't take
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeliveryFailure {
+    pub doc_number: String,
+    pub partition: i32,
+    pub error: KafkaCode,
+}
+
+/// See `KafkaSink::finish`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeliveryReport {
+    /// grants acknowledged, or sent if no acks are required
+    pub delivered: usize,
+    pub failures: Vec<DeliveryFailure>,
+}
+
+pub struct KafkaSink {
+    producer: Producer,
+    topic: String,
+    payload: Payload,
+    partitions: u32,
+    batch_size: usize,
+    // doc-number and message of each grant not sent yet
+    batch: Vec<(String, Vec<u8>)>,
+    report: DeliveryReport,
+}
+
+impl KafkaSink {
+    /// Connects to the brokers at hosts ("host:port"), waiting up to a
+    /// second for the partition leader to acknowledge each batch
+    pub fn connect(hosts: Vec<String>, topic: &str, payload: Payload) -> Result<Self, Error> {
+        let producer = Producer::from_hosts(hosts)
+            .with_ack_timeout(Duration::from_secs(1))
+            .with_required_acks(RequiredAcks::One)
+            .create()
+            .context(WriteKafka)?;
+
+        Self::new(producer, topic, payload)
+    }
+
+    /// With a producer configured otherwise (e.g. acks from all replicas,
+    /// or compression). The topic must exist.
+    pub fn new(producer: Producer, topic: &str, payload: Payload) -> Result<Self, Error> {
+        let partitions = producer.client().topics()
+            .partitions(topic)
+            .map(|partitions| partitions.len() as u32)
+            .filter(|len| *len > 0)
+            .context(KafkaTopic { topic })?;
+
+        Ok(KafkaSink {
+            producer,
+            topic: topic.to_string(),
+            payload,
+            partitions,
+            batch_size: DEFAULT_BATCH_SIZE,
+            batch: Vec::new(),
+            report: DeliveryReport::default(),
+        })
+    }
+
+    /// Number of grants sent at a time
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn write(&mut self, patent_grant: &PatentGrant) -> Result<(), Error> {
+        let doc_number = patent_grant.us_bibliographic_data_grant.publication_reference.doc_number.clone();
+        let message = self.payload.encode(patent_grant)?;
+        self.batch.push((doc_number, message));
+
+        if self.batch.len() >= self.batch_size {
+            self.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// Sends the grants written since the last batch
+    pub fn flush(&mut self) -> Result<(), Error> {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+
+        let (topic, partitions) = (&self.topic, self.partitions);
+        let records: Vec<_> = self.batch.iter()
+            .map(|(doc_number, message)| {
+                Record::from_key_value(topic, doc_number.as_bytes(), &message[..])
+                    .with_partition(partition(doc_number, partitions))
+            })
+            .collect();
+
+        let confirms = self.producer.send_all(&records)
+            .context(WriteKafka)?;
+
+        let failures = delivery_failures(&self.batch, self.partitions, &confirms);
+        self.report.delivered += self.batch.len() - failures.len();
+        self.report.failures.extend(failures);
+        self.batch.clear();
+
+        Ok(())
+    }
+
+    /// Grants the brokers refused so far
+    pub fn failures(&self) -> &[DeliveryFailure] {
+        &self.report.failures
+    }
+
+    /// Sends the remaining grants
+    pub fn finish(mut self) -> Result<DeliveryReport, Error> {
+        self.flush()?;
+        Ok(self.report)
+    }
+}
+
+/// The grants of a batch sent to partitions that returned an error; a
+/// partition's messages are appended, or not, all together
+fn delivery_failures(batch: &[(String, Vec<u8>)], partitions: u32, confirms: &[ProduceConfirm]) -> Vec<DeliveryFailure> {
+    let mut failures = Vec::new();
+
+    for partition_confirm in confirms.iter().flat_map(|confirm| &confirm.partition_confirms) {
+        if let Err(error) = partition_confirm.offset {
+            for (doc_number, _) in batch {
+                if partition(doc_number, partitions) == partition_confirm.partition {
+                    failures.push(DeliveryFailure {
+                        doc_number: doc_number.clone(),
+                        partition: partition_confirm.partition,
+                        error,
+                    });
+                }
+            }
+        }
+    }
+
+    failures
+}
+
+/// As kafka's java DefaultPartitioner for a keyed record
+fn partition(key: &str, partitions: u32) -> i32 {
+    ((murmur2(key.as_bytes()) & 0x7fff_ffff) % partitions) as i32
+}
+
+/// As kafka's java Utils.murmur2
+fn murmur2(data: &[u8]) -> u32 {
+    const SEED: u32 = 0x9747_b28c;
+    const M: u32 = 0x5bd1_e995;
+    const R: u32 = 24;
+
+    let mut h = SEED ^ data.len() as u32;
+
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        let mut k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        h = h.wrapping_mul(M);
+        h ^= k;
+    }
+
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        for (i, byte) in tail.iter().enumerate().rev() {
+            h ^= u32::from(*byte) << (8 * i);
+        }
+        h = h.wrapping_mul(M);
+    }
+
+    h ^= h >> 13;
+    h = h.wrapping_mul(M);
+    h ^= h >> 15;
+
+    h
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PatentGrants;
+    use kafka::producer::ProducePartitionConfirm;
+
+    #[test]
+    fn test_murmur2() {
+        // from kafka's UtilsTest
+        assert_eq!(murmur2(b"21") as i32, -973_932_308);
+        assert_eq!(murmur2(b"foobar") as i32, -790_332_482);
+        assert_eq!(murmur2(b"a-little-bit-long-string") as i32, -985_981_536);
+        assert_eq!(murmur2(b"a-little-bit-longer-string") as i32, -1_486_304_829);
+        assert_eq!(murmur2(b"lkjh234lh9fiuh90y23oiuhsafujhadof229phr9h19h89h8") as i32, -58_897_971);
+        assert_eq!(murmur2(b"abc") as i32, 479_470_107);
+
+        for doc_number in &["10165721", "D0838001", "PP030001"] {
+            assert!((0..3).contains(&partition(doc_number, 3)));
+        }
+    }
+
+    #[test]
+    fn test_delivery_failures() {
+        let batch: Vec<_> = ["10165721", "D0838001", "PP030001"].iter()
+            .map(|doc_number| (doc_number.to_string(), Vec::new()))
+            .collect();
+        let failed = partition("D0838001", 4);
+
+        let confirms = vec![ProduceConfirm {
+            topic: "grants".to_string(),
+            partition_confirms: (0..4)
+                .map(|partition| ProducePartitionConfirm {
+                    offset: if partition == failed { Err(KafkaCode::NotLeaderForPartition) } else { Ok(0) },
+                    partition,
+                })
+                .collect(),
+        }];
+
+        let failures = delivery_failures(&batch, 4, &confirms);
+        assert!(failures.iter().any(|failure| failure.doc_number == "D0838001"));
+        assert!(failures.iter().all(|failure| failure.partition == failed && failure.error == KafkaCode::NotLeaderForPartition));
+        assert!(delivery_failures(&batch, 4, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_payload() {
+        let patent_grant = PatentGrants::from_reader(include_str!("../../tests/fixtures/grants.xml").as_bytes())
+            .next().unwrap().unwrap();
+
+        let json: serde_json::Value = serde_json::from_slice(&Payload::Json.encode(&patent_grant).unwrap()).unwrap();
+        assert_eq!(json["us_bibliographic_data_grant"]["publication_reference"]["doc_number"], "10165721");
+    }
+}
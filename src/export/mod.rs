@@ -0,0 +1,390 @@
+//! Flattening of PatentGrant into rows of relational tables, for export.
+//!
+//! Each grant flattens to one row in `grants` and any number of rows in the
+//! other tables, all keyed on the grant's publication doc-number. Columns are
+//! in struct field order, which is also the order given by `Row::COLUMNS`.
+
+#[cfg(feature = "avro")]
+pub mod avro;
+#[cfg(feature = "clickhouse")]
+pub mod clickhouse;
+#[cfg(feature = "csv")]
+pub mod csv;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+#[cfg(feature = "csv")]
+pub mod patentsview;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+#[cfg(feature = "csv")]
+pub mod star;
+#[cfg(feature = "csv")]
+mod tables;
+
+use serde::Serialize;
+
+use crate::data::*;
+
+/// A row of one of the export tables
+pub trait Row: Serialize {
+    /// table name, also used for the file name
+    const TABLE: &'static str;
+    /// column names, in the order the row serializes
+    const COLUMNS: &'static [&'static str];
+}
+
+/// All the rows a single PatentGrant flattens to
+#[derive(Debug, Default)]
+pub struct Rows {
+    pub grant: GrantRow,
+    pub claims: Vec<ClaimRow>,
+    pub citations: Vec<CitationRow>,
+    pub classifications: Vec<ClassificationRow>,
+    pub parties: Vec<PartyRow>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct GrantRow {
+    pub doc_number: String,
+    pub kind: Option<String>,
+    pub country: String,
+    pub date: String,
+    pub application_doc_number: String,
+    pub application_date: String,
+    pub us_application_series_code: String,
+    pub invention_title: String,
+    pub number_of_claims: Option<u32>,
+    pub number_of_figures: Option<u32>,
+    pub number_of_drawing_sheets: Option<u32>,
+    pub length_of_grant: Option<String>,
+    pub us_term_extension: Option<String>,
+    pub art_unit: Option<String>,
+}
+
+impl Row for GrantRow {
+    const TABLE: &'static str = "grants";
+    const COLUMNS: &'static [&'static str] = &[
+        "doc_number",
+        "kind",
+        "country",
+        "date",
+        "application_doc_number",
+        "application_date",
+        "us_application_series_code",
+        "invention_title",
+        "number_of_claims",
+        "number_of_figures",
+        "number_of_drawing_sheets",
+        "length_of_grant",
+        "us_term_extension",
+        "art_unit",
+    ];
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ClaimRow {
+    pub doc_number: String,
+    pub num: String,
+    pub id: String,
+    pub claim_type: Option<String>,
+    /// ids of the claims this one refers to, joined by ";"
+    pub claim_refs: String,
+    /// all claim-text segments, in order, joined by " "
+    pub text: String,
+}
+
+impl Row for ClaimRow {
+    const TABLE: &'static str = "claims";
+    const COLUMNS: &'static [&'static str] = &[
+        "doc_number",
+        "num",
+        "id",
+        "claim_type",
+        "claim_refs",
+        "text",
+    ];
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct CitationRow {
+    pub doc_number: String,
+    pub num: String,
+    /// "patent" or "npl"
+    pub citation_type: String,
+    pub category: Option<String>,
+    pub cited_country: Option<String>,
+    pub cited_doc_number: Option<String>,
+    pub cited_kind: Option<String>,
+    pub cited_name: Option<String>,
+    pub cited_date: Option<String>,
+    pub othercit: Option<String>,
+    pub classification_cpc_text: Option<String>,
+    pub classification_national: Option<String>,
+}
+
+impl Row for CitationRow {
+    const TABLE: &'static str = "citations";
+    const COLUMNS: &'static [&'static str] = &[
+        "doc_number",
+        "num",
+        "citation_type",
+        "category",
+        "cited_country",
+        "cited_doc_number",
+        "cited_kind",
+        "cited_name",
+        "cited_date",
+        "othercit",
+        "classification_cpc_text",
+        "classification_national",
+    ];
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ClassificationRow {
+    pub doc_number: String,
+    /// "cpc", "ipcr", "locarno", or "national"
+    pub scheme: String,
+    pub main: bool,
+    /// e.g. "A01B 1/00" for cpc and ipcr
+    pub symbol: String,
+    pub version: Option<String>,
+}
+
+impl Row for ClassificationRow {
+    const TABLE: &'static str = "classifications";
+    const COLUMNS: &'static [&'static str] = &[
+        "doc_number",
+        "scheme",
+        "main",
+        "symbol",
+        "version",
+    ];
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct PartyRow {
+    pub doc_number: String,
+    /// "applicant", "inventor", "agent", "assignee", "primary-examiner", or "assistant-examiner"
+    pub role: String,
+    pub sequence: Option<String>,
+    pub orgname: Option<String>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub city: Option<String>,
+    pub state: Option<String>,
+    pub country: Option<String>,
+}
+
+impl Row for PartyRow {
+    const TABLE: &'static str = "parties";
+    const COLUMNS: &'static [&'static str] = &[
+        "doc_number",
+        "role",
+        "sequence",
+        "orgname",
+        "first_name",
+        "last_name",
+        "city",
+        "state",
+        "country",
+    ];
+}
+
+impl From<&PatentGrant> for Rows {
+    fn from(pg: &PatentGrant) -> Self {
+        let dg = &pg.us_bibliographic_data_grant;
+        let doc_number = &dg.publication_reference.doc_number;
+
+        let grant = GrantRow {
+            doc_number: doc_number.clone(),
+            kind: dg.publication_reference.kind.as_ref().map(KindCode::to_string),
+            country: dg.publication_reference.country.to_string(),
+            date: date_text(&dg.publication_reference.date),
+            application_doc_number: dg.application_reference.doc_number.clone(),
+            application_date: date_text(&dg.application_reference.date),
+            us_application_series_code: dg.us_application_series_code.clone(),
+            invention_title: dg.invention_title.title.clone(),
+            number_of_claims: dg.number_of_claims,
+            number_of_figures: dg.figures.number_of_figures,
+            number_of_drawing_sheets: dg.figures.number_of_drawing_sheets,
+            length_of_grant: dg.us_term_of_grant.length_of_grant.clone(),
+            us_term_extension: dg.us_term_of_grant.us_term_extension.clone(),
+            art_unit: dg.examiners.art_unit().map(|s| s.to_owned()),
+        };
+
+        let claims = pg.claims.iter()
+            .map(|claim| {
+                let mut texts = Vec::new();
+                let mut refs = Vec::new();
+                flatten_claim_texts(&claim.claim_texts, &mut texts, &mut refs);
+
+                ClaimRow {
+                    doc_number: doc_number.clone(),
+                    num: claim.num.clone(),
+                    id: claim.id.clone(),
+                    claim_type: claim.claim_type.clone(),
+                    claim_refs: refs.join(";"),
+                    text: texts.join(" "),
+                }
+            })
+            .collect();
+
+        let citations = dg.us_references_cited.iter()
+            .map(|us_citation| {
+                let mut row = CitationRow {
+                    doc_number: doc_number.clone(),
+                    category: us_citation.category.clone(),
+                    classification_cpc_text: us_citation.classification_cpc_text.clone(),
+                    classification_national: us_citation.classification_national.as_ref()
+                        .map(|class_national| class_national.main_classification.to_string()),
+                    ..CitationRow::default()
+                };
+
+                match &us_citation.citation {
+                    Citation::Patent(patcit) => {
+                        let doc_id = &patcit.document_id;
+                        row.num = patcit.num.clone();
+                        row.citation_type = "patent".to_owned();
+                        row.cited_country = Some(doc_id.country.to_string());
+                        row.cited_doc_number = Some(doc_id.doc_number.clone());
+                        row.cited_kind = doc_id.kind.as_ref().map(KindCode::to_string);
+                        row.cited_name = doc_id.name.clone();
+                        row.cited_date = Some(date_text(&doc_id.date));
+                    },
+                    Citation::NonPatent(nplcit) => {
+                        row.num = nplcit.num.clone();
+                        row.citation_type = "npl".to_owned();
+                        row.othercit = Some(nplcit.othercit.clone());
+                    },
+                }
+
+                row
+            })
+            .collect();
+
+        Rows {
+            grant,
+            claims,
+            citations,
+            classifications: classification_rows(doc_number, dg),
+            parties: party_rows(doc_number, dg),
+        }
+    }
+}
+
+/// Depth-first, so segments keep the order they have in the claim
+fn flatten_claim_texts(claim_texts: &[ClaimText], texts: &mut Vec<String>, refs: &mut Vec<String>) {
+    for claim_text in claim_texts {
+        texts.push(claim_text.text.clone());
+        refs.extend(claim_text.claim_refs.iter().map(|claim_ref| claim_ref.idref.clone()));
+        flatten_claim_texts(&claim_text.claim_texts, texts, refs);
+    }
+}
+
+fn classification_rows(doc_number: &str, dg: &BibliographicDataGrant) -> Vec<ClassificationRow> {
+    let mut rows = Vec::new();
+    let mut push = |scheme: &str, main: bool, symbol: String, version: Option<String>| {
+        if !symbol.is_empty() {
+            rows.push(ClassificationRow {
+                doc_number: doc_number.to_owned(),
+                scheme: scheme.to_owned(),
+                main,
+                symbol,
+                version,
+            });
+        }
+    };
+
+    let cpcs = &dg.classifications_cpc;
+    if !cpcs.main_cpc.section.is_empty() {
+        push("cpc", true, cpcs.main_cpc.symbol(), Some(cpcs.main_cpc.cpc_version_indicator.clone()));
+    }
+    for cpc in &cpcs.further_cpc {
+        push("cpc", false, cpc.symbol(), Some(cpc.cpc_version_indicator.clone()));
+    }
+
+    for ipcr in &dg.classifications_ipcr {
+        let symbol = format!(
+            "{}{}{} {}/{}",
+            ipcr.section,
+            ipcr.class,
+            ipcr.subclass,
+            ipcr.main_group.as_deref().unwrap_or(""),
+            ipcr.subgroup.as_deref().unwrap_or(""),
+        );
+        // F is the first symbol listed, L any later one
+        let main = ipcr.symbol_position.as_deref() == Some("F");
+        push("ipcr", main, symbol, Some(ipcr.ipc_version_indicator.clone()));
+    }
+
+    let locarno = &dg.classification_locarno;
+    push("locarno", true, locarno.main_classification.clone(), Some(locarno.edition.clone()));
+
+    let national = &dg.classification_national;
+    push("national", true, national.main_classification.to_string(), None);
+    if let Some(further) = &national.further_classification {
+        push("national", false, further.to_string(), None);
+    }
+
+    rows
+}
+
+fn party_rows(doc_number: &str, dg: &BibliographicDataGrant) -> Vec<PartyRow> {
+    let addressbook_row = |role: &str, sequence: Option<&String>, addressbook: &AddressBook| {
+        PartyRow {
+            doc_number: doc_number.to_owned(),
+            role: role.to_owned(),
+            sequence: sequence.cloned(),
+            orgname: addressbook.orgname.clone(),
+            first_name: addressbook.first_name.clone(),
+            last_name: addressbook.last_name.clone(),
+            city: addressbook.address.city.clone(),
+            state: addressbook.address.state.clone(),
+            country: addressbook.address.country.as_ref().map(CountryCode::to_string),
+        }
+    };
+    let examiner_row = |role: &str, examiner: &Examiner| {
+        PartyRow {
+            doc_number: doc_number.to_owned(),
+            role: role.to_owned(),
+            first_name: Some(examiner.first_name.clone()),
+            last_name: Some(examiner.last_name.clone()),
+            ..PartyRow::default()
+        }
+    };
+
+    let mut rows = Vec::new();
+
+    for applicant in &dg.us_applicants {
+        rows.push(addressbook_row("applicant", Some(&applicant.sequence), &applicant.addressbook));
+    }
+    for inventor in &dg.inventors {
+        rows.push(addressbook_row("inventor", Some(&inventor.sequence), &inventor.addressbook));
+    }
+    for agent in &dg.agents {
+        rows.push(addressbook_row("agent", Some(&agent.sequence), &agent.addressbook));
+    }
+    for assignee in &dg.assignees {
+        let mut row = addressbook_row("assignee", None, &assignee.addressbook);
+        // assignee orgname can be outside of the addressbook
+        if row.orgname.is_none() {
+            row.orgname = assignee.orgname.clone();
+        }
+        rows.push(row);
+    }
+
+    let examiners = &dg.examiners;
+    if !examiners.primary_examiner.last_name.is_empty() {
+        rows.push(examiner_row("primary-examiner", &examiners.primary_examiner));
+    }
+    if let Some(assistant) = &examiners.assistant_examiner {
+        rows.push(examiner_row("assistant-examiner", assistant));
+    }
+
+    rows
+}
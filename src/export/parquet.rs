@@ -0,0 +1,412 @@
+//! Converts the export tables to arrow RecordBatches, in batches of grants
+//! as they're parsed (see `ArrowBatcher`) or all at once, and writes them
+//! as one parquet file per table into a directory.
+
+use arrow_array::RecordBatch;
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use ::parquet::arrow::ArrowWriter;
+use ::parquet::basic::Compression;
+use ::parquet::file::properties::WriterProperties;
+use snafu::ResultExt;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::data::PatentGrant;
+use crate::error::{Error, Arrow, ExportIo, WriteParquet};
+use super::*;
+
+/// rows buffered per table before they're written out as a row group
+pub const DEFAULT_BATCH_SIZE: usize = 65_536;
+
+/// Arrow types of a Row's columns.
+pub trait ArrowRow: Row {
+    /// columns that aren't strings; all others are Utf8
+    const TYPED_COLUMNS: &'static [(&'static str, DataType)] = &[];
+
+    /// all columns are nullable except the doc_number key
+    fn schema() -> SchemaRef {
+        let fields: Vec<Field> = Self::COLUMNS.iter()
+            .map(|column| {
+                let data_type = Self::TYPED_COLUMNS.iter()
+                    .find(|(name, _)| name == column)
+                    .map(|(_, data_type)| data_type.clone())
+                    .unwrap_or(DataType::Utf8);
+
+                Field::new(*column, data_type, *column != "doc_number")
+            })
+            .collect();
+
+        Arc::new(Schema::new(fields))
+    }
+}
+
+impl ArrowRow for GrantRow {
+    const TYPED_COLUMNS: &'static [(&'static str, DataType)] = &[
+        ("number_of_claims", DataType::UInt32),
+        ("number_of_figures", DataType::UInt32),
+        ("number_of_drawing_sheets", DataType::UInt32),
+    ];
+}
+
+impl ArrowRow for ClaimRow {}
+
+impl ArrowRow for CitationRow {}
+
+impl ArrowRow for ClassificationRow {
+    const TYPED_COLUMNS: &'static [(&'static str, DataType)] = &[
+        ("main", DataType::Boolean),
+    ];
+}
+
+impl ArrowRow for PartyRow {}
+
+/// Converts rows of one table to a RecordBatch
+pub fn record_batch<R: ArrowRow>(rows: &[R]) -> Result<RecordBatch, Error> {
+    let schema = R::schema();
+
+    let mut decoder = arrow_json::ReaderBuilder::new(schema.clone())
+        .with_batch_size(rows.len().max(1))
+        .build_decoder()
+        .context(Arrow)?;
+
+    decoder.serialize(rows)
+        .context(Arrow)?;
+
+    let batch = decoder.flush()
+        .context(Arrow)?;
+
+    Ok(batch.unwrap_or_else(|| RecordBatch::new_empty(schema)))
+}
+
+/// The tables for a batch of PatentGrant, as RecordBatches
+pub struct RecordBatches {
+    pub grants: RecordBatch,
+    pub claims: RecordBatch,
+    pub citations: RecordBatch,
+    pub classifications: RecordBatch,
+    pub parties: RecordBatch,
+}
+
+pub fn record_batches(patent_grants: &[PatentGrant]) -> Result<RecordBatches, Error> {
+    let mut rows = TableRows::default();
+    for patent_grant in patent_grants {
+        rows.push(patent_grant);
+    }
+
+    rows.record_batches()
+}
+
+/// rows of each table, for a number of grants
+#[derive(Default)]
+struct TableRows {
+    grants: Vec<GrantRow>,
+    claims: Vec<ClaimRow>,
+    citations: Vec<CitationRow>,
+    classifications: Vec<ClassificationRow>,
+    parties: Vec<PartyRow>,
+}
+
+impl TableRows {
+    fn push(&mut self, patent_grant: &PatentGrant) {
+        let rows = Rows::from(patent_grant);
+        self.grants.push(rows.grant);
+        self.claims.extend(rows.claims);
+        self.citations.extend(rows.citations);
+        self.classifications.extend(rows.classifications);
+        self.parties.extend(rows.parties);
+    }
+
+    fn record_batches(&self) -> Result<RecordBatches, Error> {
+        Ok(RecordBatches {
+            grants: record_batch(&self.grants)?,
+            claims: record_batch(&self.claims)?,
+            citations: record_batch(&self.citations)?,
+            classifications: record_batch(&self.classifications)?,
+            parties: record_batch(&self.parties)?,
+        })
+    }
+}
+
+/// Accumulates grants into RecordBatches of the tables, a batch for every
+/// batch_size grants, for using them in memory (e.g. in polars or
+/// datafusion) rather than writing them out.
+pub struct ArrowBatcher {
+    batch_size: usize,
+    rows: TableRows,
+}
+
+impl ArrowBatcher {
+    pub fn new(batch_size: usize) -> Self {
+        ArrowBatcher {
+            batch_size: batch_size.max(1),
+            rows: TableRows::default(),
+        }
+    }
+
+    /// The batches, if the grant fills them
+    pub fn push(&mut self, patent_grant: &PatentGrant) -> Result<Option<RecordBatches>, Error> {
+        self.rows.push(patent_grant);
+
+        if self.rows.grants.len() >= self.batch_size {
+            return self.flush();
+        }
+
+        Ok(None)
+    }
+
+    /// The batches of the grants pushed since the last, if any
+    pub fn flush(&mut self) -> Result<Option<RecordBatches>, Error> {
+        if self.rows.grants.is_empty() {
+            return Ok(None);
+        }
+
+        let batches = self.rows.record_batches()?;
+        self.rows = TableRows::default();
+
+        Ok(Some(batches))
+    }
+
+    /// Batches grants as they're parsed; an error parsing a grant is passed
+    /// on, and batching goes on with the next
+    pub fn batches<I: Iterator<Item = Result<PatentGrant, Error>>>(self, patent_grants: I) -> ArrowBatches<I> {
+        ArrowBatches {
+            batcher: self,
+            patent_grants,
+            done: false,
+        }
+    }
+}
+
+/// Iterator over the batches of grants, see `ArrowBatcher::batches`
+pub struct ArrowBatches<I> {
+    batcher: ArrowBatcher,
+    patent_grants: I,
+    done: bool,
+}
+
+impl<I: Iterator<Item = Result<PatentGrant, Error>>> Iterator for ArrowBatches<I> {
+    type Item = Result<RecordBatches, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            match self.patent_grants.next() {
+                Some(Ok(patent_grant)) => {
+                    if let Some(batches) = self.batcher.push(&patent_grant).transpose() {
+                        return Some(batches);
+                    }
+                },
+                Some(Err(err)) => return Some(Err(err)),
+                None => self.done = true,
+            }
+        }
+
+        self.batcher.flush().transpose()
+    }
+}
+
+/// Writes each table as a parquet file: to files in a directory, or to
+/// writers (e.g. `store::ObjectWriter`s) given by `from_writers`.
+pub struct ParquetExporter<W: Write + Send = File> {
+    grants: TableWriter<GrantRow, W>,
+    claims: TableWriter<ClaimRow, W>,
+    citations: TableWriter<CitationRow, W>,
+    classifications: TableWriter<ClassificationRow, W>,
+    parties: TableWriter<PartyRow, W>,
+}
+
+impl ParquetExporter {
+    /// Creates out_dir if needed, and a `<table>.parquet` in it for each table;
+    /// an existing file is truncated.
+    pub fn create(out_dir: &Path) -> Result<Self, Error> {
+        fs::create_dir_all(out_dir)
+            .context(ExportIo)?;
+
+        Self::from_writers(|table| {
+            File::create(out_dir.join(format!("{}.parquet", table)))
+                .context(ExportIo)
+        })
+    }
+
+    /// Writes out the remaining rows and the parquet footers
+    pub fn finish(self) -> Result<(), Error> {
+        self.into_writers()?;
+        Ok(())
+    }
+}
+
+impl<W: Write + Send> ParquetExporter<W> {
+    /// Writes each table to the writer create returns for its name
+    pub fn from_writers<F: FnMut(&str) -> Result<W, Error>>(mut create: F) -> Result<Self, Error> {
+        Ok(ParquetExporter {
+            grants: TableWriter::new(create(GrantRow::TABLE)?)?,
+            claims: TableWriter::new(create(ClaimRow::TABLE)?)?,
+            citations: TableWriter::new(create(CitationRow::TABLE)?)?,
+            classifications: TableWriter::new(create(ClassificationRow::TABLE)?)?,
+            parties: TableWriter::new(create(PartyRow::TABLE)?)?,
+        })
+    }
+
+    /// Number of rows buffered per table before being written; each write is
+    /// a row group.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.grants.batch_size = batch_size;
+        self.claims.batch_size = batch_size;
+        self.citations.batch_size = batch_size;
+        self.classifications.batch_size = batch_size;
+        self.parties.batch_size = batch_size;
+        self
+    }
+
+    pub fn write(&mut self, patent_grant: &PatentGrant) -> Result<(), Error> {
+        let rows = Rows::from(patent_grant);
+
+        self.grants.push(vec![rows.grant])?;
+        self.claims.push(rows.claims)?;
+        self.citations.push(rows.citations)?;
+        self.classifications.push(rows.classifications)?;
+        self.parties.push(rows.parties)?;
+
+        Ok(())
+    }
+
+    /// Writes out the remaining rows and the parquet footers, and returns
+    /// the writers, in table order (grants, claims, citations,
+    /// classifications, parties)
+    pub fn into_writers(self) -> Result<Vec<W>, Error> {
+        Ok(vec![
+            self.grants.finish()?,
+            self.claims.finish()?,
+            self.citations.finish()?,
+            self.classifications.finish()?,
+            self.parties.finish()?,
+        ])
+    }
+}
+
+struct TableWriter<R, W: Write + Send> {
+    rows: Vec<R>,
+    batch_size: usize,
+    writer: ArrowWriter<W>,
+}
+
+impl<R: ArrowRow, W: Write + Send> TableWriter<R, W> {
+    fn new(wtr: W) -> Result<Self, Error> {
+        let props = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+
+        let writer = ArrowWriter::try_new(wtr, R::schema(), Some(props))
+            .context(WriteParquet)?;
+
+        Ok(TableWriter {
+            rows: Vec::new(),
+            batch_size: DEFAULT_BATCH_SIZE,
+            writer,
+        })
+    }
+
+    fn push(&mut self, rows: Vec<R>) -> Result<(), Error> {
+        self.rows.extend(rows);
+
+        if self.rows.len() >= self.batch_size {
+            self.flush()?;
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        if !self.rows.is_empty() {
+            let batch = record_batch(&self.rows)?;
+            self.writer.write(&batch)
+                .context(WriteParquet)?;
+            // otherwise the writer buffers batches into a larger row group
+            self.writer.flush()
+                .context(WriteParquet)?;
+            self.rows.clear();
+        }
+
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<W, Error> {
+        self.flush()?;
+        self.writer.into_inner()
+            .context(WriteParquet)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PatentGrants;
+    use ::parquet::file::reader::{FileReader, SerializedFileReader};
+
+    const FIXTURE: &str = include_str!("../../tests/fixtures/grants.xml");
+
+    fn fixture_grants() -> Vec<PatentGrant> {
+        PatentGrants::from_reader(FIXTURE.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .expect("fixture should parse")
+    }
+
+    #[test]
+    fn test_record_batches() {
+        let grants = fixture_grants();
+        let batches = record_batches(&grants).unwrap();
+
+        assert_eq!(batches.grants.num_rows(), 3);
+        assert_eq!(batches.grants.num_columns(), GrantRow::COLUMNS.len());
+        assert_eq!(batches.grants.schema().field_with_name("number_of_claims").unwrap().data_type(), &DataType::UInt32);
+        assert_eq!(batches.claims.num_rows(), 5);
+        let citations: usize = grants.iter().map(|pg| pg.us_bibliographic_data_grant.us_references_cited.len()).sum();
+        assert_eq!(batches.citations.num_rows(), citations);
+
+        // empty tables still have their schema
+        let empty = record_batch::<PartyRow>(&[]).unwrap();
+        assert_eq!(empty.num_rows(), 0);
+        assert_eq!(empty.num_columns(), PartyRow::COLUMNS.len());
+    }
+
+    #[test]
+    fn test_arrow_batcher() {
+        let grants = PatentGrants::from_reader(FIXTURE.as_bytes());
+        let batches = ArrowBatcher::new(2)
+            .batches(grants)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let sizes: Vec<_> = batches.iter().map(|batches| batches.grants.num_rows()).collect();
+        assert_eq!(sizes, vec![2, 1]);
+        let claims: usize = batches.iter().map(|batches| batches.claims.num_rows()).sum();
+        assert_eq!(claims, 5);
+
+        let mut batcher = ArrowBatcher::new(10);
+        assert!(batcher.push(&fixture_grants()[0]).unwrap().is_none());
+        assert_eq!(batcher.flush().unwrap().unwrap().grants.num_rows(), 1);
+        assert!(batcher.flush().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_parquet_export() {
+        let out_dir = std::env::temp_dir().join(format!("uspto-parquet-export-{}", std::process::id()));
+
+        // small batches, so tables span several row groups
+        let mut exporter = ParquetExporter::create(&out_dir).unwrap().batch_size(2);
+        for patent_grant in fixture_grants() {
+            exporter.write(&patent_grant).unwrap();
+        }
+        exporter.finish().unwrap();
+
+        let reader = SerializedFileReader::new(File::open(out_dir.join("grants.parquet")).unwrap()).unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 3);
+        assert_eq!(reader.metadata().num_row_groups(), 2);
+
+        let reader = SerializedFileReader::new(File::open(out_dir.join("claims.parquet")).unwrap()).unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 5);
+
+        fs::remove_dir_all(&out_dir).unwrap();
+    }
+}
@@ -0,0 +1,442 @@
+//! Writes tables in the layout of PatentsView's bulk download files, so
+//! freshly parsed weeks can stand in for (or be appended to) PatentsView's
+//! before it catches up with them: `patent.tsv`, `inventor.tsv`,
+//! `assignee.tsv`, `cpc_current.tsv`, and `uspatentcitation.tsv`.
+//!
+//! Files are tab separated with a header, quoted (as csv) only where a value
+//! needs it. Patent ids are PatentsView's (see `docnum::patentsview_patent_id`),
+//! dates are YYYY-MM-DD, and sequences count from 0.
+//!
+//! PatentsView's inventor and assignee ids come from its disambiguation, so
+//! `inventor.tsv` and `assignee.tsv` have the columns of its raw tables
+//! (`rawinventor`, `rawassignee`), linked to patents by patent_id, with the
+//! disambiguated and location ids left empty. Row ids (`uuid`) are the
+//! patent id and the sequence rather than random, so exporting a week again
+//! gives the same ids.
+
+use serde::Serialize;
+use snafu::ResultExt;
+use std::fs::File;
+use std::path::Path;
+
+use crate::data::*;
+use crate::docnum::patentsview_patent_id;
+use crate::error::{Error, WriteCsv};
+use super::tables::{self, write_rows, Delimited, TableDir};
+use super::Row;
+
+#[derive(Debug, Default, Serialize)]
+pub struct PatentRow {
+    pub id: String,
+    /// "utility", "design", "plant", "reissue", or
+    /// "statutory invention registration"
+    #[serde(rename = "type")]
+    pub patent_type: String,
+    pub number: String,
+    pub country: String,
+    pub date: String,
+    /// the abstract's paragraphs, joined by " "
+    #[serde(rename = "abstract")]
+    pub patent_abstract: String,
+    pub title: String,
+    pub kind: Option<String>,
+    pub num_claims: Option<u32>,
+    /// the bulk file's name, see `PatentsViewExporter::filename`
+    pub filename: String,
+    pub withdrawn: u8,
+}
+
+impl Row for PatentRow {
+    const TABLE: &'static str = "patent";
+    const COLUMNS: &'static [&'static str] = &[
+        "id",
+        "type",
+        "number",
+        "country",
+        "date",
+        "abstract",
+        "title",
+        "kind",
+        "num_claims",
+        "filename",
+        "withdrawn",
+    ];
+}
+
+/// As PatentsView's `rawinventor`
+#[derive(Debug, Default, Serialize)]
+pub struct InventorRow {
+    pub uuid: String,
+    pub patent_id: String,
+    pub inventor_id: Option<String>,
+    pub rawlocation_id: Option<String>,
+    pub name_first: Option<String>,
+    pub name_last: Option<String>,
+    pub sequence: usize,
+    pub rule_47: Option<String>,
+    pub deceased: Option<String>,
+}
+
+impl Row for InventorRow {
+    const TABLE: &'static str = "inventor";
+    const COLUMNS: &'static [&'static str] = &[
+        "uuid",
+        "patent_id",
+        "inventor_id",
+        "rawlocation_id",
+        "name_first",
+        "name_last",
+        "sequence",
+        "rule_47",
+        "deceased",
+    ];
+}
+
+/// As PatentsView's `rawassignee`
+#[derive(Debug, Default, Serialize)]
+pub struct AssigneeRow {
+    pub uuid: String,
+    pub patent_id: String,
+    pub assignee_id: Option<String>,
+    pub rawlocation_id: Option<String>,
+    /// the assignee's role code, without the leading zero, e.g. "2" for a
+    /// US company
+    #[serde(rename = "type")]
+    pub assignee_type: Option<String>,
+    pub name_first: Option<String>,
+    pub name_last: Option<String>,
+    pub organization: Option<String>,
+    pub sequence: usize,
+}
+
+impl Row for AssigneeRow {
+    const TABLE: &'static str = "assignee";
+    const COLUMNS: &'static [&'static str] = &[
+        "uuid",
+        "patent_id",
+        "assignee_id",
+        "rawlocation_id",
+        "type",
+        "name_first",
+        "name_last",
+        "organization",
+        "sequence",
+    ];
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct CpcCurrentRow {
+    pub uuid: String,
+    pub patent_id: String,
+    /// e.g. "A"
+    pub section_id: String,
+    /// e.g. "A01"
+    pub subsection_id: String,
+    /// e.g. "A01B"
+    pub group_id: String,
+    /// e.g. "A01B1/00"
+    pub subgroup_id: String,
+    /// "inventional" or "additional"
+    pub category: String,
+    /// 0 for the main cpc
+    pub sequence: usize,
+}
+
+impl Row for CpcCurrentRow {
+    const TABLE: &'static str = "cpc_current";
+    const COLUMNS: &'static [&'static str] = &[
+        "uuid",
+        "patent_id",
+        "section_id",
+        "subsection_id",
+        "group_id",
+        "subgroup_id",
+        "category",
+        "sequence",
+    ];
+}
+
+/// A US patent cited. Other citations (foreign patents, and non-patent
+/// literature) are in other PatentsView tables, not written here.
+#[derive(Debug, Default, Serialize)]
+pub struct UsPatentCitationRow {
+    pub uuid: String,
+    pub patent_id: String,
+    /// the cited patent's id
+    pub citation_id: String,
+    pub date: String,
+    pub name: Option<String>,
+    pub kind: Option<String>,
+    pub country: String,
+    pub category: Option<String>,
+    /// position among all the references cited
+    pub sequence: usize,
+}
+
+impl Row for UsPatentCitationRow {
+    const TABLE: &'static str = "uspatentcitation";
+    const COLUMNS: &'static [&'static str] = &[
+        "uuid",
+        "patent_id",
+        "citation_id",
+        "date",
+        "name",
+        "kind",
+        "country",
+        "category",
+        "sequence",
+    ];
+}
+
+/// All the rows a single PatentGrant gives in the PatentsView tables
+#[derive(Debug, Default)]
+pub struct PatentsViewRows {
+    pub patent: PatentRow,
+    pub inventors: Vec<InventorRow>,
+    pub assignees: Vec<AssigneeRow>,
+    pub cpc_current: Vec<CpcCurrentRow>,
+    pub us_patent_citations: Vec<UsPatentCitationRow>,
+}
+
+impl PatentsViewRows {
+    /// filename is the bulk file the grant is from, e.g. "ipg190101.xml"
+    pub fn new(pg: &PatentGrant, filename: &str) -> Self {
+        let dg = &pg.us_bibliographic_data_grant;
+        let patent_id = patentsview_patent_id(&dg.publication_reference.doc_number);
+        let uuid = |sequence: usize| format!("{}-{}", patent_id, sequence);
+
+        let patent = PatentRow {
+            id: patent_id.clone(),
+            patent_type: pg.doc_type().map(patent_type).unwrap_or("").to_owned(),
+            number: patent_id.clone(),
+            country: dg.publication_reference.country.to_string(),
+            date: dashed_date(&date_text(&dg.publication_reference.date)),
+            patent_abstract: pg.r#abstract.iter()
+                .map(|paragraph| paragraph.text.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+            title: dg.invention_title.title.clone(),
+            kind: dg.publication_reference.kind.as_ref().map(KindCode::to_string),
+            num_claims: dg.number_of_claims,
+            filename: filename.to_owned(),
+            withdrawn: 0,
+        };
+
+        let inventors = dg.inventors.iter()
+            .enumerate()
+            .map(|(sequence, inventor)| InventorRow {
+                uuid: uuid(sequence),
+                patent_id: patent_id.clone(),
+                name_first: inventor.addressbook.first_name.clone(),
+                name_last: inventor.addressbook.last_name.clone(),
+                sequence,
+                ..InventorRow::default()
+            })
+            .collect();
+
+        let assignees = dg.assignees.iter()
+            .enumerate()
+            .map(|(sequence, assignee)| {
+                let addressbook = &assignee.addressbook;
+                let role = addressbook.role.as_ref().or(assignee.role.as_ref());
+
+                AssigneeRow {
+                    uuid: uuid(sequence),
+                    patent_id: patent_id.clone(),
+                    assignee_type: role.map(|role| role.trim_start_matches('0').to_owned()),
+                    name_first: addressbook.first_name.clone(),
+                    name_last: addressbook.last_name.clone(),
+                    // assignee orgname can be outside of the addressbook
+                    organization: addressbook.orgname.clone().or_else(|| assignee.orgname.clone()),
+                    sequence,
+                    ..AssigneeRow::default()
+                }
+            })
+            .collect();
+
+        let cpcs = &dg.classifications_cpc;
+        let cpc_current = std::iter::once(&cpcs.main_cpc)
+            .chain(&cpcs.further_cpc)
+            .filter(|cpc| !cpc.section.is_empty())
+            .enumerate()
+            .map(|(sequence, cpc)| CpcCurrentRow {
+                uuid: uuid(sequence),
+                patent_id: patent_id.clone(),
+                section_id: cpc.section.clone(),
+                subsection_id: format!("{}{}", cpc.section, cpc.class),
+                group_id: format!("{}{}{}", cpc.section, cpc.class, cpc.subclass),
+                subgroup_id: format!("{}{}{}{}/{}", cpc.section, cpc.class, cpc.subclass, cpc.main_group, cpc.subgroup),
+                category: match cpc.classification_value.as_str() {
+                    "I" => "inventional",
+                    _ => "additional",
+                }.to_owned(),
+                sequence,
+            })
+            .collect();
+
+        let us_patent_citations = dg.us_references_cited.iter()
+            .enumerate()
+            .filter_map(|(sequence, us_citation)| match &us_citation.citation {
+                Citation::Patent(patcit) if patcit.document_id.country.to_string() == "US" => {
+                    let doc_id = &patcit.document_id;
+
+                    Some(UsPatentCitationRow {
+                        uuid: uuid(sequence),
+                        patent_id: patent_id.clone(),
+                        citation_id: patentsview_patent_id(&doc_id.doc_number),
+                        date: dashed_date(&date_text(&doc_id.date)),
+                        name: doc_id.name.clone(),
+                        kind: doc_id.kind.as_ref().map(KindCode::to_string),
+                        country: doc_id.country.to_string(),
+                        category: us_citation.category.clone(),
+                        sequence,
+                    })
+                },
+                _ => None,
+            })
+            .collect();
+
+        PatentsViewRows {
+            patent,
+            inventors,
+            assignees,
+            cpc_current,
+            us_patent_citations,
+        }
+    }
+}
+
+fn patent_type(doc_type: DocType) -> &'static str {
+    match doc_type {
+        DocType::Utility => "utility",
+        DocType::Design => "design",
+        DocType::Plant => "plant",
+        DocType::Reissue => "reissue",
+        DocType::Sir => "statutory invention registration",
+    }
+}
+
+/// YYYYMMDD as YYYY-MM-DD; anything else (e.g. a missing date) as is
+fn dashed_date(date: &str) -> String {
+    if date.len() == 8 && date.bytes().all(|b| b.is_ascii_digit()) {
+        format!("{}-{}-{}", &date[..4], &date[4..6], &date[6..])
+    } else {
+        date.to_owned()
+    }
+}
+
+pub struct PatentsViewExporter {
+    filename: String,
+    patents: ::csv::Writer<File>,
+    inventors: ::csv::Writer<File>,
+    assignees: ::csv::Writer<File>,
+    cpc_current: ::csv::Writer<File>,
+    us_patent_citations: ::csv::Writer<File>,
+}
+
+impl PatentsViewExporter {
+    /// A `<table>.tsv` in out_dir for each table, see `export::tables`
+    pub fn create(out_dir: &Path) -> Result<Self, Error> {
+        let dir = TableDir::create(out_dir, Delimited::Tsv)?;
+
+        Ok(PatentsViewExporter {
+            filename: String::new(),
+            patents: dir.writer::<PatentRow>()?,
+            inventors: dir.writer::<InventorRow>()?,
+            assignees: dir.writer::<AssigneeRow>()?,
+            cpc_current: dir.writer::<CpcCurrentRow>()?,
+            us_patent_citations: dir.writer::<UsPatentCitationRow>()?,
+        })
+    }
+
+    /// The bulk file the grants written next are from, for patent.filename,
+    /// e.g. "ipg190101.xml"; empty if not given
+    pub fn filename(mut self, filename: &str) -> Self {
+        self.filename = filename.to_owned();
+        self
+    }
+
+    pub fn write(&mut self, patent_grant: &PatentGrant) -> Result<(), Error> {
+        let rows = PatentsViewRows::new(patent_grant, &self.filename);
+
+        self.patents.serialize(&rows.patent).context(WriteCsv)?;
+        write_rows(&mut self.inventors, &rows.inventors)?;
+        write_rows(&mut self.assignees, &rows.assignees)?;
+        write_rows(&mut self.cpc_current, &rows.cpc_current)?;
+        write_rows(&mut self.us_patent_citations, &rows.us_patent_citations)?;
+
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<(), Error> {
+        tables::flush(&mut [
+            &mut self.patents,
+            &mut self.inventors,
+            &mut self.assignees,
+            &mut self.cpc_current,
+            &mut self.us_patent_citations,
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::PatentGrants;
+
+    const FIXTURE: &str = include_str!("../../tests/fixtures/grants.xml");
+
+    fn serialized_header<R: Row + Default>() -> Vec<String> {
+        let mut wtr = ::csv::Writer::from_writer(vec![]);
+        wtr.serialize(R::default()).unwrap();
+        let data = String::from_utf8(wtr.into_inner().unwrap()).unwrap();
+
+        data.lines().next().unwrap().split(',').map(|s| s.to_owned()).collect()
+    }
+
+    #[test]
+    fn test_columns_match_rows() {
+        assert_eq!(serialized_header::<PatentRow>(), PatentRow::COLUMNS);
+        assert_eq!(serialized_header::<InventorRow>(), InventorRow::COLUMNS);
+        assert_eq!(serialized_header::<AssigneeRow>(), AssigneeRow::COLUMNS);
+        assert_eq!(serialized_header::<CpcCurrentRow>(), CpcCurrentRow::COLUMNS);
+        assert_eq!(serialized_header::<UsPatentCitationRow>(), UsPatentCitationRow::COLUMNS);
+    }
+
+    #[test]
+    fn test_patentsview_export() {
+        let out_dir = std::env::temp_dir().join(format!("uspto-patentsview-export-{}", std::process::id()));
+
+        let mut exporter = PatentsViewExporter::create(&out_dir).unwrap()
+            .filename("ipg190101.xml");
+        for patent_grant in PatentGrants::from_reader(FIXTURE.as_bytes()) {
+            exporter.write(&patent_grant.unwrap()).unwrap();
+        }
+        exporter.finish().unwrap();
+
+        let patents = fs::read_to_string(out_dir.join("patent.tsv")).unwrap();
+        assert_eq!(patents.lines().count(), 4);
+        assert_eq!(patents.lines().next().unwrap(), PatentRow::COLUMNS.join("\t"));
+        assert!(patents.lines().nth(1).unwrap().starts_with("10165721\tutility\t10165721\tUS\t2019-01-01\t"));
+        assert!(patents.lines().nth(1).unwrap().ends_with("\tB2\t3\tipg190101.xml\t0"));
+        assert!(patents.lines().nth(2).unwrap().starts_with("D838001\tdesign\tD838001\t"));
+
+        let inventors = fs::read_to_string(out_dir.join("inventor.tsv")).unwrap();
+        assert!(inventors.lines().nth(1).unwrap().starts_with("10165721-0\t10165721\t\t\t"));
+        assert!(inventors.lines().nth(2).unwrap().contains("\t1\t\t"));
+
+        let assignees = fs::read_to_string(out_dir.join("assignee.tsv")).unwrap();
+        assert!(assignees.contains("10165721-0\t10165721\t\t\t3\t\t\tACME TOOL CO., LTD.\t0"));
+
+        let cpc_current = fs::read_to_string(out_dir.join("cpc_current.tsv")).unwrap();
+        assert!(cpc_current.contains("10165721-0\t10165721\tA\tA01\tA01B\tA01B1/00\tinventional\t0"));
+
+        let citations = fs::read_to_string(out_dir.join("uspatentcitation.tsv")).unwrap();
+        // typed dates take the 00 day as the first
+        let cited_date = if cfg!(feature = "typed-dates") { "1983-12-01" } else { "1983-12-00" };
+        assert!(citations.contains(&format!("10165721-0\t10165721\t4418955\t{}\tMuckle\tA\tUS\tcited by examiner\t0", cited_date)));
+
+        fs::remove_dir_all(&out_dir).unwrap();
+    }
+}
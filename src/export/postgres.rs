@@ -0,0 +1,365 @@
+//! Loads the export tables into postgres, over a tokio-postgres Client,
+//! with binary COPY.
+//!
+//! The tables are created if they aren't there (see `schema`), with the
+//! others referencing `grants` by doc_number. Loads upsert on doc_number: a
+//! grant already loaded (e.g. from last week's file) is replaced, with all
+//! its rows, so weekly files can be loaded as they come. Each batch of
+//! grants is copied into temporary staging tables and merged into the tables
+//! in one transaction.
+
+use std::pin::pin;
+
+use snafu::ResultExt;
+use tokio_postgres::binary_copy::BinaryCopyInWriter;
+use tokio_postgres::types::{ToSql, Type};
+use tokio_postgres::{Client, Transaction};
+
+use crate::data::PatentGrant;
+use crate::error::{Error, WritePostgres};
+use super::*;
+
+/// grants loaded per transaction
+pub const DEFAULT_BATCH_SIZE: usize = 10_000;
+
+/// Postgres types and values of a Row's columns.
+pub trait PgRow: Row {
+    /// columns that aren't text
+    const TYPED_COLUMNS: &'static [(&'static str, Type)] = &[];
+
+    /// in COLUMNS order, typed as in types
+    fn values(&self) -> Vec<Box<dyn ToSql + Sync + Send + '_>>;
+
+    fn types() -> Vec<Type> {
+        Self::COLUMNS.iter()
+            .map(|column| {
+                Self::TYPED_COLUMNS.iter()
+                    .find(|(name, _)| name == column)
+                    .map(|(_, pg_type)| pg_type.clone())
+                    .unwrap_or(Type::TEXT)
+            })
+            .collect()
+    }
+
+    /// doc_number is the key of grants, and references it in the other
+    /// tables
+    fn create_table() -> String {
+        let columns: Vec<String> = Self::COLUMNS.iter()
+            .zip(Self::types())
+            .map(|(column, pg_type)| match *column {
+                "doc_number" if Self::TABLE == GrantRow::TABLE => "doc_number text PRIMARY KEY".to_string(),
+                "doc_number" => format!("doc_number text NOT NULL REFERENCES {}(doc_number)", GrantRow::TABLE),
+                _ => format!("{} {}", column, pg_type.name()),
+            })
+            .collect();
+
+        let mut sql = format!("CREATE TABLE IF NOT EXISTS {} ({});", Self::TABLE, columns.join(", "));
+        if Self::TABLE != GrantRow::TABLE {
+            sql.push_str(&format!("\nCREATE INDEX IF NOT EXISTS {0}_doc_number ON {0}(doc_number);", Self::TABLE));
+        }
+
+        sql
+    }
+}
+
+impl PgRow for GrantRow {
+    const TYPED_COLUMNS: &'static [(&'static str, Type)] = &[
+        ("number_of_claims", Type::INT4),
+        ("number_of_figures", Type::INT4),
+        ("number_of_drawing_sheets", Type::INT4),
+    ];
+
+    fn values(&self) -> Vec<Box<dyn ToSql + Sync + Send + '_>> {
+        // postgres has no unsigned ints
+        let int = |n: Option<u32>| n.map(|n| n as i32);
+
+        vec![
+            Box::new(&self.doc_number),
+            Box::new(&self.kind),
+            Box::new(&self.country),
+            Box::new(&self.date),
+            Box::new(&self.application_doc_number),
+            Box::new(&self.application_date),
+            Box::new(&self.us_application_series_code),
+            Box::new(&self.invention_title),
+            Box::new(int(self.number_of_claims)),
+            Box::new(int(self.number_of_figures)),
+            Box::new(int(self.number_of_drawing_sheets)),
+            Box::new(&self.length_of_grant),
+            Box::new(&self.us_term_extension),
+            Box::new(&self.art_unit),
+        ]
+    }
+}
+
+impl PgRow for ClaimRow {
+    fn values(&self) -> Vec<Box<dyn ToSql + Sync + Send + '_>> {
+        vec![
+            Box::new(&self.doc_number),
+            Box::new(&self.num),
+            Box::new(&self.id),
+            Box::new(&self.claim_type),
+            Box::new(&self.claim_refs),
+            Box::new(&self.text),
+        ]
+    }
+}
+
+impl PgRow for CitationRow {
+    fn values(&self) -> Vec<Box<dyn ToSql + Sync + Send + '_>> {
+        vec![
+            Box::new(&self.doc_number),
+            Box::new(&self.num),
+            Box::new(&self.citation_type),
+            Box::new(&self.category),
+            Box::new(&self.cited_country),
+            Box::new(&self.cited_doc_number),
+            Box::new(&self.cited_kind),
+            Box::new(&self.cited_name),
+            Box::new(&self.cited_date),
+            Box::new(&self.othercit),
+            Box::new(&self.classification_cpc_text),
+            Box::new(&self.classification_national),
+        ]
+    }
+}
+
+impl PgRow for ClassificationRow {
+    const TYPED_COLUMNS: &'static [(&'static str, Type)] = &[
+        ("main", Type::BOOL),
+    ];
+
+    fn values(&self) -> Vec<Box<dyn ToSql + Sync + Send + '_>> {
+        vec![
+            Box::new(&self.doc_number),
+            Box::new(&self.scheme),
+            Box::new(self.main),
+            Box::new(&self.symbol),
+            Box::new(&self.version),
+        ]
+    }
+}
+
+impl PgRow for PartyRow {
+    fn values(&self) -> Vec<Box<dyn ToSql + Sync + Send + '_>> {
+        vec![
+            Box::new(&self.doc_number),
+            Box::new(&self.role),
+            Box::new(&self.sequence),
+            Box::new(&self.orgname),
+            Box::new(&self.first_name),
+            Box::new(&self.last_name),
+            Box::new(&self.city),
+            Box::new(&self.state),
+            Box::new(&self.country),
+        ]
+    }
+}
+
+/// DDL creating the tables and their indexes, if they aren't there
+pub fn schema() -> String {
+    [
+        GrantRow::create_table(),
+        ClaimRow::create_table(),
+        CitationRow::create_table(),
+        ClassificationRow::create_table(),
+        PartyRow::create_table(),
+    ].join("\n")
+}
+
+pub struct PostgresLoader<'a> {
+    client: &'a mut Client,
+    batch_size: usize,
+    // grants not loaded yet, each doc_number once
+    grants: Vec<GrantRow>,
+    claims: Vec<ClaimRow>,
+    citations: Vec<CitationRow>,
+    classifications: Vec<ClassificationRow>,
+    parties: Vec<PartyRow>,
+}
+
+impl<'a> PostgresLoader<'a> {
+    /// Creates the tables in client's database if they aren't there
+    pub async fn new(client: &'a mut Client) -> Result<PostgresLoader<'a>, Error> {
+        client.batch_execute(&schema())
+            .await
+            .context(WritePostgres)?;
+
+        Ok(PostgresLoader {
+            client,
+            batch_size: DEFAULT_BATCH_SIZE,
+            grants: Vec::new(),
+            claims: Vec::new(),
+            citations: Vec::new(),
+            classifications: Vec::new(),
+            parties: Vec::new(),
+        })
+    }
+
+    /// Number of grants buffered before being loaded, in one transaction
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub async fn write(&mut self, patent_grant: &PatentGrant) -> Result<(), Error> {
+        let rows = Rows::from(patent_grant);
+
+        // a grant twice in a batch can't be upserted twice in one statement;
+        // the later one replaces the earlier, as it would across batches
+        if self.grants.iter().any(|grant| grant.doc_number == rows.grant.doc_number) {
+            let doc_number = &rows.grant.doc_number;
+            self.grants.retain(|row| &row.doc_number != doc_number);
+            self.claims.retain(|row| &row.doc_number != doc_number);
+            self.citations.retain(|row| &row.doc_number != doc_number);
+            self.classifications.retain(|row| &row.doc_number != doc_number);
+            self.parties.retain(|row| &row.doc_number != doc_number);
+        }
+
+        self.grants.push(rows.grant);
+        self.claims.extend(rows.claims);
+        self.citations.extend(rows.citations);
+        self.classifications.extend(rows.classifications);
+        self.parties.extend(rows.parties);
+
+        if self.grants.len() >= self.batch_size {
+            self.flush().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Loads the grants still buffered
+    pub async fn finish(mut self) -> Result<(), Error> {
+        self.flush().await
+    }
+
+    async fn flush(&mut self) -> Result<(), Error> {
+        if self.grants.is_empty() {
+            return Ok(());
+        }
+
+        let tx = self.client.transaction()
+            .await
+            .context(WritePostgres)?;
+
+        copy_staging(&tx, &self.grants).await?;
+        copy_staging(&tx, &self.claims).await?;
+        copy_staging(&tx, &self.citations).await?;
+        copy_staging(&tx, &self.classifications).await?;
+        copy_staging(&tx, &self.parties).await?;
+
+        tx.batch_execute(&merge_staging())
+            .await
+            .context(WritePostgres)?;
+        tx.commit()
+            .await
+            .context(WritePostgres)?;
+
+        self.grants.clear();
+        self.claims.clear();
+        self.citations.clear();
+        self.classifications.clear();
+        self.parties.clear();
+
+        Ok(())
+    }
+}
+
+/// the table rows are copied into before they're merged, dropped on commit
+fn staging_table(table: &str) -> String {
+    format!("staging_{}", table)
+}
+
+async fn copy_staging<R: PgRow>(tx: &Transaction<'_>, rows: &[R]) -> Result<(), Error> {
+    let staging = staging_table(R::TABLE);
+    tx.batch_execute(&format!("CREATE TEMP TABLE {} (LIKE {}) ON COMMIT DROP", staging, R::TABLE))
+        .await
+        .context(WritePostgres)?;
+
+    let sink = tx.copy_in(&format!("COPY {} ({}) FROM STDIN BINARY", staging, R::COLUMNS.join(", ")))
+        .await
+        .context(WritePostgres)?;
+    let mut writer = pin!(BinaryCopyInWriter::new(sink, &R::types()));
+
+    for row in rows {
+        let values = row.values();
+        let values: Vec<&(dyn ToSql + Sync)> = values.iter().map(|value| &**value as &(dyn ToSql + Sync)).collect();
+        writer.as_mut().write(&values)
+            .await
+            .context(WritePostgres)?;
+    }
+
+    writer.finish()
+        .await
+        .context(WritePostgres)?;
+
+    Ok(())
+}
+
+/// Replaces the rows of the staged grants with the staged rows
+fn merge_staging() -> String {
+    let staged = format!("SELECT doc_number FROM {}", staging_table(GrantRow::TABLE));
+    let mut statements = Vec::new();
+
+    for table in &[ClaimRow::TABLE, CitationRow::TABLE, ClassificationRow::TABLE, PartyRow::TABLE] {
+        statements.push(format!("DELETE FROM {} WHERE doc_number IN ({});", table, staged));
+    }
+
+    let updates: Vec<String> = GrantRow::COLUMNS.iter()
+        .filter(|column| **column != "doc_number")
+        .map(|column| format!("{0} = EXCLUDED.{0}", column))
+        .collect();
+    statements.push(format!(
+        "INSERT INTO {0} SELECT * FROM {1} ON CONFLICT (doc_number) DO UPDATE SET {2};",
+        GrantRow::TABLE, staging_table(GrantRow::TABLE), updates.join(", "),
+    ));
+
+    for table in &[ClaimRow::TABLE, CitationRow::TABLE, ClassificationRow::TABLE, PartyRow::TABLE] {
+        statements.push(format!("INSERT INTO {} SELECT * FROM {};", table, staging_table(table)));
+    }
+
+    statements.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // values have to be kept in sync with COLUMNS by hand; binary COPY
+    // fails on a value of the wrong type
+    fn assert_values_match_types<R: PgRow + Default>() {
+        let row = R::default();
+        let values = row.values();
+        let types = R::types();
+        assert_eq!(values.len(), types.len(), "{}", R::TABLE);
+
+        for ((value, pg_type), column) in values.iter().zip(&types).zip(R::COLUMNS) {
+            let mut buf = bytes::BytesMut::new();
+            assert!(value.to_sql_checked(pg_type, &mut buf).is_ok(), "{}.{}", R::TABLE, column);
+        }
+    }
+
+    #[test]
+    fn test_values_match_columns() {
+        assert_values_match_types::<GrantRow>();
+        assert_values_match_types::<ClaimRow>();
+        assert_values_match_types::<CitationRow>();
+        assert_values_match_types::<ClassificationRow>();
+        assert_values_match_types::<PartyRow>();
+    }
+
+    #[test]
+    fn test_schema() {
+        let schema = schema();
+        assert!(schema.contains("CREATE TABLE IF NOT EXISTS grants (doc_number text PRIMARY KEY, kind text, "));
+        assert!(schema.contains(", number_of_claims int4, "));
+        assert!(schema.contains("CREATE TABLE IF NOT EXISTS classifications (doc_number text NOT NULL REFERENCES grants(doc_number), scheme text, main bool, "));
+        assert!(schema.contains("CREATE INDEX IF NOT EXISTS claims_doc_number ON claims(doc_number);"));
+
+        let merge = merge_staging();
+        assert!(merge.starts_with("DELETE FROM claims WHERE doc_number IN (SELECT doc_number FROM staging_grants);"));
+        assert!(merge.contains("ON CONFLICT (doc_number) DO UPDATE SET kind = EXCLUDED.kind, "));
+        assert!(!merge.contains("doc_number = EXCLUDED.doc_number"));
+    }
+}
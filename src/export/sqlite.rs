@@ -0,0 +1,297 @@
+//! Writes the export tables into a sqlite database, a table for each: the
+//! others reference `grants` by doc_number, and are indexed on it. Grants
+//! are inserted in transactions of many at a time.
+
+use rusqlite::{Connection, ToSql};
+use snafu::ResultExt;
+use std::path::Path;
+
+use crate::data::PatentGrant;
+use crate::error::{Error, WriteSqlite};
+use super::*;
+
+/// grants inserted per transaction
+pub const DEFAULT_BATCH_SIZE: usize = 1_000;
+
+/// Sqlite types and values of a Row's columns.
+pub trait SqlRow: Row {
+    /// columns that aren't TEXT
+    const TYPED_COLUMNS: &'static [(&'static str, &'static str)] = &[];
+
+    /// in COLUMNS order
+    fn values(&self) -> Vec<&dyn ToSql>;
+
+    /// doc_number is the key of grants, and references it in the other
+    /// tables
+    fn create_table() -> String {
+        let columns: Vec<String> = Self::COLUMNS.iter()
+            .map(|column| {
+                let sql_type = Self::TYPED_COLUMNS.iter()
+                    .find(|(name, _)| name == column)
+                    .map(|(_, sql_type)| *sql_type)
+                    .unwrap_or("TEXT");
+
+                match *column {
+                    "doc_number" if Self::TABLE == GrantRow::TABLE => "doc_number TEXT PRIMARY KEY".to_string(),
+                    "doc_number" => format!("doc_number TEXT NOT NULL REFERENCES {}(doc_number)", GrantRow::TABLE),
+                    _ => format!("{} {}", column, sql_type),
+                }
+            })
+            .collect();
+
+        let mut sql = format!("CREATE TABLE IF NOT EXISTS {} ({});", Self::TABLE, columns.join(", "));
+        if Self::TABLE != GrantRow::TABLE {
+            sql.push_str(&format!("\nCREATE INDEX IF NOT EXISTS {0}_doc_number ON {0}(doc_number);", Self::TABLE));
+        }
+
+        sql
+    }
+
+    /// a grant's row replaces the one with its doc_number, if any
+    fn insert() -> String {
+        let placeholders = vec!["?"; Self::COLUMNS.len()].join(", ");
+        format!("INSERT OR REPLACE INTO {} ({}) VALUES ({})", Self::TABLE, Self::COLUMNS.join(", "), placeholders)
+    }
+}
+
+impl SqlRow for GrantRow {
+    const TYPED_COLUMNS: &'static [(&'static str, &'static str)] = &[
+        ("number_of_claims", "INTEGER"),
+        ("number_of_figures", "INTEGER"),
+        ("number_of_drawing_sheets", "INTEGER"),
+    ];
+
+    fn values(&self) -> Vec<&dyn ToSql> {
+        vec![
+            &self.doc_number,
+            &self.kind,
+            &self.country,
+            &self.date,
+            &self.application_doc_number,
+            &self.application_date,
+            &self.us_application_series_code,
+            &self.invention_title,
+            &self.number_of_claims,
+            &self.number_of_figures,
+            &self.number_of_drawing_sheets,
+            &self.length_of_grant,
+            &self.us_term_extension,
+            &self.art_unit,
+        ]
+    }
+}
+
+impl SqlRow for ClaimRow {
+    fn values(&self) -> Vec<&dyn ToSql> {
+        vec![
+            &self.doc_number,
+            &self.num,
+            &self.id,
+            &self.claim_type,
+            &self.claim_refs,
+            &self.text,
+        ]
+    }
+}
+
+impl SqlRow for CitationRow {
+    fn values(&self) -> Vec<&dyn ToSql> {
+        vec![
+            &self.doc_number,
+            &self.num,
+            &self.citation_type,
+            &self.category,
+            &self.cited_country,
+            &self.cited_doc_number,
+            &self.cited_kind,
+            &self.cited_name,
+            &self.cited_date,
+            &self.othercit,
+            &self.classification_cpc_text,
+            &self.classification_national,
+        ]
+    }
+}
+
+impl SqlRow for ClassificationRow {
+    const TYPED_COLUMNS: &'static [(&'static str, &'static str)] = &[
+        ("main", "INTEGER"),
+    ];
+
+    fn values(&self) -> Vec<&dyn ToSql> {
+        vec![
+            &self.doc_number,
+            &self.scheme,
+            &self.main,
+            &self.symbol,
+            &self.version,
+        ]
+    }
+}
+
+impl SqlRow for PartyRow {
+    fn values(&self) -> Vec<&dyn ToSql> {
+        vec![
+            &self.doc_number,
+            &self.role,
+            &self.sequence,
+            &self.orgname,
+            &self.first_name,
+            &self.last_name,
+            &self.city,
+            &self.state,
+            &self.country,
+        ]
+    }
+}
+
+pub struct SqliteExporter {
+    conn: Connection,
+    batch_size: usize,
+    // grants written in the open transaction, if any
+    pending: usize,
+}
+
+impl SqliteExporter {
+    /// Opens the database at path, creating it and the tables if needed. A
+    /// grant already in it (e.g. from an earlier file) is replaced, with all
+    /// its rows, when it's written again.
+    pub fn create(path: &Path) -> Result<Self, Error> {
+        let conn = Connection::open(path)
+            .context(WriteSqlite)?;
+
+        let schema = [
+            GrantRow::create_table(),
+            ClaimRow::create_table(),
+            CitationRow::create_table(),
+            ClassificationRow::create_table(),
+            PartyRow::create_table(),
+        ];
+        conn.execute_batch(&schema.join("\n"))
+            .context(WriteSqlite)?;
+
+        Ok(SqliteExporter {
+            conn,
+            batch_size: DEFAULT_BATCH_SIZE,
+            pending: 0,
+        })
+    }
+
+    /// Number of grants inserted per transaction
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn write(&mut self, patent_grant: &PatentGrant) -> Result<(), Error> {
+        let rows = Rows::from(patent_grant);
+
+        if self.pending == 0 {
+            self.conn.execute_batch("BEGIN")
+                .context(WriteSqlite)?;
+        }
+
+        // rows of the grant written before, if it was
+        for table in &[ClaimRow::TABLE, CitationRow::TABLE, ClassificationRow::TABLE, PartyRow::TABLE] {
+            self.conn.prepare_cached(&format!("DELETE FROM {} WHERE doc_number = ?", table))
+                .and_then(|mut stmt| stmt.execute([&rows.grant.doc_number]))
+                .context(WriteSqlite)?;
+        }
+
+        insert_rows(&self.conn, &[rows.grant])?;
+        insert_rows(&self.conn, &rows.claims)?;
+        insert_rows(&self.conn, &rows.citations)?;
+        insert_rows(&self.conn, &rows.classifications)?;
+        insert_rows(&self.conn, &rows.parties)?;
+
+        self.pending += 1;
+        if self.pending >= self.batch_size {
+            self.commit()?;
+        }
+
+        Ok(())
+    }
+
+    /// Commits the grants written since the last transaction
+    pub fn finish(mut self) -> Result<(), Error> {
+        self.commit()
+    }
+
+    fn commit(&mut self) -> Result<(), Error> {
+        if self.pending > 0 {
+            self.conn.execute_batch("COMMIT")
+                .context(WriteSqlite)?;
+            self.pending = 0;
+        }
+
+        Ok(())
+    }
+}
+
+fn insert_rows<R: SqlRow>(conn: &Connection, rows: &[R]) -> Result<(), Error> {
+    let mut stmt = conn.prepare_cached(&R::insert())
+        .context(WriteSqlite)?;
+
+    for row in rows {
+        stmt.execute(&row.values()[..])
+            .context(WriteSqlite)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PatentGrants;
+
+    const FIXTURE: &str = include_str!("../../tests/fixtures/grants.xml");
+
+    #[test]
+    fn test_values_match_columns() {
+        assert_eq!(GrantRow::default().values().len(), GrantRow::COLUMNS.len());
+        assert_eq!(ClaimRow::default().values().len(), ClaimRow::COLUMNS.len());
+        assert_eq!(CitationRow::default().values().len(), CitationRow::COLUMNS.len());
+        assert_eq!(ClassificationRow::default().values().len(), ClassificationRow::COLUMNS.len());
+        assert_eq!(PartyRow::default().values().len(), PartyRow::COLUMNS.len());
+    }
+
+    #[test]
+    fn test_sqlite_export() {
+        let path = std::env::temp_dir().join(format!("uspto-sqlite-export-{}.sqlite", std::process::id()));
+
+        // twice, to replace what's there
+        for _ in 0..2 {
+            let mut exporter = SqliteExporter::create(&path).unwrap().batch_size(2);
+            for patent_grant in PatentGrants::from_reader(FIXTURE.as_bytes()) {
+                exporter.write(&patent_grant.unwrap()).unwrap();
+            }
+            exporter.finish().unwrap();
+        }
+
+        let conn = Connection::open(&path).unwrap();
+        let count = |table: &str| -> i64 {
+            conn.query_row(&format!("SELECT count(*) FROM {}", table), [], |row| row.get(0)).unwrap()
+        };
+        assert_eq!(count("grants"), 3);
+        assert_eq!(count("claims"), 5);
+
+        let (kind, claims): (String, i64) = conn.query_row(
+            "SELECT kind, number_of_claims FROM grants WHERE doc_number = '10165721'", [], |row| Ok((row.get(0)?, row.get(1)?))
+        ).unwrap();
+        assert_eq!((kind.as_str(), claims), ("B2", 3));
+
+        let claim_refs: String = conn.query_row(
+            "SELECT claim_refs FROM claims WHERE doc_number = '10165721' AND id = 'CLM-00003'", [], |row| row.get(0)
+        ).unwrap();
+        assert_eq!(claim_refs, "CLM-00001;CLM-00002");
+
+        let main: bool = conn.query_row(
+            "SELECT main FROM classifications WHERE doc_number = 'D0838001' AND scheme = 'locarno'", [], |row| row.get(0)
+        ).unwrap();
+        assert!(main);
+
+        drop(conn);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,521 @@
+//! Writes a star schema of grants for an OLAP cube (e.g. Tesseract or
+//! Mondrian), as one csv file per table into a directory: a fact table of
+//! grants, dimension tables of dates, assignees, inventors and cpc codes, and
+//! bridge tables linking each grant to its assignees, inventors and cpc
+//! codes.
+//!
+//! Grants have many of each, so the bridges carry a `share` (1 over the
+//! grant's number of them), for fractional counts that add up to the number
+//! of grants.
+//!
+//! Surrogate ids are a hash (see `fingerprint::Fnv`) of a dimension member's
+//! natural key, which is all of its columns, and date ids are YYYYMMDD. So
+//! ids are the same across runs, and exports of different weeks can be
+//! loaded into the same cube: a dimension row exported again is the same
+//! row, and can be skipped on its id. Within an export, each dimension row
+//! is written once.
+
+use chrono::{Datelike, NaiveDate};
+use serde::Serialize;
+use snafu::ResultExt;
+use std::collections::HashSet;
+use std::fs::File;
+use std::path::Path;
+
+use crate::data::*;
+use crate::docnum::grant_number;
+use crate::error::{Error, WriteCsv};
+use crate::fingerprint::Fnv;
+use crate::text::normalize;
+use super::tables::{self, write_rows, Delimited, TableDir};
+use super::Row;
+
+#[derive(Debug, Default, Serialize)]
+pub struct DateDim {
+    /// YYYYMMDD
+    pub date_id: u32,
+    /// YYYY-MM-DD
+    pub date: String,
+    pub year: i32,
+    pub quarter: u32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Row for DateDim {
+    const TABLE: &'static str = "dim_date";
+    const COLUMNS: &'static [&'static str] = &[
+        "date_id",
+        "date",
+        "year",
+        "quarter",
+        "month",
+        "day",
+    ];
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct AssigneeDim {
+    pub assignee_id: i64,
+    /// the organization, or for a person "first last"
+    pub name: String,
+    pub city: Option<String>,
+    pub state: Option<String>,
+    pub country: Option<String>,
+}
+
+impl Row for AssigneeDim {
+    const TABLE: &'static str = "dim_assignee";
+    const COLUMNS: &'static [&'static str] = &[
+        "assignee_id",
+        "name",
+        "city",
+        "state",
+        "country",
+    ];
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct InventorDim {
+    pub inventor_id: i64,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub city: Option<String>,
+    pub state: Option<String>,
+    pub country: Option<String>,
+}
+
+impl Row for InventorDim {
+    const TABLE: &'static str = "dim_inventor";
+    const COLUMNS: &'static [&'static str] = &[
+        "inventor_id",
+        "first_name",
+        "last_name",
+        "city",
+        "state",
+        "country",
+    ];
+}
+
+/// A cpc code, with the levels above it for drilling down
+#[derive(Debug, Default, Serialize)]
+pub struct CpcDim {
+    pub cpc_id: i64,
+    /// e.g. "A01B 1/00"
+    pub symbol: String,
+    /// e.g. "A"
+    pub section: String,
+    /// e.g. "A01"
+    pub class: String,
+    /// e.g. "A01B"
+    pub subclass: String,
+    /// e.g. "A01B 1"
+    pub main_group: String,
+}
+
+impl Row for CpcDim {
+    const TABLE: &'static str = "dim_cpc";
+    const COLUMNS: &'static [&'static str] = &[
+        "cpc_id",
+        "symbol",
+        "section",
+        "class",
+        "subclass",
+        "main_group",
+    ];
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct GrantFact {
+    pub grant_id: i64,
+    pub doc_number: String,
+    pub kind: Option<String>,
+    /// None for a missing (or partial) date
+    pub date_id: Option<u32>,
+    pub application_date_id: Option<u32>,
+    pub number_of_claims: Option<u32>,
+    pub number_of_figures: Option<u32>,
+    pub number_of_drawing_sheets: Option<u32>,
+    pub number_of_citations: usize,
+    pub number_of_inventors: usize,
+    pub number_of_assignees: usize,
+}
+
+impl Row for GrantFact {
+    const TABLE: &'static str = "fact_grant";
+    const COLUMNS: &'static [&'static str] = &[
+        "grant_id",
+        "doc_number",
+        "kind",
+        "date_id",
+        "application_date_id",
+        "number_of_claims",
+        "number_of_figures",
+        "number_of_drawing_sheets",
+        "number_of_citations",
+        "number_of_inventors",
+        "number_of_assignees",
+    ];
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct GrantAssignee {
+    pub grant_id: i64,
+    pub assignee_id: i64,
+    /// the assignee's position on the grant, from 0
+    pub sequence: usize,
+    pub share: f64,
+}
+
+impl Row for GrantAssignee {
+    const TABLE: &'static str = "bridge_grant_assignee";
+    const COLUMNS: &'static [&'static str] = &[
+        "grant_id",
+        "assignee_id",
+        "sequence",
+        "share",
+    ];
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct GrantInventor {
+    pub grant_id: i64,
+    pub inventor_id: i64,
+    /// the inventor's position on the grant, from 0
+    pub sequence: usize,
+    pub share: f64,
+}
+
+impl Row for GrantInventor {
+    const TABLE: &'static str = "bridge_grant_inventor";
+    const COLUMNS: &'static [&'static str] = &[
+        "grant_id",
+        "inventor_id",
+        "sequence",
+        "share",
+    ];
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct GrantCpc {
+    pub grant_id: i64,
+    pub cpc_id: i64,
+    pub main: bool,
+    pub share: f64,
+}
+
+impl Row for GrantCpc {
+    const TABLE: &'static str = "bridge_grant_cpc";
+    const COLUMNS: &'static [&'static str] = &[
+        "grant_id",
+        "cpc_id",
+        "main",
+        "share",
+    ];
+}
+
+/// All the rows a single PatentGrant gives in the star schema; dimension
+/// rows may already have been given by another grant
+#[derive(Debug, Default)]
+pub struct StarRows {
+    pub grant: GrantFact,
+    pub dates: Vec<DateDim>,
+    pub assignees: Vec<AssigneeDim>,
+    pub inventors: Vec<InventorDim>,
+    pub cpcs: Vec<CpcDim>,
+    pub grant_assignees: Vec<GrantAssignee>,
+    pub grant_inventors: Vec<GrantInventor>,
+    pub grant_cpcs: Vec<GrantCpc>,
+}
+
+impl From<&PatentGrant> for StarRows {
+    fn from(pg: &PatentGrant) -> Self {
+        let dg = &pg.us_bibliographic_data_grant;
+        let doc_number = grant_number(&dg.publication_reference.doc_number);
+        let grant_id = surrogate_id("grant", &[&doc_number]);
+
+        let mut rows = StarRows::default();
+
+        let date = date_dim(&dg.publication_reference.date);
+        let application_date = date_dim(&dg.application_reference.date);
+        rows.grant = GrantFact {
+            grant_id,
+            doc_number,
+            kind: dg.publication_reference.kind.as_ref().map(KindCode::to_string),
+            date_id: date.as_ref().map(|date| date.date_id),
+            application_date_id: application_date.as_ref().map(|date| date.date_id),
+            number_of_claims: dg.number_of_claims,
+            number_of_figures: dg.figures.number_of_figures,
+            number_of_drawing_sheets: dg.figures.number_of_drawing_sheets,
+            number_of_citations: dg.us_references_cited.len(),
+            number_of_inventors: dg.inventors.len(),
+            number_of_assignees: dg.assignees.len(),
+        };
+        rows.dates.extend(date);
+        rows.dates.extend(application_date);
+
+        for (sequence, assignee) in dg.assignees.iter().enumerate() {
+            let addressbook = &assignee.addressbook;
+            // assignee orgname can be outside of the addressbook
+            let name = match addressbook.orgname.as_ref().or(assignee.orgname.as_ref()) {
+                Some(orgname) => normalize(orgname),
+                None => normalize(&format!(
+                    "{} {}",
+                    addressbook.first_name.as_deref().unwrap_or_default(),
+                    addressbook.last_name.as_deref().unwrap_or_default(),
+                )),
+            };
+            let (city, state, country) = address(&addressbook.address);
+            let assignee_id = surrogate_id("assignee", &[&name, opt(&city), opt(&state), opt(&country)]);
+
+            rows.assignees.push(AssigneeDim { assignee_id, name, city, state, country });
+            rows.grant_assignees.push(GrantAssignee {
+                grant_id,
+                assignee_id,
+                sequence,
+                share: 1.0 / dg.assignees.len() as f64,
+            });
+        }
+
+        for (sequence, inventor) in dg.inventors.iter().enumerate() {
+            let addressbook = &inventor.addressbook;
+            let first_name = addressbook.first_name.as_deref().map(normalize);
+            let last_name = addressbook.last_name.as_deref().map(normalize);
+            let (city, state, country) = address(&addressbook.address);
+            let inventor_id = surrogate_id("inventor", &[opt(&first_name), opt(&last_name), opt(&city), opt(&state), opt(&country)]);
+
+            rows.inventors.push(InventorDim { inventor_id, first_name, last_name, city, state, country });
+            rows.grant_inventors.push(GrantInventor {
+                grant_id,
+                inventor_id,
+                sequence,
+                share: 1.0 / dg.inventors.len() as f64,
+            });
+        }
+
+        let cpcs = &dg.classifications_cpc;
+        let codes = std::iter::once(&cpcs.main_cpc)
+            .chain(&cpcs.further_cpc)
+            .enumerate()
+            .filter(|(_, cpc)| !cpc.section.is_empty());
+        for (i, cpc) in codes {
+            let symbol = cpc.symbol();
+            let cpc_id = surrogate_id("cpc", &[&symbol]);
+
+            // the same code can be listed more than once
+            if rows.grant_cpcs.iter().any(|grant_cpc| grant_cpc.cpc_id == cpc_id) {
+                continue;
+            }
+
+            rows.cpcs.push(CpcDim {
+                cpc_id,
+                section: cpc.section.clone(),
+                class: format!("{}{}", cpc.section, cpc.class),
+                subclass: format!("{}{}{}", cpc.section, cpc.class, cpc.subclass),
+                main_group: format!("{}{}{} {}", cpc.section, cpc.class, cpc.subclass, cpc.main_group),
+                symbol,
+            });
+            rows.grant_cpcs.push(GrantCpc {
+                grant_id,
+                cpc_id,
+                main: i == 0,
+                share: 0.0,
+            });
+        }
+        let shares = rows.grant_cpcs.len() as f64;
+        for grant_cpc in &mut rows.grant_cpcs {
+            grant_cpc.share = 1.0 / shares;
+        }
+
+        rows
+    }
+}
+
+/// A positive 63-bit id (to fit a signed bigint) from a dimension member's
+/// natural key
+fn surrogate_id(dimension: &str, key: &[&str]) -> i64 {
+    let mut hash = Fnv::new();
+    hash.field("dimension", dimension);
+    for field in key {
+        hash.field("key", field);
+    }
+
+    (hash.0 & i64::MAX as u64) as i64
+}
+
+fn opt(text: &Option<String>) -> &str {
+    text.as_deref().unwrap_or_default()
+}
+
+fn address(address: &Address) -> (Option<String>, Option<String>, Option<String>) {
+    (
+        address.city.as_deref().map(normalize),
+        address.state.clone(),
+        address.country.as_ref().map(CountryCode::to_string),
+    )
+}
+
+/// None for a missing date, or one with no day or month
+fn date_dim(date: &Date) -> Option<DateDim> {
+    let date = NaiveDate::parse_from_str(&date_text(date), "%Y%m%d").ok()?;
+
+    Some(DateDim {
+        date_id: date.year() as u32 * 10000 + date.month() * 100 + date.day(),
+        date: date.format("%Y-%m-%d").to_string(),
+        year: date.year(),
+        quarter: (date.month() - 1) / 3 + 1,
+        month: date.month(),
+        day: date.day(),
+    })
+}
+
+pub struct StarExporter {
+    // dimension ids written so far
+    seen: HashSet<(&'static str, i64)>,
+    grants: ::csv::Writer<File>,
+    dates: ::csv::Writer<File>,
+    assignees: ::csv::Writer<File>,
+    inventors: ::csv::Writer<File>,
+    cpcs: ::csv::Writer<File>,
+    grant_assignees: ::csv::Writer<File>,
+    grant_inventors: ::csv::Writer<File>,
+    grant_cpcs: ::csv::Writer<File>,
+}
+
+impl StarExporter {
+    /// A `<table>.csv` in out_dir for each table, see `export::tables`
+    pub fn create(out_dir: &Path) -> Result<Self, Error> {
+        let dir = TableDir::create(out_dir, Delimited::Csv)?;
+
+        Ok(StarExporter {
+            seen: HashSet::new(),
+            grants: dir.writer::<GrantFact>()?,
+            dates: dir.writer::<DateDim>()?,
+            assignees: dir.writer::<AssigneeDim>()?,
+            inventors: dir.writer::<InventorDim>()?,
+            cpcs: dir.writer::<CpcDim>()?,
+            grant_assignees: dir.writer::<GrantAssignee>()?,
+            grant_inventors: dir.writer::<GrantInventor>()?,
+            grant_cpcs: dir.writer::<GrantCpc>()?,
+        })
+    }
+
+    pub fn write(&mut self, patent_grant: &PatentGrant) -> Result<(), Error> {
+        let rows = StarRows::from(patent_grant);
+        let seen = &mut self.seen;
+        let mut new = |table: &'static str, id: i64| seen.insert((table, id));
+
+        let dates: Vec<_> = rows.dates.into_iter()
+            .filter(|date| new(DateDim::TABLE, i64::from(date.date_id)))
+            .collect();
+        let assignees: Vec<_> = rows.assignees.into_iter()
+            .filter(|assignee| new(AssigneeDim::TABLE, assignee.assignee_id))
+            .collect();
+        let inventors: Vec<_> = rows.inventors.into_iter()
+            .filter(|inventor| new(InventorDim::TABLE, inventor.inventor_id))
+            .collect();
+        let cpcs: Vec<_> = rows.cpcs.into_iter()
+            .filter(|cpc| new(CpcDim::TABLE, cpc.cpc_id))
+            .collect();
+
+        self.grants.serialize(&rows.grant).context(WriteCsv)?;
+        write_rows(&mut self.dates, &dates)?;
+        write_rows(&mut self.assignees, &assignees)?;
+        write_rows(&mut self.inventors, &inventors)?;
+        write_rows(&mut self.cpcs, &cpcs)?;
+        write_rows(&mut self.grant_assignees, &rows.grant_assignees)?;
+        write_rows(&mut self.grant_inventors, &rows.grant_inventors)?;
+        write_rows(&mut self.grant_cpcs, &rows.grant_cpcs)?;
+
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<(), Error> {
+        tables::flush(&mut [
+            &mut self.grants,
+            &mut self.dates,
+            &mut self.assignees,
+            &mut self.inventors,
+            &mut self.cpcs,
+            &mut self.grant_assignees,
+            &mut self.grant_inventors,
+            &mut self.grant_cpcs,
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::PatentGrants;
+
+    const FIXTURE: &str = include_str!("../../tests/fixtures/grants.xml");
+
+    fn serialized_header<R: Row + Default>() -> Vec<String> {
+        let mut wtr = ::csv::Writer::from_writer(vec![]);
+        wtr.serialize(R::default()).unwrap();
+        let data = String::from_utf8(wtr.into_inner().unwrap()).unwrap();
+
+        data.lines().next().unwrap().split(',').map(|s| s.to_owned()).collect()
+    }
+
+    #[test]
+    fn test_columns_match_rows() {
+        assert_eq!(serialized_header::<DateDim>(), DateDim::COLUMNS);
+        assert_eq!(serialized_header::<AssigneeDim>(), AssigneeDim::COLUMNS);
+        assert_eq!(serialized_header::<InventorDim>(), InventorDim::COLUMNS);
+        assert_eq!(serialized_header::<CpcDim>(), CpcDim::COLUMNS);
+        assert_eq!(serialized_header::<GrantFact>(), GrantFact::COLUMNS);
+        assert_eq!(serialized_header::<GrantAssignee>(), GrantAssignee::COLUMNS);
+        assert_eq!(serialized_header::<GrantInventor>(), GrantInventor::COLUMNS);
+        assert_eq!(serialized_header::<GrantCpc>(), GrantCpc::COLUMNS);
+    }
+
+    #[test]
+    fn test_star_rows() {
+        let grants = PatentGrants::from_reader(FIXTURE.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let rows = StarRows::from(&grants[0]);
+
+        assert_eq!(rows.grant.doc_number, "10165721");
+        assert_eq!(rows.grant.date_id, Some(20190101));
+        assert_eq!(rows.dates[0].quarter, 1);
+        assert_eq!(rows.grant_inventors.len(), 2);
+        assert_eq!(rows.grant_inventors[0].share, 0.5);
+        assert_eq!(rows.grant_assignees[0].assignee_id, rows.assignees[0].assignee_id);
+        assert_eq!(rows.cpcs[0].main_group, "A01B 1");
+        assert!(rows.grant_cpcs[0].main);
+        assert!((rows.grant_cpcs.iter().map(|grant_cpc| grant_cpc.share).sum::<f64>() - 1.0).abs() < 1e-9);
+
+        // ids depend on the natural key only
+        let again = StarRows::from(&grants[0]);
+        assert_eq!(again.grant.grant_id, rows.grant.grant_id);
+        assert_eq!(again.inventors[1].inventor_id, rows.inventors[1].inventor_id);
+        assert_eq!(rows.grant.grant_id, surrogate_id("grant", &["10165721"]));
+        assert!(rows.grant.grant_id >= 0);
+        assert_ne!(StarRows::from(&grants[1]).grant.grant_id, rows.grant.grant_id);
+    }
+
+    #[test]
+    fn test_star_export() {
+        let out_dir = std::env::temp_dir().join(format!("uspto-star-export-{}", std::process::id()));
+
+        let mut exporter = StarExporter::create(&out_dir).unwrap();
+        for patent_grant in PatentGrants::from_reader(FIXTURE.as_bytes()).chain(PatentGrants::from_reader(FIXTURE.as_bytes())) {
+            exporter.write(&patent_grant.unwrap()).unwrap();
+        }
+        exporter.finish().unwrap();
+
+        let lines = |table: &str| fs::read_to_string(out_dir.join(format!("{}.csv", table))).unwrap().lines().count() - 1;
+        // every grant twice, but dimension rows once
+        assert_eq!(lines("fact_grant"), 6);
+        assert_eq!(lines("bridge_grant_inventor"), 8);
+        // one of the inventors is on two of the grants
+        assert_eq!(lines("dim_inventor"), 3);
+        assert!(fs::read_to_string(out_dir.join("dim_date.csv")).unwrap().contains("20190101,2019-01-01,2019,1,1,1"));
+
+        fs::remove_dir_all(&out_dir).unwrap();
+    }
+}
@@ -0,0 +1,77 @@
+//! The table files of the exporters writing delimited text (csv, and the
+//! PatentsView and star layouts): a `<table>.csv` (or `.tsv`) per table,
+//! in a directory created if needed.
+//!
+//! Each file is created with its header already written, rather than by
+//! serialize on the first row, so a table with no rows still gets it; an
+//! existing file is truncated.
+
+use snafu::ResultExt;
+use std::fs::{self, File};
+use std::path::Path;
+
+use crate::error::{Error, ExportIo, WriteCsv};
+use super::Row;
+
+/// How a table's values are separated, and so its file's extension
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Delimited {
+    Csv,
+    Tsv,
+}
+
+/// The directory the table files are created in
+pub(super) struct TableDir<'a> {
+    out_dir: &'a Path,
+    delimited: Delimited,
+}
+
+impl<'a> TableDir<'a> {
+    /// Creates out_dir if needed
+    pub(super) fn create(out_dir: &'a Path, delimited: Delimited) -> Result<Self, Error> {
+        fs::create_dir_all(out_dir)
+            .context(ExportIo)?;
+
+        Ok(TableDir { out_dir, delimited })
+    }
+
+    /// The table's file, with its header written
+    pub(super) fn writer<R: Row>(&self) -> Result<::csv::Writer<File>, Error> {
+        let (extension, delimiter) = match self.delimited {
+            Delimited::Csv => ("csv", b','),
+            Delimited::Tsv => ("tsv", b'\t'),
+        };
+        let path = self.out_dir.join(format!("{}.{}", R::TABLE, extension));
+
+        let mut wtr = ::csv::WriterBuilder::new()
+            .delimiter(delimiter)
+            .has_headers(false)
+            .from_path(path)
+            .context(WriteCsv)?;
+
+        wtr.write_record(R::COLUMNS)
+            .context(WriteCsv)?;
+
+        Ok(wtr)
+    }
+}
+
+pub(super) fn write_rows<R: Row>(wtr: &mut ::csv::Writer<File>, rows: &[R]) -> Result<(), Error> {
+    for row in rows {
+        wtr.serialize(row)
+            .context(WriteCsv)?;
+    }
+
+    Ok(())
+}
+
+/// Flushes each of an exporter's tables, when it's finished
+pub(super) fn flush(wtrs: &mut [&mut ::csv::Writer<File>]) -> Result<(), Error> {
+    for wtr in wtrs {
+        wtr.flush()
+            .map_err(::csv::Error::from)
+            .context(WriteCsv)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,150 @@
+//! A content hash of a grant, the same for the same content however it's
+//! formatted, e.g. to deduplicate grants across overlapping bulk files: a
+//! corrected re-publication changes the fingerprint, a copy doesn't.
+//!
+//! What's hashed is the content, normalized: doc-numbers without their zero
+//! padding (see `docnum::grant_number`), text as in `text::normalize`,
+//! description sections in name order, and cited documents dated to the
+//! month. The grant meta (file name, date produced, etc.) isn't, nor is how
+//! the xml is laid out. The hash (64-bit FNV-1a) is fixed, so fingerprints
+//! can be stored and compared across runs, versions of this crate, and
+//! features (e.g. `typed-dates`).
+
+use crate::data::{date_text, Citation, ClaimText, Date, DocumentId, PatentGrant};
+use crate::docnum::grant_number;
+use crate::text::normalize;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+impl PatentGrant {
+    /// See the `fingerprint` module
+    pub fn fingerprint(&self) -> u64 {
+        let biblio = &self.us_bibliographic_data_grant;
+        let mut hash = Fnv::new();
+
+        hash.document_id("publication-reference", &biblio.publication_reference);
+        hash.document_id("application-reference", &biblio.application_reference);
+        hash.field("invention-title", &normalize(&biblio.invention_title.title));
+
+        let cpc = &biblio.classifications_cpc;
+        for classification in std::iter::once(&cpc.main_cpc).chain(&cpc.further_cpc) {
+            if !classification.section.is_empty() {
+                hash.field("classification-cpc", &classification.symbol());
+            }
+        }
+
+        for inventor in &biblio.inventors {
+            let name = &inventor.addressbook;
+            hash.field("inventor", &format!("{} {}", name.first_name.as_deref().unwrap_or_default(), name.last_name.as_deref().unwrap_or_default()));
+        }
+        for assignee in &biblio.assignees {
+            let name = assignee.orgname.as_deref()
+                .or(assignee.addressbook.orgname.as_deref())
+                .or(assignee.addressbook.last_name.as_deref())
+                .unwrap_or_default();
+            hash.field("assignee", &normalize(name));
+        }
+
+        for cited in &biblio.us_references_cited {
+            match cited.citation {
+                Citation::Patent(ref patcit) => hash.document_id("patcit", &patcit.document_id),
+                Citation::NonPatent(ref nplcit) => hash.field("nplcit", &normalize(&nplcit.othercit)),
+            }
+        }
+
+        for paragraph in &self.r#abstract {
+            hash.field("abstract", &normalize(&paragraph.text));
+        }
+
+        for claim in &self.claims {
+            let mut texts = Vec::new();
+            claim_texts(&claim.claim_texts, &mut texts);
+            hash.field("claim", &normalize(&texts.join(" ")));
+        }
+
+        let mut names: Vec<_> = self.descriptions.keys().collect();
+        names.sort();
+        for name in names {
+            hash.field(name, &normalize(&self.descriptions[name]));
+        }
+
+        hash.0
+    }
+}
+
+/// Depth-first, as in the claim
+fn claim_texts<'a>(claim_texts: &'a [ClaimText], texts: &mut Vec<&'a str>) {
+    for claim_text in claim_texts {
+        texts.push(&claim_text.text);
+        self::claim_texts(&claim_text.claim_texts, texts);
+    }
+}
+
+/// FNV-1a, hashing each field as its name and text, each ended by a unit
+/// separator, so fields can't run into each other
+pub(crate) struct Fnv(pub(crate) u64);
+
+impl Fnv {
+    pub(crate) fn new() -> Self {
+        Fnv(FNV_OFFSET)
+    }
+
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    pub(crate) fn field(&mut self, name: &str, text: &str) {
+        self.write(name.as_bytes());
+        self.write(b"\x1f");
+        self.write(text.as_bytes());
+        self.write(b"\x1f");
+    }
+
+    fn document_id(&mut self, name: &str, doc_id: &DocumentId) {
+        let kind = doc_id.kind.as_ref().map(|kind| kind.as_str()).unwrap_or_default();
+        // a missing date is "", typed dates or not
+        let mut date = if doc_id.date == Date::default() { String::new() } else { date_text(&doc_id.date) };
+        // cited documents are often dated only to the month (a 00 day, which
+        // typed dates make the first)
+        if name == "patcit" {
+            date.truncate(6);
+        }
+        let text = format!("{} {} {} {}", doc_id.country.as_str(), grant_number(&doc_id.doc_number), kind, date);
+        self.field(name, &text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::deserialize::PatentGrants;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/grants.xml");
+
+    #[test]
+    fn test_fingerprint() {
+        let grants = PatentGrants::from_reader(FIXTURE.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let fingerprints: Vec<_> = grants.iter().map(|grant| grant.fingerprint()).collect();
+        assert_ne!(fingerprints[0], fingerprints[1]);
+
+        // the same content laid out differently
+        let reformatted = FIXTURE.replace("<p id=\"p-0002\" num=\"0000\">The handle has", "<p id=\"p-0002\" num=\"0000\">The  handle\n has");
+        assert_ne!(reformatted, FIXTURE);
+        let grant = PatentGrants::from_reader(reformatted.as_bytes()).next().unwrap().unwrap();
+        assert_eq!(grant.fingerprint(), fingerprints[0]);
+
+        let mut grant = grant;
+        grant.meta.date_produced = Some("20990101".to_string());
+        assert_eq!(grant.fingerprint(), fingerprints[0]);
+        grant.claims.pop();
+        assert_ne!(grant.fingerprint(), fingerprints[0]);
+
+        // unchanged from version to version
+        assert_eq!(fingerprints[0], 0xc253_79de_853e_54ce);
+    }
+}
@@ -0,0 +1,447 @@
+//! ParseService, the Parser service of `proto/uspto.proto` (served by the
+//! `uspto-serve` binary): a client streams a bulk file's bytes, or sends
+//! its url, and is sent back each grant parsed as a `proto::PatentGrant`.
+//!
+//! Grants are parsed leniently, on a blocking thread per call, as the bytes
+//! come in; a document that fails to parse is an error response, and the
+//! rest are still parsed. Streamed bytes are plain or gzipped xml, told
+//! apart by the first chunk's first bytes. A zip archive can't be read
+//! until it's all there, so it's an invalid argument, unless by url.
+//!
+//! The server fetches a url for whoever sends it, so urls are refused
+//! unless they're under a prefix allowed with `ParseService::allow_urls`,
+//! and are only ever remote: http(s), or with the `object-store` feature
+//! s3 and gs, never a local file. A redirect is only followed to a url
+//! that's allowed too.
+
+use futures_core::Stream;
+use snafu::ResultExt;
+use std::future::poll_fn;
+use std::io::{self, BufReader, Read};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+use tonic::{Request, Response, Status, Streaming};
+
+use crate::bulkdata::HttpReader;
+use crate::deserialize::PatentGrants;
+use crate::error::{Error, Fetch};
+use crate::input::{detect_format, Format, InputReader};
+use crate::options::ParserOptions;
+use crate::proto::parse_request::Input;
+use crate::proto::parse_response;
+use crate::proto::parser_server::Parser;
+use crate::proto::{self, ParseError, ParseRequest, ParseResponse};
+
+/// responses, and chunks, buffered per call
+const CHANNEL_SIZE: usize = 64;
+
+/// followed when fetching a url, as long as each is allowed
+const MAX_REDIRECTS: usize = 10;
+
+/// of the urls a client can send
+#[cfg(not(feature = "object-store"))]
+const REMOTE_SCHEMES: &[&str] = &["http", "https"];
+#[cfg(feature = "object-store")]
+const REMOTE_SCHEMES: &[&str] = &["http", "https", "s3", "s3a", "gs"];
+
+/// The Parser service, serve it in a `proto::parser_server::ParserServer`
+#[derive(Debug, Clone, Default)]
+pub struct ParseService {
+    options: ParserOptions,
+    allowed_urls: Vec<String>,
+}
+
+impl ParseService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// See `PatentGrants::with_options`
+    pub fn options(mut self, options: ParserOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Takes urls starting with one of the prefixes (e.g.
+    /// `https://bulkdata.uspto.gov/`), as well as chunks; by default no
+    /// url is taken. A prefix matches up to a `/`, so `https://host` doesn't
+    /// allow `https://host.example`.
+    pub fn allow_urls<I, S>(mut self, prefixes: I) -> Self
+        where I: IntoIterator<Item = S>, S: Into<String>
+    {
+        self.allowed_urls.extend(prefixes.into_iter().map(Into::into));
+        self
+    }
+
+    /// The responses to a call's requests, as they're parsed
+    pub fn parse_requests<S>(&self, requests: S) -> ResponseStream
+        where S: Stream<Item = Result<ParseRequest, Status>> + Send + Unpin + 'static
+    {
+        let (tx, rx) = mpsc::channel(CHANNEL_SIZE);
+        tokio::spawn(forward_requests(requests, self.clone(), tx));
+
+        ResponseStream(rx)
+    }
+
+    /// The url, normalized, if it's remote and allowed
+    fn check_url(&self, url: &str) -> Result<String, Status> {
+        let parsed = reqwest::Url::parse(url)
+            .map_err(|err| Status::invalid_argument(format!("invalid url {:?}: {}", url, err)))?;
+        if !REMOTE_SCHEMES.contains(&parsed.scheme()) {
+            return Err(Status::invalid_argument(format!("{} urls can't be parsed, only {}", parsed.scheme(), REMOTE_SCHEMES.join(", "))));
+        }
+
+        let url = parsed.into_string();
+        if !is_allowed(&self.allowed_urls, &url) {
+            return Err(Status::permission_denied(format!("{} isn't under an allowed url", url)));
+        }
+
+        Ok(url)
+    }
+}
+
+#[tonic::async_trait]
+impl Parser for ParseService {
+    type ParseStream = ResponseStream;
+
+    async fn parse(&self, request: Request<Streaming<ParseRequest>>) -> Result<Response<ResponseStream>, Status> {
+        Ok(Response::new(self.parse_requests(request.into_inner())))
+    }
+}
+
+/// The responses of a call, see `ParseService::parse_requests`
+#[derive(Debug)]
+pub struct ResponseStream(mpsc::Receiver<Result<ParseResponse, Status>>);
+
+impl Stream for ResponseStream {
+    type Item = Result<ParseResponse, Status>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.0.poll_recv(cx)
+    }
+}
+
+type Responses = mpsc::Sender<Result<ParseResponse, Status>>;
+
+/// Starts the parse on the first request, then passes it the chunks after
+async fn forward_requests<S>(mut requests: S, service: ParseService, tx: Responses)
+    where S: Stream<Item = Result<ParseRequest, Status>> + Unpin
+{
+    let first = match poll_fn(|cx| Pin::new(&mut requests).poll_next(cx)).await {
+        Some(Ok(request)) => request,
+        Some(Err(status)) => {
+            let _ = tx.send(Err(status)).await;
+            return;
+        },
+        None => return,
+    };
+
+    let options = service.options.clone();
+    match first.input {
+        Some(Input::Url(url)) => {
+            let url = match service.check_url(&url) {
+                Ok(url) => url,
+                Err(status) => {
+                    let _ = tx.send(Err(status)).await;
+                    return;
+                },
+            };
+
+            let allowed_urls = service.allowed_urls.clone();
+            tokio::task::spawn_blocking(move || {
+                match open_url(&url, allowed_urls) {
+                    Ok(grants) => send_grants(grants, options, &tx),
+                    Err(err) => {
+                        let _ = tx.blocking_send(Err(Status::invalid_argument(err.to_string())));
+                    },
+                }
+            });
+        },
+        Some(Input::Chunk(chunk)) => {
+            let format = detect_format(&chunk);
+            if format == Format::Zip {
+                let _ = tx.send(Err(Status::invalid_argument("a zip archive can't be parsed as it's streamed; send its url"))).await;
+                return;
+            }
+
+            let (chunk_tx, chunk_rx) = mpsc::channel(CHANNEL_SIZE);
+            let parse_tx = tx.clone();
+            tokio::task::spawn_blocking(move || {
+                let rdr = ChunkReader { chunk, pos: 0, rx: chunk_rx };
+                let grants = if format == Format::Gzip {
+                    PatentGrants::from_gzip_reader(rdr)
+                } else {
+                    let rdr: Box<dyn Read + Send> = Box::new(rdr);
+                    PatentGrants::from_reader(BufReader::new(rdr))
+                };
+                send_grants(grants, options, &parse_tx);
+            });
+
+            // until the client's done, or the parse has stopped
+            while let Some(request) = poll_fn(|cx| Pin::new(&mut requests).poll_next(cx)).await {
+                let chunk = match request {
+                    Ok(ParseRequest { input: Some(Input::Chunk(chunk)) }) => chunk,
+                    Ok(_) => {
+                        let _ = tx.send(Err(Status::invalid_argument("only chunks can follow a chunk"))).await;
+                        break;
+                    },
+                    Err(status) => {
+                        let _ = tx.send(Err(status)).await;
+                        break;
+                    },
+                };
+                if chunk_tx.send(chunk).await.is_err() {
+                    break;
+                }
+            }
+        },
+        None => {
+            let _ = tx.send(Err(Status::invalid_argument("expected a chunk or a url"))).await;
+        },
+    }
+}
+
+/// Whether url is under one of the prefixes, see `ParseService::allow_urls`
+fn is_allowed(prefixes: &[String], url: &str) -> bool {
+    prefixes.iter().any(|prefix| match url.strip_prefix(prefix.as_str()) {
+        Some(rest) => prefix.ends_with('/') || rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    })
+}
+
+/// url is one `check_url` let through. A redirect is only followed to a
+/// url that's allowed too; otherwise it's an error.
+fn open_url(url: &str, allowed_urls: Vec<String>) -> Result<PatentGrants<InputReader>, Error> {
+    #[cfg(feature = "object-store")]
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return PatentGrants::from_url(url);
+    }
+
+    let redirects = reqwest::RedirectPolicy::custom(move |attempt| {
+        if attempt.previous().len() >= MAX_REDIRECTS {
+            attempt.too_many_redirects()
+        } else if is_allowed(&allowed_urls, attempt.url().as_str()) {
+            attempt.follow()
+        } else {
+            attempt.stop()
+        }
+    });
+    let client = reqwest::Client::builder()
+        .redirect(redirects)
+        .build()
+        .context(Fetch)?;
+
+    PatentGrants::from_seekable(HttpReader::with_client(client, url)?)
+}
+
+/// Until the input's done, or the client's gone
+fn send_grants(grants: PatentGrants<InputReader>, options: ParserOptions, tx: &Responses) {
+    for patent_grant in grants.lenient().options(options) {
+        let result = match patent_grant {
+            Ok(patent_grant) => parse_response::Result::Grant(proto::PatentGrant::from(patent_grant)),
+            Err(err) => {
+                let (start, end) = match err {
+                    Error::Document { start, end, .. } => (start, end),
+                    _ => (0, 0),
+                };
+                parse_response::Result::Error(ParseError { message: err.to_string(), start, end })
+            },
+        };
+
+        if tx.blocking_send(Ok(ParseResponse { result: Some(result) })).is_err() {
+            return;
+        }
+    }
+}
+
+/// The chunks of a call, as one input, for the parse's blocking thread
+struct ChunkReader {
+    // the one being read
+    chunk: Vec<u8>,
+    pos: usize,
+    rx: mpsc::Receiver<Vec<u8>>,
+}
+
+impl Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.chunk.len() {
+            match self.rx.blocking_recv() {
+                Some(chunk) => {
+                    self.chunk = chunk;
+                    self.pos = 0;
+                },
+                None => return Ok(0),
+            }
+        }
+
+        let read = (self.chunk.len() - self.pos).min(buf.len());
+        buf[..read].copy_from_slice(&self.chunk[self.pos..self.pos + read]);
+        self.pos += read;
+
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::io::Write;
+
+    const FIXTURE: &[u8] = include_bytes!("../tests/fixtures/grants.xml");
+
+    struct Requests(VecDeque<Result<ParseRequest, Status>>);
+
+    impl Stream for Requests {
+        type Item = Result<ParseRequest, Status>;
+
+        fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            Poll::Ready(self.0.pop_front())
+        }
+    }
+
+    fn chunks(input: &[u8]) -> Requests {
+        Requests(input.chunks(1000)
+            .map(|chunk| Ok(ParseRequest { input: Some(Input::Chunk(chunk.to_vec())) }))
+            .collect())
+    }
+
+    async fn parse(requests: Requests) -> Vec<Result<ParseResponse, Status>> {
+        parse_with(ParseService::new(), requests).await
+    }
+
+    async fn parse_with(service: ParseService, requests: Requests) -> Vec<Result<ParseResponse, Status>> {
+        let mut responses = service.parse_requests(requests);
+        let mut collected = Vec::new();
+        while let Some(response) = poll_fn(|cx| Pin::new(&mut responses).poll_next(cx)).await {
+            collected.push(response);
+        }
+
+        collected
+    }
+
+    fn doc_numbers(responses: &[Result<ParseResponse, Status>]) -> Vec<String> {
+        responses.iter()
+            .filter_map(|response| match &response.as_ref().unwrap().result {
+                Some(parse_response::Result::Grant(grant)) => Some(grant.us_bibliographic_data_grant.as_ref()?.publication_reference.as_ref()?.doc_number.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_parse_chunks() {
+        let expected: Vec<_> = PatentGrants::from_reader(FIXTURE)
+            .map(|grant| grant.unwrap().us_bibliographic_data_grant.publication_reference.doc_number)
+            .collect();
+
+        let responses = parse(chunks(FIXTURE)).await;
+        assert_eq!(doc_numbers(&responses), expected);
+
+        let mut gzipped = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzipped.write_all(FIXTURE).unwrap();
+        let responses = parse(chunks(&gzipped.finish().unwrap())).await;
+        assert_eq!(doc_numbers(&responses), expected);
+
+        // the first document truncated: an error, then the rest
+        let second = FIXTURE.windows(5).skip(1).position(|window| window == b"<?xml").unwrap() + 1;
+        let input = [&FIXTURE[..second / 2], &FIXTURE[second..]].concat();
+        let responses = parse(chunks(&input)).await;
+        match &responses[0].as_ref().unwrap().result {
+            Some(parse_response::Result::Error(err)) => assert_eq!((err.start, err.end), (0, second as u64 / 2)),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+        assert_eq!(doc_numbers(&responses), expected[1..]);
+    }
+
+    #[tokio::test]
+    async fn test_parse_invalid() {
+        let responses = parse(chunks(b"PK\x03\x04 not really a zip")).await;
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].as_ref().unwrap_err().code(), tonic::Code::InvalidArgument);
+
+        let responses = parse(Requests(vec![Ok(ParseRequest { input: None })].into())).await;
+        assert_eq!(responses[0].as_ref().unwrap_err().code(), tonic::Code::InvalidArgument);
+    }
+
+    /// Serves body to every request at the returned base url
+    fn serve(body: &'static [u8]) -> String {
+        serve_response("200 OK".to_string(), body)
+    }
+
+    /// Redirects every request to location, at the returned base url
+    fn serve_redirect(location: &str) -> String {
+        serve_response(format!("302 Found\r\nLocation: {}", location), b"")
+    }
+
+    /// status, followed by any headers
+    fn serve_response(status: String, body: &'static [u8]) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                    match stream.read(&mut buf) {
+                        Ok(0) | Err(_) => break,
+                        Ok(read) => request.extend_from_slice(&buf[..read]),
+                    }
+                }
+                let _ = write!(stream, "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", status, body.len())
+                    .and_then(|_| stream.write_all(body));
+            }
+        });
+
+        base_url
+    }
+
+    fn url(url: &str) -> Requests {
+        Requests(vec![Ok(ParseRequest { input: Some(Input::Url(url.to_string())) })].into())
+    }
+
+    #[tokio::test]
+    async fn test_parse_urls() {
+        let base_url = serve(FIXTURE);
+        let grants_url = format!("{}/grants/ipg190101.xml", base_url);
+
+        // none allowed by default
+        let responses = parse(url(&grants_url)).await;
+        assert_eq!(responses[0].as_ref().unwrap_err().code(), tonic::Code::PermissionDenied);
+
+        let service = ParseService::new().allow_urls([format!("{}/grants", base_url)]);
+        let responses = parse_with(service.clone(), url(&grants_url)).await;
+        assert_eq!(doc_numbers(&responses).len(), 3);
+        for other in [format!("{}/grantsx/ipg190101.xml", base_url), format!("{}/grants/../ipg190101.xml", base_url)] {
+            let responses = parse_with(service.clone(), url(&other)).await;
+            assert_eq!(responses[0].as_ref().unwrap_err().code(), tonic::Code::PermissionDenied, "{}", other);
+        }
+
+        // never a local file, even if allowed
+        let service = ParseService::new().allow_urls(["file:///"]);
+        let responses = parse_with(service, url("file:///etc/passwd")).await;
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].as_ref().unwrap_err().code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_parse_redirects() {
+        let grants_url = format!("{}/grants/ipg190101.xml", serve(FIXTURE));
+        let redirect_url = format!("{}/grants/ipg190101.xml", serve_redirect(&grants_url));
+
+        // followed only if the url it redirects to is allowed too
+        let service = ParseService::new().allow_urls([redirect_url.clone()]);
+        let responses = parse_with(service, url(&redirect_url)).await;
+        assert_eq!(responses.len(), 1);
+        let status = responses[0].as_ref().unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert!(status.message().contains("redirects to"), "{}", status.message());
+
+        let service = ParseService::new().allow_urls([redirect_url.clone(), grants_url]);
+        let responses = parse_with(service, url(&redirect_url)).await;
+        assert_eq!(doc_numbers(&responses).len(), 3);
+    }
+}
@@ -0,0 +1,313 @@
+//! A sidecar index of where each grant is in a bulk file, by doc-number, so
+//! one grant can be read without parsing the file up to it.
+//!
+//! The index is text, one grant per line: its doc-number (see
+//! `docnum::grant_number`), and the start and end of its document in bytes,
+//! tab-separated. The first line holds the indexed file's length and
+//! modification time (in nanoseconds since the epoch), to tell when the
+//! index is stale: a corrected file of the same length is indexed again.
+
+use snafu::{OptionExt, ResultExt};
+use std::collections::HashMap;
+use std::fs::{File, Metadata};
+use std::io::{BufRead, BufReader, BufWriter, Read as IoRead, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::data::PatentGrant;
+use crate::deserialize::PatentGrants;
+use crate::docnum::grant_number;
+use crate::error::{Error, IndexFormat, IndexWrite, Read};
+use crate::options::{ParserOptions, Projection};
+use crate::split::Documents;
+
+const HEADER: &str = "uspto-index";
+
+/// Byte ranges of the grants in a bulk file (plain xml), by doc-number
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GrantIndex {
+    // of the indexed file
+    file_len: u64,
+    file_modified: u64,
+    // in file order
+    entries: Vec<(String, Range<u64>)>,
+    by_doc_number: HashMap<String, usize>,
+}
+
+impl GrantIndex {
+    /// Scans the file once, reading only each document's
+    /// publication-reference. Documents that fail to parse aren't indexed.
+    pub fn build<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::open(path)
+            .context(Read)?;
+        let metadata = file.metadata()
+            .context(Read)?;
+        let options = ParserOptions::new().projection(Projection::NONE);
+
+        let mut index = GrantIndex {
+            file_len: metadata.len(),
+            file_modified: modified(&metadata),
+            ..GrantIndex::default()
+        };
+        let mut offset = 0;
+        for document in Documents::from_reader(BufReader::new(file)) {
+            let document = document?;
+            let start = offset;
+            offset += document.len() as u64;
+
+            if let Some(Ok(patent_grant)) = PatentGrants::with_options(&document[..], options.clone()).next() {
+                let doc_number = &patent_grant.us_bibliographic_data_grant.publication_reference.doc_number;
+                index.insert(doc_number, start..offset);
+            }
+        }
+
+        Ok(index)
+    }
+
+    /// Reads an index written by `write`
+    pub fn read<R: BufRead>(rdr: R) -> Result<Self, Error> {
+        let mut lines = rdr.lines();
+
+        let header = lines.next()
+            .unwrap_or_else(|| Ok(String::new()))
+            .context(Read)?;
+        let (file_len, file_modified) = match header.split('\t').collect::<Vec<_>>()[..] {
+            [HEADER, file_len, file_modified] => (parse_number(file_len, 1)?, parse_number(file_modified, 1)?),
+            _ => return IndexFormat { line: 1usize, src: format!("expected {:?} header, with the file's length and modification time", HEADER) }.fail(),
+        };
+
+        let mut index = GrantIndex {
+            file_len,
+            file_modified,
+            ..GrantIndex::default()
+        };
+        for (i, line) in lines.enumerate() {
+            let line = line.context(Read)?;
+            let line_number = i + 2;
+
+            match line.split('\t').collect::<Vec<_>>()[..] {
+                [doc_number, start, end] => {
+                    let range = parse_number(start, line_number)?..parse_number(end, line_number)?;
+                    index.insert(doc_number, range);
+                },
+                _ => return IndexFormat { line: line_number, src: "expected doc-number, start and end".to_string() }.fail(),
+            }
+        }
+
+        Ok(index)
+    }
+
+    /// Writes the index, see the module docs for its format
+    pub fn write<W: Write>(&self, wtr: W) -> Result<(), Error> {
+        let mut wtr = BufWriter::new(wtr);
+
+        writeln!(wtr, "{}\t{}\t{}", HEADER, self.file_len, self.file_modified)
+            .context(IndexWrite)?;
+        for (doc_number, range) in &self.entries {
+            writeln!(wtr, "{}\t{}\t{}", doc_number, range.start, range.end)
+                .context(IndexWrite)?;
+        }
+
+        wtr.flush()
+            .context(IndexWrite)
+    }
+
+    /// The byte range of the grant's document; the doc-number in any form
+    /// `docnum::grant_number` takes, e.g. `D0838001` or `D838001`
+    pub fn get(&self, doc_number: &str) -> Option<Range<u64>> {
+        self.by_doc_number.get(&grant_number(doc_number))
+            .map(|&i| self.entries[i].1.clone())
+    }
+
+    /// Number of grants indexed
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The doc-numbers indexed, in file order
+    pub fn doc_numbers(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(doc_number, _)| doc_number.as_str())
+    }
+
+    /// Length of the file indexed
+    pub fn file_len(&self) -> u64 {
+        self.file_len
+    }
+
+    /// Whether the index is of the file as it is now: the same length, and
+    /// modified at the same time
+    pub fn is_current(&self, metadata: &Metadata) -> bool {
+        self.file_len == metadata.len() && self.file_modified == modified(metadata)
+    }
+
+    /// The sidecar index's path for a bulk file, e.g. `ipg230103.xml.idx`
+    pub fn sidecar_path<P: AsRef<Path>>(path: P) -> PathBuf {
+        let mut sidecar = path.as_ref().as_os_str().to_owned();
+        sidecar.push(".idx");
+        sidecar.into()
+    }
+
+    // a doc-number seen again (e.g. a grant repeated in a file) keeps its
+    // first range
+    fn insert(&mut self, doc_number: &str, range: Range<u64>) {
+        let doc_number = grant_number(doc_number);
+        if self.by_doc_number.contains_key(&doc_number) {
+            return;
+        }
+
+        self.by_doc_number.insert(doc_number.clone(), self.entries.len());
+        self.entries.push((doc_number, range));
+    }
+}
+
+/// in nanoseconds since the epoch, 0 if the platform doesn't keep it
+fn modified(metadata: &Metadata) -> u64 {
+    metadata.modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |since| since.as_nanos() as u64)
+}
+
+fn parse_number(text: &str, line: usize) -> Result<u64, Error> {
+    text.parse()
+        .ok()
+        .context(IndexFormat { line, src: format!("invalid number {:?}", text) })
+}
+
+/// A bulk file read through its GrantIndex
+pub struct IndexedGrants {
+    file: File,
+    index: GrantIndex,
+    options: ParserOptions,
+}
+
+impl IndexedGrants {
+    /// Opens a bulk file (plain xml) for reading grants by doc-number, e.g.
+    /// `IndexedGrants::open(path)?.get("11223344")`.
+    ///
+    /// Uses the sidecar index (see `GrantIndex::sidecar_path`) if there is one
+    /// for the file as it is (see `GrantIndex::is_current`); otherwise indexes
+    /// the file, and writes the sidecar if it can (a read-only directory isn't
+    /// an error).
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .context(Read)?;
+        let metadata = file.metadata()
+            .context(Read)?;
+
+        let sidecar = GrantIndex::sidecar_path(path);
+        let index = match File::open(&sidecar).map(|sidecar| GrantIndex::read(BufReader::new(sidecar))) {
+            Ok(Ok(index)) if index.is_current(&metadata) => index,
+            _ => {
+                let index = GrantIndex::build(path)?;
+                if let Ok(sidecar) = File::create(&sidecar) {
+                    let _ = index.write(sidecar);
+                }
+                index
+            },
+        };
+
+        Ok(IndexedGrants {
+            file,
+            index,
+            options: ParserOptions::default(),
+        })
+    }
+
+    /// See `PatentGrants::with_options`
+    pub fn options(mut self, options: ParserOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn index(&self) -> &GrantIndex {
+        &self.index
+    }
+
+    /// Reads and parses only the grant's document; None if it isn't indexed
+    pub fn get(&mut self, doc_number: &str) -> Option<Result<PatentGrant, Error>> {
+        let range = self.index.get(doc_number)?;
+
+        let mut document = vec![0; (range.end - range.start) as usize];
+        let read = self.file.seek(SeekFrom::Start(range.start))
+            .and_then(|_| self.file.read_exact(&mut document))
+            .context(Read);
+        if let Err(err) = read {
+            return Some(Err(err));
+        }
+
+        PatentGrants::with_options(&document[..], self.options.clone()).next()
+    }
+}
+
+impl PatentGrants<BufReader<File>> {
+    /// See `IndexedGrants::open`
+    pub fn open_indexed<P: AsRef<Path>>(path: P) -> Result<IndexedGrants, Error> {
+        IndexedGrants::open(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/grants.xml");
+
+    #[test]
+    fn test_grant_index() {
+        let index = GrantIndex::build(FIXTURE_PATH).unwrap();
+        assert_eq!(index.len(), 3);
+
+        let mut written = Vec::new();
+        index.write(&mut written).unwrap();
+        assert!(written.starts_with(b"uspto-index\t"));
+        assert_eq!(GrantIndex::read(&written[..]).unwrap(), index);
+
+        assert!(GrantIndex::read(&b"uspto-index\t10\t0\nD838001\t0\n"[..]).is_err());
+        // from before the modification time was kept
+        assert!(GrantIndex::read(&b"uspto-index\t10\n"[..]).is_err());
+        assert!(GrantIndex::read(&b"other\n"[..]).is_err());
+    }
+
+    #[test]
+    fn test_open_indexed() {
+        let path = std::env::temp_dir().join(format!("uspto-index-{}.xml", std::process::id()));
+        std::fs::copy(FIXTURE_PATH, &path).unwrap();
+
+        let expected: Vec<_> = PatentGrants::from_path(FIXTURE_PATH).unwrap()
+            .map(|pg| pg.unwrap().us_bibliographic_data_grant.publication_reference.doc_number)
+            .collect();
+
+        let mut grants = IndexedGrants::open(&path).unwrap();
+        assert!(GrantIndex::sidecar_path(&path).exists());
+        // in reverse, and without leading zeros
+        for doc_number in expected.iter().rev() {
+            let patent_grant = grants.get(&grant_number(doc_number)).unwrap().unwrap();
+            assert_eq!(&patent_grant.us_bibliographic_data_grant.publication_reference.doc_number, doc_number);
+        }
+        assert!(grants.get("1").is_none());
+
+        // from the sidecar this time
+        let grants = PatentGrants::open_indexed(&path).unwrap();
+        assert_eq!(grants.index().doc_numbers().count(), expected.len());
+
+        // a corrected file of the same length is indexed again
+        let corrected = std::fs::read_to_string(&path).unwrap().replacen("<doc-number>10165721<", "<doc-number>10165722<", 1);
+        let file = File::create(&path).unwrap();
+        (&file).write_all(corrected.as_bytes()).unwrap();
+        file.set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(60)).unwrap();
+        drop(file);
+        let mut grants = IndexedGrants::open(&path).unwrap();
+        assert!(grants.get("10165721").is_none());
+        assert!(grants.get("10165722").unwrap().is_ok());
+
+        std::fs::remove_file(GrantIndex::sidecar_path(&path)).unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+}
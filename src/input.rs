@@ -0,0 +1,258 @@
+//! Constructors for PatentGrants over the files USPTO distributes, so callers
+//! don't need to set up the readers themselves.
+
+use flate2::read::MultiGzDecoder;
+#[cfg(feature = "zip")]
+use snafu::OptionExt;
+use snafu::ResultExt;
+use std::fs::File;
+use std::io::{BufReader, Read as IoRead, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::deserialize::PatentGrants;
+use crate::error::{Error, Read};
+use crate::split::Documents;
+#[cfg(feature = "zip")]
+use crate::error::{Zip, ZipNoXml, ZipCompression};
+#[cfg(not(feature = "zip"))]
+use crate::error::ZipDisabled;
+
+/// The reader for PatentGrants opened from a path, or a compressed reader
+pub type InputReader = BufReader<Box<dyn IoRead + Send>>;
+
+pub(crate) const GZIP_MAGIC: &[u8] = b"\x1f\x8b";
+pub(crate) const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+/// What an input is, by its first bytes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Format {
+    Xml,
+    Gzip,
+    Zip,
+}
+
+/// From at least the first `ZIP_MAGIC.len()` bytes of the input, or all of
+/// it if shorter; anything not compressed is taken as xml
+pub(crate) fn detect_format(magic: &[u8]) -> Format {
+    if magic.starts_with(GZIP_MAGIC) {
+        Format::Gzip
+    } else if magic.starts_with(ZIP_MAGIC) {
+        Format::Zip
+    } else {
+        Format::Xml
+    }
+}
+
+impl PatentGrants<InputReader> {
+    /// Opens plain xml, gzipped xml, or a zip archive (see from_zip_path),
+    /// detected from the file's first bytes rather than its extension.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::open(path)
+            .context(Read)?;
+
+        Self::from_seekable(file)
+    }
+
+    /// As from_path, from any reader that can seek (e.g. an
+    /// `store::ObjectReader`), which a zip archive needs
+    pub fn from_seekable<R: IoRead + Seek + Send + 'static>(mut rdr: R) -> Result<Self, Error> {
+        let mut magic = Vec::with_capacity(ZIP_MAGIC.len());
+        rdr.by_ref().take(ZIP_MAGIC.len() as u64).read_to_end(&mut magic)
+            .context(Read)?;
+        rdr.seek(SeekFrom::Start(0))
+            .context(Read)?;
+
+        match detect_format(&magic) {
+            Format::Gzip => Ok(Self::from_gzip_reader(rdr)),
+            #[cfg(feature = "zip")]
+            Format::Zip => Self::from_zip_reader(rdr),
+            #[cfg(not(feature = "zip"))]
+            Format::Zip => ZipDisabled.fail(),
+            Format::Xml => {
+                let rdr: Box<dyn IoRead + Send> = Box::new(rdr);
+                Ok(PatentGrants::from_reader(BufReader::new(rdr)))
+            },
+        }
+    }
+
+    /// Decompresses gzipped xml as it's read; concatenated gzip members (e.g.
+    /// from appending weeks together) are read as one stream.
+    pub fn from_gzip_reader<R: IoRead + Send + 'static>(rdr: R) -> Self {
+        let rdr: Box<dyn IoRead + Send> = Box::new(MultiGzDecoder::new(rdr));
+        PatentGrants::from_reader(BufReader::new(rdr))
+    }
+
+    /// Streams the grants xml (`ipg*.xml`, or `pg*.xml` before 2005) inside a weekly zip archive,
+    /// decompressing as it's read; nothing is extracted to disk.
+    #[cfg(feature = "zip")]
+    pub fn from_zip_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::open(path)
+            .context(Read)?;
+
+        Self::from_zip_reader(file)
+    }
+
+    /// As from_zip_path, from any reader that can seek
+    #[cfg(feature = "zip")]
+    pub fn from_zip_reader<R: IoRead + Seek + Send + 'static>(rdr: R) -> Result<Self, Error> {
+        let mut archive = zip::ZipArchive::new(rdr)
+            .context(Zip)?;
+
+        let index = (0..archive.len())
+            .find(|i| match archive.name_for_index(*i) {
+                Some(Ok(name)) => is_grants_xml(&name),
+                _ => false,
+            })
+            .context(ZipNoXml)?;
+
+        // the entry borrows the archive, so only take where its data is, and
+        // read that from the reader directly
+        let (data_start, compressed_size, compression) = {
+            let entry = archive.by_index_raw(index)
+                .context(Zip)?;
+            let data_start = entry.data_start()
+                .context(ZipNoXml)?;
+
+            (data_start, entry.compressed_size(), entry.compression())
+        };
+
+        let mut rdr = archive.into_inner();
+        rdr.seek(SeekFrom::Start(data_start))
+            .context(Read)?;
+        let data = rdr.take(compressed_size);
+
+        let rdr: Box<dyn IoRead + Send> = match compression {
+            zip::CompressionMethod::Stored => Box::new(data),
+            zip::CompressionMethod::Deflated => Box::new(flate2::read::DeflateDecoder::new(data)),
+            method => return ZipCompression { method: method.to_string() }.fail(),
+        };
+
+        Ok(PatentGrants::from_reader(BufReader::new(rdr)))
+    }
+}
+
+impl Documents<InputReader> {
+    /// The raw documents of plain xml, gzipped xml, or a zip archive, as
+    /// `PatentGrants::from_path` opens them
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Ok(Documents::from_reader(PatentGrants::from_path(path)?.into_reader()))
+    }
+}
+
+/// e.g. `ipg190101.xml`, `ipgb20190101.xml` for bibliographic files, or
+/// `pg020101.xml` for 2002 to 2004
+#[cfg(feature = "zip")]
+fn is_grants_xml(name: &str) -> bool {
+    let file_name = name.rsplit('/').next().unwrap_or(name).to_lowercase();
+
+    (file_name.starts_with("ipg") || file_name.starts_with("pg")) && file_name.ends_with(".xml")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+    #[cfg(feature = "zip")]
+    use zip::write::SimpleFileOptions;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/grants.xml");
+    const FIXTURE_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/grants.xml");
+
+    fn doc_numbers(grants: PatentGrants<InputReader>) -> Vec<String> {
+        grants
+            .map(|pg| pg.unwrap().us_bibliographic_data_grant.publication_reference.doc_number)
+            .collect()
+    }
+
+    fn fixture_doc_numbers() -> Vec<String> {
+        PatentGrants::from_reader(FIXTURE.as_bytes())
+            .map(|pg| pg.unwrap().us_bibliographic_data_grant.publication_reference.doc_number)
+            .collect()
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_from_gzip_reader() {
+        // two members, split between documents
+        let second = FIXTURE.match_indices("<?xml").nth(1).unwrap().0;
+        let mut data = gzip(&FIXTURE.as_bytes()[..second]);
+        data.extend(gzip(&FIXTURE.as_bytes()[second..]));
+
+        let grants = PatentGrants::from_gzip_reader(std::io::Cursor::new(data));
+        assert_eq!(doc_numbers(grants), fixture_doc_numbers());
+    }
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(doc_numbers(PatentGrants::from_path(FIXTURE_PATH).unwrap()), fixture_doc_numbers());
+
+        // detected by content, not extension
+        let path = std::env::temp_dir().join(format!("uspto-gzip-{}.xml", std::process::id()));
+        std::fs::write(&path, gzip(FIXTURE.as_bytes())).unwrap();
+        assert_eq!(doc_numbers(PatentGrants::from_path(&path).unwrap()), fixture_doc_numbers());
+        std::fs::remove_file(&path).unwrap();
+
+        #[cfg(feature = "zip")]
+        {
+            let path = std::env::temp_dir().join(format!("uspto-zip-{}.bin", std::process::id()));
+            write_zip(&path, zip::CompressionMethod::Deflated);
+            assert_eq!(doc_numbers(PatentGrants::from_path(&path).unwrap()), fixture_doc_numbers());
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_documents_from_path() {
+        let path = std::env::temp_dir().join(format!("uspto-documents-{}.xml.gz", std::process::id()));
+        std::fs::write(&path, gzip(FIXTURE.as_bytes())).unwrap();
+
+        let documents = Documents::from_path(&path).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(documents.concat(), FIXTURE.as_bytes());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "zip")]
+    fn write_zip(path: &Path, compression: zip::CompressionMethod) {
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        let options = SimpleFileOptions::default().compression_method(compression);
+
+        // not every entry is the grants xml
+        zip.start_file("README.txt", options).unwrap();
+        zip.write_all(b"not this one").unwrap();
+        zip.start_file("ipg190101.xml", options).unwrap();
+        zip.write_all(FIXTURE.as_bytes()).unwrap();
+        zip.finish().unwrap();
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn test_from_zip_path() {
+        let sequential = fixture_doc_numbers();
+
+        for (i, compression) in [zip::CompressionMethod::Deflated, zip::CompressionMethod::Stored].iter().enumerate() {
+            let path = std::env::temp_dir().join(format!("uspto-zip-{}-{}.zip", std::process::id(), i));
+            write_zip(&path, *compression);
+
+            assert_eq!(doc_numbers(PatentGrants::from_zip_path(&path).unwrap()), sequential);
+
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn test_is_grants_xml() {
+        assert!(is_grants_xml("ipg190101.xml"));
+        assert!(is_grants_xml("ipgb20190101.xml"));
+        assert!(is_grants_xml("ipg190101/IPG190101.XML"));
+        assert!(is_grants_xml("pg020101.xml"));
+        assert!(!is_grants_xml("ipg190101.dtd"));
+        assert!(!is_grants_xml("README.txt"));
+    }
+}
@@ -0,0 +1,239 @@
+//! Parsed grants kept in an embedded key-value store (sled), for looking
+//! grants up by doc-number, application number, or publication date without
+//! parsing bulk files again.
+//!
+//! Grants are serialized with bincode, keyed by their doc-number zero-padded
+//! as in the xml (see `docnum::padded_grant_number`), so `D838001` finds
+//! `D0838001`. Two secondary indexes, each a tree of keys ending in the
+//! doc-number, map application numbers and publication dates to grants.
+//!
+//! A week's grants are appended as it comes out. A grant inserted again
+//! (e.g. from a corrected file) replaces the one stored, and its index
+//! entries; each insert is a transaction over all three trees.
+
+use chrono::NaiveDate;
+use sled::transaction::{ConflictableTransactionError, TransactionError};
+use sled::{Transactional, Tree};
+use snafu::ResultExt;
+use std::path::Path;
+
+use crate::data::{date_text, Date, PatentGrant};
+use crate::docnum::{application_number, padded_grant_number};
+use crate::error::{Error, KvEncode, KvStore};
+
+pub struct GrantStore {
+    db: sled::Db,
+    grants: Tree,
+    applications: Tree,
+    dates: Tree,
+}
+
+impl GrantStore {
+    /// Opens the store at path, creating it if needed
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let db = sled::open(path)
+            .context(KvStore)?;
+        let tree = |name: &str| db.open_tree(name).context(KvStore);
+
+        Ok(GrantStore {
+            grants: tree("grants")?,
+            applications: tree("applications")?,
+            dates: tree("dates")?,
+            db,
+        })
+    }
+
+    /// Stores the grant, replacing any with its doc-number. True if one was
+    /// replaced.
+    pub fn insert(&self, patent_grant: &PatentGrant) -> Result<bool, Error> {
+        let key = grant_key(patent_grant);
+        let value = bincode::serialize(patent_grant)
+            .context(KvEncode)?;
+        let new_keys = index_keys(patent_grant, &key);
+
+        let replaced = (&self.grants, &self.applications, &self.dates)
+            .transaction(|(grants, applications, dates)| {
+                let old = grants.insert(key.as_bytes(), value.as_slice())?;
+
+                if let Some(old) = &old {
+                    let old: PatentGrant = bincode::deserialize(old)
+                        .map_err(ConflictableTransactionError::Abort)?;
+                    let (application, date) = index_keys(&old, &key);
+                    applications.remove(application)?;
+                    if let Some(date) = date {
+                        dates.remove(date)?;
+                    }
+                }
+
+                let (application, date) = &new_keys;
+                applications.insert(application.as_slice(), &[])?;
+                if let Some(date) = date {
+                    dates.insert(date.as_slice(), &[])?;
+                }
+
+                Ok(old.is_some())
+            })
+            .map_err(|err| match err {
+                TransactionError::Abort(err) => Error::KvEncode { source: err },
+                TransactionError::Storage(err) => Error::KvStore { source: err },
+            })?;
+
+        Ok(replaced)
+    }
+
+    /// Stores the grants (e.g. a week's), and flushes them to disk. Returns
+    /// how many were new.
+    pub fn append<I: IntoIterator<Item = PatentGrant>>(&self, patent_grants: I) -> Result<usize, Error> {
+        let mut added = 0;
+        for patent_grant in patent_grants {
+            if !self.insert(&patent_grant)? {
+                added += 1;
+            }
+        }
+
+        self.flush()?;
+
+        Ok(added)
+    }
+
+    /// Waits for everything stored to be written to disk
+    pub fn flush(&self) -> Result<(), Error> {
+        self.db.flush()
+            .context(KvStore)?;
+
+        Ok(())
+    }
+
+    pub fn get(&self, doc_number: &str) -> Result<Option<PatentGrant>, Error> {
+        self.grants.get(padded_grant_number(doc_number))
+            .context(KvStore)?
+            .map(|value| bincode::deserialize(&value).context(KvEncode))
+            .transpose()
+    }
+
+    pub fn contains(&self, doc_number: &str) -> Result<bool, Error> {
+        self.grants.contains_key(padded_grant_number(doc_number))
+            .context(KvStore)
+    }
+
+    /// Grants from the application, in any of the forms
+    /// `docnum::application_number` takes (usually only one, but e.g. a
+    /// reissue is from the same application as the patent it reissues)
+    pub fn by_application(&self, application: &str) -> Result<Vec<PatentGrant>, Error> {
+        let mut prefix = application_key(application).into_bytes();
+        prefix.push(0);
+
+        self.doc_numbers(self.applications.scan_prefix(prefix))
+            .collect()
+    }
+
+    /// Grants published from `from` to `to` (inclusive), by date, then
+    /// doc-number
+    pub fn published_between(&self, from: NaiveDate, to: NaiveDate) -> impl Iterator<Item = Result<PatentGrant, Error>> + '_ {
+        let start = from.format("%Y%m%d").to_string().into_bytes();
+        let mut end = to.format("%Y%m%d").to_string().into_bytes();
+        // past every key of the date, which are followed by a 0
+        end.push(1);
+
+        self.doc_numbers(self.dates.range(start..end))
+    }
+
+    /// The grants of index entries, by the doc-number at the end of their
+    /// keys
+    fn doc_numbers<'a, I>(&'a self, entries: I) -> impl Iterator<Item = Result<PatentGrant, Error>> + 'a
+    where
+        I: Iterator<Item = sled::Result<(sled::IVec, sled::IVec)>> + 'a,
+    {
+        entries.filter_map(move |entry| {
+            let key = match entry {
+                Ok((key, _)) => key,
+                Err(err) => return Some(Err(Error::KvStore { source: err })),
+            };
+            let doc_number = key.rsplit(|b| *b == 0).next().unwrap_or_default();
+
+            // an entry is removed along with its grant, so it's always there
+            self.grants.get(doc_number)
+                .context(KvStore)
+                .and_then(|value| value.map(|value| bincode::deserialize(&value).context(KvEncode)).transpose())
+                .transpose()
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.grants.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.grants.is_empty()
+    }
+}
+
+fn grant_key(patent_grant: &PatentGrant) -> String {
+    padded_grant_number(&patent_grant.us_bibliographic_data_grant.publication_reference.doc_number)
+}
+
+fn application_key(application: &str) -> String {
+    application_number(application, None).unwrap_or_else(|| application.trim().to_string())
+}
+
+/// The grant's keys in the application and (if it's dated) date indexes
+fn index_keys(patent_grant: &PatentGrant, key: &str) -> (Vec<u8>, Option<Vec<u8>>) {
+    let dg = &patent_grant.us_bibliographic_data_grant;
+    let entry = |prefix: String| format!("{}\0{}", prefix, key).into_bytes();
+
+    let application = entry(application_key(&dg.application_reference.doc_number));
+    let date: &Date = &dg.publication_reference.date;
+    // a missing date is "", or with typed dates the default
+    let date = if *date == Date::default() { None } else { Some(entry(date_text(date))) };
+
+    (application, date)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PatentGrants;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/grants.xml");
+
+    fn grants() -> Vec<PatentGrant> {
+        PatentGrants::from_reader(FIXTURE.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_grant_store() {
+        let path = std::env::temp_dir().join(format!("uspto-kvstore-{}", std::process::id()));
+
+        {
+            let store = GrantStore::open(&path).unwrap();
+            assert!(store.is_empty());
+            assert_eq!(store.append(grants()).unwrap(), 3);
+            // a week appended again replaces what's stored
+            assert_eq!(store.append(grants()).unwrap(), 0);
+            assert_eq!(store.len(), 3);
+        }
+
+        let store = GrantStore::open(&path).unwrap();
+        let grant = store.get("D838001").unwrap().unwrap();
+        assert_eq!(grant.us_bibliographic_data_grant.publication_reference.doc_number, "D0838001");
+        assert!(store.contains("10165721").unwrap());
+        assert!(store.get("10000000").unwrap().is_none());
+
+        let application = grants()[0].us_bibliographic_data_grant.application_reference.doc_number.clone();
+        let by_application = store.by_application(&application).unwrap();
+        assert_eq!(by_application.len(), 1);
+        assert_eq!(by_application[0].us_bibliographic_data_grant.publication_reference.doc_number, "10165721");
+
+        let day = |d| NaiveDate::from_ymd_opt(2019, 1, d).unwrap();
+        let published = store.published_between(day(1), day(1))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(published.len(), 3);
+        assert_eq!(store.published_between(day(2), day(8)).count(), 0);
+
+        drop(store);
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+}
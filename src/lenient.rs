@@ -0,0 +1,140 @@
+//! Lenient parsing: a malformed document is reported, and parsing resumes at
+//! the next document.
+//!
+//! Documents are split out by their `<?xml` declarations (see `split`) before
+//! they're parsed, so an error can't leave the reader partway through one.
+
+use snafu::IntoError;
+use std::io::BufRead;
+
+use crate::data::PatentGrant;
+use crate::deserialize::PatentGrants;
+use crate::options::ParserOptions;
+use crate::error::{Document, Error};
+use crate::progress::{BytesRead, Observer, ProgressObserver};
+use crate::split::Documents;
+
+/// Like PatentGrants, but a document that fails to parse is an
+/// `Error::Document` with its byte range in the input, and the grants after
+/// it are still read. See `PatentGrants::lenient`.
+pub struct LenientPatentGrants<B: BufRead> {
+    documents: Documents<B>,
+    // bytes read before the next document
+    offset: u64,
+    options: ParserOptions,
+    observer: Option<Observer>,
+}
+
+impl<B: BufRead> LenientPatentGrants<B> {
+    pub fn from_reader(b: B) -> Self {
+        LenientPatentGrants {
+            documents: Documents::from_reader(b),
+            offset: 0,
+            options: ParserOptions::default(),
+            observer: None,
+        }
+    }
+
+    /// See `PatentGrants::strict`
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.options.check_consistency = strict;
+        self
+    }
+
+    /// See `PatentGrants::with_options`
+    pub fn options(mut self, options: ParserOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Tells observer of the progress after each document, and once the
+    /// input's done
+    pub fn observe<O: ProgressObserver + Send + 'static>(mut self, observer: O) -> Self {
+        self.observer = Some(Observer::new(observer));
+        self
+    }
+
+    /// After `observe`: reports the input's position, as a ProgressReader
+    /// counts it, of total_bytes, rather than the bytes of documents parsed
+    pub fn input_progress(mut self, bytes_read: BytesRead, total_bytes: Option<u64>) -> Self {
+        if let Some(observer) = self.observer.as_mut() {
+            observer.input(bytes_read, total_bytes);
+        }
+        self
+    }
+}
+
+impl<B: BufRead> Iterator for LenientPatentGrants<B> {
+    type Item = Result<PatentGrant, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // a read error isn't in any document, and ends the input
+            let document = match self.documents.next() {
+                Some(Ok(document)) => document,
+                Some(Err(err)) => return Some(Err(err)),
+                None => {
+                    if let Some(observer) = self.observer.as_mut() {
+                        observer.finish(self.offset);
+                    }
+                    return None;
+                },
+            };
+
+            let start = self.offset;
+            self.offset += document.len() as u64;
+
+            let res = match PatentGrants::with_options(&document[..], self.options.clone()).next() {
+                Some(Ok(patent_grant)) => Ok(patent_grant),
+                Some(Err(err)) => Err(Document { start, end: self.offset }.into_error(Box::new(err))),
+                // e.g. only a declaration
+                None => continue,
+            };
+            if let Some(observer) = self.observer.as_mut() {
+                observer.document(self.offset, res.is_err());
+            }
+
+            return Some(res);
+        }
+    }
+}
+
+impl<B: BufRead> PatentGrants<B> {
+    /// Switches to lenient parsing (see LenientPatentGrants); call before
+    /// reading any grants.
+    pub fn lenient(self) -> LenientPatentGrants<B> {
+        let options = self.parser_options().clone();
+
+        LenientPatentGrants::from_reader(self.into_reader())
+            .options(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/grants.xml");
+
+    #[test]
+    fn test_lenient_resumes() {
+        let second = FIXTURE.match_indices("<?xml").nth(1).unwrap().0;
+        // truncate the first document
+        let input = format!("{}{}", &FIXTURE[..second / 2], &FIXTURE[second..]);
+
+        let results: Vec<_> = PatentGrants::from_reader(input.as_bytes()).lenient().collect();
+        assert_eq!(results.len(), 3);
+        match &results[0] {
+            Err(Error::Document { start, end, .. }) => assert_eq!((*start, *end), (0, second as u64 / 2)),
+            other => panic!("expected a document error, got {:?}", other),
+        }
+
+        let doc_numbers: Vec<_> = results[1..].iter()
+            .map(|pg| pg.as_ref().unwrap().us_bibliographic_data_grant.publication_reference.doc_number.clone())
+            .collect();
+        let expected: Vec<_> = PatentGrants::from_reader(&FIXTURE.as_bytes()[second..])
+            .map(|pg| pg.unwrap().us_bibliographic_data_grant.publication_reference.doc_number)
+            .collect();
+        assert_eq!(doc_numbers, expected);
+    }
+}
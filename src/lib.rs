@@ -1,13 +1,101 @@
+mod application;
+mod assignment;
+#[cfg(feature = "assignment-api")]
+pub mod assignment_api;
+mod aps;
+pub mod aggregate;
+#[cfg(feature = "bulkdata")]
+pub mod bulkdata;
+pub mod borrowed;
+pub mod census;
+pub mod checkpoint;
+pub mod claims;
+pub mod correction;
+pub mod country;
+pub mod cpc;
 pub mod data;
 mod deserialize;
+pub mod description;
+pub mod diff;
+pub mod docnum;
+pub mod entities;
 pub mod error;
+pub mod export;
+#[cfg(feature = "bulkdata")]
 pub mod fetch;
-mod serialize;
+pub mod fingerprint;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod index;
+#[cfg(feature = "sled")]
+pub mod kvstore;
+mod input;
+mod lenient;
+pub mod maintfee;
+pub mod maths;
+pub mod metrics;
+#[cfg(feature = "mmap")]
+mod mmap;
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(feature = "csv")]
+pub mod office_actions;
+pub mod options;
+mod output;
+mod patdoc;
+#[cfg(feature = "patentsview")]
+pub mod patentsview;
+#[cfg(feature = "peds")]
+pub mod peds;
+pub mod progress;
+#[cfg(feature = "protobuf")]
+pub mod proto;
+#[cfg(feature = "ptab")]
+pub mod ptab;
+pub mod schema;
+#[cfg(feature = "search")]
+pub mod search;
+pub mod render;
+pub mod serialize;
+pub mod split;
+mod st96;
+#[cfg(feature = "object-store")]
+pub mod store;
+#[cfg(feature = "async")]
+mod stream;
+#[cfg(feature = "bulkdata")]
+pub mod sync;
+pub mod tables;
+pub mod text;
+pub mod trademark;
+pub mod uspc;
 pub mod util;
+mod visitor;
 
-pub use crate::deserialize::PatentGrants;
+pub use crate::application::PatentApplications;
+pub use crate::assignment::Assignments;
+pub use crate::aps::ApsGrants;
+pub use crate::borrowed::{LendingIterator, PatentGrantRef, PatentGrantRefs};
+pub use crate::checkpoint::{Checkpoint, CheckpointedGrants};
+pub use crate::correction::Corrections;
+pub use crate::deserialize::{FilteredPatentGrants, PatentGrantBiblios, PatentGrants};
 pub use crate::error::Error;
-pub use crate::serialize::PatentOutput;
+pub use crate::index::{GrantIndex, IndexedGrants};
+pub use crate::input::InputReader;
+pub use crate::lenient::LenientPatentGrants;
+#[cfg(feature = "mmap")]
+pub use crate::mmap::{MappedGrants, MappedGrantsIter};
+pub use crate::options::{Decoding, ParserOptions, Projection, SkippedElements};
+#[cfg(feature = "rayon")]
+pub use crate::parallel::ParallelPatentGrants;
+pub use crate::schema::SchemaVersion;
+pub use crate::st96::St96Grants;
+pub use crate::trademark::{TrademarkApplications, TrademarkAssignments};
+pub use crate::output::PatentOutput;
+pub use crate::progress::{ProgressObserver, ProgressReader};
+#[cfg(feature = "async")]
+pub use crate::stream::PatentGrantStream;
+pub use crate::visitor::{parse_with_visitor, GrantVisitor};
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,362 @@
+//! Maintenance fee events (MaintFeeEvents_*.txt): every payment, reminder,
+//! expiry and reinstatement recorded for a patent's maintenance fees, so
+//! whether a grant has lapsed can be joined to it.
+//!
+//! The file is fixed-width text, a line per event:
+//!
+//! ```text
+//! patent number, application number, entity status (Y small, N
+//! undiscounted, M micro), filing date, grant date, event date, event code
+//! ```
+//!
+//! No field has spaces in it, so fields are read as whatever is between the
+//! spaces; how wide the patent number column is has changed over the years.
+//! Dates are YYYYMMDD, as `data::Date`.
+
+use snafu::ResultExt;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::BufRead;
+
+use crate::data::{date_text, Date};
+use crate::docnum::padded_grant_number;
+use crate::error::{Error, MaintFeeFormat, Read};
+use crate::util::FromText;
+
+/// Maintenance fee event codes, those for fees, expiry and entity status.
+/// Any other code is kept as is in `Other`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EventCode {
+    /// Payment of maintenance fee, 4th year, large entity
+    M1551,
+    /// 8th year, large entity
+    M1552,
+    /// 12th year, large entity
+    M1553,
+    /// Surcharge for late payment, large entity
+    M1554,
+    /// Payment of maintenance fee, 4th year, small entity
+    M2551,
+    M2552,
+    M2553,
+    /// Surcharge for late payment, small entity
+    M2554,
+    /// Payment of maintenance fee, 4th year, micro entity
+    M3551,
+    M3552,
+    M3553,
+    /// Surcharge for late payment, micro entity
+    M3554,
+    /// Maintenance fee reminder mailed
+    Reminder,
+    /// Patent expired for failure to pay maintenance fees (`EXP.`)
+    Expired,
+    /// Patent reinstated after maintenance fee payment confirmed (`EXPX`)
+    Reinstated,
+    /// Petition related to maintenance fees filed
+    PetitionFiled,
+    /// Petition related to maintenance fees granted
+    PetitionGranted,
+    /// Petition related to maintenance fees dismissed
+    PetitionDismissed,
+    /// Entity status set to small (`SMAL`)
+    SmallEntity,
+    /// Entity status set to undiscounted (`BIG.`)
+    Undiscounted,
+    /// Entity status set to micro (`MICR`)
+    MicroEntity,
+    /// Payer number assigned
+    PayerAssigned,
+    /// Payer number de-assigned
+    PayerDeassigned,
+    Other(String),
+}
+
+impl EventCode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            EventCode::M1551 => "M1551",
+            EventCode::M1552 => "M1552",
+            EventCode::M1553 => "M1553",
+            EventCode::M1554 => "M1554",
+            EventCode::M2551 => "M2551",
+            EventCode::M2552 => "M2552",
+            EventCode::M2553 => "M2553",
+            EventCode::M2554 => "M2554",
+            EventCode::M3551 => "M3551",
+            EventCode::M3552 => "M3552",
+            EventCode::M3553 => "M3553",
+            EventCode::M3554 => "M3554",
+            EventCode::Reminder => "REM.",
+            EventCode::Expired => "EXP.",
+            EventCode::Reinstated => "EXPX",
+            EventCode::PetitionFiled => "PMFP",
+            EventCode::PetitionGranted => "PMFG",
+            EventCode::PetitionDismissed => "PMFD",
+            EventCode::SmallEntity => "SMAL",
+            EventCode::Undiscounted => "BIG.",
+            EventCode::MicroEntity => "MICR",
+            EventCode::PayerAssigned => "ASPN",
+            EventCode::PayerDeassigned => "RMPN",
+            EventCode::Other(code) => code,
+        }
+    }
+
+    /// For a fee payment, the years after grant it's for: 4, 8 or 12
+    pub fn fee_year(&self) -> Option<u8> {
+        match self {
+            EventCode::M1551 | EventCode::M2551 | EventCode::M3551 => Some(4),
+            EventCode::M1552 | EventCode::M2552 | EventCode::M3552 => Some(8),
+            EventCode::M1553 | EventCode::M2553 | EventCode::M3553 => Some(12),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for EventCode {
+    fn from(code: &str) -> Self {
+        match code.trim() {
+            "M1551" => EventCode::M1551,
+            "M1552" => EventCode::M1552,
+            "M1553" => EventCode::M1553,
+            "M1554" => EventCode::M1554,
+            "M2551" => EventCode::M2551,
+            "M2552" => EventCode::M2552,
+            "M2553" => EventCode::M2553,
+            "M2554" => EventCode::M2554,
+            "M3551" => EventCode::M3551,
+            "M3552" => EventCode::M3552,
+            "M3553" => EventCode::M3553,
+            "M3554" => EventCode::M3554,
+            "REM." => EventCode::Reminder,
+            "EXP." => EventCode::Expired,
+            "EXPX" => EventCode::Reinstated,
+            "PMFP" => EventCode::PetitionFiled,
+            "PMFG" => EventCode::PetitionGranted,
+            "PMFD" => EventCode::PetitionDismissed,
+            "SMAL" => EventCode::SmallEntity,
+            "BIG." => EventCode::Undiscounted,
+            "MICR" => EventCode::MicroEntity,
+            "ASPN" => EventCode::PayerAssigned,
+            "RMPN" => EventCode::PayerDeassigned,
+            other => EventCode::Other(other.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for EventCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The fee discount the patent holder claimed, as of the event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntityStatus {
+    Undiscounted,
+    Small,
+    Micro,
+}
+
+/// A line of the file
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaintFeeEvent {
+    /// as in the file, see `docnum::padded_grant_number` to match grants
+    pub patent_number: String,
+    pub application_number: String,
+    pub entity_status: EntityStatus,
+    pub filing_date: Date,
+    pub grant_date: Date,
+    pub event_date: Date,
+    pub event_code: EventCode,
+}
+
+/// Iterator over the events in a MaintFeeEvents file
+pub struct MaintFeeEvents<B: BufRead> {
+    rdr: B,
+    line_number: usize,
+    eof: bool,
+}
+
+impl<B: BufRead> MaintFeeEvents<B> {
+    pub fn from_reader(rdr: B) -> Self {
+        MaintFeeEvents {
+            rdr,
+            line_number: 0,
+            eof: false,
+        }
+    }
+
+    /// The next line with anything on it
+    fn read_line(&mut self) -> Result<Option<String>, Error> {
+        loop {
+            let mut line = Vec::new();
+            let read = self.rdr.read_until(b'\n', &mut line)
+                .context(Read)?;
+            if read == 0 {
+                return Ok(None);
+            }
+            self.line_number += 1;
+
+            let line = String::from_utf8_lossy(&line);
+            if !line.trim().is_empty() {
+                return Ok(Some(line.into_owned()));
+            }
+        }
+    }
+}
+
+impl<B: BufRead> Iterator for MaintFeeEvents<B> {
+    type Item = Result<MaintFeeEvent, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.eof {
+            return None;
+        }
+
+        match self.read_line() {
+            Ok(Some(line)) => Some(parse_event(&line, self.line_number)),
+            Ok(None) => {
+                self.eof = true;
+                None
+            },
+            Err(err) => {
+                // stop after a read error
+                self.eof = true;
+                Some(Err(err))
+            },
+        }
+    }
+}
+
+fn parse_event(line: &str, line_number: usize) -> Result<MaintFeeEvent, Error> {
+    let fields = line.split_whitespace().collect::<Vec<_>>();
+    let (patent_number, application_number, entity, filing_date, grant_date, event_date, event_code) = match fields[..] {
+        [a, b, c, d, e, f, g] => (a, b, c, d, e, f, g),
+        _ => return MaintFeeFormat { line: line_number, src: format!("expected 7 fields, found {}", fields.len()) }.fail(),
+    };
+
+    let entity_status = match entity {
+        "N" => EntityStatus::Undiscounted,
+        "Y" => EntityStatus::Small,
+        "M" => EntityStatus::Micro,
+        other => return MaintFeeFormat { line: line_number, src: format!("invalid entity status {:?}", other) }.fail(),
+    };
+    let date = |text: &str, name: &str| {
+        Date::from_text(text.to_string(), name.as_bytes())
+            .map_err(|err| Error::MaintFeeFormat { line: line_number, src: err.to_string() })
+    };
+
+    Ok(MaintFeeEvent {
+        patent_number: patent_number.to_string(),
+        application_number: application_number.to_string(),
+        entity_status,
+        filing_date: date(filing_date, "filing date")?,
+        grant_date: date(grant_date, "grant date")?,
+        event_date: date(event_date, "event date")?,
+        event_code: EventCode::from(event_code),
+    })
+}
+
+/// Where a patent's maintenance fees stand, after all its events
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MaintenanceStatus {
+    /// The last fee paid (4, 8 or 12 years after grant), if any
+    pub fee_year_paid: Option<u8>,
+    /// When it expired for failure to pay, unless it's been reinstated since
+    pub expired: Option<Date>,
+}
+
+impl MaintenanceStatus {
+    pub fn is_expired(&self) -> bool {
+        self.expired.is_some()
+    }
+}
+
+/// Each patent's status, keyed by its padded grant number (as grants'
+/// doc-numbers are in the xml). Events are applied by date, whatever order
+/// they're in.
+pub fn maintenance_statuses<I>(events: I) -> HashMap<String, MaintenanceStatus>
+where
+    I: IntoIterator<Item = MaintFeeEvent>,
+{
+    let mut by_patent: HashMap<String, Vec<MaintFeeEvent>> = HashMap::new();
+    for event in events {
+        by_patent.entry(padded_grant_number(&event.patent_number))
+            .or_default()
+            .push(event);
+    }
+
+    by_patent.into_iter()
+        .map(|(patent_number, mut events)| {
+            // stable, so events of the same day stay in file order
+            events.sort_by_key(|event| date_text(&event.event_date));
+
+            let mut status = MaintenanceStatus::default();
+            for event in events {
+                match event.event_code {
+                    EventCode::Expired => status.expired = Some(event.event_date),
+                    EventCode::Reinstated => status.expired = None,
+                    ref code => if let Some(year) = code.fee_year() {
+                        status.fee_year_paid = status.fee_year_paid.max(Some(year));
+                    },
+                }
+            }
+
+            (patent_number, status)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EVENTS: &str = "\
+10165721 15123456 N 20170301 20190101 20220615 M1551
+04287053 06218343 Y 19800721 19810901 19850301 M2551
+
+04287053 06218343 Y 19800721 19810901 19891101 EXP.
+D0838001 29612345 N 20170801 20190101 20190102 ASPN
+04287053 06218343 Y 19800721 19810901 19891001 M2552
+04287053 06218343 Y 19800721 19810901 19900115 EXPX
+05000001 07123456 Y 19890101 19910319 19950601 XYZ9
+05000001 07123456 Y 19890101 19910319 19950801 EXP.
+";
+
+    #[test]
+    fn test_maint_fee_events() {
+        let events = MaintFeeEvents::from_reader(EVENTS.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(events.len(), 8);
+        assert_eq!(events[0].patent_number, "10165721");
+        assert_eq!(events[0].application_number, "15123456");
+        assert_eq!(events[0].entity_status, EntityStatus::Undiscounted);
+        assert_eq!(date_text(&events[0].event_date), "20220615");
+        assert_eq!(events[0].event_code, EventCode::M1551);
+        assert_eq!(events[0].event_code.fee_year(), Some(4));
+        assert_eq!(events[2].event_code.as_str(), "EXP.");
+        assert_eq!(events[6].event_code, EventCode::Other("XYZ9".to_string()));
+
+        let statuses = maintenance_statuses(events);
+        assert_eq!(statuses["10165721"].fee_year_paid, Some(4));
+        assert!(!statuses["10165721"].is_expired());
+        // the 8th year fee paid late, and reinstated
+        assert_eq!(statuses["04287053"].fee_year_paid, Some(8));
+        assert!(!statuses["04287053"].is_expired());
+        assert!(statuses["05000001"].is_expired());
+        assert_eq!(statuses["D0838001"], MaintenanceStatus::default());
+    }
+
+    #[test]
+    fn test_maint_fee_events_invalid() {
+        let mut events = MaintFeeEvents::from_reader("10165721 15123456 N 20170301\n".as_bytes());
+        let err = events.next().unwrap().unwrap_err();
+        assert!(err.to_string().contains("line 1"));
+
+        let mut events = MaintFeeEvents::from_reader("\n10165721 15123456 X 20170301 20190101 20220615 M1551\n".as_bytes());
+        let err = events.next().unwrap().unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+    }
+}
@@ -0,0 +1,376 @@
+//! Formulae (`maths`, holding MathML) in paragraphs and claims, kept as
+//! MathML and optionally converted to LaTeX. See `ParserOptions::formulae`.
+//!
+//! A formula's text is still part of its paragraph's (or claim-text's) text,
+//! flattened, as it is without capturing formulae.
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::fmt;
+use std::sync::Arc;
+
+/// Converts a formula's MathML (the `math` element, as in `Formula::mathml`)
+/// to LaTeX; None if it can't.
+pub trait LatexConverter: Send + Sync {
+    fn to_latex(&self, mathml: &str) -> Option<String>;
+}
+
+/// The converter given to `ParserOptions::latex`
+#[derive(Clone)]
+pub(crate) struct Latex(pub(crate) Arc<dyn LatexConverter>);
+
+impl fmt::Debug for Latex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Latex(..)")
+    }
+}
+
+/// Converts the presentation MathML in grants: rows, scripts, fractions,
+/// roots, fences, under/over scripts and tokens. None for a formula with
+/// anything else (e.g. tables, or content MathML).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BasicLatex;
+
+impl LatexConverter for BasicLatex {
+    fn to_latex(&self, mathml: &str) -> Option<String> {
+        let node = parse(mathml)?;
+
+        Some(latex(&node)?.trim().to_string())
+    }
+}
+
+/// a MathML element, or text
+#[derive(Debug)]
+enum Node {
+    Element { name: String, attrs: Vec<(String, String)>, children: Vec<Node> },
+    Text(String),
+}
+
+impl Node {
+    fn element(name: String, attrs: Vec<(String, String)>) -> Self {
+        Node::Element { name, attrs, children: Vec::new() }
+    }
+
+    fn attr(&self, key: &str) -> Option<&str> {
+        match self {
+            Node::Element { attrs, .. } => attrs.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str()),
+            Node::Text(_) => None,
+        }
+    }
+
+    /// the text of a token element (mi, mn, mo, mtext)
+    fn text(&self) -> String {
+        match self {
+            Node::Element { children, .. } => children.iter().map(Node::text).collect(),
+            Node::Text(text) => text.clone(),
+        }
+    }
+}
+
+/// the single top-level element of mathml, as a tree
+fn parse(mathml: &str) -> Option<Node> {
+    let mut rdr = Reader::from_str(mathml);
+    rdr.trim_text(true);
+    let mut buf = Vec::new();
+
+    // elements still open, outermost first; the first collects the top level
+    let mut open = vec![Node::element(String::new(), Vec::new())];
+
+    loop {
+        let node = match rdr.read_event(&mut buf).ok()? {
+            Event::Start(ref e) => {
+                let element = Node::element(rdr.decode(e.local_name()).into_owned(), attrs(e, &rdr)?);
+                open.push(element);
+                buf.clear();
+                continue;
+            },
+            Event::Empty(ref e) => Node::element(rdr.decode(e.local_name()).into_owned(), attrs(e, &rdr)?),
+            Event::Text(ref e) => Node::Text(e.unescape_and_decode(&rdr).ok()?),
+            Event::End(_) => {
+                if open.len() == 1 {
+                    return None;
+                }
+                open.pop()?
+            },
+            Event::Eof => break,
+            _ => {
+                buf.clear();
+                continue;
+            },
+        };
+
+        match open.last_mut()? {
+            Node::Element { children, .. } => children.push(node),
+            Node::Text(_) => return None,
+        }
+        buf.clear();
+    }
+
+    match open.pop()? {
+        Node::Element { mut children, .. } if open.is_empty() && children.len() == 1 => children.pop(),
+        _ => None,
+    }
+}
+
+fn attrs(start: &quick_xml::events::BytesStart, rdr: &Reader<&[u8]>) -> Option<Vec<(String, String)>> {
+    start.attributes()
+        .map(|attr| {
+            let attr = attr.ok()?;
+            Some((rdr.decode(attr.key).into_owned(), attr.unescape_and_decode_value(rdr).ok()?))
+        })
+        .collect()
+}
+
+fn latex(node: &Node) -> Option<String> {
+    let (name, children) = match node {
+        Node::Element { name, children, .. } => (name.as_str(), children),
+        Node::Text(text) => return Some(escape(text)),
+    };
+
+    // the nth child, converted
+    let arg = |n: usize| children.get(n).and_then(latex);
+
+    let latex = match name {
+        "math" | "mrow" | "mstyle" | "mpadded" | "mphantom" => row(children)?,
+        "mi" => identifier(&node.text()),
+        "mn" => escape(&node.text()),
+        "mo" => operator(&node.text()),
+        "mtext" => format!("\\text{{{}}}", escape(&node.text())),
+        "mspace" => String::new(),
+        "msup" => format!("{}^{{{}}}", base(&arg(0)?), arg(1)?),
+        "msub" => format!("{}_{{{}}}", base(&arg(0)?), arg(1)?),
+        "msubsup" => format!("{}_{{{}}}^{{{}}}", base(&arg(0)?), arg(1)?, arg(2)?),
+        "mfrac" => format!("\\frac{{{}}}{{{}}}", arg(0)?, arg(1)?),
+        "msqrt" => format!("\\sqrt{{{}}}", row(children)?),
+        "mroot" => format!("\\sqrt[{}]{{{}}}", arg(1)?, arg(0)?),
+        "mfenced" => {
+            let open = node.attr("open").unwrap_or("(");
+            let close = node.attr("close").unwrap_or(")");
+            let separator = node.attr("separators").unwrap_or(",").trim();
+
+            let inner = children.iter()
+                .map(latex)
+                .collect::<Option<Vec<_>>>()?
+                .join(&operator(separator));
+            format!("\\left{}{}\\right{}", fence(open), inner, fence(close))
+        },
+        "munder" | "mover" | "munderover" => {
+            let base = arg(0)?;
+            let (under, over) = match name {
+                "munder" => (arg(1), None),
+                "mover" => (None, arg(1)),
+                _ => (arg(1), arg(2)),
+            };
+
+            if is_big_operator(&base) {
+                let under = under.map(|under| format!("_{{{}}}", under)).unwrap_or_default();
+                let over = over.map(|over| format!("^{{{}}}", over)).unwrap_or_default();
+                format!("{}{}{}", base, under, over)
+            } else {
+                let base = match under {
+                    Some(under) => format!("\\underset{{{}}}{{{}}}", under, base),
+                    None => base,
+                };
+                match over {
+                    Some(over) => format!("\\overset{{{}}}{{{}}}", over, base),
+                    None => base,
+                }
+            }
+        },
+        _ => return None,
+    };
+
+    Some(latex)
+}
+
+/// the children, one after the other
+fn row(children: &[Node]) -> Option<String> {
+    let mut latex = String::new();
+    for child in children {
+        append(&mut latex, &self::latex(child)?);
+    }
+
+    Some(latex)
+}
+
+/// appends piece, after a space if latex ends with a command that piece
+/// would otherwise run into (e.g. `\alpha` and `x`)
+fn append(latex: &mut String, piece: &str) {
+    let name_start = latex.trim_end_matches(|c: char| c.is_ascii_alphabetic()).len();
+    let ends_with_command = name_start < latex.len() && latex[..name_start].ends_with('\\');
+    if ends_with_command && piece.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        latex.push(' ');
+    }
+    latex.push_str(piece);
+}
+
+/// a script's base, in braces unless it's one character or command
+fn base(latex: &str) -> String {
+    let is_command = latex.starts_with('\\') && latex[1..].chars().all(|c| c.is_ascii_alphabetic());
+    if latex.chars().count() == 1 || is_command {
+        latex.to_string()
+    } else {
+        format!("{{{}}}", latex)
+    }
+}
+
+fn is_big_operator(latex: &str) -> bool {
+    matches!(latex, "\\sum" | "\\prod" | "\\int" | "\\oint" | "\\lim" | "\\max" | "\\min" | "\\bigcup" | "\\bigcap")
+}
+
+/// a name of more than one letter is upright text, e.g. `sin` or `max`
+fn identifier(text: &str) -> String {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => greek(c).map(str::to_string).unwrap_or_else(|| escape(text)),
+        _ => match text {
+            "sin" | "cos" | "tan" | "log" | "ln" | "exp" | "lim" | "max" | "min" | "det" => format!("\\{}", text),
+            _ => format!("\\mathrm{{{}}}", escape(text)),
+        },
+    }
+}
+
+fn operator(text: &str) -> String {
+    let latex = match text {
+        // invisible times, function application and separator
+        "\u{2062}" | "\u{2061}" | "\u{2063}" => "",
+        "\u{2212}" => "-",
+        "\u{d7}" => "\\times",
+        "\u{b7}" | "\u{22c5}" => "\\cdot",
+        "\u{f7}" => "\\div",
+        "\u{b1}" => "\\pm",
+        "\u{2213}" => "\\mp",
+        "\u{2264}" => "\\leq",
+        "\u{2265}" => "\\geq",
+        "\u{2260}" => "\\neq",
+        "\u{2248}" => "\\approx",
+        "\u{2261}" => "\\equiv",
+        "\u{221d}" => "\\propto",
+        "\u{2192}" => "\\to",
+        "\u{21d2}" => "\\Rightarrow",
+        "\u{2208}" => "\\in",
+        "\u{2211}" => "\\sum",
+        "\u{220f}" => "\\prod",
+        "\u{222b}" => "\\int",
+        "\u{222e}" => "\\oint",
+        "\u{221e}" => "\\infty",
+        "\u{2202}" => "\\partial",
+        "\u{2207}" => "\\nabla",
+        "\u{222a}" => "\\cup",
+        "\u{2229}" => "\\cap",
+        "\u{2032}" => "'",
+        "lim" | "max" | "min" => return format!("\\{}", text),
+        _ => return identifier(text),
+    };
+
+    latex.to_string()
+}
+
+fn fence(delimiter: &str) -> &str {
+    match delimiter {
+        "" => ".",
+        "{" => "\\{",
+        "}" => "\\}",
+        "\u{2329}" | "\u{27e8}" => "\\langle",
+        "\u{232a}" | "\u{27e9}" => "\\rangle",
+        "|" | "(" | ")" | "[" | "]" => delimiter,
+        _ => ".",
+    }
+}
+
+fn greek(c: char) -> Option<&'static str> {
+    let name = match c {
+        '\u{3b1}' => "\\alpha",
+        '\u{3b2}' => "\\beta",
+        '\u{3b3}' => "\\gamma",
+        '\u{3b4}' => "\\delta",
+        '\u{3b5}' => "\\epsilon",
+        '\u{3b6}' => "\\zeta",
+        '\u{3b7}' => "\\eta",
+        '\u{3b8}' => "\\theta",
+        '\u{3b9}' => "\\iota",
+        '\u{3ba}' => "\\kappa",
+        '\u{3bb}' => "\\lambda",
+        '\u{3bc}' | '\u{b5}' => "\\mu",
+        '\u{3bd}' => "\\nu",
+        '\u{3be}' => "\\xi",
+        '\u{3c0}' => "\\pi",
+        '\u{3c1}' => "\\rho",
+        '\u{3c3}' => "\\sigma",
+        '\u{3c4}' => "\\tau",
+        '\u{3c5}' => "\\upsilon",
+        '\u{3c6}' => "\\phi",
+        '\u{3c7}' => "\\chi",
+        '\u{3c8}' => "\\psi",
+        '\u{3c9}' => "\\omega",
+        '\u{393}' => "\\Gamma",
+        '\u{394}' => "\\Delta",
+        '\u{398}' => "\\Theta",
+        '\u{39b}' => "\\Lambda",
+        '\u{39e}' => "\\Xi",
+        '\u{3a0}' => "\\Pi",
+        '\u{3a3}' => "\\Sigma",
+        '\u{3a5}' => "\\Upsilon",
+        '\u{3a6}' => "\\Phi",
+        '\u{3a8}' => "\\Psi",
+        '\u{3a9}' => "\\Omega",
+        _ => return None,
+    };
+
+    Some(name)
+}
+
+/// escapes LaTeX's special characters in text
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '#' | '$' | '%' | '&' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            },
+            '\\' => escaped.push_str("\\backslash "),
+            '~' => escaped.push_str("\\sim "),
+            '^' => escaped.push_str("\\hat{}"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_latex(mathml: &str) -> Option<String> {
+        BasicLatex.to_latex(mathml)
+    }
+
+    #[test]
+    fn test_basic_latex() {
+        // as in the fixture
+        assert_eq!(
+            to_latex(r#"<math overflow="scroll"><mrow><mi>F</mi><mo>=</mo><mrow><mi>m</mi><mo>&#x2062;</mo><mi>a</mi></mrow></mrow></math>"#).unwrap(),
+            "F=ma",
+        );
+        assert_eq!(
+            to_latex("<math><msup><mi>x</mi><mn>2</mn></msup><mo>+</mo><mfrac><mn>1</mn><msub><mi>y</mi><mi>ij</mi></msub></mfrac></math>").unwrap(),
+            "x^{2}+\\frac{1}{y_{\\mathrm{ij}}}",
+        );
+        assert_eq!(
+            to_latex("<math><munderover><mo>&#x2211;</mo><mrow><mi>i</mi><mo>=</mo><mn>1</mn></mrow><mi>n</mi></munderover><msub><mi>&#x3b1;</mi><mi>i</mi></msub></math>").unwrap(),
+            "\\sum_{i=1}^{n}\\alpha_{i}",
+        );
+        assert_eq!(
+            to_latex(r#"<math><msqrt><mi>&#x3bb;</mi><mi>x</mi></msqrt><mfenced open="[" close="]"><mi>a</mi><mi>b</mi></mfenced><mroot><mn>8</mn><mn>3</mn></mroot></math>"#).unwrap(),
+            "\\sqrt{\\lambda x}\\left[a,b\\right]\\sqrt[3]{8}",
+        );
+        assert_eq!(to_latex("<math><mtext>100% of</mtext></math>").unwrap(), "\\text{100\\% of}");
+
+        // unsupported, or not MathML
+        assert!(to_latex("<math><mtable><mtr><mtd><mn>1</mn></mtd></mtr></mtable></math>").is_none());
+        assert!(to_latex("<math><mi>x</mi>").is_none());
+        assert!(to_latex("<math/><math/>").is_none());
+    }
+}
@@ -0,0 +1,253 @@
+//! Metrics of an ingestion (e.g. the cli's `sync`), in the Prometheus text
+//! format, served over HTTP at `/metrics` for a Prometheus server to scrape.
+//!
+//! Documents and bytes are counted by a `MetricsObserver` (see
+//! `LenientPatentGrants::observe`); errors, by their `Error` variant, and
+//! files, as they're exported. Alongside the counters are the rates of the
+//! parse in progress (or the last), and the export lag: how long ago the
+//! oldest file not yet exported was issued, zero when none are waiting.
+
+use chrono::{NaiveDate, Utc};
+use snafu::ResultExt;
+use std::collections::BTreeMap;
+use std::fmt::Write as FmtWrite;
+use std::io::{Read as IoRead, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+use crate::error::{Error, MetricsServer};
+use crate::progress::{Progress, ProgressObserver};
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+    documents: AtomicU64,
+    bytes: AtomicU64,
+    files: AtomicU64,
+    errors: Mutex<BTreeMap<&'static str, u64>>,
+    // documents and bytes per second
+    rates: Mutex<(f64, f64)>,
+    oldest_pending: Mutex<Option<NaiveDate>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts the documents and bytes of a parse
+    pub fn observer(self: &Arc<Self>) -> MetricsObserver {
+        MetricsObserver {
+            metrics: Arc::clone(self),
+            documents: 0,
+            bytes: 0,
+        }
+    }
+
+    /// Counts an error by its variant; a document's error by the error
+    /// within it, e.g. "Deser"
+    pub fn error(&self, err: &Error) {
+        *lock(&self.errors)
+            .entry(err.kind())
+            .or_insert(0) += 1;
+    }
+
+    pub fn file_exported(&self) {
+        self.files.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The issue date of the oldest file waiting to be exported, if any
+    pub fn pending(&self, oldest: Option<NaiveDate>) {
+        *lock(&self.oldest_pending) = oldest;
+    }
+
+    /// The metrics, in the Prometheus text format
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+
+        let mut metric = |name: &str, kind: &str, help: &str, samples: &[(String, String)]| {
+            let _ = writeln!(text, "# HELP uspto_{} {}", name, help);
+            let _ = writeln!(text, "# TYPE uspto_{} {}", name, kind);
+            for (labels, value) in samples {
+                let _ = writeln!(text, "uspto_{}{} {}", name, labels, value);
+            }
+        };
+
+        let (documents_per_second, bytes_per_second) = *lock(&self.rates);
+        let errors = lock(&self.errors).iter()
+            .map(|(error_type, count)| (format!("{{type=\"{}\"}}", error_type), count.to_string()))
+            .collect::<Vec<_>>();
+        let lag = lock(&self.oldest_pending)
+            .map_or(0, |date| (Utc::now().naive_utc() - date.and_hms_opt(0, 0, 0).unwrap()).num_seconds().max(0));
+
+        metric("documents_total", "counter", "Documents parsed", &[(String::new(), self.documents.load(Ordering::Relaxed).to_string())]);
+        metric("bytes_total", "counter", "Bytes of documents parsed", &[(String::new(), self.bytes.load(Ordering::Relaxed).to_string())]);
+        metric("errors_total", "counter", "Errors, by type", &errors);
+        metric("files_exported_total", "counter", "Files exported", &[(String::new(), self.files.load(Ordering::Relaxed).to_string())]);
+        metric("documents_per_second", "gauge", "Documents parsed per second, in the current or last parse", &[(String::new(), documents_per_second.to_string())]);
+        metric("bytes_per_second", "gauge", "Bytes parsed per second, in the current or last parse", &[(String::new(), bytes_per_second.to_string())]);
+        metric("export_lag_seconds", "gauge", "Since the issue date of the oldest file not yet exported", &[(String::new(), lag.to_string())]);
+
+        text
+    }
+
+    /// Serves the metrics on a thread of their own, at addr (e.g.
+    /// `0.0.0.0:9898`), until the process exits. Each connection gets a
+    /// thread too, so a slow client doesn't hold up the scrapes after it.
+    pub fn serve<A: ToSocketAddrs>(self: &Arc<Self>, addr: A) -> Result<thread::JoinHandle<()>, Error> {
+        let listener = TcpListener::bind(addr)
+            .context(MetricsServer)?;
+
+        Ok(self.serve_on(listener))
+    }
+
+    /// As serve, on a listener already bound (e.g. to port 0, its port then
+    /// from `TcpListener::local_addr`)
+    pub fn serve_on(self: &Arc<Self>, listener: TcpListener) -> thread::JoinHandle<()> {
+        let metrics = Arc::clone(self);
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let metrics = Arc::clone(&metrics);
+                // a client hanging up isn't the server's problem
+                thread::spawn(move || metrics.respond(stream));
+            }
+        })
+    }
+
+    fn respond(&self, mut stream: TcpStream) -> std::io::Result<()> {
+        stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+        // only the request line is needed
+        let mut request = Vec::new();
+        let mut buf = [0; 1024];
+        while !request.windows(4).any(|window| window == b"\r\n\r\n") && request.len() < 8192 {
+            let read = stream.read(&mut buf)?;
+            if read == 0 {
+                break;
+            }
+            request.extend_from_slice(&buf[..read]);
+        }
+
+        let request_line = String::from_utf8_lossy(&request);
+        let (status, body) = match request_line.split_whitespace().take(2).collect::<Vec<_>>()[..] {
+            ["GET", "/metrics"] => ("200 OK", self.render()),
+            _ => ("404 Not Found", "Not Found\n".to_string()),
+        };
+
+        write!(stream, "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, body.len(), body)?;
+        stream.flush()
+    }
+}
+
+/// A panic on another thread while it held the lock leaves the metrics as
+/// they were, which are still worth serving
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// A parse's progress, as Metrics, see `Metrics::observer`
+pub struct MetricsObserver {
+    metrics: Arc<Metrics>,
+    // counted so far
+    documents: u64,
+    bytes: u64,
+}
+
+impl ProgressObserver for MetricsObserver {
+    fn progress(&mut self, progress: &Progress) {
+        self.metrics.documents.fetch_add(progress.documents.saturating_sub(self.documents), Ordering::Relaxed);
+        self.metrics.bytes.fetch_add(progress.bytes_read.saturating_sub(self.bytes), Ordering::Relaxed);
+        self.documents = self.documents.max(progress.documents);
+        self.bytes = self.bytes.max(progress.bytes_read);
+
+        let seconds = progress.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            *lock(&self.metrics.rates) = (progress.documents as f64 / seconds, progress.bytes_read as f64 / seconds);
+        }
+    }
+
+    fn finish(&mut self, progress: &Progress) {
+        self.progress(progress);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::PatentGrants;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/grants.xml");
+
+    #[test]
+    fn test_metrics() {
+        let metrics = Arc::new(Metrics::new());
+
+        // the first document truncated
+        let second = FIXTURE.match_indices("<?xml").nth(1).unwrap().0;
+        let input = format!("{}{}", &FIXTURE[..second / 2], &FIXTURE[second..]);
+        for patent_grant in PatentGrants::from_reader(input.as_bytes()).lenient().observe(metrics.observer()) {
+            if let Err(err) = patent_grant {
+                metrics.error(&err);
+            }
+        }
+        metrics.file_exported();
+        metrics.pending(Some(Utc::now().date_naive()));
+
+        let text = metrics.render();
+        let documents = FIXTURE.matches("<?xml").count();
+        assert!(text.contains(&format!("\nuspto_documents_total {}\n", documents)), "{}", text);
+        assert!(text.contains(&format!("\nuspto_bytes_total {}\n", input.len())), "{}", text);
+        assert!(text.contains("\nuspto_errors_total{type=\"Deser\"} 1\n"), "{}", text);
+        assert!(text.contains("\nuspto_files_exported_total 1\n"), "{}", text);
+        assert!(text.contains("# TYPE uspto_export_lag_seconds gauge\n"), "{}", text);
+    }
+
+    #[test]
+    fn test_serve() {
+        let metrics = Arc::new(Metrics::new());
+        metrics.file_exported();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        metrics.serve_on(listener);
+
+        let get = |path: &str| {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+
+        let response = get("/metrics");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        assert!(response.contains("\nuspto_files_exported_total 1\n"), "{}", response);
+        assert!(get("/").starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+        // a client that never sends its request doesn't stall the others
+        let _idle = TcpStream::connect(addr).unwrap();
+        assert!(get("/metrics").starts_with("HTTP/1.1 200 OK\r\n"));
+    }
+
+    #[test]
+    fn test_poisoned() {
+        let metrics = Arc::new(Metrics::new());
+
+        // an observer panicking while it holds the lock
+        let poisoner = Arc::clone(&metrics);
+        let _ = thread::spawn(move || {
+            let _rates = poisoner.rates.lock().unwrap();
+            panic!("observer");
+        }).join();
+        assert!(metrics.rates.is_poisoned());
+
+        metrics.error(&Error::deser("test".to_string()));
+        let text = metrics.render();
+        assert!(text.contains("\nuspto_errors_total{type=\"Deser\"} 1\n"), "{}", text);
+        assert!(text.contains("\nuspto_documents_per_second 0\n"), "{}", text);
+    }
+}
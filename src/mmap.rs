@@ -0,0 +1,220 @@
+//! Random access to the grants of a bulk file, memory-mapped instead of read
+//! through BufRead.
+//!
+//! The file is indexed by its document boundaries (see `split`) when it's
+//! opened, so any grant can be parsed on its own: by position, from either
+//! end, or all in parallel.
+
+use memmap2::Mmap;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use snafu::{IntoError, ResultExt};
+use std::fs::File;
+use std::ops::Range;
+use std::path::Path;
+
+use crate::data::PatentGrant;
+use crate::deserialize::PatentGrants;
+use crate::error::{Document, Error, Read};
+use crate::options::ParserOptions;
+use crate::split::document_ranges;
+
+/// The grants of a memory-mapped bulk file (plain xml, not compressed), each
+/// parsed when it's asked for. A document that fails to parse is an
+/// `Error::Document` with its byte range in the file, as for
+/// LenientPatentGrants.
+pub struct MappedGrants {
+    mmap: Mmap,
+    ranges: Vec<Range<usize>>,
+    options: ParserOptions,
+}
+
+impl MappedGrants {
+    /// Maps the file and indexes its documents.
+    ///
+    /// The file mustn't be changed (e.g. truncated, or written to) while it's
+    /// mapped; the grants read from it would be garbled, or reading them
+    /// could crash.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::open(path)
+            .context(Read)?;
+        // see above for the file not changing while mapped
+        let mmap = unsafe { Mmap::map(&file) }
+            .context(Read)?;
+        let ranges = document_ranges(&mmap);
+
+        Ok(MappedGrants {
+            mmap,
+            ranges,
+            options: ParserOptions::default(),
+        })
+    }
+
+    /// See `PatentGrants::strict`
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.options.check_consistency = strict;
+        self
+    }
+
+    /// See `PatentGrants::with_options`
+    pub fn options(mut self, options: ParserOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Number of documents in the file
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// The nth document's byte range in the file
+    pub fn range(&self, n: usize) -> Option<Range<usize>> {
+        self.ranges.get(n).cloned()
+    }
+
+    /// The nth document's bytes, unparsed
+    pub fn document(&self, n: usize) -> Option<&[u8]> {
+        self.ranges.get(n).map(|range| &self.mmap[range.clone()])
+    }
+
+    /// Parses the nth document; None if there are only n documents. A
+    /// document without a grant (e.g. only a declaration) is an error.
+    pub fn get(&self, n: usize) -> Option<Result<PatentGrant, Error>> {
+        let range = self.ranges.get(n)?;
+
+        let res = PatentGrants::with_options(&self.mmap[range.clone()], self.options.clone())
+            .next()
+            .unwrap_or_else(|| Err(Error::deser("no grant in document".to_string())));
+
+        Some(res.map_err(|err| Document { start: range.start as u64, end: range.end as u64 }.into_error(Box::new(err))))
+    }
+
+    /// The grants in order; reversible, e.g. `grants.iter().rev()` for the
+    /// last grants first
+    pub fn iter(&self) -> MappedGrantsIter<'_> {
+        MappedGrantsIter {
+            grants: self,
+            next: 0,
+            end: self.len(),
+        }
+    }
+
+    /// The grants, parsed on the rayon thread pool; collects in order
+    #[cfg(feature = "rayon")]
+    pub fn par_iter(&self) -> impl IndexedParallelIterator<Item = Result<PatentGrant, Error>> + '_ {
+        (0..self.len())
+            .into_par_iter()
+            .map(move |n| self.get(n).expect("n < len"))
+    }
+}
+
+impl<'a> IntoIterator for &'a MappedGrants {
+    type Item = Result<PatentGrant, Error>;
+    type IntoIter = MappedGrantsIter<'a>;
+
+    fn into_iter(self) -> MappedGrantsIter<'a> {
+        self.iter()
+    }
+}
+
+/// Iterator over the grants of a MappedGrants, from either end
+pub struct MappedGrantsIter<'a> {
+    grants: &'a MappedGrants,
+    // documents left are next..end
+    next: usize,
+    end: usize,
+}
+
+impl Iterator for MappedGrantsIter<'_> {
+    type Item = Result<PatentGrant, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next == self.end {
+            return None;
+        }
+        self.next += 1;
+
+        self.grants.get(self.next - 1)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.next;
+        (len, Some(len))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.next = self.next.saturating_add(n).min(self.end);
+        self.next()
+    }
+}
+
+impl DoubleEndedIterator for MappedGrantsIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.next == self.end {
+            return None;
+        }
+        self.end -= 1;
+
+        self.grants.get(self.end)
+    }
+}
+
+impl ExactSizeIterator for MappedGrantsIter<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/grants.xml");
+    const FIXTURE_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/grants.xml");
+
+    fn doc_number(res: Result<PatentGrant, Error>) -> String {
+        res.unwrap().us_bibliographic_data_grant.publication_reference.doc_number
+    }
+
+    #[test]
+    fn test_mapped_grants() {
+        let expected: Vec<_> = PatentGrants::from_reader(FIXTURE.as_bytes()).map(doc_number).collect();
+
+        let grants = MappedGrants::open(FIXTURE_PATH).unwrap();
+        assert_eq!(grants.len(), expected.len());
+        assert_eq!(grants.iter().map(doc_number).collect::<Vec<_>>(), expected);
+        assert_eq!(grants.iter().rev().map(doc_number).collect::<Vec<_>>(), expected.iter().rev().cloned().collect::<Vec<_>>());
+        assert_eq!(doc_number(grants.get(1).unwrap()), expected[1]);
+        assert!(grants.get(expected.len()).is_none());
+        assert!(grants.document(0).unwrap().starts_with(b"<?xml"));
+
+        let mut iter = grants.iter();
+        assert_eq!(doc_number(iter.next_back().unwrap()), expected[2]);
+        assert_eq!(iter.len(), 2);
+        assert_eq!(doc_number(iter.nth(1).unwrap()), expected[1]);
+        assert!(iter.next().is_none());
+
+        #[cfg(feature = "rayon")]
+        assert_eq!(grants.par_iter().map(doc_number).collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_mapped_grants_error() {
+        // the second document is cut off
+        let second = FIXTURE.match_indices("<?xml").nth(1).unwrap().0;
+        let third = FIXTURE.match_indices("<?xml").nth(2).unwrap().0;
+        let data = format!("{}{}{}", &FIXTURE[..second], &FIXTURE[second..third - 200], &FIXTURE[third..]);
+
+        let path = std::env::temp_dir().join(format!("uspto-mmap-{}.xml", std::process::id()));
+        std::fs::write(&path, &data).unwrap();
+        let grants = MappedGrants::open(&path).unwrap();
+
+        assert_eq!(grants.len(), 3);
+        match grants.get(1) {
+            Some(Err(Error::Document { start, end, .. })) => assert_eq!((start, end), (second as u64, third as u64 - 200)),
+            res => panic!("expected a document error, got {:?}", res.map(|res| res.map(|_| ()))),
+        }
+        assert!(grants.get(2).unwrap().is_ok());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
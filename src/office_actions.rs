@@ -0,0 +1,282 @@
+//! The USPTO's Office Action Research Dataset: office actions (rejections
+//! and objections) mailed in prosecution of applications, from its csv
+//! files. Records link to applications by `app_id`, and through that to the
+//! grants issued from them (see `ApplicationRecord::application_number`).
+//!
+//! - office_actions.csv: an `OfficeAction` per action, what it rejects the
+//!   claims under, and flags for how well its form paragraphs (the standard
+//!   text examiners build rejections from) could be parsed
+//! - rejections.csv: a `Rejection` per rejection of claims within an action
+//! - citations.csv: an `ActionCitation` per prior art document an action
+//!   relies on
+//!
+//! Flags are 0 or 1 in the files, and read as bools.
+
+use serde::{Deserialize, Deserializer};
+use serde::de::DeserializeOwned;
+use snafu::ResultExt;
+use std::collections::HashMap;
+use std::io::Read;
+
+use crate::docnum;
+use crate::error::{Error, ReadCsv};
+
+/// A record of one of the files, of an application's action
+pub trait ApplicationRecord {
+    /// as in the files, sometimes without the series code's leading zero
+    fn app_id(&self) -> &str;
+
+    /// The eight-digit application number, as in grants'
+    /// application-reference, if it is one
+    fn application_number(&self) -> Option<String> {
+        docnum::application_number(&format!("{:0>8}", self.app_id().trim()), None)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct OfficeAction {
+    pub app_id: String,
+    /// the action's document in the image file wrapper
+    pub ifw_number: String,
+    /// CTNF for a non-final rejection, CTFR for a final one
+    pub document_cd: String,
+    pub mail_dt: String,
+    pub art_unit: Option<String>,
+    pub uspc_class: Option<String>,
+    pub uspc_subclass: Option<String>,
+
+    #[serde(deserialize_with = "flag")]
+    pub header_missing: bool,
+    /// no form paragraphs were found
+    #[serde(deserialize_with = "flag")]
+    pub fp_missing: bool,
+    /// the rejections found in the text don't match those of the form
+    /// paragraphs
+    #[serde(deserialize_with = "flag")]
+    pub rejection_fp_mismatch: bool,
+    #[serde(deserialize_with = "flag")]
+    pub closing_missing: bool,
+
+    #[serde(deserialize_with = "flag")]
+    pub rejection_101: bool,
+    #[serde(deserialize_with = "flag")]
+    pub rejection_102: bool,
+    #[serde(deserialize_with = "flag")]
+    pub rejection_103: bool,
+    #[serde(deserialize_with = "flag")]
+    pub rejection_112: bool,
+    /// obviousness-type double patenting
+    #[serde(deserialize_with = "flag")]
+    pub rejection_dp: bool,
+    #[serde(deserialize_with = "flag")]
+    pub objection: bool,
+    #[serde(deserialize_with = "flag")]
+    pub allowed_claims: bool,
+
+    /// more than one document cited in 102 rejections
+    #[serde(deserialize_with = "flag")]
+    pub cite102_gt1: bool,
+    /// more than three documents cited in a 103 rejection
+    #[serde(deserialize_with = "flag")]
+    pub cite103_gt3: bool,
+    /// exactly one document cited in a 103 rejection
+    #[serde(deserialize_with = "flag")]
+    pub cite103_eq1: bool,
+    /// the most documents cited in any 103 rejection
+    pub cite103_max: Option<u32>,
+    pub signature_type: Option<String>,
+}
+
+impl OfficeAction {
+    pub fn is_final(&self) -> bool {
+        self.document_cd == "CTFR"
+    }
+}
+
+impl ApplicationRecord for OfficeAction {
+    fn app_id(&self) -> &str {
+        &self.app_id
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Rejection {
+    pub app_id: String,
+    pub ifw_number: String,
+    /// the statute, e.g. "102", "103", or "101"
+    pub action_type: String,
+    /// e.g. "b" for a 102(b) rejection
+    pub action_subtype: Option<String>,
+    /// e.g. "1-3,5", see `claims`
+    pub claim_numbers: Option<String>,
+
+    /// the 101 rejection cites Alice v. CLS Bank
+    #[serde(deserialize_with = "flag")]
+    pub alice_in: bool,
+    #[serde(deserialize_with = "flag")]
+    pub bilski_in: bool,
+    #[serde(deserialize_with = "flag")]
+    pub mayo_in: bool,
+    #[serde(deserialize_with = "flag")]
+    pub myriad_in: bool,
+}
+
+impl Rejection {
+    /// The claims rejected, with ranges expanded. Anything that isn't a claim
+    /// number or a range of them is left out.
+    pub fn claims(&self) -> Vec<u32> {
+        let claim_numbers = match &self.claim_numbers {
+            Some(claim_numbers) => claim_numbers,
+            None => return Vec::new(),
+        };
+
+        let mut claims = Vec::new();
+        for part in claim_numbers.split(',') {
+            let mut range = part.splitn(2, '-').map(|n| n.trim().parse::<u32>());
+            match (range.next(), range.next()) {
+                (Some(Ok(start)), Some(Ok(end))) if start <= end => claims.extend(start..=end),
+                (Some(Ok(claim)), None) => claims.push(claim),
+                _ => continue,
+            }
+        }
+
+        claims
+    }
+}
+
+impl ApplicationRecord for Rejection {
+    fn app_id(&self) -> &str {
+        &self.app_id
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct ActionCitation {
+    pub app_id: String,
+    /// the cited patent or publication, as written in the action
+    pub citation_pat_pgpub_id: String,
+    /// as parsed into a number, e.g. "5123456" or "20040123456"
+    pub parsed: Option<String>,
+    pub ifw_number: String,
+    pub action_type: Option<String>,
+    pub action_subtype: Option<String>,
+    /// cited by the examiner (on a PTO-892)
+    #[serde(deserialize_with = "flag")]
+    pub form892: bool,
+    /// cited by the applicant (on an IDS, PTO-1449)
+    #[serde(deserialize_with = "flag")]
+    pub form1449: bool,
+    /// cited in the action's text
+    #[serde(deserialize_with = "flag")]
+    pub citation_in_oa: bool,
+}
+
+impl ApplicationRecord for ActionCitation {
+    fn app_id(&self) -> &str {
+        &self.app_id
+    }
+}
+
+/// 0 or 1, or empty (false)
+fn flag<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    let text = String::deserialize(deserializer)?;
+
+    match text.trim() {
+        "1" | "true" | "TRUE" => Ok(true),
+        "0" | "false" | "FALSE" | "" => Ok(false),
+        other => Err(serde::de::Error::custom(format!("invalid flag {:?}", other))),
+    }
+}
+
+fn records<R: Read, T: DeserializeOwned>(rdr: R) -> impl Iterator<Item = Result<T, Error>> {
+    csv::Reader::from_reader(rdr)
+        .into_deserialize()
+        .map(|record| record.context(ReadCsv))
+}
+
+/// The actions of an office_actions.csv
+pub fn office_actions<R: Read>(rdr: R) -> impl Iterator<Item = Result<OfficeAction, Error>> {
+    records(rdr)
+}
+
+/// The rejections of a rejections.csv
+pub fn rejections<R: Read>(rdr: R) -> impl Iterator<Item = Result<Rejection, Error>> {
+    records(rdr)
+}
+
+/// The citations of a citations.csv
+pub fn citations<R: Read>(rdr: R) -> impl Iterator<Item = Result<ActionCitation, Error>> {
+    records(rdr)
+}
+
+/// Records by application number, for joining to grants' or applications'
+/// application-reference. Records with an app_id that isn't an application
+/// number are left out.
+pub fn by_application<T, I>(records: I) -> HashMap<String, Vec<T>>
+where
+    T: ApplicationRecord,
+    I: IntoIterator<Item = T>,
+{
+    let mut by_application: HashMap<String, Vec<T>> = HashMap::new();
+    for record in records {
+        if let Some(application_number) = record.application_number() {
+            by_application.entry(application_number)
+                .or_default()
+                .push(record);
+        }
+    }
+
+    by_application
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OFFICE_ACTIONS: &str = "\
+app_id,ifw_number,document_cd,mail_dt,art_unit,uspc_class,uspc_subclass,header_missing,fp_missing,rejection_fp_mismatch,closing_missing,rejection_101,rejection_102,rejection_103,rejection_112,rejection_dp,objection,allowed_claims,cite102_gt1,cite103_gt3,cite103_eq1,cite103_max,signature_type
+15123456,ABC123,CTNF,2018-03-01,3671,172,371,0,0,0,0,0,1,1,0,0,0,0,0,0,1,1,1
+15123456,ABC124,CTFR,2018-09-01,3671,172,371,0,0,0,0,0,0,1,0,0,0,1,0,0,0,2,1
+9876543,ABC200,CTNF,2009-01-15,2100,,,0,1,0,0,1,0,0,0,0,0,0,0,0,0,,
+";
+
+    const REJECTIONS: &str = "\
+app_id,ifw_number,action_type,action_subtype,claim_numbers,alice_in,bilski_in,mayo_in,myriad_in
+15123456,ABC123,102,a1,\"1-3,5\",0,0,0,0
+9876543,ABC200,101,,1,1,0,0,0
+";
+
+    #[test]
+    fn test_office_actions() {
+        let actions = office_actions(OFFICE_ACTIONS.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(actions.len(), 3);
+        assert!(!actions[0].is_final());
+        assert!(actions[0].rejection_102 && actions[0].rejection_103);
+        assert_eq!(actions[0].cite103_max, Some(1));
+        assert!(actions[1].is_final());
+        assert!(actions[1].allowed_claims);
+        assert!(actions[2].uspc_class.is_none());
+        assert_eq!(actions[2].cite103_max, None);
+
+        let by_application = by_application(actions);
+        assert_eq!(by_application["15123456"].len(), 2);
+        // the series code's zero, dropped in the file
+        assert_eq!(by_application["09876543"][0].ifw_number, "ABC200");
+    }
+
+    #[test]
+    fn test_rejections() {
+        let rejections = rejections(REJECTIONS.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(rejections[0].action_type, "102");
+        assert_eq!(rejections[0].claims(), vec![1, 2, 3, 5]);
+        assert!(rejections[1].alice_in);
+        assert_eq!(rejections[1].claims(), vec![1]);
+
+        let invalid = REJECTIONS.replace(",1,1,0,0,0", ",1,2,0,0,0");
+        assert!(super::rejections(invalid.as_bytes()).any(|rejection| rejection.is_err()));
+    }
+}
@@ -0,0 +1,302 @@
+//! How forgiving the parser is of documents that don't match what it expects.
+//!
+//! Applies to the redbook xml (2005 on, and applications); the older PATDOC
+//! and APS formats have their own fixed handling.
+
+use std::collections::{BTreeMap, HashMap};
+use std::ops::BitOr;
+use std::sync::{Arc, Mutex};
+
+use crate::maths::{Latex, LatexConverter};
+
+/// What to do with an element the parser doesn't know.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnknownElements {
+    /// skip the element and everything in it
+    #[default]
+    Skip,
+    /// the document is an error
+    Error,
+}
+
+/// What to do when a required element is missing (e.g. a document-id without
+/// a doc-number).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MissingFields {
+    /// leave the field at its default (usually empty)
+    #[default]
+    Default,
+    /// the document is an error
+    Error,
+}
+
+/// How entities in text (and attribute values) are handled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Entities {
+    /// unescape them; an unknown entity is an error
+    #[default]
+    Unescape,
+    /// unescape them, but keep text with an unknown entity (e.g. one declared
+    /// in the dtd, like `&mgr;`) escaped as is
+    KeepUnknown,
+    /// unescape them, including those declared in the USPTO dtds (see
+    /// `entities`); any other unknown entity is kept escaped as is, without
+    /// the rest of the text
+    Dtd,
+    /// keep all text escaped as is
+    Raw,
+}
+
+/// How text that isn't valid in the document's encoding (UTF-8, unless the
+/// declaration says otherwise) is decoded, e.g. the stray Windows-1252 bytes
+/// in some older files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Decoding {
+    /// the document is an error
+    Strict,
+    /// the invalid bytes are replaced with U+FFFD
+    #[default]
+    Lossy,
+    /// text that isn't valid is decoded with this encoding instead, e.g.
+    /// `encoding_rs::WINDOWS_1252`
+    Fallback(&'static encoding_rs::Encoding),
+}
+
+/// Sections of a document to parse, combined with `|`, e.g.
+/// `Projection::BIBLIO | Projection::CLAIMS`. The rest are skipped over
+/// without being parsed, and left empty. The publication-reference is always
+/// parsed, to identify the document by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Projection(u8);
+
+impl Projection {
+    /// only the publication-reference
+    pub const NONE: Projection = Projection(0);
+    /// the biblio, besides its citations and parties
+    pub const BIBLIO: Projection = Projection(1);
+    pub const ABSTRACT: Projection = Projection(1 << 1);
+    /// the claims and the claim statement
+    pub const CLAIMS: Projection = Projection(1 << 2);
+    /// the description sections (and the government interest in them)
+    pub const DESCRIPTION: Projection = Projection(1 << 3);
+    /// references cited
+    pub const CITATIONS: Projection = Projection(1 << 4);
+    /// applicants, inventors, agents, assignees and examiners
+    pub const PARTIES: Projection = Projection(1 << 5);
+    pub const ALL: Projection = Projection((1 << 6) - 1);
+
+    /// All of other's sections are in these
+    pub fn contains(self, other: Projection) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// These without other's sections
+    pub fn without(self, other: Projection) -> Projection {
+        Projection(self.0 & !other.0)
+    }
+}
+
+impl Default for Projection {
+    fn default() -> Self {
+        Projection::ALL
+    }
+}
+
+impl BitOr for Projection {
+    type Output = Projection;
+
+    fn bitor(self, other: Projection) -> Projection {
+        Projection(self.0 | other.0)
+    }
+}
+
+/// Options for PatentGrants (and PatentApplications), see `with_options`.
+///
+/// The default skips unknown elements, doesn't require any fields, trims
+/// text, unescapes entities, and replaces invalid bytes in text. `strict` and
+/// `lenient` are presets for the two ends.
+#[derive(Debug, Clone)]
+pub struct ParserOptions {
+    pub(crate) unknown_elements: UnknownElements,
+    pub(crate) missing_fields: MissingFields,
+    pub(crate) trim_text: bool,
+    pub(crate) entities: Entities,
+    pub(crate) decoding: Decoding,
+    pub(crate) check_consistency: bool,
+    pub(crate) skipped: Option<SkippedElements>,
+    pub(crate) projection: Projection,
+    pub(crate) formulae: bool,
+    pub(crate) latex: Option<Latex>,
+    pub(crate) sequence_listing_text: bool,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        ParserOptions {
+            unknown_elements: UnknownElements::default(),
+            missing_fields: MissingFields::default(),
+            trim_text: true,
+            entities: Entities::default(),
+            decoding: Decoding::default(),
+            check_consistency: false,
+            skipped: None,
+            projection: Projection::ALL,
+            formulae: false,
+            latex: None,
+            sequence_listing_text: true,
+        }
+    }
+}
+
+impl ParserOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Errors on anything unexpected, and checks each grant for consistency
+    pub fn strict() -> Self {
+        ParserOptions {
+            unknown_elements: UnknownElements::Error,
+            missing_fields: MissingFields::Error,
+            entities: Entities::Unescape,
+            decoding: Decoding::Strict,
+            check_consistency: true,
+            ..Self::default()
+        }
+    }
+
+    /// Reads whatever it can
+    pub fn lenient() -> Self {
+        ParserOptions {
+            unknown_elements: UnknownElements::Skip,
+            missing_fields: MissingFields::Default,
+            entities: Entities::KeepUnknown,
+            decoding: Decoding::Fallback(encoding_rs::WINDOWS_1252),
+            check_consistency: false,
+            ..Self::default()
+        }
+    }
+
+    pub fn unknown_elements(mut self, unknown_elements: UnknownElements) -> Self {
+        self.unknown_elements = unknown_elements;
+        self
+    }
+
+    pub fn missing_fields(mut self, missing_fields: MissingFields) -> Self {
+        self.missing_fields = missing_fields;
+        self
+    }
+
+    /// Trim whitespace around text, and drop text that's only whitespace
+    pub fn trim_text(mut self, trim_text: bool) -> Self {
+        self.trim_text = trim_text;
+        self
+    }
+
+    pub fn entities(mut self, entities: Entities) -> Self {
+        self.entities = entities;
+        self
+    }
+
+    pub fn decoding(mut self, decoding: Decoding) -> Self {
+        self.decoding = decoding;
+        self
+    }
+
+    /// Check each grant for consistency after it's parsed (e.g.
+    /// number-of-claims matches the claims found), and error if not. This is
+    /// `PatentGrants::strict`.
+    pub fn check_consistency(mut self, check_consistency: bool) -> Self {
+        self.check_consistency = check_consistency;
+        self
+    }
+
+    /// Parse only these sections, see Projection
+    pub fn projection(mut self, projection: Projection) -> Self {
+        self.projection = projection;
+        self
+    }
+
+    /// Keep each formula (`maths`) in paragraphs and claims as MathML, in
+    /// their `formulae`
+    pub fn formulae(mut self, formulae: bool) -> Self {
+        self.formulae = formulae;
+        self
+    }
+
+    /// Keep formulae (see `formulae`), and convert each to LaTeX, e.g. with
+    /// `maths::BasicLatex`
+    pub fn latex<C: LatexConverter + 'static>(mut self, converter: C) -> Self {
+        self.formulae = true;
+        self.latex = Some(Latex(Arc::new(converter)));
+        self
+    }
+
+    /// Keep the sequence listing (SEQLST) section in descriptions (and
+    /// descriptions_raw), as any other section. It can be most of a
+    /// biotech grant's xml; its ids and files are in `sequence_listing`
+    /// either way.
+    pub fn sequence_listing_text(mut self, sequence_listing_text: bool) -> Self {
+        self.sequence_listing_text = sequence_listing_text;
+        self
+    }
+
+    /// Record each unknown element skipped into `skipped`, which can be read
+    /// during or after iteration. Options cloned from these (e.g. by the
+    /// parallel parser, for each document) record into the same one.
+    pub fn collect_skipped(mut self, skipped: &SkippedElements) -> Self {
+        self.skipped = Some(skipped.clone());
+        self
+    }
+}
+
+/// Number of example positions kept for each skipped element
+const SKIPPED_EXAMPLES: usize = 5;
+
+/// An unknown element that was skipped
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SkippedElement {
+    pub count: u64,
+    /// byte positions (after the element's start tag) of the first few. When
+    /// documents are parsed separately (lenient, parallel or async parsing)
+    /// these are within the document, not the input.
+    pub positions: Vec<usize>,
+}
+
+/// Unknown elements skipped by the parser, by path (the parent element and
+/// the element's name, e.g. `us-bibliographic-data-grant/us-sir-flag`).
+/// Clones share the same records. See `ParserOptions::collect_skipped`.
+#[derive(Debug, Clone, Default)]
+pub struct SkippedElements {
+    elements: Arc<Mutex<HashMap<String, SkippedElement>>>,
+}
+
+impl SkippedElements {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The elements skipped so far, sorted by path
+    pub fn elements(&self) -> BTreeMap<String, SkippedElement> {
+        self.lock()
+            .iter()
+            .map(|(path, element)| (path.clone(), element.clone()))
+            .collect()
+    }
+
+    pub(crate) fn record(&self, parent: &[u8], name: &[u8], position: usize) {
+        let path = format!("{}/{}", String::from_utf8_lossy(parent), String::from_utf8_lossy(name));
+
+        let mut elements = self.lock();
+        let element = elements.entry(path).or_default();
+        element.count += 1;
+        if element.positions.len() < SKIPPED_EXAMPLES {
+            element.positions.push(position);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, SkippedElement>> {
+        // a panic elsewhere while recording leaves the counts usable
+        self.elements.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
@@ -0,0 +1,48 @@
+use serde::Serialize;
+use std::convert::From;
+
+use crate::data::{date_text, CountryCode, PatentGrant};
+
+/// Output format (csv) to be ingested into rows of cube
+///
+/// No measures; just count
+#[derive(Serialize)]
+pub struct PatentOutput {
+    id: String,
+    date: String,
+    country_inventor: String,
+    country_assignee: String,
+    classification_locarno: String,
+    classification_national: String,
+}
+
+impl From<&PatentGrant> for PatentOutput {
+    fn from(pg: &PatentGrant) -> Self {
+        let dg = &pg.us_bibliographic_data_grant;
+
+        let country_inventor: Vec<String> = dg
+            .inventors
+            .iter()
+            .filter_map(|inventor| inventor.addressbook.address.country.as_ref())
+            .map(CountryCode::to_string)
+            .collect();
+        let country_inventor = country_inventor.join(";");
+
+        let country_assignee: Vec<String> = dg
+            .assignees
+            .iter()
+            .filter_map(|assignee| assignee.addressbook.address.country.as_ref())
+            .map(CountryCode::to_string)
+            .collect();
+        let country_assignee = country_assignee.join(";");
+
+        PatentOutput {
+            id: dg.publication_reference.doc_number.clone(),
+            date: date_text(&dg.publication_reference.date),
+            country_inventor,
+            country_assignee,
+            classification_locarno: dg.classification_locarno.main_classification.clone(),
+            classification_national: dg.classification_national.main_classification.to_string(),
+        }
+    }
+}
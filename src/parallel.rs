@@ -0,0 +1,133 @@
+//! Parallel parsing of a bulk file: documents are split out (see `split`) on
+//! the calling thread, and parsed in batches on the rayon thread pool.
+
+use rayon::prelude::*;
+use std::collections::VecDeque;
+use std::io::BufRead;
+
+use crate::data::PatentGrant;
+use crate::deserialize::PatentGrants;
+use crate::options::ParserOptions;
+use crate::error::Error;
+use crate::split::Documents;
+
+/// Like PatentGrants, but parses on the rayon thread pool. Results are yielded
+/// in the same order as the documents in the input.
+pub struct ParallelPatentGrants<B: BufRead> {
+    documents: Documents<B>,
+    parsed: VecDeque<Result<PatentGrant, Error>>,
+    batch_size: usize,
+    options: ParserOptions,
+}
+
+impl<B: BufRead> ParallelPatentGrants<B> {
+    pub fn from_reader(b: B) -> Self {
+        ParallelPatentGrants {
+            documents: Documents::from_reader(b),
+            parsed: VecDeque::new(),
+            // enough documents to keep every thread busy
+            batch_size: rayon::current_num_threads() * 16,
+            options: ParserOptions::default(),
+        }
+    }
+
+    /// See `PatentGrants::strict`
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.options.check_consistency = strict;
+        self
+    }
+
+    /// See `PatentGrants::with_options`
+    pub fn options(mut self, options: ParserOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Number of documents read into memory and parsed at a time
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    fn parse_batch(&mut self) {
+        let mut batch = Vec::with_capacity(self.batch_size);
+        let mut read_err = None;
+        for document in self.documents.by_ref().take(self.batch_size) {
+            match document {
+                Ok(document) => batch.push(document),
+                Err(err) => {
+                    read_err = Some(Err(err));
+                    break;
+                },
+            }
+        }
+
+        let options = &self.options;
+        let parsed: Vec<_> = batch.par_iter()
+            .filter_map(|document| PatentGrants::with_options(&document[..], options.clone()).next())
+            .collect();
+
+        // a read error goes after the documents read before it
+        self.parsed.extend(parsed);
+        self.parsed.extend(read_err);
+    }
+}
+
+impl<B: BufRead> Iterator for ParallelPatentGrants<B> {
+    type Item = Result<PatentGrant, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.parsed.is_empty() {
+            self.parse_batch();
+        }
+
+        self.parsed.pop_front()
+    }
+}
+
+impl<B: BufRead> PatentGrants<B> {
+    /// A ParallelPatentGrants over the reader
+    pub fn par_from_reader(b: B) -> ParallelPatentGrants<B> {
+        ParallelPatentGrants::from_reader(b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/grants.xml");
+
+    fn doc_numbers<I: Iterator<Item = Result<PatentGrant, Error>>>(grants: I) -> Vec<String> {
+        grants
+            .map(|pg| pg.unwrap().us_bibliographic_data_grant.publication_reference.doc_number)
+            .collect()
+    }
+
+    #[test]
+    fn test_parallel_in_order() {
+        let sequential = doc_numbers(PatentGrants::from_reader(FIXTURE.as_bytes()));
+        assert_eq!(sequential.len(), 3);
+
+        // batches smaller than the input, and larger
+        for batch_size in &[1, 2, 100] {
+            let rdr = BufReader::with_capacity(16, FIXTURE.as_bytes());
+            let parallel = doc_numbers(PatentGrants::par_from_reader(rdr).batch_size(*batch_size));
+            assert_eq!(parallel, sequential);
+        }
+    }
+
+    #[test]
+    fn test_parallel_error_doesnt_stop_others() {
+        let second = FIXTURE.match_indices("<?xml").nth(1).unwrap().0;
+        // truncate the first document
+        let input = format!("{}{}", &FIXTURE[..second / 2], &FIXTURE[second..]);
+
+        let results: Vec<_> = ParallelPatentGrants::from_reader(input.as_bytes()).collect();
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_err());
+        assert!(results[1].is_ok());
+        assert!(results[2].is_ok());
+    }
+}
@@ -0,0 +1,878 @@
+//! PATDOC v2.5, the format of grant files from 2002 to 2004 (pg*.xml).
+//!
+//! Elements are named by their WIPO ST.32 codes (e.g. B110 for the document
+//! number), and all text is in PDAT leaves. PatentGrants dispatches here on a
+//! PATDOC root, and the SDOBI fields are mapped onto BibliographicDataGrant
+//! so these grants come out the same as later years'.
+//!
+//! Text is decoded per the ParserOptions, though the dtd's entities (e.g.
+//! `&deg;`) are always resolved, unless text is kept raw.
+//!
+//! Not mapped yet: B400 (publication of unexamined documents), B600 (related
+//! documents), B800 (PCT data), and the drawings.
+
+use quick_xml::events::{BytesStart, Event};
+use std::io::BufRead;
+
+use crate::data::*;
+use crate::error::{Error, WithinExt};
+use crate::options::{Entities, ParserOptions};
+use crate::util::{decode_attribute, decode_text, FromText};
+
+/// call with the PATDOC start tag; only reads attributes
+pub(crate) fn deser_patdoc_meta<B: BufRead>(
+    start: &BytesStart,
+    rdr: &quick_xml::Reader<B>,
+    opts: &ParserOptions,
+    meta: &mut GrantMeta,
+    ) -> Result<(), Error>
+{
+    for attr_res in start.attributes() {
+        let attr = attr_res
+            .map_err(|err| Error::deser(err.to_string()))?;
+
+        match attr.key {
+            b"DTD" => meta.dtd_version = Some(decode_attribute(&attr, rdr, opts)?),
+            b"STATUS" => meta.status = Some(decode_attribute(&attr, rdr, opts)?),
+            _ => return Err(Error::deser("unrecognized attr in PATDOC".to_string())),
+        }
+    }
+    meta.lang = "EN".to_string();
+
+    Ok(())
+}
+
+/// called after tag PATDOC is already hit; reads through its end tag
+pub(crate) fn deser_patdoc<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    opts: &ParserOptions,
+    patent_grant: &mut PatentGrant,
+    ) -> Result<(), Error>
+{
+    let opts = &pdat_options(opts);
+
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"SDOBI" => deser_sdobi(rdr, buf, opts, &mut patent_grant.us_bibliographic_data_grant).within(b"SDOBI")?,
+                    b"SDOAB" => deser_sdoab(rdr, buf, opts, &mut patent_grant.r#abstract).within(b"SDOAB")?,
+                    b"SDODE" => deser_sdode(rdr, buf, opts, patent_grant).within(b"SDODE")?,
+                    b"SDOCL" => deser_sdocl(rdr, buf, opts, patent_grant).within(b"SDOCL")?,
+                    name => skip_element(name.to_vec(), rdr, buf)?,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"PATDOC" {
+                    break;
+                }
+            },
+            Ok(Event::Eof) => return Err(Error::deser("eof before end of PATDOC".to_string())),
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    patent_grant.meta.country = patent_grant.us_bibliographic_data_grant.publication_reference.country.to_string();
+    patent_grant.meta.date_publ = Some(date_text(&patent_grant.us_bibliographic_data_grant.publication_reference.date));
+
+    Ok(())
+}
+
+/// bibliographic data, by ST.32 code:
+/// - B100 document (publication reference)
+/// - B200 application
+/// - B300 priority claims
+/// - B500 classifications, title, citations, claims and drawings counts
+/// - B700 parties and examiners
+///
+/// called after tag SDOBI is already hit
+fn deser_sdobi<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    opts: &ParserOptions,
+    biblio: &mut BibliographicDataGrant,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"B100" => deser_b100(rdr, buf, opts, &mut biblio.publication_reference).within(b"B100")?,
+                    b"B200" => {
+                        deser_b200(rdr, buf, opts, biblio).within(b"B200")?;
+                        biblio.application_reference.country = biblio.publication_reference.country.clone();
+                    },
+                    b"B300" => {
+                        let mut priority_claim = PriorityClaim {
+                            sequence: format!("{:02}", biblio.priority_claims.len() + 1),
+                            kind: "national".to_string(),
+                            ..PriorityClaim::default()
+                        };
+                        deser_b300(rdr, buf, opts, &mut priority_claim).within(b"B300")?;
+                        biblio.priority_claims.push(priority_claim);
+                    },
+                    b"B500" => deser_b500(rdr, buf, opts, biblio).within(b"B500")?,
+                    b"B700" => deser_b700(rdr, buf, opts, biblio).within(b"B700")?,
+                    name => skip_element(name.to_vec(), rdr, buf)?,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"SDOBI" {
+                    break;
+                }
+            },
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    Ok(())
+}
+
+/// B110 number, B130 kind, B140 date, B190 country
+///
+/// called after tag B100 is already hit
+fn deser_b100<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    opts: &ParserOptions,
+    doc_id: &mut DocumentId,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"B110" => doc_id.doc_number = deser_pdat_from(b"B110", rdr, buf, opts)?,
+                    b"B130" => doc_id.kind = Some(KindCode::from(deser_pdat_from(b"B130", rdr, buf, opts)?)),
+                    b"B140" => doc_id.date = deser_pdat_date_from(b"B140", rdr, buf, opts)?,
+                    b"B190" => doc_id.country = CountryCode::from(deser_pdat_from(b"B190", rdr, buf, opts)?),
+                    name => skip_element(name.to_vec(), rdr, buf)?,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"B100" {
+                    break;
+                }
+            },
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    Ok(())
+}
+
+/// B210 number, B211US series code, B220 filing date
+///
+/// called after tag B200 is already hit
+fn deser_b200<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    opts: &ParserOptions,
+    biblio: &mut BibliographicDataGrant,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"B210" => biblio.application_reference.doc_number = deser_pdat_from(b"B210", rdr, buf, opts)?,
+                    b"B211US" => biblio.us_application_series_code = deser_pdat_from(b"B211US", rdr, buf, opts)?,
+                    b"B220" => biblio.application_reference.date = deser_pdat_date_from(b"B220", rdr, buf, opts)?,
+                    name => skip_element(name.to_vec(), rdr, buf)?,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"B200" {
+                    break;
+                }
+            },
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    Ok(())
+}
+
+/// B310 number, B320 date, B330 country; one B300 per priority claim
+///
+/// called after tag B300 is already hit
+fn deser_b300<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    opts: &ParserOptions,
+    priority_claim: &mut PriorityClaim,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"B310" => priority_claim.doc_number = Some(deser_pdat_from(b"B310", rdr, buf, opts)?),
+                    b"B320" => priority_claim.date = deser_pdat_date_from(b"B320", rdr, buf, opts)?,
+                    b"B330" => priority_claim.country = CountryCode::from(deser_pdat_from(b"B330", rdr, buf, opts)?),
+                    name => skip_element(name.to_vec(), rdr, buf)?,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"B300" {
+                    break;
+                }
+            },
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    Ok(())
+}
+
+/// - B510 ipc (B511 main, B512 further, B516 edition)
+/// - B520 us classification (B521 main, B522 further)
+/// - B540 title
+/// - B560 citations (B561 patent, B562 non-patent)
+/// - B570 claims (B577 number, B578US exemplary)
+/// - B580 field of search (B582 us classification)
+/// - B590 drawings (B595 sheets, B596 figures)
+///
+/// called after tag B500 is already hit
+fn deser_b500<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    opts: &ParserOptions,
+    biblio: &mut BibliographicDataGrant,
+    ) -> Result<(), Error>
+{
+    let mut ipc_edition = String::new();
+    let mut ipcs = Vec::new();
+
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"B511" => ipcs.push(deser_pdat_from(b"B511", rdr, buf, opts)?),
+                    b"B512" => ipcs.push(deser_pdat_from(b"B512", rdr, buf, opts)?),
+                    b"B516" => ipc_edition = deser_pdat_from(b"B516", rdr, buf, opts)?,
+                    b"B521" => {
+                        biblio.classification_national.country = CountryCode::US;
+                        biblio.classification_national.main_classification = Uspc::from(deser_pdat_from(b"B521", rdr, buf, opts).within(b"B521")?);
+                    },
+                    b"B522" => {
+                        let further = Uspc::from(deser_pdat_from(b"B522", rdr, buf, opts).within(b"B522")?);
+                        // only one further classification is kept
+                        if biblio.classification_national.further_classification.is_none() {
+                            biblio.classification_national.further_classification = Some(further);
+                        }
+                    },
+                    b"B540" => biblio.invention_title.title = deser_pdat_from(b"B540", rdr, buf, opts)?,
+                    b"B561" => {
+                        let num = format!("{:05}", biblio.us_references_cited.len() + 1);
+                        biblio.us_references_cited.push(deser_b561(rdr, buf, opts, num)?);
+                    },
+                    b"B562" => {
+                        let num = format!("{:05}", biblio.us_references_cited.len() + 1);
+                        biblio.us_references_cited.push(deser_b562(rdr, buf, opts, num)?);
+                    },
+                    b"B577" => biblio.number_of_claims = Some(deser_pdat_u32_from(b"B577", rdr, buf, opts)?),
+                    b"B578US" => biblio.us_exemplary_claim.push(deser_pdat_u32_from(b"B578US", rdr, buf, opts)?),
+                    b"B582" => {
+                        biblio.us_field_of_classification_search.classification_nationals.push(ClassificationNational {
+                            country: CountryCode::US,
+                            main_classification: Uspc::from(deser_pdat_from(b"B582", rdr, buf, opts)?),
+                            ..ClassificationNational::default()
+                        });
+                    },
+                    b"B595" => biblio.figures.number_of_drawing_sheets = Some(deser_pdat_u32_from(b"B595", rdr, buf, opts)?),
+                    b"B596" => biblio.figures.number_of_figures = Some(deser_pdat_u32_from(b"B596", rdr, buf, opts)?),
+                    // groups (B510, B520, ...) are stepped into
+                    _ => continue,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"B500" {
+                    break;
+                }
+            },
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    biblio.classifications_ipcr = ipcs
+        .iter()
+        .map(|ipc| ipc_from_text(ipc, &ipc_edition))
+        .collect();
+
+    Ok(())
+}
+
+/// Splits an ipc symbol as written in B511, e.g. `A01B 1/00`
+fn ipc_from_text(ipc: &str, edition: &str) -> ClassificationIpcr {
+    let (subclass, group) = match ipc.find(char::is_whitespace) {
+        Some(i) => (&ipc[..i], ipc[i..].trim()),
+        None => (ipc, ""),
+    };
+    let (main_group, subgroup) = match group.find('/') {
+        Some(i) => (Some(group[..i].to_string()), Some(group[i + 1..].to_string())),
+        None if !group.is_empty() => (Some(group.to_string()), None),
+        None => (None, None),
+    };
+
+    ClassificationIpcr {
+        ipc_version_indicator: edition.to_string(),
+        section: subclass.get(..1).unwrap_or_default().to_string(),
+        class: subclass.get(1..3).unwrap_or_default().to_string(),
+        subclass: subclass.get(3..).unwrap_or_default().to_string(),
+        main_group,
+        subgroup,
+        ..ClassificationIpcr::default()
+    }
+}
+
+/// patent citation: PCIT with the cited DOC (DNUM, DATE, KIND, CTRY), its
+/// patentee (PARTY-US), and a CITED-BY-EXAMINER or CITED-BY-OTHER flag
+///
+/// called after tag B561 is already hit
+fn deser_b561<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    opts: &ParserOptions,
+    num: String,
+    ) -> Result<UsCitation, Error>
+{
+    let mut patcit = PatCit {
+        num,
+        ..PatCit::default()
+    };
+    let mut category = None;
+    let mut classification_national = None;
+    let doc_id = &mut patcit.document_id;
+
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"DNUM" => doc_id.doc_number = deser_pdat_from(b"DNUM", rdr, buf, opts)?,
+                    b"DATE" => doc_id.date = deser_pdat_date_from(b"DATE", rdr, buf, opts)?,
+                    b"KIND" => doc_id.kind = Some(KindCode::from(deser_pdat_from(b"KIND", rdr, buf, opts)?)),
+                    b"CTRY" => doc_id.country = CountryCode::from(deser_pdat_from(b"CTRY", rdr, buf, opts)?),
+                    b"NAM" => doc_id.name = Some(deser_pdat_from(b"NAM", rdr, buf, opts)?),
+                    b"PNC" => {
+                        classification_national = Some(ClassificationNational {
+                            country: CountryCode::US,
+                            main_classification: Uspc::from(deser_pdat_from(b"PNC", rdr, buf, opts)?),
+                            ..ClassificationNational::default()
+                        });
+                    },
+                    _ => continue,
+                }
+            },
+            Ok(Event::Empty(ref e)) => {
+                if let Some(cited_by) = cited_by(e.name()) {
+                    category = Some(cited_by.to_string());
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"B561" {
+                    break;
+                }
+            },
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    // us patents don't repeat the country
+    if doc_id.country.is_empty() {
+        doc_id.country = CountryCode::US;
+    }
+
+    Ok(UsCitation {
+        citation: Citation::Patent(patcit),
+        category,
+        classification_cpc_text: None,
+        classification_national,
+    })
+}
+
+/// non-patent citation: NCIT text, and a CITED-BY-EXAMINER or CITED-BY-OTHER
+/// flag
+///
+/// called after tag B562 is already hit
+fn deser_b562<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    opts: &ParserOptions,
+    num: String,
+    ) -> Result<UsCitation, Error>
+{
+    let mut nplcit = NplCit {
+        num,
+        ..NplCit::default()
+    };
+    let mut category = None;
+
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"NCIT" => nplcit.othercit = deser_pdat_from(b"NCIT", rdr, buf, opts)?,
+                    _ => continue,
+                }
+            },
+            Ok(Event::Empty(ref e)) => {
+                if let Some(cited_by) = cited_by(e.name()) {
+                    category = Some(cited_by.to_string());
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"B562" {
+                    break;
+                }
+            },
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    Ok(UsCitation {
+        citation: Citation::NonPatent(nplcit),
+        category,
+        classification_cpc_text: None,
+        classification_national: None,
+    })
+}
+
+/// the category of a citation, as written in later versions
+fn cited_by(flag: &[u8]) -> Option<&'static str> {
+    match flag {
+        b"CITED-BY-EXAMINER" => Some("cited by examiner"),
+        b"CITED-BY-OTHER" => Some("cited by other"),
+        _ => None,
+    }
+}
+
+/// - B720 inventors (B721)
+/// - B730 assignees (B731, with B732US role)
+/// - B740 agents (B741)
+/// - B745 examiners (B746 primary, B747 assistant)
+///
+/// called after tag B700 is already hit
+fn deser_b700<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    opts: &ParserOptions,
+    biblio: &mut BibliographicDataGrant,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"B721" => {
+                        let mut inventor = Inventor {
+                            sequence: format!("{:03}", biblio.inventors.len() + 1),
+                            ..Inventor::default()
+                        };
+                        deser_party_us(b"B721", rdr, buf, opts, &mut inventor.addressbook).within(b"B721")?;
+                        biblio.inventors.push(inventor);
+                    },
+                    b"B731" => {
+                        let mut assignee = Assignee::default();
+                        deser_party_us(b"B731", rdr, buf, opts, &mut assignee.addressbook).within(b"B731")?;
+                        assignee.orgname = assignee.addressbook.orgname.clone();
+                        biblio.assignees.push(assignee);
+                    },
+                    b"B732US" => {
+                        let role = deser_pdat_from(b"B732US", rdr, buf, opts).within(b"B732US")?;
+                        if let Some(assignee) = biblio.assignees.last_mut() {
+                            assignee.addressbook.role = Some(role.clone());
+                            assignee.role = Some(role);
+                        }
+                    },
+                    b"B741" => {
+                        let mut agent = Agent {
+                            sequence: format!("{:02}", biblio.agents.len() + 1),
+                            rep_type: "attorney".to_string(),
+                            ..Agent::default()
+                        };
+                        deser_party_us(b"B741", rdr, buf, opts, &mut agent.addressbook).within(b"B741")?;
+                        biblio.agents.push(agent);
+                    },
+                    b"B746" => {
+                        biblio.examiners.primary_examiner = deser_examiner(b"B746", rdr, buf, opts).within(b"B746")?;
+                    },
+                    b"B747" => {
+                        biblio.examiners.assistant_examiner = Some(deser_examiner(b"B747", rdr, buf, opts)?);
+                    },
+                    // groups (B720, B730, ...) are stepped into
+                    _ => continue,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"B700" {
+                    break;
+                }
+            },
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    Ok(())
+}
+
+/// PARTY-US: NAM (FNM first name, SNM surname, ONM organization) and ADR
+/// (CITY, STATE, CTRY)
+///
+/// called after the party's tag (e.g. B721) is already hit
+fn deser_party_us<B: BufRead>(
+    end: &[u8],
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    opts: &ParserOptions,
+    addressbook: &mut AddressBook,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"FNM" => addressbook.first_name = Some(deser_pdat_from(b"FNM", rdr, buf, opts)?),
+                    b"SNM" => addressbook.last_name = Some(deser_pdat_from(b"SNM", rdr, buf, opts)?),
+                    b"ONM" => addressbook.orgname = Some(deser_pdat_from(b"ONM", rdr, buf, opts)?),
+                    b"CITY" => addressbook.address.city = Some(deser_pdat_from(b"CITY", rdr, buf, opts)?),
+                    b"STATE" => addressbook.address.state = Some(deser_pdat_from(b"STATE", rdr, buf, opts)?),
+                    b"CTRY" => addressbook.address.country = Some(CountryCode::from(deser_pdat_from(b"CTRY", rdr, buf, opts)?)),
+                    _ => continue,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == end {
+                    break;
+                }
+            },
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    // us addresses have a state instead of a country
+    if addressbook.address.country.is_none() && addressbook.address.state.is_some() {
+        addressbook.address.country = Some(CountryCode::US);
+    }
+
+    Ok(())
+}
+
+/// called after the examiner's tag (B746 or B747) is already hit
+fn deser_examiner<B: BufRead>(
+    end: &[u8],
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    opts: &ParserOptions,
+    ) -> Result<Examiner, Error>
+{
+    let mut addressbook = AddressBook::default();
+    deser_party_us(end, rdr, buf, opts, &mut addressbook)?;
+
+    Ok(Examiner {
+        first_name: addressbook.first_name.unwrap_or_default(),
+        last_name: addressbook.last_name.unwrap_or_default(),
+        ..Examiner::default()
+    })
+}
+
+/// abstract paragraphs (PARA, with an ID attr)
+///
+/// called after tag SDOAB is already hit
+fn deser_sdoab<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    opts: &ParserOptions,
+    paragraphs: &mut Vec<Paragraph>,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"PARA" => {
+                        let id = deser_id_attr(e, rdr, opts).within(b"PARA")?;
+                        paragraphs.push(Paragraph {
+                            id,
+                            num: format!("{:04}", paragraphs.len()),
+                            text: deser_pdat_from(b"PARA", rdr, buf, opts)?,
+                            ..Paragraph::default()
+                        });
+                    },
+                    _ => continue,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"SDOAB" {
+                    break;
+                }
+            },
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    Ok(())
+}
+
+/// description sections (e.g. BRFSUM, DETDESC, DRWDESC), keyed by element
+/// name like the grants' description PIs. Only the text is kept.
+///
+/// called after tag SDODE is already hit
+fn deser_sdode<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    opts: &ParserOptions,
+    patent_grant: &mut PatentGrant,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                let name = e.name().to_vec();
+                let text = deser_pdat_from(&name, rdr, buf, opts)?;
+                patent_grant.descriptions.insert(String::from_utf8_lossy(&name).into_owned(), text);
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"SDODE" {
+                    break;
+                }
+            },
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    Ok(())
+}
+
+/// H claim statement, then CL with a CLM (ID attr) per claim. Each claim's
+/// text is kept as one segment.
+///
+/// called after tag SDOCL is already hit
+fn deser_sdocl<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    opts: &ParserOptions,
+    patent_grant: &mut PatentGrant,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"H" => patent_grant.us_claim_statement = deser_pdat_from(b"H", rdr, buf, opts)?,
+                    b"CLM" => {
+                        let id = deser_id_attr(e, rdr, opts).within(b"CLM")?;
+                        let num = id.trim_start_matches("CLM-").to_string();
+                        let text = deser_pdat_from(b"CLM", rdr, buf, opts).within(b"CLM")?;
+
+                        patent_grant.claims.push(Claim {
+                            id,
+                            num,
+                            claim_type: None,
+                            claim_texts: vec![ClaimText { text, ..ClaimText::default() }],
+                        });
+                    },
+                    _ => continue,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"SDOCL" {
+                    break;
+                }
+            },
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    Ok(())
+}
+
+fn deser_id_attr<B: BufRead>(e: &BytesStart, rdr: &quick_xml::Reader<B>, opts: &ParserOptions) -> Result<String, Error> {
+    for attr_res in e.attributes() {
+        let attr = attr_res
+            .map_err(|err| Error::deser(err.to_string()))?;
+
+        if attr.key == b"ID" {
+            return decode_attribute(&attr, rdr, opts);
+        }
+    }
+
+    Ok(String::new())
+}
+
+/// call when the start tag has already been consumed; all the text (PDAT)
+/// within, through the end tag, joined by spaces, decoded per the options
+/// (see `pdat_options`).
+fn deser_pdat_from<B: BufRead, K: AsRef<[u8]>>(
+    end: K,
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    opts: &ParserOptions,
+    ) -> Result<String, Error>
+{
+    let end = end.as_ref();
+    let mut texts = Vec::new();
+    // the end tag could be nested, e.g. a PARA in a PARA
+    let mut depth = 0;
+
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Text(ref e)) => texts.push(decode_text(e, rdr, opts)?),
+            Ok(Event::Start(ref e)) if e.name() == end => depth += 1,
+            Ok(Event::End(ref e)) if e.name() == end => {
+                if depth == 0 {
+                    break;
+                }
+                depth -= 1;
+            },
+            Ok(Event::Eof) => return Err(Error::deser(format!("eof before end of {:?}", std::str::from_utf8(end)))),
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+    }
+
+    Ok(texts.join(" "))
+}
+
+fn deser_pdat_date_from<B: BufRead>(end: &[u8], rdr: &mut quick_xml::Reader<B>, buf: &mut Vec<u8>, opts: &ParserOptions) -> Result<Date, Error> {
+    let txt = deser_pdat_from(end, rdr, buf, opts)?;
+
+    Date::from_text(txt, end)
+}
+
+fn deser_pdat_u32_from<B: BufRead>(end: &[u8], rdr: &mut quick_xml::Reader<B>, buf: &mut Vec<u8>, opts: &ParserOptions) -> Result<u32, Error> {
+    let txt = deser_pdat_from(end, rdr, buf, opts)?;
+
+    txt.parse()
+        .map_err(|err| Error::deser(format!("err: {} parsing {:?} in {:?}, position: {}", err, txt, std::str::from_utf8(end), rdr.buffer_position())))
+}
+
+/// skips over an element whose start tag was just read, without decoding it
+fn skip_element<B: BufRead>(name: Vec<u8>, rdr: &mut quick_xml::Reader<B>, buf: &mut Vec<u8>) -> Result<(), Error> {
+    rdr.read_to_end(name, buf)
+        .map_err(|err| Error::deser(err.to_string()))
+}
+
+/// The options PATDOC text is decoded with. Its text is full of entities its
+/// dtd declares (e.g. `&deg;`), so those are always resolved, as with
+/// `Entities::Dtd`, unless the options keep text `Raw`; the decoding is the
+/// options'.
+fn pdat_options(opts: &ParserOptions) -> ParserOptions {
+    match opts.entities {
+        Entities::Raw => opts.clone(),
+        _ => opts.clone().entities(Entities::Dtd),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::PatentGrants;
+    use crate::options::{Decoding, Entities, ParserOptions};
+    use crate::data::{date_text, CountryCode, KindCode, Uspc};
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/grant_v25.xml");
+
+    #[test]
+    fn test_patdoc() {
+        let grants = PatentGrants::from_reader(FIXTURE.as_bytes())
+            .strict(true)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(grants.len(), 1);
+
+        let pg = &grants[0];
+        assert_eq!(pg.meta.dtd_version.as_deref(), Some("2.5"));
+        assert_eq!(pg.meta.date_publ.as_deref(), Some("20020101"));
+
+        let biblio = &pg.us_bibliographic_data_grant;
+        assert_eq!(biblio.publication_reference.doc_number, "06334853");
+        assert_eq!(biblio.publication_reference.kind, Some(KindCode::B1));
+        assert_eq!(biblio.publication_reference.country, CountryCode::US);
+        assert_eq!(biblio.application_reference.doc_number, "09084742");
+        assert_eq!(date_text(&biblio.application_reference.date), "19980526");
+        assert_eq!(biblio.us_application_series_code, "09");
+
+        assert_eq!(biblio.priority_claims.len(), 1);
+        assert_eq!(biblio.priority_claims[0].country, CountryCode::from("JP"));
+        assert_eq!(biblio.priority_claims[0].doc_number.as_deref(), Some("9-140123"));
+
+        assert_eq!(biblio.classifications_ipcr.len(), 2);
+        let ipc = &biblio.classifications_ipcr[0];
+        assert_eq!((&ipc.section[..], &ipc.class[..], &ipc.subclass[..]), ("A", "01", "B"));
+        assert_eq!(ipc.main_group.as_deref(), Some("1"));
+        assert_eq!(ipc.subgroup.as_deref(), Some("00"));
+        assert_eq!(ipc.ipc_version_indicator, "7");
+        assert_eq!(biblio.classification_national.main_classification, Uspc::new("172", "13"));
+        assert_eq!(biblio.classification_national.further_classification, Some(Uspc::new("294", "49")));
+        assert_eq!(biblio.invention_title.title, "Hand tool with ergonomic handle");
+
+        let citations = &biblio.us_references_cited;
+        assert_eq!(citations.len(), 2);
+        match &citations[0].citation {
+            crate::data::Citation::Patent(patcit) => {
+                assert_eq!(patcit.document_id.doc_number, "4418955");
+                assert_eq!(patcit.document_id.country, CountryCode::US);
+                assert_eq!(patcit.document_id.name.as_deref(), Some("Muckle"));
+            },
+            other => panic!("expected patcit, got {:?}", other),
+        }
+        assert_eq!(citations[0].category.as_deref(), Some("cited by examiner"));
+        assert_eq!(citations[1].category.as_deref(), Some("cited by other"));
+
+        assert_eq!(biblio.number_of_claims, Some(2));
+        assert_eq!(biblio.us_exemplary_claim, vec![1]);
+        assert_eq!(biblio.us_field_of_classification_search.classification_nationals.len(), 2);
+        assert_eq!(biblio.figures.number_of_figures, Some(5));
+
+        assert_eq!(biblio.inventors.len(), 2);
+        assert_eq!(biblio.inventors[0].addressbook.last_name.as_deref(), Some("Tanaka"));
+        assert_eq!(biblio.inventors[1].addressbook.address.country, Some(CountryCode::US));
+        assert_eq!(biblio.assignees[0].orgname.as_deref(), Some("ACME TOOL CO., LTD."));
+        assert_eq!(biblio.assignees[0].role.as_deref(), Some("03"));
+        assert_eq!(biblio.agents[0].addressbook.orgname.as_deref(), Some("Example & Partners LLP"));
+        assert_eq!(biblio.examiners.primary_examiner.last_name, "Novosad");
+
+        assert_eq!(pg.r#abstract.len(), 1);
+        assert!(pg.descriptions["DETDESC"].contains("made of steel, about 5\u{b0} off axis"));
+        assert_eq!(pg.us_claim_statement, "What is claimed is:");
+        assert_eq!(pg.claims.len(), 2);
+        assert_eq!(pg.claims[1].num, "00002");
+        assert!(pg.claims[1].claim_texts[0].text.starts_with("2. The hand tool of claim 1"));
+    }
+    #[test]
+    fn test_patdoc_decoding() {
+        let first = |input: &[u8], options| PatentGrants::with_options(input, options).next().unwrap();
+
+        // a stray Windows-1252 right quote (marked by \u{1} until it's put
+        // in) in the title
+        let stray: Vec<u8> = FIXTURE
+            .replacen("Hand tool with ", "Hand\u{1}s tool with ", 1)
+            .bytes()
+            .map(|b| if b == 1 { 0x92 } else { b })
+            .collect();
+
+        let grant = first(&stray, ParserOptions::default()).unwrap();
+        assert_eq!(grant.us_bibliographic_data_grant.invention_title.title, "Hand\u{fffd}s tool with ergonomic handle");
+        assert!(first(&stray, ParserOptions::default().decoding(Decoding::Strict)).is_err());
+        let grant = first(&stray, ParserOptions::default().decoding(Decoding::Fallback(encoding_rs::WINDOWS_1252))).unwrap();
+        assert_eq!(grant.us_bibliographic_data_grant.invention_title.title, "Hand\u{2019}s tool with ergonomic handle");
+
+        // the dtd's entities are resolved, unless text is kept raw
+        let grant = first(FIXTURE.as_bytes(), ParserOptions::default().entities(Entities::Raw)).unwrap();
+        assert!(grant.descriptions["DETDESC"].contains("about 5&deg; off axis"));
+    }
+}
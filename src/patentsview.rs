@@ -0,0 +1,490 @@
+//! The PatentsView api (PatentSearch, `search.patentsview.org`): grants'
+//! disambiguated inventors and assignees and current CPC, queried live,
+//! for joining to (or filling in) grants parsed from the bulk files.
+//!
+//! Queries are the api's json, built with `PatentsViewQuery`. Results are
+//! paged through by patent_id, the api's cursor, and requests are spaced
+//! out to the api's rate limit (45 a minute by default); a response that
+//! the limit was hit anyway (429) is retried after the time it asks for.
+//! A `PvPatent` can be made into a `PatentGrant` of the fields it has (see
+//! `PvPatent::to_grant`).
+
+use serde::Deserialize;
+use snafu::ResultExt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::data::*;
+use crate::docnum::{application_number, grant_number, padded_grant_number};
+use crate::error::{Error, PatentsView};
+use crate::util::FromText;
+
+const PATENTSVIEW_URL: &str = "https://search.patentsview.org/api/v1/patent/";
+
+/// the api's maximum page size
+const MAX_SIZE: usize = 1000;
+/// times a rate-limited request is retried
+const RETRIES: usize = 3;
+
+/// A query, in the api's json
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatentsViewQuery(serde_json::Value);
+
+impl PatentsViewQuery {
+    /// the field equal to the value
+    pub fn eq<V: Into<serde_json::Value>>(field: &str, value: V) -> Self {
+        PatentsViewQuery(serde_json::json!({ field: value.into() }))
+    }
+
+    /// an operator on a field, e.g. `_gte`, `_text_any`
+    pub fn op<V: Into<serde_json::Value>>(op: &str, field: &str, value: V) -> Self {
+        PatentsViewQuery(serde_json::json!({ op: { field: value.into() } }))
+    }
+
+    pub fn gte<V: Into<serde_json::Value>>(field: &str, value: V) -> Self {
+        Self::op("_gte", field, value)
+    }
+
+    pub fn lte<V: Into<serde_json::Value>>(field: &str, value: V) -> Self {
+        Self::op("_lte", field, value)
+    }
+
+    /// any of the words in the field's text
+    pub fn text_any(field: &str, words: &str) -> Self {
+        Self::op("_text_any", field, words)
+    }
+
+    pub fn and(queries: Vec<PatentsViewQuery>) -> Self {
+        PatentsViewQuery(serde_json::json!({ "_and": queries.into_iter().map(|query| query.0).collect::<Vec<_>>() }))
+    }
+
+    pub fn or(queries: Vec<PatentsViewQuery>) -> Self {
+        PatentsViewQuery(serde_json::json!({ "_or": queries.into_iter().map(|query| query.0).collect::<Vec<_>>() }))
+    }
+
+    /// grants by (padded or unpadded) number
+    pub fn patent_ids(patent_ids: &[&str]) -> Self {
+        Self::or(patent_ids.iter()
+            .map(|patent_id| Self::eq("patent_id", grant_number(patent_id)))
+            .collect())
+    }
+
+    pub fn as_json(&self) -> &serde_json::Value {
+        &self.0
+    }
+}
+
+/// `!query`, the api's `_not`
+impl std::ops::Not for PatentsViewQuery {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        PatentsViewQuery(serde_json::json!({ "_not": self.0 }))
+    }
+}
+
+/// The fields requested, all those of `PvPatent`
+const FIELDS: &[&str] = &[
+    "patent_id", "patent_title", "patent_date", "patent_type", "patent_abstract",
+    "application.application_id", "application.filing_date",
+    "inventors.inventor_sequence", "inventors.inventor_name_first", "inventors.inventor_name_last",
+    "inventors.inventor_city", "inventors.inventor_state", "inventors.inventor_country",
+    "assignees.assignee_organization", "assignees.assignee_individual_name_first",
+    "assignees.assignee_individual_name_last", "assignees.assignee_city", "assignees.assignee_state",
+    "assignees.assignee_country",
+    "cpc_current.cpc_sequence", "cpc_current.cpc_group_id",
+];
+
+/// A page's request: the query, sorted by patent_id, after the last page's
+fn page_request(query: &PatentsViewQuery, size: usize, after: Option<&str>) -> serde_json::Value {
+    let mut options = serde_json::json!({ "size": size.min(MAX_SIZE) });
+    if let Some(after) = after {
+        options["after"] = serde_json::json!(after);
+    }
+
+    serde_json::json!({
+        "q": query.0,
+        "f": FIELDS,
+        "s": [{ "patent_id": "asc" }],
+        "o": options,
+    })
+}
+
+/// A grant, as PatentsView has it
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct PvPatent {
+    /// unpadded, e.g. "10165721", "D838001"
+    pub patent_id: String,
+    pub patent_title: Option<String>,
+    /// YYYY-MM-DD
+    pub patent_date: Option<String>,
+    /// e.g. "utility", "design"
+    pub patent_type: Option<String>,
+    pub patent_abstract: Option<String>,
+    pub application: Vec<PvApplication>,
+    pub inventors: Vec<PvInventor>,
+    pub assignees: Vec<PvAssignee>,
+    pub cpc_current: Vec<PvCpc>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct PvApplication {
+    /// e.g. "15/123456"
+    pub application_id: Option<String>,
+    pub filing_date: Option<String>,
+}
+
+/// Disambiguated across grants
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct PvInventor {
+    pub inventor_sequence: Option<u32>,
+    pub inventor_name_first: Option<String>,
+    pub inventor_name_last: Option<String>,
+    pub inventor_city: Option<String>,
+    pub inventor_state: Option<String>,
+    pub inventor_country: Option<String>,
+}
+
+/// Disambiguated across grants; an organization, or else a person
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct PvAssignee {
+    pub assignee_organization: Option<String>,
+    pub assignee_individual_name_first: Option<String>,
+    pub assignee_individual_name_last: Option<String>,
+    pub assignee_city: Option<String>,
+    pub assignee_state: Option<String>,
+    pub assignee_country: Option<String>,
+}
+
+/// A current CPC group, reclassified since the grant
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct PvCpc {
+    /// 0 for the main
+    pub cpc_sequence: Option<u32>,
+    /// e.g. "A01B1/00"
+    pub cpc_group_id: Option<String>,
+}
+
+impl PvCpc {
+    fn to_cpc(&self) -> Option<ClassificationCpc> {
+        let group_id = self.cpc_group_id.as_deref()?.trim();
+        let (group, subgroup) = group_id.split_once('/')?;
+        if group.len() < 5 || !group.is_ascii() {
+            return None;
+        }
+        let (subclass, main_group) = group.split_at(4);
+
+        Some(ClassificationCpc {
+            section: subclass[..1].to_string(),
+            class: subclass[1..3].to_string(),
+            subclass: subclass[3..].to_string(),
+            main_group: main_group.trim().to_string(),
+            subgroup: subgroup.trim().to_string(),
+            ..ClassificationCpc::default()
+        })
+    }
+}
+
+/// YYYY-MM-DD as a Date
+fn pv_date(text: &str, field: &str) -> Result<Date, Error> {
+    Date::from_text(text.trim().replace('-', ""), field.as_bytes())
+}
+
+impl PvPatent {
+    /// Padded as grants' doc-numbers are, for joining to them
+    pub fn grant_number(&self) -> String {
+        padded_grant_number(&self.patent_id)
+    }
+
+    /// The grant, of the fields PatentsView has: its number and date, the
+    /// application's, title, abstract, inventors, assignees and (current)
+    /// cpc. Everything else is left at the default.
+    pub fn to_grant(&self) -> Result<PatentGrant, Error> {
+        let mut patent_grant = PatentGrant::default();
+        patent_grant.meta.country = "US".to_string();
+        patent_grant.meta.date_publ = self.patent_date.as_ref().map(|date| date.replace('-', ""));
+
+        let biblio = &mut patent_grant.us_bibliographic_data_grant;
+        biblio.publication_reference.country = CountryCode::US;
+        biblio.publication_reference.doc_number = self.grant_number();
+        if let Some(date) = &self.patent_date {
+            biblio.publication_reference.date = pv_date(date, "patent_date")?;
+        }
+
+        if let Some(application) = self.application.first() {
+            biblio.application_reference.country = CountryCode::US;
+            biblio.application_reference.doc_number = application.application_id.as_deref()
+                .map(|application_id| application_number(application_id, None).unwrap_or_else(|| application_id.to_string()))
+                .unwrap_or_default();
+            if let Some(date) = &application.filing_date {
+                biblio.application_reference.date = pv_date(date, "filing_date")?;
+            }
+        }
+
+        biblio.invention_title.title = self.patent_title.clone().unwrap_or_default();
+
+        let mut cpcs = self.cpc_current.iter().collect::<Vec<_>>();
+        cpcs.sort_by_key(|cpc| cpc.cpc_sequence);
+        let mut cpcs = cpcs.into_iter().filter_map(PvCpc::to_cpc);
+        if let Some(main_cpc) = cpcs.next() {
+            biblio.classifications_cpc.main_cpc = main_cpc;
+        }
+        biblio.classifications_cpc.further_cpc = cpcs.collect();
+
+        biblio.inventors = self.inventors.iter()
+            .map(|inventor| Inventor {
+                sequence: inventor.inventor_sequence.map(|sequence| sequence.to_string()).unwrap_or_default(),
+                addressbook: AddressBook {
+                    first_name: inventor.inventor_name_first.clone(),
+                    last_name: inventor.inventor_name_last.clone(),
+                    address: Address {
+                        city: inventor.inventor_city.clone(),
+                        state: inventor.inventor_state.clone(),
+                        country: inventor.inventor_country.as_deref().map(CountryCode::from),
+                    },
+                    ..AddressBook::default()
+                },
+                ..Inventor::default()
+            })
+            .collect();
+
+        biblio.assignees = self.assignees.iter()
+            .map(|assignee| Assignee {
+                orgname: assignee.assignee_organization.clone(),
+                addressbook: AddressBook {
+                    orgname: assignee.assignee_organization.clone(),
+                    first_name: assignee.assignee_individual_name_first.clone(),
+                    last_name: assignee.assignee_individual_name_last.clone(),
+                    address: Address {
+                        city: assignee.assignee_city.clone(),
+                        state: assignee.assignee_state.clone(),
+                        country: assignee.assignee_country.as_deref().map(CountryCode::from),
+                    },
+                    ..AddressBook::default()
+                },
+                ..Assignee::default()
+            })
+            .collect();
+
+        if let Some(text) = &self.patent_abstract {
+            patent_grant.r#abstract.push(Paragraph {
+                text: text.clone(),
+                ..Paragraph::default()
+            });
+        }
+
+        Ok(patent_grant)
+    }
+}
+
+/// A page of results
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct PvPage {
+    pub patents: Vec<PvPatent>,
+    pub count: usize,
+    /// of the whole query
+    pub total_hits: usize,
+}
+
+impl PvPage {
+    /// The next page's cursor, if there may be one
+    pub fn after(&self, size: usize) -> Option<&str> {
+        if self.patents.len() < size.min(MAX_SIZE) {
+            return None;
+        }
+
+        self.patents.last().map(|patent| patent.patent_id.as_str())
+    }
+}
+
+/// Queries PatentsView, over a reqwest (async) client
+#[derive(Debug)]
+pub struct PatentsViewClient {
+    client: reqwest_async::Client,
+    url: String,
+    api_key: String,
+    interval: Duration,
+    // when the next request may be sent
+    next_request: Mutex<Option<Instant>>,
+}
+
+impl PatentsViewClient {
+    pub fn new(api_key: &str) -> Self {
+        Self::with_client(reqwest_async::Client::new(), PATENTSVIEW_URL, api_key)
+    }
+
+    /// Posts queries to url, a PatentSearch patent endpoint (e.g.
+    /// `https://search.patentsview.org/api/v1/patent/`), with client and
+    /// the api_key in each request's X-Api-Key header. Rate limiting is
+    /// done here (see with_rate_limit), not by the client.
+    pub fn with_client(client: reqwest_async::Client, url: &str, api_key: &str) -> Self {
+        PatentsViewClient {
+            client,
+            url: url.to_string(),
+            api_key: api_key.to_string(),
+            interval: Duration::from_secs(60) / 45,
+            next_request: Mutex::new(None),
+        }
+    }
+
+    /// Spaces requests out to this many a minute
+    pub fn with_rate_limit(mut self, requests_per_minute: u32) -> Self {
+        self.interval = Duration::from_secs(60) / requests_per_minute.max(1);
+        self
+    }
+
+    async fn throttle(&self) {
+        let wait = {
+            let mut next_request = self.next_request.lock().unwrap_or_else(|err| err.into_inner());
+            let now = Instant::now();
+            let at = next_request.map_or(now, |next_request| next_request.max(now));
+            *next_request = Some(at + self.interval);
+            at - now
+        };
+
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// A page of the query's results, of up to `size` (at most 1000) grants,
+    /// after the grant numbered `after` (see `PvPage::after`)
+    pub async fn page(&self, query: &PatentsViewQuery, size: usize, after: Option<&str>) -> Result<PvPage, Error> {
+        let body = page_request(query, size, after);
+
+        let mut retries = 0;
+        loop {
+            self.throttle().await;
+            let resp = self.client.post(&self.url)
+                .header("X-Api-Key", &self.api_key)
+                .json(&body)
+                .send()
+                .await
+                .context(PatentsView)?;
+
+            if resp.status() == reqwest_async::StatusCode::TOO_MANY_REQUESTS && retries < RETRIES {
+                let retry_after = resp.headers()
+                    .get(reqwest_async::header::RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.trim().parse::<u64>().ok())
+                    .map_or(self.interval, Duration::from_secs);
+                tokio::time::sleep(retry_after).await;
+                retries += 1;
+                continue;
+            }
+
+            return resp.error_for_status()
+                .context(PatentsView)?
+                .json()
+                .await
+                .context(PatentsView);
+        }
+    }
+
+    /// All of the query's results, up to `limit` grants, a page at a time
+    pub async fn patents(&self, query: &PatentsViewQuery, limit: usize) -> Result<Vec<PvPatent>, Error> {
+        let mut patents = Vec::new();
+        let mut after: Option<String> = None;
+
+        while patents.len() < limit {
+            let size = (limit - patents.len()).min(MAX_SIZE);
+            let page = self.page(query, size, after.as_deref()).await?;
+            after = page.after(size).map(str::to_string);
+            patents.extend(page.patents);
+
+            if after.is_none() {
+                break;
+            }
+        }
+        patents.truncate(limit);
+
+        Ok(patents)
+    }
+
+    /// The grants' PatentsView records, by (padded or unpadded) number; any
+    /// not in PatentsView are left out
+    pub async fn patents_by_number(&self, patent_numbers: &[&str]) -> Result<Vec<PvPatent>, Error> {
+        let mut patents = Vec::new();
+        for chunk in patent_numbers.chunks(MAX_SIZE) {
+            patents.extend(self.patents(&PatentsViewQuery::patent_ids(chunk), chunk.len()).await?);
+        }
+
+        Ok(patents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"{
+        "error": false,
+        "count": 1,
+        "total_hits": 1,
+        "patents": [
+            {
+                "patent_id": "10165721",
+                "patent_title": "Hand tool with ergonomic grip",
+                "patent_date": "2019-01-01",
+                "patent_type": "utility",
+                "patent_abstract": "A hand tool includes a body and a handle attached to the body.",
+                "application": [{"application_id": "15/123456", "filing_date": "2017-03-01"}],
+                "inventors": [
+                    {"inventor_sequence": 0, "inventor_name_first": "Hiro", "inventor_name_last": "Tanaka", "inventor_city": "Osaka", "inventor_country": "JP"}
+                ],
+                "assignees": [
+                    {"assignee_organization": "ACME TOOL CO., LTD.", "assignee_city": "Osaka", "assignee_country": "JP"}
+                ],
+                "cpc_current": [
+                    {"cpc_sequence": 1, "cpc_group_id": "B25G1/10"},
+                    {"cpc_sequence": 0, "cpc_group_id": "A01B1/00"}
+                ]
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_patentsview_page() {
+        let page: PvPage = serde_json::from_str(PAGE).unwrap();
+        assert_eq!(page.total_hits, 1);
+        assert_eq!(page.after(1), Some("10165721"));
+        assert_eq!(page.after(100), None);
+
+        let grant = page.patents[0].to_grant().unwrap();
+        let biblio = &grant.us_bibliographic_data_grant;
+        assert_eq!(biblio.publication_reference.doc_number, "10165721");
+        assert_eq!(date_text(&biblio.publication_reference.date), "20190101");
+        assert_eq!(biblio.application_reference.doc_number, "15123456");
+        assert_eq!(biblio.classifications_cpc.main_cpc.symbol(), "A01B 1/00");
+        assert_eq!(biblio.classifications_cpc.further_cpc[0].symbol(), "B25G 1/10");
+        assert_eq!(biblio.inventors[0].addressbook.last_name.as_deref(), Some("Tanaka"));
+        assert_eq!(biblio.assignees[0].orgname.as_deref(), Some("ACME TOOL CO., LTD."));
+        assert_eq!(grant.r#abstract.len(), 1);
+    }
+
+    #[test]
+    fn test_patentsview_query() {
+        let query = PatentsViewQuery::and(vec![
+            PatentsViewQuery::gte("patent_date", "2019-01-01"),
+            PatentsViewQuery::text_any("patent_title", "hand tool"),
+            !PatentsViewQuery::eq("patent_type", "design"),
+        ]);
+        assert_eq!(query.as_json(), &serde_json::json!({
+            "_and": [
+                {"_gte": {"patent_date": "2019-01-01"}},
+                {"_text_any": {"patent_title": "hand tool"}},
+                {"_not": {"patent_type": "design"}},
+            ]
+        }));
+
+        let request = page_request(&PatentsViewQuery::patent_ids(&["D0838001"]), 5000, Some("D838000"));
+        assert_eq!(request["q"], serde_json::json!({"_or": [{"patent_id": "D838001"}]}));
+        assert_eq!(request["o"], serde_json::json!({"size": 1000, "after": "D838000"}));
+    }
+}
@@ -0,0 +1,268 @@
+//! The Patent Examination Data System (PEDS): an application's prosecution
+//! as the USPTO has it now (its status, transaction history, and continuity),
+//! for grants parsed from bulk files, which only have it as of their issue.
+//!
+//! `PedsClient` queries the PEDS api by application number, as in grants'
+//! application-reference. Fields are read by the api's (camelCase) names,
+//! and any missing are left at None, or empty for lists. Dates are kept as
+//! the api writes them.
+
+use serde::Deserialize;
+use snafu::ResultExt;
+
+use crate::data::PatentGrant;
+use crate::error::{Error, Peds};
+
+const PEDS_URL: &str = "https://ped.uspto.gov/api/queries";
+
+/// An application, as in PEDS
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PedsApplication {
+    #[serde(rename = "applId")]
+    pub application_number: String,
+    /// e.g. "Utility", "Design"
+    pub app_type: Option<String>,
+    /// e.g. "Patented Case", "Abandoned -- Failure to Respond to an Office Action"
+    #[serde(rename = "appStatus")]
+    pub status: Option<String>,
+    #[serde(rename = "appStatusDate")]
+    pub status_date: Option<String>,
+    #[serde(rename = "appFilingDate")]
+    pub filing_date: Option<String>,
+    #[serde(rename = "appGrpArtNumber")]
+    pub art_unit: Option<String>,
+    #[serde(rename = "appExamName")]
+    pub examiner_name: Option<String>,
+    #[serde(rename = "appEarlyPubNumber")]
+    pub publication_number: Option<String>,
+    #[serde(rename = "patentTitle")]
+    pub title: Option<String>,
+    /// once granted, usually unpadded
+    pub patent_number: Option<String>,
+    pub patent_issue_date: Option<String>,
+
+    /// newest first, as given
+    #[serde(default)]
+    pub transactions: Vec<PedsTransaction>,
+    /// the applications it claims the benefit of
+    #[serde(default)]
+    pub parent_continuity: Vec<Continuity>,
+    /// the applications claiming the benefit of it
+    #[serde(default)]
+    pub child_continuity: Vec<Continuity>,
+}
+
+impl PedsApplication {
+    pub fn is_patented(&self) -> bool {
+        self.patent_number.as_deref().is_some_and(|patent_number| !patent_number.trim().is_empty())
+    }
+}
+
+/// An event in prosecution, e.g. a mailed office action or a payment
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PedsTransaction {
+    pub record_date: Option<String>,
+    /// e.g. "CTNF", "N/=.", "WPIR"
+    pub code: Option<String>,
+    pub description: Option<String>,
+}
+
+/// A benefit claim between two applications. In a parent's continuity, the
+/// claiming application is the one queried and the application is its
+/// parent; in a child's, the other way around.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Continuity {
+    #[serde(rename = "claimApplicationNumberText")]
+    pub claiming_application_number: Option<String>,
+    #[serde(rename = "applicationNumberText")]
+    pub application_number: Option<String>,
+    pub filing_date: Option<String>,
+    #[serde(rename = "patentNumberText")]
+    pub patent_number: Option<String>,
+    #[serde(rename = "applicationStatusDescription")]
+    pub status: Option<String>,
+    /// e.g. "CON" (continuation), "DIV" (division), "CIP", "PRO" (a
+    /// provisional)
+    #[serde(rename = "claimParentageTypeCode")]
+    pub claim_type: Option<String>,
+    #[serde(rename = "claimParentageTypeCodeDescription")]
+    pub claim_type_description: Option<String>,
+}
+
+/// The api's response, around the applications found
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QueryResponse {
+    query_results: QueryResults,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QueryResults {
+    search_response: SearchResponse,
+}
+
+#[derive(Deserialize)]
+struct SearchResponse {
+    response: Response,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    #[serde(default)]
+    docs: Vec<PedsApplication>,
+}
+
+/// The query's body, for all (and only) the applications
+fn query(application_numbers: &[&str]) -> serde_json::Value {
+    let ids = application_numbers.iter()
+        .map(|application_number| application_number.trim())
+        .collect::<Vec<_>>()
+        .join(" OR ");
+
+    serde_json::json!({
+        "searchText": format!("applId:({})", ids),
+        "fl": "*",
+        "mm": "100%",
+        "df": "patentTitle",
+        "qf": "applId",
+        "facet": "false",
+        "sort": "applId asc",
+        "start": "0",
+        "rows": application_numbers.len().to_string(),
+    })
+}
+
+/// Queries PEDS, over a reqwest (async) client
+#[derive(Debug, Clone)]
+pub struct PedsClient {
+    client: reqwest_async::Client,
+    url: String,
+}
+
+impl Default for PedsClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PedsClient {
+    pub fn new() -> Self {
+        Self::with_client(reqwest_async::Client::new(), PEDS_URL)
+    }
+
+    /// Posts each search, for the application numbers asked for at once,
+    /// to url (a PEDS queries endpoint like `https://ped.uspto.gov/api/queries`)
+    /// with client, whose timeout is then that of a whole search
+    pub fn with_client(client: reqwest_async::Client, url: &str) -> Self {
+        PedsClient {
+            client,
+            url: url.to_string(),
+        }
+    }
+
+    /// The applications found, in application number order; any not in
+    /// PEDS are left out
+    pub async fn applications(&self, application_numbers: &[&str]) -> Result<Vec<PedsApplication>, Error> {
+        if application_numbers.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let resp = self.client.post(&self.url)
+            .json(&query(application_numbers))
+            .send()
+            .await
+            .and_then(|resp| resp.error_for_status())
+            .context(Peds)?;
+        let resp: QueryResponse = resp.json()
+            .await
+            .context(Peds)?;
+
+        Ok(resp.query_results.search_response.response.docs)
+    }
+
+    pub async fn application(&self, application_number: &str) -> Result<Option<PedsApplication>, Error> {
+        let mut applications = self.applications(&[application_number]).await?;
+
+        Ok(applications.pop())
+    }
+
+    /// The grant's application, by its application-reference
+    pub async fn grant_application(&self, patent_grant: &PatentGrant) -> Result<Option<PedsApplication>, Error> {
+        self.application(&patent_grant.us_bibliographic_data_grant.application_reference.doc_number).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESPONSE: &str = r#"{
+        "queryResults": {
+            "searchResponse": {
+                "response": {
+                    "numFound": 1,
+                    "start": 0,
+                    "docs": [
+                        {
+                            "applId": "15123456",
+                            "appType": "Utility",
+                            "appStatus": "Patented Case",
+                            "appStatusDate": "2018-12-12T09:01:12Z",
+                            "appFilingDate": "2017-03-01T00:00:00Z",
+                            "appGrpArtNumber": "3671",
+                            "patentNumber": "10165721",
+                            "patentIssueDate": "2019-01-01T00:00:00Z",
+                            "patentTitle": "Hand tool with ergonomic grip",
+                            "transactions": [
+                                {"recordDate": "2019-01-01T00:00:00Z", "code": "PTAC", "description": "Patent Issue Date Used in PTA Calculation"},
+                                {"recordDate": "2018-03-01T00:00:00Z", "code": "CTNF", "description": "Non-Final Rejection"}
+                            ],
+                            "parentContinuity": [
+                                {
+                                    "claimApplicationNumberText": "15123456",
+                                    "applicationNumberText": "14500123",
+                                    "filingDate": "2014-09-29",
+                                    "applicationStatusDescription": "Patented Case",
+                                    "patentNumberText": "9876543",
+                                    "claimParentageTypeCode": "CIP",
+                                    "claimParentageTypeCodeDescription": "is a Continuation in part of"
+                                }
+                            ]
+                        }
+                    ]
+                }
+            }
+        }
+    }"#;
+
+    #[test]
+    fn test_peds_response() {
+        let resp: QueryResponse = serde_json::from_str(RESPONSE).unwrap();
+        let response = resp.query_results.search_response.response;
+        assert_eq!(response.docs.len(), 1);
+
+        let application = &response.docs[0];
+        assert_eq!(application.application_number, "15123456");
+        assert!(application.is_patented());
+        assert_eq!(application.status.as_deref(), Some("Patented Case"));
+        assert_eq!(application.art_unit.as_deref(), Some("3671"));
+        assert_eq!(application.transactions.len(), 2);
+        assert_eq!(application.transactions[1].code.as_deref(), Some("CTNF"));
+        assert!(application.child_continuity.is_empty());
+
+        let parent = &application.parent_continuity[0];
+        assert_eq!(parent.application_number.as_deref(), Some("14500123"));
+        assert_eq!(parent.claim_type.as_deref(), Some("CIP"));
+    }
+
+    #[test]
+    fn test_peds_query() {
+        let query = query(&["15123456", " 14500123"]);
+        assert_eq!(query["searchText"], "applId:(15123456 OR 14500123)");
+        assert_eq!(query["rows"], "2");
+    }
+}
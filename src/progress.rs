@@ -0,0 +1,213 @@
+//! Progress reporting for long parses: a `ProgressObserver` is told how far
+//! a parse has got after each document, see `LenientPatentGrants::observe`.
+//!
+//! By default the bytes read are of the documents parsed, as the parser
+//! sees them (decompressed). Reading the input through a `ProgressReader`
+//! reports its position in the file instead (compressed, for gzip and zip),
+//! which with the file's length gives an ETA.
+
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How far a parse has got
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress {
+    pub bytes_read: u64,
+    /// of the input, if known
+    pub total_bytes: Option<u64>,
+    /// parsed, including those that failed
+    pub documents: u64,
+    pub errors: u64,
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    /// The time left at the rate so far, if the total is known
+    pub fn eta(&self) -> Option<Duration> {
+        let total_bytes = self.total_bytes?;
+        if self.bytes_read == 0 {
+            return None;
+        }
+
+        let left = total_bytes.saturating_sub(self.bytes_read) as f64;
+        Some(self.elapsed.mul_f64(left / self.bytes_read as f64))
+    }
+}
+
+/// Called by the iterator as it parses
+pub trait ProgressObserver {
+    /// After each document
+    fn progress(&mut self, progress: &Progress);
+
+    /// Once the input's done
+    fn finish(&mut self, _progress: &Progress) {}
+}
+
+/// The position of a ProgressReader in its input, shared with it
+#[derive(Debug, Clone, Default)]
+pub struct BytesRead(Arc<AtomicU64>);
+
+impl BytesRead {
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A reader keeping its position for a BytesRead, e.g. around a bulk file
+/// for `PatentGrants::from_seekable`
+#[derive(Debug)]
+pub struct ProgressReader<R> {
+    inner: R,
+    position: BytesRead,
+}
+
+impl<R> ProgressReader<R> {
+    pub fn new(inner: R) -> Self {
+        ProgressReader {
+            inner,
+            position: BytesRead::default(),
+        }
+    }
+
+    pub fn bytes_read(&self) -> BytesRead {
+        self.position.clone()
+    }
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.position.0.fetch_add(read as u64, Ordering::Relaxed);
+
+        Ok(read)
+    }
+}
+
+impl<R: Seek> Seek for ProgressReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = self.inner.seek(pos)?;
+        self.position.0.store(position, Ordering::Relaxed);
+
+        Ok(position)
+    }
+}
+
+/// An observer, and the progress it's told of
+pub(crate) struct Observer {
+    observer: Box<dyn ProgressObserver + Send>,
+    bytes_read: Option<BytesRead>,
+    total_bytes: Option<u64>,
+    start: Instant,
+    documents: u64,
+    errors: u64,
+}
+
+impl Observer {
+    pub(crate) fn new<O: ProgressObserver + Send + 'static>(observer: O) -> Self {
+        Observer {
+            observer: Box::new(observer),
+            bytes_read: None,
+            total_bytes: None,
+            start: Instant::now(),
+            documents: 0,
+            errors: 0,
+        }
+    }
+
+    pub(crate) fn input(&mut self, bytes_read: BytesRead, total_bytes: Option<u64>) {
+        self.bytes_read = Some(bytes_read);
+        self.total_bytes = total_bytes;
+    }
+
+    /// offset is the bytes of documents read so far
+    pub(crate) fn document(&mut self, offset: u64, failed: bool) {
+        self.documents += 1;
+        if failed {
+            self.errors += 1;
+        }
+
+        let progress = self.progress(offset);
+        self.observer.progress(&progress);
+    }
+
+    pub(crate) fn finish(&mut self, offset: u64) {
+        let progress = self.progress(offset);
+        self.observer.finish(&progress);
+    }
+
+    fn progress(&self, offset: u64) -> Progress {
+        Progress {
+            bytes_read: self.bytes_read.as_ref().map_or(offset, BytesRead::get),
+            total_bytes: self.total_bytes,
+            documents: self.documents,
+            errors: self.errors,
+            elapsed: self.start.elapsed(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    use crate::PatentGrants;
+
+    const FIXTURE_PATH: &str = "tests/fixtures/grants.xml";
+
+    #[derive(Clone, Default)]
+    struct Recorder {
+        progress: Arc<Mutex<Vec<Progress>>>,
+        finished: Arc<Mutex<Option<Progress>>>,
+    }
+
+    impl ProgressObserver for Recorder {
+        fn progress(&mut self, progress: &Progress) {
+            self.progress.lock().unwrap().push(progress.clone());
+        }
+
+        fn finish(&mut self, progress: &Progress) {
+            *self.finished.lock().unwrap() = Some(progress.clone());
+        }
+    }
+
+    #[test]
+    fn test_observe() {
+        let file_len = std::fs::metadata(FIXTURE_PATH).unwrap().len();
+        let rdr = ProgressReader::new(std::fs::File::open(FIXTURE_PATH).unwrap());
+        let bytes_read = rdr.bytes_read();
+
+        let recorder = Recorder::default();
+        let grants = PatentGrants::from_seekable(rdr).unwrap()
+            .lenient()
+            .observe(recorder.clone())
+            .input_progress(bytes_read, Some(file_len));
+        let parsed = grants.count() as u64;
+
+        let progress = recorder.progress.lock().unwrap();
+        assert_eq!(progress.len() as u64, parsed);
+        assert_eq!(progress.iter().map(|progress| progress.documents).collect::<Vec<_>>(), (1..=parsed).collect::<Vec<_>>());
+        assert!(progress.iter().all(|progress| progress.errors == 0));
+
+        let finished = recorder.finished.lock().unwrap().clone().unwrap();
+        assert_eq!(finished.documents, parsed);
+        assert_eq!(finished.bytes_read, file_len);
+        assert_eq!(finished.eta(), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_eta() {
+        let progress = Progress {
+            bytes_read: 250,
+            total_bytes: Some(1000),
+            documents: 10,
+            errors: 0,
+            elapsed: Duration::from_secs(10),
+        };
+        assert_eq!(progress.eta(), Some(Duration::from_secs(30)));
+        assert_eq!(Progress { total_bytes: None, ..progress.clone() }.eta(), None);
+        assert_eq!(Progress { bytes_read: 0, ..progress }.eta(), None);
+    }
+}
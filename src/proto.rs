@@ -0,0 +1,1339 @@
+//! Protobuf messages of the grant model, as defined in `proto/uspto.proto`,
+//! for gRPC pipelines and other languages' consumers; convert a parsed grant
+//! with `proto::PatentGrant::from(grant)` and encode it with
+//! `prost::Message`.
+//!
+//! The messages are written out here, as prost-build would generate them,
+//! rather than generated at build time, so building doesn't need protoc.
+//! They must be kept in sync with the `.proto` file. Dates are YYYYMMDD
+//! text, empty when missing, whether or not dates are typed.
+//!
+//! The Parser service's requests and responses are here too, and with the
+//! `grpc` feature its server, `parser_server`, see `grpc::ParseService`.
+
+use std::collections::HashMap;
+
+use crate::data;
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PatentGrant {
+    #[prost(message, optional, tag = "1")]
+    pub meta: Option<GrantMeta>,
+    #[prost(message, optional, tag = "2")]
+    pub us_bibliographic_data_grant: Option<BibliographicDataGrant>,
+    #[prost(message, repeated, tag = "3")]
+    pub r#abstract: Vec<Paragraph>,
+    #[prost(map = "string, string", tag = "4")]
+    pub descriptions: HashMap<String, String>,
+    #[prost(map = "string, string", tag = "5")]
+    pub descriptions_raw: HashMap<String, String>,
+    #[prost(string, optional, tag = "6")]
+    pub government_interest: Option<String>,
+    #[prost(message, repeated, tag = "7")]
+    pub chemistry: Vec<Chemistry>,
+    #[prost(message, optional, tag = "8")]
+    pub sequence_listing: Option<SequenceListing>,
+    #[prost(string, tag = "9")]
+    pub us_claim_statement: String,
+    #[prost(message, repeated, tag = "10")]
+    pub claims: Vec<Claim>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Paragraph {
+    #[prost(string, tag = "1")]
+    pub id: String,
+    #[prost(string, tag = "2")]
+    pub num: String,
+    #[prost(string, tag = "3")]
+    pub text: String,
+    #[prost(message, repeated, tag = "4")]
+    pub formulae: Vec<Formula>,
+    #[prost(message, repeated, tag = "5")]
+    pub figrefs: Vec<FigRef>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FigRef {
+    #[prost(string, tag = "1")]
+    pub idref: String,
+    #[prost(string, tag = "2")]
+    pub text: String,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Chemistry {
+    #[prost(string, tag = "1")]
+    pub id: String,
+    #[prost(string, tag = "2")]
+    pub num: String,
+    #[prost(string, repeated, tag = "3")]
+    pub files: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SequenceListing {
+    #[prost(string, repeated, tag = "1")]
+    pub ids: Vec<String>,
+    #[prost(string, repeated, tag = "2")]
+    pub files: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Formula {
+    #[prost(string, tag = "1")]
+    pub id: String,
+    #[prost(string, tag = "2")]
+    pub num: String,
+    #[prost(string, tag = "3")]
+    pub mathml: String,
+    #[prost(string, optional, tag = "4")]
+    pub latex: Option<String>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Claim {
+    #[prost(string, tag = "1")]
+    pub id: String,
+    #[prost(string, tag = "2")]
+    pub num: String,
+    #[prost(string, optional, tag = "3")]
+    pub claim_type: Option<String>,
+    #[prost(message, repeated, tag = "4")]
+    pub claim_texts: Vec<ClaimText>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ClaimText {
+    #[prost(string, tag = "1")]
+    pub text: String,
+    #[prost(message, repeated, tag = "2")]
+    pub claim_refs: Vec<ClaimRef>,
+    #[prost(message, repeated, tag = "3")]
+    pub formulae: Vec<Formula>,
+    #[prost(message, repeated, tag = "4")]
+    pub chemistry: Vec<Chemistry>,
+    #[prost(message, repeated, tag = "5")]
+    pub figrefs: Vec<FigRef>,
+    #[prost(message, repeated, tag = "6")]
+    pub claim_texts: Vec<ClaimText>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ClaimRef {
+    #[prost(string, tag = "1")]
+    pub idref: String,
+    #[prost(string, tag = "2")]
+    pub text: String,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GrantMeta {
+    #[prost(string, tag = "1")]
+    pub lang: String,
+    #[prost(string, optional, tag = "2")]
+    pub dtd_version: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    pub file: Option<String>,
+    #[prost(string, optional, tag = "4")]
+    pub status: Option<String>,
+    #[prost(string, optional, tag = "5")]
+    pub id: Option<String>,
+    #[prost(string, tag = "6")]
+    pub country: String,
+    #[prost(string, optional, tag = "7")]
+    pub file_reference_id: Option<String>,
+    #[prost(string, optional, tag = "8")]
+    pub date_produced: Option<String>,
+    #[prost(string, optional, tag = "9")]
+    pub date_publ: Option<String>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BibliographicDataGrant {
+    #[prost(message, optional, tag = "1")]
+    pub publication_reference: Option<DocumentId>,
+    #[prost(message, optional, tag = "2")]
+    pub application_reference: Option<DocumentId>,
+    #[prost(string, tag = "3")]
+    pub us_application_series_code: String,
+    #[prost(message, repeated, tag = "4")]
+    pub priority_claims: Vec<PriorityClaim>,
+    #[prost(message, optional, tag = "5")]
+    pub us_term_of_grant: Option<UsTermOfGrant>,
+    #[prost(message, repeated, tag = "6")]
+    pub classifications_ipcr: Vec<ClassificationIpcr>,
+    #[prost(message, optional, tag = "7")]
+    pub classifications_cpc: Option<ClassificationsCpc>,
+    #[prost(message, optional, tag = "8")]
+    pub classification_locarno: Option<ClassificationLocarno>,
+    #[prost(message, optional, tag = "9")]
+    pub classification_national: Option<ClassificationNational>,
+    #[prost(message, optional, tag = "10")]
+    pub invention_title: Option<InventionTitle>,
+    #[prost(message, optional, tag = "11")]
+    pub botanic: Option<UsBotanic>,
+    #[prost(message, repeated, tag = "12")]
+    pub us_references_cited: Vec<UsCitation>,
+    #[prost(uint32, optional, tag = "13")]
+    pub number_of_claims: Option<u32>,
+    #[prost(uint32, repeated, tag = "14")]
+    pub us_exemplary_claim: Vec<u32>,
+    #[prost(message, optional, tag = "15")]
+    pub us_field_of_classification_search: Option<UsFieldOfClassificationSearch>,
+    #[prost(message, optional, tag = "16")]
+    pub figures: Option<Figures>,
+    #[prost(message, repeated, tag = "17")]
+    pub us_related_documents: Vec<RelatedDocument>,
+    #[prost(message, repeated, tag = "18")]
+    pub us_applicants: Vec<UsApplicant>,
+    #[prost(message, repeated, tag = "19")]
+    pub inventors: Vec<Inventor>,
+    #[prost(message, repeated, tag = "20")]
+    pub agents: Vec<Agent>,
+    #[prost(message, repeated, tag = "21")]
+    pub assignees: Vec<Assignee>,
+    #[prost(message, optional, tag = "22")]
+    pub examiners: Option<Examiners>,
+    #[prost(message, optional, tag = "23")]
+    pub pct_or_regional_filing_data: Option<PctOrRegionalFilingData>,
+    #[prost(message, optional, tag = "24")]
+    pub pct_or_regional_publishing_data: Option<PctOrRegionalPublishingData>,
+    #[prost(message, optional, tag = "25")]
+    pub hague_agreement_data: Option<HagueAgreementData>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DocumentId {
+    #[prost(string, tag = "1")]
+    pub country: String,
+    #[prost(string, tag = "2")]
+    pub doc_number: String,
+    #[prost(string, optional, tag = "3")]
+    pub kind: Option<String>,
+    #[prost(string, optional, tag = "4")]
+    pub name: Option<String>,
+    #[prost(string, tag = "5")]
+    pub date: String,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UsCitation {
+    #[prost(oneof = "us_citation::Citation", tags = "1, 2")]
+    pub citation: Option<us_citation::Citation>,
+    #[prost(string, optional, tag = "3")]
+    pub category: Option<String>,
+    #[prost(string, optional, tag = "4")]
+    pub classification_cpc_text: Option<String>,
+    #[prost(message, optional, tag = "5")]
+    pub classification_national: Option<ClassificationNational>,
+}
+
+/// The oneof of `UsCitation`
+pub mod us_citation {
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Citation {
+        #[prost(message, tag = "1")]
+        Patcit(super::PatCit),
+        #[prost(message, tag = "2")]
+        Nplcit(super::NplCit),
+    }
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PatCit {
+    #[prost(string, tag = "1")]
+    pub num: String,
+    #[prost(message, optional, tag = "2")]
+    pub document_id: Option<DocumentId>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NplCit {
+    #[prost(string, tag = "1")]
+    pub num: String,
+    #[prost(string, tag = "2")]
+    pub othercit: String,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UsTermOfGrant {
+    #[prost(string, optional, tag = "1")]
+    pub length_of_grant: Option<String>,
+    #[prost(string, optional, tag = "2")]
+    pub us_term_extension: Option<String>,
+    #[prost(message, optional, tag = "3")]
+    pub disclaimer: Option<Disclaimer>,
+    #[prost(string, optional, tag = "4")]
+    pub prior_disclosure_affidavit_filed: Option<String>,
+    #[prost(string, optional, tag = "5")]
+    pub text: Option<String>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Disclaimer {
+    #[prost(string, optional, tag = "1")]
+    pub date: Option<String>,
+    #[prost(string, optional, tag = "2")]
+    pub text: Option<String>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PriorityClaim {
+    #[prost(string, tag = "1")]
+    pub sequence: String,
+    #[prost(string, tag = "2")]
+    pub kind: String,
+    #[prost(string, tag = "3")]
+    pub country: String,
+    #[prost(string, optional, tag = "4")]
+    pub doc_number: Option<String>,
+    #[prost(string, tag = "5")]
+    pub date: String,
+    #[prost(string, optional, tag = "6")]
+    pub office_of_filing: Option<String>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ClassificationIpcr {
+    #[prost(string, tag = "1")]
+    pub ipc_version_indicator: String,
+    #[prost(string, optional, tag = "2")]
+    pub classification_level: Option<String>,
+    #[prost(string, tag = "3")]
+    pub section: String,
+    #[prost(string, tag = "4")]
+    pub class: String,
+    #[prost(string, tag = "5")]
+    pub subclass: String,
+    #[prost(string, optional, tag = "6")]
+    pub main_group: Option<String>,
+    #[prost(string, optional, tag = "7")]
+    pub subgroup: Option<String>,
+    #[prost(string, optional, tag = "8")]
+    pub symbol_position: Option<String>,
+    #[prost(string, optional, tag = "9")]
+    pub classification_value: Option<String>,
+    #[prost(string, optional, tag = "10")]
+    pub action_date: Option<String>,
+    #[prost(string, optional, tag = "11")]
+    pub generating_office: Option<String>,
+    #[prost(string, optional, tag = "12")]
+    pub classification_status: Option<String>,
+    #[prost(string, optional, tag = "13")]
+    pub classification_data_source: Option<String>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ClassificationsCpc {
+    #[prost(message, optional, tag = "1")]
+    pub main_cpc: Option<ClassificationCpc>,
+    #[prost(message, repeated, tag = "2")]
+    pub further_cpc: Vec<ClassificationCpc>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ClassificationCpc {
+    #[prost(string, tag = "1")]
+    pub cpc_version_indicator: String,
+    #[prost(string, tag = "2")]
+    pub section: String,
+    #[prost(string, tag = "3")]
+    pub class: String,
+    #[prost(string, tag = "4")]
+    pub subclass: String,
+    #[prost(string, tag = "5")]
+    pub main_group: String,
+    #[prost(string, tag = "6")]
+    pub subgroup: String,
+    #[prost(string, tag = "7")]
+    pub symbol_position: String,
+    #[prost(string, tag = "8")]
+    pub classification_value: String,
+    #[prost(string, tag = "9")]
+    pub action_date: String,
+    #[prost(string, tag = "10")]
+    pub generating_office: String,
+    #[prost(string, optional, tag = "11")]
+    pub classification_status: Option<String>,
+    #[prost(string, optional, tag = "12")]
+    pub classification_data_source: Option<String>,
+    #[prost(string, optional, tag = "13")]
+    pub scheme_origination_code: Option<String>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RelatedDocument {
+    #[prost(oneof = "related_document::Document", tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13")]
+    pub document: Option<related_document::Document>,
+}
+
+/// The oneof of `RelatedDocument`
+pub mod related_document {
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Document {
+        #[prost(message, tag = "1")]
+        Addition(super::Relation),
+        #[prost(message, tag = "2")]
+        Division(super::Relation),
+        #[prost(message, tag = "3")]
+        Continuation(super::Relation),
+        #[prost(message, tag = "4")]
+        ContinuationInPart(super::Relation),
+        #[prost(message, tag = "5")]
+        ContinuingReissue(super::Relation),
+        #[prost(message, tag = "6")]
+        Reissue(super::Relation),
+        #[prost(message, tag = "7")]
+        DivisionalReissue(super::Relation),
+        #[prost(message, tag = "8")]
+        Reexamination(super::Relation),
+        #[prost(message, tag = "9")]
+        ReexaminationReissueMerger(super::Relation),
+        #[prost(message, tag = "10")]
+        Substitution(super::Relation),
+        #[prost(message, tag = "11")]
+        UtilityModelBasis(super::Relation),
+        #[prost(message, tag = "12")]
+        ProvisionalApplication(super::ProvisionalApplication),
+        #[prost(message, tag = "13")]
+        RelatedPublication(super::DocumentId),
+    }
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Relation {
+    #[prost(message, optional, tag = "1")]
+    pub parent_doc: Option<ParentDoc>,
+    #[prost(message, repeated, tag = "2")]
+    pub child_docs: Vec<ChildDoc>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ParentDoc {
+    #[prost(message, optional, tag = "1")]
+    pub document_id: Option<DocumentId>,
+    #[prost(string, optional, tag = "2")]
+    pub parent_status: Option<String>,
+    #[prost(message, optional, tag = "3")]
+    pub parent_grant_document: Option<DocumentId>,
+    #[prost(message, optional, tag = "4")]
+    pub parent_pct_document: Option<DocumentId>,
+    #[prost(string, optional, tag = "5")]
+    pub international_filing_date: Option<String>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChildDoc {
+    #[prost(message, optional, tag = "1")]
+    pub document_id: Option<DocumentId>,
+    #[prost(string, optional, tag = "2")]
+    pub international_filing_date: Option<String>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ProvisionalApplication {
+    #[prost(message, optional, tag = "1")]
+    pub document_id: Option<DocumentId>,
+    #[prost(string, optional, tag = "2")]
+    pub status: Option<String>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ClassificationLocarno {
+    #[prost(string, tag = "1")]
+    pub edition: String,
+    #[prost(string, tag = "2")]
+    pub main_classification: String,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ClassificationNational {
+    #[prost(string, tag = "1")]
+    pub country: String,
+    #[prost(string, optional, tag = "2")]
+    pub additional_info: Option<String>,
+    #[prost(message, optional, tag = "3")]
+    pub main_classification: Option<Uspc>,
+    #[prost(message, optional, tag = "4")]
+    pub further_classification: Option<Uspc>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Uspc {
+    #[prost(string, tag = "1")]
+    pub class: String,
+    #[prost(string, tag = "2")]
+    pub subclass: String,
+    #[prost(bool, tag = "3")]
+    pub digest: bool,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct InventionTitle {
+    #[prost(string, optional, tag = "1")]
+    pub id: Option<String>,
+    #[prost(string, optional, tag = "2")]
+    pub lang: Option<String>,
+    #[prost(string, tag = "3")]
+    pub title: String,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UsBotanic {
+    #[prost(string, tag = "1")]
+    pub latin_name: String,
+    #[prost(string, tag = "2")]
+    pub variety: String,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UsFieldOfClassificationSearch {
+    #[prost(string, repeated, tag = "1")]
+    pub us_classifications_ipcr: Vec<String>,
+    #[prost(message, repeated, tag = "2")]
+    pub classification_nationals: Vec<ClassificationNational>,
+    #[prost(string, repeated, tag = "3")]
+    pub classification_cpc_texts: Vec<String>,
+    #[prost(string, repeated, tag = "4")]
+    pub classification_cpc_combination_texts: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Figures {
+    #[prost(uint32, optional, tag = "1")]
+    pub number_of_drawing_sheets: Option<u32>,
+    #[prost(uint32, optional, tag = "2")]
+    pub number_of_figures: Option<u32>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UsApplicant {
+    #[prost(string, tag = "1")]
+    pub sequence: String,
+    #[prost(string, tag = "2")]
+    pub app_type: String,
+    #[prost(string, tag = "3")]
+    pub designation: String,
+    #[prost(string, optional, tag = "4")]
+    pub applicant_authority_category: Option<String>,
+    #[prost(message, optional, tag = "5")]
+    pub addressbook: Option<AddressBook>,
+    #[prost(string, optional, tag = "6")]
+    pub residence: Option<String>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AddressBook {
+    #[prost(string, optional, tag = "1")]
+    pub orgname: Option<String>,
+    #[prost(string, optional, tag = "2")]
+    pub first_name: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    pub last_name: Option<String>,
+    #[prost(string, optional, tag = "4")]
+    pub role: Option<String>,
+    #[prost(message, optional, tag = "5")]
+    pub address: Option<Address>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Address {
+    #[prost(string, optional, tag = "1")]
+    pub city: Option<String>,
+    #[prost(string, optional, tag = "2")]
+    pub state: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    pub country: Option<String>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Inventor {
+    #[prost(string, tag = "1")]
+    pub sequence: String,
+    #[prost(string, tag = "2")]
+    pub designation: String,
+    #[prost(message, optional, tag = "3")]
+    pub addressbook: Option<AddressBook>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Agent {
+    #[prost(string, tag = "1")]
+    pub sequence: String,
+    #[prost(string, tag = "2")]
+    pub rep_type: String,
+    #[prost(message, optional, tag = "3")]
+    pub addressbook: Option<AddressBook>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Assignee {
+    #[prost(string, optional, tag = "1")]
+    pub orgname: Option<String>,
+    #[prost(string, optional, tag = "2")]
+    pub role: Option<String>,
+    #[prost(message, optional, tag = "3")]
+    pub addressbook: Option<AddressBook>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Examiners {
+    #[prost(message, optional, tag = "1")]
+    pub primary_examiner: Option<Examiner>,
+    #[prost(message, optional, tag = "2")]
+    pub assistant_examiner: Option<Examiner>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Examiner {
+    #[prost(string, tag = "1")]
+    pub first_name: String,
+    #[prost(string, tag = "2")]
+    pub last_name: String,
+    #[prost(string, optional, tag = "3")]
+    pub middle_name: Option<String>,
+    #[prost(string, optional, tag = "4")]
+    pub suffix: Option<String>,
+    #[prost(string, optional, tag = "5")]
+    pub department: Option<String>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PctOrRegionalFilingData {
+    #[prost(message, optional, tag = "1")]
+    pub document_id: Option<DocumentId>,
+    #[prost(string, optional, tag = "2")]
+    pub us_371c124_date: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    pub us_371c12_date: Option<String>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PctOrRegionalPublishingData {
+    #[prost(message, optional, tag = "1")]
+    pub document_id: Option<DocumentId>,
+    #[prost(string, optional, tag = "2")]
+    pub gazette_num: Option<String>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct HagueAgreementData {
+    #[prost(string, tag = "1")]
+    pub international_filing_date: String,
+    #[prost(string, tag = "2")]
+    pub international_registration_publication_date: String,
+    #[prost(string, tag = "3")]
+    pub international_registration_number: String,
+    #[prost(string, tag = "4")]
+    pub international_registration_date: String,
+}
+
+/// A request of the Parser service's Parse: the next chunk of a bulk file,
+/// or its url
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ParseRequest {
+    #[prost(oneof = "parse_request::Input", tags = "1, 2")]
+    pub input: Option<parse_request::Input>,
+}
+
+pub mod parse_request {
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Input {
+        #[prost(bytes = "vec", tag = "1")]
+        Chunk(Vec<u8>),
+        #[prost(string, tag = "2")]
+        Url(String),
+    }
+}
+
+/// A response of Parse: a grant, or a document that failed to parse
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ParseResponse {
+    #[prost(oneof = "parse_response::Result", tags = "1, 2")]
+    pub result: Option<parse_response::Result>,
+}
+
+pub mod parse_response {
+    // as generated, unboxed
+    #[allow(clippy::large_enum_variant)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Result {
+        #[prost(message, tag = "1")]
+        Grant(super::PatentGrant),
+        #[prost(message, tag = "2")]
+        Error(super::ParseError),
+    }
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ParseError {
+    #[prost(string, tag = "1")]
+    pub message: String,
+    #[prost(uint64, tag = "2")]
+    pub start: u64,
+    #[prost(uint64, tag = "3")]
+    pub end: u64,
+}
+
+/// The Parser service's server, as tonic-build would generate it (without
+/// its compression and message size settings); implement `Parser` and
+/// serve it in a `ParserServer`, see `grpc::ParseService`
+#[cfg(feature = "grpc")]
+pub mod parser_server {
+    use tonic::codegen::*;
+
+    #[async_trait]
+    pub trait Parser: Send + Sync + 'static {
+        type ParseStream: tokio_stream::Stream<Item = Result<super::ParseResponse, tonic::Status>> + Send + 'static;
+
+        async fn parse(&self, request: tonic::Request<tonic::Streaming<super::ParseRequest>>) -> Result<tonic::Response<Self::ParseStream>, tonic::Status>;
+    }
+
+    pub const SERVICE_NAME: &str = "uspto.Parser";
+
+    #[derive(Debug)]
+    pub struct ParserServer<T> {
+        inner: Arc<T>,
+    }
+
+    impl<T> ParserServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            ParserServer { inner }
+        }
+    }
+
+    impl<T> Clone for ParserServer<T> {
+        fn clone(&self) -> Self {
+            ParserServer { inner: Arc::clone(&self.inner) }
+        }
+    }
+
+    impl<T> tonic::server::NamedService for ParserServer<T> {
+        const NAME: &'static str = SERVICE_NAME;
+    }
+
+    impl<T, B> Service<http::Request<B>> for ParserServer<T>
+        where T: Parser,
+              B: Body + Send + 'static,
+              B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::Body>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            match req.uri().path() {
+                "/uspto.Parser/Parse" => {
+                    struct ParseSvc<T: Parser>(Arc<T>);
+
+                    impl<T: Parser> tonic::server::StreamingService<super::ParseRequest> for ParseSvc<T> {
+                        type Response = super::ParseResponse;
+                        type ResponseStream = T::ParseStream;
+                        type Future = BoxFuture<tonic::Response<Self::ResponseStream>, tonic::Status>;
+
+                        fn call(&mut self, request: tonic::Request<tonic::Streaming<super::ParseRequest>>) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            Box::pin(async move { inner.parse(request).await })
+                        }
+                    }
+
+                    let inner = Arc::clone(&self.inner);
+                    Box::pin(async move {
+                        let mut grpc = tonic::server::Grpc::new(tonic_prost::ProstCodec::default());
+                        Ok(grpc.streaming(ParseSvc(inner), req).await)
+                    })
+                },
+                _ => Box::pin(async move {
+                    let mut response = http::Response::new(tonic::body::Body::default());
+                    let headers = response.headers_mut();
+                    headers.insert(tonic::Status::GRPC_STATUS, (tonic::Code::Unimplemented as i32).into());
+                    headers.insert(http::header::CONTENT_TYPE, tonic::metadata::GRPC_CONTENT_TYPE);
+                    Ok(response)
+                }),
+            }
+        }
+    }
+}
+
+/// YYYYMMDD, or empty if missing, typed dates or not
+fn date(date: data::Date) -> String {
+    if date == data::Date::default() {
+        String::new()
+    } else {
+        data::date_text(&date)
+    }
+}
+
+fn kind(kind: Option<data::KindCode>) -> Option<String> {
+    kind.map(String::from)
+}
+
+fn convert<T: Into<U>, U>(items: Vec<T>) -> Vec<U> {
+    items.into_iter().map(Into::into).collect()
+}
+
+impl From<data::PatentGrant> for PatentGrant {
+    fn from(grant: data::PatentGrant) -> Self {
+        PatentGrant {
+            meta: Some(grant.meta.into()),
+            us_bibliographic_data_grant: Some(grant.us_bibliographic_data_grant.into()),
+            r#abstract: convert(grant.r#abstract),
+            descriptions: grant.descriptions,
+            descriptions_raw: grant.descriptions_raw,
+            government_interest: grant.government_interest,
+            chemistry: convert(grant.chemistry),
+            sequence_listing: grant.sequence_listing.map(Into::into),
+            us_claim_statement: grant.us_claim_statement,
+            claims: convert(grant.claims),
+        }
+    }
+}
+
+impl From<data::Paragraph> for Paragraph {
+    fn from(paragraph: data::Paragraph) -> Self {
+        Paragraph {
+            id: paragraph.id,
+            num: paragraph.num,
+            text: paragraph.text,
+            formulae: convert(paragraph.formulae),
+            figrefs: convert(paragraph.figrefs),
+        }
+    }
+}
+
+impl From<data::FigRef> for FigRef {
+    fn from(figref: data::FigRef) -> Self {
+        FigRef {
+            idref: figref.idref,
+            text: figref.text,
+        }
+    }
+}
+
+impl From<data::Chemistry> for Chemistry {
+    fn from(chemistry: data::Chemistry) -> Self {
+        Chemistry {
+            id: chemistry.id,
+            num: chemistry.num,
+            files: chemistry.files,
+        }
+    }
+}
+
+impl From<data::SequenceListing> for SequenceListing {
+    fn from(sequence_listing: data::SequenceListing) -> Self {
+        SequenceListing {
+            ids: sequence_listing.ids,
+            files: sequence_listing.files,
+        }
+    }
+}
+
+impl From<data::Formula> for Formula {
+    fn from(formula: data::Formula) -> Self {
+        Formula {
+            id: formula.id,
+            num: formula.num,
+            mathml: formula.mathml,
+            latex: formula.latex,
+        }
+    }
+}
+
+impl From<data::Claim> for Claim {
+    fn from(claim: data::Claim) -> Self {
+        Claim {
+            id: claim.id,
+            num: claim.num,
+            claim_type: claim.claim_type,
+            claim_texts: convert(claim.claim_texts),
+        }
+    }
+}
+
+impl From<data::ClaimText> for ClaimText {
+    fn from(claim_text: data::ClaimText) -> Self {
+        ClaimText {
+            text: claim_text.text,
+            claim_refs: convert(claim_text.claim_refs),
+            formulae: convert(claim_text.formulae),
+            chemistry: convert(claim_text.chemistry),
+            figrefs: convert(claim_text.figrefs),
+            claim_texts: convert(claim_text.claim_texts),
+        }
+    }
+}
+
+impl From<data::ClaimRef> for ClaimRef {
+    fn from(claim_ref: data::ClaimRef) -> Self {
+        ClaimRef {
+            idref: claim_ref.idref,
+            text: claim_ref.text,
+        }
+    }
+}
+
+impl From<data::GrantMeta> for GrantMeta {
+    fn from(meta: data::GrantMeta) -> Self {
+        GrantMeta {
+            lang: meta.lang,
+            dtd_version: meta.dtd_version,
+            file: meta.file,
+            status: meta.status,
+            id: meta.id,
+            country: meta.country,
+            file_reference_id: meta.file_reference_id,
+            date_produced: meta.date_produced,
+            date_publ: meta.date_publ,
+        }
+    }
+}
+
+impl From<data::BibliographicDataGrant> for BibliographicDataGrant {
+    fn from(biblio: data::BibliographicDataGrant) -> Self {
+        BibliographicDataGrant {
+            publication_reference: Some(biblio.publication_reference.into()),
+            application_reference: Some(biblio.application_reference.into()),
+            us_application_series_code: biblio.us_application_series_code,
+            priority_claims: convert(biblio.priority_claims),
+            us_term_of_grant: Some(biblio.us_term_of_grant.into()),
+            classifications_ipcr: convert(biblio.classifications_ipcr),
+            classifications_cpc: Some(biblio.classifications_cpc.into()),
+            classification_locarno: Some(biblio.classification_locarno.into()),
+            classification_national: Some(biblio.classification_national.into()),
+            invention_title: Some(biblio.invention_title.into()),
+            botanic: biblio.botanic.map(Into::into),
+            us_references_cited: convert(biblio.us_references_cited),
+            number_of_claims: biblio.number_of_claims,
+            us_exemplary_claim: biblio.us_exemplary_claim,
+            us_field_of_classification_search: Some(biblio.us_field_of_classification_search.into()),
+            figures: Some(biblio.figures.into()),
+            us_related_documents: convert(biblio.us_related_documents),
+            us_applicants: convert(biblio.us_applicants),
+            inventors: convert(biblio.inventors),
+            agents: convert(biblio.agents),
+            assignees: convert(biblio.assignees),
+            examiners: Some(biblio.examiners.into()),
+            pct_or_regional_filing_data: biblio.pct_or_regional_filing_data.map(Into::into),
+            pct_or_regional_publishing_data: biblio.pct_or_regional_publishing_data.map(Into::into),
+            hague_agreement_data: biblio.hague_agreement_data.map(Into::into),
+        }
+    }
+}
+
+impl From<data::DocumentId> for DocumentId {
+    fn from(doc_id: data::DocumentId) -> Self {
+        DocumentId {
+            country: doc_id.country.to_string(),
+            doc_number: doc_id.doc_number,
+            kind: kind(doc_id.kind),
+            name: doc_id.name,
+            date: date(doc_id.date),
+        }
+    }
+}
+
+impl From<data::UsCitation> for UsCitation {
+    fn from(cited: data::UsCitation) -> Self {
+        let citation = match cited.citation {
+            data::Citation::Patent(patcit) => us_citation::Citation::Patcit(patcit.into()),
+            data::Citation::NonPatent(nplcit) => us_citation::Citation::Nplcit(nplcit.into()),
+        };
+
+        UsCitation {
+            citation: Some(citation),
+            category: cited.category,
+            classification_cpc_text: cited.classification_cpc_text,
+            classification_national: cited.classification_national.map(Into::into),
+        }
+    }
+}
+
+impl From<data::PatCit> for PatCit {
+    fn from(patcit: data::PatCit) -> Self {
+        PatCit {
+            num: patcit.num,
+            document_id: Some(patcit.document_id.into()),
+        }
+    }
+}
+
+impl From<data::NplCit> for NplCit {
+    fn from(nplcit: data::NplCit) -> Self {
+        NplCit {
+            num: nplcit.num,
+            othercit: nplcit.othercit,
+        }
+    }
+}
+
+impl From<data::UsTermOfGrant> for UsTermOfGrant {
+    fn from(term: data::UsTermOfGrant) -> Self {
+        UsTermOfGrant {
+            length_of_grant: term.length_of_grant,
+            us_term_extension: term.us_term_extension,
+            disclaimer: term.disclaimer.map(Into::into),
+            prior_disclosure_affidavit_filed: term.prior_disclosure_affidavit_filed,
+            text: term.text,
+        }
+    }
+}
+
+impl From<data::Disclaimer> for Disclaimer {
+    fn from(disclaimer: data::Disclaimer) -> Self {
+        Disclaimer {
+            date: disclaimer.date,
+            text: disclaimer.text,
+        }
+    }
+}
+
+impl From<data::PriorityClaim> for PriorityClaim {
+    fn from(priority_claim: data::PriorityClaim) -> Self {
+        PriorityClaim {
+            sequence: priority_claim.sequence,
+            kind: priority_claim.kind,
+            country: priority_claim.country.to_string(),
+            doc_number: priority_claim.doc_number,
+            date: date(priority_claim.date),
+            office_of_filing: priority_claim.office_of_filing,
+        }
+    }
+}
+
+impl From<data::ClassificationIpcr> for ClassificationIpcr {
+    fn from(ipcr: data::ClassificationIpcr) -> Self {
+        ClassificationIpcr {
+            ipc_version_indicator: ipcr.ipc_version_indicator,
+            classification_level: ipcr.classification_level,
+            section: ipcr.section,
+            class: ipcr.class,
+            subclass: ipcr.subclass,
+            main_group: ipcr.main_group,
+            subgroup: ipcr.subgroup,
+            symbol_position: ipcr.symbol_position,
+            classification_value: ipcr.classification_value,
+            action_date: ipcr.action_date.map(date),
+            generating_office: ipcr.generating_office,
+            classification_status: ipcr.classification_status,
+            classification_data_source: ipcr.classification_data_source,
+        }
+    }
+}
+
+impl From<data::ClassificationsCpc> for ClassificationsCpc {
+    fn from(cpc: data::ClassificationsCpc) -> Self {
+        ClassificationsCpc {
+            main_cpc: Some(cpc.main_cpc.into()),
+            further_cpc: convert(cpc.further_cpc),
+        }
+    }
+}
+
+impl From<data::ClassificationCpc> for ClassificationCpc {
+    fn from(cpc: data::ClassificationCpc) -> Self {
+        ClassificationCpc {
+            cpc_version_indicator: cpc.cpc_version_indicator,
+            section: cpc.section,
+            class: cpc.class,
+            subclass: cpc.subclass,
+            main_group: cpc.main_group,
+            subgroup: cpc.subgroup,
+            symbol_position: cpc.symbol_position,
+            classification_value: cpc.classification_value,
+            action_date: date(cpc.action_date),
+            generating_office: cpc.generating_office,
+            classification_status: cpc.classification_status,
+            classification_data_source: cpc.classification_data_source,
+            scheme_origination_code: cpc.scheme_origination_code,
+        }
+    }
+}
+
+impl From<data::RelatedDocument> for RelatedDocument {
+    fn from(related_document: data::RelatedDocument) -> Self {
+        use data::RelatedDocument as Data;
+        use related_document::Document;
+
+        let document = match related_document {
+            Data::Addition(relation) => Document::Addition(relation.into()),
+            Data::Division(relation) => Document::Division(relation.into()),
+            Data::Continuation(relation) => Document::Continuation(relation.into()),
+            Data::ContinuationInPart(relation) => Document::ContinuationInPart(relation.into()),
+            Data::ContinuingReissue(relation) => Document::ContinuingReissue(relation.into()),
+            Data::Reissue(relation) => Document::Reissue(relation.into()),
+            Data::DivisionalReissue(relation) => Document::DivisionalReissue(relation.into()),
+            Data::Reexamination(relation) => Document::Reexamination(relation.into()),
+            Data::ReexaminationReissueMerger(relation) => Document::ReexaminationReissueMerger(relation.into()),
+            Data::Substitution(relation) => Document::Substitution(relation.into()),
+            Data::UtilityModelBasis(relation) => Document::UtilityModelBasis(relation.into()),
+            Data::ProvisionalApplication(provisional) => Document::ProvisionalApplication(provisional.into()),
+            Data::RelatedPublication(doc_id) => Document::RelatedPublication(doc_id.into()),
+        };
+
+        RelatedDocument {
+            document: Some(document),
+        }
+    }
+}
+
+impl From<data::Relation> for Relation {
+    fn from(relation: data::Relation) -> Self {
+        Relation {
+            parent_doc: Some(relation.parent_doc.into()),
+            child_docs: convert(relation.child_docs),
+        }
+    }
+}
+
+impl From<data::ParentDoc> for ParentDoc {
+    fn from(parent_doc: data::ParentDoc) -> Self {
+        ParentDoc {
+            document_id: Some(parent_doc.document_id.into()),
+            parent_status: parent_doc.parent_status,
+            parent_grant_document: parent_doc.parent_grant_document.map(Into::into),
+            parent_pct_document: parent_doc.parent_pct_document.map(Into::into),
+            international_filing_date: parent_doc.international_filing_date,
+        }
+    }
+}
+
+impl From<data::ChildDoc> for ChildDoc {
+    fn from(child_doc: data::ChildDoc) -> Self {
+        ChildDoc {
+            document_id: Some(child_doc.document_id.into()),
+            international_filing_date: child_doc.international_filing_date,
+        }
+    }
+}
+
+impl From<data::ProvisionalApplication> for ProvisionalApplication {
+    fn from(provisional: data::ProvisionalApplication) -> Self {
+        ProvisionalApplication {
+            document_id: Some(provisional.document_id.into()),
+            status: provisional.status,
+        }
+    }
+}
+
+impl From<data::ClassificationLocarno> for ClassificationLocarno {
+    fn from(locarno: data::ClassificationLocarno) -> Self {
+        ClassificationLocarno {
+            edition: locarno.edition,
+            main_classification: locarno.main_classification,
+        }
+    }
+}
+
+impl From<data::ClassificationNational> for ClassificationNational {
+    fn from(national: data::ClassificationNational) -> Self {
+        ClassificationNational {
+            country: national.country.to_string(),
+            additional_info: national.additional_info,
+            main_classification: Some(national.main_classification.into()),
+            further_classification: national.further_classification.map(Into::into),
+        }
+    }
+}
+
+impl From<data::Uspc> for Uspc {
+    fn from(uspc: data::Uspc) -> Self {
+        Uspc {
+            class: uspc.class,
+            subclass: uspc.subclass,
+            digest: uspc.digest,
+        }
+    }
+}
+
+impl From<data::InventionTitle> for InventionTitle {
+    fn from(title: data::InventionTitle) -> Self {
+        InventionTitle {
+            id: title.id,
+            lang: title.lang,
+            title: title.title,
+        }
+    }
+}
+
+impl From<data::UsBotanic> for UsBotanic {
+    fn from(botanic: data::UsBotanic) -> Self {
+        UsBotanic {
+            latin_name: botanic.latin_name,
+            variety: botanic.variety,
+        }
+    }
+}
+
+impl From<data::UsFieldOfClassificationSearch> for UsFieldOfClassificationSearch {
+    fn from(search: data::UsFieldOfClassificationSearch) -> Self {
+        UsFieldOfClassificationSearch {
+            us_classifications_ipcr: search.us_classifications_ipcr,
+            classification_nationals: convert(search.classification_nationals),
+            classification_cpc_texts: search.classification_cpc_texts,
+            classification_cpc_combination_texts: search.classification_cpc_combination_texts,
+        }
+    }
+}
+
+impl From<data::Figures> for Figures {
+    fn from(figures: data::Figures) -> Self {
+        Figures {
+            number_of_drawing_sheets: figures.number_of_drawing_sheets,
+            number_of_figures: figures.number_of_figures,
+        }
+    }
+}
+
+impl From<data::UsApplicant> for UsApplicant {
+    fn from(applicant: data::UsApplicant) -> Self {
+        UsApplicant {
+            sequence: applicant.sequence,
+            app_type: applicant.app_type,
+            designation: applicant.designation,
+            applicant_authority_category: applicant.applicant_authority_category,
+            addressbook: Some(applicant.addressbook.into()),
+            residence: applicant.residence,
+        }
+    }
+}
+
+impl From<data::AddressBook> for AddressBook {
+    fn from(addressbook: data::AddressBook) -> Self {
+        AddressBook {
+            orgname: addressbook.orgname,
+            first_name: addressbook.first_name,
+            last_name: addressbook.last_name,
+            role: addressbook.role,
+            address: Some(addressbook.address.into()),
+        }
+    }
+}
+
+impl From<data::Address> for Address {
+    fn from(address: data::Address) -> Self {
+        Address {
+            city: address.city,
+            state: address.state,
+            country: address.country.map(|country| country.to_string()),
+        }
+    }
+}
+
+impl From<data::Inventor> for Inventor {
+    fn from(inventor: data::Inventor) -> Self {
+        Inventor {
+            sequence: inventor.sequence,
+            designation: inventor.designation,
+            addressbook: Some(inventor.addressbook.into()),
+        }
+    }
+}
+
+impl From<data::Agent> for Agent {
+    fn from(agent: data::Agent) -> Self {
+        Agent {
+            sequence: agent.sequence,
+            rep_type: agent.rep_type,
+            addressbook: Some(agent.addressbook.into()),
+        }
+    }
+}
+
+impl From<data::Assignee> for Assignee {
+    fn from(assignee: data::Assignee) -> Self {
+        Assignee {
+            orgname: assignee.orgname,
+            role: assignee.role,
+            addressbook: Some(assignee.addressbook.into()),
+        }
+    }
+}
+
+impl From<data::Examiners> for Examiners {
+    fn from(examiners: data::Examiners) -> Self {
+        Examiners {
+            primary_examiner: Some(examiners.primary_examiner.into()),
+            assistant_examiner: examiners.assistant_examiner.map(Into::into),
+        }
+    }
+}
+
+impl From<data::Examiner> for Examiner {
+    fn from(examiner: data::Examiner) -> Self {
+        Examiner {
+            first_name: examiner.first_name,
+            last_name: examiner.last_name,
+            middle_name: examiner.middle_name,
+            suffix: examiner.suffix,
+            department: examiner.department,
+        }
+    }
+}
+
+impl From<data::PctOrRegionalFilingData> for PctOrRegionalFilingData {
+    fn from(filing: data::PctOrRegionalFilingData) -> Self {
+        PctOrRegionalFilingData {
+            document_id: Some(filing.document_id.into()),
+            us_371c124_date: filing.us_371c124_date,
+            us_371c12_date: filing.us_371c12_date,
+        }
+    }
+}
+
+impl From<data::PctOrRegionalPublishingData> for PctOrRegionalPublishingData {
+    fn from(publishing: data::PctOrRegionalPublishingData) -> Self {
+        PctOrRegionalPublishingData {
+            document_id: Some(publishing.document_id.into()),
+            gazette_num: publishing.gazette_num,
+        }
+    }
+}
+
+impl From<data::HagueAgreementData> for HagueAgreementData {
+    fn from(hague: data::HagueAgreementData) -> Self {
+        HagueAgreementData {
+            international_filing_date: hague.international_filing_date,
+            international_registration_publication_date: hague.international_registration_publication_date,
+            international_registration_number: hague.international_registration_number,
+            international_registration_date: hague.international_registration_date,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deserialize::PatentGrants;
+    use prost::Message;
+
+    #[test]
+    fn test_from_patent_grant() {
+        let grants: Vec<PatentGrant> = PatentGrants::from_reader(include_str!("../tests/fixtures/grants.xml").as_bytes())
+            .map(|grant| grant.unwrap().into())
+            .collect();
+
+        let biblio = grants[0].us_bibliographic_data_grant.as_ref().unwrap();
+        let publication_reference = biblio.publication_reference.as_ref().unwrap();
+        assert_eq!(publication_reference.doc_number, "10165721");
+        assert_eq!(publication_reference.kind.as_deref(), Some("B2"));
+        assert_eq!(publication_reference.country, "US");
+        assert_eq!(biblio.number_of_claims, Some(3));
+        assert_eq!(grants[0].claims.len(), 3);
+        assert!(matches!(biblio.us_references_cited[0].citation, Some(us_citation::Citation::Patcit(_))));
+
+        for grant in &grants {
+            let bytes = grant.encode_to_vec();
+            assert_eq!(&PatentGrant::decode(&bytes[..]).unwrap(), grant);
+        }
+    }
+}
@@ -0,0 +1,293 @@
+//! PTAB (Patent Trial and Appeal Board) trials: the proceedings and
+//! decisions of the PTAB trials api's json dumps, so trial outcomes (e.g. of
+//! an inter partes review) can be joined to grants by patent number.
+//!
+//! A dump is either the api's response, `{"results": [...], ...}`, or just
+//! the list of records. Fields are read by the api's (camelCase) names, and
+//! any missing are left at None. Dates are kept as the api writes them
+//! (MM-DD-YYYY); the api's xml dumps have the same records, but aren't read
+//! here.
+
+use serde::Deserialize;
+use snafu::ResultExt;
+use std::collections::HashMap;
+use std::io::Read;
+
+use crate::docnum::padded_grant_number;
+use crate::error::{Error, PtabJson};
+
+/// What kind of trial, from the proceeding number's prefix
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TrialType {
+    /// inter partes review
+    Ipr,
+    /// post-grant review
+    Pgr,
+    /// covered business method review
+    Cbm,
+    /// derivation proceeding
+    Der,
+    Other(String),
+}
+
+impl From<&str> for TrialType {
+    fn from(proceeding_number: &str) -> Self {
+        let prefix = proceeding_number.trim()
+            .split(|c: char| c.is_ascii_digit())
+            .next()
+            .unwrap_or_default();
+
+        match prefix {
+            "IPR" => TrialType::Ipr,
+            "PGR" => TrialType::Pgr,
+            "CBM" => TrialType::Cbm,
+            "DER" => TrialType::Der,
+            other => TrialType::Other(other.to_string()),
+        }
+    }
+}
+
+/// A side of a trial
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Party {
+    pub name: Option<String>,
+    pub counsel_name: Option<String>,
+}
+
+/// A petition for a trial, and where it's at
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(from = "RawProceeding")]
+pub struct Proceeding {
+    /// e.g. "IPR2019-00001"
+    pub proceeding_number: String,
+    /// e.g. "AIA Trial"
+    pub proceeding_type: Option<String>,
+    /// e.g. "FWD Entered", "Terminated-Settled"
+    pub status: Option<String>,
+    pub filing_date: Option<String>,
+    pub accorded_filing_date: Option<String>,
+    pub institution_decision_date: Option<String>,
+    pub last_modified_date: Option<String>,
+
+    pub petitioner: Party,
+    pub patent_owner: Party,
+
+    /// the challenged patent, as the api writes it (usually unpadded)
+    pub patent_number: Option<String>,
+    pub application_number: Option<String>,
+    pub inventor_name: Option<String>,
+    pub technology_center: Option<String>,
+    pub art_unit: Option<String>,
+}
+
+impl Proceeding {
+    pub fn trial_type(&self) -> TrialType {
+        TrialType::from(self.proceeding_number.as_str())
+    }
+}
+
+/// The api's flat record, its parties' fields prefixed
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawProceeding {
+    proceeding_number: String,
+    proceeding_type_category: Option<String>,
+    proceeding_status_category: Option<String>,
+    proceeding_filing_date: Option<String>,
+    accorded_filing_date: Option<String>,
+    institution_decision_date: Option<String>,
+    proceeding_last_modified_date: Option<String>,
+    petitioner_party_name: Option<String>,
+    petitioner_counsel_name: Option<String>,
+    respondent_party_name: Option<String>,
+    respondent_patent_owner_name: Option<String>,
+    respondent_counsel_name: Option<String>,
+    respondent_patent_number: Option<String>,
+    respondent_application_number_text: Option<String>,
+    respondent_inventor_name: Option<String>,
+    respondent_technology_center_number: Option<String>,
+    respondent_group_art_unit_number: Option<String>,
+}
+
+impl From<RawProceeding> for Proceeding {
+    fn from(raw: RawProceeding) -> Self {
+        Proceeding {
+            proceeding_number: raw.proceeding_number,
+            proceeding_type: raw.proceeding_type_category,
+            status: raw.proceeding_status_category,
+            filing_date: raw.proceeding_filing_date,
+            accorded_filing_date: raw.accorded_filing_date,
+            institution_decision_date: raw.institution_decision_date,
+            last_modified_date: raw.proceeding_last_modified_date,
+            petitioner: Party {
+                name: raw.petitioner_party_name,
+                counsel_name: raw.petitioner_counsel_name,
+            },
+            patent_owner: Party {
+                // the owner's name, when it differs from the respondent's
+                name: raw.respondent_patent_owner_name.or(raw.respondent_party_name),
+                counsel_name: raw.respondent_counsel_name,
+            },
+            patent_number: raw.respondent_patent_number,
+            application_number: raw.respondent_application_number_text,
+            inventor_name: raw.respondent_inventor_name,
+            technology_center: raw.respondent_technology_center_number,
+            art_unit: raw.respondent_group_art_unit_number,
+        }
+    }
+}
+
+/// A decision in a trial, e.g. on institution, or the final written
+/// decision
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Decision {
+    pub proceeding_number: String,
+    pub document_identifier: Option<String>,
+    pub document_name: Option<String>,
+    /// e.g. "Final Written Decision"
+    pub document_type_name: Option<String>,
+    /// e.g. "Decision", "Institution"
+    pub decision_type_category: Option<String>,
+    /// the outcome, e.g. "Denied", "All Claims Unpatentable"
+    pub subdecision_type_category: Option<String>,
+    pub decision_date: Option<String>,
+    /// e.g. "103" for obviousness
+    #[serde(default)]
+    pub issue_type: Vec<String>,
+    #[serde(rename = "respondentPatentNumber")]
+    pub patent_number: Option<String>,
+}
+
+impl Decision {
+    pub fn trial_type(&self) -> TrialType {
+        TrialType::from(self.proceeding_number.as_str())
+    }
+
+    /// By its document type, or (in older dumps, without one) its category
+    pub fn is_final_written_decision(&self) -> bool {
+        self.document_type_name.as_deref()
+            .or(self.decision_type_category.as_deref())
+            .is_some_and(|name| name.eq_ignore_ascii_case("final written decision"))
+    }
+}
+
+/// A dump: the api's response, or only its results
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Dump<T> {
+    Response { results: Vec<T> },
+    Results(Vec<T>),
+}
+
+fn read_dump<T: serde::de::DeserializeOwned, R: Read>(rdr: R) -> Result<Vec<T>, Error> {
+    let dump: Dump<T> = serde_json::from_reader(rdr)
+        .context(PtabJson)?;
+
+    Ok(match dump {
+        Dump::Response { results } => results,
+        Dump::Results(results) => results,
+    })
+}
+
+/// Reads a json dump of proceedings
+pub fn read_proceedings<R: Read>(rdr: R) -> Result<Vec<Proceeding>, Error> {
+    read_dump(rdr)
+}
+
+/// Reads a json dump of decisions
+pub fn read_decisions<R: Read>(rdr: R) -> Result<Vec<Decision>, Error> {
+    read_dump(rdr)
+}
+
+/// The proceedings against each patent, keyed by its padded grant number (as
+/// grants' doc-numbers are in the xml)
+pub fn proceedings_by_patent(proceedings: &[Proceeding]) -> HashMap<String, Vec<&Proceeding>> {
+    let mut by_patent: HashMap<String, Vec<&Proceeding>> = HashMap::new();
+    for proceeding in proceedings {
+        if let Some(patent_number) = &proceeding.patent_number {
+            by_patent.entry(padded_grant_number(patent_number))
+                .or_default()
+                .push(proceeding);
+        }
+    }
+
+    by_patent
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROCEEDINGS: &str = r#"{
+        "recordTotalQuantity": 2,
+        "results": [
+            {
+                "proceedingNumber": "IPR2019-00001",
+                "proceedingTypeCategory": "AIA Trial",
+                "proceedingStatusCategory": "FWD Entered",
+                "proceedingFilingDate": "10-01-2018",
+                "institutionDecisionDate": "04-01-2019",
+                "petitionerPartyName": "Garden Tools Inc.",
+                "petitionerCounselName": "Jones, Pat",
+                "respondentPartyName": "ACME TOOL CO., LTD.",
+                "respondentPatentNumber": "10165721",
+                "respondentApplicationNumberText": "15123456",
+                "respondentTechnologyCenterNumber": "3600",
+                "respondentGroupArtUnitNumber": "3671"
+            },
+            {
+                "proceedingNumber": "PGR2019-00002",
+                "proceedingStatusCategory": "Terminated-Settled",
+                "respondentPatentNumber": "D838001"
+            }
+        ]
+    }"#;
+
+    const DECISIONS: &str = r#"[
+        {
+            "proceedingNumber": "IPR2019-00001",
+            "documentIdentifier": "abc123",
+            "documentName": "Final Written Decision",
+            "documentTypeName": "Final Written Decision",
+            "decisionTypeCategory": "Decision",
+            "subdecisionTypeCategory": "All Claims Unpatentable",
+            "decisionDate": "03-31-2020",
+            "issueType": ["103"],
+            "respondentPatentNumber": "10165721"
+        }
+    ]"#;
+
+    #[test]
+    fn test_proceedings() {
+        let proceedings = read_proceedings(PROCEEDINGS.as_bytes()).unwrap();
+        assert_eq!(proceedings.len(), 2);
+
+        let proceeding = &proceedings[0];
+        assert_eq!(proceeding.trial_type(), TrialType::Ipr);
+        assert_eq!(proceeding.status.as_deref(), Some("FWD Entered"));
+        assert_eq!(proceeding.petitioner.name.as_deref(), Some("Garden Tools Inc."));
+        assert_eq!(proceeding.patent_owner.name.as_deref(), Some("ACME TOOL CO., LTD."));
+        assert_eq!(proceeding.art_unit.as_deref(), Some("3671"));
+        assert_eq!(proceedings[1].trial_type(), TrialType::Pgr);
+        assert!(proceedings[1].petitioner.name.is_none());
+
+        let by_patent = proceedings_by_patent(&proceedings);
+        assert_eq!(by_patent["10165721"].len(), 1);
+        assert_eq!(by_patent["D0838001"][0].proceeding_number, "PGR2019-00002");
+
+        assert!(read_proceedings(r#"{"results": [{}]}"#.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_decisions() {
+        let decisions = read_decisions(DECISIONS.as_bytes()).unwrap();
+        assert_eq!(decisions.len(), 1);
+
+        let decision = &decisions[0];
+        assert!(decision.is_final_written_decision());
+        assert_eq!(decision.subdecision_type_category.as_deref(), Some("All Claims Unpatentable"));
+        assert_eq!(decision.issue_type, vec!["103"]);
+        assert_eq!(decision.patent_number.as_deref(), Some("10165721"));
+    }
+}
@@ -0,0 +1,588 @@
+//! A grant as HTML, e.g. for a patent viewer built from bulk data: its
+//! title, abstract, description (headings, paragraphs, tables, formulae as
+//! MathML), claims, and a placeholder for each figure referred to.
+//!
+//! `html` is an `article` element to put in a page; `html_document` is a
+//! page of its own. Figures aren't in the xml (they're images in the
+//! grant's zip), so figure references link to placeholders.
+//!
+//! `markdown` is the same as a Markdown document, e.g. for documentation or
+//! feeding to an LLM.
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use crate::data::{ClaimText, PatentGrant};
+use crate::error::Error;
+use crate::maths::{BasicLatex, LatexConverter};
+use crate::options::{Entities, ParserOptions};
+use crate::tables::{tables_from_raw, Table};
+use crate::text::{normalize, DESCRIPTION_ORDER};
+use crate::util::decode_text;
+
+/// The grant as an `article`
+pub fn html(grant: &PatentGrant) -> String {
+    let mut out = String::new();
+    let mut figures = Vec::new();
+    let biblio = &grant.us_bibliographic_data_grant;
+    let publication = &biblio.publication_reference;
+
+    out.push_str("<article class=\"patent-grant\">\n");
+    out.push_str(&format!("<h1>{}</h1>\n", escape(&biblio.invention_title.title)));
+    let kind = publication.kind.as_ref().map(|kind| format!(" {}", kind)).unwrap_or_default();
+    out.push_str(&format!("<p class=\"publication\">{} {}{}</p>\n", escape(&publication.country.to_string()), escape(&publication.doc_number), escape(&kind)));
+
+    if !grant.r#abstract.is_empty() {
+        out.push_str("<section class=\"abstract\">\n<h2>Abstract</h2>\n");
+        for paragraph in &grant.r#abstract {
+            out.push_str(&format!("<p id=\"{}\">{}</p>\n", escape(&paragraph.id), escape(&normalize(&paragraph.text))));
+        }
+        out.push_str("</section>\n");
+    }
+
+    let mut names: Vec<_> = grant.descriptions.keys().map(String::as_str).collect();
+    names.sort_by_key(|name| (DESCRIPTION_ORDER.iter().position(|n| n == name).unwrap_or(DESCRIPTION_ORDER.len()), *name));
+    if !names.is_empty() {
+        out.push_str("<section class=\"description\">\n<h2>Description</h2>\n");
+        for name in names {
+            out.push_str(&format!("<section class=\"description-section\" data-name=\"{}\">\n", escape(name)));
+            // the section's text, if its raw xml doesn't read
+            match grant.descriptions_raw.get(name).map(|raw| description_html(raw, &mut figures)) {
+                Some(Ok(html)) => out.push_str(&html),
+                _ => out.push_str(&format!("<p>{}</p>\n", escape(&grant.descriptions[name]))),
+            }
+            out.push_str("</section>\n");
+        }
+        out.push_str("</section>\n");
+    }
+
+    if !grant.claims.is_empty() {
+        out.push_str("<section class=\"claims\">\n<h2>Claims</h2>\n<ol class=\"claims\">\n");
+        for claim in &grant.claims {
+            out.push_str(&format!("<li id=\"{}\">\n", escape(&claim.id)));
+            claim_texts_html(&claim.claim_texts, &mut figures, &mut out);
+            out.push_str("</li>\n");
+        }
+        out.push_str("</ol>\n</section>\n");
+    }
+
+    if !figures.is_empty() {
+        out.push_str("<section class=\"drawings\">\n<h2>Drawings</h2>\n");
+        for (n, figure) in figures.iter().enumerate() {
+            out.push_str(&format!("<figure class=\"figure-placeholder\" id=\"figure-{}\"><figcaption>{}</figcaption></figure>\n", n + 1, escape(figure)));
+        }
+        out.push_str("</section>\n");
+    }
+
+    out.push_str("</article>\n");
+    out
+}
+
+/// The grant as a page, titled with its title
+pub fn html_document(grant: &PatentGrant) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>ol.claims {{ list-style: none; }} .claim-text .claim-text {{ margin-left: 2em; }} table {{ border-collapse: collapse; }} td, th {{ border: 1px solid #ccc; padding: 0.2em 0.5em; }}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape(&grant.us_bibliographic_data_grant.invention_title.title),
+        html(grant),
+    )
+}
+
+impl PatentGrant {
+    /// See `render::html`
+    pub fn to_html(&self) -> String {
+        html(self)
+    }
+}
+
+/// The grant as Markdown: its title as the heading, then the abstract,
+/// description (headings, paragraphs and tables) and claims under their own.
+/// Formulae are LaTeX (`$...$`) where `BasicLatex` converts them.
+pub fn markdown(grant: &PatentGrant) -> String {
+    let mut blocks = Vec::new();
+    let biblio = &grant.us_bibliographic_data_grant;
+    let publication = &biblio.publication_reference;
+
+    blocks.push(format!("# {}", md_escape(&normalize(&biblio.invention_title.title))));
+    let kind = publication.kind.as_ref().map(|kind| format!(" {}", kind)).unwrap_or_default();
+    blocks.push(format!("**{} {}{}**", publication.country, md_escape(&publication.doc_number), kind));
+
+    if !grant.r#abstract.is_empty() {
+        blocks.push("## Abstract".to_string());
+        blocks.extend(grant.r#abstract.iter().map(|paragraph| md_escape(&normalize(&paragraph.text))));
+    }
+
+    let mut names: Vec<_> = grant.descriptions.keys().map(String::as_str).collect();
+    names.sort_by_key(|name| (DESCRIPTION_ORDER.iter().position(|n| n == name).unwrap_or(DESCRIPTION_ORDER.len()), *name));
+    if !names.is_empty() {
+        blocks.push("## Description".to_string());
+        for name in names {
+            // the section's text, if its raw xml doesn't read
+            match grant.descriptions_raw.get(name).map(|raw| description_markdown(raw)) {
+                Some(Ok(section)) => blocks.extend(section),
+                _ => blocks.push(md_escape(&normalize(&grant.descriptions[name]))),
+            }
+        }
+    }
+
+    if !grant.claims.is_empty() {
+        blocks.push("## Claims".to_string());
+        for claim in &grant.claims {
+            let mut lines = Vec::new();
+            claim_texts_markdown(&claim.claim_texts, 0, &mut lines);
+            blocks.push(lines.join("\n"));
+        }
+    }
+
+    let mut markdown = blocks.into_iter()
+        .filter(|block| !block.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    markdown.push('\n');
+    markdown
+}
+
+impl PatentGrant {
+    /// See `render::markdown`
+    pub fn to_markdown(&self) -> String {
+        markdown(self)
+    }
+}
+
+/// Depth-first, each nested claim-text a list item under its parent's
+fn claim_texts_markdown(claim_texts: &[ClaimText], depth: usize, lines: &mut Vec<String>) {
+    for claim_text in claim_texts {
+        let text = md_escape(&normalize(&claim_text.text));
+        match depth {
+            0 => lines.push(text),
+            _ => lines.push(format!("{}- {}", "   ".repeat(depth), text)),
+        }
+        claim_texts_markdown(&claim_text.claim_texts, depth + 1, lines);
+    }
+}
+
+/// The raw xml of a description section as Markdown blocks
+fn description_markdown(raw: &str) -> Result<Vec<String>, Error> {
+    let opts = ParserOptions::default().entities(Entities::Dtd);
+    let mut rdr = Reader::from_str(raw);
+    let mut buf = Vec::new();
+    let mut blocks = Vec::new();
+    // the paragraph or heading being read
+    let mut block: Option<String> = None;
+    // the markup closing each element open, if it has any
+    let mut open: Vec<Option<&'static str>> = Vec::new();
+
+    fn flush(block: &mut Option<String>, blocks: &mut Vec<String>) {
+        if let Some(text) = block.as_mut() {
+            blocks.push(normalize(text));
+            text.clear();
+        }
+    }
+
+    loop {
+        match rdr.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                let close = match e.name() {
+                    b"p" => {
+                        block = Some(String::new());
+                        None
+                    },
+                    b"heading" => {
+                        let level = attr(e, b"level", &rdr)?
+                            .and_then(|level| level.parse::<usize>().ok())
+                            .unwrap_or(1);
+                        block = Some(format!("{} ", "#".repeat((level + 2).min(6))));
+                        None
+                    },
+                    b"tables" => {
+                        let xml = element_xml(e, &mut rdr)?;
+                        flush(&mut block, &mut blocks);
+                        for table in tables_from_raw(&xml, &opts)? {
+                            blocks.push(table.to_markdown().trim_end().to_string());
+                        }
+                        buf.clear();
+                        continue;
+                    },
+                    b"maths" => {
+                        let mathml = maths_mathml(&mut rdr)?;
+                        let formula = match BasicLatex.to_latex(&mathml) {
+                            Some(latex) => format!(" ${}$ ", latex),
+                            None => " [formula] ".to_string(),
+                        };
+                        block.get_or_insert_with(String::new).push_str(&formula);
+                        buf.clear();
+                        continue;
+                    },
+                    b"b" => Some("**"),
+                    b"i" => Some("*"),
+                    _ => None,
+                };
+
+                if let (Some(markup), Some(text)) = (close, block.as_mut()) {
+                    text.push_str(markup);
+                }
+                open.push(close);
+            },
+            Ok(Event::End(ref e)) => {
+                match e.name() {
+                    b"p" | b"heading" => {
+                        flush(&mut block, &mut blocks);
+                        block = None;
+                    },
+                    _ => {
+                        if let (Some(Some(markup)), Some(text)) = (open.last(), block.as_mut()) {
+                            text.push_str(markup);
+                        }
+                    },
+                }
+                open.pop();
+            },
+            Ok(Event::Empty(ref e)) if e.name() == b"br" => {
+                if let Some(text) = block.as_mut() {
+                    text.push(' ');
+                }
+            },
+            Ok(Event::Text(ref e)) => {
+                if let Some(text) = block.as_mut() {
+                    text.push_str(&md_escape(&decode_text(e, &rdr, &opts)?));
+                }
+            },
+            Ok(Event::Eof) => break,
+            Ok(_) => {},
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+        buf.clear();
+    }
+
+    Ok(blocks.into_iter().filter(|block| !block.is_empty()).collect())
+}
+
+/// escapes the characters Markdown would take as markup
+pub fn md_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '#' | '|') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+/// Renders a table as a `table`, the head's rows with `th` cells
+pub fn table_html(table: &Table) -> String {
+    let mut out = format!("<table id=\"{}\">\n", escape(&table.id));
+    for row in &table.rows {
+        out.push_str("<tr>");
+        let tag = if row.header { "th" } else { "td" };
+        for cell in &row.cells {
+            out.push('<');
+            out.push_str(tag);
+            if cell.colspan > 1 {
+                out.push_str(&format!(" colspan=\"{}\"", cell.colspan));
+            }
+            if cell.rowspan > 1 {
+                out.push_str(&format!(" rowspan=\"{}\"", cell.rowspan));
+            }
+            out.push_str(&format!(">{}</{}>", escape(&cell.text), tag));
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</table>\n");
+
+    out
+}
+
+/// Depth-first. A claim-ref's text links to the claim it refers to, and a
+/// figref's to its figure's placeholder.
+fn claim_texts_html(claim_texts: &[ClaimText], figures: &mut Vec<String>, out: &mut String) {
+    for claim_text in claim_texts {
+        let mut text = escape(&normalize(&claim_text.text));
+
+        // each one's text, after the previous one's
+        let mut from = 0;
+        for claim_ref in &claim_text.claim_refs {
+            let link = format!("<a class=\"claim-ref\" href=\"#{}\">", escape(&claim_ref.idref));
+            from = link_text(&mut text, from, &claim_ref.text, &link);
+        }
+        let mut from = 0;
+        for figref in &claim_text.figrefs {
+            let link = format!("<a class=\"figref\" href=\"#figure-{}\">", figure(figures, &figref.text));
+            from = link_text(&mut text, from, &figref.text, &link);
+        }
+
+        out.push_str(&format!("<div class=\"claim-text\">{}\n", text));
+        claim_texts_html(&claim_text.claim_texts, figures, out);
+        out.push_str("</div>\n");
+    }
+}
+
+/// Puts the first target text in html from `from` in the link (an `a` start
+/// tag); returns where to look for the next one
+fn link_text(html: &mut String, from: usize, target: &str, link: &str) -> usize {
+    let target = escape(&normalize(target));
+    match html[from..].find(&target).filter(|_| !target.is_empty()) {
+        Some(at) => {
+            let linked = format!("{}{}</a>", link, target);
+            html.replace_range(from + at..from + at + target.len(), &linked);
+            from + at + linked.len()
+        },
+        None => from,
+    }
+}
+
+/// The number of a figure's placeholder (from 1), by its figref text; added
+/// to figures if it's the first reference to it
+fn figure(figures: &mut Vec<String>, text: &str) -> usize {
+    match figures.iter().position(|figure| figure == text) {
+        Some(n) => n + 1,
+        None => {
+            figures.push(text.to_string());
+            figures.len()
+        },
+    }
+}
+
+/// The raw xml of a description section as HTML. Figure references are
+/// added to figures (by their text, once each) and link to its placeholder.
+fn description_html(raw: &str, figures: &mut Vec<String>) -> Result<String, Error> {
+    let opts = ParserOptions::default().entities(Entities::Dtd);
+    let mut rdr = Reader::from_str(raw);
+    let mut buf = Vec::new();
+    let mut out = String::new();
+    // the closing tag for each element open, None for elements not rendered
+    let mut open: Vec<Option<&'static str>> = Vec::new();
+
+    loop {
+        match rdr.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"tables" => {
+                        let xml = element_xml(e, &mut rdr)?;
+                        for table in tables_from_raw(&xml, &opts)? {
+                            out.push_str(&table_html(&table));
+                        }
+                    },
+                    b"maths" => out.push_str(&maths_html(&mut rdr)?),
+                    b"figref" => {
+                        let text = element_text(b"figref", &mut rdr, &opts)?;
+                        out.push_str(&format!("<a class=\"figref\" href=\"#figure-{}\">{}</a>", figure(figures, &text), escape(&text)));
+                    },
+                    name => {
+                        let (tag, close) = match html_tag(name, e, &rdr)? {
+                            Some((tag, close)) => (tag, Some(close)),
+                            None => (String::new(), None),
+                        };
+                        out.push_str(&tag);
+                        open.push(close);
+                    },
+                }
+            },
+            Ok(Event::Empty(ref e)) => {
+                match e.name() {
+                    b"br" => out.push_str("<br>"),
+                    b"img" => out.push_str("<span class=\"image-placeholder\"></span>"),
+                    _ => {},
+                }
+            },
+            Ok(Event::End(_)) => {
+                if let Some(Some(close)) = open.pop() {
+                    out.push_str(close);
+                }
+            },
+            Ok(Event::Text(ref e)) => out.push_str(&escape(&decode_text(e, &rdr, &opts)?)),
+            Ok(Event::Eof) => break,
+            Ok(_) => {},
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+        buf.clear();
+    }
+
+    Ok(out)
+}
+
+/// The open tag and close tag an element is rendered as; None for those
+/// only their content is rendered of (e.g. description-of-drawings)
+fn html_tag(name: &[u8], start: &BytesStart, rdr: &Reader<&[u8]>) -> Result<Option<(String, &'static str)>, Error> {
+    let simple = |tag: &str, close: &'static str| Some((format!("<{}>", tag), close));
+
+    let tag = match name {
+        b"p" => {
+            let id = attr(start, b"id", rdr)?
+                .map(|id| format!(" id=\"{}\"", escape(&id)))
+                .unwrap_or_default();
+            Some((format!("<p{}>", id), "</p>"))
+        },
+        b"heading" => {
+            let level = attr(start, b"level", rdr)?
+                .and_then(|level| level.parse::<usize>().ok())
+                .unwrap_or(1);
+            let (open, close) = match level {
+                1 => ("<h3>", "</h3>"),
+                2 => ("<h4>", "</h4>"),
+                3 => ("<h5>", "</h5>"),
+                _ => ("<h6>", "</h6>"),
+            };
+            Some((open.to_string(), close))
+        },
+        b"b" => simple("b", "</b>"),
+        b"i" => simple("i", "</i>"),
+        b"u" => simple("u", "</u>"),
+        b"sup" => simple("sup", "</sup>"),
+        b"sub" => simple("sub", "</sub>"),
+        b"smallcaps" => Some(("<span style=\"font-variant: small-caps\">".to_string(), "</span>")),
+        b"ul" => simple("ul", "</ul>"),
+        b"ol" => simple("ol", "</ol>"),
+        b"li" => simple("li", "</li>"),
+        _ => None,
+    };
+
+    Ok(tag)
+}
+
+/// the element, whose start tag was just read, as xml
+fn element_xml(start: &BytesStart, rdr: &mut Reader<&[u8]>) -> Result<String, Error> {
+    let mut writer = quick_xml::Writer::new(Vec::new());
+    writer.write_event(Event::Start(start.clone()))
+        .map_err(|err| Error::deser(err.to_string()))?;
+    let name = start.name().to_vec();
+
+    let mut buf = Vec::new();
+    let mut depth = 1;
+    while depth > 0 {
+        let event = rdr.read_event(&mut buf)
+            .map_err(|err| Error::deser(err.to_string()))?;
+        match event {
+            Event::Start(ref e) if e.name() == &name[..] => depth += 1,
+            Event::End(ref e) if e.name() == &name[..] => depth -= 1,
+            Event::Eof => return Err(Error::deser(format!("unexpected eof in {}", String::from_utf8_lossy(&name)))),
+            _ => {},
+        }
+        writer.write_event(&event)
+            .map_err(|err| Error::deser(err.to_string()))?;
+        buf.clear();
+    }
+
+    String::from_utf8(writer.into_inner())
+        .map_err(|err| Error::deser(err.to_string()))
+}
+
+/// the text of the element whose start tag was just read, tags dropped
+fn element_text(end: &[u8], rdr: &mut Reader<&[u8]>, opts: &ParserOptions) -> Result<String, Error> {
+    let mut buf = Vec::new();
+    let mut text = String::new();
+
+    loop {
+        match rdr.read_event(&mut buf) {
+            Ok(Event::Text(ref e)) => text.push_str(&decode_text(e, rdr, opts)?),
+            Ok(Event::End(ref e)) if e.name() == end => break,
+            Ok(Event::Eof) => return Err(Error::deser(format!("unexpected eof in {}", String::from_utf8_lossy(end)))),
+            Ok(_) => {},
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+        buf.clear();
+    }
+
+    Ok(text)
+}
+
+/// A maths element, whose start tag was just read, as its MathML (which
+/// browsers render); the rest of it (e.g. its img) is dropped
+fn maths_html(rdr: &mut Reader<&[u8]>) -> Result<String, Error> {
+    Ok(format!("<span class=\"formula\">{}</span>", maths_mathml(rdr)?))
+}
+
+/// A maths element, whose start tag was just read, as its math element
+fn maths_mathml(rdr: &mut Reader<&[u8]>) -> Result<String, Error> {
+    let mut buf = Vec::new();
+    let mut mathml = String::new();
+
+    loop {
+        match rdr.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) if e.name() == b"math" => mathml = element_xml(e, rdr)?,
+            Ok(Event::End(ref e)) if e.name() == b"maths" => break,
+            Ok(Event::Eof) => return Err(Error::deser("unexpected eof in maths".to_string())),
+            Ok(_) => {},
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+        buf.clear();
+    }
+
+    Ok(mathml)
+}
+
+fn attr(start: &BytesStart, key: &[u8], rdr: &Reader<&[u8]>) -> Result<Option<String>, Error> {
+    for attr_res in start.attributes() {
+        let attr = attr_res
+            .map_err(|err| Error::deser(err.to_string()))?;
+
+        if attr.key == key {
+            return attr.unescape_and_decode_value(rdr)
+                .map(Some)
+                .map_err(|err| Error::deser(err.to_string()));
+        }
+    }
+
+    Ok(None)
+}
+
+/// escapes text for HTML, in content or an attribute value
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deserialize::PatentGrants;
+
+    #[test]
+    fn test_html() {
+        let grant = PatentGrants::from_reader(include_str!("../tests/fixtures/grants.xml").as_bytes()).next().unwrap().unwrap();
+        let html = grant.to_html();
+
+        assert!(html.starts_with("<article class=\"patent-grant\">\n<h1>Hand tool with ergonomic grip</h1>\n"));
+        assert!(html.contains("<h3>BACKGROUND</h3>\n<p id=\"p-0005\">Hand tools are known. See <i>Muckle</i>, U.S. Pat. No. 4,418,955.</p>"));
+        assert!(html.contains("<p id=\"p-0007\"><a class=\"figref\" href=\"#figure-1\">FIG. 1</a> is a perspective view of the hand tool.</p>"));
+        assert!(html.contains("<span class=\"formula\"><math overflow=\"scroll\"><mrow><mi>F</mi>"));
+        assert!(html.contains("<tr><th colspan=\"2\">TABLE 1</th></tr>\n<tr><td>Sample</td><td>Force (N)</td></tr>"));
+        assert!(html.contains("<li id=\"CLM-00003\">\n<div class=\"claim-text\">3. The hand tool of <a class=\"claim-ref\" href=\"#CLM-00001\">claim 1</a> or <a class=\"claim-ref\" href=\"#CLM-00002\">2</a>, wherein"));
+        assert!(html.contains("wherein the handle is shown in <a class=\"figref\" href=\"#figure-3\">FIG. 2A</a>."));
+        assert!(html.contains("<figure class=\"figure-placeholder\" id=\"figure-2\"><figcaption>FIGS. 2A and 2B</figcaption></figure>"));
+        // section order
+        assert!(html.find("CROSS-REFERENCE").unwrap() < html.find("DETAILED DESCRIPTION").unwrap());
+
+        assert!(html_document(&grant).starts_with("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Hand tool with ergonomic grip</title>"));
+    }
+
+    #[test]
+    fn test_markdown() {
+        let grant = PatentGrants::from_reader(include_str!("../tests/fixtures/grants.xml").as_bytes()).next().unwrap().unwrap();
+        let markdown = grant.to_markdown();
+
+        assert!(markdown.starts_with("# Hand tool with ergonomic grip\n\n**US 10165721 B2**\n\n## Abstract\n\n"));
+        assert!(markdown.contains("\n\n### BACKGROUND\n\nHand tools are known. See *Muckle*, U.S. Pat. No. 4,418,955.\n\n"));
+        assert!(markdown.contains("The grip force satisfies the relation $F=ma$ where F is force."));
+        assert!(markdown.contains("\n\n#### Test Results\n\n| TABLE 1 |  |\n| --- | --- |\n| Sample | Force (N) |\n"));
+        assert!(markdown.contains("\n\n## Claims\n\n1. A hand tool comprising:\n   - a body; and\n   - a handle attached to the body, the handle having a grip.\n\n"));
+        assert!(markdown.ends_with("wherein the body is steel.\n"));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("a < b & \"c\""), "a &lt; b &amp; &quot;c&quot;");
+        assert_eq!(md_escape("*a* [b]"), "\\*a\\* \\[b\\]");
+    }
+}
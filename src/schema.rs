@@ -0,0 +1,110 @@
+//! Versions of the redbook xml schema, and the elements renamed between them.
+//!
+//! The version is detected from the root element's dtd-version attr, so one
+//! iterator reads any year's file. Wrappers that were only renamed (e.g.
+//! `parties` vs `us-parties`) are stepped into either way and aren't listed.
+
+/// dtd-version of a grant (or application) document. The default is the
+/// latest, for documents with no (or an unknown) dtd-version.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SchemaVersion {
+    V4_0,
+    V4_1,
+    V4_2,
+    V4_3,
+    V4_4,
+    V4_5,
+    V4_6,
+    #[default]
+    V4_7,
+}
+
+impl SchemaVersion {
+    /// From the dtd-version attr, e.g. `v4.5 2014-04-03`. Also takes the
+    /// version without its date, with or without the dot (as in dtd file
+    /// names, e.g. `v45`).
+    pub fn from_dtd_version(dtd_version: &str) -> Option<Self> {
+        let version = dtd_version.split_whitespace().next()?;
+        let version = version.strip_prefix('v').unwrap_or(version);
+        let digits: String = version.chars().filter(|c| *c != '.').collect();
+
+        match digits.as_str() {
+            "40" => Some(SchemaVersion::V4_0),
+            "41" => Some(SchemaVersion::V4_1),
+            "42" => Some(SchemaVersion::V4_2),
+            "43" => Some(SchemaVersion::V4_3),
+            "44" => Some(SchemaVersion::V4_4),
+            "45" => Some(SchemaVersion::V4_5),
+            "46" => Some(SchemaVersion::V4_6),
+            "47" => Some(SchemaVersion::V4_7),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn element_names(self) -> &'static ElementNames {
+        match self {
+            SchemaVersion::V4_0 | SchemaVersion::V4_1 => &V4_0_NAMES,
+            SchemaVersion::V4_2 => &V4_2_NAMES,
+            _ => &V4_3_NAMES,
+        }
+    }
+}
+
+/// Names of the elements that differ between versions; the parser matches
+/// on these instead of literals.
+#[derive(Debug)]
+pub(crate) struct ElementNames {
+    pub references_cited: &'static [u8],
+    pub citation: &'static [u8],
+    pub applicants: &'static [u8],
+    pub applicant: &'static [u8],
+    pub field_of_search: &'static [u8],
+}
+
+const V4_0_NAMES: ElementNames = ElementNames {
+    references_cited: b"references-cited",
+    citation: b"citation",
+    applicants: b"applicants",
+    applicant: b"applicant",
+    field_of_search: b"field-of-search",
+};
+
+const V4_2_NAMES: ElementNames = ElementNames {
+    field_of_search: b"us-field-of-classification-search",
+    ..V4_0_NAMES
+};
+
+const V4_3_NAMES: ElementNames = ElementNames {
+    references_cited: b"us-references-cited",
+    citation: b"us-citation",
+    applicants: b"us-applicants",
+    applicant: b"us-applicant",
+    field_of_search: b"us-field-of-classification-search",
+};
+
+/// Names for the document's dtd-version attr, if any
+pub(crate) fn element_names(dtd_version: Option<&str>) -> &'static ElementNames {
+    dtd_version
+        .and_then(SchemaVersion::from_dtd_version)
+        .unwrap_or_default()
+        .element_names()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_dtd_version() {
+        assert_eq!(SchemaVersion::from_dtd_version("v4.5 2014-04-03"), Some(SchemaVersion::V4_5));
+        assert_eq!(SchemaVersion::from_dtd_version("v4.2 2006-08-23"), Some(SchemaVersion::V4_2));
+        assert_eq!(SchemaVersion::from_dtd_version("v47"), Some(SchemaVersion::V4_7));
+        assert_eq!(SchemaVersion::from_dtd_version("4.0"), Some(SchemaVersion::V4_0));
+        assert_eq!(SchemaVersion::from_dtd_version("v2.5"), None);
+        assert_eq!(SchemaVersion::from_dtd_version(""), None);
+
+        assert_eq!(element_names(Some("v4.1 2005-08-25")).citation, b"citation");
+        assert_eq!(element_names(Some("v4.2 2006-08-23")).field_of_search, b"us-field-of-classification-search");
+        assert_eq!(element_names(None).citation, b"us-citation");
+    }
+}
@@ -0,0 +1,285 @@
+//! A full-text index of grants, with tantivy, so bulk files can be searched
+//! locally.
+//!
+//! The title, abstract, claims and description are indexed as text, and
+//! searched by default. The doc-number, kind, publication date (YYYYMMDD, as
+//! a number) and cpc codes are stored and can be queried on too, e.g.
+//! `cpc_subclass:A01B AND date:[20190101 TO 20191231] AND shovel`. Cpc
+//! codes are indexed whole (`cpc:"A01B 1/00"`) and by subclass.
+//!
+//! Indexing a grant that's already in the index replaces it, so weeks can be
+//! added to one index as they come out, and re-indexing a file is harmless.
+
+use snafu::ResultExt;
+use std::fs;
+use std::path::Path;
+use tantivy::collector::TopDocs;
+use tantivy::directory::MmapDirectory;
+use tantivy::query::QueryParser;
+use tantivy::schema::{Field, Schema, Value, FAST, INDEXED, STORED, STRING, TEXT};
+use tantivy::{Index, IndexWriter, TantivyDocument, Term};
+
+use crate::data::{date_text, KindCode, PatentGrant};
+use crate::error::{Error, ExportIo, Search, SearchQuery};
+
+/// memory for an index writer's buffer, shared among its threads
+pub const WRITER_HEAP_SIZE: usize = 100 * 1024 * 1024;
+
+/// A grant found by `SearchIndex::search`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit {
+    pub doc_number: String,
+    pub kind: Option<String>,
+    /// YYYYMMDD, if the grant had a date
+    pub date: Option<u64>,
+    pub title: String,
+    /// e.g. "A01B 1/00", main first
+    pub cpc: Vec<String>,
+    pub score: f32,
+}
+
+#[derive(Clone, Copy)]
+struct Fields {
+    doc_number: Field,
+    kind: Field,
+    date: Field,
+    cpc: Field,
+    cpc_subclass: Field,
+    title: Field,
+    r#abstract: Field,
+    claims: Field,
+    description: Field,
+}
+
+impl Fields {
+    fn schema() -> Schema {
+        let mut builder = Schema::builder();
+        builder.add_text_field("doc_number", STRING | STORED);
+        builder.add_text_field("kind", STRING | STORED);
+        builder.add_u64_field("date", INDEXED | STORED | FAST);
+        builder.add_text_field("cpc", STRING | STORED);
+        builder.add_text_field("cpc_subclass", STRING);
+        builder.add_text_field("title", TEXT | STORED);
+        builder.add_text_field("abstract", TEXT);
+        builder.add_text_field("claims", TEXT);
+        builder.add_text_field("description", TEXT);
+        builder.build()
+    }
+
+    fn from_schema(schema: &Schema) -> Result<Self, Error> {
+        let field = |name: &str| schema.get_field(name).context(Search);
+
+        Ok(Fields {
+            doc_number: field("doc_number")?,
+            kind: field("kind")?,
+            date: field("date")?,
+            cpc: field("cpc")?,
+            cpc_subclass: field("cpc_subclass")?,
+            title: field("title")?,
+            r#abstract: field("abstract")?,
+            claims: field("claims")?,
+            description: field("description")?,
+        })
+    }
+
+    fn document(&self, patent_grant: &PatentGrant) -> TantivyDocument {
+        let dg = &patent_grant.us_bibliographic_data_grant;
+        let mut doc = TantivyDocument::default();
+
+        doc.add_text(self.doc_number, &dg.publication_reference.doc_number);
+        if let Some(kind) = &dg.publication_reference.kind {
+            doc.add_text(self.kind, KindCode::as_str(kind));
+        }
+        if let Ok(date) = date_text(&dg.publication_reference.date).parse() {
+            doc.add_u64(self.date, date);
+        }
+
+        let cpcs = &dg.classifications_cpc;
+        for cpc in std::iter::once(&cpcs.main_cpc).chain(&cpcs.further_cpc) {
+            if !cpc.section.is_empty() {
+                doc.add_text(self.cpc, cpc.symbol());
+                doc.add_text(self.cpc_subclass, format!("{}{}{}", cpc.section, cpc.class, cpc.subclass));
+            }
+        }
+
+        doc.add_text(self.title, &dg.invention_title.title);
+        for paragraph in &patent_grant.r#abstract {
+            doc.add_text(self.r#abstract, &paragraph.text);
+        }
+        for claim in &patent_grant.claims {
+            let mut texts = Vec::new();
+            claim_texts(&claim.claim_texts, &mut texts);
+            doc.add_text(self.claims, texts.join(" "));
+        }
+        for text in patent_grant.descriptions.values() {
+            doc.add_text(self.description, text);
+        }
+
+        doc
+    }
+
+    fn hit(&self, doc: &TantivyDocument, score: f32) -> Hit {
+        let text = |field: Field| doc.get_first(field).and_then(|value| value.as_str()).map(str::to_owned);
+
+        Hit {
+            doc_number: text(self.doc_number).unwrap_or_default(),
+            kind: text(self.kind),
+            date: doc.get_first(self.date).and_then(|value| value.as_u64()),
+            title: text(self.title).unwrap_or_default(),
+            cpc: doc.get_all(self.cpc)
+                .filter_map(|value| value.as_str())
+                .map(str::to_owned)
+                .collect(),
+            score,
+        }
+    }
+}
+
+/// Depth-first, as in the claim
+fn claim_texts<'a>(claim_texts: &'a [crate::data::ClaimText], texts: &mut Vec<&'a str>) {
+    for claim_text in claim_texts {
+        texts.push(&claim_text.text);
+        self::claim_texts(&claim_text.claim_texts, texts);
+    }
+}
+
+pub struct SearchIndex {
+    index: Index,
+    fields: Fields,
+}
+
+impl SearchIndex {
+    /// Opens the index in dir, or creates an empty one (and dir, if needed)
+    pub fn open_or_create(dir: &Path) -> Result<Self, Error> {
+        fs::create_dir_all(dir)
+            .context(ExportIo)?;
+        let directory = MmapDirectory::open(dir)
+            .map_err(tantivy::TantivyError::from)
+            .context(Search)?;
+        let index = Index::open_or_create(directory, Fields::schema())
+            .context(Search)?;
+
+        Self::from_index(index)
+    }
+
+    /// Opens an existing index, for searching
+    pub fn open(dir: &Path) -> Result<Self, Error> {
+        let index = Index::open_in_dir(dir)
+            .context(Search)?;
+
+        Self::from_index(index)
+    }
+
+    fn from_index(index: Index) -> Result<Self, Error> {
+        let fields = Fields::from_schema(&index.schema())?;
+
+        Ok(SearchIndex {
+            index,
+            fields,
+        })
+    }
+
+    /// Only one writer can be open on an index at a time, across processes
+    pub fn writer(&self) -> Result<SearchIndexWriter, Error> {
+        let writer = self.index.writer(WRITER_HEAP_SIZE)
+            .context(Search)?;
+
+        Ok(SearchIndexWriter {
+            writer,
+            fields: self.fields,
+        })
+    }
+
+    /// The best limit grants matching the query (in tantivy's query
+    /// syntax), best first. Grants written are searched once committed.
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<Hit>, Error> {
+        let fields = &self.fields;
+        let parser = QueryParser::for_index(&self.index, vec![fields.title, fields.r#abstract, fields.claims, fields.description]);
+        let query = parser.parse_query(query)
+            .context(SearchQuery)?;
+
+        let searcher = self.index.reader()
+            .context(Search)?
+            .searcher();
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(limit).order_by_score())
+            .context(Search)?;
+
+        top_docs.into_iter()
+            .map(|(score, address)| {
+                let doc: TantivyDocument = searcher.doc(address)
+                    .context(Search)?;
+                Ok(fields.hit(&doc, score))
+            })
+            .collect()
+    }
+}
+
+pub struct SearchIndexWriter {
+    writer: IndexWriter,
+    fields: Fields,
+}
+
+impl SearchIndexWriter {
+    /// Adds the grant, replacing any grant with its doc-number
+    pub fn write(&mut self, patent_grant: &PatentGrant) -> Result<(), Error> {
+        let doc_number = &patent_grant.us_bibliographic_data_grant.publication_reference.doc_number;
+        self.writer.delete_term(Term::from_field_text(self.fields.doc_number, doc_number));
+        self.writer.add_document(self.fields.document(patent_grant))
+            .context(Search)?;
+
+        Ok(())
+    }
+
+    /// Makes the grants written searchable
+    pub fn commit(&mut self) -> Result<(), Error> {
+        self.writer.commit()
+            .context(Search)?;
+
+        Ok(())
+    }
+
+    /// Commits, and waits for merges to finish
+    pub fn finish(mut self) -> Result<(), Error> {
+        self.commit()?;
+        self.writer.wait_merging_threads()
+            .context(Search)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PatentGrants;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/grants.xml");
+
+    #[test]
+    fn test_search_index() {
+        let dir = std::env::temp_dir().join(format!("uspto-search-{}", std::process::id()));
+
+        // indexed twice, but each grant is only in the index once
+        for _ in 0..2 {
+            let index = SearchIndex::open_or_create(&dir).unwrap();
+            let mut writer = index.writer().unwrap();
+            for patent_grant in PatentGrants::from_reader(FIXTURE.as_bytes()) {
+                writer.write(&patent_grant.unwrap()).unwrap();
+            }
+            writer.finish().unwrap();
+        }
+
+        let index = SearchIndex::open(&dir).unwrap();
+        let hits = index.search("ergonomic", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].doc_number, "10165721");
+        assert_eq!(hits[0].kind.as_deref(), Some("B2"));
+        assert_eq!(hits[0].date, Some(20190101));
+        assert_eq!(hits[0].cpc[0], "A01B 1/00");
+
+        assert_eq!(index.search("cpc_subclass:A01B", 10).unwrap().len(), 1);
+        assert_eq!(index.search("doc_number:D0838001", 10).unwrap().len(), 1);
+        assert_eq!(index.search("date:[20190101 TO 20191231]", 10).unwrap().len(), 3);
+        assert!(index.search("title:(", 10).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,48 +1,742 @@
-use serde::Serialize;
-use std::convert::From;
-
-use crate::data::PatentGrant;
-
-/// Output format (csv) to be ingested into rows of cube
-///
-/// No measures; just count
-#[derive(Serialize)]
-pub struct PatentOutput {
-    id: String,
-    date: String,
-    country_inventor: String,
-    country_assignee: String,
-    classification_locarno: String,
-    classification_national: String,
-}
-
-impl From<&PatentGrant> for PatentOutput {
-    fn from(pg: &PatentGrant) -> Self {
-        let dg = &pg.us_bibliographic_data_grant;
-
-        let country_inventor: Vec<String> = dg
-            .inventors
-            .iter()
-            .filter_map(|inventor| inventor.addressbook.address.country.as_ref())
-            .cloned()
-            .collect();
-        let country_inventor = country_inventor.join(";");
-
-        let country_assignee: Vec<String> = dg
-            .assignees
-            .iter()
-            .filter_map(|assignee| assignee.addressbook.address.country.as_ref())
-            .cloned()
-            .collect();
-        let country_assignee = country_assignee.join(";");
-
-        PatentOutput {
-            id: dg.publication_reference.doc_number.clone(),
-            date: dg.publication_reference.date.clone(),
-            country_inventor,
-            country_assignee,
-            classification_locarno: dg.classification_locarno.main_classification.clone(),
-            classification_national: dg.classification_national.main_classification.clone(),
+//! Writes grants back out as us-patent-grant xml: to subset a bulk file
+//! (documents written one after another read as a bulk file again), to
+//! redact fields before passing grants on, or to round-trip test the parser.
+//!
+//! Elements are in the dtd's order, with the names of the grant's
+//! dtd-version. What the parser doesn't keep can't be written: the
+//! drawings, inline markup in the title, abstract and claims (but for
+//! claim-refs and figrefs, which are put back around their text), and the
+//! formulae and chemistry in claims. Description sections are written from
+//! their raw xml, as they were read.
+
+use snafu::ResultExt;
+use std::fmt::Write as FmtWrite;
+use std::io::Write;
+
+use crate::data::*;
+use crate::error::{Error, WriteXml};
+use crate::render::escape;
+use crate::schema::{self, ElementNames};
+use crate::text::DESCRIPTION_ORDER;
+
+/// The description attr of each description section's PIs
+const SECTION_DESCRIPTIONS: &[(&str, &str)] = &[
+    ("RELAPP", "Other Patent Relations"),
+    ("GOVINT", "Government Interest"),
+    ("BRFSUM", "Brief Summary"),
+    ("brief-description-of-drawings", "Brief Description of Drawings"),
+    ("DETDESC", "Detailed Description"),
+    ("SEQLST", "Sequence Listing"),
+];
+
+/// The grant as an xml document, with its xml declaration (and doctype, if
+/// it has a dtd-version)
+pub fn to_xml(grant: &PatentGrant) -> String {
+    let meta = &grant.meta;
+    let mut xml = Xml {
+        out: String::new(),
+        names: schema::element_names(meta.dtd_version.as_deref()),
+    };
+
+    xml.out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    if let Some(dtd) = meta.dtd_version.as_deref().and_then(dtd_file) {
+        let _ = writeln!(xml.out, "<!DOCTYPE us-patent-grant SYSTEM \"{}\" [ ]>", dtd);
+    }
+
+    xml.start("us-patent-grant", &[
+        ("lang", Some(&meta.lang)),
+        ("dtd-version", meta.dtd_version.as_ref()),
+        ("file", meta.file.as_ref()),
+        ("status", meta.status.as_ref()),
+        ("id", meta.id.as_ref()),
+        ("country", Some(&meta.country)),
+        ("file-reference-id", meta.file_reference_id.as_ref()),
+        ("date-produced", meta.date_produced.as_ref()),
+        ("date-publ", meta.date_publ.as_ref()),
+    ]);
+
+    biblio(&mut xml, grant);
+
+    if !grant.r#abstract.is_empty() {
+        xml.start("abstract", &[("id", Some(&"abstract".to_string()))]);
+        for paragraph in &grant.r#abstract {
+            xml.paragraph(paragraph);
+        }
+        xml.end("abstract");
+    }
+
+    description(&mut xml, grant);
+
+    xml.element("us-claim-statement", &grant.us_claim_statement);
+    xml.start("claims", &[("id", Some(&"claims".to_string()))]);
+    for claim in &grant.claims {
+        xml.start("claim", &[
+            ("id", Some(&claim.id)),
+            ("num", Some(&claim.num)),
+            ("claim-type", claim.claim_type.as_ref()),
+        ]);
+        for claim_text in &claim.claim_texts {
+            xml.claim_text(claim_text);
+        }
+        xml.end("claim");
+    }
+    xml.end("claims");
+
+    xml.end("us-patent-grant");
+
+    xml.out
+}
+
+/// Writes the grant's xml document to wtr; for a bulk file, one grant after
+/// another
+pub fn write_xml<W: Write>(grant: &PatentGrant, wtr: &mut W) -> Result<(), Error> {
+    wtr.write_all(to_xml(grant).as_bytes())
+        .context(WriteXml)
+}
+
+impl PatentGrant {
+    /// See `serialize::to_xml`
+    pub fn to_xml(&self) -> String {
+        to_xml(self)
+    }
+}
+
+/// The dtd file of a dtd-version, e.g. us-patent-grant-v45-2014-04-03.dtd
+/// for "v4.5 2014-04-03"
+fn dtd_file(dtd_version: &str) -> Option<String> {
+    let mut parts = dtd_version.split_whitespace();
+    let version = parts.next()?.replace('.', "");
+    let date = parts.next()?;
+
+    Some(format!("us-patent-grant-{}-{}.dtd", version, date))
+}
+
+fn biblio(xml: &mut Xml, grant: &PatentGrant) {
+    let biblio = &grant.us_bibliographic_data_grant;
+    let names = xml.names;
+
+    xml.start("us-bibliographic-data-grant", &[]);
+
+    xml.start("publication-reference", &[]);
+    xml.document_id(&biblio.publication_reference);
+    xml.end("publication-reference");
+
+    let appl_type = grant.doc_type().map(|doc_type| match doc_type {
+        DocType::Utility => "utility",
+        DocType::Design => "design",
+        DocType::Plant => "plant",
+        DocType::Reissue => "reissue",
+        DocType::Sir => "sir",
+    }.to_string());
+    xml.start("application-reference", &[("appl-type", appl_type.as_ref())]);
+    xml.document_id(&biblio.application_reference);
+    xml.end("application-reference");
+
+    xml.element("us-application-series-code", &biblio.us_application_series_code);
+
+    if !biblio.priority_claims.is_empty() {
+        xml.start("priority-claims", &[]);
+        for priority_claim in &biblio.priority_claims {
+            xml.start("priority-claim", &[
+                ("sequence", Some(&priority_claim.sequence)),
+                ("kind", Some(&priority_claim.kind)),
+            ]);
+            xml.element("country", priority_claim.country.as_str());
+            xml.opt_element("doc-number", priority_claim.doc_number.as_deref());
+            xml.date("date", &priority_claim.date);
+            xml.wrapped("office-of-filing", "country", priority_claim.office_of_filing.as_deref());
+            xml.end("priority-claim");
+        }
+        xml.end("priority-claims");
+    }
+
+    let term = &biblio.us_term_of_grant;
+    if term.length_of_grant.is_some() || term.us_term_extension.is_some() || term.disclaimer.is_some()
+        || term.prior_disclosure_affidavit_filed.is_some() || term.text.is_some()
+    {
+        xml.start("us-term-of-grant", &[]);
+        if let Some(ref disclaimer) = term.disclaimer {
+            xml.start("disclaimer", &[]);
+            xml.opt_element("date", disclaimer.date.as_deref());
+            xml.opt_element("text", disclaimer.text.as_deref());
+            xml.end("disclaimer");
+        }
+        xml.opt_element("length-of-grant", term.length_of_grant.as_deref());
+        xml.opt_element("us-term-extension", term.us_term_extension.as_deref());
+        xml.opt_element("prior-disclosure-affidavit-filed", term.prior_disclosure_affidavit_filed.as_deref());
+        xml.opt_element("text", term.text.as_deref());
+        xml.end("us-term-of-grant");
+    }
+
+    if !biblio.classifications_ipcr.is_empty() {
+        xml.start("classifications-ipcr", &[]);
+        for ipcr in &biblio.classifications_ipcr {
+            xml.start("classification-ipcr", &[]);
+            xml.wrapped("ipc-version-indicator", "date", Some(&ipcr.ipc_version_indicator));
+            xml.opt_element("classification-level", ipcr.classification_level.as_deref());
+            xml.element("section", &ipcr.section);
+            xml.element("class", &ipcr.class);
+            xml.element("subclass", &ipcr.subclass);
+            xml.opt_element("main-group", ipcr.main_group.as_deref());
+            xml.opt_element("subgroup", ipcr.subgroup.as_deref());
+            xml.opt_element("symbol-position", ipcr.symbol_position.as_deref());
+            xml.opt_element("classification-value", ipcr.classification_value.as_deref());
+            if let Some(ref action_date) = ipcr.action_date {
+                xml.wrapped("action-date", "date", Some(&date_text(action_date)));
+            }
+            xml.wrapped("generating-office", "country", ipcr.generating_office.as_deref());
+            xml.opt_element("classification-status", ipcr.classification_status.as_deref());
+            xml.opt_element("classification-data-source", ipcr.classification_data_source.as_deref());
+            xml.end("classification-ipcr");
+        }
+        xml.end("classifications-ipcr");
+    }
+
+    let cpc = &biblio.classifications_cpc;
+    if !cpc.main_cpc.section.is_empty() {
+        xml.start("classifications-cpc", &[]);
+        xml.start("main-cpc", &[]);
+        xml.classification_cpc(&cpc.main_cpc);
+        xml.end("main-cpc");
+        if !cpc.further_cpc.is_empty() {
+            xml.start("further-cpc", &[]);
+            for further_cpc in &cpc.further_cpc {
+                xml.classification_cpc(further_cpc);
+            }
+            xml.end("further-cpc");
+        }
+        xml.end("classifications-cpc");
+    }
+
+    let locarno = &biblio.classification_locarno;
+    if !locarno.edition.is_empty() || !locarno.main_classification.is_empty() {
+        xml.start("classification-locarno", &[]);
+        xml.element("edition", &locarno.edition);
+        xml.element("main-classification", &locarno.main_classification);
+        xml.end("classification-locarno");
+    }
+
+    xml.classification_national(&biblio.classification_national);
+
+    let title = &biblio.invention_title;
+    xml.start_inline("invention-title", &[("id", title.id.as_ref()), ("lang", title.lang.as_ref())]);
+    xml.out.push_str(&escape(&title.title));
+    xml.end("invention-title");
+
+    if let Some(ref botanic) = biblio.botanic {
+        xml.start("us-botanic", &[]);
+        xml.element("latin-name", &botanic.latin_name);
+        xml.element("variety", &botanic.variety);
+        xml.end("us-botanic");
+    }
+
+    if !biblio.us_references_cited.is_empty() {
+        let references_cited = name(names.references_cited);
+        let citation_name = name(names.citation);
+
+        xml.start(references_cited, &[]);
+        for citation in &biblio.us_references_cited {
+            xml.start(citation_name, &[]);
+            match citation.citation {
+                Citation::Patent(ref patcit) => {
+                    xml.start("patcit", &[("num", Some(&patcit.num))]);
+                    xml.document_id(&patcit.document_id);
+                    xml.end("patcit");
+                },
+                Citation::NonPatent(ref nplcit) => {
+                    xml.start("nplcit", &[("num", Some(&nplcit.num))]);
+                    xml.element("othercit", &nplcit.othercit);
+                    xml.end("nplcit");
+                },
+            }
+            xml.opt_element("category", citation.category.as_deref());
+            xml.opt_element("classification-cpc-text", citation.classification_cpc_text.as_deref());
+            if let Some(ref classification_national) = citation.classification_national {
+                xml.classification_national(classification_national);
+            }
+            xml.end(citation_name);
+        }
+        xml.end(references_cited);
+    }
+
+    if let Some(number_of_claims) = biblio.number_of_claims {
+        xml.element("number-of-claims", &number_of_claims.to_string());
+    }
+    for exemplary_claim in &biblio.us_exemplary_claim {
+        xml.element("us-exemplary-claim", &exemplary_claim.to_string());
+    }
+
+    let search = &biblio.us_field_of_classification_search;
+    if !search.us_classifications_ipcr.is_empty() || !search.classification_nationals.is_empty()
+        || !search.classification_cpc_texts.is_empty() || !search.classification_cpc_combination_texts.is_empty()
+    {
+        let field_of_search = name(names.field_of_search);
+
+        xml.start(field_of_search, &[]);
+        for ipcr in &search.us_classifications_ipcr {
+            xml.element("us-classifications-ipcr", ipcr);
+        }
+        for classification_national in &search.classification_nationals {
+            xml.classification_national(classification_national);
+        }
+        for cpc_text in &search.classification_cpc_texts {
+            xml.element("classification-cpc-text", cpc_text);
+        }
+        for combination_text in &search.classification_cpc_combination_texts {
+            xml.element("classification-cpc-combination-text", combination_text);
+        }
+        xml.end(field_of_search);
+    }
+
+    let figures = &biblio.figures;
+    if figures.number_of_drawing_sheets.is_some() || figures.number_of_figures.is_some() {
+        xml.start("figures", &[]);
+        xml.opt_element("number-of-drawing-sheets", figures.number_of_drawing_sheets.map(|n| n.to_string()).as_deref());
+        xml.opt_element("number-of-figures", figures.number_of_figures.map(|n| n.to_string()).as_deref());
+        xml.end("figures");
+    }
+
+    if !biblio.us_related_documents.is_empty() {
+        xml.start("us-related-documents", &[]);
+        for related_document in &biblio.us_related_documents {
+            xml.related_document(related_document);
+        }
+        xml.end("us-related-documents");
+    }
+
+    parties(xml, biblio);
+
+    if !biblio.assignees.is_empty() {
+        xml.start("assignees", &[]);
+        for assignee in &biblio.assignees {
+            xml.start("assignee", &[]);
+            xml.addressbook(&assignee.addressbook);
+            xml.opt_element("orgname", assignee.orgname.as_deref());
+            xml.opt_element("role", assignee.role.as_deref());
+            xml.end("assignee");
+        }
+        xml.end("assignees");
+    }
+
+    let examiners = &biblio.examiners;
+    xml.start("examiners", &[]);
+    xml.examiner("primary-examiner", &examiners.primary_examiner);
+    if let Some(ref assistant_examiner) = examiners.assistant_examiner {
+        xml.examiner("assistant-examiner", assistant_examiner);
+    }
+    xml.end("examiners");
+
+    if let Some(ref filing_data) = biblio.pct_or_regional_filing_data {
+        xml.start("pct-or-regional-filing-data", &[]);
+        xml.document_id(&filing_data.document_id);
+        xml.wrapped("us-371c124-date", "date", filing_data.us_371c124_date.as_deref());
+        xml.wrapped("us-371c12-date", "date", filing_data.us_371c12_date.as_deref());
+        xml.end("pct-or-regional-filing-data");
+    }
+
+    if let Some(ref publishing_data) = biblio.pct_or_regional_publishing_data {
+        xml.start("pct-or-regional-publishing-data", &[]);
+        xml.document_id(&publishing_data.document_id);
+        xml.opt_element("gazette-num", publishing_data.gazette_num.as_deref());
+        xml.end("pct-or-regional-publishing-data");
+    }
+
+    if let Some(ref hague) = biblio.hague_agreement_data {
+        xml.start("hague-agreement-data", &[]);
+        xml.wrapped("international-filing-date", "date", Some(&hague.international_filing_date));
+        xml.wrapped("international-registration-publication-date", "date", Some(&hague.international_registration_publication_date));
+        xml.element("international-registration-number", &hague.international_registration_number);
+        xml.wrapped("international-registration-date", "date", Some(&hague.international_registration_date));
+        xml.end("hague-agreement-data");
+    }
+
+    xml.end("us-bibliographic-data-grant");
+}
+
+/// applicants, inventors and agents, in us-parties (parties before v4.3)
+fn parties(xml: &mut Xml, biblio: &BibliographicDataGrant) {
+    if biblio.us_applicants.is_empty() && biblio.inventors.is_empty() && biblio.agents.is_empty() {
+        return;
+    }
+
+    let names = xml.names;
+    let parties = match names.applicants {
+        b"us-applicants" => "us-parties",
+        _ => "parties",
+    };
+
+    xml.start(parties, &[]);
+
+    if !biblio.us_applicants.is_empty() {
+        xml.start(name(names.applicants), &[]);
+        for applicant in &biblio.us_applicants {
+            xml.start(name(names.applicant), &[
+                ("sequence", Some(&applicant.sequence)),
+                ("app-type", Some(&applicant.app_type)),
+                ("designation", Some(&applicant.designation)),
+                ("applicant-authority-category", applicant.applicant_authority_category.as_ref()),
+            ]);
+            xml.addressbook(&applicant.addressbook);
+            xml.wrapped("residence", "country", applicant.residence.as_deref());
+            xml.end(name(names.applicant));
+        }
+        xml.end(name(names.applicants));
+    }
+
+    if !biblio.inventors.is_empty() {
+        xml.start("inventors", &[]);
+        for inventor in &biblio.inventors {
+            xml.start("inventor", &[
+                ("sequence", Some(&inventor.sequence)),
+                ("designation", Some(&inventor.designation)),
+            ]);
+            xml.addressbook(&inventor.addressbook);
+            xml.end("inventor");
+        }
+        xml.end("inventors");
+    }
+
+    if !biblio.agents.is_empty() {
+        xml.start("agents", &[]);
+        for agent in &biblio.agents {
+            xml.start("agent", &[
+                ("sequence", Some(&agent.sequence)),
+                ("rep-type", Some(&agent.rep_type)),
+            ]);
+            xml.addressbook(&agent.addressbook);
+            xml.end("agent");
         }
+        xml.end("agents");
+    }
+
+    xml.end(parties);
+}
+
+/// Each description section within its lead and tail PIs, in the order
+/// grants have them
+fn description(xml: &mut Xml, grant: &PatentGrant) {
+    let mut names: Vec<_> = grant.descriptions_raw.keys().map(String::as_str).collect();
+    names.sort_by_key(|name| (DESCRIPTION_ORDER.iter().position(|n| n == name).unwrap_or(DESCRIPTION_ORDER.len()), *name));
+
+    xml.start("description", &[("id", Some(&"description".to_string()))]);
+    for name in names {
+        let description = SECTION_DESCRIPTIONS.iter()
+            .find(|(section, _)| *section == name)
+            .map_or(name, |(_, description)| *description);
+
+        let _ = writeln!(xml.out, "<?{} description=\"{}\" end=\"lead\"?>", name, escape(description));
+        xml.out.push_str(&grant.descriptions_raw[name]);
+        let _ = writeln!(xml.out, "\n<?{} description=\"{}\" end=\"tail\"?>", name, escape(description));
+    }
+    xml.end("description");
+}
+
+/// An element name from `ElementNames`
+fn name(name: &'static [u8]) -> &'static str {
+    std::str::from_utf8(name).expect("element names are ascii")
+}
+
+/// Text with its claim-refs and figrefs put back around their text: each at
+/// the first of its text after the one before it of the same kind, unless
+/// that overlaps another
+fn inline_xml(text: &str, claim_refs: &[ClaimRef], figrefs: &[FigRef]) -> String {
+    let kinds: [(&str, Vec<(&str, &str)>); 2] = [
+        ("claim-ref", claim_refs.iter().map(|r| (r.idref.as_str(), r.text.as_str())).collect()),
+        ("figref", figrefs.iter().map(|r| (r.idref.as_str(), r.text.as_str())).collect()),
+    ];
+
+    let mut spans = Vec::new();
+    for (element, refs) in &kinds {
+        let mut from = 0;
+        for &(idref, ref_text) in refs.iter().filter(|(_, ref_text)| !ref_text.is_empty()) {
+            if let Some(at) = text[from..].find(ref_text) {
+                spans.push((from + at, from + at + ref_text.len(), *element, idref));
+                from += at + ref_text.len();
+            }
+        }
+    }
+    spans.sort_unstable();
+
+    let mut xml = String::with_capacity(text.len());
+    let mut written = 0;
+    for (start, end, element, idref) in spans {
+        if start < written {
+            continue;
+        }
+        xml.push_str(&escape(&text[written..start]));
+        let _ = write!(xml, "<{} idref=\"{}\">{}</{}>", element, escape(idref), escape(&text[start..end]), element);
+        written = end;
+    }
+    xml.push_str(&escape(&text[written..]));
+
+    xml
+}
+
+/// xml being written, an element to a line as in the bulk files
+struct Xml {
+    out: String,
+    names: &'static ElementNames,
+}
+
+impl Xml {
+    /// a start tag, with the attrs that are Some
+    fn start(&mut self, name: &str, attrs: &[(&str, Option<&String>)]) {
+        self.start_inline(name, attrs);
+        self.out.push('\n');
+    }
+
+    /// a start tag, its content to follow on the same line
+    fn start_inline(&mut self, name: &str, attrs: &[(&str, Option<&String>)]) {
+        self.out.push('<');
+        self.out.push_str(name);
+        for (key, value) in attrs {
+            if let Some(value) = value {
+                let _ = write!(self.out, " {}=\"{}\"", key, escape(value));
+            }
+        }
+        self.out.push('>');
+    }
+
+    fn end(&mut self, name: &str) {
+        let _ = writeln!(self.out, "</{}>", name);
+    }
+
+    /// nothing if text is empty
+    fn element(&mut self, name: &str, text: &str) {
+        if !text.is_empty() {
+            let _ = writeln!(self.out, "<{}>{}</{}>", name, escape(text), name);
+        }
+    }
+
+    fn opt_element(&mut self, name: &str, text: Option<&str>) {
+        if let Some(text) = text {
+            self.element(name, text);
+        }
+    }
+
+    /// an element holding one other, e.g. `<action-date><date>...</date></action-date>`
+    fn wrapped<T: AsRef<str>>(&mut self, wrapper: &str, name: &str, text: Option<T>) {
+        if let Some(text) = text {
+            let _ = writeln!(self.out, "<{}><{}>{}</{}></{}>", wrapper, name, escape(text.as_ref()), name, wrapper);
+        }
+    }
+
+    /// nothing for a missing (default) date
+    fn date(&mut self, name: &str, date: &Date) {
+        if *date != Date::default() {
+            self.element(name, &date_text(date));
+        }
+    }
+
+    fn document_id(&mut self, doc_id: &DocumentId) {
+        self.start("document-id", &[]);
+        self.element("country", doc_id.country.as_str());
+        self.element("doc-number", &doc_id.doc_number);
+        self.opt_element("kind", doc_id.kind.as_ref().map(KindCode::as_str));
+        self.opt_element("name", doc_id.name.as_deref());
+        self.date("date", &doc_id.date);
+        self.end("document-id");
+    }
+
+    fn classification_cpc(&mut self, cpc: &ClassificationCpc) {
+        self.start("classification-cpc", &[]);
+        self.wrapped("cpc-version-indicator", "date", Some(&cpc.cpc_version_indicator));
+        self.element("section", &cpc.section);
+        self.element("class", &cpc.class);
+        self.element("subclass", &cpc.subclass);
+        self.element("main-group", &cpc.main_group);
+        self.element("subgroup", &cpc.subgroup);
+        self.element("symbol-position", &cpc.symbol_position);
+        self.element("classification-value", &cpc.classification_value);
+        if cpc.action_date != Date::default() {
+            self.wrapped("action-date", "date", Some(&date_text(&cpc.action_date)));
+        }
+        self.wrapped("generating-office", "country", Some(&cpc.generating_office));
+        self.opt_element("classification-status", cpc.classification_status.as_deref());
+        self.opt_element("classification-data-source", cpc.classification_data_source.as_deref());
+        self.opt_element("scheme-origination-code", cpc.scheme_origination_code.as_deref());
+        self.end("classification-cpc");
+    }
+
+    /// nothing for a missing classification
+    fn classification_national(&mut self, classification: &ClassificationNational) {
+        let main_classification = classification.main_classification.to_string();
+        if main_classification.is_empty() {
+            return;
+        }
+
+        self.start("classification-national", &[]);
+        self.element("country", classification.country.as_str());
+        self.opt_element("additional-info", classification.additional_info.as_deref());
+        self.element("main-classification", &main_classification);
+        if let Some(ref further_classification) = classification.further_classification {
+            self.element("further-classification", &further_classification.to_string());
+        }
+        self.end("classification-national");
+    }
+
+    fn related_document(&mut self, related_document: &RelatedDocument) {
+        let (element, relation) = match related_document {
+            RelatedDocument::Addition(relation) => ("addition", relation),
+            RelatedDocument::Division(relation) => ("division", relation),
+            RelatedDocument::Continuation(relation) => ("continuation", relation),
+            RelatedDocument::ContinuationInPart(relation) => ("continuation-in-part", relation),
+            RelatedDocument::ContinuingReissue(relation) => ("continuing-reissue", relation),
+            RelatedDocument::Reissue(relation) => ("reissue", relation),
+            RelatedDocument::DivisionalReissue(relation) => ("us-divisional-reissue", relation),
+            RelatedDocument::Reexamination(relation) => ("reexamination", relation),
+            RelatedDocument::ReexaminationReissueMerger(relation) => ("us-reexamination-reissue-merger", relation),
+            RelatedDocument::Substitution(relation) => ("substitution", relation),
+            RelatedDocument::UtilityModelBasis(relation) => ("utility-model-basis", relation),
+            RelatedDocument::ProvisionalApplication(provisional) => {
+                self.start("us-provisional-application", &[]);
+                self.document_id(&provisional.document_id);
+                self.opt_element("us-provisional-application-status", provisional.status.as_deref());
+                self.end("us-provisional-application");
+                return;
+            },
+            RelatedDocument::RelatedPublication(doc_id) => {
+                self.start("related-publication", &[]);
+                self.document_id(doc_id);
+                self.end("related-publication");
+                return;
+            },
+        };
+
+        let relation_element = match related_document {
+            RelatedDocument::DivisionalReissue(_) => "us-relation",
+            _ => "relation",
+        };
+
+        self.start(element, &[]);
+        self.start(relation_element, &[]);
+
+        let parent_doc = &relation.parent_doc;
+        self.start("parent-doc", &[]);
+        self.document_id(&parent_doc.document_id);
+        self.opt_element("parent-status", parent_doc.parent_status.as_deref());
+        if let Some(ref doc_id) = parent_doc.parent_grant_document {
+            self.start("parent-grant-document", &[]);
+            self.document_id(doc_id);
+            self.end("parent-grant-document");
+        }
+        if let Some(ref doc_id) = parent_doc.parent_pct_document {
+            self.start("parent-pct-document", &[]);
+            self.document_id(doc_id);
+            self.end("parent-pct-document");
+        }
+        self.wrapped("international-filing-date", "date", parent_doc.international_filing_date.as_deref());
+        self.end("parent-doc");
+
+        for child_doc in &relation.child_docs {
+            self.start("child-doc", &[]);
+            self.document_id(&child_doc.document_id);
+            self.wrapped("international-filing-date", "date", child_doc.international_filing_date.as_deref());
+            self.end("child-doc");
+        }
+
+        self.end(relation_element);
+        self.end(element);
+    }
+
+    fn addressbook(&mut self, addressbook: &AddressBook) {
+        let address = &addressbook.address;
+        if addressbook.orgname.is_none() && addressbook.first_name.is_none() && addressbook.last_name.is_none()
+            && addressbook.role.is_none() && address.city.is_none() && address.state.is_none() && address.country.is_none()
+        {
+            return;
+        }
+
+        self.start("addressbook", &[]);
+        self.opt_element("last-name", addressbook.last_name.as_deref());
+        self.opt_element("first-name", addressbook.first_name.as_deref());
+        self.opt_element("orgname", addressbook.orgname.as_deref());
+        self.opt_element("role", addressbook.role.as_deref());
+        if address.city.is_some() || address.state.is_some() || address.country.is_some() {
+            self.start("address", &[]);
+            self.opt_element("city", address.city.as_deref());
+            self.opt_element("state", address.state.as_deref());
+            self.opt_element("country", address.country.as_ref().map(CountryCode::as_str));
+            self.end("address");
+        }
+        self.end("addressbook");
+    }
+
+    fn examiner(&mut self, element: &str, examiner: &Examiner) {
+        self.start(element, &[]);
+        self.element("last-name", &examiner.last_name);
+        self.element("first-name", &examiner.first_name);
+        self.opt_element("middle-name", examiner.middle_name.as_deref());
+        self.opt_element("suffix", examiner.suffix.as_deref());
+        self.opt_element("department", examiner.department.as_deref());
+        self.end(element);
+    }
+
+    fn paragraph(&mut self, paragraph: &Paragraph) {
+        self.start_inline("p", &[("id", Some(&paragraph.id)), ("num", Some(&paragraph.num))]);
+        self.out.push_str(&inline_xml(&paragraph.text, &[], &paragraph.figrefs));
+        self.end("p");
+    }
+
+    /// nested claim-text each on its own line, after the segment's text
+    fn claim_text(&mut self, claim_text: &ClaimText) {
+        self.out.push_str("<claim-text>");
+        self.out.push_str(&inline_xml(&claim_text.text, &claim_text.claim_refs, &claim_text.figrefs));
+        if !claim_text.claim_texts.is_empty() {
+            self.out.push('\n');
+        }
+        for nested in &claim_text.claim_texts {
+            self.claim_text(nested);
+        }
+        self.end("claim-text");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deserialize::PatentGrants;
+
+    fn read(xml: &str) -> Vec<PatentGrant> {
+        PatentGrants::from_reader(xml.as_bytes()).collect::<Result<_, _>>().unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for fixture in &[include_str!("../tests/fixtures/grants.xml"), include_str!("../tests/fixtures/grant_v41.xml")] {
+            let grants = read(fixture);
+
+            let mut bulk = Vec::new();
+            for grant in &grants {
+                write_xml(grant, &mut bulk).unwrap();
+            }
+            let round_tripped = read(std::str::from_utf8(&bulk).unwrap());
+
+            assert_eq!(round_tripped.len(), grants.len());
+            for (mut grant, mut round_tripped) in grants.into_iter().zip(round_tripped) {
+                // as maps; their Debug is in no particular order
+                assert_eq!(round_tripped.descriptions, grant.descriptions);
+                assert_eq!(round_tripped.descriptions_raw, grant.descriptions_raw);
+                for grant in [&mut grant, &mut round_tripped] {
+                    grant.descriptions.clear();
+                    grant.descriptions_raw.clear();
+                }
+
+                assert_eq!(format!("{:?}", round_tripped), format!("{:?}", grant));
+            }
+        }
+    }
+
+    #[test]
+    fn test_to_xml() {
+        let grant = read(include_str!("../tests/fixtures/grants.xml")).remove(0);
+        let xml = grant.to_xml();
+
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE us-patent-grant SYSTEM \"us-patent-grant-v45-2014-04-03.dtd\" [ ]>\n<us-patent-grant lang=\"EN\" dtd-version=\"v4.5 2014-04-03\""));
+        assert!(xml.contains("<application-reference appl-type=\"utility\">\n"));
+        assert!(xml.contains("<orgname>Example &amp; Partners LLP</orgname>\n"));
+        assert!(xml.contains("<claim-text>3. The hand tool of <claim-ref idref=\"CLM-00001\">claim 1</claim-ref> or <claim-ref idref=\"CLM-00002\">2</claim-ref> , wherein the body is steel.</claim-text>\n"));
+        assert!(xml.contains("<?DETDESC description=\"Detailed Description\" end=\"lead\"?>\n<heading id=\"h-0005\" level=\"1\">DETAILED DESCRIPTION</heading>"));
+        assert!(xml.ends_with("</claims>\n</us-patent-grant>\n"));
     }
 }
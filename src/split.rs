@@ -0,0 +1,308 @@
+//! Splits a bulk file into its documents.
+//!
+//! Bulk files are concatenations of independent xml documents, each starting
+//! with its own `<?xml` declaration, so documents can be found without parsing
+//! and then parsed independently (e.g. in parallel). A declaration is `<?xml`
+//! followed by whitespace, so other processing instructions (e.g.
+//! `<?xml-stylesheet`) don't split a document.
+
+use memchr::memmem::Finder;
+use snafu::ResultExt;
+use std::io::BufRead;
+use std::mem;
+use std::ops::Range;
+
+use crate::deserialize::PatentGrants;
+use crate::docnum::grant_number;
+use crate::error::{Error, Read};
+
+const DECL: &[u8] = b"<?xml";
+
+/// Where finder (of DECL) finds a declaration in bytes: DECL and then xml
+/// whitespace
+fn find_decls<'a>(finder: &'a Finder, bytes: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+    finder.find_iter(bytes)
+        .filter(move |pos| matches!(bytes.get(pos + DECL.len()), Some(b' ' | b'\t' | b'\r' | b'\n')))
+}
+
+/// Finds document boundaries in bytes as they're pushed in, independent of
+/// how they're read (see Documents, and the async PatentGrantStream).
+pub(crate) struct Splitter {
+    finder: Finder<'static>,
+    pending: Vec<u8>,
+    // pending before this offset has no declaration (besides the one
+    // starting the document)
+    searched: usize,
+}
+
+impl Splitter {
+    pub(crate) fn new() -> Self {
+        Splitter {
+            finder: Finder::new(DECL),
+            pending: Vec::new(),
+            searched: 1,
+        }
+    }
+
+    pub(crate) fn push(&mut self, data: &[u8]) {
+        self.pending.extend_from_slice(data);
+    }
+
+    /// drops everything pushed since the last document
+    pub(crate) fn clear(&mut self) {
+        self.pending.clear();
+        self.searched = 1;
+    }
+
+    /// The next complete document, if one has been pushed. At eof, the rest of
+    /// the input is the last document.
+    pub(crate) fn next_document(&mut self, eof: bool) -> Option<Vec<u8>> {
+        while self.pending.len() > self.searched {
+            let found = find_decls(&self.finder, &self.pending[self.searched..]).next();
+            match found {
+                Some(pos) => {
+                    let split = self.searched + pos;
+
+                    // whitespace before the first declaration is dropped;
+                    // anything else (e.g. a byte order mark) stays with the
+                    // first document
+                    if find_decls(&self.finder, &self.pending[..split]).next().is_none() {
+                        if is_blank(&self.pending[..split]) {
+                            self.pending.drain(..split);
+                            self.searched = 1;
+                        } else {
+                            self.searched = split + 1;
+                        }
+                        continue;
+                    }
+
+                    let rest = self.pending.split_off(split);
+                    self.searched = 1;
+
+                    return Some(mem::replace(&mut self.pending, rest));
+                },
+                None => {
+                    // a declaration (with the whitespace after it) may
+                    // straddle the end of pending
+                    self.searched = self.searched.max(self.pending.len().saturating_sub(DECL.len()));
+                    break;
+                },
+            }
+        }
+
+        if eof && !is_blank(&self.pending) {
+            self.searched = 1;
+            return Some(mem::take(&mut self.pending));
+        }
+
+        None
+    }
+}
+
+/// Iterator over the raw bytes of each document in a bulk file, in order.
+///
+/// Each document starts with its xml declaration; whatever is before the first
+/// declaration (e.g. a byte order mark) is part of the first document, unless
+/// it's only whitespace.
+pub struct Documents<B: BufRead> {
+    rdr: B,
+    splitter: Splitter,
+    eof: bool,
+}
+
+impl<B: BufRead> Documents<B> {
+    pub fn from_reader(rdr: B) -> Self {
+        Documents {
+            rdr,
+            splitter: Splitter::new(),
+            eof: false,
+        }
+    }
+
+    fn next_document(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        loop {
+            if let Some(document) = self.splitter.next_document(self.eof) {
+                return Ok(Some(document));
+            }
+
+            if self.eof {
+                return Ok(None);
+            }
+
+            let read = match self.rdr.fill_buf() {
+                Ok(data) => {
+                    self.splitter.push(data);
+                    data.len()
+                },
+                Err(err) => {
+                    // the partial document is lost; stop after the error
+                    self.splitter.clear();
+                    self.eof = true;
+                    return Err(err).context(Read);
+                },
+            };
+            self.rdr.consume(read);
+
+            if read == 0 {
+                self.eof = true;
+            }
+        }
+    }
+}
+
+impl<B: BufRead> Iterator for Documents<B> {
+    type Item = Result<Vec<u8>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_document().transpose()
+    }
+}
+
+/// The byte range of each document in bytes holding a whole bulk file, split
+/// as Documents would.
+pub fn document_ranges(bytes: &[u8]) -> Vec<Range<usize>> {
+    // the first declaration starts the first document, with whatever is
+    // before it unless that's blank; each one after starts the next
+    let finder = Finder::new(DECL);
+    let mut starts: Vec<usize> = find_decls(&finder, bytes).collect();
+    match starts.first_mut() {
+        Some(first) if !is_blank(&bytes[..*first]) => *first = 0,
+        Some(_) => (),
+        None => starts.push(0),
+    }
+
+    let mut ranges: Vec<Range<usize>> = starts.windows(2).map(|w| w[0]..w[1]).collect();
+    let last = starts[starts.len() - 1]..bytes.len();
+    if !is_blank(&bytes[last.clone()]) {
+        ranges.push(last);
+    }
+
+    ranges
+}
+
+/// A document's publication doc-number, read from its publication-reference
+/// without parsing the rest of it (see `PatentGrants::filter_raw`). None if
+/// it hasn't got one, or it doesn't parse that far.
+pub fn doc_number(document: &[u8]) -> Option<String> {
+    let mut doc_number = None;
+    let _ = PatentGrants::from_reader(document)
+        .filter_raw(|doc_id| {
+            doc_number = Some(doc_id.doc_number.clone());
+            false
+        })
+        .next();
+
+    doc_number.filter(|doc_number| !doc_number.is_empty())
+}
+
+/// The first document with this doc-number (normalized on both sides, see
+/// `docnum::grant_number`, so `D838001` finds `D0838001`), as it is in the
+/// bulk file. Only each document's publication-reference is parsed until
+/// it's found, and reading stops there.
+pub fn find_document<B: BufRead>(documents: Documents<B>, doc_number: &str) -> Result<Option<Vec<u8>>, Error> {
+    let wanted = grant_number(doc_number);
+
+    for document in documents {
+        let document = document?;
+        if self::doc_number(&document).is_some_and(|doc_number| grant_number(&doc_number) == wanted) {
+            return Ok(Some(document));
+        }
+    }
+
+    Ok(None)
+}
+
+fn is_blank(bytes: &[u8]) -> bool {
+    bytes.iter().all(|b| b.is_ascii_whitespace())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/grants.xml");
+
+    #[test]
+    fn test_documents() {
+        let documents = Documents::from_reader(FIXTURE.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(documents.len(), 3);
+        assert!(documents.iter().all(|doc| doc.starts_with(DECL)));
+        assert_eq!(documents.concat(), FIXTURE.as_bytes());
+    }
+
+    #[test]
+    fn test_documents_small_reads() {
+        // declarations straddle reads
+        for capacity in &[1, 3, 7, 64] {
+            let documents = Documents::from_reader(BufReader::with_capacity(*capacity, FIXTURE.as_bytes()))
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+
+            assert_eq!(documents.len(), 3);
+            assert_eq!(documents.concat(), FIXTURE.as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_documents_blank() {
+        assert_eq!(Documents::from_reader(&b""[..]).count(), 0);
+        assert_eq!(Documents::from_reader(&b"\n"[..]).count(), 0);
+
+        let documents = Documents::from_reader(&b"\n<?xml a?>\n<?xml b?>\n"[..])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(documents, vec![b"<?xml a?>\n".to_vec(), b"<?xml b?>\n".to_vec()]);
+    }
+
+    #[test]
+    fn test_documents_stylesheet() {
+        // a processing instruction starting like a declaration is part of
+        // its document
+        let prolog = FIXTURE.find('\n').unwrap() + 1;
+        let input = format!("{}<?xml-stylesheet type=\"text/xsl\" href=\"grant.xsl\"?>\n{}", &FIXTURE[..prolog], &FIXTURE[prolog..]);
+
+        for capacity in &[1, 5, 6, 64] {
+            let documents = Documents::from_reader(BufReader::with_capacity(*capacity, input.as_bytes()))
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            assert_eq!(documents.len(), 3);
+            assert_eq!(documents.concat(), input.as_bytes());
+        }
+        assert_eq!(document_ranges(input.as_bytes()).len(), 3);
+        assert_eq!(PatentGrants::from_reader(input.as_bytes()).lenient().filter(Result::is_ok).count(), 3);
+    }
+
+    #[test]
+    fn test_find_document() {
+        let documents = Documents::from_reader(FIXTURE.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(doc_number(&documents[1]).as_deref(), Some("D0838001"));
+        assert_eq!(doc_number(b"<?xml version=\"1.0\"?>\n"), None);
+
+        let found = find_document(Documents::from_reader(FIXTURE.as_bytes()), "D838001").unwrap();
+        assert_eq!(found.as_ref(), Some(&documents[1]));
+        let found = find_document(Documents::from_reader(FIXTURE.as_bytes()), "PP30001").unwrap();
+        assert_eq!(found.as_ref(), Some(&documents[2]));
+        assert_eq!(find_document(Documents::from_reader(FIXTURE.as_bytes()), "1").unwrap(), None);
+    }
+
+    #[test]
+    fn test_document_ranges() {
+        let documents = Documents::from_reader(FIXTURE.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let ranges = document_ranges(FIXTURE.as_bytes());
+        assert_eq!(ranges.len(), documents.len());
+        for (range, document) in ranges.iter().zip(&documents) {
+            assert_eq!(&FIXTURE.as_bytes()[range.clone()], &document[..]);
+        }
+
+        assert!(document_ranges(b"\n").is_empty());
+        assert_eq!(document_ranges(b"\n<?xml a?>\n<?xml b?>\n"), vec![1..11, 11..21]);
+    }
+}
@@ -0,0 +1,460 @@
+//! WIPO ST.96 patent documents, the namespaced xml the USPTO is moving its
+//! products to, mapped onto the same PatentGrant as the other formats.
+//!
+//! Elements are matched by namespace and local name, not by prefix, since
+//! files bind the ST.96 namespaces (Common, Patent, and the USPTO's
+//! extension) to whatever prefixes they like. Elements in other namespaces
+//! are skipped, though text within a paragraph or claim is kept whatever
+//! its markup.
+//!
+//! Mapped: the grant (or publication) and application identifications, the
+//! title, main and further cpc, inventors, assignees, abstract, description
+//! and claims. Description sections are keyed like the grants' description
+//! PIs: the drawings' description as DRWDESC, embodiments and best mode as
+//! DETDESC, and the rest (technical field, background, disclosure) as
+//! BRFSUM. A claim's text is kept flat, a claim-text per top-level
+//! ClaimText.
+
+use quick_xml::{self, Reader};
+use quick_xml::events::{BytesStart, Event};
+use std::io::BufRead;
+
+use crate::data::*;
+use crate::error::Error;
+use crate::options::ParserOptions;
+use crate::util::{decode_attribute, decode_bytes, decode_text, FromText};
+
+const COMMON: &[u8] = b"http://www.wipo.int/standards/XMLSchema/ST96/Common";
+const PATENT: &[u8] = b"http://www.wipo.int/standards/XMLSchema/ST96/Patent";
+const US_PATENT: &[u8] = b"urn:us:gov:doc:uspto:patent";
+
+/// root elements of a patent document, in the Patent or USPTO namespace
+const ROOTS: &[&[u8]] = &[b"PatentPublication", b"PatentGrantDocument", b"PatentApplicationDocument"];
+
+fn is_st96(ns: Option<&[u8]>) -> bool {
+    matches!(ns, Some(ns) if ns == COMMON || ns == PATENT || ns == US_PATENT)
+}
+
+/// Like PatentGrants, over a file of ST.96 documents
+pub struct St96Grants<B: BufRead> {
+    rdr: quick_xml::Reader<B>,
+    buf: Vec<u8>,
+    ns_buf: Vec<u8>,
+    options: ParserOptions,
+}
+
+impl<B: BufRead> St96Grants<B> {
+    pub fn from_reader(b: B) -> Self {
+        Self::with_options(b, ParserOptions::default())
+    }
+
+    /// Of the options, `trim_text` and how text is unescaped and decoded
+    /// apply; the rest are for the us-patent-grant parser.
+    pub fn with_options(b: B, options: ParserOptions) -> Self {
+        let mut rdr = Reader::from_reader(b);
+        rdr.trim_text(options.trim_text);
+
+        St96Grants {
+            rdr,
+            buf: Vec::new(),
+            ns_buf: Vec::new(),
+            options,
+        }
+    }
+
+    /// returns None if no more documents
+    fn deser_st96_grant(&mut self) -> Option<Result<PatentGrant, Error>> {
+        let mut patent_grant = PatentGrant::default();
+
+        loop {
+            match self.rdr.read_namespaced_event(&mut self.buf, &mut self.ns_buf) {
+                Ok((ns, Event::Start(ref e))) if is_st96(ns) && ROOTS.contains(&e.local_name()) => {
+                    if let Err(err) = deser_root_meta(e, &self.rdr, &self.options, &mut patent_grant.meta) {
+                        return Some(Err(err));
+                    }
+                    break;
+                },
+                Ok((_, Event::Eof)) => return None,
+                Ok(_) => continue,
+                Err(err) => return Some(Err(Error::deser(err.to_string()))),
+            }
+        }
+        self.buf.clear();
+
+        if let Err(err) = self.deser_document(&mut patent_grant) {
+            let doc_number = &patent_grant.us_bibliographic_data_grant.publication_reference.doc_number;
+            return Some(Err(err.at_position(self.rdr.buffer_position(), doc_number)));
+        }
+
+        Some(Ok(patent_grant))
+    }
+
+    /// the document after its root's start tag, through its end tag
+    fn deser_document(&mut self, patent_grant: &mut PatentGrant) -> Result<(), Error> {
+        // local names of the elements open under the root; empty for those
+        // in other namespaces
+        let mut path: Vec<Vec<u8>> = Vec::new();
+        let mut text = String::new();
+        // the depth of the paragraph, claim text or title whose text (with
+        // everything in it) is being collected
+        let mut collecting: Option<usize> = None;
+
+        loop {
+            match self.rdr.read_namespaced_event(&mut self.buf, &mut self.ns_buf) {
+                Ok((ns, Event::Start(ref e))) => {
+                    let name = if is_st96(ns) { e.local_name().to_vec() } else { Vec::new() };
+
+                    if collecting.is_none() {
+                        text.clear();
+                        deser_start(e, &name, &path, &self.rdr, &self.options, patent_grant)?;
+                        if is_collected(&name, &path) {
+                            collecting = Some(path.len());
+                        }
+                    }
+
+                    path.push(name);
+                },
+                Ok((_, event @ Event::Text(_))) | Ok((_, event @ Event::CData(_))) => {
+                    let txt = match event {
+                        Event::CData(ref e) => decode_bytes(e.escaped(), &self.rdr, &self.options)?.into_owned(),
+                        Event::Text(ref e) => decode_text(e, &self.rdr, &self.options)?,
+                        _ => unreachable!(),
+                    };
+                    // inline markup's text, trimmed of the spaces around it
+                    let spaced = txt.starts_with(|c: char| c.is_whitespace() || matches!(c, '.' | ',' | ';' | ':' | ')'));
+                    if !text.is_empty() && !text.ends_with(char::is_whitespace) && !spaced {
+                        text.push(' ');
+                    }
+                    text.push_str(&txt);
+                },
+                Ok((_, Event::End(_))) => {
+                    let name = match path.pop() {
+                        Some(name) => name,
+                        // the root's end
+                        None => break,
+                    };
+
+                    match collecting {
+                        Some(depth) if depth == path.len() => {
+                            collecting = None;
+                            deser_collected(&name, &path, std::mem::take(&mut text), patent_grant);
+                        },
+                        Some(_) => continue,
+                        None => deser_end(&name, &path, &text, patent_grant)?,
+                    }
+                },
+                Ok((_, Event::Eof)) => return Err(Error::deser("eof before end of ST.96 document".to_string())),
+                Ok(_) => continue,
+                Err(err) => return Err(Error::deser(err.to_string())),
+            }
+            self.buf.clear();
+        }
+
+        let publication_reference = &patent_grant.us_bibliographic_data_grant.publication_reference;
+        patent_grant.meta.country = publication_reference.country.to_string();
+        patent_grant.meta.date_publ = Some(date_text(&publication_reference.date));
+        patent_grant.government_interest = patent_grant.descriptions.get("GOVINT").cloned();
+
+        Ok(())
+    }
+}
+
+impl<B: BufRead> Iterator for St96Grants<B> {
+    type Item = Result<PatentGrant, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let res = self.deser_st96_grant();
+        self.buf.clear();
+
+        res
+    }
+}
+
+/// The version (as dtd_version, e.g. "ST.96 V4_0") and language, from the
+/// root's attributes
+fn deser_root_meta<B: BufRead>(start: &BytesStart, rdr: &quick_xml::Reader<B>, opts: &ParserOptions, meta: &mut GrantMeta) -> Result<(), Error> {
+    for attr_res in start.attributes() {
+        let attr = attr_res
+            .map_err(|err| Error::deser(err.to_string()))?;
+        let value = || decode_attribute(&attr, rdr, opts);
+
+        match local_name(attr.key) {
+            b"st96Version" => meta.dtd_version = Some(format!("ST.96 {}", value()?)),
+            b"languageCode" | b"lang" => meta.lang = value()?,
+            _ => continue,
+        }
+    }
+
+    Ok(())
+}
+
+fn local_name(name: &[u8]) -> &[u8] {
+    match name.iter().position(|b| *b == b':') {
+        Some(i) => &name[i + 1..],
+        None => name,
+    }
+}
+
+fn within(path: &[Vec<u8>], name: &[u8]) -> bool {
+    path.iter().any(|open| open.as_slice() == name)
+}
+
+/// The description section a paragraph's in, by the element under
+/// Description it's in (if any)
+fn description_section(path: &[Vec<u8>]) -> &'static str {
+    let section = path.iter()
+        .skip_while(|open| open.as_slice() != b"Description")
+        .nth(1);
+
+    match section.map(Vec::as_slice) {
+        Some(b"DrawingDescription") | Some(b"BriefDescriptionOfDrawings") => "DRWDESC",
+        Some(b"EmbodimentDescription") | Some(b"BestMode") => "DETDESC",
+        Some(b"GovernmentInterestStatement") => "GOVINT",
+        Some(b"RelatedApplicationBag") | Some(b"CrossReferenceText") => "RELAPP",
+        _ => "BRFSUM",
+    }
+}
+
+/// Elements whose text is kept with all the markup within them
+fn is_collected(name: &[u8], path: &[Vec<u8>]) -> bool {
+    match name {
+        b"InventionTitle" => true,
+        b"P" => within(path, b"Abstract") || within(path, b"Description"),
+        b"ClaimText" => within(path, b"Claim"),
+        _ => false,
+    }
+}
+
+/// Starts the lists' items, for the elements in them to fill in
+fn deser_start<B: BufRead>(
+    start: &BytesStart,
+    name: &[u8],
+    path: &[Vec<u8>],
+    rdr: &quick_xml::Reader<B>,
+    opts: &ParserOptions,
+    patent_grant: &mut PatentGrant,
+    ) -> Result<(), Error>
+{
+    let biblio = &mut patent_grant.us_bibliographic_data_grant;
+    let attr = |key: &[u8]| {
+        start.attributes()
+            .filter_map(Result::ok)
+            .find(|attr| local_name(attr.key) == key)
+            .map_or(Ok(String::new()), |attr| decode_attribute(&attr, rdr, opts))
+    };
+
+    match name {
+        b"Claim" if within(path, b"Claims") => {
+            patent_grant.claims.push(Claim {
+                id: attr(b"id")?,
+                ..Claim::default()
+            });
+        },
+        b"CPCClassification" if within(path, b"FurtherCPC") => {
+            biblio.classifications_cpc.further_cpc.push(ClassificationCpc::default());
+        },
+        b"Inventor" if within(path, b"InventorBag") => {
+            biblio.inventors.push(Inventor {
+                sequence: attr(b"sequenceNumber")?,
+                ..Inventor::default()
+            });
+        },
+        b"Assignee" if within(path, b"AssigneeBag") => biblio.assignees.push(Assignee::default()),
+        _ => (),
+    }
+
+    Ok(())
+}
+
+/// Text collected from a paragraph, claim text or title
+fn deser_collected(name: &[u8], path: &[Vec<u8>], text: String, patent_grant: &mut PatentGrant) {
+    match name {
+        b"InventionTitle" => patent_grant.us_bibliographic_data_grant.invention_title.title = text,
+        b"P" if within(path, b"Abstract") => {
+            patent_grant.r#abstract.push(Paragraph {
+                text,
+                ..Paragraph::default()
+            });
+        },
+        b"P" => {
+            let description = patent_grant.descriptions.entry(description_section(path).to_string()).or_default();
+            if !description.is_empty() {
+                description.push(' ');
+            }
+            description.push_str(&text);
+        },
+        b"ClaimText" => {
+            if let Some(claim) = patent_grant.claims.last_mut() {
+                claim.claim_texts.push(ClaimText {
+                    text,
+                    ..ClaimText::default()
+                });
+            }
+        },
+        _ => (),
+    }
+}
+
+/// A leaf element's text, onto what it's within
+fn deser_end(name: &[u8], path: &[Vec<u8>], text: &str, patent_grant: &mut PatentGrant) -> Result<(), Error> {
+    let biblio = &mut patent_grant.us_bibliographic_data_grant;
+    let text = text.trim();
+    let date = |text: &str| Date::from_text(text.replace('-', ""), name);
+
+    let publication = within(path, b"PatentGrantIdentification") || within(path, b"PatentPublicationIdentification");
+    if publication {
+        let publication_reference = &mut biblio.publication_reference;
+        match name {
+            b"PatentNumber" | b"PublicationNumber" => publication_reference.doc_number = text.to_string(),
+            b"PatentDocumentKindCode" => publication_reference.kind = Some(KindCode::from(text)),
+            b"GrantDate" | b"PublicationDate" => publication_reference.date = date(text)?,
+            b"IPOfficeCode" => publication_reference.country = CountryCode::from(text),
+            _ => (),
+        }
+    } else if within(path, b"ApplicationIdentification") {
+        let application_reference = &mut biblio.application_reference;
+        match name {
+            b"ApplicationNumberText" => application_reference.doc_number = text.to_string(),
+            b"FilingDate" => application_reference.date = date(text)?,
+            b"IPOfficeCode" => application_reference.country = CountryCode::from(text),
+            _ => (),
+        }
+    } else if within(path, b"CPCClassification") {
+        let cpcs = &mut biblio.classifications_cpc;
+        let cpc = if within(path, b"MainCPC") {
+            &mut cpcs.main_cpc
+        } else {
+            match cpcs.further_cpc.last_mut() {
+                Some(cpc) => cpc,
+                None => return Ok(()),
+            }
+        };
+        match name {
+            b"CPCSection" => cpc.section = text.to_string(),
+            b"Class" => cpc.class = text.to_string(),
+            b"Subclass" => cpc.subclass = text.to_string(),
+            b"MainGroup" => cpc.main_group = text.to_string(),
+            b"Subgroup" => cpc.subgroup = text.to_string(),
+            b"CPCSymbolPositionCode" => cpc.symbol_position = text.to_string(),
+            b"CPCClassificationValueCode" => cpc.classification_value = text.to_string(),
+            _ => (),
+        }
+    } else if within(path, b"Inventor") || within(path, b"Assignee") {
+        let addressbook = if within(path, b"Inventor") {
+            match biblio.inventors.last_mut() {
+                Some(inventor) => &mut inventor.addressbook,
+                None => return Ok(()),
+            }
+        } else {
+            match biblio.assignees.last_mut() {
+                Some(assignee) => {
+                    if matches!(name, b"OrganizationStandardName" | b"EntityName") {
+                        assignee.orgname = Some(text.to_string());
+                    }
+                    &mut assignee.addressbook
+                },
+                None => return Ok(()),
+            }
+        };
+        match name {
+            b"FirstName" => addressbook.first_name = Some(text.to_string()),
+            b"LastName" => addressbook.last_name = Some(text.to_string()),
+            b"OrganizationStandardName" | b"EntityName" => addressbook.orgname = Some(text.to_string()),
+            b"CityName" => addressbook.address.city = Some(text.to_string()),
+            b"GeographicRegionName" => addressbook.address.state = Some(text.to_string()),
+            // the address's, not e.g. a nationality given after it
+            b"CountryCode" if addressbook.address.country.is_none() => {
+                addressbook.address.country = Some(CountryCode::from(text));
+            },
+            _ => (),
+        }
+    } else if name == b"ClaimNumber" {
+        if let Some(claim) = patent_grant.claims.last_mut() {
+            claim.num = text.to_string();
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/grant_st96.xml");
+
+    #[test]
+    fn test_st96_grants() {
+        let grants = St96Grants::from_reader(FIXTURE.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(grants.len(), 1);
+
+        let grant = &grants[0];
+        assert_eq!(grant.meta.dtd_version.as_deref(), Some("ST.96 V4_0"));
+        assert_eq!(grant.meta.country, "US");
+
+        let biblio = &grant.us_bibliographic_data_grant;
+        assert_eq!(biblio.publication_reference.doc_number, "10165721");
+        assert_eq!(biblio.publication_reference.kind, Some(KindCode::B2));
+        assert_eq!(date_text(&biblio.publication_reference.date), "20190101");
+        assert_eq!(biblio.application_reference.doc_number, "15123456");
+        assert_eq!(date_text(&biblio.application_reference.date), "20170301");
+        assert_eq!(biblio.invention_title.title, "Hand tool with ergonomic grip");
+        assert_eq!(biblio.classifications_cpc.main_cpc.symbol(), "A01B 1/00");
+        assert_eq!(biblio.classifications_cpc.further_cpc.len(), 1);
+        assert_eq!(biblio.classifications_cpc.further_cpc[0].symbol(), "B25G 1/10");
+
+        assert_eq!(biblio.inventors.len(), 2);
+        let addressbook = &biblio.inventors[0].addressbook;
+        assert_eq!(addressbook.last_name.as_deref(), Some("Tanaka"));
+        assert_eq!(addressbook.address.city.as_deref(), Some("Osaka"));
+        assert_eq!(addressbook.address.country.as_ref().map(|c| c.to_string()).as_deref(), Some("JP"));
+        assert_eq!(biblio.assignees[0].orgname.as_deref(), Some("ACME TOOL CO., LTD."));
+
+        assert_eq!(grant.r#abstract.len(), 1);
+        assert_eq!(grant.r#abstract[0].text, "A hand tool with a grip of rubber.");
+        assert!(grant.descriptions["BRFSUM"].contains("hand tools"));
+        assert!(grant.descriptions["DETDESC"].contains("rubber"));
+
+        assert_eq!(grant.claims.len(), 2);
+        assert_eq!(grant.claims[0].num, "1");
+        assert_eq!(grant.claims[0].id, "CLM-00001");
+        assert!(grant.claims[1].claim_texts[0].text.contains("claim 1"));
+    }
+
+    #[test]
+    fn test_st96_other_prefixes() {
+        // the same namespaces under other prefixes are the same elements
+        let xml = FIXTURE.replace("com:", "c:").replace("xmlns:com=", "xmlns:c=");
+        let grant = St96Grants::from_reader(xml.as_bytes()).next().unwrap().unwrap();
+        assert_eq!(grant.us_bibliographic_data_grant.application_reference.doc_number, "15123456");
+
+        // and other namespaces' aren't
+        let xml = FIXTURE.replace("http://www.wipo.int/standards/XMLSchema/ST96/Common", "urn:other");
+        let grant = St96Grants::from_reader(xml.as_bytes()).next().unwrap().unwrap();
+        assert!(grant.us_bibliographic_data_grant.application_reference.doc_number.is_empty());
+    }
+    #[test]
+    fn test_st96_decoding() {
+        use crate::options::Decoding;
+
+        let first = |input: &[u8], options| St96Grants::with_options(input, options).next().unwrap();
+
+        // a stray Windows-1252 right quote (marked by \u{1} until it's put
+        // in), in the title and in an attribute
+        let stray: Vec<u8> = FIXTURE
+            .replacen("Hand tool with", "Hand\u{1}s tool with", 1)
+            .replacen("\"CLM-00001\"", "\"CLM-00001\u{1}\"", 1)
+            .bytes()
+            .map(|b| if b == 1 { 0x92 } else { b })
+            .collect();
+
+        let grant = first(&stray, ParserOptions::default()).unwrap();
+        assert_eq!(grant.us_bibliographic_data_grant.invention_title.title, "Hand\u{fffd}s tool with ergonomic grip");
+        assert!(first(&stray, ParserOptions::default().decoding(Decoding::Strict)).is_err());
+        let grant = first(&stray, ParserOptions::default().decoding(Decoding::Fallback(encoding_rs::WINDOWS_1252))).unwrap();
+        assert_eq!(grant.us_bibliographic_data_grant.invention_title.title, "Hand\u{2019}s tool with ergonomic grip");
+        assert_eq!(grant.claims[0].id, "CLM-00001\u{2019}");
+    }
+}
@@ -0,0 +1,238 @@
+//! Reading bulk files from, and writing exports to, object storage by url:
+//! `s3://bucket/path`, `gs://bucket/path`, or `file:///path`.
+//!
+//! Credentials and other settings come from the environment, as the stores'
+//! own tools take them (e.g. `AWS_ACCESS_KEY_ID`, `AWS_REGION`,
+//! `GOOGLE_SERVICE_ACCOUNT`).
+//!
+//! Both ends are blocking, like the rest of the crate, each running its
+//! requests on its own single-threaded tokio runtime. An ObjectReader reads
+//! an object in ranges as it's read, and can seek, so a weekly zip archive is
+//! read through its central directory without downloading it first. An
+//! ObjectWriter uploads as it's written (in parts, once past a buffer), and
+//! the object only exists once it's finished.
+
+use object_store::buffered::{BufReader as ObjectBufReader, BufWriter as ObjectBufWriter};
+use object_store::path::Path as ObjectPath;
+use object_store::{ObjectStore as Store, ObjectStoreExt};
+use snafu::ResultExt;
+use std::io::{self, Read as IoRead, Seek, SeekFrom, Write};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::runtime::Runtime;
+use url::Url;
+
+use crate::deserialize::PatentGrants;
+use crate::error::{Error, ExportIo, ObjectStore, ObjectStoreRuntime, ObjectStoreUrl};
+use crate::input::InputReader;
+use crate::split::Documents;
+
+/// bytes requested at a time by an ObjectReader
+pub const READ_CAPACITY: usize = 8 * 1024 * 1024;
+
+/// The store for the url, configured from the environment, and the path of
+/// the object in it
+pub fn parse_url(url: &str) -> Result<(Arc<dyn Store>, ObjectPath), Error> {
+    let parsed = Url::parse(url)
+        .context(ObjectStoreUrl { url })?;
+    let (store, path) = object_store::parse_url_opts(&parsed, std::env::vars())
+        .context(ObjectStore)?;
+
+    Ok((Arc::from(store), path))
+}
+
+/// Joins a file name onto a url, as a directory
+pub fn join_url(url: &str, name: &str) -> String {
+    format!("{}/{}", url.trim_end_matches('/'), name)
+}
+
+fn runtime() -> Result<Runtime, Error> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .context(ObjectStoreRuntime)
+}
+
+/// An object, read in ranges of READ_CAPACITY bytes
+pub struct ObjectReader {
+    runtime: Runtime,
+    reader: ObjectBufReader,
+}
+
+impl ObjectReader {
+    pub fn open(url: &str) -> Result<Self, Error> {
+        let (store, path) = parse_url(url)?;
+        let runtime = runtime()?;
+        let meta = runtime.block_on(store.head(&path))
+            .context(ObjectStore)?;
+
+        Ok(ObjectReader {
+            reader: ObjectBufReader::with_capacity(store, &meta, READ_CAPACITY),
+            runtime,
+        })
+    }
+}
+
+impl IoRead for ObjectReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.runtime.block_on(self.reader.read(buf))
+    }
+}
+
+impl Seek for ObjectReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.runtime.block_on(self.reader.seek(pos))
+    }
+}
+
+/// An object, uploaded as it's written. Nothing is stored unless it's
+/// finished.
+pub struct ObjectWriter {
+    runtime: Runtime,
+    writer: ObjectBufWriter,
+}
+
+impl ObjectWriter {
+    /// Replaces the object at url, if any, once finished
+    pub fn create(url: &str) -> Result<Self, Error> {
+        let (store, path) = parse_url(url)?;
+
+        Ok(ObjectWriter {
+            runtime: runtime()?,
+            writer: ObjectBufWriter::new(store, path),
+        })
+    }
+
+    /// Uploads the rest, and completes the object
+    pub fn finish(mut self) -> Result<(), Error> {
+        self.runtime.block_on(self.writer.shutdown())
+            .context(ExportIo)
+    }
+}
+
+impl Write for ObjectWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.runtime.block_on(self.writer.write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.runtime.block_on(self.writer.flush())
+    }
+}
+
+impl PatentGrants<InputReader> {
+    /// Opens plain xml, gzipped xml, or a zip archive in object storage, as
+    /// `from_path` opens a file
+    pub fn from_url(url: &str) -> Result<Self, Error> {
+        Self::from_seekable(ObjectReader::open(url)?)
+    }
+}
+
+impl Documents<InputReader> {
+    /// The raw documents of a file in object storage, as
+    /// `PatentGrants::from_url` opens it
+    pub fn from_url(url: &str) -> Result<Self, Error> {
+        Ok(Documents::from_reader(PatentGrants::from_url(url)?.into_reader()))
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl crate::export::parquet::ParquetExporter<ObjectWriter> {
+    /// Writes a `<table>.parquet` object for each table under the url
+    pub fn create_url(url: &str) -> Result<Self, Error> {
+        Self::from_writers(|table| ObjectWriter::create(&join_url(url, &format!("{}.parquet", table))))
+    }
+
+    /// Writes out the remaining rows and the parquet footers, and completes
+    /// the objects
+    pub fn finish(self) -> Result<(), Error> {
+        for writer in self.into_writers()? {
+            writer.finish()?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/grants.xml");
+
+    fn temp_url(name: &str) -> (std::path::PathBuf, String) {
+        let dir = std::env::temp_dir().join(format!("uspto-store-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let url = Url::from_directory_path(&dir).unwrap().to_string();
+        (dir, url)
+    }
+
+    #[test]
+    fn test_object_reader_writer() {
+        let (dir, url) = temp_url("rw");
+        let xml_url = join_url(&url, "ipg190101.xml");
+
+        let mut writer = ObjectWriter::create(&xml_url).unwrap();
+        writer.write_all(FIXTURE.as_bytes()).unwrap();
+        // not there until finished
+        assert!(ObjectReader::open(&xml_url).is_err());
+        writer.finish().unwrap();
+
+        let mut reader = ObjectReader::open(&xml_url).unwrap();
+        reader.seek(SeekFrom::Start(5)).unwrap();
+        let mut xml = String::new();
+        reader.read_to_string(&mut xml).unwrap();
+        assert_eq!(xml, &FIXTURE[5..]);
+
+        let grants = PatentGrants::from_url(&xml_url).unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(grants.len(), 3);
+        assert_eq!(Documents::from_url(&xml_url).unwrap().count(), 3);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn test_from_url_zip() {
+        let (dir, url) = temp_url("zip");
+        let zip_url = join_url(&url, "ipg190101.zip");
+
+        // the zip writer needs to seek, so it's written to memory first
+        let mut buf = io::Cursor::new(Vec::new());
+        let mut zip = zip::ZipWriter::new(&mut buf);
+        zip.start_file("ipg190101.xml", zip::write::SimpleFileOptions::default()).unwrap();
+        zip.write_all(FIXTURE.as_bytes()).unwrap();
+        zip.finish().unwrap();
+        let mut writer = ObjectWriter::create(&zip_url).unwrap();
+        writer.write_all(buf.get_ref()).unwrap();
+        writer.finish().unwrap();
+
+        let grants = PatentGrants::from_url(&zip_url).unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(grants.len(), 3);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_parquet_to_url() {
+        use crate::export::parquet::ParquetExporter;
+
+        let (dir, url) = temp_url("parquet");
+        let mut exporter = ParquetExporter::create_url(&url).unwrap();
+        for grant in PatentGrants::from_reader(FIXTURE.as_bytes()) {
+            exporter.write(&grant.unwrap()).unwrap();
+        }
+        exporter.finish().unwrap();
+
+        for table in &["grants", "claims", "citations", "classifications", "parties"] {
+            assert!(dir.join(format!("{}.parquet", table)).is_file());
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,111 @@
+//! Async interface: a Stream of PatentGrant over a tokio AsyncBufRead.
+//!
+//! Documents are split out as bytes arrive (see `split`), and each is parsed
+//! once complete. Parsing a document doesn't wait on io, so it's done inline.
+
+use futures_core::Stream;
+use snafu::IntoError;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::AsyncBufRead;
+
+use crate::data::PatentGrant;
+use crate::deserialize::PatentGrants;
+use crate::options::ParserOptions;
+use crate::error::{Error, Read};
+use crate::split::Splitter;
+
+pub struct PatentGrantStream<R: AsyncBufRead + Unpin> {
+    rdr: R,
+    splitter: Splitter,
+    eof: bool,
+    options: ParserOptions,
+}
+
+impl<R: AsyncBufRead + Unpin> PatentGrantStream<R> {
+    pub fn from_reader(rdr: R) -> Self {
+        PatentGrantStream {
+            rdr,
+            splitter: Splitter::new(),
+            eof: false,
+            options: ParserOptions::default(),
+        }
+    }
+
+    /// See `PatentGrants::strict`
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.options.check_consistency = strict;
+        self
+    }
+
+    /// See `PatentGrants::with_options`
+    pub fn options(mut self, options: ParserOptions) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> Stream for PatentGrantStream<R> {
+    type Item = Result<PatentGrant, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if let Some(document) = this.splitter.next_document(this.eof) {
+                match PatentGrants::with_options(&document[..], this.options.clone()).next() {
+                    Some(res) => return Poll::Ready(Some(res)),
+                    None => continue,
+                }
+            }
+
+            if this.eof {
+                return Poll::Ready(None);
+            }
+
+            let read = match Pin::new(&mut this.rdr).poll_fill_buf(cx) {
+                Poll::Ready(Ok(data)) => {
+                    this.splitter.push(data);
+                    data.len()
+                },
+                Poll::Ready(Err(err)) => {
+                    // the partial document is lost; stop after the error
+                    this.splitter.clear();
+                    this.eof = true;
+                    return Poll::Ready(Some(Err(Read.into_error(err))));
+                },
+                Poll::Pending => return Poll::Pending,
+            };
+            Pin::new(&mut this.rdr).consume(read);
+
+            if read == 0 {
+                this.eof = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+    use tokio::io::BufReader;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/grants.xml");
+
+    #[tokio::test]
+    async fn test_stream() {
+        let sequential: Vec<_> = PatentGrants::from_reader(FIXTURE.as_bytes())
+            .map(|pg| pg.unwrap().us_bibliographic_data_grant.publication_reference.doc_number)
+            .collect();
+
+        // small reads, so documents arrive over many polls
+        let rdr = BufReader::with_capacity(16, FIXTURE.as_bytes());
+        let streamed: Vec<_> = PatentGrantStream::from_reader(rdr)
+            .map(|pg| pg.unwrap().us_bibliographic_data_grant.publication_reference.doc_number)
+            .collect()
+            .await;
+
+        assert_eq!(streamed, sequential);
+    }
+}
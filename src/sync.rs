@@ -0,0 +1,261 @@
+//! The state of a sync of the weekly bulk files (see the cli's `sync`): the
+//! files already downloaded, parsed and exported, so each is exported once
+//! however often the sync is restarted. A file some of whose documents
+//! failed to parse is recorded with the number that did, and isn't synced:
+//! it's exported again, until they all parse.
+//!
+//! The state file is text: a header line, then one line per file exported,
+//! its name, issue date, number of grants exported, when it was exported and
+//! the number of documents that failed to parse, tab-separated (the last is
+//! missing from lines written before it was kept, for none). It's rewritten
+//! whole (to a temporary file, then renamed over the old one) as each file
+//! is recorded, so an interrupted sync leaves it as it was before that file.
+
+use chrono::{NaiveDate, Utc};
+use snafu::ResultExt;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::bulkdata::BulkFile;
+use crate::error::{Error, Read, SyncStateFormat, SyncStateWrite};
+
+const HEADER: &str = "uspto-sync-state";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncedFile {
+    pub name: String,
+    /// issue date
+    pub date: NaiveDate,
+    pub grants: usize,
+    /// RFC 3339, in UTC
+    pub synced_at: String,
+    /// documents that failed to parse
+    pub errors: usize,
+}
+
+/// The files exported, kept in a state file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncState {
+    path: PathBuf,
+    // in the order first exported
+    files: Vec<SyncedFile>,
+    // of the files synced, without errors
+    names: HashSet<String>,
+}
+
+impl SyncState {
+    /// Reads the state file, or starts a new state if there isn't one yet
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        let files = match File::open(&path) {
+            Ok(file) => read_files(BufReader::new(file))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err).context(Read),
+        };
+        let names = files.iter()
+            .filter(|file| file.errors == 0)
+            .map(|file| file.name.clone())
+            .collect();
+
+        Ok(SyncState {
+            path,
+            files,
+            names,
+        })
+    }
+
+    pub fn files(&self) -> &[SyncedFile] {
+        &self.files
+    }
+
+    /// Exported, with no errors
+    pub fn is_synced(&self, name: &str) -> bool {
+        self.names.contains(name)
+    }
+
+    /// The latest issue date synced
+    pub fn last_date(&self) -> Option<NaiveDate> {
+        self.files.iter()
+            .filter(|file| file.errors == 0)
+            .map(|file| file.date)
+            .max()
+    }
+
+    /// The issue date the files not synced yet are listed from: of the
+    /// earliest exported with errors, or else the latest synced
+    pub fn resume_date(&self) -> Option<NaiveDate> {
+        self.files.iter()
+            .filter(|file| file.errors > 0)
+            .map(|file| file.date)
+            .min()
+            .or_else(|| self.last_date())
+    }
+
+    /// The files not synced yet, in the order given
+    pub fn pending(&self, files: Vec<BulkFile>) -> Vec<BulkFile> {
+        files.into_iter()
+            .filter(|file| !self.is_synced(&file.name))
+            .collect()
+    }
+
+    /// Records a file as exported, once all the grants parsed are, and
+    /// writes the state file. It's synced if none of its documents failed to
+    /// parse (errors); otherwise it's recorded again the next time.
+    pub fn record(&mut self, file: &BulkFile, grants: usize, errors: usize) -> Result<(), Error> {
+        if self.is_synced(&file.name) {
+            return Ok(());
+        }
+
+        let synced = SyncedFile {
+            name: file.name.clone(),
+            date: file.date,
+            grants,
+            synced_at: Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            errors,
+        };
+        match self.files.iter_mut().find(|recorded| recorded.name == file.name) {
+            Some(recorded) => *recorded = synced,
+            None => self.files.push(synced),
+        }
+        if errors == 0 {
+            self.names.insert(file.name.clone());
+        }
+
+        self.save()
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+
+        let mut wtr = BufWriter::new(File::create(&tmp_path).context(SyncStateWrite)?);
+        writeln!(wtr, "{}", HEADER)
+            .context(SyncStateWrite)?;
+        for file in &self.files {
+            writeln!(wtr, "{}\t{}\t{}\t{}\t{}", file.name, file.date, file.grants, file.synced_at, file.errors)
+                .context(SyncStateWrite)?;
+        }
+        wtr.into_inner()
+            .map_err(|err| err.into_error())
+            .and_then(|file| file.sync_all())
+            .context(SyncStateWrite)?;
+
+        fs::rename(&tmp_path, &self.path)
+            .context(SyncStateWrite)
+    }
+}
+
+fn read_files<R: BufRead>(rdr: R) -> Result<Vec<SyncedFile>, Error> {
+    let mut lines = rdr.lines();
+
+    let header = lines.next()
+        .unwrap_or_else(|| Ok(String::new()))
+        .context(Read)?;
+    if header != HEADER {
+        return SyncStateFormat { line: 1usize, src: format!("expected {:?} header", HEADER) }.fail();
+    }
+
+    let mut files = Vec::new();
+    for (i, line) in lines.enumerate() {
+        let line = line.context(Read)?;
+        let line_number = i + 2;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let err = |src: &str| SyncStateFormat { line: line_number, src: src.to_string() }.fail();
+        let fields = line.split('\t').collect::<Vec<_>>();
+        match fields[..] {
+            [name, date, grants, synced_at] | [name, date, grants, synced_at, _] => {
+                let date = match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+                    Ok(date) => date,
+                    Err(_) => return err("invalid issue date"),
+                };
+                let grants = match grants.parse() {
+                    Ok(grants) => grants,
+                    Err(_) => return err("invalid number of grants"),
+                };
+                let errors = match fields.get(4).map_or(Ok(0), |errors| errors.parse()) {
+                    Ok(errors) => errors,
+                    Err(_) => return err("invalid number of errors"),
+                };
+                files.push(SyncedFile {
+                    name: name.to_string(),
+                    date,
+                    grants,
+                    synced_at: synced_at.to_string(),
+                    errors,
+                });
+            },
+            _ => return err("expected name, issue date, grants, synced at and errors"),
+        }
+    }
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bulkdata::Product;
+
+    fn bulk_file(name: &str, date: (i32, u32, u32)) -> BulkFile {
+        BulkFile {
+            product: Product::GrantFullText,
+            name: name.to_string(),
+            date: NaiveDate::from_ymd_opt(date.0, date.1, date.2).unwrap(),
+            size: None,
+        }
+    }
+
+    #[test]
+    fn test_sync_state() {
+        let path = std::env::temp_dir().join(format!("uspto-sync-state-{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let files = vec![
+            bulk_file("ipg190101.zip", (2019, 1, 1)),
+            bulk_file("ipg190108.zip", (2019, 1, 8)),
+        ];
+
+        let mut state = SyncState::open(&path).unwrap();
+        assert!(state.files().is_empty());
+        assert_eq!(state.pending(files.clone()).len(), 2);
+        state.record(&files[0], 6000, 0).unwrap();
+        // recording again doesn't sync it twice
+        state.record(&files[0], 6000, 0).unwrap();
+
+        let mut state = SyncState::open(&path).unwrap();
+        assert_eq!(state.files().len(), 1);
+        assert_eq!(state.files()[0].grants, 6000);
+        assert_eq!(state.last_date(), NaiveDate::from_ymd_opt(2019, 1, 1));
+        assert_eq!(state.pending(files.clone()), vec![bulk_file("ipg190108.zip", (2019, 1, 8))]);
+
+        // a file with documents that failed to parse is pending still, and
+        // listed from
+        state.record(&files[1], 5998, 2).unwrap();
+        let mut state = SyncState::open(&path).unwrap();
+        assert_eq!(state.files()[1].errors, 2);
+        assert_eq!(state.pending(files.clone()), vec![bulk_file("ipg190108.zip", (2019, 1, 8))]);
+        assert_eq!(state.resume_date(), NaiveDate::from_ymd_opt(2019, 1, 8));
+        state.record(&files[1], 6000, 0).unwrap();
+        let state = SyncState::open(&path).unwrap();
+        assert_eq!(state.files().len(), 2);
+        assert!(state.pending(files).is_empty());
+
+        // lines from before errors were kept
+        fs::write(&path, format!("{}\nipg190101.zip\t2019-01-01\t6000\t2019-01-02T00:00:00Z\n", HEADER)).unwrap();
+        assert!(SyncState::open(&path).unwrap().is_synced("ipg190101.zip"));
+
+        fs::write(&path, format!("{}\nipg190101.zip\t2019-01-01\tmany\t2019-01-02T00:00:00Z\n", HEADER)).unwrap();
+        match SyncState::open(&path) {
+            Err(Error::SyncStateFormat { line, .. }) => assert_eq!(line, 2),
+            other => panic!("expected a format error, got {:?}", other),
+        }
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,383 @@
+//! Tables (OASIS exchange tables, in `tables`) in the description, from its
+//! raw xml, since the flattened description text loses their rows and
+//! columns. See `PatentGrant::tables`.
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+
+use crate::data::{PatentApplication, PatentGrant};
+use crate::deserialize::deser_text_with_tags_from;
+use crate::error::{Error, WithinExt};
+use crate::options::ParserOptions;
+use crate::util::Scratch;
+
+/// A table, its rows in order: the head's (if any), then the body's. A
+/// table with several tgroups has each one's rows in turn.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Table {
+    /// of the `tables`, e.g. "TABLE-US-00001"
+    pub id: String,
+    pub num: String,
+    /// the description section it's in, e.g. "DETDESC"
+    pub section: String,
+    /// the most columns of any tgroup
+    pub cols: usize,
+    pub rows: Vec<Row>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Row {
+    /// in the table's head (thead)
+    pub header: bool,
+    pub cells: Vec<Cell>,
+}
+
+/// A table entry; it spans colspan columns from col, and rowspan rows from
+/// its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cell {
+    pub text: String,
+    /// first column, from 0
+    pub col: usize,
+    pub colspan: usize,
+    pub rowspan: usize,
+}
+
+impl Table {
+    /// The table as rows of cols columns, each cell's text in its first row
+    /// and column; the rest of a spanning cell is empty.
+    pub fn grid(&self) -> Vec<Vec<String>> {
+        let mut grid = vec![vec![String::new(); self.cols]; self.rows.len()];
+        for (row, cells) in grid.iter_mut().zip(&self.rows) {
+            for cell in &cells.cells {
+                if let Some(text) = row.get_mut(cell.col) {
+                    *text = cell.text.clone();
+                }
+            }
+        }
+
+        grid
+    }
+
+    /// The grid as CSV, quoting fields as RFC 4180 does
+    pub fn to_csv(&self) -> String {
+        fn field(text: &str) -> String {
+            if text.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", text.replace('"', "\"\""))
+            } else {
+                text.to_string()
+            }
+        }
+
+        let mut csv = String::new();
+        for row in self.grid() {
+            csv.push_str(&row.iter().map(|text| field(text)).collect::<Vec<_>>().join(","));
+            csv.push('\n');
+        }
+
+        csv
+    }
+
+    /// The grid as a Markdown (GFM) table. Markdown tables have one header
+    /// row, so it's the first row, whether or not it's in the table's head.
+    pub fn to_markdown(&self) -> String {
+        fn line(row: &[String]) -> String {
+            let cells: Vec<_> = row.iter()
+                .map(|text| text.replace('|', "\\|").replace(['\n', '\r'], " "))
+                .collect();
+            format!("| {} |\n", cells.join(" | "))
+        }
+
+        let grid = self.grid();
+        let (head, body) = match grid.split_first() {
+            Some(split) => split,
+            None => return String::new(),
+        };
+
+        let mut markdown = line(head);
+        markdown.push_str(&line(&vec!["---".to_string(); self.cols]));
+        for row in body {
+            markdown.push_str(&line(row));
+        }
+
+        markdown
+    }
+}
+
+impl PatentGrant {
+    /// The tables in all of the description's sections, by num. Reads
+    /// `descriptions_raw`, with the default options.
+    pub fn tables(&self) -> Result<Vec<Table>, Error> {
+        sections_tables(&self.descriptions_raw)
+    }
+}
+
+impl PatentApplication {
+    /// See `PatentGrant::tables`
+    pub fn tables(&self) -> Result<Vec<Table>, Error> {
+        sections_tables(&self.descriptions_raw)
+    }
+}
+
+fn sections_tables(descriptions_raw: &HashMap<String, String>) -> Result<Vec<Table>, Error> {
+    let opts = ParserOptions::default();
+
+    let mut tables = Vec::new();
+    for (section, raw) in descriptions_raw {
+        for mut table in tables_from_raw(raw, &opts)? {
+            table.section = section.clone();
+            tables.push(table);
+        }
+    }
+    tables.sort_by(|a, b| (&a.num, &a.id, &a.section).cmp(&(&b.num, &b.id, &b.section)));
+
+    Ok(tables)
+}
+
+/// The tables in the raw xml of a description section (see
+/// `PatentGrant::descriptions_raw`), in order; their section isn't set.
+pub fn tables_from_raw(raw: &str, opts: &ParserOptions) -> Result<Vec<Table>, Error> {
+    let mut rdr = Reader::from_str(raw);
+    rdr.trim_text(opts.trim_text);
+    let mut buf = Vec::new();
+    let mut scratch = Scratch::default();
+
+    let mut tables = Vec::new();
+    // of the tables being read
+    let (mut id, mut num) = (String::new(), String::new());
+
+    loop {
+        match rdr.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"tables" => {
+                        id = attr(e, b"id", &rdr)?.unwrap_or_default();
+                        num = attr(e, b"num", &rdr)?.unwrap_or_default();
+                    },
+                    b"table" => {
+                        let mut table = Table {
+                            id: id.clone(),
+                            num: num.clone(),
+                            ..Table::default()
+                        };
+                        deser_table(&mut rdr, &mut scratch, opts, &mut table).within(b"table")?;
+                        tables.push(table);
+                    },
+                    _ => continue,
+                }
+            },
+            Ok(Event::Eof) => break,
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        }
+        buf.clear();
+    }
+
+    Ok(tables)
+}
+
+/// called after tag table is already hit
+fn deser_table(rdr: &mut Reader<&[u8]>, scratch: &mut Scratch, opts: &ParserOptions, table: &mut Table) -> Result<(), Error> {
+    let mut buf = Vec::new();
+    let mut tgroup = TGroup::default();
+    let mut header = false;
+
+    loop {
+        let (e, empty) = match rdr.read_event(&mut buf) {
+            Ok(Event::Start(e)) => (e, false),
+            Ok(Event::Empty(e)) => (e, true),
+            Ok(Event::End(ref e)) => {
+                match e.name() {
+                    b"row" => tgroup.end_row(),
+                    b"tgroup" => table.cols = table.cols.max(tgroup.cols),
+                    b"table" => break,
+                    _ => {},
+                }
+                buf.clear();
+                continue;
+            },
+            Ok(Event::Eof) => return Err(Error::deser("unexpected eof in table".to_string())),
+            Ok(_) => {
+                buf.clear();
+                continue;
+            },
+            Err(err) => return Err(Error::deser(err.to_string())),
+        };
+
+        match e.name() {
+            b"tgroup" => {
+                let cols = attr(&e, b"cols", rdr)?
+                    .and_then(|cols| cols.parse().ok())
+                    .unwrap_or(0);
+                tgroup = TGroup { cols, ..TGroup::default() };
+            },
+            b"colspec" => {
+                let n = attr(&e, b"colnum", rdr)?
+                    .and_then(|colnum| colnum.parse::<usize>().ok())
+                    .map(|colnum| colnum.saturating_sub(1))
+                    .unwrap_or(tgroup.colspecs);
+                if let Some(colname) = attr(&e, b"colname", rdr)? {
+                    tgroup.colnames.insert(colname, n);
+                }
+                tgroup.colspecs += 1;
+            },
+            b"spanspec" => {
+                if let (Some(spanname), Some(namest), Some(nameend)) = (attr(&e, b"spanname", rdr)?, attr(&e, b"namest", rdr)?, attr(&e, b"nameend", rdr)?) {
+                    tgroup.spanspecs.insert(spanname, (namest, nameend));
+                }
+            },
+            b"thead" => header = true,
+            b"tbody" | b"tfoot" => header = false,
+            b"row" => table.rows.push(Row { header, cells: Vec::new() }),
+            b"entry" => {
+                let span = tgroup.span(&e, rdr)?;
+                let morerows = attr(&e, b"morerows", rdr)?
+                    .and_then(|morerows| morerows.parse().ok())
+                    .unwrap_or(0);
+                let text = if empty {
+                    String::new()
+                } else {
+                    deser_text_with_tags_from(b"entry", rdr, scratch, opts).within(b"entry")?
+                };
+
+                let row = table.rows.last_mut()
+                    .ok_or_else(|| Error::deser("entry outside of a row".to_string()))?;
+                tgroup.place(row, text, span, morerows);
+            },
+            _ => {},
+        }
+        buf.clear();
+    }
+    // a tgroup without its end tag still has its columns
+    table.cols = table.cols.max(tgroup.cols);
+
+    Ok(())
+}
+
+/// The column layout of a tgroup, and the cells of rows above spanning down
+#[derive(Debug, Default)]
+struct TGroup {
+    cols: usize,
+    // number of colspecs so far, and each one's column by colname
+    colspecs: usize,
+    colnames: HashMap<String, usize>,
+    // spanname to its namest and nameend
+    spanspecs: HashMap<String, (String, String)>,
+    // for each column, the number of rows below the current one that a cell
+    // above (or in it) still spans
+    spanned: Vec<usize>,
+    // spanned, as of the start of the current row
+    spanned_above: Vec<usize>,
+    // next column to fill in the current row
+    next_col: usize,
+}
+
+impl TGroup {
+    /// an entry's first column, if it names one, and the columns it spans
+    fn span(&self, entry: &BytesStart, rdr: &Reader<&[u8]>) -> Result<(Option<usize>, usize), Error> {
+        let col = |name: &str| self.colnames.get(name).copied();
+
+        let (namest, nameend) = match attr(entry, b"spanname", rdr)?.and_then(|spanname| self.spanspecs.get(&spanname).cloned()) {
+            Some((namest, nameend)) => (Some(namest), Some(nameend)),
+            None => (attr(entry, b"namest", rdr)?.or(attr(entry, b"colname", rdr)?), attr(entry, b"nameend", rdr)?),
+        };
+
+        let start = namest.as_deref().and_then(col);
+        let end = nameend.as_deref().and_then(col);
+        let colspan = match (start, end) {
+            (Some(start), Some(end)) if end >= start => end - start + 1,
+            _ => 1,
+        };
+
+        Ok((start, colspan))
+    }
+
+    /// adds the entry to the row, at its own column or the next one not
+    /// spanned from above
+    fn place(&mut self, row: &mut Row, text: String, (start, colspan): (Option<usize>, usize), morerows: usize) {
+        let mut col = start.unwrap_or(self.next_col);
+        if start.is_none() {
+            while self.spanned_above.get(col).map(|&rows| rows > 0).unwrap_or(false) {
+                col += 1;
+            }
+        }
+
+        let end = col + colspan;
+        self.cols = self.cols.max(end);
+        if self.spanned.len() < end {
+            self.spanned.resize(end, 0);
+        }
+        for spanned in &mut self.spanned[col..end] {
+            *spanned = morerows + 1;
+        }
+        self.next_col = end;
+
+        row.cells.push(Cell { text, col, colspan, rowspan: morerows + 1 });
+    }
+
+    fn end_row(&mut self) {
+        for spanned in &mut self.spanned {
+            *spanned = spanned.saturating_sub(1);
+        }
+        self.spanned_above = self.spanned.clone();
+        self.next_col = 0;
+    }
+}
+
+fn attr(start: &BytesStart, key: &[u8], rdr: &Reader<&[u8]>) -> Result<Option<String>, Error> {
+    for attr_res in start.attributes() {
+        let attr = attr_res
+            .map_err(|err| Error::deser(err.to_string()))?;
+
+        if attr.key == key {
+            return attr.unescape_and_decode_value(rdr)
+                .map(Some)
+                .map_err(|err| Error::deser(err.to_string()));
+        }
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deserialize::PatentGrants;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/grants.xml");
+
+    #[test]
+    fn test_tables() {
+        let grant = PatentGrants::from_reader(FIXTURE.as_bytes()).next().unwrap().unwrap();
+        let tables = grant.tables().unwrap();
+        assert_eq!(tables.len(), 1);
+
+        let table = &tables[0];
+        assert_eq!((table.id.as_str(), table.section.as_str(), table.cols), ("TABLE-US-00001", "DETDESC", 2));
+        assert!(table.rows[0].header);
+        assert_eq!(table.rows[0].cells[0], Cell { text: "TABLE 1".to_string(), col: 0, colspan: 2, rowspan: 1 });
+        assert_eq!(table.grid()[2], vec!["A", "12.5"]);
+        assert_eq!(table.to_csv(), "TABLE 1,\nSample,Force (N)\nA,12.5\n");
+        assert_eq!(table.to_markdown(), "| TABLE 1 |  |\n| --- | --- |\n| Sample | Force (N) |\n| A | 12.5 |\n");
+    }
+
+    #[test]
+    fn test_table_spans() {
+        // a first column spanning two rows, next to a cell with a comma
+        let raw = r#"<tables id="TABLE-US-00002" num="00002"><table><tgroup cols="3">
+            <colspec colname="1"/><colspec colname="2"/><colspec colname="3"/>
+            <tbody>
+            <row><entry morerows="1">Group</entry><entry>1,5</entry><entry>x</entry></row>
+            <row><entry>2</entry><entry/></row>
+            <row><entry colname="3">last</entry></row>
+            </tbody></tgroup></table></tables>"#;
+
+        let tables = tables_from_raw(raw, &ParserOptions::default()).unwrap();
+        let table = &tables[0];
+        assert_eq!(table.rows[1].cells[0].col, 1);
+        assert_eq!(table.rows[0].cells[0].rowspan, 2);
+        assert_eq!(table.rows[2].cells[0].col, 2);
+        assert_eq!(table.to_csv(), "Group,\"1,5\",x\n,2,\n,,last\n");
+    }
+}
@@ -0,0 +1,180 @@
+//! A grant as plain text, e.g. for NLP: title, abstract, claims, then
+//! description, a blank line between paragraphs. See
+//! `PatentGrant::to_plain_text`.
+//!
+//! Text is normalized: entities left in it (e.g. by `Entities::KeepUnknown`)
+//! are resolved, runs of whitespace are one space, and the spaces that
+//! joining text around inline markup leaves before punctuation (e.g.
+//! "Muckle , U.S.") are dropped.
+
+use crate::data::{ClaimText, PatentGrant};
+use crate::entities;
+
+/// Description sections, in the order grants have them; any others follow,
+/// by name
+pub(crate) const DESCRIPTION_ORDER: &[&str] = &[
+    "RELAPP",
+    "GOVINT",
+    "BRFSUM",
+    "brief-description-of-drawings",
+    "DETDESC",
+];
+
+/// How to render plain text, see `PatentGrant::to_plain_text`
+#[derive(Debug, Clone, Default)]
+pub struct PlainText {
+    section_markers: bool,
+}
+
+impl PlainText {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start each part with a marker line, e.g. `[ABSTRACT]`, and each
+    /// description section with its name, e.g. `[DESCRIPTION DETDESC]`
+    pub fn section_markers(mut self, section_markers: bool) -> Self {
+        self.section_markers = section_markers;
+        self
+    }
+
+    pub fn render(&self, grant: &PatentGrant) -> String {
+        let mut out = Out::default();
+
+        self.marker(&mut out, "TITLE");
+        out.paragraph(&grant.us_bibliographic_data_grant.invention_title.title);
+
+        if !grant.r#abstract.is_empty() {
+            self.marker(&mut out, "ABSTRACT");
+            for paragraph in &grant.r#abstract {
+                out.paragraph(&paragraph.text);
+            }
+        }
+
+        if !grant.claims.is_empty() {
+            self.marker(&mut out, "CLAIMS");
+            for claim in &grant.claims {
+                let mut texts = Vec::new();
+                claim_texts(&claim.claim_texts, &mut texts);
+                out.paragraph(&texts.join(" "));
+            }
+        }
+
+        let mut names: Vec<_> = grant.descriptions.keys().map(String::as_str).collect();
+        names.sort_by_key(|name| (DESCRIPTION_ORDER.iter().position(|n| n == name).unwrap_or(DESCRIPTION_ORDER.len()), *name));
+        for name in names {
+            if self.section_markers {
+                out.paragraph(&format!("[DESCRIPTION {}]", name));
+            }
+
+            // by heading and paragraph, from the raw xml, if it reads;
+            // otherwise the section's text as one paragraph
+            match grant.description().sections_of(name) {
+                Ok(ref sections) if !sections.is_empty() => {
+                    for (heading, paragraphs) in sections {
+                        if let Some(heading) = heading {
+                            out.paragraph(heading);
+                        }
+                        for paragraph in paragraphs {
+                            out.paragraph(&paragraph.text);
+                        }
+                    }
+                },
+                _ => out.paragraph(&grant.descriptions[name]),
+            }
+        }
+
+        out.text
+    }
+
+    fn marker(&self, out: &mut Out, name: &str) {
+        if self.section_markers {
+            out.paragraph(&format!("[{}]", name));
+        }
+    }
+}
+
+impl PatentGrant {
+    /// The grant as plain text, see the `text` module. `PlainText` renders
+    /// it with section markers.
+    pub fn to_plain_text(&self) -> String {
+        PlainText::new().render(self)
+    }
+}
+
+/// text being rendered: paragraphs, a blank line between each
+#[derive(Default)]
+struct Out {
+    text: String,
+}
+
+impl Out {
+    /// normalized; nothing if that leaves nothing
+    fn paragraph(&mut self, text: &str) {
+        let text = normalize(text);
+        if text.is_empty() {
+            return;
+        }
+
+        if !self.text.is_empty() {
+            self.text.push_str("\n\n");
+        }
+        self.text.push_str(&text);
+    }
+}
+
+/// Depth-first, so segments keep the order they have in the claim
+fn claim_texts<'a>(claim_texts: &'a [ClaimText], texts: &mut Vec<&'a str>) {
+    for claim_text in claim_texts {
+        texts.push(&claim_text.text);
+        self::claim_texts(&claim_text.claim_texts, texts);
+    }
+}
+
+/// See the module docs
+pub fn normalize(text: &str) -> String {
+    let resolved = entities::resolve(text.as_bytes());
+    let resolved = String::from_utf8_lossy(&resolved);
+
+    let mut normalized = String::with_capacity(resolved.len());
+    for word in resolved.split_whitespace() {
+        let joins_previous = word.starts_with([',', '.', ';', ':', ')', ']', '!', '?'])
+            || normalized.ends_with(['(', '[']);
+        if !normalized.is_empty() && !joins_previous {
+            normalized.push(' ');
+        }
+        normalized.push_str(word);
+    }
+
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deserialize::PatentGrants;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize(" See  Muckle , U.S. Pat. No.\n4,418,955 ( 1983 ) ."), "See Muckle, U.S. Pat. No. 4,418,955 (1983).");
+        assert_eq!(normalize("about 5&deg; off axis &amp; &unknown;"), "about 5\u{b0} off axis & &unknown;");
+    }
+
+    #[test]
+    fn test_to_plain_text() {
+        let grant = PatentGrants::from_reader(include_str!("../tests/fixtures/grants.xml").as_bytes()).next().unwrap().unwrap();
+
+        let text = grant.to_plain_text();
+        assert!(text.starts_with("Hand tool with ergonomic grip\n\nA hand tool includes a body and a handle attached to the body.\n\n"));
+        assert!(text.contains("\n\n1. A hand tool comprising: a body; and a handle attached to the body, the handle having a grip.\n\n"));
+        assert!(text.contains("\n\nBACKGROUND\n\nHand tools are known. See Muckle, U.S. Pat. No. 4,418,955.\n\n"));
+        // in document order
+        assert!(text.find("CROSS-REFERENCE").unwrap() < text.find("BACKGROUND").unwrap());
+        assert!(text.find("BACKGROUND").unwrap() < text.find("DETAILED DESCRIPTION").unwrap());
+        assert!(!text.contains("[ABSTRACT]"));
+
+        let text = PlainText::new().section_markers(true).render(&grant);
+        assert!(text.starts_with("[TITLE]\n\nHand tool with ergonomic grip\n\n[ABSTRACT]\n\n"));
+        assert!(text.contains("\n\n[DESCRIPTION DETDESC]\n\nDETAILED DESCRIPTION\n\n"));
+    }
+}
@@ -0,0 +1,753 @@
+//! Trademark applications (`trademark-applications-daily`, the daily
+//! apc*.xml files): each `case-file` is a trademark's application, its
+//! mark, goods and services classes, owners and status, as of the day's
+//! changes to it.
+//!
+//! A file is one document, like the assignment files, and its `case-file`s
+//! are what's iterated over. Of each case file's many elements, the header's
+//! identification, dates and status, the statements, classifications and
+//! owners are read; the rest (e.g. its event history, correspondent and
+//! design searches) are skipped as unknown elements.
+//!
+//! Trademark assignments (`trademark-assignments`, the daily asb*.xml
+//! files) are iterated over by `TrademarkAssignments`, an
+//! `assignment-entry` at a time. They're recorded like patents', so share
+//! their record, correspondent and address types.
+
+use quick_xml::{self, Reader};
+use quick_xml::events::Event;
+use std::io::BufRead;
+
+use crate::assignment::deser_correspondent;
+use crate::data::{AssignmentAssignee, AssignmentRecord, Date};
+use crate::deserialize::deser_text_from;
+use crate::error::{Error, WithinExt};
+use crate::options::ParserOptions;
+use crate::util::{unknown_element, FromText, Scratch};
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrademarkApplication {
+    pub serial_number: String,
+    // once registered; "0000000" in the file before then
+    pub registration_number: Option<String>,
+    pub transaction_date: Date,
+
+    pub filing_date: Date,
+    pub registration_date: Option<Date>,
+    /// the application's status, e.g. 630 (new application, record
+    /// initialized), 700 (registered), 602 (abandoned)
+    pub status_code: Option<u16>,
+    pub status_date: Option<Date>,
+    /// the mark's text, for a mark with any
+    pub mark_identification: Option<String>,
+    /// e.g. "4000" for standard characters
+    pub mark_drawing_code: Option<String>,
+
+    pub statements: Vec<CaseFileStatement>,
+    pub classifications: Vec<TrademarkClassification>,
+    pub owners: Vec<TrademarkOwner>,
+}
+
+impl TrademarkApplication {
+    /// The goods and services statements, by their international class (e.g.
+    /// "008"), from the statements' type codes (GS0081 is class 008's)
+    pub fn goods_and_services(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.statements.iter()
+            .filter(|statement| statement.type_code.starts_with("GS"))
+            .filter_map(|statement| Some((statement.type_code.get(2..5)?, statement.text.as_str())))
+    }
+}
+
+/// Text about the mark, by a type code, e.g. GS for goods and services, DM
+/// for a description of the mark
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CaseFileStatement {
+    pub type_code: String,
+    pub text: String,
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrademarkClassification {
+    /// Nice classes, e.g. "008"
+    pub international_codes: Vec<String>,
+    /// the older US classes, e.g. "023"
+    pub us_codes: Vec<String>,
+    pub primary_code: Option<String>,
+    pub status_code: Option<String>,
+    pub first_use_anywhere_date: Option<Date>,
+    pub first_use_in_commerce_date: Option<Date>,
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrademarkOwner {
+    pub entry_number: Option<String>,
+    /// e.g. 10 for the original applicant, 30 for an owner after
+    /// registration
+    pub party_type: Option<String>,
+    pub party_name: String,
+    /// e.g. 01 for an individual, 03 for a corporation
+    pub legal_entity_type_code: Option<String>,
+    /// a country code, or a state code for US citizens
+    pub nationality: Option<String>,
+    // address-1 and address-2, those given
+    pub address: Vec<String>,
+    pub city: Option<String>,
+    pub state: Option<String>,
+    pub country: Option<String>,
+    pub postcode: Option<String>,
+}
+
+/// Like PatentGrants, over a daily file of trademark applications
+pub struct TrademarkApplications<B: BufRead> {
+    rdr: quick_xml::Reader<B>,
+    buf: Vec<u8>,
+    scratch: Scratch,
+    options: ParserOptions,
+}
+
+impl<B: BufRead> TrademarkApplications<B> {
+    pub fn from_reader(b: B) -> Self {
+        Self::with_options(b, ParserOptions::default())
+    }
+
+    /// Of the options (see `PatentGrants::with_options`), `trim_text`,
+    /// `entities`, `decoding`, `unknown_elements` and `skipped` apply; no
+    /// field of a case-file is required, so `missing_fields` doesn't.
+    pub fn with_options(b: B, options: ParserOptions) -> Self {
+        let mut rdr = Reader::from_reader(b);
+        rdr.trim_text(options.trim_text);
+
+        TrademarkApplications {
+            rdr,
+            buf: Vec::new(),
+            scratch: Scratch::default(),
+            options,
+        }
+    }
+
+    /// returns None if no more case files
+    fn deser_trademark_application(&mut self) -> Option<Result<TrademarkApplication, Error>> {
+        loop {
+            match self.rdr.read_event(&mut self.buf) {
+                Ok(Event::Start(ref e)) if e.name() == b"case-file" => break,
+                Ok(Event::Eof) => return None,
+                Ok(_) => continue,
+                Err(err) => return Some(Err(Error::deser(err.to_string()))),
+            }
+        }
+        self.buf.clear();
+
+        let mut application = TrademarkApplication::default();
+        if let Err(err) = deser_case_file(&mut self.rdr, &mut self.buf, &mut self.scratch, &self.options, &mut application).within(b"case-file") {
+            return Some(Err(err.at_position(self.rdr.buffer_position(), &application.serial_number)));
+        }
+
+        Some(Ok(application))
+    }
+}
+
+impl<B: BufRead> Iterator for TrademarkApplications<B> {
+    type Item = Result<TrademarkApplication, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let res = self.deser_trademark_application();
+        self.buf.clear();
+
+        res
+    }
+}
+
+fn deser_date_text<B: BufRead>(name: &[u8], rdr: &mut quick_xml::Reader<B>, scratch: &mut Scratch, opts: &ParserOptions) -> Result<Date, Error> {
+    Date::from_text(deser_text_from(name, rdr, scratch, opts)?, name)
+}
+
+/// called after tag case-file is already hit
+fn deser_case_file<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    application: &mut TrademarkApplication,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"serial-number" => application.serial_number = deser_text_from(e.name(), rdr, scratch, opts)?,
+                    b"registration-number" => {
+                        let registration_number = deser_text_from(e.name(), rdr, scratch, opts)?;
+                        if registration_number.bytes().any(|b| b != b'0') {
+                            application.registration_number = Some(registration_number);
+                        }
+                    },
+                    b"transaction-date" => application.transaction_date = deser_date_text(e.name(), rdr, scratch, opts)?,
+                    b"case-file-header" => {
+                        deser_case_file_header(rdr, buf, scratch, opts, application).within(b"case-file-header")?;
+                    },
+                    // the lists' items are read as they come
+                    b"case-file-statements" | b"classifications" | b"case-file-owners" => continue,
+                    b"case-file-statement" => {
+                        let mut statement = CaseFileStatement::default();
+                        deser_statement(rdr, buf, scratch, opts, &mut statement).within(b"case-file-statement")?;
+                        application.statements.push(statement);
+                    },
+                    b"classification" => {
+                        let mut classification = TrademarkClassification::default();
+                        deser_classification(rdr, buf, scratch, opts, &mut classification).within(b"classification")?;
+                        application.classifications.push(classification);
+                    },
+                    b"case-file-owner" => {
+                        let mut owner = TrademarkOwner::default();
+                        deser_owner(rdr, buf, scratch, opts, &mut owner).within(b"case-file-owner")?;
+                        application.owners.push(owner);
+                    },
+                    _ => unknown_element(e.name(), "case-file", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"case-file" {
+                    break;
+                }
+            },
+            Ok(Event::Eof) => return Err(Error::deser("unexpected end of file in case-file".to_string())),
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        };
+    }
+
+    Ok(())
+}
+
+/// called after tag case-file-header is already hit
+fn deser_case_file_header<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    application: &mut TrademarkApplication,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"filing-date" => application.filing_date = deser_date_text(e.name(), rdr, scratch, opts)?,
+                    b"registration-date" => application.registration_date = Some(deser_date_text(e.name(), rdr, scratch, opts)?),
+                    b"status-code" => {
+                        let txt = deser_text_from(e.name(), rdr, scratch, opts)?;
+                        let status_code = txt.trim().parse()
+                            .map_err(|err| Error::deser(format!("err: {} parsing {:?} in status-code", err, txt)))?;
+                        application.status_code = Some(status_code);
+                    },
+                    b"status-date" => application.status_date = Some(deser_date_text(e.name(), rdr, scratch, opts)?),
+                    b"mark-identification" => application.mark_identification = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"mark-drawing-code" => application.mark_drawing_code = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    // mostly indicator fields (T or F), not kept
+                    _ => unknown_element(e.name(), "case-file-header", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"case-file-header" {
+                    break;
+                }
+            },
+            Ok(Event::Eof) => return Err(Error::deser("unexpected end of file in case-file-header".to_string())),
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        };
+    }
+
+    Ok(())
+}
+
+/// called after tag case-file-statement is already hit
+fn deser_statement<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    statement: &mut CaseFileStatement,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"type-code" => statement.type_code = deser_text_from(e.name(), rdr, scratch, opts)?,
+                    b"text" => statement.text = deser_text_from(e.name(), rdr, scratch, opts)?,
+                    _ => unknown_element(e.name(), "case-file-statement", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"case-file-statement" {
+                    break;
+                }
+            },
+            Ok(Event::Eof) => return Err(Error::deser("unexpected end of file in case-file-statement".to_string())),
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        };
+    }
+
+    Ok(())
+}
+
+/// called after tag classification is already hit
+fn deser_classification<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    classification: &mut TrademarkClassification,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"international-code" => classification.international_codes.push(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"us-code" => classification.us_codes.push(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"primary-code" => classification.primary_code = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"status-code" => classification.status_code = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"first-use-anywhere-date" => classification.first_use_anywhere_date = Some(deser_date_text(e.name(), rdr, scratch, opts)?),
+                    b"first-use-in-commerce-date" => classification.first_use_in_commerce_date = Some(deser_date_text(e.name(), rdr, scratch, opts)?),
+                    // the codes' counts, and the class status's date, not kept
+                    b"international-code-total-no" | b"us-code-total-no" | b"status-date" => {
+                        deser_text_from(e.name(), rdr, scratch, opts)?;
+                    },
+                    _ => unknown_element(e.name(), "classification", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"classification" {
+                    break;
+                }
+            },
+            Ok(Event::Eof) => return Err(Error::deser("unexpected end of file in classification".to_string())),
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        };
+    }
+
+    Ok(())
+}
+
+/// called after tag case-file-owner is already hit
+fn deser_owner<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    owner: &mut TrademarkOwner,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"entry-number" => owner.entry_number = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"party-type" => owner.party_type = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"party-name" => owner.party_name = deser_text_from(e.name(), rdr, scratch, opts)?,
+                    b"legal-entity-type-code" => owner.legal_entity_type_code = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"nationality" => {
+                        // a country, or a state for US citizens
+                        let nationality = match rdr.read_event(buf) {
+                            Ok(Event::Start(ref e)) if e.name() == b"country" || e.name() == b"state" => deser_text_from(e.name(), rdr, scratch, opts)?,
+                            Ok(e) => return Err(Error::deser(format!("found {:?} in nationality", e))),
+                            Err(err) => return Err(Error::deser(err.to_string())),
+                        };
+                        owner.nationality = Some(nationality);
+                    },
+                    b"address-1" | b"address-2" => owner.address.push(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"city" => owner.city = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"state" => owner.state = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"country" => owner.country = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"postcode" => owner.postcode = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    _ => unknown_element(e.name(), "case-file-owner", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"case-file-owner" {
+                    break;
+                }
+            },
+            Ok(Event::Eof) => return Err(Error::deser("unexpected end of file in case-file-owner".to_string())),
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        };
+    }
+
+    Ok(())
+}
+
+/// A recorded assignment of trademarks (`assignment-entry`, in the daily
+/// asb*.xml trademark assignment files). The record is as for patents'.
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrademarkAssignment {
+    pub assignment_record: AssignmentRecord,
+    pub assignors: Vec<TrademarkParty>,
+    pub assignees: Vec<TrademarkParty>,
+    pub properties: Vec<TrademarkProperty>,
+}
+
+impl TrademarkAssignment {
+    /// e.g. "006521/0112", see `PatentAssignment::reel_frame`
+    pub fn reel_frame(&self) -> String {
+        let record = &self.assignment_record;
+        format!("{:0>6}/{:0>4}", record.reel_no, record.frame_no)
+    }
+}
+
+/// An assignor or assignee: its name and address, as a patent assignment's
+/// assignee's, and what trademark assignments add to them
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrademarkParty {
+    pub party: AssignmentAssignee,
+    /// e.g. "CORPORATION", "LIMITED LIABILITY COMPANY"
+    pub legal_entity_text: Option<String>,
+    pub nationality: Option<String>,
+    // assignors only
+    pub execution_date: Option<Date>,
+    pub date_acknowledged: Option<Date>,
+}
+
+/// A trademark the assignment is recorded against
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrademarkProperty {
+    /// the application's, as `TrademarkApplication::serial_number`
+    pub serial_number: Option<String>,
+    // once registered; "0000000" in the file before then
+    pub registration_number: Option<String>,
+    // for a mark registered through the Madrid protocol
+    pub intl_reg_number: Option<String>,
+}
+
+/// Like TrademarkApplications, over a daily file of trademark assignments
+pub struct TrademarkAssignments<B: BufRead> {
+    rdr: quick_xml::Reader<B>,
+    buf: Vec<u8>,
+    scratch: Scratch,
+    options: ParserOptions,
+}
+
+impl<B: BufRead> TrademarkAssignments<B> {
+    pub fn from_reader(b: B) -> Self {
+        Self::with_options(b, ParserOptions::default())
+    }
+
+    /// Of the options (see `PatentGrants::with_options`), `trim_text`,
+    /// `entities`, `decoding`, `unknown_elements` and `skipped` apply; no
+    /// field of an assignment-entry is required, so `missing_fields` doesn't.
+    pub fn with_options(b: B, options: ParserOptions) -> Self {
+        let mut rdr = Reader::from_reader(b);
+        rdr.trim_text(options.trim_text);
+
+        TrademarkAssignments {
+            rdr,
+            buf: Vec::new(),
+            scratch: Scratch::default(),
+            options,
+        }
+    }
+
+    /// returns None if no more assignments
+    fn deser_trademark_assignment(&mut self) -> Option<Result<TrademarkAssignment, Error>> {
+        loop {
+            match self.rdr.read_event(&mut self.buf) {
+                Ok(Event::Start(ref e)) if e.name() == b"assignment-entry" => break,
+                Ok(Event::Eof) => return None,
+                Ok(_) => continue,
+                Err(err) => return Some(Err(Error::deser(err.to_string()))),
+            }
+        }
+        self.buf.clear();
+
+        let mut assignment = TrademarkAssignment::default();
+        if let Err(err) = deser_assignment_entry(&mut self.rdr, &mut self.buf, &mut self.scratch, &self.options, &mut assignment).within(b"assignment-entry") {
+            return Some(Err(err.at_position(self.rdr.buffer_position(), &assignment.reel_frame())));
+        }
+
+        Some(Ok(assignment))
+    }
+}
+
+impl<B: BufRead> Iterator for TrademarkAssignments<B> {
+    type Item = Result<TrademarkAssignment, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let res = self.deser_trademark_assignment();
+        self.buf.clear();
+
+        res
+    }
+}
+
+/// called after tag assignment-entry is already hit
+fn deser_assignment_entry<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    assignment: &mut TrademarkAssignment,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"assignment" => {
+                        deser_trademark_assignment_record(rdr, buf, scratch, opts, &mut assignment.assignment_record).within(b"assignment")?;
+                    },
+                    // the lists' items are read as they come
+                    b"assignors" | b"assignees" | b"properties" => continue,
+                    b"assignor" | b"assignee" => {
+                        let name = e.name().to_vec();
+                        let mut party = TrademarkParty::default();
+                        deser_trademark_party(&name, rdr, buf, scratch, opts, &mut party).within(&name)?;
+                        if name == b"assignor" {
+                            assignment.assignors.push(party);
+                        } else {
+                            assignment.assignees.push(party);
+                        }
+                    },
+                    b"property" => {
+                        let mut property = TrademarkProperty::default();
+                        deser_trademark_property(rdr, buf, scratch, opts, &mut property).within(b"property")?;
+                        assignment.properties.push(property);
+                    },
+                    _ => unknown_element(e.name(), "assignment-entry", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"assignment-entry" {
+                    break;
+                }
+            },
+            Ok(Event::Eof) => return Err(Error::deser("unexpected end of file in assignment-entry".to_string())),
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        };
+    }
+
+    Ok(())
+}
+
+/// Mirrors the patent assignment-record, but its dates aren't nested in a
+/// date element.
+///
+/// called after tag assignment is already hit
+fn deser_trademark_assignment_record<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    record: &mut AssignmentRecord,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"reel-no" => record.reel_no = deser_text_from(e.name(), rdr, scratch, opts)?,
+                    b"frame-no" => record.frame_no = deser_text_from(e.name(), rdr, scratch, opts)?,
+                    b"last-update-date" => record.last_update_date = deser_date_text(e.name(), rdr, scratch, opts)?,
+                    b"purge-indicator" => record.purge_indicator = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"date-recorded" => record.recorded_date = deser_date_text(e.name(), rdr, scratch, opts)?,
+                    b"page-count" => {
+                        let txt = deser_text_from(e.name(), rdr, scratch, opts)?;
+                        let page_count = txt.trim().parse()
+                            .map_err(|err| Error::deser(format!("err: {} parsing {:?} in page-count", err, txt)))?;
+                        record.page_count = Some(page_count);
+                    },
+                    b"correspondent" => {
+                        deser_correspondent(rdr, buf, scratch, opts, &mut record.correspondent).within(b"correspondent")?;
+                    },
+                    b"conveyance-text" => record.conveyance_text = deser_text_from(e.name(), rdr, scratch, opts)?,
+                    _ => unknown_element(e.name(), "assignment", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"assignment" {
+                    break;
+                }
+            },
+            Ok(Event::Eof) => return Err(Error::deser("unexpected end of file in assignment".to_string())),
+            // empty elements (e.g. `<page-count/>`) are left out
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        };
+    }
+
+    Ok(())
+}
+
+/// called after tag assignor or assignee (end) is already hit
+fn deser_trademark_party<B: BufRead>(
+    end: &[u8],
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    party: &mut TrademarkParty,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"person-or-organization-name" => party.party.name = deser_text_from(e.name(), rdr, scratch, opts)?,
+                    b"address-1" | b"address-2" => party.party.address.push(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"city" => party.party.city = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"state" => party.party.state = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"country-name" => party.party.country_name = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"postcode" => party.party.postcode = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"legal-entity-text" => party.legal_entity_text = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"nationality" => party.nationality = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"execution-date" => party.execution_date = Some(deser_date_text(e.name(), rdr, scratch, opts)?),
+                    b"date-acknowledged" => party.date_acknowledged = Some(deser_date_text(e.name(), rdr, scratch, opts)?),
+                    _ => unknown_element(e.name(), end, rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == end {
+                    break;
+                }
+            },
+            Ok(Event::Eof) => return Err(Error::deser(format!("unexpected end of file in {}", String::from_utf8_lossy(end)))),
+            // empty elements (e.g. `<date-acknowledged/>`) are left at None
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        };
+    }
+
+    Ok(())
+}
+
+/// called after tag property is already hit
+fn deser_trademark_property<B: BufRead>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    property: &mut TrademarkProperty,
+    ) -> Result<(), Error>
+{
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"serial-no" => property.serial_number = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    b"registration-no" => {
+                        let registration_number = deser_text_from(e.name(), rdr, scratch, opts)?;
+                        if registration_number.bytes().any(|b| b != b'0') {
+                            property.registration_number = Some(registration_number);
+                        }
+                    },
+                    b"intl-reg-no" => property.intl_reg_number = Some(deser_text_from(e.name(), rdr, scratch, opts)?),
+                    _ => unknown_element(e.name(), "property", rdr, scratch, opts)?,
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                if e.name() == b"property" {
+                    break;
+                }
+            },
+            Ok(Event::Eof) => return Err(Error::deser("unexpected end of file in property".to_string())),
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        };
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::date_text;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/trademarks.xml");
+    const ASSIGNMENTS_FIXTURE: &str = include_str!("../tests/fixtures/trademark_assignments.xml");
+
+    #[test]
+    fn test_trademark_applications() {
+        let applications = TrademarkApplications::from_reader(FIXTURE.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(applications.len(), 2);
+
+        let application = &applications[0];
+        assert_eq!(application.serial_number, "88123456");
+        assert!(application.registration_number.is_none());
+        assert_eq!(date_text(&application.filing_date), "20180901");
+        assert_eq!(application.status_code, Some(630));
+        assert_eq!(application.mark_identification.as_deref(), Some("ACME GRIP"));
+        assert_eq!(application.goods_and_services().collect::<Vec<_>>(), vec![("008", "Hand tools, namely, trowels")]);
+
+        let classification = &application.classifications[0];
+        assert_eq!(classification.international_codes, vec!["008"]);
+        assert_eq!(classification.us_codes, vec!["023", "028"]);
+        assert_eq!(classification.primary_code.as_deref(), Some("008"));
+
+        let owner = &application.owners[0];
+        assert_eq!(owner.party_name, "ACME TOOL CO., LTD.");
+        assert_eq!(owner.nationality.as_deref(), Some("JP"));
+        assert_eq!(owner.legal_entity_type_code.as_deref(), Some("03"));
+        assert_eq!(owner.city.as_deref(), Some("OSAKA"));
+
+        let application = &applications[1];
+        assert_eq!(application.registration_number.as_deref(), Some("5678901"));
+        assert_eq!(application.status_code, Some(700));
+        assert!(application.registration_date.is_some());
+        assert_eq!(application.owners.len(), 2);
+        assert!(application.mark_identification.is_none());
+    }
+
+    #[test]
+    fn test_trademark_applications_strict() {
+        // the fixture's header has an indicator field not read
+        let options = ParserOptions::strict();
+        let err = TrademarkApplications::with_options(FIXTURE.as_bytes(), options).next().unwrap().unwrap_err();
+        assert!(err.to_string().contains("standard-characters-claimed-in"));
+    }
+
+    #[test]
+    fn test_trademark_assignments() {
+        let assignments = TrademarkAssignments::from_reader(ASSIGNMENTS_FIXTURE.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(assignments.len(), 1);
+
+        let assignment = &assignments[0];
+        assert_eq!(assignment.reel_frame(), "006521/0112");
+        let record = &assignment.assignment_record;
+        assert_eq!(date_text(&record.recorded_date), "20181231");
+        assert_eq!(record.page_count, Some(4));
+        assert_eq!(record.correspondent.name, "IP GROUP LLP");
+        assert_eq!(record.conveyance_text, "ASSIGNS THE ENTIRE INTEREST AND THE GOODWILL");
+
+        let assignor = &assignment.assignors[0];
+        assert_eq!(assignor.party.name, "Garden Tools Inc.");
+        assert_eq!(assignor.legal_entity_text.as_deref(), Some("CORPORATION"));
+        assert_eq!(assignor.execution_date.as_ref().map(date_text).as_deref(), Some("20181201"));
+        assert!(assignor.date_acknowledged.is_none());
+
+        let assignee = &assignment.assignees[0];
+        assert_eq!(assignee.party.name, "ACME TOOL CO., LTD.");
+        assert_eq!(assignee.party.country_name.as_deref(), Some("JAPAN"));
+        assert!(assignee.execution_date.is_none());
+
+        assert_eq!(assignment.properties.len(), 2);
+        assert_eq!(assignment.properties[0].serial_number.as_deref(), Some("88123456"));
+        assert!(assignment.properties[0].registration_number.is_none());
+        assert_eq!(assignment.properties[1].registration_number.as_deref(), Some("5678901"));
+    }
+}
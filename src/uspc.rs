@@ -0,0 +1,276 @@
+//! USPC (US Patent Classification) symbols, as in classification-national.
+//!
+//! The text is fixed-width: the class right-justified in three columns (e.g.
+//! ` 16`, `294`, `D 8`, `PLT`), then the subclass's whole number
+//! right-justified in three more, then any decimal or letters (e.g. `110.1`,
+//! `110R`). A digest replaces the subclass with `DIG` and its number. Leading
+//! spaces are usually trimmed away by the time it's parsed, so the subclass
+//! is found from the right of the numbers instead.
+//!
+//! The USPTO's USPC-to-CPC concordance maps the subclasses of older grants,
+//! classified before CPC, onto CPC symbols (see `Concordance`).
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::BufRead;
+
+use crate::error::{Error, Read};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Uspc {
+    /// e.g. `294`, `16`, `D8`, `PLT`
+    pub class: String,
+    /// e.g. `49`, `110.1`, `110R`; a range in a field of search (`49-59`);
+    /// or the digest number. Empty for a whole class (in a field of search).
+    pub subclass: String,
+    /// a cross-reference art collection (DIG), not a subclass
+    pub digest: bool,
+}
+
+impl Uspc {
+    pub fn new(class: &str, subclass: &str) -> Self {
+        Uspc {
+            class: class.to_string(),
+            subclass: subclass.to_string(),
+            digest: false,
+        }
+    }
+}
+
+impl From<&str> for Uspc {
+    /// Doesn't fail: text that isn't a symbol is all kept as the class
+    fn from(text: &str) -> Self {
+        let text = text.trim();
+
+        let (class, rest) = if text.starts_with(|c: char| c.is_ascii_digit()) {
+            let numbers_end = text.find(|c: char| !c.is_ascii_digit() && c != ' ').unwrap_or(text.len());
+            let numbers = text[..numbers_end].trim_end();
+
+            // the last three columns of the numbers are the subclass, unless
+            // it's a digest (or only a class)
+            if text[numbers_end..].starts_with("DIG") || numbers.len() <= 3 {
+                (numbers.to_string(), &text[numbers.len()..])
+            } else {
+                let split = numbers.len() - 3;
+                (numbers[..split].trim().to_string(), &text[split..])
+            }
+        } else {
+            // letters (design, plant, and a few others like G9B) take all
+            // three columns
+            let split = text.char_indices().nth(3).map(|(i, _)| i).unwrap_or(text.len());
+            (text[..split].replace(' ', ""), &text[split..])
+        };
+
+        let rest = rest.trim();
+        match rest.strip_prefix("DIG") {
+            Some(number) => Uspc {
+                class,
+                subclass: number.trim().to_string(),
+                digest: true,
+            },
+            None => Uspc {
+                class,
+                subclass: rest.replace(' ', ""),
+                digest: false,
+            },
+        }
+    }
+}
+
+impl From<String> for Uspc {
+    fn from(text: String) -> Self {
+        Uspc::from(text.as_str())
+    }
+}
+
+/// The fixed-width text, parsing back to the same Uspc
+impl fmt::Display for Uspc {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // a missing classification
+        if self.class.is_empty() && self.subclass.is_empty() {
+            return Ok(());
+        }
+
+        match self.class.strip_prefix('D') {
+            Some(design) if !design.is_empty() && design.len() <= 2 && design.bytes().all(|b| b.is_ascii_digit()) => {
+                write!(f, "D{:>2}", design)?;
+            },
+            _ => write!(f, "{:>3}", self.class)?,
+        }
+
+        if self.digest {
+            write!(f, "DIG{:>3}", self.subclass)
+        } else if self.subclass.is_empty() {
+            Ok(())
+        } else {
+            let number_end = self.subclass.find(|c: char| !c.is_ascii_digit()).unwrap_or(self.subclass.len());
+            write!(f, "{:>3}{}", &self.subclass[..number_end], &self.subclass[number_end..])
+        }
+    }
+}
+
+/// The concordance table: each USPC subclass's CPC symbols.
+///
+/// A line of the table is a USPC symbol, written class/subclass (e.g.
+/// "705/37", "D08/107"), and its CPC symbols, all separated by tabs, commas
+/// or semicolons; a header line is skipped. Symbols' leading zeros (in the
+/// class, and the subclass's whole number) don't matter, and CPC symbols are
+/// kept without spaces, as `cpc::CpcScheme` looks them up.
+#[derive(Debug, Default)]
+pub struct Concordance {
+    cpc_by_uspc: HashMap<Uspc, Vec<String>>,
+}
+
+impl Concordance {
+    pub fn from_reader<B: BufRead>(mut rdr: B) -> Result<Self, Error> {
+        let mut concordance = Concordance::default();
+        let mut line_number = 0;
+
+        loop {
+            let mut line = Vec::new();
+            let read = rdr.read_until(b'\n', &mut line)
+                .context(Read)?;
+            if read == 0 {
+                break;
+            }
+            line_number += 1;
+
+            let line = String::from_utf8_lossy(&line);
+            let mut fields = line.split(['\t', ',', ';'])
+                .map(|field| field.trim().trim_matches('"').trim())
+                .filter(|field| !field.is_empty());
+
+            let uspc = match fields.next() {
+                Some(uspc) => uspc,
+                None => continue,
+            };
+            let uspc = match concordance_key(uspc) {
+                Some(uspc) => uspc,
+                None if line_number == 1 => continue,
+                None => return Err(Error::ConcordanceFormat { line: line_number, src: format!("invalid USPC symbol {:?}", uspc) }),
+            };
+
+            let cpcs = concordance.cpc_by_uspc.entry(uspc).or_default();
+            for cpc in fields {
+                let cpc = cpc.replace(' ', "");
+                if !cpcs.contains(&cpc) {
+                    cpcs.push(cpc);
+                }
+            }
+        }
+
+        Ok(concordance)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cpc_by_uspc.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cpc_by_uspc.is_empty()
+    }
+
+    /// The CPC symbols of a class/subclass (e.g. "705/37"). A subclass not in
+    /// the table falls back to its whole number's, e.g. 705/37.1 to 705/37.
+    pub fn uspc_to_cpc(&self, uspc: &str) -> &[String] {
+        match concordance_key(uspc) {
+            Some(uspc) => self.cpc(&uspc),
+            None => &[],
+        }
+    }
+
+    /// Like `uspc_to_cpc`, for a parsed classification, e.g. a grant's
+    /// main_classification
+    pub fn cpc(&self, uspc: &Uspc) -> &[String] {
+        let uspc = Uspc::new(&trim_zeros(&uspc.class), &trim_zeros(&uspc.subclass));
+        if let Some(cpcs) = self.cpc_by_uspc.get(&uspc) {
+            return cpcs;
+        }
+
+        let number_end = uspc.subclass.find(|c: char| !c.is_ascii_digit()).unwrap_or(uspc.subclass.len());
+        self.cpc_by_uspc.get(&Uspc::new(&uspc.class, &uspc.subclass[..number_end]))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+/// "705/37" as a Uspc, without leading zeros
+fn concordance_key(text: &str) -> Option<Uspc> {
+    let (class, subclass) = text.split_once('/')?;
+    let (class, subclass) = (class.trim(), subclass.trim());
+    if class.is_empty() || !class.bytes().all(|b| b.is_ascii_alphanumeric()) {
+        return None;
+    }
+
+    Some(Uspc::new(&trim_zeros(class), &trim_zeros(subclass)))
+}
+
+/// Leading zeros of a class or subclass number, after any letters (e.g.
+/// "D08" to "D8", "037.1" to "37.1")
+fn trim_zeros(text: &str) -> String {
+    let letters = text.find(|c: char| c.is_ascii_digit()).unwrap_or(text.len());
+    let (prefix, number) = text.split_at(letters);
+    let trimmed = number.trim_start_matches('0');
+
+    if trimmed.starts_with(|c: char| c.is_ascii_digit()) || number.is_empty() {
+        format!("{}{}", prefix, trimmed)
+    } else {
+        // all zeros, or zeros then a decimal or letters
+        format!("{}0{}", prefix, trimmed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uspc() {
+        let cases = [
+            ("294 49", "294", "49", false, "294 49"),
+            ("16110.1", "16", "110.1", false, " 16110.1"),
+            ("16110 R", "16", "110R", false, " 16110R"),
+            ("2  5", "2", "5", false, "  2  5"),
+            ("294 49-59", "294", "49-59", false, "294 49-59"),
+            ("D 8107", "D8", "107", false, "D 8107"),
+            ("PLT 156", "PLT", "156", false, "PLT156"),
+            ("172", "172", "", false, "172"),
+            ("294DIG  2", "294", "2", true, "294DIG  2"),
+            ("", "", "", false, ""),
+        ];
+
+        for &(text, class, subclass, digest, display) in &cases {
+            let uspc = Uspc::from(text);
+            assert_eq!(uspc, Uspc { class: class.to_string(), subclass: subclass.to_string(), digest }, "{}", text);
+            assert_eq!(uspc.to_string(), display);
+            assert_eq!(Uspc::from(uspc.to_string()), uspc);
+        }
+    }
+
+    #[test]
+    fn test_concordance() {
+        let table = "\
+USPC,CPC
+705/37,G06Q 40/04
+705/37,G06Q 40/06
+705/037,\"G06Q 40/04; G06Q 50/00\"
+D08/107\tA01B 1/00
+172/371,A01B1/02
+";
+        let concordance = Concordance::from_reader(table.as_bytes()).unwrap();
+        assert_eq!(concordance.len(), 3);
+        assert_eq!(concordance.uspc_to_cpc("705/37"), ["G06Q40/04", "G06Q40/06", "G06Q50/00"]);
+        assert_eq!(concordance.uspc_to_cpc("705/37.1"), concordance.uspc_to_cpc("705/37"));
+        assert_eq!(concordance.uspc_to_cpc("D8/107"), ["A01B1/00"]);
+        assert!(concordance.uspc_to_cpc("705/38").is_empty());
+        assert!(concordance.uspc_to_cpc("705").is_empty());
+        assert_eq!(concordance.cpc(&Uspc::from("172371")), ["A01B1/02"]);
+
+        let invalid = table.replace("172/371", "172 371");
+        assert!(Concordance::from_reader(invalid.as_bytes()).is_err());
+    }
+}
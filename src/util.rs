@@ -1,7 +1,12 @@
-use quick_xml::events::Event;
+use quick_xml::events::attributes::Attribute;
+use quick_xml::events::{BytesText, Event};
+use std::borrow::Cow;
 use std::io::BufRead;
 
+use crate::data::{CountryCode, KindCode, Uspc};
 use crate::error::Error;
+use crate::entities;
+use crate::options::{Decoding, Entities, MissingFields, ParserOptions, UnknownElements};
 
 #[macro_export]
 macro_rules! try_some {
@@ -14,26 +19,38 @@ macro_rules! try_some {
 }
 
 //parse_struct_fields_update
+//Fields are in groups: Required (an error if missing and the options say so),
+//Option, and optionally a last group not required but not an Option either
+//(left at the default if missing).
 #[macro_export]
 macro_rules! parse_struct_update {
     ($rdr:expr,
      $buf:expr,
+     $scratch:expr,
+     $opts:expr,
      $xml_element:expr,
      $data_struct:ident,
      {$($xml_field:expr => $data_struct_field:ident),* $(,)?},
      {$($xml_field_opt:expr => $data_struct_field_opt:ident),* $(,)?}
+     $(, {$($xml_field_def:expr => $data_struct_field_def:ident),* $(,)?})?
      ) => (
         match $rdr.read_event($buf) {
             Ok(Event::Start(ref e)) => {
                 match e.name() {
                     b"document-id" => {
+                        #[allow(unused_mut)]
+                        let mut found: Vec<&[u8]> = Vec::new();
                         loop {
                             match $rdr.read_event($buf) {
                                 Ok(Event::Start(ref e)) => {
                                     match e.name() {
-                                        $($xml_field => $data_struct.$data_struct_field = deser_text_from(e.name(), $rdr,)?,)*
-                                        $($xml_field_opt => $data_struct.$data_struct_field_opt = Some(deser_text_from(e.name(), $rdr,)?),)*
-                                        _ => return Err(Error::Deser { src: format!("unrecognized element {:?} in {}", std::str::from_utf8(e.name()), $xml_element) }),
+                                        $($xml_field => {
+                                            $data_struct.$data_struct_field = $crate::util::FromText::from_text(deser_text_from(e.name(), $rdr, $scratch, $opts)?, e.name())?;
+                                            found.push(&$xml_field[..]);
+                                        },)*
+                                        $($xml_field_opt => $data_struct.$data_struct_field_opt = Some($crate::util::FromText::from_text(deser_text_from(e.name(), $rdr, $scratch, $opts)?, e.name())?),)*
+                                        $($($xml_field_def => $data_struct.$data_struct_field_def = $crate::util::FromText::from_text(deser_text_from(e.name(), $rdr, $scratch, $opts)?, e.name())?,)*)?
+                                        _ => unknown_element(e.name(), $xml_element, $rdr, $scratch, $opts)?,
                                     }
                                 },
                                 Ok(Event::End(ref e)) => {
@@ -42,13 +59,14 @@ macro_rules! parse_struct_update {
                                 _ => break,
                             }
                         }
+                        check_required(&found, &[$(&$xml_field[..]),*], $xml_element, $opts)?;
                     }
-                    _ => return Err(Error::Deser { src: format!("found element {:?}, not {}", std::str::from_utf8(e.name()), $xml_element) }),
+                    _ => return Err(Error::deser(format!("found element {:?}, not {}", std::str::from_utf8(e.name()), $xml_element))),
                 }
             },
-            Ok(e) => return Err(Error::Deser { src: format!("found non-start-element {:?} besides {}", e, $xml_element) }),
+            Ok(e) => return Err(Error::deser(format!("found non-start-element {:?} besides {}", e, $xml_element))),
 
-            Err(err) => return Err(Error::Deser { src: err.to_string() }),
+            Err(err) => return Err(Error::deser(err.to_string())),
         }
     )
 }
@@ -59,25 +77,37 @@ macro_rules! parse_struct_update {
 macro_rules! parse_struct_update_from {
     ($rdr:expr,
      $buf:expr,
+     $scratch:expr,
+     $opts:expr,
      $xml_element:expr,
      $data_struct:ident,
      {$($xml_field:expr => $data_struct_field:ident),* $(,)?},
      {$($xml_field_opt:expr => $data_struct_field_opt:ident),* $(,)?}
+     $(, {$($xml_field_def:expr => $data_struct_field_def:ident),* $(,)?})?
      ) => (
-        loop {
-            match $rdr.read_event($buf) {
-                Ok(Event::Start(ref e)) => {
-                    match e.name() {
-                        $($xml_field => $data_struct.$data_struct_field = deser_text_from(e.name(), $rdr,)?,)*
-                        $($xml_field_opt => $data_struct.$data_struct_field_opt = Some(deser_text_from(e.name(), $rdr,)?),)*
-                        _ => return Err(Error::Deser { src: format!("unrecognized element {:?} in {}", std::str::from_utf8(e.name()), $xml_element) }),
-                    }
-                },
-                Ok(Event::End(ref e)) => {
-                    if e.name() == $xml_element.as_bytes() { break };
-                },
-                _ => break,
+        {
+            #[allow(unused_mut)]
+            let mut found: Vec<&[u8]> = Vec::new();
+            loop {
+                match $rdr.read_event($buf) {
+                    Ok(Event::Start(ref e)) => {
+                        match e.name() {
+                            $($xml_field => {
+                                $data_struct.$data_struct_field = $crate::util::FromText::from_text(deser_text_from(e.name(), $rdr, $scratch, $opts)?, e.name())?;
+                                found.push(&$xml_field[..]);
+                            },)*
+                            $($xml_field_opt => $data_struct.$data_struct_field_opt = Some($crate::util::FromText::from_text(deser_text_from(e.name(), $rdr, $scratch, $opts)?, e.name())?),)*
+                            $($($xml_field_def => $data_struct.$data_struct_field_def = $crate::util::FromText::from_text(deser_text_from(e.name(), $rdr, $scratch, $opts)?, e.name())?,)*)?
+                            _ => unknown_element(e.name(), $xml_element, $rdr, $scratch, $opts)?,
+                        }
+                    },
+                    Ok(Event::End(ref e)) => {
+                        if e.name() == $xml_element.as_bytes() { break };
+                    },
+                    _ => break,
+                }
             }
+            check_required(&found, &[$(&$xml_field[..]),*], $xml_element, $opts)?;
         }
     )
 }
@@ -97,11 +127,11 @@ pub fn consume_start<B: BufRead>(
                 let name = std::str::from_utf8(e.name());
                 let elem = std::str::from_utf8(xml_element);
                 let pos = rdr.buffer_position();
-                Err(Error::Deser { src: format!("found element {:?}, not {:?}; at {}", name, elem, pos) })
+                Err(Error::deser(format!("found element {:?}, not {:?}; at {}", name, elem, pos)))
             }
         },
-        Ok(e) => Err(Error::Deser { src: format!("found non-start-element {:?} besides {:?}", e, std::str::from_utf8(xml_element)) }),
-        Err(err) => Err(Error::Deser { src: err.to_string() }),
+        Ok(e) => Err(Error::deser(format!("found non-start-element {:?} besides {:?}", e, std::str::from_utf8(xml_element)))),
+        Err(err) => Err(Error::deser(err.to_string())),
     }
 }
 
@@ -117,19 +147,249 @@ pub fn skip_to_tag_within<B: BufRead>(
 {
     loop {
         match rdr.read_event(buf) {
-            Ok(Event::Start(ref e)) => {
-                if e.name() == to_tag {
-                    return Ok(true);
-                }
+            Ok(Event::Start(ref e)) if e.name() == to_tag => {
+                return Ok(true);
             },
-            Ok(Event::End(ref e)) => {
-                if e.name() == within_tag {
-                    return Ok(false);
-                }
+            Ok(Event::End(ref e)) if e.name() == within_tag => {
+                return Ok(false);
             },
-            Err(err) => return Err(Error::Deser { src: err.to_string() }),
+            Err(err) => return Err(Error::deser(err.to_string())),
             _ => {},
         }
     }
 }
 
+
+/// An element's text as a field's type: e.g. kept as a String, or parsed into
+/// a date (see `data::Date`)
+pub trait FromText: Sized {
+    fn from_text(text: String, element: &[u8]) -> Result<Self, Error>;
+}
+
+impl FromText for String {
+    fn from_text(text: String, _element: &[u8]) -> Result<Self, Error> {
+        Ok(text)
+    }
+}
+
+impl FromText for CountryCode {
+    fn from_text(text: String, _element: &[u8]) -> Result<Self, Error> {
+        Ok(CountryCode::from(text))
+    }
+}
+
+impl FromText for Uspc {
+    fn from_text(text: String, _element: &[u8]) -> Result<Self, Error> {
+        Ok(Uspc::from(text))
+    }
+}
+
+impl FromText for KindCode {
+    fn from_text(text: String, _element: &[u8]) -> Result<Self, Error> {
+        Ok(KindCode::from(text))
+    }
+}
+
+#[cfg(feature = "typed-dates")]
+impl FromText for chrono::NaiveDate {
+    fn from_text(text: String, element: &[u8]) -> Result<Self, Error> {
+        let err = |reason: &str| Error::deser(format!("invalid date {:?} in {}: {}", text, String::from_utf8_lossy(element), reason));
+
+        if text.len() != 8 || !text.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(err("expected YYYYMMDD"));
+        }
+
+        // cited documents are often dated only to the month (or year), with
+        // 00 for the rest; those are taken as the first
+        let number = |range: std::ops::Range<usize>| text[range].parse::<u32>().unwrap_or(0);
+        let (year, month, day) = (number(0..4), number(4..6).max(1), number(6..8).max(1));
+
+        chrono::NaiveDate::from_ymd_opt(year as i32, month, day)
+            .ok_or_else(|| err("no such day"))
+    }
+}
+
+/// Scratch buffers bigger than this (e.g. after skipping a large element) are
+/// let go of, rather than kept for the next elements
+const SCRATCH_CAPACITY: usize = 64 * 1024;
+
+/// Buffers for reading events into, kept by a reader (e.g. `PatentGrants`)
+/// and reused across elements and documents instead of allocated each time.
+///
+/// For the helpers called while the caller's buffer is still borrowed by the
+/// start tag (e.g. for its name).
+#[derive(Debug, Default)]
+pub struct Scratch {
+    bufs: Vec<Vec<u8>>,
+}
+
+impl Scratch {
+    /// Calls f with an empty buffer, and the rest of the scratch for the
+    /// helpers it calls in turn (which get the next buffer).
+    pub fn with<T>(&mut self, f: impl FnOnce(&mut Vec<u8>, &mut Scratch) -> T) -> T {
+        let mut buf = self.bufs.pop().unwrap_or_default();
+        buf.clear();
+
+        let res = f(&mut buf, self);
+
+        if buf.capacity() <= SCRATCH_CAPACITY {
+            self.bufs.push(buf);
+        }
+
+        res
+    }
+}
+
+/// Skips an element (with everything in it) without parsing it
+///
+/// called after the element's start tag is already hit
+pub fn skip_element<B: BufRead>(name: &[u8], rdr: &mut quick_xml::Reader<B>, scratch: &mut Scratch) -> Result<(), Error> {
+    scratch.with(|buf, _| rdr.read_to_end(name, buf))
+        .map_err(|err| Error::deser(err.to_string()))
+}
+
+/// For an element the parser doesn't know, per the options: skips it (with
+/// everything in it), or errors.
+///
+/// called after the element's start tag is already hit
+pub fn unknown_element<B: BufRead, K: AsRef<[u8]>>(
+    name: &[u8],
+    parent: K,
+    rdr: &mut quick_xml::Reader<B>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    ) -> Result<(), Error>
+{
+    match opts.unknown_elements {
+        UnknownElements::Skip => {
+            if let Some(ref skipped) = opts.skipped {
+                skipped.record(parent.as_ref(), name, rdr.buffer_position());
+            }
+
+            skip_element(name, rdr, scratch)
+        },
+        UnknownElements::Error => {
+            let name = String::from_utf8_lossy(name);
+            let parent = String::from_utf8_lossy(parent.as_ref());
+            Err(Error::deser(format!("unrecognized element {:?} in {}", name, parent)))
+        },
+    }
+}
+
+/// Errors if the options require fields, and a required element wasn't found
+pub fn check_required<K: AsRef<[u8]>>(
+    found: &[&[u8]],
+    required: &[&[u8]],
+    parent: K,
+    opts: &ParserOptions,
+    ) -> Result<(), Error>
+{
+    if opts.missing_fields == MissingFields::Default {
+        return Ok(());
+    }
+
+    match required.iter().find(|name| !found.contains(name)) {
+        Some(name) => {
+            let name = String::from_utf8_lossy(name);
+            let parent = String::from_utf8_lossy(parent.as_ref());
+            Err(Error::deser(format!("missing element {:?} in {}", name, parent)))
+        },
+        None => Ok(()),
+    }
+}
+
+/// Text, with its entities and decoding handled per the options
+pub fn decode_text<B: BufRead>(
+    text: &BytesText,
+    rdr: &quick_xml::Reader<B>,
+    opts: &ParserOptions,
+    ) -> Result<String, Error>
+{
+    let escaped = text.escaped();
+    let unescaped = match opts.entities {
+        Entities::Unescape => text.unescaped()
+            .map_err(|err| Error::deser(err.to_string()))?,
+        Entities::KeepUnknown => text.unescaped()
+            .unwrap_or(Cow::Borrowed(escaped)),
+        Entities::Dtd => entities::resolve(escaped),
+        Entities::Raw => Cow::Borrowed(escaped),
+    };
+
+    decode_bytes(&unescaped, rdr, opts)
+        .map(Cow::into_owned)
+}
+
+/// An attribute's value, with its entities and decoding handled as text's
+/// are, see `decode_text`
+pub fn decode_attribute<B: BufRead>(
+    attr: &Attribute,
+    rdr: &quick_xml::Reader<B>,
+    opts: &ParserOptions,
+    ) -> Result<String, Error>
+{
+    let escaped = &attr.value[..];
+    let unescaped = match opts.entities {
+        Entities::Unescape => attr.unescaped_value()
+            .map_err(|err| Error::deser(err.to_string()))?,
+        Entities::KeepUnknown => attr.unescaped_value()
+            .unwrap_or(Cow::Borrowed(escaped)),
+        Entities::Dtd => entities::resolve(escaped),
+        Entities::Raw => Cow::Borrowed(escaped),
+    };
+
+    decode_bytes(&unescaped, rdr, opts)
+        .map(Cow::into_owned)
+}
+
+/// Bytes of the document as text, per the options' decoding. Borrowed if
+/// they're already UTF-8.
+pub fn decode_bytes<'a, B: BufRead>(
+    bytes: &'a [u8],
+    rdr: &quick_xml::Reader<B>,
+    opts: &ParserOptions,
+    ) -> Result<Cow<'a, str>, Error>
+{
+    let encoding = rdr.encoding();
+
+    match opts.decoding {
+        Decoding::Lossy => Ok(rdr.decode(bytes)),
+        Decoding::Strict => encoding.decode_without_bom_handling_and_without_replacement(bytes)
+            .ok_or_else(|| Error::deser(format!("invalid {} text {:?}", encoding.name(), String::from_utf8_lossy(bytes)))),
+        Decoding::Fallback(fallback) => Ok(encoding.decode_without_bom_handling_and_without_replacement(bytes)
+            .unwrap_or_else(|| fallback.decode_without_bom_handling(bytes).0)),
+    }
+}
+
+/// Like decode_bytes, without copying bytes that are already UTF-8
+pub(crate) fn decode_owned<B: BufRead>(bytes: Vec<u8>, rdr: &quick_xml::Reader<B>, opts: &ParserOptions) -> Result<String, Error> {
+    if rdr.encoding() != encoding_rs::UTF_8 {
+        return decode_bytes(&bytes, rdr, opts).map(Cow::into_owned);
+    }
+
+    String::from_utf8(bytes)
+        .or_else(|err| decode_bytes(err.as_bytes(), rdr, opts).map(Cow::into_owned))
+}
+
+/// Like quick_xml's Reader::read_text, but with entities and decoding
+/// handled per the options
+pub fn read_text<B: BufRead, K: AsRef<[u8]>>(
+    end: K,
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    opts: &ParserOptions,
+    ) -> Result<String, Error>
+{
+    let read_err = |err: quick_xml::Error, rdr: &quick_xml::Reader<B>| Error::deser(format!("err: {}, position: {}", err, rdr.buffer_position()));
+
+    let txt = match rdr.read_event(buf) {
+        Ok(Event::Text(e)) => decode_text(&e, rdr, opts)?,
+        Ok(Event::End(ref e)) if e.name() == end.as_ref() => return Ok(String::new()),
+        Ok(Event::Eof) => return Err(read_err(quick_xml::Error::UnexpectedEof("Text".to_string()), rdr)),
+        Ok(_) => return Err(read_err(quick_xml::Error::TextNotFound, rdr)),
+        Err(err) => return Err(read_err(err, rdr)),
+    };
+    rdr.read_to_end(end, buf)
+        .map_err(|err| read_err(err, rdr))?;
+
+    Ok(txt)
+}
@@ -0,0 +1,241 @@
+//! Callbacks for each part of each grant as it's parsed, for consumers that
+//! aggregate over a file and don't need whole PatentGrants.
+//!
+//! Claims and description sections are passed one at a time, and dropped
+//! after. The biblio is parsed whole, then its citations are passed one at a
+//! time, taken out of it.
+
+use quick_xml::{self, Reader};
+use quick_xml::events::Event;
+use std::io::BufRead;
+
+use crate::data::*;
+use crate::deserialize::*;
+use crate::error::{Error, WithinExt};
+use crate::options::{ParserOptions, Projection};
+use crate::patdoc::{deser_patdoc, deser_patdoc_meta};
+use crate::schema;
+use crate::util::{skip_element, Scratch};
+
+/// Called by `parse_with_visitor`, in document order for each grant: start,
+/// biblio, citations, then the abstract, claims and description sections (in
+/// the order they're in the document), and end. All do nothing by default.
+pub trait GrantVisitor {
+    fn on_grant_start(&mut self, _meta: &GrantMeta) {}
+
+    /// without its citations, see on_citation
+    fn on_biblio(&mut self, _biblio: &BibliographicDataGrant) {}
+
+    fn on_citation(&mut self, _citation: &UsCitation) {}
+
+    fn on_abstract(&mut self, _paragraphs: &[Paragraph]) {}
+
+    fn on_claim(&mut self, _claim: &Claim) {}
+
+    /// e.g. `BRFSUM` and its text; see PatentGrant::descriptions
+    fn on_description_section(&mut self, _name: &str, _text: &str) {}
+
+    fn on_grant_end(&mut self) {}
+}
+
+/// Parses each grant in rdr, calling the visitor for its parts. Stops at the
+/// first error. The options' projection applies, so e.g. without
+/// `Projection::DESCRIPTION` on_description_section is never called.
+pub fn parse_with_visitor<B: BufRead, V: GrantVisitor>(
+    rdr: B,
+    options: &ParserOptions,
+    visitor: &mut V,
+    ) -> Result<(), Error>
+{
+    let mut rdr = Reader::from_reader(rdr);
+    rdr.trim_text(options.trim_text);
+    let mut buf = Vec::new();
+    let mut scratch = Scratch::default();
+
+    loop {
+        match deser_header(&mut rdr, &mut buf) {
+            Some(Err(err)) => return Err(err),
+            Some(Ok(())) => (),
+            None => return Ok(()),
+        }
+        buf.clear();
+
+        let mut doc_number = String::new();
+        visit_grant(&mut rdr, &mut buf, &mut scratch, options, visitor, &mut doc_number)
+            .map_err(|err| err.at_position(rdr.buffer_position(), &doc_number))?;
+        buf.clear();
+
+        visitor.on_grant_end();
+    }
+}
+
+/// the document after its headers; sets doc_number once known, to locate
+/// errors with
+fn visit_grant<B: BufRead, V: GrantVisitor>(
+    rdr: &mut quick_xml::Reader<B>,
+    buf: &mut Vec<u8>,
+    scratch: &mut Scratch,
+    opts: &ParserOptions,
+    visitor: &mut V,
+    doc_number: &mut String,
+    ) -> Result<(), Error>
+{
+    let projection = opts.projection;
+    let mut names = schema::element_names(None);
+
+    loop {
+        match rdr.read_event(buf) {
+            Ok(Event::PI(pi_bytes)) => {
+                if let Some((name, text, _raw)) = deser_description_section(pi_bytes, rdr, scratch, opts)? {
+                    visitor.on_description_section(&name, &text);
+                }
+            },
+            Ok(Event::Start(ref e)) => {
+                match e.name() {
+                    b"us-patent-grant" => {
+                        let mut meta = GrantMeta::default();
+                        deser_grant_meta(e, rdr, opts, &mut meta).within(b"us-patent-grant")?;
+                        names = schema::element_names(meta.dtd_version.as_deref());
+                        visitor.on_grant_start(&meta);
+                    },
+                    b"us-bibliographic-data-grant" => {
+                        let mut biblio = BibliographicDataGrant::default();
+                        deser_biblio(rdr, buf, scratch, opts, names, &mut biblio).within(b"us-bibliographic-data-grant")?;
+                        *doc_number = biblio.publication_reference.doc_number.clone();
+
+                        let citations = std::mem::take(&mut biblio.us_references_cited);
+                        visitor.on_biblio(&biblio);
+                        for citation in &citations {
+                            visitor.on_citation(citation);
+                        }
+                    },
+                    b"claims" if projection.contains(Projection::CLAIMS) => {
+                        deser_claims_each(rdr, buf, scratch, opts, |claim| visitor.on_claim(&claim)).within(b"claims")?;
+                    },
+                    // not visited
+                    b"claims" => skip_element(b"claims", rdr, scratch)?,
+                    b"description" if !projection.contains(Projection::DESCRIPTION) => skip_element(b"description", rdr, scratch)?,
+                    b"abstract" if !projection.contains(Projection::ABSTRACT) => skip_element(b"abstract", rdr, scratch)?,
+                    b"abstract" => {
+                        let mut paragraphs = Vec::new();
+                        deser_abstract(rdr, buf, scratch, opts, &mut paragraphs).within(b"abstract")?;
+                        visitor.on_abstract(&paragraphs);
+                    },
+                    // 2002 to 2004 grants have no parts to visit separately,
+                    // so are parsed whole
+                    b"PATDOC" => {
+                        let mut patent_grant = PatentGrant::default();
+                        deser_patdoc_meta(e, rdr, opts, &mut patent_grant.meta).within(b"PATDOC")?;
+                        deser_patdoc(rdr, buf, opts, &mut patent_grant).within(b"PATDOC")?;
+                        *doc_number = patent_grant.us_bibliographic_data_grant.publication_reference.doc_number.clone();
+                        visit_patent_grant(patent_grant, projection, visitor);
+                        break;
+                    },
+                    _ => continue,
+                }
+            },
+            Ok(Event::Eof) => break,
+            Ok(Event::End(e)) => {
+                if e.name() == b"us-patent-grant" {
+                    break;
+                }
+            },
+            Ok(_) => continue,
+            Err(err) => return Err(Error::deser(err.to_string())),
+        };
+    }
+
+    Ok(())
+}
+
+/// calls the visitor for the parts (in the projection) of an already parsed
+/// grant
+fn visit_patent_grant<V: GrantVisitor>(mut patent_grant: PatentGrant, projection: Projection, visitor: &mut V) {
+    visitor.on_grant_start(&patent_grant.meta);
+
+    let biblio = &mut patent_grant.us_bibliographic_data_grant;
+    let citations = std::mem::take(&mut biblio.us_references_cited);
+    visitor.on_biblio(biblio);
+    if projection.contains(Projection::CITATIONS) {
+        for citation in &citations {
+            visitor.on_citation(citation);
+        }
+    }
+
+    if projection.contains(Projection::ABSTRACT) {
+        visitor.on_abstract(&patent_grant.r#abstract);
+    }
+    if projection.contains(Projection::CLAIMS) {
+        for claim in &patent_grant.claims {
+            visitor.on_claim(claim);
+        }
+    }
+    if projection.contains(Projection::DESCRIPTION) {
+        for (name, text) in &patent_grant.descriptions {
+            visitor.on_description_section(name, text);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/grants.xml");
+
+    #[derive(Default)]
+    struct Counts {
+        grants: usize,
+        ended: usize,
+        claims: usize,
+        citations: usize,
+        sections: Vec<String>,
+    }
+
+    impl GrantVisitor for Counts {
+        fn on_biblio(&mut self, biblio: &BibliographicDataGrant) {
+            assert!(biblio.us_references_cited.is_empty());
+            self.grants += 1;
+        }
+
+        fn on_citation(&mut self, _citation: &UsCitation) {
+            self.citations += 1;
+        }
+
+        fn on_claim(&mut self, _claim: &Claim) {
+            self.claims += 1;
+        }
+
+        fn on_description_section(&mut self, name: &str, _text: &str) {
+            self.sections.push(name.to_string());
+        }
+
+        fn on_grant_end(&mut self) {
+            self.ended += 1;
+        }
+    }
+
+    #[test]
+    fn test_parse_with_visitor() {
+        let grants = crate::PatentGrants::from_reader(FIXTURE.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let mut counts = Counts::default();
+        parse_with_visitor(FIXTURE.as_bytes(), &ParserOptions::default(), &mut counts).unwrap();
+
+        assert_eq!(counts.grants, grants.len());
+        assert_eq!(counts.ended, grants.len());
+        assert_eq!(counts.claims, grants.iter().map(|grant| grant.claims.len()).sum::<usize>());
+        assert_eq!(counts.citations, grants.iter().map(|grant| grant.us_bibliographic_data_grant.us_references_cited.len()).sum::<usize>());
+        assert_eq!(counts.sections.len(), grants.iter().map(|grant| grant.descriptions.len()).sum::<usize>());
+
+        let mut counts = Counts::default();
+        let options = ParserOptions::new().projection(Projection::BIBLIO);
+        parse_with_visitor(FIXTURE.as_bytes(), &options, &mut counts).unwrap();
+        assert_eq!(counts.grants, grants.len());
+        assert_eq!(counts.claims, 0);
+        assert_eq!(counts.citations, 0);
+        assert!(counts.sections.is_empty());
+    }
+}